@@ -0,0 +1,19 @@
+//! Records the compiler version for the `x-stainless-runtime-version` header
+
+use std::{env, process::Command};
+
+fn main() {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        // "rustc 1.95.0 (59807616e 2026-04-14)"
+        .and_then(|output| output.split_whitespace().nth(1).map(str::to_string))
+        .unwrap_or_else(|| "unknown".to_string());
+
+    println!("cargo:rustc-env=RUSTC_VERSION={}", version);
+    println!("cargo:rerun-if-env-changed=RUSTC");
+}
@@ -0,0 +1,343 @@
+//! Derive macros for `threatflux-anthropic-sdk`.
+//!
+//! Use these through the SDK's `derive` feature rather than depending on this
+//! crate directly:
+//!
+//! ```toml
+//! threatflux-anthropic-sdk = { version = "0.2", features = ["derive"] }
+//! ```
+//!
+//! See `threatflux_anthropic_sdk::tools::derived` for the traits the macros
+//! implement.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{
+    parse_macro_input, spanned::Spanned, Attribute, Data, DeriveInput, Error, Expr, ExprLit,
+    Fields, Lit, LitStr, Meta, Path, Result,
+};
+
+/// Derive `AnthropicTool` for a struct of tool arguments (or a unit-only enum
+/// used as a field type).
+///
+/// * The tool name is the snake_case struct name, or `#[tool(name = "...")]`.
+/// * The description is the struct's doc comment, or
+///   `#[tool(description = "...")]`.
+/// * Each named field becomes a property, described by its doc comment.
+///   `Option` fields and `#[serde(default)]` fields are optional; serde's
+///   `rename`, `rename_all` and `skip` are honoured.
+/// * `#[tool(handler = path::to::fn)]` also implements `AnthropicToolHandler`
+///   by calling `async fn(Self) -> Result<ToolOutput>`.
+#[proc_macro_derive(AnthropicTool, attributes(tool))]
+pub fn derive_anthropic_tool(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Options from `#[tool(...)]`
+#[derive(Default)]
+struct ToolAttrs {
+    name: Option<LitStr>,
+    description: Option<LitStr>,
+    handler: Option<Path>,
+}
+
+/// The subset of `#[serde(...)]` that changes the schema
+#[derive(Default)]
+struct SerdeAttrs {
+    rename: Option<String>,
+    rename_all: Option<String>,
+    default: bool,
+    skip: bool,
+    flatten: bool,
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2> {
+    let tool = tool_attrs(&input.attrs)?;
+    let container = serde_attrs(&input.attrs)?;
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let name = tool
+        .name
+        .as_ref()
+        .map(LitStr::value)
+        .unwrap_or_else(|| snake_case(&ident.to_string()));
+    let description = tool
+        .description
+        .as_ref()
+        .map(LitStr::value)
+        .or_else(|| doc_comment(&input.attrs))
+        .unwrap_or_default();
+    let schema = match &input.data {
+        Data::Struct(data) => struct_schema(&data.fields, &container)?,
+        Data::Enum(data) => {
+            let mut values = Vec::new();
+            for variant in &data.variants {
+                if !matches!(variant.fields, Fields::Unit) {
+                    return Err(Error::new(
+                        variant.span(),
+                        "AnthropicTool only supports enums with unit variants",
+                    ));
+                }
+                let serde = serde_attrs(&variant.attrs)?;
+                if serde.skip {
+                    continue;
+                }
+                values.push(serde.rename.unwrap_or_else(|| {
+                    apply_rename_all(&variant.ident.to_string(), container.rename_all.as_deref())
+                }));
+            }
+            quote! {
+                ::threatflux_anthropic_sdk::tools::derived::__private::enum_schema(&[#(#values),*])
+            }
+        }
+        Data::Union(_) => {
+            return Err(Error::new(
+                Span::call_site(),
+                "AnthropicTool cannot be derived for unions",
+            ))
+        }
+    };
+
+    let handler = tool.handler.map(|handler| {
+        quote! {
+            impl #impl_generics ::threatflux_anthropic_sdk::tools::derived::AnthropicToolHandler
+                for #ident #ty_generics #where_clause
+            {
+                fn run(
+                    self,
+                ) -> ::threatflux_anthropic_sdk::tools::derived::__private::BoxFuture<
+                    'static,
+                    ::threatflux_anthropic_sdk::Result<::threatflux_anthropic_sdk::tools::ToolOutput>,
+                > {
+                    ::std::boxed::Box::pin(#handler(self))
+                }
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::threatflux_anthropic_sdk::tools::derived::AnthropicTool
+            for #ident #ty_generics #where_clause
+        {
+            const NAME: &'static str = #name;
+            const DESCRIPTION: &'static str = #description;
+
+            fn input_schema() -> ::threatflux_anthropic_sdk::tools::derived::__private::Value {
+                #schema
+            }
+        }
+
+        impl #impl_generics ::threatflux_anthropic_sdk::tools::derived::ToolSchema
+            for #ident #ty_generics #where_clause
+        {
+            fn schema() -> ::threatflux_anthropic_sdk::tools::derived::__private::Value {
+                <Self as ::threatflux_anthropic_sdk::tools::derived::AnthropicTool>::input_schema()
+            }
+        }
+
+        #handler
+    })
+}
+
+fn struct_schema(fields: &Fields, container: &SerdeAttrs) -> Result<TokenStream2> {
+    let fields = match fields {
+        Fields::Named(named) => named.named.iter().collect::<Vec<_>>(),
+        Fields::Unit => Vec::new(),
+        Fields::Unnamed(unnamed) => {
+            return Err(Error::new(
+                unnamed.span(),
+                "AnthropicTool needs named fields; tool input is a JSON object",
+            ))
+        }
+    };
+
+    let mut properties = Vec::new();
+    for field in fields {
+        let serde = serde_attrs(&field.attrs)?;
+        if serde.skip {
+            continue;
+        }
+        if serde.flatten {
+            return Err(Error::new(
+                field.span(),
+                "#[serde(flatten)] is not supported by AnthropicTool",
+            ));
+        }
+        let ident = field.ident.as_ref().expect("named field");
+        let key = serde.rename.unwrap_or_else(|| {
+            apply_rename_all(
+                ident.to_string().trim_start_matches("r#"),
+                container.rename_all.as_deref(),
+            )
+        });
+        let description = match doc_comment(&field.attrs) {
+            Some(doc) => quote!(::std::option::Option::Some(#doc)),
+            None => quote!(::std::option::Option::None),
+        };
+        let ty = &field.ty;
+        let defaulted = serde.default || container.default;
+        properties.push(quote! {
+            (
+                #key,
+                <#ty as ::threatflux_anthropic_sdk::tools::derived::ToolSchema>::schema(),
+                #description,
+                !#defaulted
+                    && <#ty as ::threatflux_anthropic_sdk::tools::derived::ToolSchema>::REQUIRED,
+            )
+        });
+    }
+
+    Ok(quote! {
+        ::threatflux_anthropic_sdk::tools::derived::__private::object_schema(::std::vec![#(#properties),*])
+    })
+}
+
+fn tool_attrs(attrs: &[Attribute]) -> Result<ToolAttrs> {
+    let mut out = ToolAttrs::default();
+    for attr in attrs.iter().filter(|a| a.path().is_ident("tool")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                out.name = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("description") {
+                out.description = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("handler") {
+                let value = meta.value()?;
+                out.handler = Some(if value.peek(LitStr) {
+                    value.parse::<LitStr>()?.parse()?
+                } else {
+                    value.parse()?
+                });
+            } else {
+                return Err(meta.error("expected `name`, `description` or `handler`"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(out)
+}
+
+fn serde_attrs(attrs: &[Attribute]) -> Result<SerdeAttrs> {
+    let mut out = SerdeAttrs::default();
+    for attr in attrs.iter().filter(|a| a.path().is_ident("serde")) {
+        attr.parse_nested_meta(|meta| {
+            let key = meta
+                .path
+                .get_ident()
+                .map(ToString::to_string)
+                .unwrap_or_default();
+            match key.as_str() {
+                "rename" | "rename_all" if meta.input.peek(syn::Token![=]) => {
+                    let value = meta.value()?.parse::<LitStr>()?.value();
+                    if key == "rename" {
+                        out.rename = Some(value);
+                    } else {
+                        out.rename_all = Some(value);
+                    }
+                }
+                "default" => {
+                    out.default = true;
+                    if meta.input.peek(syn::Token![=]) {
+                        meta.value()?.parse::<Expr>()?;
+                    }
+                }
+                "skip" | "skip_deserializing" => out.skip = true,
+                "flatten" => out.flatten = true,
+                _ => {
+                    // Anything else (serialize-only options, `with`, `bound`...)
+                    // does not affect the schema.
+                    if meta.input.peek(syn::Token![=]) {
+                        meta.value()?.parse::<Expr>()?;
+                    } else if meta.input.peek(syn::token::Paren) {
+                        meta.parse_nested_meta(|nested| {
+                            if nested.input.peek(syn::Token![=]) {
+                                nested.value()?.parse::<Expr>()?;
+                            }
+                            Ok(())
+                        })?;
+                    }
+                }
+            }
+            Ok(())
+        })?;
+    }
+    Ok(out)
+}
+
+/// `///` lines joined with newlines, or `None` when there are none
+fn doc_comment(attrs: &[Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(nv) if nv.path.is_ident("doc") => match &nv.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(s), ..
+                }) => Some(s.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    let doc = lines.join("\n").trim().to_string();
+    (!doc.is_empty()).then_some(doc)
+}
+
+fn words(ident: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut prev_lower = false;
+    for c in ident.chars() {
+        if c == '_' || c == '-' {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            prev_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && prev_lower && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        prev_lower = c.is_lowercase() || c.is_ascii_digit();
+        current.extend(c.to_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn snake_case(ident: &str) -> String {
+    words(ident).join("_")
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Apply a serde `rename_all` rule to a field or variant name
+fn apply_rename_all(ident: &str, rule: Option<&str>) -> String {
+    let words = words(ident);
+    match rule {
+        Some("lowercase") => words.concat(),
+        Some("UPPERCASE") => words.concat().to_uppercase(),
+        Some("snake_case") => words.join("_"),
+        Some("SCREAMING_SNAKE_CASE") => words.join("_").to_uppercase(),
+        Some("kebab-case") => words.join("-"),
+        Some("SCREAMING-KEBAB-CASE") => words.join("-").to_uppercase(),
+        Some("camelCase") => words
+            .iter()
+            .enumerate()
+            .map(|(i, w)| if i == 0 { w.clone() } else { capitalize(w) })
+            .collect(),
+        Some("PascalCase") => words.iter().map(|w| capitalize(w)).collect(),
+        _ => ident.to_string(),
+    }
+}
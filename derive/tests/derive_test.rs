@@ -0,0 +1,115 @@
+//! Tests for `#[derive(AnthropicTool)]`
+
+use serde::Deserialize;
+use serde_json::json;
+use threatflux_anthropic_sdk::{
+    models::ContentBlock,
+    tools::{AnthropicTool, ToolOutput, ToolRegistry},
+};
+
+/// Search the product catalogue.
+///
+/// Returns at most `limit` matches.
+#[derive(Debug, Deserialize, AnthropicTool)]
+#[tool(handler = search)]
+#[serde(rename_all = "camelCase")]
+struct SearchCatalog {
+    /// Free-text query
+    query: String,
+    /// Maximum number of results
+    #[serde(default)]
+    max_results: u32,
+    sort: Option<SortOrder>,
+    #[serde(rename = "tag")]
+    tags: Vec<String>,
+    #[serde(skip)]
+    #[allow(dead_code)]
+    internal: bool,
+}
+
+#[derive(Debug, Deserialize, AnthropicTool)]
+#[serde(rename_all = "snake_case")]
+enum SortOrder {
+    Relevance,
+    PriceAscending,
+    #[serde(rename = "newest")]
+    NewestFirst,
+}
+
+#[derive(Deserialize, AnthropicTool)]
+#[tool(name = "ping", description = "Health check")]
+struct Ping {}
+
+async fn search(input: SearchCatalog) -> threatflux_anthropic_sdk::Result<ToolOutput> {
+    Ok(ToolOutput::json(json!({
+        "query": input.query,
+        "limit": input.max_results,
+        "sorted": input.sort.is_some(),
+        "tags": input.tags.len(),
+    })))
+}
+
+#[test]
+fn test_definition_from_struct_and_doc_comments() {
+    let tool = SearchCatalog::definition();
+    assert_eq!(tool.name, "search_catalog");
+    assert_eq!(
+        tool.description.as_deref(),
+        Some("Search the product catalogue.\n\nReturns at most `limit` matches.")
+    );
+    assert_eq!(
+        tool.input_schema.unwrap(),
+        json!({
+            "type": "object",
+            "properties": {
+                "query": {"type": "string", "description": "Free-text query"},
+                "maxResults": {"type": "integer", "minimum": 0, "description": "Maximum number of results"},
+                "sort": {"type": "string", "enum": ["relevance", "price_ascending", "newest"]},
+                "tag": {"type": "array", "items": {"type": "string"}}
+            },
+            "required": ["query", "tag"]
+        })
+    );
+
+    let ping = Ping::definition();
+    assert_eq!(
+        (ping.name.as_str(), ping.description.as_deref()),
+        ("ping", Some("Health check"))
+    );
+    assert_eq!(
+        ping.input_schema.unwrap(),
+        json!({"type": "object", "properties": {}, "required": []})
+    );
+}
+
+#[tokio::test]
+async fn test_registered_handler_receives_typed_input() {
+    let tools = ToolRegistry::new().with_derived::<SearchCatalog>();
+    assert_eq!(tools.definitions()[0].name, SearchCatalog::NAME);
+
+    let ok = tools
+        .call(
+            "toolu_1",
+            "search_catalog",
+            json!({"query": "lamp", "tag": [], "sort": "newest"}),
+        )
+        .await;
+    assert_eq!(
+        ok,
+        ContentBlock::tool_result_json(
+            "toolu_1",
+            json!({"query": "lamp", "limit": 0, "sorted": true, "tags": 0})
+        )
+    );
+
+    let bad = tools
+        .call("toolu_2", "search_catalog", json!({"query": 3}))
+        .await;
+    assert!(matches!(
+        bad,
+        ContentBlock::ToolResult {
+            is_error: Some(true),
+            ..
+        }
+    ));
+}
@@ -8,6 +8,7 @@ use std::error::Error;
 use std::time::Duration;
 use threatflux_anthropic_sdk::{
     builders::{BatchBuilder, MessageBuilder},
+    models::batch::PollOptions,
     Client,
 };
 
@@ -81,7 +82,10 @@ async fn main() -> Result<(), Box<dyn Error>> {
     println!("\n⏳ Waiting for batch to complete...");
     let completed_batch = client
         .message_batches()
-        .wait_for_completion(&batch.id, Duration::from_secs(5), Duration::from_secs(300))
+        .wait_for_completion(
+            &batch.id,
+            PollOptions::fixed(Duration::from_secs(5)).with_timeout(Duration::from_secs(300)),
+        )
         .await?;
 
     println!("✅ Batch completed!");
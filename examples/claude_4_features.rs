@@ -147,7 +147,7 @@ async fn streaming(client: &Client) -> Result<(), Box<dyn Error>> {
     while let Some(event) = stream.next().await {
         match event? {
             StreamEvent::ContentBlockDelta { delta, .. } => {
-                if let Some(text) = delta.text {
+                if let Some(text) = delta.as_text() {
                     print!("{text}");
                 }
             }
@@ -53,7 +53,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 io::stdout().flush()?;
             }
             StreamEvent::ContentBlockDelta { delta, .. } => {
-                if let Some(text) = &delta.text {
+                if let Some(text) = delta.as_text() {
                     print!("{}", text);
                     io::stdout().flush()?;
                     full_response.push_str(text);
@@ -161,7 +161,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
             let event = event_result?;
 
             if let StreamEvent::ContentBlockDelta { delta, .. } = event {
-                if let Some(text) = &delta.text {
+                if let Some(text) = delta.as_text() {
                     print!("{}", text);
                     io::stdout().flush()?;
                 }
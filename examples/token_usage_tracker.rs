@@ -225,7 +225,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
                         stream_input_tokens = message.usage.input_tokens;
                     }
                     StreamEvent::ContentBlockDelta { delta, .. } => {
-                        if let Some(text) = delta.text {
+                        if let Some(text) = delta.as_text() {
                             print!("{}", text);
                         }
                     }
@@ -0,0 +1,107 @@
+#ifndef THREATFLUX_ANTHROPIC_H
+#define THREATFLUX_ANTHROPIC_H
+
+/* Generated by cbindgen from src/ffi.rs; do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Status returned on success
+ */
+#define ANTHROPIC_OK 0
+
+/**
+ * Status returned when a call failed; see [`anthropic_last_error`]
+ */
+#define ANTHROPIC_ERROR -1
+
+/**
+ * A client and the runtime its calls are driven on. Opaque to C.
+ */
+typedef struct AnthropicClient AnthropicClient;
+
+/**
+ * Called with each stream event as a JSON string, valid only during the
+ * call. Return non-zero to stop the stream early.
+ */
+typedef int (*AnthropicStreamCallback)(const char *event_json, void *user_data);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Create a client for `api_key`, or from the `ANTHROPIC_*` environment
+ * variables when it is null. `base_url` overrides the API endpoint when not
+ * null. Returns null on failure.
+ *
+ * # Safety
+ *
+ * `api_key` and `base_url` must each be null or a valid NUL-terminated
+ * string.
+ */
+struct AnthropicClient *anthropic_client_new(const char *api_key, const char *base_url);
+
+/**
+ * Free a client. Null is ignored.
+ *
+ * # Safety
+ *
+ * `client` must be null or a pointer from [`anthropic_client_new`] that has
+ * not been freed, and no other call may be using it.
+ */
+void anthropic_client_free(struct AnthropicClient *client);
+
+/**
+ * Send a message request, given as Messages API JSON, and wait for the
+ * response JSON. Returns null on failure; free the result with
+ * [`anthropic_string_free`].
+ *
+ * # Safety
+ *
+ * `client` must come from [`anthropic_client_new`] and `request_json` must be
+ * a valid NUL-terminated string.
+ */
+char *anthropic_messages_create(const struct AnthropicClient *client, const char *request_json);
+
+/**
+ * Stream a message request, calling `callback` with each event's JSON until
+ * the message ends, the callback returns non-zero, or an error occurs.
+ * Returns [`ANTHROPIC_OK`] or [`ANTHROPIC_ERROR`].
+ *
+ * # Safety
+ *
+ * `client` must come from [`anthropic_client_new`], `request_json` must be a
+ * valid NUL-terminated string, and `callback` must be safe to call with
+ * `user_data` from the calling thread.
+ */
+int anthropic_messages_stream(const struct AnthropicClient *client,
+                              const char *request_json,
+                              AnthropicStreamCallback callback,
+                              void *user_data);
+
+/**
+ * The last error on this thread, or null if there was none. The string
+ * stays valid until the next failing call on the same thread.
+ */
+const char *anthropic_last_error(void);
+
+/**
+ * Free a string returned by this library. Null is ignored.
+ *
+ * # Safety
+ *
+ * `value` must be null or a string returned by this library that has not
+ * been freed.
+ */
+void anthropic_string_free(char *value);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* THREATFLUX_ANTHROPIC_H */
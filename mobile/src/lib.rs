@@ -0,0 +1,515 @@
+//! UniFFI bindings for Kotlin (Android) and Swift (iOS)
+//!
+//! Build the library for the target platforms (a `cdylib` per Android ABI, a
+//! `staticlib` per Apple target), then generate the foreign sources from any
+//! host build of it:
+//!
+//! ```sh
+//! cargo build -p threatflux-anthropic-sdk-mobile --release
+//! cargo run -p threatflux-anthropic-sdk-mobile --features bindgen --bin uniffi-bindgen -- \
+//!     generate --library target/release/libthreatflux_anthropic_mobile.so \
+//!     --language kotlin --out-dir bindings/kotlin
+//! ```
+//!
+//! ```kotlin
+//! val client = AnthropicClient(ClientOptions(apiKey = key))
+//! val reply = client.createMessage(MessageParams(
+//!     model = "claude-haiku-4-5",
+//!     maxTokens = 256u,
+//!     messages = listOf(ChatMessage(Role.USER, "Hello")),
+//! ))
+//! client.close() // releases the client and stops its runtime
+//! ```
+//!
+//! Requests are `suspend` functions in Kotlin and `async` in Swift. Streams
+//! report to a [`StreamListener`] implemented in the app, from a worker
+//! thread; hop to the main thread before touching UI.
+//!
+//! # Runtime lifecycle
+//!
+//! Each [`AnthropicClient`] owns a small tokio runtime (two worker threads
+//! unless configured otherwise) instead of relying on a process-wide one, so
+//! an app decides when those threads exist. Calls are spawned onto it and the
+//! foreign executor only awaits the result, so nothing ever blocks a caller's
+//! thread. The runtime stops when the client is released (`close()` or `use`
+//! in Kotlin, deinit in Swift) or earlier with [`AnthropicClient::shutdown`],
+//! e.g. when the app is backgrounded; either cancels any request or stream in
+//! flight. Stopping never blocks or panics, even on one of the runtime's own
+//! threads such as inside a listener callback. Calls after a shutdown fail
+//! with [`SdkError::ShutDown`].
+
+use futures::StreamExt;
+use std::{
+    future::Future,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use threatflux_anthropic_sdk::{
+    models::{
+        file::{File, FileUploadRequest},
+        message::{MessageRequest, MessageResponse, StreamEvent},
+    },
+    types::Pagination,
+    Client, Config, ContentBlockDelta, MessageAccumulator, MessageBuilder,
+};
+use tokio::{
+    runtime::{Handle, Runtime},
+    task::AbortHandle,
+};
+
+uniffi::setup_scaffolding!();
+
+/// How long [`AnthropicClient::shutdown`] waits for tasks to wind down
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// An error returned to Kotlin or Swift
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum SdkError {
+    /// The API answered with an error status
+    #[error("API error {status}: {message}")]
+    Api {
+        status: u16,
+        message: String,
+        retryable: bool,
+    },
+    /// The API could not be reached or the connection failed
+    #[error("Network error: {message}")]
+    Network { message: String },
+    /// The request or an argument was rejected before it was sent
+    #[error("Invalid input: {message}")]
+    InvalidInput { message: String },
+    /// The client's runtime was shut down
+    #[error("Client is shut down")]
+    ShutDown,
+    /// Any other SDK failure
+    #[error("{message}")]
+    Other { message: String },
+}
+
+impl From<threatflux_anthropic_sdk::AnthropicError> for SdkError {
+    fn from(error: threatflux_anthropic_sdk::AnthropicError) -> Self {
+        use threatflux_anthropic_sdk::AnthropicError;
+
+        let message = error.to_string();
+        match error {
+            AnthropicError::Api { status, .. } => Self::Api {
+                status,
+                retryable: error.is_retryable(),
+                message,
+            },
+            AnthropicError::Http(_) | AnthropicError::Network(_) | AnthropicError::Timeout(_) => {
+                Self::Network { message }
+            }
+            AnthropicError::InvalidInput(_) | AnthropicError::Config(_) => {
+                Self::InvalidInput { message }
+            }
+            _ => Self::Other { message },
+        }
+    }
+}
+
+impl From<serde_json::Error> for SdkError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::InvalidInput {
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Settings for a new [`AnthropicClient`]
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct ClientOptions {
+    pub api_key: String,
+    #[uniffi(default = None)]
+    pub base_url: Option<String>,
+    #[uniffi(default = None)]
+    pub max_retries: Option<u32>,
+    /// Per-request timeout
+    #[uniffi(default = None)]
+    pub timeout_ms: Option<u64>,
+    /// Worker threads of the client's runtime, 2 by default
+    #[uniffi(default = None)]
+    pub worker_threads: Option<u32>,
+}
+
+/// Who a [`ChatMessage`] is from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum Role {
+    User,
+    Assistant,
+}
+
+/// One text turn of a conversation
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct ChatMessage {
+    pub role: Role,
+    pub text: String,
+}
+
+/// A text conversation to send. Use the `*_json` calls for anything richer.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct MessageParams {
+    pub model: String,
+    pub max_tokens: u32,
+    pub messages: Vec<ChatMessage>,
+    #[uniffi(default = None)]
+    pub system: Option<String>,
+    #[uniffi(default = None)]
+    pub temperature: Option<f32>,
+    #[uniffi(default = [])]
+    pub stop_sequences: Vec<String>,
+}
+
+impl MessageParams {
+    fn into_request(self) -> MessageRequest {
+        let mut builder = MessageBuilder::with_model(self.model).max_tokens(self.max_tokens);
+        if let Some(system) = self.system {
+            builder = builder.system(system);
+        }
+        if let Some(temperature) = self.temperature {
+            builder = builder.temperature(temperature);
+        }
+        for message in self.messages {
+            builder = match message.role {
+                Role::User => builder.user(message.text),
+                Role::Assistant => builder.assistant(message.text),
+            };
+        }
+        builder.stop_sequences(self.stop_sequences).build()
+    }
+}
+
+/// A finished message
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct MessageResult {
+    pub id: String,
+    pub model: String,
+    /// All text blocks, joined
+    pub text: String,
+    pub stop_reason: Option<String>,
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    /// The full Messages API response
+    pub json: String,
+}
+
+impl TryFrom<MessageResponse> for MessageResult {
+    type Error = SdkError;
+
+    fn try_from(response: MessageResponse) -> Result<Self, SdkError> {
+        let stop_reason = response
+            .stop_reason
+            .as_ref()
+            .map(serde_json::to_value)
+            .transpose()?
+            .and_then(|reason| reason.as_str().map(str::to_string));
+        Ok(Self {
+            id: response.id.clone(),
+            model: response.model.clone(),
+            text: response.text(),
+            stop_reason,
+            input_tokens: response.usage.input_tokens,
+            output_tokens: response.usage.output_tokens,
+            json: serde_json::to_string(&response)?,
+        })
+    }
+}
+
+/// An uploaded file
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FileInfo {
+    pub id: String,
+    pub filename: String,
+    pub mime_type: String,
+    pub size_bytes: u64,
+    /// RFC 3339 upload time
+    pub created_at: String,
+}
+
+impl From<File> for FileInfo {
+    fn from(file: File) -> Self {
+        Self {
+            id: file.id,
+            filename: file.filename,
+            mime_type: file.mime_type,
+            size_bytes: file.size_bytes,
+            created_at: file.created_at.to_rfc3339(),
+        }
+    }
+}
+
+/// Receives a stream's progress. Called from a worker thread of the client's
+/// runtime, one call at a time and in order.
+#[uniffi::export(with_foreign)]
+pub trait StreamListener: Send + Sync {
+    /// Every event, as Messages API JSON
+    fn on_event(&self, event_json: String);
+    /// Text appended to the reply
+    fn on_text(&self, text: String);
+    /// The message finished; nothing else is called afterwards
+    fn on_complete(&self, result: MessageResult);
+    /// The stream failed; nothing else is called afterwards
+    fn on_error(&self, error: SdkError);
+}
+
+/// A running stream
+#[derive(Debug, uniffi::Object)]
+pub struct StreamTask {
+    task: AbortHandle,
+}
+
+#[uniffi::export]
+impl StreamTask {
+    /// Stop the stream. The listener is not called again.
+    pub fn cancel(&self) {
+        self.task.abort();
+    }
+
+    /// Whether the stream completed, failed or was cancelled
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+/// A client for the Anthropic API and the runtime its calls run on
+#[derive(uniffi::Object)]
+pub struct AnthropicClient {
+    client: Client,
+    runtime: Mutex<Option<Runtime>>,
+}
+
+impl AnthropicClient {
+    fn handle(&self) -> Result<Handle, SdkError> {
+        self.runtime
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .as_ref()
+            .map(|runtime| runtime.handle().clone())
+            .ok_or(SdkError::ShutDown)
+    }
+
+    /// Run `call` on the client's runtime and wait for it from whatever
+    /// executor polls the returned future
+    async fn run<T, F>(&self, call: impl FnOnce(Client) -> F) -> Result<T, SdkError>
+    where
+        F: Future<Output = Result<T, SdkError>> + Send + 'static,
+        T: Send + 'static,
+    {
+        let task = self.handle()?.spawn(call(self.client.clone()));
+        // A task only fails to finish when the runtime is shut down under it
+        task.await.map_err(|_| SdkError::ShutDown)?
+    }
+}
+
+/// Stop `runtime` without blocking a thread it owns
+fn stop(runtime: Runtime) {
+    if Handle::try_current().is_ok() {
+        runtime.shutdown_background();
+    } else {
+        runtime.shutdown_timeout(SHUTDOWN_TIMEOUT);
+    }
+}
+
+impl Drop for AnthropicClient {
+    fn drop(&mut self) {
+        let runtime = self
+            .runtime
+            .get_mut()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take();
+        if let Some(runtime) = runtime {
+            stop(runtime);
+        }
+    }
+}
+
+#[uniffi::export]
+impl AnthropicClient {
+    #[uniffi::constructor]
+    pub fn new(options: ClientOptions) -> Result<Arc<Self>, SdkError> {
+        let mut config = Config::new(options.api_key)?;
+        if let Some(base_url) = options.base_url {
+            let base_url =
+                base_url
+                    .parse()
+                    .map_err(|e: url::ParseError| SdkError::InvalidInput {
+                        message: format!("Invalid base URL: {}", e),
+                    })?;
+            config = config.with_base_url(base_url);
+        }
+        if let Some(max_retries) = options.max_retries {
+            config = config.with_max_retries(max_retries);
+        }
+        if let Some(timeout_ms) = options.timeout_ms {
+            config = config.with_timeout(Duration::from_millis(timeout_ms));
+        }
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(options.worker_threads.unwrap_or(2).max(1) as usize)
+            .thread_name("anthropic-sdk")
+            .enable_all()
+            .build()
+            .map_err(|e| SdkError::Other {
+                message: format!("Failed to start runtime: {}", e),
+            })?;
+        // The HTTP client must be built inside the runtime its connections
+        // will live on
+        let client = {
+            let _guard = runtime.enter();
+            Client::try_new(config)?
+        };
+        Ok(Arc::new(Self {
+            client,
+            runtime: Mutex::new(Some(runtime)),
+        }))
+    }
+
+    /// Send a conversation and wait for the reply
+    pub async fn create_message(&self, params: MessageParams) -> Result<MessageResult, SdkError> {
+        self.run(|client| async move {
+            let response = client
+                .messages()
+                .create(params.into_request(), None)
+                .await?;
+            MessageResult::try_from(response)
+        })
+        .await
+    }
+
+    /// Send a request given as Messages API JSON and return the response JSON
+    pub async fn create_message_json(&self, request_json: String) -> Result<String, SdkError> {
+        let request: MessageRequest = serde_json::from_str(&request_json)?;
+        self.run(|client| async move {
+            let response = client.messages().create(request, None).await?;
+            Ok(serde_json::to_string(&response)?)
+        })
+        .await
+    }
+
+    /// Stream the reply to a conversation into `listener`
+    pub fn stream_message(
+        &self,
+        params: MessageParams,
+        listener: Arc<dyn StreamListener>,
+    ) -> Result<Arc<StreamTask>, SdkError> {
+        self.stream(params.into_request(), listener)
+    }
+
+    /// Stream a request given as Messages API JSON into `listener`
+    pub fn stream_message_json(
+        &self,
+        request_json: String,
+        listener: Arc<dyn StreamListener>,
+    ) -> Result<Arc<StreamTask>, SdkError> {
+        self.stream(serde_json::from_str(&request_json)?, listener)
+    }
+
+    /// Upload the file at `path`; the MIME type is taken from its extension
+    pub async fn upload_file(&self, path: String) -> Result<FileInfo, SdkError> {
+        self.run(|client| async move {
+            let uploaded = client
+                .files()
+                .upload_from_path(Path::new(&path), "user_data", None, None)
+                .await?;
+            Ok(uploaded.file.into())
+        })
+        .await
+    }
+
+    /// Upload `content` as a file named `filename`
+    pub async fn upload_bytes(
+        &self,
+        filename: String,
+        content: Vec<u8>,
+        mime_type: String,
+    ) -> Result<FileInfo, SdkError> {
+        self.run(|client| async move {
+            let request = FileUploadRequest::new(content, filename, mime_type);
+            Ok(client.files().upload(request, None).await?.file.into())
+        })
+        .await
+    }
+
+    /// The most recently uploaded files, up to `limit`
+    pub async fn list_files(&self, limit: Option<u32>) -> Result<Vec<FileInfo>, SdkError> {
+        self.run(|client| async move {
+            let pagination = limit.map(|limit| Pagination::new().with_limit(limit));
+            let page = client.files().list(pagination, None).await?;
+            Ok(page.data.into_iter().map(FileInfo::from).collect())
+        })
+        .await
+    }
+
+    pub async fn get_file(&self, file_id: String) -> Result<FileInfo, SdkError> {
+        self.run(|client| async move { Ok(client.files().get(&file_id, None).await?.into()) })
+            .await
+    }
+
+    /// The content of a file
+    pub async fn download_file(&self, file_id: String) -> Result<Vec<u8>, SdkError> {
+        self.run(|client| async move { Ok(client.files().download(&file_id, None).await?) })
+            .await
+    }
+
+    pub async fn delete_file(&self, file_id: String) -> Result<(), SdkError> {
+        self.run(|client| async move { Ok(client.files().delete(&file_id, None).await?) })
+            .await
+    }
+
+    /// Stop the client's runtime, cancelling everything in flight. Later
+    /// calls fail with [`SdkError::ShutDown`]; shutting down twice is
+    /// harmless.
+    pub fn shutdown(&self) {
+        let runtime = self
+            .runtime
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take();
+        if let Some(runtime) = runtime {
+            stop(runtime);
+        }
+    }
+
+    pub fn is_shut_down(&self) -> bool {
+        self.handle().is_err()
+    }
+}
+
+impl AnthropicClient {
+    fn stream(
+        &self,
+        request: MessageRequest,
+        listener: Arc<dyn StreamListener>,
+    ) -> Result<Arc<StreamTask>, SdkError> {
+        let client = self.client.clone();
+        let task = self.handle()?.spawn(async move {
+            let streamed = async {
+                let mut stream = client.messages().create_stream(request, None).await?;
+                let mut accumulator = MessageAccumulator::new();
+                while let Some(event) = stream.next().await {
+                    let event = event?;
+                    listener.on_event(serde_json::to_string(&event)?);
+                    if let StreamEvent::ContentBlockDelta {
+                        delta: ContentBlockDelta::TextDelta { text },
+                        ..
+                    } = &event
+                    {
+                        listener.on_text(text.clone());
+                    }
+                    accumulator.push(event)?;
+                    if accumulator.is_stopped() {
+                        break;
+                    }
+                }
+                MessageResult::try_from(accumulator.finish()?)
+            };
+            match streamed.await {
+                Ok(result) => listener.on_complete(result),
+                Err(error) => listener.on_error(error),
+            }
+        });
+        Ok(Arc::new(StreamTask {
+            task: task.abort_handle(),
+        }))
+    }
+}
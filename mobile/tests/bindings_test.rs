@@ -0,0 +1,158 @@
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use threatflux_anthropic_mobile::{
+    AnthropicClient, ChatMessage, ClientOptions, MessageParams, MessageResult, Role, SdkError,
+    StreamListener,
+};
+use tokio::sync::oneshot;
+use wiremock::{
+    matchers::{body_partial_json, method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+fn client(server: &MockServer) -> Arc<AnthropicClient> {
+    AnthropicClient::new(ClientOptions {
+        api_key: "sk-ant-test-key".to_string(),
+        base_url: Some(server.uri()),
+        max_retries: Some(0),
+        timeout_ms: None,
+        worker_threads: Some(1),
+    })
+    .unwrap()
+}
+
+fn params() -> MessageParams {
+    MessageParams {
+        model: "claude-haiku-4-5".to_string(),
+        max_tokens: 64,
+        messages: vec![ChatMessage {
+            role: Role::User,
+            text: "Hello".to_string(),
+        }],
+        system: Some("Be brief".to_string()),
+        temperature: None,
+        stop_sequences: Vec::new(),
+    }
+}
+
+#[derive(Default)]
+struct Recorder {
+    text: Mutex<String>,
+    events: Mutex<usize>,
+    done: Mutex<Option<oneshot::Sender<Result<MessageResult, SdkError>>>>,
+    client: Mutex<Option<Arc<AnthropicClient>>>,
+}
+
+impl Recorder {
+    fn finish(&self, result: Result<MessageResult, SdkError>) {
+        // Shutting the client down from its own worker thread must not panic
+        if let Some(client) = self.client.lock().unwrap().take() {
+            client.shutdown();
+        }
+        if let Some(done) = self.done.lock().unwrap().take() {
+            let _ = done.send(result);
+        }
+    }
+}
+
+impl StreamListener for Recorder {
+    fn on_event(&self, _event_json: String) {
+        *self.events.lock().unwrap() += 1;
+    }
+
+    fn on_text(&self, text: String) {
+        self.text.lock().unwrap().push_str(&text);
+    }
+
+    fn on_complete(&self, result: MessageResult) {
+        self.finish(Ok(result));
+    }
+
+    fn on_error(&self, error: SdkError) {
+        self.finish(Err(error));
+    }
+}
+
+#[tokio::test]
+async fn test_create_message_and_shutdown() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .and(body_partial_json(json!({"system": "Be brief"})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "msg_1",
+            "type": "message",
+            "role": "assistant",
+            "model": "claude-haiku-4-5",
+            "content": [{"type": "text", "text": "Hi there"}],
+            "stop_reason": "end_turn",
+            "stop_sequence": null,
+            "usage": {"input_tokens": 5, "output_tokens": 3}
+        })))
+        .mount(&server)
+        .await;
+
+    let client = client(&server);
+    let result = client.create_message(params()).await.unwrap();
+    assert_eq!(result.text, "Hi there");
+    assert_eq!(result.stop_reason.as_deref(), Some("end_turn"));
+    assert_eq!((result.input_tokens, result.output_tokens), (5, 3));
+
+    client.shutdown();
+    assert!(client.is_shut_down());
+    assert!(matches!(
+        client.create_message(params()).await,
+        Err(SdkError::ShutDown)
+    ));
+}
+
+#[tokio::test]
+async fn test_stream_message_reports_to_listener() {
+    let server = MockServer::start().await;
+    let body = [
+        "event: message_start",
+        r#"data: {"type":"message_start","message":{"id":"msg_2","type":"message","role":"assistant","model":"claude-haiku-4-5","content":[],"stop_reason":null,"stop_sequence":null,"usage":{"input_tokens":5,"output_tokens":0}}}"#,
+        "",
+        "event: content_block_start",
+        r#"data: {"type":"content_block_start","index":0,"content_block":{"type":"text","text":""}}"#,
+        "",
+        "event: content_block_delta",
+        r#"data: {"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hi"}}"#,
+        "",
+        "event: content_block_delta",
+        r#"data: {"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":" there"}}"#,
+        "",
+        "event: content_block_stop",
+        r#"data: {"type":"content_block_stop","index":0}"#,
+        "",
+        "event: message_delta",
+        r#"data: {"type":"message_delta","delta":{"stop_reason":"end_turn"},"usage":{"output_tokens":2}}"#,
+        "",
+        "event: message_stop",
+        r#"data: {"type":"message_stop"}"#,
+        "",
+        "",
+    ]
+    .join("\n");
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/event-stream"))
+        .mount(&server)
+        .await;
+
+    let client = client(&server);
+    let (done, finished) = oneshot::channel();
+    let recorder = Arc::new(Recorder::default());
+    *recorder.done.lock().unwrap() = Some(done);
+    *recorder.client.lock().unwrap() = Some(Arc::clone(&client));
+
+    let task = client.stream_message(params(), recorder.clone()).unwrap();
+    let result = finished.await.unwrap().unwrap();
+
+    assert_eq!(result.text, "Hi there");
+    assert_eq!(result.output_tokens, 2);
+    assert_eq!(*recorder.text.lock().unwrap(), "Hi there");
+    assert_eq!(*recorder.events.lock().unwrap(), 7);
+    assert!(client.is_shut_down());
+    task.cancel();
+}
@@ -0,0 +1,440 @@
+//! Python bindings for threatflux-anthropic-sdk
+//!
+//! Build with [maturin](https://www.maturin.rs) from this directory
+//! (`maturin develop --release`) and import `threatflux_anthropic`:
+//!
+//! ```python
+//! from threatflux_anthropic import Client, MessageBuilder
+//!
+//! client = Client()  # ANTHROPIC_API_KEY
+//! request = MessageBuilder("claude-haiku-4-5").max_tokens(256).user("Hello")
+//! print(client.create_message(request)["content"][0]["text"])
+//!
+//! for event in client.stream_message(request):
+//!     print(event["type"])
+//!
+//! # In asyncio code every call has an `_async` twin, and streams are async
+//! # iterators
+//! response = await client.create_message_async(request)
+//! async for event in await client.stream_message_async(request):
+//!     ...
+//! ```
+//!
+//! Requests, responses and stream events cross into Python as the JSON shapes
+//! of the Anthropic API, as dicts and lists. Blocking calls release the GIL
+//! while they wait; async calls run on a shared tokio runtime. Errors raise
+//! `threatflux_anthropic.AnthropicError`.
+
+use futures::StreamExt;
+use pyo3::{
+    create_exception,
+    exceptions::{PyException, PyStopAsyncIteration, PyValueError},
+    prelude::*,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{future::Future, sync::Arc};
+use threatflux_anthropic_sdk::{
+    builders,
+    models::{
+        batch::{BatchRequestItem, MessageBatchCreateRequest, PollOptions},
+        message::MessageRequest,
+    },
+    Config, MessageStream,
+};
+use tokio::sync::Mutex;
+
+create_exception!(
+    threatflux_anthropic,
+    AnthropicError,
+    PyException,
+    "An error returned by the SDK or the Anthropic API"
+);
+
+fn to_py_err(error: threatflux_anthropic_sdk::AnthropicError) -> PyErr {
+    AnthropicError::new_err(error.to_string())
+}
+
+/// `value` as Python objects, via its JSON form
+fn to_python<T: Serialize>(py: Python<'_>, value: &T) -> PyResult<PyObject> {
+    let json = serde_json::to_string(value).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    Ok(py.import("json")?.call_method1("loads", (json,))?.unbind())
+}
+
+/// A Python object converted through its JSON form
+fn from_python<T: DeserializeOwned>(value: &Bound<'_, PyAny>) -> PyResult<T> {
+    let json: String = value
+        .py()
+        .import("json")?
+        .call_method1("dumps", (value,))?
+        .extract()?;
+    serde_json::from_str(&json).map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+/// A request given as a [`MessageBuilder`] or a Messages API dict
+fn message_request(request: &Bound<'_, PyAny>) -> PyResult<MessageRequest> {
+    match request.downcast::<MessageBuilder>() {
+        Ok(builder) => Ok(builder.borrow().inner.clone().build()),
+        Err(_) => from_python(request),
+    }
+}
+
+fn runtime() -> &'static tokio::runtime::Runtime {
+    pyo3_async_runtimes::tokio::get_runtime()
+}
+
+/// Run `call` to completion with the GIL released
+fn call<F, T>(py: Python<'_>, call: F) -> PyResult<PyObject>
+where
+    F: Future<Output = threatflux_anthropic_sdk::Result<T>> + Send,
+    T: Serialize + Send,
+{
+    let value = py
+        .allow_threads(|| runtime().block_on(call))
+        .map_err(to_py_err)?;
+    to_python(py, &value)
+}
+
+/// `call` as a Python awaitable
+fn call_async<'py, F, T>(py: Python<'py>, call: F) -> PyResult<Bound<'py, PyAny>>
+where
+    F: Future<Output = threatflux_anthropic_sdk::Result<T>> + Send + 'static,
+    T: Serialize + Send + 'static,
+{
+    pyo3_async_runtimes::tokio::future_into_py(py, async move {
+        let value = call.await.map_err(to_py_err)?;
+        Python::with_gil(|py| to_python(py, &value))
+    })
+}
+
+fn poll_options(interval: Option<f64>) -> PollOptions {
+    match interval {
+        Some(seconds) => PollOptions::fixed(std::time::Duration::from_secs_f64(seconds)),
+        None => PollOptions::new(),
+    }
+}
+
+/// Fluent builder for Messages API requests; each method returns the builder
+#[pyclass(module = "threatflux_anthropic")]
+struct MessageBuilder {
+    inner: builders::MessageBuilder,
+}
+
+impl MessageBuilder {
+    fn update(
+        mut slf: PyRefMut<'_, Self>,
+        change: impl FnOnce(builders::MessageBuilder) -> builders::MessageBuilder,
+    ) -> PyRefMut<'_, Self> {
+        let inner = std::mem::take(&mut slf.inner);
+        slf.inner = change(inner);
+        slf
+    }
+}
+
+#[pymethods]
+impl MessageBuilder {
+    #[new]
+    #[pyo3(signature = (model=None))]
+    fn new(model: Option<String>) -> Self {
+        let inner = match model {
+            Some(model) => builders::MessageBuilder::with_model(model),
+            None => builders::MessageBuilder::new(),
+        };
+        Self { inner }
+    }
+
+    fn model(slf: PyRefMut<'_, Self>, model: String) -> PyRefMut<'_, Self> {
+        Self::update(slf, |b| b.model(model))
+    }
+
+    fn max_tokens(slf: PyRefMut<'_, Self>, max_tokens: u32) -> PyRefMut<'_, Self> {
+        Self::update(slf, |b| b.max_tokens(max_tokens))
+    }
+
+    fn system(slf: PyRefMut<'_, Self>, system: String) -> PyRefMut<'_, Self> {
+        Self::update(slf, |b| b.system(system))
+    }
+
+    fn user(slf: PyRefMut<'_, Self>, text: String) -> PyRefMut<'_, Self> {
+        Self::update(slf, |b| b.user(text))
+    }
+
+    fn assistant(slf: PyRefMut<'_, Self>, text: String) -> PyRefMut<'_, Self> {
+        Self::update(slf, |b| b.assistant(text))
+    }
+
+    fn temperature(slf: PyRefMut<'_, Self>, temperature: f32) -> PyRefMut<'_, Self> {
+        Self::update(slf, |b| b.temperature(temperature))
+    }
+
+    fn top_p(slf: PyRefMut<'_, Self>, top_p: f32) -> PyRefMut<'_, Self> {
+        Self::update(slf, |b| b.top_p(top_p))
+    }
+
+    fn top_k(slf: PyRefMut<'_, Self>, top_k: u32) -> PyRefMut<'_, Self> {
+        Self::update(slf, |b| b.top_k(top_k))
+    }
+
+    fn stop_sequence(slf: PyRefMut<'_, Self>, stop: String) -> PyRefMut<'_, Self> {
+        Self::update(slf, |b| b.stop_sequence(stop))
+    }
+
+    /// The request as a Messages API dict
+    fn build(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_python(py, &self.inner.clone().build())
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.inner)
+    }
+}
+
+/// Events of a message stream; iterate it, or `async for` over it
+#[pyclass(module = "threatflux_anthropic")]
+struct MessageEvents {
+    stream: Arc<Mutex<MessageStream>>,
+}
+
+#[pymethods]
+impl MessageEvents {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&self, py: Python<'_>) -> PyResult<Option<PyObject>> {
+        let stream = Arc::clone(&self.stream);
+        let event =
+            py.allow_threads(|| runtime().block_on(async { stream.lock().await.next().await }));
+        match event {
+            Some(event) => to_python(py, &event.map_err(to_py_err)?).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __anext__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let stream = Arc::clone(&self.stream);
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            match stream.lock().await.next().await {
+                Some(event) => {
+                    let event = event.map_err(to_py_err)?;
+                    Python::with_gil(|py| to_python(py, &event))
+                }
+                None => Err(PyStopAsyncIteration::new_err(())),
+            }
+        })
+    }
+}
+
+/// Anthropic API client
+#[pyclass(module = "threatflux_anthropic")]
+struct Client {
+    inner: threatflux_anthropic_sdk::Client,
+}
+
+#[pymethods]
+impl Client {
+    /// A client for `api_key`, or configured from the `ANTHROPIC_*`
+    /// environment variables when it is omitted
+    #[new]
+    #[pyo3(signature = (api_key=None, base_url=None, max_retries=None))]
+    fn new(
+        api_key: Option<String>,
+        base_url: Option<String>,
+        max_retries: Option<u32>,
+    ) -> PyResult<Self> {
+        let mut config = match api_key {
+            Some(api_key) => Config::new(api_key),
+            None => Config::from_env(),
+        }
+        .map_err(to_py_err)?;
+        if let Some(base_url) = base_url {
+            let base_url = base_url
+                .parse()
+                .map_err(|e: url::ParseError| PyValueError::new_err(e.to_string()))?;
+            config = config.with_base_url(base_url);
+        }
+        if let Some(max_retries) = max_retries {
+            config = config.with_max_retries(max_retries);
+        }
+        let inner = threatflux_anthropic_sdk::Client::try_new(config).map_err(to_py_err)?;
+        Ok(Self { inner })
+    }
+
+    /// Send a message request and return the response
+    fn create_message(&self, py: Python<'_>, request: &Bound<'_, PyAny>) -> PyResult<PyObject> {
+        let request = message_request(request)?;
+        call(py, self.inner.messages().create(request, None))
+    }
+
+    fn create_message_async<'py>(
+        &self,
+        py: Python<'py>,
+        request: &Bound<'_, PyAny>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let request = message_request(request)?;
+        let client = self.inner.clone();
+        call_async(
+            py,
+            async move { client.messages().create(request, None).await },
+        )
+    }
+
+    /// Stream a message request, returning its events
+    fn stream_message(
+        &self,
+        py: Python<'_>,
+        request: &Bound<'_, PyAny>,
+    ) -> PyResult<MessageEvents> {
+        let request = message_request(request)?;
+        let client = self.inner.clone();
+        let stream = py
+            .allow_threads(|| runtime().block_on(client.messages().create_stream(request, None)))
+            .map_err(to_py_err)?;
+        Ok(MessageEvents {
+            stream: Arc::new(Mutex::new(stream)),
+        })
+    }
+
+    fn stream_message_async<'py>(
+        &self,
+        py: Python<'py>,
+        request: &Bound<'_, PyAny>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let request = message_request(request)?;
+        let client = self.inner.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            let stream = client
+                .messages()
+                .create_stream(request, None)
+                .await
+                .map_err(to_py_err)?;
+            Ok(MessageEvents {
+                stream: Arc::new(Mutex::new(stream)),
+            })
+        })
+    }
+
+    /// Submit a batch of `{"custom_id": ..., "params": {...}}` requests
+    fn create_batch(&self, py: Python<'_>, requests: &Bound<'_, PyAny>) -> PyResult<PyObject> {
+        let requests: Vec<BatchRequestItem> = from_python(requests)?;
+        let client = self.inner.clone();
+        call(py, async move {
+            let request = MessageBatchCreateRequest { requests };
+            client.message_batches().create(request, None).await
+        })
+    }
+
+    fn create_batch_async<'py>(
+        &self,
+        py: Python<'py>,
+        requests: &Bound<'_, PyAny>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let requests: Vec<BatchRequestItem> = from_python(requests)?;
+        let client = self.inner.clone();
+        call_async(py, async move {
+            let request = MessageBatchCreateRequest { requests };
+            client.message_batches().create(request, None).await
+        })
+    }
+
+    fn get_batch(&self, py: Python<'_>, batch_id: String) -> PyResult<PyObject> {
+        let client = self.inner.clone();
+        call(py, async move {
+            client.message_batches().retrieve(&batch_id, None).await
+        })
+    }
+
+    fn get_batch_async<'py>(
+        &self,
+        py: Python<'py>,
+        batch_id: String,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let client = self.inner.clone();
+        call_async(py, async move {
+            client.message_batches().retrieve(&batch_id, None).await
+        })
+    }
+
+    fn cancel_batch(&self, py: Python<'_>, batch_id: String) -> PyResult<PyObject> {
+        let client = self.inner.clone();
+        call(py, async move {
+            client.message_batches().cancel(&batch_id, None).await
+        })
+    }
+
+    fn cancel_batch_async<'py>(
+        &self,
+        py: Python<'py>,
+        batch_id: String,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let client = self.inner.clone();
+        call_async(py, async move {
+            client.message_batches().cancel(&batch_id, None).await
+        })
+    }
+
+    /// Wait until a batch has ended, polling every `poll_interval` seconds
+    /// or with the SDK's backoff when omitted
+    #[pyo3(signature = (batch_id, poll_interval=None))]
+    fn wait_for_batch(
+        &self,
+        py: Python<'_>,
+        batch_id: String,
+        poll_interval: Option<f64>,
+    ) -> PyResult<PyObject> {
+        let client = self.inner.clone();
+        call(py, async move {
+            client
+                .message_batches()
+                .wait_for_completion(&batch_id, poll_options(poll_interval))
+                .await
+        })
+    }
+
+    #[pyo3(signature = (batch_id, poll_interval=None))]
+    fn wait_for_batch_async<'py>(
+        &self,
+        py: Python<'py>,
+        batch_id: String,
+        poll_interval: Option<f64>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let client = self.inner.clone();
+        call_async(py, async move {
+            client
+                .message_batches()
+                .wait_for_completion(&batch_id, poll_options(poll_interval))
+                .await
+        })
+    }
+
+    /// The result entries of an ended batch
+    fn batch_results(&self, py: Python<'_>, batch_id: String) -> PyResult<PyObject> {
+        let client = self.inner.clone();
+        call(py, async move {
+            client.message_batches().results(&batch_id, None).await
+        })
+    }
+
+    fn batch_results_async<'py>(
+        &self,
+        py: Python<'py>,
+        batch_id: String,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let client = self.inner.clone();
+        call_async(py, async move {
+            client.message_batches().results(&batch_id, None).await
+        })
+    }
+}
+
+#[pymodule]
+fn threatflux_anthropic(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Client>()?;
+    m.add_class::<MessageBuilder>()?;
+    m.add_class::<MessageEvents>()?;
+    m.add("AnthropicError", m.py().get_type::<AnthropicError>())?;
+    Ok(())
+}
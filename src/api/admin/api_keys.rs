@@ -26,7 +26,7 @@ impl ApiKeysApi {
     pub async fn list(
         &self,
         workspace_id: Option<&str>,
-        pagination: Option<Pagination>,
+        pagination: Option<Pagination<ApiKey>>,
         options: Option<RequestOptions>,
     ) -> Result<ApiKeyListResponse> {
         let mut params = ApiKeyListParams::new();
@@ -188,7 +188,7 @@ impl ApiKeysApi {
                 break;
             }
 
-            after_id = response.last_id;
+            after_id = response.last_id.map(String::from);
         }
 
         Ok(all_keys)
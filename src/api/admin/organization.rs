@@ -10,7 +10,7 @@ use crate::{
         MemberRole, MemberStatus, MemberUpdateRequest, Organization, User, UserDeleteResponse,
         UserListParams, UserListResponse, UserRole, UserUpdateRequest, UserUpdateRole,
     },
-    types::{HttpMethod, Pagination, RequestOptions},
+    types::{Cursor, HttpMethod, Pagination, RequestOptions},
 };
 
 /// API client for Organization admin endpoints
@@ -35,7 +35,7 @@ impl OrganizationApi {
     /// List organization users.
     pub async fn list_users(
         &self,
-        pagination: Option<Pagination>,
+        pagination: Option<Pagination<User>>,
         options: Option<RequestOptions>,
     ) -> Result<UserListResponse> {
         let mut params = UserListParams::new();
@@ -116,7 +116,7 @@ impl OrganizationApi {
     /// List organization invites.
     pub async fn list_invites(
         &self,
-        pagination: Option<Pagination>,
+        pagination: Option<Pagination<Invite>>,
         options: Option<RequestOptions>,
     ) -> Result<InviteListResponse> {
         let mut params = InviteListParams::new();
@@ -223,9 +223,15 @@ impl OrganizationApi {
     #[deprecated(note = "Use list_users/list_users_with_params for full Admin API parity")]
     pub async fn list_members(
         &self,
-        pagination: Option<Pagination>,
+        pagination: Option<Pagination<Member>>,
         options: Option<RequestOptions>,
     ) -> Result<MemberListResponse> {
+        // Members are users under their legacy name
+        let pagination = pagination.map(|pagination| Pagination {
+            limit: pagination.limit,
+            after: pagination.after.map(Cursor::cast),
+            before: pagination.before.map(Cursor::cast),
+        });
         let response = self.list_users(pagination, options).await?;
         Ok(MemberListResponse {
             data: response
@@ -234,8 +240,8 @@ impl OrganizationApi {
                 .map(Self::user_to_member)
                 .collect(),
             has_more: response.has_more,
-            first_id: response.first_id,
-            last_id: response.last_id,
+            first_id: response.first_id.map(Cursor::cast),
+            last_id: response.last_id.map(Cursor::cast),
         })
     }
 
@@ -168,6 +168,25 @@ mod tests {
             "/v1/organizations/usage_report/claude_code"
         );
     }
+
+    #[tokio::test]
+    async fn test_inverted_report_window_is_rejected_locally() {
+        let server = MockServer::start().await;
+        let config = Config::new("test-key")
+            .unwrap()
+            .with_admin_key("admin-key")
+            .with_base_url(server.uri().parse().unwrap());
+        let api = UsageApi::new(Client::new(config));
+
+        let start = Utc.with_ymd_and_hms(2026, 1, 2, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+        let error = api
+            .get_message_usage_report(MessageUsageReportParams::new(start).ending_at(end), None)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, crate::AnthropicError::InvalidInput(_)));
+        assert!(server.received_requests().await.unwrap().is_empty());
+    }
 }
 
 impl UsageApi {
@@ -186,6 +205,18 @@ impl UsageApi {
         }
     }
 
+    /// Reject windows whose end does not come after their start before they
+    /// reach the API.
+    fn check_window<T: PartialOrd + std::fmt::Display>(start: &T, end: Option<&T>) -> Result<()> {
+        match end {
+            Some(end) if end <= start => Err(AnthropicError::invalid_input(format!(
+                "Report ending_at ({}) must be after starting_at ({})",
+                end, start
+            ))),
+            _ => Ok(()),
+        }
+    }
+
     fn build_message_usage_report_query(params: MessageUsageReportParams) -> Vec<String> {
         let mut query = vec![format!("starting_at={}", params.starting_at.to_rfc3339())];
 
@@ -274,6 +305,7 @@ impl UsageApi {
         params: MessageUsageReportParams,
         options: Option<RequestOptions>,
     ) -> Result<MessageUsageReportResponse> {
+        Self::check_window(&params.starting_at, params.ending_at.as_ref())?;
         let query = Self::build_message_usage_report_query(params);
         let path = build_path_with_query("/organizations/usage_report/messages", query);
         self.client
@@ -287,6 +319,7 @@ impl UsageApi {
         params: MessageCostReportParams,
         options: Option<RequestOptions>,
     ) -> Result<MessageCostReportResponse> {
+        Self::check_window(&params.starting_at, params.ending_at.as_ref())?;
         let query = Self::build_message_cost_report_query(params);
         let path = build_path_with_query("/organizations/cost_report", query);
         self.client
@@ -300,6 +333,7 @@ impl UsageApi {
         params: ClaudeCodeUsageReportParams,
         options: Option<RequestOptions>,
     ) -> Result<ClaudeCodeUsageReportResponse> {
+        Self::check_window(&params.starting_at, params.ending_at.as_ref())?;
         let query = Self::build_claude_code_usage_report_query(params);
         let path = build_path_with_query("/organizations/usage_report/claude_code", query);
         self.client
@@ -385,7 +419,7 @@ impl UsageApi {
     pub async fn list_usage_history(
         &self,
         workspace_id: Option<&str>,
-        pagination: Option<Pagination>,
+        pagination: Option<Pagination<UsageReport>>,
         options: Option<RequestOptions>,
     ) -> Result<UsageReportListResponse> {
         let _ = workspace_id;
@@ -28,7 +28,7 @@ impl WorkspaceApi {
     /// List workspaces
     pub async fn list(
         &self,
-        pagination: Option<Pagination>,
+        pagination: Option<Pagination<Workspace>>,
         options: Option<RequestOptions>,
     ) -> Result<WorkspaceListResponse> {
         let mut params = WorkspaceListParams::new();
@@ -153,7 +153,7 @@ impl WorkspaceApi {
     pub async fn list_members(
         &self,
         workspace_id: &str,
-        pagination: Option<Pagination>,
+        pagination: Option<Pagination<WorkspaceMember>>,
         options: Option<RequestOptions>,
     ) -> Result<WorkspaceMemberListResponse> {
         let mut params = WorkspaceMemberListParams::new();
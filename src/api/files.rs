@@ -6,9 +6,16 @@ use crate::{
         create_default_pagination,
     },
     client::Client,
-    error::Result,
+    error::{ResponseClass, Result},
+    metrics::BufferKind,
     models::file::{File, FileListParams, FileListResponse, FileUploadRequest, FileUploadResponse},
     types::{HttpMethod, Pagination, ProgressCallback, RequestOptions},
+    utils::{
+        http::{read_body, read_download, read_error_text},
+        instrumentation,
+        integrity::ContentDigest,
+        object_store::{key_name, ObjectStore},
+    },
 };
 use reqwest::multipart::{Form, Part};
 use std::path::Path;
@@ -28,6 +35,9 @@ impl FilesApi {
 
     /// Upload a file
     ///
+    /// The file part carries `Content-MD5` and `Content-Digest` headers; see
+    /// [`crate::utils::integrity`].
+    ///
     /// # Example
     /// ```rust,no_run
     /// use threatflux_anthropic_sdk::{Client, Config, models::file::FileUploadRequest};
@@ -44,15 +54,25 @@ impl FilesApi {
     /// # Ok(())
     /// # }
     /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "anthropic.files.upload",
+            skip_all,
+            fields(filename = %request.filename, size = request.content.len())
+        )
+    )]
     pub async fn upload(
         &self,
         request: FileUploadRequest,
         options: Option<RequestOptions>,
     ) -> Result<FileUploadResponse> {
+        let digest = ContentDigest::compute(&request.content);
         let form = Form::new()
             .part(
                 "file",
                 Part::bytes(request.content)
+                    .headers(digest.headers())
                     .file_name(request.filename)
                     .mime_str(&request.mime_type)
                     .map_err(|e| {
@@ -65,7 +85,7 @@ impl FilesApi {
             .text("purpose", request.purpose);
 
         // For file uploads, we need to use multipart form data instead of JSON
-        let mut url = self.client.config().base_url.clone();
+        let mut url = self.client.config().active_base_url();
         url.set_path("/v1/files");
         let headers = self.client.build_headers(&options)?;
 
@@ -84,7 +104,9 @@ impl FilesApi {
         let status = response.status();
 
         if !status.is_success() {
-            let error_text = response.text().await.unwrap_or_default();
+            let error_text = read_error_text(response, &self.client.config().response_limits)
+                .await
+                .unwrap_or_default();
             return Err(crate::error::AnthropicError::api_error(
                 status.as_u16(),
                 error_text,
@@ -92,7 +114,9 @@ impl FilesApi {
             ));
         }
 
-        let file_response: FileUploadResponse = response.json().await?;
+        let limits = &self.client.config().response_limits;
+        let body = read_body(response, limits, ResponseClass::Api).await?;
+        let file_response: FileUploadResponse = serde_json::from_slice(&body)?;
         Ok(file_response)
     }
 
@@ -154,6 +178,30 @@ impl FilesApi {
         result
     }
 
+    /// Upload the object under `key` in `store`, named after the key's last
+    /// segment.
+    ///
+    /// Lets files staged in a bucket by another system be handed to the API
+    /// without a local copy.
+    pub async fn upload_from_store(
+        &self,
+        store: &dyn ObjectStore,
+        key: &str,
+        purpose: &str,
+        options: Option<RequestOptions>,
+    ) -> Result<FileUploadResponse> {
+        let content = store
+            .get(key)
+            .await
+            .map_err(|e| e.with_context(format!("Staged upload {}", key)))?;
+        let filename = key_name(key);
+        let mime_type = mime_guess::from_path(filename)
+            .first_or_octet_stream()
+            .to_string();
+        let request = FileUploadRequest::new(content, filename, &mime_type).purpose(purpose);
+        self.upload(request, options).await
+    }
+
     /// List files
     ///
     /// # Example
@@ -171,9 +219,13 @@ impl FilesApi {
     /// # Ok(())
     /// # }
     /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "anthropic.files.list", skip_all)
+    )]
     pub async fn list(
         &self,
-        pagination: Option<Pagination>,
+        pagination: Option<Pagination<File>>,
         options: Option<RequestOptions>,
     ) -> Result<FileListResponse> {
         let path = build_paginated_path("/files", pagination.as_ref());
@@ -204,9 +256,13 @@ impl FilesApi {
     /// # Ok(())
     /// # }
     /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "anthropic.files.list", skip_all)
+    )]
     pub async fn list_with_params(
         &self,
-        pagination: Option<Pagination>,
+        pagination: Option<Pagination<File>>,
         params: FileListParams,
         options: Option<RequestOptions>,
     ) -> Result<FileListResponse> {
@@ -237,6 +293,14 @@ impl FilesApi {
     /// # Ok(())
     /// # }
     /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "anthropic.files.get",
+            skip_all,
+            fields(file_id = %file_id)
+        )
+    )]
     pub async fn get(&self, file_id: &str, options: Option<RequestOptions>) -> Result<File> {
         let path = format!("/files/{}", file_id);
         self.client
@@ -246,6 +310,11 @@ impl FilesApi {
 
     /// Download file content
     ///
+    /// Checksum headers on the response are verified against the received
+    /// bytes, failing with [`AnthropicError::Integrity`] on a mismatch.
+    ///
+    /// [`AnthropicError::Integrity`]: crate::error::AnthropicError::Integrity
+    ///
     /// # Example
     /// ```rust,no_run
     /// use threatflux_anthropic_sdk::{Client, Config};
@@ -258,6 +327,14 @@ impl FilesApi {
     /// # Ok(())
     /// # }
     /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "anthropic.files.download",
+            skip_all,
+            fields(file_id = %file_id, bytes = tracing::field::Empty)
+        )
+    )]
     pub async fn download(
         &self,
         file_id: &str,
@@ -269,7 +346,11 @@ impl FilesApi {
             .request_stream(HttpMethod::Get, &path, None, options)
             .await?;
 
-        let bytes = response.bytes().await?;
+        let headers = response.headers().clone();
+        let limits = &self.client.config().response_limits;
+        let bytes = read_download(response, limits, BufferKind::FileDownload).await?;
+        instrumentation::record("bytes", bytes.len());
+        ContentDigest::compute(&bytes).verify(&headers, &format!("file {}", file_id))?;
         Ok(bytes.to_vec())
     }
 
@@ -325,6 +406,14 @@ impl FilesApi {
     /// # Ok(())
     /// # }
     /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "anthropic.files.delete",
+            skip_all,
+            fields(file_id = %file_id)
+        )
+    )]
     pub async fn delete(&self, file_id: &str, options: Option<RequestOptions>) -> Result<()> {
         let path = format!("/files/{}", file_id);
         let _: serde_json::Value = self
@@ -58,7 +58,7 @@ impl AgentsApi {
     /// List agents (cursor-style pagination).
     pub async fn list(
         &self,
-        pagination: Option<Pagination>,
+        pagination: Option<Pagination<Agent>>,
         options: Option<RequestOptions>,
     ) -> Result<AgentListResponse> {
         let path = build_paginated_path("/agents", pagination.as_ref());
@@ -45,7 +45,7 @@ impl DeploymentsApi {
     /// List deployments (cursor-style pagination).
     pub async fn list(
         &self,
-        pagination: Option<Pagination>,
+        pagination: Option<Pagination<Deployment>>,
         options: Option<RequestOptions>,
     ) -> Result<DeploymentListResponse> {
         let path = build_paginated_path("/deployments", pagination.as_ref());
@@ -136,7 +136,7 @@ impl DeploymentRunsApi {
     /// List runs for the deployment (cursor-style pagination).
     pub async fn list(
         &self,
-        pagination: Option<Pagination>,
+        pagination: Option<Pagination<DeploymentRun>>,
         options: Option<RequestOptions>,
     ) -> Result<DeploymentRunListResponse> {
         let base = format!("/deployments/{}/runs", self.deployment_id);
@@ -43,7 +43,7 @@ impl EnvironmentsApi {
     /// List environments (cursor-style pagination).
     pub async fn list(
         &self,
-        pagination: Option<Pagination>,
+        pagination: Option<Pagination<Environment>>,
         options: Option<RequestOptions>,
     ) -> Result<EnvironmentListResponse> {
         let path = build_paginated_path("/environments", pagination.as_ref());
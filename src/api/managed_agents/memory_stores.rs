@@ -46,7 +46,7 @@ impl MemoryStoresApi {
     /// List memory stores (cursor-style pagination).
     pub async fn list(
         &self,
-        pagination: Option<Pagination>,
+        pagination: Option<Pagination<MemoryStore>>,
         options: Option<RequestOptions>,
     ) -> Result<MemoryStoreListResponse> {
         let path = build_paginated_path("/memory_stores", pagination.as_ref());
@@ -152,7 +152,7 @@ impl MemoriesApi {
     /// List memory entries (cursor-style pagination).
     pub async fn list(
         &self,
-        pagination: Option<Pagination>,
+        pagination: Option<Pagination<Memory>>,
         options: Option<RequestOptions>,
     ) -> Result<MemoryListResponse> {
         let base = format!("/memory_stores/{}/memories", self.store_id);
@@ -240,7 +240,7 @@ impl MemoriesApi {
     pub async fn list_versions(
         &self,
         memory_id: &str,
-        pagination: Option<Pagination>,
+        pagination: Option<Pagination<MemoryVersion>>,
         options: Option<RequestOptions>,
     ) -> Result<MemoryVersionListResponse> {
         let base = format!(
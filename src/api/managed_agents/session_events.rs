@@ -27,7 +27,7 @@ impl SessionEventsApi {
     /// List events for the session (cursor-style pagination).
     pub async fn list(
         &self,
-        pagination: Option<Pagination>,
+        pagination: Option<Pagination<SessionEvent>>,
         options: Option<RequestOptions>,
     ) -> Result<SessionEventListResponse> {
         let base = format!("/sessions/{}/events", self.session_id);
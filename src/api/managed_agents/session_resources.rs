@@ -47,7 +47,7 @@ impl SessionResourcesApi {
     /// List resources attached to the session (cursor-style pagination).
     pub async fn list(
         &self,
-        pagination: Option<Pagination>,
+        pagination: Option<Pagination<SessionResource>>,
         options: Option<RequestOptions>,
     ) -> Result<SessionResourceListResponse> {
         let base = format!("/sessions/{}/resources", self.session_id);
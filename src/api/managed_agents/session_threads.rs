@@ -6,7 +6,7 @@ use crate::{
     client::Client,
     error::Result,
     models::managed_agents::session::{SessionThread, SessionThreadListResponse},
-    models::managed_agents::session_event::SessionEventListResponse,
+    models::managed_agents::session_event::{SessionEvent, SessionEventListResponse},
     streaming::session_event_stream::SessionEventStream,
     types::{HttpMethod, Pagination, RequestOptions},
 };
@@ -28,7 +28,7 @@ impl SessionThreadsApi {
     /// List threads for the session (cursor-style pagination).
     pub async fn list(
         &self,
-        pagination: Option<Pagination>,
+        pagination: Option<Pagination<SessionThread>>,
         options: Option<RequestOptions>,
     ) -> Result<SessionThreadListResponse> {
         let base = format!("/sessions/{}/threads", self.session_id);
@@ -84,7 +84,7 @@ impl SessionThreadsApi {
     pub async fn list_events(
         &self,
         thread_id: &str,
-        pagination: Option<Pagination>,
+        pagination: Option<Pagination<SessionEvent>>,
         options: Option<RequestOptions>,
     ) -> Result<SessionEventListResponse> {
         let base = format!("/sessions/{}/threads/{}/events", self.session_id, thread_id);
@@ -44,7 +44,7 @@ impl VaultsApi {
     /// List vaults (cursor-style pagination).
     pub async fn list(
         &self,
-        pagination: Option<Pagination>,
+        pagination: Option<Pagination<Vault>>,
         options: Option<RequestOptions>,
     ) -> Result<VaultListResponse> {
         let path = build_paginated_path("/vaults", pagination.as_ref());
@@ -146,7 +146,7 @@ impl CredentialsApi {
     /// List credentials in the vault (cursor-style pagination).
     pub async fn list(
         &self,
-        pagination: Option<Pagination>,
+        pagination: Option<Pagination<Credential>>,
         options: Option<RequestOptions>,
     ) -> Result<CredentialListResponse> {
         let base = format!("/vaults/{}/credentials", self.vault_id);
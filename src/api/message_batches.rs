@@ -3,13 +3,34 @@
 use crate::{
     api::utils::{build_paginated_path, create_default_pagination},
     client::Client,
-    error::Result,
+    error::{AnthropicError, ResponseClass, Result},
+    metrics::{BufferGauge, BufferKind},
     models::batch::{
-        MessageBatch, MessageBatchCreateRequest, MessageBatchListResponse, MessageBatchResultEntry,
-        MessageBatchStatus,
+        BatchRequestItem, BatchResults, BatchRetry, BatchRetryPolicy, MessageBatch,
+        MessageBatchCreateRequest, MessageBatchListResponse, MessageBatchResultEntry,
+        MessageBatchStatus, PollOptions,
     },
     types::{HttpMethod, Pagination, RequestOptions},
+    utils::{
+        http::{read_download, read_error_text, AcceptedResponse, MaybeAccepted},
+        instrumentation,
+    },
+};
+use futures::{
+    stream::{self, BoxStream},
+    Stream, StreamExt, TryStreamExt,
 };
+use std::{path::Path, time::Duration};
+use tokio::io::AsyncWriteExt;
+
+/// Wait before re-fetching a batch accepted with `202` and no `retry-after`
+const ACCEPTED_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Fetches of an accepted batch before giving up on it appearing
+const ACCEPTED_MAX_FETCHES: u32 = 5;
+
+/// Batch creations [`MessageBatchesApi::create_all`] has in flight at once
+const MAX_CONCURRENT_CREATES: usize = 4;
 
 /// API client for Message Batches endpoints
 #[derive(Clone)]
@@ -25,6 +46,9 @@ impl MessageBatchesApi {
 
     /// Create a message batch
     ///
+    /// If the API answers `202 Accepted` without the batch in the body, the
+    /// batch is re-fetched by the id in the body or `Location` header.
+    ///
     /// # Example
     /// ```rust,no_run
     /// use threatflux_anthropic_sdk::{Client, Config, models::batch::MessageBatchCreateRequest};
@@ -39,14 +63,114 @@ impl MessageBatchesApi {
     /// # Ok(())
     /// # }
     /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "anthropic.batches.create",
+            skip_all,
+            fields(requests = request.requests.len(), batch_id = tracing::field::Empty)
+        )
+    )]
     pub async fn create(
         &self,
-        request: MessageBatchCreateRequest,
+        mut request: MessageBatchCreateRequest,
         options: Option<RequestOptions>,
     ) -> Result<MessageBatch> {
+        if let Some(profile) = &self.client.config().deterministic {
+            for item in &mut request.requests {
+                profile.apply(&mut item.params);
+            }
+        }
         let body = serde_json::to_value(request)?;
-        self.client
-            .request(HttpMethod::Post, "/messages/batches", Some(body), options)
+        let accepted = match self
+            .client
+            .request_accepting::<MessageBatch>(
+                HttpMethod::Post,
+                "/messages/batches",
+                Some(body),
+                options.clone(),
+            )
+            .await?
+        {
+            MaybeAccepted::Ready(batch) => {
+                instrumentation::record("batch_id", batch.id.as_str());
+                return Ok(batch);
+            }
+            MaybeAccepted::Accepted(accepted) => accepted,
+        };
+
+        // The batch was accepted but not returned yet: re-fetch it by id,
+        // giving it a moment to become visible if it is not there at once.
+        let batch_id = accepted_batch_id(&accepted).ok_or_else(|| {
+            AnthropicError::api_error(
+                202,
+                "Batch accepted, but the response has neither a batch id nor a Location header"
+                    .to_string(),
+                None,
+            )
+        })?;
+        instrumentation::record("batch_id", batch_id.as_str());
+        let delay = accepted.retry_after.unwrap_or(ACCEPTED_POLL_INTERVAL);
+        let mut attempt = 1;
+        loop {
+            tokio::time::sleep(delay).await;
+            match self.retrieve(&batch_id, options.clone()).await {
+                Err(err)
+                    if attempt < ACCEPTED_MAX_FETCHES
+                        && (err.status_code() == Some(404) || err.is_retryable()) =>
+                {
+                    attempt += 1;
+                }
+                result => {
+                    return result.map_err(|err| {
+                        err.with_context(format!("Fetching accepted batch {}", batch_id))
+                    })
+                }
+            }
+        }
+    }
+
+    /// Create several batches concurrently, e.g. the chunks from
+    /// [`BatchBuilder::build_chunked`](crate::builders::BatchBuilder::build_chunked).
+    ///
+    /// Returns one result per request, in order. A failure does not stop the
+    /// others, so check each: the batches that were created are running.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use threatflux_anthropic_sdk::{builders::BatchBuilder, config::limits, Client};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = Client::from_env()?;
+    /// let mut builder = BatchBuilder::new();
+    /// for i in 0..250_000 {
+    ///     builder = builder.add_simple_request(format!("q{}", i), "claude-haiku-4-5", "Hi", 64);
+    /// }
+    /// let chunks = builder.build_chunked(limits::MAX_BATCH_REQUESTS, limits::MAX_BATCH_BYTES)?;
+    ///
+    /// for batch in client.message_batches().create_all(chunks, None).await {
+    ///     println!("Created batch: {}", batch?.id);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create_all(
+        &self,
+        requests: Vec<MessageBatchCreateRequest>,
+        options: Option<RequestOptions>,
+    ) -> Vec<Result<MessageBatch>> {
+        stream::iter(requests)
+            .enumerate()
+            .map(|(index, request)| {
+                let options = options.clone();
+                async move {
+                    self.create(request, options)
+                        .await
+                        .map_err(|err| err.with_context(format!("Batch chunk {}", index)))
+                }
+            })
+            .buffered(MAX_CONCURRENT_CREATES)
+            .collect()
             .await
     }
 
@@ -64,6 +188,14 @@ impl MessageBatchesApi {
     /// # Ok(())
     /// # }
     /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "anthropic.batches.retrieve",
+            skip_all,
+            fields(batch_id = %batch_id)
+        )
+    )]
     pub async fn retrieve(
         &self,
         batch_id: &str,
@@ -92,9 +224,13 @@ impl MessageBatchesApi {
     /// # Ok(())
     /// # }
     /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "anthropic.batches.list", skip_all)
+    )]
     pub async fn list(
         &self,
-        pagination: Option<Pagination>,
+        pagination: Option<Pagination<MessageBatch>>,
         options: Option<RequestOptions>,
     ) -> Result<MessageBatchListResponse> {
         let path = build_paginated_path("/messages/batches", pagination.as_ref());
@@ -118,6 +254,14 @@ impl MessageBatchesApi {
     /// # Ok(())
     /// # }
     /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "anthropic.batches.cancel",
+            skip_all,
+            fields(batch_id = %batch_id)
+        )
+    )]
     pub async fn cancel(
         &self,
         batch_id: &str,
@@ -143,6 +287,14 @@ impl MessageBatchesApi {
     /// # Ok(())
     /// # }
     /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "anthropic.batches.delete",
+            skip_all,
+            fields(batch_id = %batch_id)
+        )
+    )]
     pub async fn delete(&self, batch_id: &str, options: Option<RequestOptions>) -> Result<()> {
         let path = format!("/messages/batches/{}", batch_id);
         let _: serde_json::Value = self
@@ -155,6 +307,14 @@ impl MessageBatchesApi {
     /// Retrieve raw batch results (JSONL) for a completed batch.
     ///
     /// This hits `/messages/batches/{batch_id}/results` and returns the raw bytes.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "anthropic.batches.results",
+            skip_all,
+            fields(batch_id = %batch_id, bytes = tracing::field::Empty)
+        )
+    )]
     pub async fn results_raw(
         &self,
         batch_id: &str,
@@ -168,7 +328,9 @@ impl MessageBatchesApi {
         let status = response.status();
 
         if !status.is_success() {
-            let error_text = response.text().await.unwrap_or_default();
+            let error_text = read_error_text(response, &self.client.config().response_limits)
+                .await
+                .unwrap_or_default();
             return Err(crate::error::AnthropicError::api_error(
                 status.as_u16(),
                 error_text,
@@ -176,8 +338,80 @@ impl MessageBatchesApi {
             ));
         }
 
-        let bytes = response.bytes().await?;
-        Ok(bytes.to_vec())
+        let limits = &self.client.config().response_limits;
+        let bytes = read_download(response, limits, BufferKind::BatchResults).await?;
+        instrumentation::record("bytes", bytes.len());
+        Ok(bytes)
+    }
+
+    /// Download batch results (JSONL) to `path` without holding them in
+    /// memory; returns the bytes written.
+    ///
+    /// The file is written as `<file name>.partial` next to `path` and only
+    /// renamed into place once complete.
+    pub async fn results_to_file(
+        &self,
+        batch_id: &str,
+        path: impl AsRef<Path>,
+        options: Option<RequestOptions>,
+    ) -> Result<u64> {
+        let path = path.as_ref();
+        // Named after the whole file name, so `results.jsonl` and
+        // `results.json` don't share a temporary file
+        let partial = match path.file_name() {
+            Some(name) => {
+                let mut partial = name.to_os_string();
+                partial.push(".partial");
+                path.with_file_name(partial)
+            }
+            None => {
+                return Err(AnthropicError::invalid_input(format!(
+                    "Not a file path: {}",
+                    path.display()
+                )))
+            }
+        };
+        let route = format!("/messages/batches/{}/results", batch_id);
+        let mut response = self
+            .client
+            .request_stream(HttpMethod::Get, &route, None, options)
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = read_error_text(response, &self.client.config().response_limits)
+                .await
+                .unwrap_or_default();
+            return Err(AnthropicError::api_error(status.as_u16(), error_text, None));
+        }
+
+        let file_error = |action: &str, target: &Path, e: std::io::Error| {
+            AnthropicError::file_error(format!("Failed to {} {}: {}", action, target.display(), e))
+        };
+        let mut file = tokio::fs::File::create(&partial)
+            .await
+            .map_err(|e| file_error("create", &partial, e))?;
+        let mut written = 0u64;
+        let result = async {
+            while let Some(chunk) = response.chunk().await? {
+                file.write_all(&chunk)
+                    .await
+                    .map_err(|e| file_error("write", &partial, e))?;
+                written += chunk.len() as u64;
+            }
+            file.flush()
+                .await
+                .map_err(|e| file_error("write", &partial, e))?;
+            tokio::fs::rename(&partial, path)
+                .await
+                .map_err(|e| file_error("rename", &partial, e))
+        }
+        .await;
+        if let Err(error) = result {
+            let _ = tokio::fs::remove_file(&partial).await;
+            return Err(error);
+        }
+        instrumentation::record("bytes", written);
+        Ok(written)
     }
 
     /// Retrieve batch results as UTF-8 text (JSONL).
@@ -197,66 +431,161 @@ impl MessageBatchesApi {
 
     /// Retrieve and parse batch results into structured entries.
     ///
-    /// The endpoint returns one JSON object per line.
+    /// The endpoint returns one JSON object per line. This holds every entry
+    /// in memory; see [`results_stream`](Self::results_stream) for large
+    /// batches.
     pub async fn results(
         &self,
         batch_id: &str,
         options: Option<RequestOptions>,
     ) -> Result<Vec<MessageBatchResultEntry>> {
-        let text = self.results_text(batch_id, options).await?;
-        let mut parsed = Vec::new();
+        self.results_stream(batch_id, options)
+            .await?
+            .try_collect()
+            .await
+    }
 
-        for (idx, line) in text.lines().enumerate() {
-            let trimmed = line.trim();
-            if trimmed.is_empty() {
-                continue;
-            }
+    /// Retrieve batch results paired with the `requests` the batch was
+    /// created from, so entries can be looked up and failures resubmitted by
+    /// custom ID.
+    pub async fn results_for(
+        &self,
+        batch_id: &str,
+        requests: impl IntoIterator<Item = BatchRequestItem>,
+        options: Option<RequestOptions>,
+    ) -> Result<BatchResults> {
+        let entries = self.results(batch_id, options).await?;
+        Ok(BatchResults::new(requests, entries))
+    }
 
-            let item: MessageBatchResultEntry = serde_json::from_str(trimmed).map_err(|e| {
-                crate::error::AnthropicError::json(format!(
-                    "Failed to parse batch result line {}: {}",
-                    idx + 1,
-                    e
-                ))
-            })?;
-            parsed.push(item);
+    /// Stream batch results, downloading the JSONL incrementally and
+    /// yielding each entry as soon as its line has arrived.
+    ///
+    /// A line that fails to parse is reported as an error item and the
+    /// stream carries on with the next line.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use futures::StreamExt;
+    /// use threatflux_anthropic_sdk::Client;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = Client::from_env()?;
+    /// let mut results = client
+    ///     .message_batches()
+    ///     .results_stream("msgbatch_123", None)
+    ///     .await?;
+    /// while let Some(entry) = results.next().await {
+    ///     let entry = entry?;
+    ///     println!("{}: {:?}", entry.custom_id, entry.result);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "anthropic.batches.results_stream",
+            skip_all,
+            fields(batch_id = %batch_id)
+        )
+    )]
+    pub async fn results_stream(
+        &self,
+        batch_id: &str,
+        options: Option<RequestOptions>,
+    ) -> Result<BoxStream<'static, Result<MessageBatchResultEntry>>> {
+        let path = format!("/messages/batches/{}/results", batch_id);
+        let response = self
+            .client
+            .request_stream(HttpMethod::Get, &path, None, options)
+            .await?;
+        let status = response.status();
+
+        if !status.is_success() {
+            let error_text = read_error_text(response, &self.client.config().response_limits)
+                .await
+                .unwrap_or_default();
+            return Err(AnthropicError::api_error(status.as_u16(), error_text, None));
         }
 
-        Ok(parsed)
+        let max_line = self.client.config().response_limits.stream_event;
+        Ok(parse_result_lines(response.bytes_stream(), max_line).boxed())
     }
 
-    /// Wait for a batch to complete processing
+    /// Poll a batch until it ends, backing off between polls as `poll`
+    /// describes and reporting its request counts after each poll.
+    ///
+    /// Fails with [`AnthropicError::Timeout`] once `poll.timeout` has passed,
+    /// or with [`AnthropicError::Cancelled`] when `poll.cancel` fires. Either way the batch keeps
+    /// processing on the server.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    /// use threatflux_anthropic_sdk::{models::batch::PollOptions, Client};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = Client::from_env()?;
+    /// let poll = PollOptions::new()
+    ///     .with_timeout(Duration::from_secs(3600))
+    ///     .on_progress(|counts| println!("{} of {} processed", counts.completed + counts.failed, counts.total));
+    ///
+    /// let batch = client.message_batches().wait_for_completion("batch_123", poll).await?;
+    /// println!("Batch ended: {:?}", batch.processing_status);
+    /// # Ok(())
+    /// # }
+    /// ```
     pub async fn wait_for_completion(
         &self,
         batch_id: &str,
-        poll_interval: std::time::Duration,
-        max_wait: std::time::Duration,
+        poll: PollOptions,
     ) -> Result<MessageBatch> {
-        let start_time = std::time::Instant::now();
+        let deadline = poll
+            .timeout
+            .map(|timeout| tokio::time::Instant::now() + timeout);
+        let mut interval = poll.initial_interval;
 
         loop {
             let batch = self.retrieve(batch_id, None).await?;
+            poll.report(&batch.request_counts);
+            if batch.is_complete() {
+                return Ok(batch);
+            }
 
-            match batch.processing_status {
-                MessageBatchStatus::Completed
-                | MessageBatchStatus::Failed
-                | MessageBatchStatus::Cancelled => {
-                    return Ok(batch);
+            let mut wake = tokio::time::Instant::now() + interval;
+            if let Some(deadline) = deadline {
+                if deadline <= tokio::time::Instant::now() {
+                    return Err(AnthropicError::Timeout(poll.timeout.unwrap_or_default())
+                        .with_context(format!("Waiting for batch {}", batch_id)));
                 }
-                _ => {
-                    if start_time.elapsed() >= max_wait {
-                        return Err(crate::error::AnthropicError::invalid_input(format!(
-                            "Batch {} did not complete within timeout",
-                            batch_id
-                        )));
-                    }
-
-                    tokio::time::sleep(poll_interval).await;
+                wake = wake.min(deadline);
+            }
+            let cancelled = async {
+                match &poll.cancel {
+                    Some(token) => token.cancelled().await,
+                    None => std::future::pending().await,
                 }
+            };
+            tokio::select! {
+                _ = tokio::time::sleep_until(wake) => {}
+                _ = cancelled => return Err(AnthropicError::Cancelled),
             }
+            interval = poll.next_interval(interval);
         }
     }
 
+    /// [`wait_for_completion`](Self::wait_for_completion), then fetch the
+    /// batch's results
+    pub async fn wait_for_results(
+        &self,
+        batch_id: &str,
+        poll: PollOptions,
+    ) -> Result<Vec<MessageBatchResultEntry>> {
+        self.wait_for_completion(batch_id, poll).await?;
+        self.results(batch_id, None).await
+    }
+
     /// List batches by status
     pub async fn list_by_status(
         &self,
@@ -274,3 +603,181 @@ impl MessageBatchesApi {
             .collect())
     }
 }
+
+impl BatchResults {
+    /// Resubmit the entries `policy` selects as a new batch.
+    ///
+    /// Returns `None` without calling the API when nothing qualifies.
+    ///
+    /// ```rust,no_run
+    /// use threatflux_anthropic_sdk::{
+    ///     builders::BatchBuilder,
+    ///     models::batch::{BatchResults, BatchRetryPolicy},
+    ///     Client,
+    /// };
+    ///
+    /// # async fn example() -> threatflux_anthropic_sdk::Result<()> {
+    /// let client = Client::from_env()?;
+    /// let batches = client.message_batches();
+    /// let request = BatchBuilder::new()
+    ///     .add_simple_request("q1", "claude-haiku-4-5", "Hello", 256)
+    ///     .build();
+    ///
+    /// let batch = batches.create(request.clone(), None).await?;
+    /// // ... wait for it to end ...
+    /// let results = BatchResults::new(request.requests, batches.results(&batch.id, None).await?);
+    /// if let Some(retry) = results.retry_failed(&batches, &BatchRetryPolicy::new()).await? {
+    ///     println!("retrying {} entries in {}", retry.id_map.len(), retry.batch.id);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn retry_failed(
+        &self,
+        api: &MessageBatchesApi,
+        policy: &BatchRetryPolicy,
+    ) -> Result<Option<BatchRetry>> {
+        let Some((request, id_map)) = self.retry_plan(policy) else {
+            return Ok(None);
+        };
+        let batch = api.create(request, None).await?;
+        Ok(Some(BatchRetry { batch, id_map }))
+    }
+}
+
+/// Id of the batch a `202 Accepted` response refers to: the body's `id`, or
+/// the path segment after `batches/` in the `Location` header
+fn accepted_batch_id(accepted: &AcceptedResponse) -> Option<String> {
+    if let Some(id) = accepted
+        .body
+        .as_ref()
+        .and_then(|body| body.get("id"))
+        .and_then(|id| id.as_str())
+    {
+        return Some(id.to_string());
+    }
+    let location = accepted.location.as_deref()?;
+    let path = location.split(['?', '#']).next().unwrap_or(location);
+    let (_, rest) = path.rsplit_once("batches/")?;
+    let id = rest.split('/').next().unwrap_or(rest);
+    (!id.is_empty()).then(|| id.to_string())
+}
+
+/// Reader state for [`parse_result_lines`]
+struct LineReader<S> {
+    chunks: S,
+    buffer: Vec<u8>,
+    gauge: BufferGauge,
+    line: usize,
+    done: bool,
+    max_line: u64,
+}
+
+/// Entries parsed from a JSONL body arriving in arbitrary chunks, giving up
+/// on a line longer than `max_line` bytes
+fn parse_result_lines<S, B>(
+    chunks: S,
+    max_line: u64,
+) -> impl Stream<Item = Result<MessageBatchResultEntry>>
+where
+    S: Stream<Item = std::result::Result<B, reqwest::Error>> + Unpin,
+    B: AsRef<[u8]>,
+{
+    let reader = LineReader {
+        chunks,
+        buffer: Vec::new(),
+        gauge: BufferGauge::new(BufferKind::BatchResults),
+        line: 0,
+        done: false,
+        max_line,
+    };
+    stream::unfold(reader, |mut reader| async move {
+        loop {
+            reader.gauge.set(reader.buffer.len());
+            let newline = reader.buffer.iter().position(|&b| b == b'\n');
+            let raw: Vec<u8> = match newline {
+                Some(at) => reader.buffer.drain(..=at).collect(),
+                None if reader.buffer.len() as u64 > reader.max_line => {
+                    reader.done = true;
+                    reader.buffer.clear();
+                    let err = AnthropicError::ResponseTooLarge {
+                        class: ResponseClass::StreamEvent,
+                        limit: reader.max_line,
+                    };
+                    return Some((Err(err), reader));
+                }
+                None if !reader.done => {
+                    match reader.chunks.next().await {
+                        Some(Ok(chunk)) => reader.buffer.extend_from_slice(chunk.as_ref()),
+                        Some(Err(e)) => {
+                            reader.done = true;
+                            reader.buffer.clear();
+                            return Some((Err(e.into()), reader));
+                        }
+                        None => reader.done = true,
+                    }
+                    continue;
+                }
+                None if reader.buffer.is_empty() => return None,
+                None => std::mem::take(&mut reader.buffer),
+            };
+            reader.line += 1;
+
+            let line = match std::str::from_utf8(&raw) {
+                Ok(line) => line.trim(),
+                Err(e) => {
+                    let err = AnthropicError::invalid_input(format!(
+                        "Batch result line {} is not valid UTF-8: {}",
+                        reader.line, e
+                    ));
+                    return Some((Err(err), reader));
+                }
+            };
+            if line.is_empty() {
+                continue;
+            }
+            let item = serde_json::from_str(line).map_err(|e| {
+                AnthropicError::json(format!(
+                    "Failed to parse batch result line {}: {}",
+                    reader.line, e
+                ))
+            });
+            return Some((item, reader));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_parse_result_lines_across_chunks() {
+        let line = r#"{"custom_id":"a","result":{"type":"expired"}}"#;
+        let body = format!("{line}\n{line}");
+        let (head, tail) = body.as_bytes().split_at(10);
+        let chunks = stream::iter([
+            Ok::<_, reqwest::Error>(head.to_vec()),
+            Ok(tail[..line.len()].to_vec()),
+            Ok(tail[line.len()..].to_vec()),
+        ]);
+
+        let entries: Vec<_> = parse_result_lines(chunks, 1 << 20)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|entry| entry.custom_id == "a"));
+    }
+
+    #[tokio::test]
+    async fn test_parse_result_lines_rejects_overlong_line() {
+        let chunks = stream::iter([Ok::<_, reqwest::Error>(vec![b'x'; 64])]);
+        let entries: Vec<_> = parse_result_lines(chunks, 32).collect().await;
+        assert_eq!(entries.len(), 1);
+        assert!(matches!(
+            entries[0],
+            Err(AnthropicError::ResponseTooLarge { limit: 32, .. })
+        ));
+    }
+}
@@ -1,12 +1,49 @@
 //! Messages API implementation
 
 use crate::{
-    client::Client,
-    error::Result,
-    models::message::{MessageRequest, MessageResponse, TokenCountRequest, TokenCountResponse},
-    streaming::message_stream::MessageStream,
-    types::{HttpMethod, RequestOptions},
+    builders::ValidationUtils,
+    client::{bounded, Client},
+    conversation::UsageSummary,
+    error::{AnthropicError, Result},
+    models::{
+        code::{CodeCheck, CodeRun},
+        common::{ContentBlock, Role, StopReason, Usage},
+        comparison::{Comparison, ComparisonSide},
+        message::Message,
+        message::{MessageRequest, MessageResponse, TokenCountRequest, TokenCountResponse},
+        refusal::{Outcome, RefusalPolicy},
+        structured::StructuredOutput,
+    },
+    priority::PriorityContext,
+    streaming::{
+        message_stream::{MessageStream, StreamOptions},
+        raw::RawEventStream,
+    },
+    tools::{
+        delegate::AgentFrame,
+        registry::{add_usage, RunLimit, RunLimitExceeded, RunToolsOptions, ToolRegistry, ToolRun},
+        trace::{AgentIteration, AgentTrace, ToolCallTrace},
+    },
+    types::{Concurrency, HttpMethod, RequestOptions},
+    user_context::UserContext,
+    utils::{
+        concurrency, instrumentation,
+        rate_limit::{RateLimiter, TokenReservation},
+        shadow::ShadowMode,
+    },
 };
+use futures::{stream, StreamExt};
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use std::{sync::Arc, time::Instant};
+use tokio::sync::OwnedSemaphorePermit;
+
+/// Concurrency permit and token reservation held by an admitted request
+type Admission = (
+    Option<OwnedSemaphorePermit>,
+    Option<(RateLimiter, TokenReservation)>,
+);
 
 /// API client for Messages endpoints
 #[derive(Clone)]
@@ -22,6 +59,15 @@ impl MessagesApi {
 
     /// Create a message
     ///
+    /// Requests over the documented size, image, PDF or message-count limits
+    /// (see [`config::limits`](crate::config::limits)) fail with
+    /// [`AnthropicError::InvalidInput`](crate::error::AnthropicError::InvalidInput)
+    /// before anything is sent.
+    ///
+    /// With [`Config::with_auto_stream_threshold`](crate::Config::with_auto_stream_threshold)
+    /// set, requests with a large `max_tokens` are sent as with
+    /// [`create_auto`](Self::create_auto).
+    ///
     /// # Example
     /// ```rust,no_run
     /// use threatflux_anthropic_sdk::{Client, Config, models::message::MessageRequest};
@@ -38,17 +84,740 @@ impl MessagesApi {
     /// # Ok(())
     /// # }
     /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "anthropic.messages.create",
+            skip_all,
+            fields(model = %request.model, max_tokens = request.max_tokens, message_id = tracing::field::Empty, input_tokens = tracing::field::Empty, output_tokens = tracing::field::Empty, cache_read_input_tokens = tracing::field::Empty, stop_reason = tracing::field::Empty)
+        )
+    )]
     pub async fn create(
         &self,
         request: MessageRequest,
         options: Option<RequestOptions>,
     ) -> Result<MessageResponse> {
-        let body = serde_json::to_value(request)?;
+        let auto_stream = self
+            .client
+            .config()
+            .auto_stream_threshold
+            .is_some_and(|threshold| request.max_tokens >= threshold);
+        let mut response = if auto_stream {
+            self.create_auto(request, options.clone()).await?
+        } else {
+            self.create_unary(request, options.clone()).await?
+        };
         self.client
+            .config()
+            .postprocessors
+            .apply(&mut response, &options)?;
+        instrumentation::record_message(&response);
+        Ok(response)
+    }
+
+    /// Send a message as one plain (non-streaming) request
+    async fn create_unary(
+        &self,
+        mut request: MessageRequest,
+        options: Option<RequestOptions>,
+    ) -> Result<MessageResponse> {
+        Self::attach_user_context(&mut request, &options);
+        self.attach_priority(&mut request, &options);
+        if let Some(profile) = &self.client.config().deterministic {
+            profile.apply(&mut request);
+        }
+        self.client.config().warnings.check_model(&request.model);
+        let body = serde_json::to_value(&request)?;
+        ValidationUtils::validate_body_limits(&body, "Request")?;
+        self.mirror(&request, &options);
+        let (_permit, reservation) = self.admit(&request, &options).await?;
+        let response: Result<MessageResponse> = self
+            .client
             .request(HttpMethod::Post, "/messages", Some(body), options)
+            .await;
+        if let Some((limiter, reservation)) = reservation {
+            match &response {
+                Ok(response) => limiter.reconcile(reservation, &response.usage),
+                Err(_) => limiter.release(reservation),
+            }
+        }
+        let mut response = response?;
+        if let Some(metrics) = &self.client.config().metrics {
+            metrics.tokens(&response.model, &response.usage);
+        }
+        if self.client.config().provenance {
+            response.stamp(&request)?;
+        }
+        Ok(response)
+    }
+
+    /// Fill in `metadata.user_id` from the request's end-user context unless
+    /// the caller set one
+    fn attach_user_context(request: &mut MessageRequest, options: &Option<RequestOptions>) {
+        let Some(user_id) = UserContext::resolve(options).and_then(|context| context.user_id)
+        else {
+            return;
+        };
+        let metadata = request.metadata.get_or_insert_with(Default::default);
+        if metadata.user_id.is_none() {
+            metadata.user_id = Some(user_id);
+        }
+    }
+
+    /// Pick a `service_tier` from the request's priority unless the caller
+    /// set one, when [`Config::priority_service_tiers`](crate::config::Config::priority_service_tiers)
+    /// is enabled
+    fn attach_priority(&self, request: &mut MessageRequest, options: &Option<RequestOptions>) {
+        if self.client.config().priority_service_tiers && request.service_tier.is_none() {
+            request.service_tier = PriorityContext::resolve(options)
+                .service_tier()
+                .map(String::from);
+        }
+    }
+
+    /// Wait for the client's request rate limit when one is configured, then
+    /// for the configured rate limit pool of `model` and the request's
+    /// workspace header
+    async fn pace(&self, model: &str, options: &Option<RequestOptions>) {
+        let config = self.client.config();
+        if !config.enable_rate_limiting
+            || !config.pace_rate_limit_rps && config.rate_limit_pools.is_none()
+        {
+            return;
+        }
+        let started = Instant::now();
+        // Limiters only ever wait, they never fail
+        if config.pace_rate_limit_rps {
+            let _ = self.client.rate_limiter().acquire().await;
+        }
+        if let Some(pools) = &config.rate_limit_pools {
+            let workspace = pools.workspace_header().and_then(|name| {
+                options.as_ref()?.headers.iter().find_map(|(key, value)| {
+                    key.eq_ignore_ascii_case(&name).then_some(value.as_str())
+                })
+            });
+            let _ = pools.apply_for(model, workspace).await;
+        }
+        if let Some(metrics) = &config.metrics {
+            metrics.rate_limit_wait(started.elapsed());
+        }
+    }
+
+    /// Wait until `request`'s estimated input tokens and its `max_tokens`
+    /// fit under the configured token rate limit, and reserve them
+    async fn reserve_tokens(
+        &self,
+        request: &MessageRequest,
+    ) -> Option<(RateLimiter, TokenReservation)> {
+        let config = self.client.config();
+        let limiter = self.client.rate_limiter();
+        if !config.enable_rate_limiting || !limiter.is_token_aware() {
+            return None;
+        }
+        let started = Instant::now();
+        let reservation = limiter
+            .acquire_tokens(request.estimated_input_tokens(), request.max_tokens)
+            .await;
+        if let Some(metrics) = &config.metrics {
+            metrics.rate_limit_wait(started.elapsed());
+        }
+        Some((limiter.clone(), reservation))
+    }
+
+    /// Wait for a per-model concurrency slot, the request rate limit and a
+    /// token reservation, giving up as soon as the request is cancelled or its
+    /// deadline passes
+    async fn admit(
+        &self,
+        request: &MessageRequest,
+        options: &Option<RequestOptions>,
+    ) -> Result<Admission> {
+        // Boxed to keep the futures of every request awaiting this small
+        Box::pin(bounded(options, async {
+            let permit = self.model_permit(&request.model).await;
+            self.pace(&request.model, options).await;
+            Ok((permit, self.reserve_tokens(request).await))
+        }))
+        .await
+    }
+
+    /// Wait for a slot under the configured per-model concurrency limit
+    async fn model_permit(&self, model: &str) -> Option<OwnedSemaphorePermit> {
+        concurrency::acquire(&self.client.config().model_concurrency, model).await
+    }
+
+    /// Send many independent requests with at most `concurrency` in flight,
+    /// returning each result in the position of its request.
+    ///
+    /// One failure does not stop the rest. Each request goes through
+    /// [`create`](Self::create), so the client's shared rate limits and token
+    /// reservations, and any per-model concurrency limits, apply as usual and
+    /// are shared with every other request on the client. For large jobs
+    /// that can wait, the Batch API is cheaper.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use threatflux_anthropic_sdk::{models::message::MessageRequest, Client, Concurrency};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = Client::from_env()?;
+    /// let requests = ["red", "green", "blue"].map(|colour| {
+    ///     MessageRequest::new()
+    ///         .max_tokens(100)
+    ///         .add_user_message(format!("Name a fruit that is {}.", colour))
+    /// });
+    ///
+    /// let results = client
+    ///     .messages()
+    ///     .create_many(requests, Concurrency(2), None)
+    ///     .await;
+    /// for result in results {
+    ///     println!("{}", result?.text());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create_many(
+        &self,
+        requests: impl IntoIterator<Item = MessageRequest>,
+        concurrency: Concurrency,
+        options: Option<RequestOptions>,
+    ) -> Vec<Result<MessageResponse>> {
+        stream::iter(requests)
+            .map(|request| self.create(request, options.clone()))
+            .buffered(concurrency.get())
+            .collect()
+            .await
+    }
+
+    /// Run the same request against two models concurrently and compare
+    /// latency, token usage, estimated cost and response text.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use threatflux_anthropic_sdk::{Client, models::message::MessageRequest};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = Client::from_env()?;
+    /// let request = MessageRequest::new()
+    ///     .max_tokens(500)
+    ///     .add_user_message("Summarize the CAP theorem.");
+    ///
+    /// let comparison = client
+    ///     .messages()
+    ///     .compare(request, "claude-sonnet-4-6", "claude-opus-4-8", None)
+    ///     .await?;
+    /// println!("{}", comparison.summary());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn compare(
+        &self,
+        request: MessageRequest,
+        model_a: impl Into<String>,
+        model_b: impl Into<String>,
+        options: Option<RequestOptions>,
+    ) -> Result<Comparison> {
+        let model_a = model_a.into();
+        let model_b = model_b.into();
+        let request_a = request.clone().model(model_a.clone());
+        let request_b = request.model(model_b.clone());
+
+        let timed = |request: MessageRequest, options: Option<RequestOptions>| async move {
+            let started = std::time::Instant::now();
+            let response = self.create(request, options).await?;
+            Ok::<_, crate::error::AnthropicError>((response, started.elapsed()))
+        };
+
+        let (a, b) = tokio::join!(timed(request_a, options.clone()), timed(request_b, options));
+        let (response_a, latency_a) =
+            a.map_err(|e| e.with_context(format!("model {}", model_a)))?;
+        let (response_b, latency_b) =
+            b.map_err(|e| e.with_context(format!("model {}", model_b)))?;
+
+        Ok(Comparison::new(
+            ComparisonSide::new(model_a, response_a, latency_a),
+            ComparisonSide::new(model_b, response_b, latency_b),
+        ))
+    }
+
+    /// Create a message and classify the response as answered or refused,
+    /// trying `policy`'s fallbacks in order after a refusal.
+    ///
+    /// Returns [`Outcome::Refused`] with the last declining response when
+    /// every fallback is refused too (or skipped).
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use threatflux_anthropic_sdk::{
+    ///     models::{message::MessageRequest, refusal::{Outcome, RefusalPolicy}},
+    ///     Client,
+    /// };
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = Client::from_env()?;
+    /// let request = MessageRequest::new()
+    ///     .model("claude-haiku-4-5")
+    ///     .max_tokens(1000)
+    ///     .add_user_message("Explain how lock picking works");
+    /// let policy = RefusalPolicy::new()
+    ///     .with_recommended_model()
+    ///     .with_fallback_model("claude-sonnet-4-6");
+    ///
+    /// match client.messages().create_outcome(request, &policy, None).await? {
+    ///     Outcome::Completed(response) => println!("{}", response.text()),
+    ///     Outcome::Refused { reason_text, .. } => println!("declined: {:?}", reason_text),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create_outcome(
+        &self,
+        request: MessageRequest,
+        policy: &RefusalPolicy,
+        options: Option<RequestOptions>,
+    ) -> Result<Outcome> {
+        let mut outcome = self
+            .create(request.clone(), options.clone())
+            .await?
+            .outcome();
+
+        for fallback in &policy.fallbacks {
+            let Outcome::Refused { response, .. } = &outcome else {
+                break;
+            };
+            let Some(refusal) = response.refusal() else {
+                break;
+            };
+            let Some(next) = fallback.next_request(&request, &refusal) else {
+                continue;
+            };
+            tracing::debug!(
+                "Request refused by {}; trying fallback {:?}",
+                response.model,
+                fallback
+            );
+            outcome = self.create(next, options.clone()).await?.outcome();
+        }
+        Ok(outcome)
+    }
+
+    /// Create a message whose reply is parsed into `T`, with the schema
+    /// derived from `T` and up to two retries when the reply does not parse.
+    ///
+    /// See [`crate::models::structured`] for how the schema is applied.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use schemars::JsonSchema;
+    /// use serde::Deserialize;
+    /// use threatflux_anthropic_sdk::{models::message::MessageRequest, Client};
+    ///
+    /// #[derive(Deserialize, JsonSchema)]
+    /// struct Sentiment {
+    ///     label: String,
+    ///     confidence: f64,
+    /// }
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = Client::from_env()?;
+    /// let request = MessageRequest::new()
+    ///     .max_tokens(200)
+    ///     .add_user_message("Classify: 'The update broke everything.'");
+    ///
+    /// let sentiment: Sentiment = client.messages().create_structured(request, None).await?;
+    /// println!("{} ({:.2})", sentiment.label, sentiment.confidence);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "schemars")]
+    pub async fn create_structured<T>(
+        &self,
+        request: MessageRequest,
+        options: Option<RequestOptions>,
+    ) -> Result<T>
+    where
+        T: DeserializeOwned + JsonSchema,
+    {
+        self.create_structured_with(request, &StructuredOutput::default(), options)
             .await
     }
 
+    /// [`create_structured`](Self::create_structured) with explicit
+    /// [`StructuredOutput`] settings
+    #[cfg(feature = "schemars")]
+    pub async fn create_structured_with<T>(
+        &self,
+        request: MessageRequest,
+        structured: &StructuredOutput,
+        options: Option<RequestOptions>,
+    ) -> Result<T>
+    where
+        T: DeserializeOwned + JsonSchema,
+    {
+        let schema = crate::tools::typed::input_schema::<T>();
+        self.create_structured_with_schema(request, schema, structured, options)
+            .await
+    }
+
+    /// Create a message constrained to `schema` and parse the reply into `T`.
+    ///
+    /// A reply that is not valid JSON or does not deserialize is sent back to
+    /// the model with the error, and the request retried, up to
+    /// `structured.max_retries` times before the last error is returned.
+    pub async fn create_structured_with_schema<T: DeserializeOwned>(
+        &self,
+        request: MessageRequest,
+        schema: serde_json::Value,
+        structured: &StructuredOutput,
+        options: Option<RequestOptions>,
+    ) -> Result<T> {
+        let mut request = structured.apply(request, schema);
+        let mut attempt = 0;
+        loop {
+            let response = self.create(request.clone(), options.clone()).await?;
+            let err = match structured.parse(&response) {
+                Ok(value) => return Ok(value),
+                Err(err) => err,
+            };
+            if attempt >= structured.max_retries {
+                return Err(err.with_context(format!(
+                    "Structured output failed after {} attempts",
+                    attempt + 1
+                )));
+            }
+            attempt += 1;
+            tracing::debug!("Structured output did not parse ({}); retrying", err);
+            request
+                .messages
+                .extend(structured.correction(&response, &err));
+        }
+    }
+
+    /// Create a message that should contain code, and check that code with
+    /// `check`'s command.
+    ///
+    /// A response without a code block in `check.language`, or whose code
+    /// fails the command, is sent back to the model with the command's output
+    /// and a request for the complete fixed code, up to `check.max_fixes`
+    /// times. The run is returned either way; check [`CodeRun::passed`].
+    /// See [`crate::models::code`].
+    pub async fn create_code(
+        &self,
+        mut request: MessageRequest,
+        check: &CodeCheck,
+        options: Option<RequestOptions>,
+    ) -> Result<CodeRun> {
+        let mut usage = Usage::default();
+        let mut attempts = 0;
+        loop {
+            let response = self.create(request.clone(), options.clone()).await?;
+            attempts += 1;
+            add_usage(&mut usage, &response.usage);
+
+            let code = check.code(&response);
+            let problem = match &code {
+                Some(code) => check.check(code).await?.map(|output| {
+                    format!(
+                        "Running `{}` on the code failed:\n\n{}",
+                        check.command_line(),
+                        output
+                    )
+                }),
+                None => Some(format!(
+                    "The response did not contain a ```{} code block.",
+                    check.language
+                )),
+            };
+            let Some(problem) = problem else {
+                return Ok(CodeRun {
+                    code: code.unwrap_or_default(),
+                    passed: true,
+                    diagnostics: None,
+                    response,
+                    attempts,
+                    usage,
+                });
+            };
+            if attempts > check.max_fixes {
+                return Ok(CodeRun {
+                    code: code.unwrap_or_default(),
+                    passed: false,
+                    diagnostics: Some(problem),
+                    response,
+                    attempts,
+                    usage,
+                });
+            }
+            tracing::debug!(
+                "Code check failed on attempt {}; asking for a fix",
+                attempts
+            );
+            request
+                .messages
+                .extend(check.correction(&response, &problem));
+        }
+    }
+
+    /// Run the tool-use loop: send `request` with `tools`' definitions added,
+    /// execute each round of `tool_use` blocks through the registry, append
+    /// the results and resend, until the model stops asking for tools or
+    /// `options.max_iterations` requests have been sent.
+    ///
+    /// Check [`ToolRun::hit_iteration_limit`] to tell the two apart. See
+    /// [`crate::tools::registry`] for an example.
+    ///
+    /// With `options.approval` set, gated calls wait for approval first and
+    /// denied ones are answered with an error result instead of running.
+    ///
+    /// With `options.limits` set, a run that passes one of them fails with
+    /// [`AnthropicError::RunLimitExceeded`] carrying its partial progress. A
+    /// round of tool calls that would pass the tool call limit is not run.
+    pub async fn run_tools(
+        &self,
+        request: MessageRequest,
+        tools: &ToolRegistry,
+        options: RunToolsOptions,
+    ) -> Result<ToolRun> {
+        self.run_tools_traced(request, tools, options).await.1
+    }
+
+    /// [`run_tools`](Self::run_tools), also returning an [`AgentTrace`] of
+    /// every iteration. The trace is returned even when the run fails, with
+    /// the error recorded; see [`crate::tools::trace`].
+    pub async fn run_tools_traced(
+        &self,
+        mut request: MessageRequest,
+        tools: &ToolRegistry,
+        options: RunToolsOptions,
+    ) -> (AgentTrace, Result<ToolRun>) {
+        let definitions = request.tools.get_or_insert_with(Vec::new);
+        for tool in tools.definitions() {
+            if !definitions
+                .iter()
+                .any(|existing| existing.name == tool.name)
+            {
+                definitions.push(tool.clone());
+            }
+        }
+
+        let mut trace = AgentTrace::new(request.clone());
+        let frame = AgentFrame::enter(options.budget.as_ref());
+        let result = self
+            .tool_loop(request, tools, &options, frame, &mut trace)
+            .await;
+        if let Err(err) = &result {
+            trace.error = Some(err.to_string());
+        }
+        trace.finished_at = Some(chrono::Utc::now());
+        (trace, result)
+    }
+
+    async fn tool_loop(
+        &self,
+        mut request: MessageRequest,
+        tools: &ToolRegistry,
+        options: &RunToolsOptions,
+        frame: AgentFrame,
+        trace: &mut AgentTrace,
+    ) -> Result<ToolRun> {
+        let limits = &options.limits;
+        let started_run = Instant::now();
+        let deadline = limits
+            .max_wall_time
+            .map(|max| tokio::time::Instant::from_std(started_run + max));
+        let mut usage = Usage::default();
+        let mut spent = UsageSummary::default();
+        let mut last_response = None;
+        let mut tool_calls = 0;
+        let mut iterations = 0;
+        // Only reported once the deadline, and so `max_wall_time`, is set
+        let wall_time = RunLimit::WallTime {
+            limit: limits.max_wall_time.unwrap_or_default(),
+        };
+        let stop = |limit: RunLimit,
+                    response: Option<MessageResponse>,
+                    messages: Vec<Message>,
+                    iterations: usize,
+                    tool_calls: usize,
+                    usage: Usage| {
+            AnthropicError::RunLimitExceeded(Box::new(RunLimitExceeded {
+                limit,
+                response,
+                messages,
+                iterations,
+                tool_calls,
+                usage,
+                elapsed: started_run.elapsed(),
+            }))
+        };
+        loop {
+            if let Some(budget) = &frame.budget {
+                budget.check()?;
+            }
+            if deadline.is_some_and(|deadline| tokio::time::Instant::now() >= deadline) {
+                return Err(stop(
+                    wall_time,
+                    last_response,
+                    request.messages,
+                    iterations,
+                    tool_calls,
+                    usage,
+                ));
+            }
+            let request_hash = request.fingerprint()?;
+            let started_at = chrono::Utc::now();
+            let started = Instant::now();
+            let sent = self.create(request.clone(), options.request_options.clone());
+            let Some(response) = within(deadline, sent).await else {
+                return Err(stop(
+                    wall_time,
+                    last_response,
+                    request.messages,
+                    iterations,
+                    tool_calls,
+                    usage,
+                ));
+            };
+            let response = response?;
+            iterations += 1;
+            trace.iterations.push(AgentIteration {
+                request_hash,
+                started_at,
+                latency_ms: started.elapsed().as_millis() as u64,
+                response: response.clone(),
+                tool_calls: Vec::new(),
+            });
+            add_usage(&mut usage, &response.usage);
+            spent.record(&response.model, &response.usage);
+            if let Some(budget) = &frame.budget {
+                budget.record(&response.model, &response.usage);
+            }
+            request
+                .messages
+                .push(Message::new(Role::Assistant, response.content.clone()));
+
+            let done = response.stop_reason != Some(StopReason::ToolUse)
+                || iterations >= options.max_iterations;
+            if let Some(limit) = limits.check_usage(&spent, !done) {
+                return Err(stop(
+                    limit,
+                    Some(response),
+                    request.messages,
+                    iterations,
+                    tool_calls,
+                    usage,
+                ));
+            }
+            if done {
+                return Ok(ToolRun {
+                    response,
+                    messages: request.messages,
+                    iterations,
+                    tool_calls,
+                    usage,
+                });
+            }
+
+            let round = response
+                .content
+                .iter()
+                .filter(|block| matches!(block, ContentBlock::ToolUse { .. }))
+                .count();
+            if let Some(limit) = limits.check_tool_calls(tool_calls, round) {
+                return Err(stop(
+                    limit,
+                    Some(response),
+                    request.messages,
+                    iterations,
+                    tool_calls,
+                    usage,
+                ));
+            }
+            let calls = frame
+                .clone()
+                .scope(tools.run_calls(&response.content, options.approval.as_ref()));
+            let Some(results) = within(deadline, calls).await else {
+                return Err(stop(
+                    wall_time,
+                    Some(response),
+                    request.messages,
+                    iterations,
+                    tool_calls,
+                    usage,
+                ));
+            };
+            let calls = response.content.iter().filter_map(|block| match block {
+                ContentBlock::ToolUse { id, name, input } => Some((id, name, input)),
+                _ => None,
+            });
+            if let Some(iteration) = trace.iterations.last_mut() {
+                iteration.tool_calls = calls
+                    .zip(&results)
+                    .map(|((id, name, input), (result, duration))| ToolCallTrace {
+                        tool_use_id: id.clone(),
+                        name: name.clone(),
+                        input: input.clone(),
+                        result: result.clone(),
+                        duration_ms: duration.as_millis() as u64,
+                    })
+                    .collect();
+            }
+            tool_calls += results.len();
+            request.messages.push(Message::new(
+                Role::User,
+                results.into_iter().map(|(result, _)| result).collect(),
+            ));
+            last_response = Some(response);
+        }
+    }
+
+    /// Fire a shadow copy of the request in the background when configured
+    fn mirror(&self, request: &MessageRequest, options: &Option<RequestOptions>) {
+        let Some(shadow) = self.client.config().shadow.clone() else {
+            return;
+        };
+        if !shadow.should_mirror() {
+            return;
+        }
+
+        let mirrored = shadow.mirror_request(request);
+        let client = self.client.shadow_client();
+        // Shadow requests must never add retry load to a struggling endpoint.
+        let options = Some(options.clone().unwrap_or_default().no_retry());
+        tokio::spawn(async move {
+            let model = mirrored.model.clone();
+            let started = std::time::Instant::now();
+            let body = match serde_json::to_value(mirrored) {
+                Ok(body) => body,
+                Err(e) => {
+                    tracing::debug!("Failed to serialize shadow request: {}", e);
+                    return;
+                }
+            };
+            let result: Result<MessageResponse> = client
+                .request(HttpMethod::Post, "/messages", Some(body), options)
+                .await;
+            match (shadow.mode(), result) {
+                (ShadowMode::Log, Ok(response)) => tracing::info!(
+                    model = %model,
+                    latency_ms = started.elapsed().as_millis() as u64,
+                    input_tokens = response.usage.input_tokens,
+                    output_tokens = response.usage.output_tokens,
+                    stop_reason = ?response.stop_reason,
+                    "Shadow request completed"
+                ),
+                (ShadowMode::Log, Err(e)) => {
+                    tracing::warn!(model = %model, "Shadow request failed: {}", e)
+                }
+                (ShadowMode::Discard, Err(e)) => {
+                    tracing::debug!(model = %model, "Shadow request failed: {}", e)
+                }
+                (ShadowMode::Discard, Ok(_)) => {}
+            }
+        });
+    }
+
     /// Create a streaming message
     ///
     /// # Example
@@ -74,21 +843,160 @@ impl MessagesApi {
     /// # Ok(())
     /// # }
     /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "anthropic.messages.create_stream",
+            skip_all,
+            fields(model = %request.model, max_tokens = request.max_tokens)
+        )
+    )]
     pub async fn create_stream(
         &self,
         mut request: MessageRequest,
         options: Option<RequestOptions>,
     ) -> Result<MessageStream> {
-        // Ensure streaming is enabled
-        request.stream = Some(true);
+        let body = self.prepare_stream(&mut request, &options)?;
+        let (permit, reservation) = self.admit(&request, &options).await?;
+        let cancel = options.as_ref().and_then(|o| o.cancel.clone());
+        let resume_options = options.clone();
+        let response = match self
+            .client
+            .request_stream(HttpMethod::Post, "/messages", Some(body), options)
+            .await
+        {
+            Ok(response) => response,
+            Err(err) => {
+                if let Some((limiter, reservation)) = reservation {
+                    limiter.release(reservation);
+                }
+                return Err(err);
+            }
+        };
 
-        let body = serde_json::to_value(request)?;
+        let config = self.client.config();
+        let stream_options = StreamOptions {
+            malformed_events: config.malformed_stream_events,
+            idle_timeout: config.stream_idle_timeout,
+            limits: config.response_limits,
+        };
+        let stream = MessageStream::new_with_options(response, stream_options)
+            .await?
+            .with_permit(permit)
+            .with_token_reservation(reservation)
+            .with_resume_context(self.clone(), request.clone(), resume_options)
+            .with_provenance(
+                config
+                    .provenance
+                    .then(|| request.fingerprint())
+                    .transpose()?,
+            );
+        let stream = match cancel {
+            Some(token) => stream.with_cancellation(token),
+            None => stream,
+        };
+        Ok(match &config.metrics {
+            Some(metrics) => stream.with_metrics(Arc::clone(metrics), &request.model),
+            None => stream,
+        })
+    }
+
+    /// Stream a message without parsing the events, for proxies forwarding
+    /// the server-sent events as they are; see [`crate::streaming::raw`].
+    ///
+    /// The request is prepared, paced and retried on connect exactly like
+    /// [`create_stream`](Self::create_stream). Since usage is never read
+    /// from the body, a configured token rate limit keeps the request's full
+    /// estimate as spent.
+    pub async fn create_stream_raw(
+        &self,
+        mut request: MessageRequest,
+        options: Option<RequestOptions>,
+    ) -> Result<RawEventStream> {
+        let body = self.prepare_stream(&mut request, &options)?;
+        // The reservation is kept as spent: there is no usage to reconcile it
+        // against
+        let (permit, _reservation) = self.admit(&request, &options).await?;
+        let cancel = options.as_ref().and_then(|o| o.cancel.clone());
         let response = self
             .client
             .request_stream(HttpMethod::Post, "/messages", Some(body), options)
             .await?;
+        let stream = RawEventStream::new(response, &self.client.config().response_limits)
+            .await?
+            .with_permit(permit);
+        Ok(match cancel {
+            Some(token) => stream.with_cancellation(token),
+            None => stream,
+        })
+    }
 
-        MessageStream::new(response).await
+    /// Turn `request` into a streaming request body with the configured
+    /// rewrites applied
+    fn prepare_stream(
+        &self,
+        request: &mut MessageRequest,
+        options: &Option<RequestOptions>,
+    ) -> Result<serde_json::Value> {
+        request.stream = Some(true);
+        Self::attach_user_context(request, options);
+        self.attach_priority(request, options);
+        if let Some(profile) = &self.client.config().deterministic {
+            profile.apply(request);
+        }
+        self.client.config().warnings.check_model(&request.model);
+
+        let body = serde_json::to_value(&*request)?;
+        ValidationUtils::validate_body_limits(&body, "Request")?;
+        Ok(body)
+    }
+
+    /// Create a message over a stream, returning the complete response.
+    ///
+    /// Streaming keeps a long generation from running into HTTP timeouts.
+    /// If the stream cannot be set up (a connection or SSE failure before the
+    /// first event, such as a proxy that does not pass `text/event-stream`
+    /// through), the request is sent again without streaming. Errors the API
+    /// itself returned, and failures after events have arrived, are returned
+    /// as they are.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use threatflux_anthropic_sdk::{Client, models::message::MessageRequest};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = Client::from_env()?;
+    /// let request = MessageRequest::new()
+    ///     .max_tokens(32_000)
+    ///     .add_user_message("Write a detailed design document for ...");
+    ///
+    /// let response = client.messages().create_auto(request, None).await?;
+    /// println!("{}", response.text());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create_auto(
+        &self,
+        request: MessageRequest,
+        options: Option<RequestOptions>,
+    ) -> Result<MessageResponse> {
+        let mut started = false;
+        let streamed = match self.create_stream(request.clone(), options.clone()).await {
+            Ok(stream) => stream.accumulate(|_| started = true).await,
+            Err(err) => Err(err),
+        };
+        match streamed {
+            Err(err) if !started && is_stream_setup_failure(&err) => {
+                tracing::debug!(
+                    "Streaming failed before any event ({}); retrying without",
+                    err
+                );
+                let mut request = request;
+                request.stream = None;
+                self.create_unary(request, options).await
+            }
+            result => result,
+        }
     }
 
     /// Count tokens in a message
@@ -108,6 +1016,14 @@ impl MessagesApi {
     /// # Ok(())
     /// # }
     /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "anthropic.messages.count_tokens",
+            skip_all,
+            fields(model = %request.model)
+        )
+    )]
     pub async fn count_tokens(
         &self,
         request: TokenCountRequest,
@@ -136,3 +1052,28 @@ impl MessagesApi {
         self.count_tokens(request, options).await
     }
 }
+
+/// Run `future` to completion, or until `deadline` passes
+async fn within<F: std::future::Future>(
+    deadline: Option<tokio::time::Instant>,
+    future: F,
+) -> Option<F::Output> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline, future).await.ok(),
+        None => Some(future.await),
+    }
+}
+
+/// Whether `err` is a transport or SSE problem rather than an answer from
+/// the API, so the same request may succeed without streaming
+fn is_stream_setup_failure(err: &AnthropicError) -> bool {
+    matches!(
+        err,
+        AnthropicError::Stream(_)
+            | AnthropicError::Network(_)
+            | AnthropicError::Http(_)
+            | AnthropicError::Timeout(_)
+            | AnthropicError::Json(_)
+            | AnthropicError::Io(_)
+    )
+}
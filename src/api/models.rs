@@ -39,7 +39,7 @@ impl ModelsApi {
     /// ```
     pub async fn list(
         &self,
-        pagination: Option<Pagination>,
+        pagination: Option<Pagination<Model>>,
         options: Option<RequestOptions>,
     ) -> Result<ModelListResponse> {
         let path = build_paginated_path("/models", pagination.as_ref());
@@ -2,14 +2,15 @@
 
 use crate::{
     api::utils::build_path_with_query,
-    client::{beta_headers, Client, API_VERSION},
-    error::{AnthropicError, Result},
+    client::{beta_headers, secret_header, Client, API_VERSION},
+    error::{AnthropicError, ResponseClass, Result},
     models::skill::{
         Skill, SkillCreateRequest, SkillDeleteResponse, SkillFileUpload, SkillListParams,
         SkillListResponse, SkillVersion, SkillVersionCreateRequest, SkillVersionDeleteResponse,
         SkillVersionListParams, SkillVersionListResponse,
     },
     types::{HttpMethod, RequestOptions},
+    utils::http::{read_body, read_error_text},
 };
 use reqwest::{
     header::{HeaderMap, HeaderValue},
@@ -17,6 +18,7 @@ use reqwest::{
 };
 use serde::de::DeserializeOwned;
 use std::{collections::HashMap, path::Path};
+use zeroize::Zeroizing;
 
 /// API client for Skills endpoints
 #[derive(Clone)]
@@ -39,10 +41,10 @@ impl SkillsApi {
     fn build_skill_headers(&self, options: &Option<RequestOptions>) -> Result<HeaderMap> {
         let mut headers = HeaderMap::new();
 
-        let auth_value = format!("Bearer {}", self.client.config().api_key);
+        let auth_value = Zeroizing::new(format!("Bearer {}", self.client.api_key().expose()));
         headers.insert(
             "Authorization",
-            HeaderValue::from_str(&auth_value)
+            secret_header(&auth_value)
                 .map_err(|e| AnthropicError::config(format!("Invalid auth header: {}", e)))?,
         );
 
@@ -50,10 +52,19 @@ impl SkillsApi {
 
         headers.insert(
             "User-Agent",
-            HeaderValue::from_str(&self.client.config().user_agent)
+            HeaderValue::from_str(&self.client.config().full_user_agent())
                 .map_err(|e| AnthropicError::config(format!("Invalid user agent: {}", e)))?,
         );
 
+        for (name, value) in self.client.config().telemetry_headers() {
+            headers.insert(
+                name,
+                HeaderValue::from_str(&value).map_err(|e| {
+                    AnthropicError::config(format!("Invalid telemetry header: {}", e))
+                })?,
+            );
+        }
+
         let mut beta_features = vec![beta_headers::SKILLS_API];
 
         if let Some(options) = options {
@@ -134,7 +145,7 @@ impl SkillsApi {
     where
         T: DeserializeOwned,
     {
-        let mut url = self.client.config().base_url.clone();
+        let mut url = self.client.config().active_base_url();
         url.set_path(&format!("/v1{}", path));
 
         let options = Self::with_skills_beta(options);
@@ -162,14 +173,15 @@ impl SkillsApi {
         let status = response.status();
 
         if !status.is_success() {
-            let error_text = response.text().await.unwrap_or_default();
+            let error_text = read_error_text(response, &self.client.config().response_limits)
+                .await
+                .unwrap_or_default();
             return Err(AnthropicError::api_error(status.as_u16(), error_text, None));
         }
 
-        response
-            .json::<T>()
-            .await
-            .map_err(|e| AnthropicError::json(e.to_string()))
+        let limits = &self.client.config().response_limits;
+        let body = read_body(response, limits, ResponseClass::Api).await?;
+        serde_json::from_slice(&body).map_err(|e| AnthropicError::json(e.to_string()))
     }
 
     /// Convert a local directory into skill upload files.
@@ -416,11 +428,15 @@ impl SkillsApi {
         let status = response.status();
 
         if !status.is_success() {
-            let error_text = response.text().await.unwrap_or_default();
+            let error_text = read_error_text(response, &self.client.config().response_limits)
+                .await
+                .unwrap_or_default();
             return Err(AnthropicError::api_error(status.as_u16(), error_text, None));
         }
 
-        let body = response.text().await.unwrap_or_default();
+        let limits = &self.client.config().response_limits;
+        let body = read_body(response, limits, ResponseClass::Api).await?;
+        let body = String::from_utf8_lossy(&body);
         if body.trim().is_empty() {
             return Ok(SkillDeleteResponse {
                 id: skill_id.to_string(),
@@ -584,11 +600,15 @@ impl SkillsApi {
         let status = response.status();
 
         if !status.is_success() {
-            let error_text = response.text().await.unwrap_or_default();
+            let error_text = read_error_text(response, &self.client.config().response_limits)
+                .await
+                .unwrap_or_default();
             return Err(AnthropicError::api_error(status.as_u16(), error_text, None));
         }
 
-        let body = response.text().await.unwrap_or_default();
+        let limits = &self.client.config().response_limits;
+        let body = read_body(response, limits, ResponseClass::Api).await?;
+        let body = String::from_utf8_lossy(&body);
         if body.trim().is_empty() {
             return Ok(SkillVersionDeleteResponse {
                 id: version_id.to_string(),
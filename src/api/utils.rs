@@ -1,9 +1,9 @@
 //! Shared utilities for API modules
 
-use crate::types::Pagination;
+use crate::types::{Cursor, Pagination};
 
 /// Builds query parameters for pagination
-pub fn build_pagination_query(pagination: &Pagination) -> Vec<String> {
+pub fn build_pagination_query<T>(pagination: &Pagination<T>) -> Vec<String> {
     let mut query_params = Vec::new();
 
     if let Some(limit) = pagination.limit {
@@ -34,7 +34,7 @@ pub fn build_path_with_query(base_path: &str, query_params: Vec<String>) -> Stri
 }
 
 /// Builds pagination query parameters and adds them to a path
-pub fn build_paginated_path(base_path: &str, pagination: Option<&Pagination>) -> String {
+pub fn build_paginated_path<T>(base_path: &str, pagination: Option<&Pagination<T>>) -> String {
     if let Some(pagination) = pagination {
         let query_params = build_pagination_query(pagination);
         build_path_with_query(base_path, query_params)
@@ -44,7 +44,7 @@ pub fn build_paginated_path(base_path: &str, pagination: Option<&Pagination>) ->
 }
 
 /// Creates a default pagination for list_all operations
-pub fn create_default_pagination(after: Option<String>) -> Pagination {
+pub fn create_default_pagination<T>(after: Option<Cursor<T>>) -> Pagination<T> {
     Pagination::new()
         .with_limit(100)
         .with_after(after.unwrap_or_default())
@@ -56,7 +56,7 @@ mod tests {
 
     #[test]
     fn test_build_pagination_query_empty() {
-        let pagination = Pagination {
+        let pagination: Pagination<()> = Pagination {
             limit: None,
             after: None,
             before: None,
@@ -67,14 +67,14 @@ mod tests {
 
     #[test]
     fn test_build_pagination_query_with_limit() {
-        let pagination = Pagination::new().with_limit(50);
+        let pagination = Pagination::<()>::new().with_limit(50);
         let query = build_pagination_query(&pagination);
         assert_eq!(query, vec!["limit=50"]);
     }
 
     #[test]
     fn test_build_pagination_query_full() {
-        let pagination = Pagination::new()
+        let pagination = Pagination::<()>::new()
             .with_limit(50)
             .with_after("after_id".to_string())
             .with_before("before_id".to_string());
@@ -102,30 +102,30 @@ mod tests {
 
     #[test]
     fn test_build_paginated_path_none() {
-        let path = build_paginated_path("/test", None);
+        let path = build_paginated_path::<()>("/test", None);
         assert_eq!(path, "/test");
     }
 
     #[test]
     fn test_build_paginated_path_some() {
-        let pagination = Pagination::new().with_limit(25);
+        let pagination = Pagination::<()>::new().with_limit(25);
         let path = build_paginated_path("/test", Some(&pagination));
         assert_eq!(path, "/test?limit=25");
     }
 
     #[test]
     fn test_create_default_pagination_no_after() {
-        let pagination = create_default_pagination(None);
+        let pagination = create_default_pagination::<()>(None);
         assert_eq!(pagination.limit, Some(100));
-        assert_eq!(pagination.after, Some(String::new()));
+        assert_eq!(pagination.after, Some(Cursor::new("")));
         assert_eq!(pagination.before, None);
     }
 
     #[test]
     fn test_create_default_pagination_with_after() {
-        let pagination = create_default_pagination(Some("test_id".to_string()));
+        let pagination = create_default_pagination::<()>(Some("test_id".into()));
         assert_eq!(pagination.limit, Some(100));
-        assert_eq!(pagination.after, Some("test_id".to_string()));
+        assert_eq!(pagination.after, Some(Cursor::new("test_id")));
         assert_eq!(pagination.before, None);
     }
 }
@@ -0,0 +1,425 @@
+//! Synchronous client, enabled by the `blocking` feature
+//!
+//! For programs that do not run an async runtime of their own, such as CLI
+//! tools and plugin hosts. [`Client`] owns a small tokio runtime and drives
+//! the async client on it, so every call simply blocks until it is done:
+//!
+//! ```rust,no_run
+//! use threatflux_anthropic_sdk::{
+//!     blocking::Client,
+//!     models::{MessageRequest, StreamEvent},
+//! };
+//!
+//! # fn example() -> threatflux_anthropic_sdk::Result<()> {
+//! let client = Client::from_env()?;
+//! let request = MessageRequest::new()
+//!     .max_tokens(256)
+//!     .add_user_message("Hello, Claude!");
+//! let response = client.messages().create(request, None)?;
+//! println!("{}", response.text());
+//!
+//! // Streams are plain iterators
+//! let request = MessageRequest::new()
+//!     .max_tokens(256)
+//!     .add_user_message("Tell me a story");
+//! for event in client.messages().create_stream(request, None)? {
+//!     if let StreamEvent::ContentBlockDelta { delta, .. } = event? {
+//!         print!("{}", delta.as_text().unwrap_or_default());
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The methods mirror those of [`crate::Client`]'s APIs with the `.await`
+//! taken away. Like any blocking call they must not be made from inside an
+//! async runtime, where they panic; use the async client there, or reach it
+//! with [`Client::as_async`].
+
+use crate::{
+    api::{
+        files::FilesApi, message_batches::MessageBatchesApi, messages::MessagesApi,
+        models::ModelsApi,
+    },
+    config::Config,
+    error::{AnthropicError, Result},
+    models::{
+        batch::{
+            MessageBatch, MessageBatchCreateRequest, MessageBatchListResponse,
+            MessageBatchResultEntry, PollOptions,
+        },
+        file::{File, FileListResponse, FileUploadRequest, FileUploadResponse},
+        message::{
+            MessageRequest, MessageResponse, StreamEvent, TokenCountRequest, TokenCountResponse,
+        },
+        model::{Model, ModelListResponse},
+    },
+    streaming,
+    types::{Pagination, ProgressCallback, RequestOptions},
+};
+use futures::StreamExt;
+use std::{fmt, path::Path, sync::Arc};
+use tokio::runtime::Runtime;
+
+/// Synchronous Anthropic API client; see the [module docs](self).
+///
+/// Clones share the runtime and the async client's connection pool and
+/// rate limiter.
+#[derive(Clone)]
+pub struct Client {
+    inner: crate::Client,
+    runtime: Arc<Runtime>,
+}
+
+impl Client {
+    /// Create a client from `config`
+    pub fn new(config: Config) -> Result<Self> {
+        Ok(Self {
+            inner: crate::Client::try_new(config)?,
+            runtime: Arc::new(start_runtime()?),
+        })
+    }
+
+    /// Create a client from the `ANTHROPIC_*` environment variables
+    pub fn from_env() -> Result<Self> {
+        Self::new(Config::from_env()?)
+    }
+
+    /// Client configuration
+    pub fn config(&self) -> &Config {
+        self.inner.config()
+    }
+
+    /// The async client calls are made with, for parts of the SDK this
+    /// facade does not cover; run them with [`Client::block_on`]
+    pub fn as_async(&self) -> &crate::Client {
+        &self.inner
+    }
+
+    /// Run `future` to completion on the client's runtime
+    pub fn block_on<F: std::future::Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    /// Messages API
+    pub fn messages(&self) -> Messages {
+        Messages {
+            api: self.inner.messages(),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    /// Models API
+    pub fn models(&self) -> Models {
+        Models {
+            api: self.inner.models(),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    /// Files API
+    pub fn files(&self) -> Files {
+        Files {
+            api: self.inner.files(),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    /// Message Batches API
+    pub fn message_batches(&self) -> MessageBatches {
+        MessageBatches {
+            api: self.inner.message_batches(),
+            runtime: self.runtime.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for Client {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Client")
+            .field("base_url", &self.config().base_url.as_str())
+            .finish_non_exhaustive()
+    }
+}
+
+/// One worker keeps background tasks, such as stream readers, running
+/// between calls
+fn start_runtime() -> Result<Runtime> {
+    tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name("anthropic-blocking")
+        .enable_all()
+        .build()
+        .map_err(|e| AnthropicError::config(format!("Failed to start runtime: {}", e)))
+}
+
+/// Synchronous [`MessagesApi`]
+#[derive(Clone)]
+pub struct Messages {
+    api: MessagesApi,
+    runtime: Arc<Runtime>,
+}
+
+impl Messages {
+    /// Send a message and wait for the response
+    pub fn create(
+        &self,
+        request: MessageRequest,
+        options: Option<RequestOptions>,
+    ) -> Result<MessageResponse> {
+        self.runtime.block_on(self.api.create(request, options))
+    }
+
+    /// Send a message, streaming it when it is too large to send unary
+    pub fn create_auto(
+        &self,
+        request: MessageRequest,
+        options: Option<RequestOptions>,
+    ) -> Result<MessageResponse> {
+        self.runtime
+            .block_on(self.api.create_auto(request, options))
+    }
+
+    /// Start streaming a message; iterate the result for its events
+    pub fn create_stream(
+        &self,
+        request: MessageRequest,
+        options: Option<RequestOptions>,
+    ) -> Result<MessageStream> {
+        let stream = self
+            .runtime
+            .block_on(self.api.create_stream(request, options))?;
+        Ok(MessageStream {
+            stream,
+            runtime: self.runtime.clone(),
+        })
+    }
+
+    /// Count the input tokens of a request
+    pub fn count_tokens(
+        &self,
+        request: TokenCountRequest,
+        options: Option<RequestOptions>,
+    ) -> Result<TokenCountResponse> {
+        self.runtime
+            .block_on(self.api.count_tokens(request, options))
+    }
+}
+
+/// Events of a streaming message, as a blocking iterator
+pub struct MessageStream {
+    stream: streaming::MessageStream,
+    runtime: Arc<Runtime>,
+}
+
+impl MessageStream {
+    /// Wait for the rest of the message and return it whole
+    pub fn collect_message(self) -> Result<MessageResponse> {
+        self.runtime.block_on(self.stream.collect_message())
+    }
+
+    /// Wait for the rest of the message and return its text
+    pub fn collect_text(self) -> Result<String> {
+        self.runtime.block_on(self.stream.collect_text())
+    }
+
+    /// The underlying async stream
+    pub fn into_async(self) -> streaming::MessageStream {
+        self.stream
+    }
+}
+
+impl Iterator for MessageStream {
+    type Item = Result<StreamEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.runtime.block_on(self.stream.next())
+    }
+}
+
+impl fmt::Debug for MessageStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MessageStream")
+            .field("done", &self.stream.is_done())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Synchronous [`ModelsApi`]
+#[derive(Clone)]
+pub struct Models {
+    api: ModelsApi,
+    runtime: Arc<Runtime>,
+}
+
+impl Models {
+    /// List available models
+    pub fn list(
+        &self,
+        pagination: Option<Pagination<Model>>,
+        options: Option<RequestOptions>,
+    ) -> Result<ModelListResponse> {
+        self.runtime.block_on(self.api.list(pagination, options))
+    }
+
+    /// Get a model by id
+    pub fn get(&self, model_id: &str, options: Option<RequestOptions>) -> Result<Model> {
+        self.runtime.block_on(self.api.get(model_id, options))
+    }
+
+    /// List every model, following pagination
+    pub fn list_all(&self, options: Option<RequestOptions>) -> Result<Vec<Model>> {
+        self.runtime.block_on(self.api.list_all(options))
+    }
+
+    /// Whether a model exists
+    pub fn exists(&self, model_id: &str, options: Option<RequestOptions>) -> bool {
+        self.runtime.block_on(self.api.exists(model_id, options))
+    }
+}
+
+/// Synchronous [`FilesApi`]
+#[derive(Clone)]
+pub struct Files {
+    api: FilesApi,
+    runtime: Arc<Runtime>,
+}
+
+impl Files {
+    /// Upload a file
+    pub fn upload(
+        &self,
+        request: FileUploadRequest,
+        options: Option<RequestOptions>,
+    ) -> Result<FileUploadResponse> {
+        self.runtime.block_on(self.api.upload(request, options))
+    }
+
+    /// Upload the file at `file_path`
+    pub fn upload_from_path(
+        &self,
+        file_path: impl AsRef<Path>,
+        purpose: &str,
+        progress_callback: Option<ProgressCallback>,
+        options: Option<RequestOptions>,
+    ) -> Result<FileUploadResponse> {
+        self.runtime.block_on(self.api.upload_from_path(
+            file_path,
+            purpose,
+            progress_callback,
+            options,
+        ))
+    }
+
+    /// List files
+    pub fn list(
+        &self,
+        pagination: Option<Pagination<File>>,
+        options: Option<RequestOptions>,
+    ) -> Result<FileListResponse> {
+        self.runtime.block_on(self.api.list(pagination, options))
+    }
+
+    /// Get a file's metadata
+    pub fn get(&self, file_id: &str, options: Option<RequestOptions>) -> Result<File> {
+        self.runtime.block_on(self.api.get(file_id, options))
+    }
+
+    /// Download a file's content
+    pub fn download(&self, file_id: &str, options: Option<RequestOptions>) -> Result<Vec<u8>> {
+        self.runtime.block_on(self.api.download(file_id, options))
+    }
+
+    /// Download a file's content to `output_path`
+    pub fn download_to_path(
+        &self,
+        file_id: &str,
+        output_path: impl AsRef<Path>,
+        progress_callback: Option<ProgressCallback>,
+        options: Option<RequestOptions>,
+    ) -> Result<()> {
+        self.runtime.block_on(self.api.download_to_path(
+            file_id,
+            output_path,
+            progress_callback,
+            options,
+        ))
+    }
+
+    /// Delete a file
+    pub fn delete(&self, file_id: &str, options: Option<RequestOptions>) -> Result<()> {
+        self.runtime.block_on(self.api.delete(file_id, options))
+    }
+}
+
+/// Synchronous [`MessageBatchesApi`]
+#[derive(Clone)]
+pub struct MessageBatches {
+    api: MessageBatchesApi,
+    runtime: Arc<Runtime>,
+}
+
+impl MessageBatches {
+    /// Create a batch
+    pub fn create(
+        &self,
+        request: MessageBatchCreateRequest,
+        options: Option<RequestOptions>,
+    ) -> Result<MessageBatch> {
+        self.runtime.block_on(self.api.create(request, options))
+    }
+
+    /// Get a batch
+    pub fn retrieve(
+        &self,
+        batch_id: &str,
+        options: Option<RequestOptions>,
+    ) -> Result<MessageBatch> {
+        self.runtime.block_on(self.api.retrieve(batch_id, options))
+    }
+
+    /// List batches
+    pub fn list(
+        &self,
+        pagination: Option<Pagination<MessageBatch>>,
+        options: Option<RequestOptions>,
+    ) -> Result<MessageBatchListResponse> {
+        self.runtime.block_on(self.api.list(pagination, options))
+    }
+
+    /// Cancel a batch
+    pub fn cancel(&self, batch_id: &str, options: Option<RequestOptions>) -> Result<MessageBatch> {
+        self.runtime.block_on(self.api.cancel(batch_id, options))
+    }
+
+    /// Delete a batch
+    pub fn delete(&self, batch_id: &str, options: Option<RequestOptions>) -> Result<()> {
+        self.runtime.block_on(self.api.delete(batch_id, options))
+    }
+
+    /// Download and parse a batch's results
+    pub fn results(
+        &self,
+        batch_id: &str,
+        options: Option<RequestOptions>,
+    ) -> Result<Vec<MessageBatchResultEntry>> {
+        self.runtime.block_on(self.api.results(batch_id, options))
+    }
+
+    /// Download a batch's results to `path`, returning the bytes written
+    pub fn results_to_file(
+        &self,
+        batch_id: &str,
+        path: impl AsRef<Path>,
+        options: Option<RequestOptions>,
+    ) -> Result<u64> {
+        self.runtime
+            .block_on(self.api.results_to_file(batch_id, path, options))
+    }
+
+    /// Poll a batch until it has ended
+    pub fn wait_for_completion(&self, batch_id: &str, poll: PollOptions) -> Result<MessageBatch> {
+        self.runtime
+            .block_on(self.api.wait_for_completion(batch_id, poll))
+    }
+}
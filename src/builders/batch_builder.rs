@@ -1,11 +1,19 @@
 //! Builder for constructing batch requests
 
-use crate::builders::common::{FluentBuilder, ParameterBuilder, ValidatedBuilder, ValidationUtils};
+use crate::builders::common::{
+    format_bytes, serialized_len, BuilderState, FluentBuilder, ParameterBuilder, ValidatedBuilder,
+    ValidationUtils,
+};
+use crate::builders::template::{PromptTemplate, TemplateVars};
 use crate::builders::MessageBuilder;
+use crate::config::limits;
 use crate::models::{
-    batch::{BatchRequestItem, MessageBatchCreateRequest},
+    batch::{
+        BatchRequestItem, MessageBatchCreateRequest, MessageBatchResult, MessageBatchResultEntry,
+    },
     message::MessageRequest,
 };
+use std::collections::HashMap;
 
 /// Builder for constructing batch requests with a fluent API
 #[derive(Debug, Clone)]
@@ -218,6 +226,59 @@ impl BatchBuilder {
         self
     }
 
+    /// Add one request per row by rendering `template`, naming each with
+    /// `custom_id(index, &row)`.
+    ///
+    /// Returns the builder together with a [`BatchJoin`] that maps the
+    /// generated custom IDs back to their rows, for joining with the batch
+    /// results once they are in. Fails if a row is missing a template
+    /// variable or two rows get the same custom ID.
+    ///
+    /// ```rust
+    /// use serde_json::json;
+    /// use threatflux_anthropic_sdk::builders::{BatchBuilder, PromptTemplate};
+    ///
+    /// let rows = vec![
+    ///     json!({"sku": "A-1", "review": "Arrived broken"}),
+    ///     json!({"sku": "B-2", "review": "Works great"}),
+    /// ];
+    /// let template = PromptTemplate::new("Sentiment of: {review}").max_tokens(8);
+    /// let (batch, join) = BatchBuilder::new()
+    ///     .add_templated(rows, &template, |_, row| format!("review-{}", row["sku"].as_str().unwrap()))
+    ///     .unwrap();
+    ///
+    /// assert_eq!(batch.len(), 2);
+    /// assert_eq!(join.row("review-B-2").unwrap()["review"], "Works great");
+    /// ```
+    pub fn add_templated<R, F>(
+        mut self,
+        rows: impl IntoIterator<Item = R>,
+        template: &PromptTemplate,
+        mut custom_id: F,
+    ) -> Result<(Self, BatchJoin<R>), crate::error::AnthropicError>
+    where
+        R: TemplateVars,
+        F: FnMut(usize, &R) -> String,
+    {
+        let mut join = BatchJoin::new();
+        for (index, row) in rows.into_iter().enumerate() {
+            let id = custom_id(index, &row);
+            if join.rows.contains_key(&id) || self.requests.iter().any(|r| r.custom_id == id) {
+                return Err(crate::error::AnthropicError::invalid_input(format!(
+                    "Duplicate custom_id found: {}",
+                    id
+                )));
+            }
+            let request = template
+                .render(&row)
+                .map_err(|e| e.with_context(format!("Row {}", index)))?;
+            self.requests
+                .push(BatchRequestItem::new(id.clone(), request));
+            join.insert(id, row);
+        }
+        Ok((self, join))
+    }
+
     /// Set default parameters for subsequent requests
     pub fn with_defaults(
         self,
@@ -328,12 +389,85 @@ impl BatchBuilder {
                     ))
                 })?;
             }
+
+            ValidationUtils::validate_request_limits(
+                &request.params,
+                &format!("Request {}", request.custom_id),
+            )?;
         }
 
-        Ok(MessageBatchCreateRequest {
+        let batch = MessageBatchCreateRequest {
             requests: self.requests,
-        })
+        };
+        validate_batch_limits(&batch)?;
+        Ok(batch)
     }
+
+    /// Split the requests into as few batches as fit within `max_requests`
+    /// requests and `max_bytes` serialized bytes each, keeping their order.
+    ///
+    /// Both bounds are capped at the API limits in [`limits`]. A single
+    /// request too large for a batch on its own is an error.
+    pub fn build_chunked(
+        self,
+        max_requests: usize,
+        max_bytes: usize,
+    ) -> Result<Vec<MessageBatchCreateRequest>, crate::error::AnthropicError> {
+        let max_requests = max_requests.clamp(1, limits::MAX_BATCH_REQUESTS);
+        let max_bytes = max_bytes.min(limits::MAX_BATCH_BYTES);
+        // `{"requests":[]}`, plus a comma between consecutive items
+        let envelope = serialized_len(&MessageBatchCreateRequest::new());
+
+        let mut chunks = Vec::new();
+        let mut current = Vec::new();
+        let mut current_bytes = envelope;
+        for item in self.requests {
+            let item_bytes = serialized_len(&item);
+            if envelope + item_bytes > max_bytes {
+                return Err(crate::error::AnthropicError::invalid_input(format!(
+                    "Request {} is {} serialized, too large for a batch of at most {}",
+                    item.custom_id,
+                    format_bytes(item_bytes),
+                    format_bytes(max_bytes)
+                )));
+            }
+            let separator = usize::from(!current.is_empty());
+            if current.len() == max_requests || current_bytes + separator + item_bytes > max_bytes {
+                chunks.push(MessageBatchCreateRequest {
+                    requests: std::mem::take(&mut current),
+                });
+                current_bytes = envelope;
+            }
+            current_bytes += usize::from(!current.is_empty()) + item_bytes;
+            current.push(item);
+        }
+        if !current.is_empty() {
+            chunks.push(MessageBatchCreateRequest { requests: current });
+        }
+        Ok(chunks)
+    }
+}
+
+/// Check the batch-wide request count and size limits
+fn validate_batch_limits(
+    batch: &MessageBatchCreateRequest,
+) -> Result<(), crate::error::AnthropicError> {
+    if batch.requests.len() > limits::MAX_BATCH_REQUESTS {
+        return Err(crate::error::AnthropicError::invalid_input(format!(
+            "Batch has {} requests; at most {} are allowed",
+            batch.requests.len(),
+            limits::MAX_BATCH_REQUESTS
+        )));
+    }
+    let size = serialized_len(batch);
+    if size > limits::MAX_BATCH_BYTES {
+        return Err(crate::error::AnthropicError::invalid_input(format!(
+            "Batch is {} serialized, over the {} batch size limit",
+            format_bytes(size),
+            format_bytes(limits::MAX_BATCH_BYTES)
+        )));
+    }
+    Ok(())
 }
 
 impl Default for BatchBuilder {
@@ -355,8 +489,135 @@ impl ValidatedBuilder<MessageBatchCreateRequest> for BatchBuilder {
 }
 
 impl FluentBuilder for BatchBuilder {
-    fn inspect(&self) -> Option<&dyn std::fmt::Debug> {
-        Some(self)
+    fn inspect(&self) -> BuilderState {
+        let mut state = BuilderState::default();
+        if let Err(error) =
+            ValidationUtils::validate_messages_not_empty(self.requests.len(), "Batch")
+        {
+            state.warnings.push(match error {
+                crate::error::AnthropicError::InvalidInput(message) => message,
+                other => other.to_string(),
+            });
+        }
+
+        let mut custom_ids = std::collections::HashSet::new();
+        for request in &self.requests {
+            if !custom_ids.insert(&request.custom_id) {
+                state
+                    .warnings
+                    .push(format!("Duplicate custom_id found: {}", request.custom_id));
+            }
+
+            for field in BuilderState::fields_set(&request.params) {
+                if !state.fields_set.contains(&field) {
+                    state.fields_set.push(field);
+                }
+            }
+            state.estimated_tokens = state
+                .estimated_tokens
+                .saturating_add(request.params.estimated_input_tokens());
+
+            let context = format!("Request {}", request.custom_id);
+            for issue in ValidationUtils::request_issues(&request.params, &context) {
+                if issue.starts_with(&context) {
+                    state.warnings.push(issue);
+                } else {
+                    state.warnings.push(format!("{}: {}", context, issue));
+                }
+            }
+        }
+        state
+    }
+}
+
+/// Rows behind a templated batch, keyed by the custom ID each was given.
+///
+/// Produced by [`BatchBuilder::add_templated`]; use [`join`](Self::join) to
+/// pair every row with its result.
+#[derive(Debug, Clone)]
+pub struct BatchJoin<R> {
+    order: Vec<String>,
+    rows: HashMap<String, R>,
+}
+
+impl<R> BatchJoin<R> {
+    fn new() -> Self {
+        Self {
+            order: Vec::new(),
+            rows: HashMap::new(),
+        }
+    }
+
+    fn insert(&mut self, custom_id: String, row: R) {
+        self.order.push(custom_id.clone());
+        self.rows.insert(custom_id, row);
+    }
+
+    /// Row that produced `custom_id`
+    pub fn row(&self, custom_id: &str) -> Option<&R> {
+        self.rows.get(custom_id)
+    }
+
+    /// Custom IDs in row order
+    pub fn custom_ids(&self) -> impl Iterator<Item = &str> {
+        self.order.iter().map(String::as_str)
+    }
+
+    /// Number of rows
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Whether there are no rows
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// Pair each row with its result, in row order.
+    ///
+    /// Results for custom IDs that are not in this join are ignored; rows
+    /// without a result get `None`.
+    pub fn join(
+        mut self,
+        results: impl IntoIterator<Item = MessageBatchResultEntry>,
+    ) -> Vec<JoinedResult<R>> {
+        let mut by_id: HashMap<String, MessageBatchResult> = results
+            .into_iter()
+            .map(|entry| (entry.custom_id, entry.result))
+            .collect();
+        self.order
+            .into_iter()
+            .filter_map(|custom_id| {
+                let row = self.rows.remove(&custom_id)?;
+                let result = by_id.remove(&custom_id);
+                Some(JoinedResult {
+                    custom_id,
+                    row,
+                    result,
+                })
+            })
+            .collect()
+    }
+}
+
+/// A dataset row together with the batch result it produced
+#[derive(Debug, Clone)]
+pub struct JoinedResult<R> {
+    /// Custom ID of the request
+    pub custom_id: String,
+    /// The row the request was rendered from
+    pub row: R,
+    /// The result, or `None` if the batch returned nothing for this row
+    pub result: Option<MessageBatchResult>,
+}
+
+impl<R> JoinedResult<R> {
+    /// Text of a successful response
+    pub fn text(&self) -> Option<String> {
+        self.result
+            .as_ref()
+            .and_then(MessageBatchResult::message)
+            .map(|message| message.text())
     }
 }
 
@@ -493,7 +754,7 @@ impl ValidatedBuilder<MessageBatchCreateRequest> for BatchBuilderWithDefaults {
 }
 
 impl FluentBuilder for BatchBuilderWithDefaults {
-    fn inspect(&self) -> Option<&dyn std::fmt::Debug> {
-        Some(self)
+    fn inspect(&self) -> BuilderState {
+        self.builder.inspect()
     }
 }
@@ -1,6 +1,9 @@
 //! Common builder traits and validation utilities
 
+use crate::config::limits;
 use crate::error::AnthropicError;
+use crate::models::message::MessageRequest;
+use serde::Serialize;
 
 /// Common validation utilities for builders
 pub struct ValidationUtils;
@@ -84,6 +87,245 @@ impl ValidationUtils {
         }
         Ok(())
     }
+
+    /// Every problem [`MessageBuilder::build_validated`](super::MessageBuilder::build_validated)
+    /// would reject in `request`, in the order it checks them
+    pub fn request_issues(request: &MessageRequest, context: &str) -> Vec<String> {
+        let checks = [
+            Self::validate_messages_not_empty(request.messages.len(), context),
+            Self::validate_max_tokens(request.max_tokens, context),
+            request
+                .temperature
+                .map_or(Ok(()), Self::validate_temperature),
+            request.top_p.map_or(Ok(()), Self::validate_top_p),
+            Self::validate_claude_4_constraints(&request.model, request.temperature, request.top_p),
+            request.thinking.as_ref().map_or(Ok(()), |thinking| {
+                Self::validate_thinking_config(&request.model, thinking.budget_tokens)
+            }),
+        ];
+        let mut issues: Vec<String> = checks
+            .into_iter()
+            .filter_map(|check| check.err())
+            .map(|error| match error {
+                AnthropicError::InvalidInput(message) => message,
+                other => other.to_string(),
+            })
+            .collect();
+        if let Ok(body) = serde_json::to_value(request) {
+            issues.extend(Self::request_limit_issues(&body, context));
+        }
+        issues
+    }
+
+    /// Validate `request` against the documented size and count limits in
+    /// [`config::limits`](crate::config::limits)
+    pub fn validate_request_limits(
+        request: &MessageRequest,
+        context: &str,
+    ) -> Result<(), AnthropicError> {
+        Self::validate_body_limits(&serde_json::to_value(request)?, context)
+    }
+
+    /// Like [`validate_request_limits`](Self::validate_request_limits), for
+    /// an already serialized request body
+    pub fn validate_body_limits(
+        body: &serde_json::Value,
+        context: &str,
+    ) -> Result<(), AnthropicError> {
+        match Self::request_limit_issues(body, context).into_iter().next() {
+            Some(issue) => Err(AnthropicError::invalid_input(issue)),
+            None => Ok(()),
+        }
+    }
+
+    /// Every limit in [`config::limits`](crate::config::limits) a serialized
+    /// request body exceeds, with the actual and allowed values
+    pub fn request_limit_issues(body: &serde_json::Value, context: &str) -> Vec<String> {
+        let mut issues = Vec::new();
+
+        let size = serialized_len(body);
+        if size > limits::MAX_REQUEST_BYTES {
+            issues.push(format!(
+                "{} is {} serialized, over the {} request size limit",
+                context,
+                format_bytes(size),
+                format_bytes(limits::MAX_REQUEST_BYTES)
+            ));
+        }
+
+        let messages = body
+            .get("messages")
+            .and_then(serde_json::Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        if messages.len() > limits::MAX_MESSAGES {
+            issues.push(format!(
+                "{} has {} messages; at most {} are allowed",
+                context,
+                messages.len(),
+                limits::MAX_MESSAGES
+            ));
+        }
+
+        let mut media = MediaTally::default();
+        for (index, message) in messages.iter().enumerate() {
+            if let Some(content) = message.get("content") {
+                media.visit(content, &format!("messages[{}].content", index));
+            }
+        }
+        if media.images > limits::MAX_IMAGES {
+            issues.push(format!(
+                "{} has {} images; at most {} are allowed per request",
+                context,
+                media.images,
+                limits::MAX_IMAGES
+            ));
+        }
+        for (path, len) in media.oversized_images {
+            issues.push(format!(
+                "{} image at {} is {} base64, over the {} per-image limit",
+                context,
+                path,
+                format_bytes(len),
+                format_bytes(limits::MAX_IMAGE_BYTES)
+            ));
+        }
+        if media.pdfs > limits::MAX_PDF_PAGES {
+            issues.push(format!(
+                "{} has {} PDF documents; at most {} PDF pages are allowed per request",
+                context,
+                media.pdfs,
+                limits::MAX_PDF_PAGES
+            ));
+        }
+        issues
+    }
+}
+
+/// Images and PDFs found in message content, including inside tool results
+#[derive(Default)]
+struct MediaTally {
+    images: usize,
+    pdfs: usize,
+    oversized_images: Vec<(String, usize)>,
+}
+
+impl MediaTally {
+    fn visit(&mut self, value: &serde_json::Value, path: &str) {
+        match value {
+            serde_json::Value::Array(blocks) => {
+                for (index, block) in blocks.iter().enumerate() {
+                    self.visit(block, &format!("{}[{}]", path, index));
+                }
+            }
+            serde_json::Value::Object(block) => {
+                let source = block.get("source");
+                let source_type = source.and_then(|s| s.get("type")).and_then(|t| t.as_str());
+                match block.get("type").and_then(|t| t.as_str()) {
+                    Some("image") => {
+                        self.images += 1;
+                        let data_len = source
+                            .and_then(|s| s.get("data"))
+                            .and_then(|d| d.as_str())
+                            .map_or(0, str::len);
+                        if source_type == Some("base64") && data_len > limits::MAX_IMAGE_BYTES {
+                            self.oversized_images.push((path.to_string(), data_len));
+                        }
+                    }
+                    Some("document") => {
+                        let media_type = source
+                            .and_then(|s| s.get("media_type"))
+                            .and_then(|m| m.as_str());
+                        let is_pdf = source_type == Some("url")
+                            || (source_type == Some("base64")
+                                && media_type == Some("application/pdf"));
+                        if is_pdf {
+                            self.pdfs += 1;
+                        }
+                    }
+                    _ => {}
+                }
+                if let Some(content) = block.get("content") {
+                    self.visit(content, &format!("{}.content", path));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Serialized JSON length of `value`, without buffering it
+pub(crate) fn serialized_len(value: &impl Serialize) -> usize {
+    struct Counter(usize);
+
+    impl std::io::Write for Counter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0 += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut counter = Counter(0);
+    match serde_json::to_writer(&mut counter, value) {
+        Ok(()) => counter.0,
+        Err(_) => 0,
+    }
+}
+
+/// `bytes` as megabytes, with the exact count for precise error messages
+pub(crate) fn format_bytes(bytes: usize) -> String {
+    format!(
+        "{:.1} MB ({} bytes)",
+        bytes as f64 / (1024.0 * 1024.0),
+        bytes
+    )
+}
+
+/// Snapshot of a builder's progress, returned by [`FluentBuilder::inspect`]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct BuilderState {
+    /// Request fields given a non-default value, by their API name
+    pub fields_set: Vec<String>,
+    /// Rough input-token estimate (see [`MessageRequest::estimated_input_tokens`])
+    pub estimated_tokens: u32,
+    /// Problems that would make `build_validated` fail right now
+    pub warnings: Vec<String>,
+}
+
+impl BuilderState {
+    /// Describe a single message request
+    pub fn for_request(request: &MessageRequest) -> Self {
+        Self {
+            fields_set: Self::fields_set(request),
+            estimated_tokens: request.estimated_input_tokens(),
+            warnings: ValidationUtils::request_issues(request, "MessageRequest"),
+        }
+    }
+
+    /// Whether `build_validated` would currently succeed
+    pub fn is_valid(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    /// Top-level fields of `request` whose serialized value differs from a
+    /// fresh [`MessageRequest::new`]
+    pub(crate) fn fields_set(request: &MessageRequest) -> Vec<String> {
+        let (Ok(serde_json::Value::Object(current)), Ok(serde_json::Value::Object(default))) = (
+            serde_json::to_value(request),
+            serde_json::to_value(MessageRequest::new()),
+        ) else {
+            return Vec::new();
+        };
+        current
+            .into_iter()
+            .filter(|(key, value)| default.get(key) != Some(value))
+            .map(|(key, _)| key)
+            .collect()
+    }
 }
 
 /// Trait for builders that can be validated before building
@@ -94,10 +336,9 @@ pub trait ValidatedBuilder<T> {
 
 /// Trait for builders that support fluent configuration
 pub trait FluentBuilder {
-    /// Get the current state for inspection (optional, returns None by default)
-    fn inspect(&self) -> Option<&dyn std::fmt::Debug> {
-        None
-    }
+    /// Summarize what has been configured so far and what would fail
+    /// validation
+    fn inspect(&self) -> BuilderState;
 }
 
 /// Preset configurations for consistent parameter combinations
@@ -146,6 +387,46 @@ impl PresetConfig {
     }
 }
 
+/// Sampling profile for reproducible output, e.g. in snapshot tests.
+///
+/// Applying it sets `temperature` to 0, clears `top_p`, `top_k` and
+/// extended thinking (which requires a temperature of 1), and, when
+/// [`with_stop_sequences`](Self::with_stop_sequences) pinned any, replaces
+/// the request's stop sequences with them. Greedy sampling makes replies as
+/// repeatable as the API allows, though not byte-for-byte guaranteed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Deterministic {
+    /// Stop sequences every request gets, `None` to keep the request's own
+    pub stop_sequences: Option<Vec<String>>,
+}
+
+impl Deterministic {
+    /// Greedy sampling, keeping each request's stop sequences
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace every request's stop sequences with `stops`
+    pub fn with_stop_sequences(
+        mut self,
+        stops: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.stop_sequences = Some(stops.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Rewrite `request`'s sampling parameters
+    pub fn apply(&self, request: &mut MessageRequest) {
+        request.temperature = Some(0.0);
+        request.top_p = None;
+        request.top_k = None;
+        request.thinking = None;
+        if let Some(stops) = &self.stop_sequences {
+            request.stop_sequences = (!stops.is_empty()).then(|| stops.clone());
+        }
+    }
+}
+
 /// Trait for builders that support parameter configuration
 pub trait ParameterBuilder: Sized {
     fn temperature(self, temperature: f32) -> Self;
@@ -280,6 +561,30 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_deterministic_profile() {
+        let mut request = MessageRequest::new()
+            .temperature(0.8)
+            .top_p(0.9)
+            .top_k(40)
+            .thinking(2048);
+        request.stop_sequences = Some(vec!["END".to_string()]);
+        Deterministic::new().apply(&mut request);
+        assert_eq!(request.temperature, Some(0.0));
+        assert_eq!((request.top_p, request.top_k), (None, None));
+        assert!(request.thinking.is_none());
+        assert_eq!(request.stop_sequences, Some(vec!["END".to_string()]));
+
+        Deterministic::new()
+            .with_stop_sequences(["\n\nHuman:"])
+            .apply(&mut request);
+        assert_eq!(request.stop_sequences, Some(vec!["\n\nHuman:".to_string()]));
+        Deterministic::new()
+            .with_stop_sequences(Vec::<String>::new())
+            .apply(&mut request);
+        assert_eq!(request.stop_sequences, None);
+    }
+
     #[test]
     fn test_parameter_builder_presets() {
         let builder = MockBuilder::default().creative();
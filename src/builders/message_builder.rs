@@ -1,10 +1,13 @@
 //! Builder for constructing message requests
 
-use crate::builders::common::{FluentBuilder, ParameterBuilder, ValidatedBuilder, ValidationUtils};
+use crate::builders::common::{
+    BuilderState, Deterministic, FluentBuilder, ParameterBuilder, ValidatedBuilder, ValidationUtils,
+};
 use crate::models::{
     common::{ContentBlock, DocumentSource, ImageSource, Metadata, Role, Tool, ToolChoice},
     message::{Message, MessageRequest, OutputConfig, OutputEffort, ThinkingConfig},
 };
+use crate::prompt_cache::CacheTtl;
 use std::path::Path;
 
 /// Builder for constructing message requests with a fluent API
@@ -50,6 +53,26 @@ impl MessageBuilder {
         self
     }
 
+    /// Set a system prompt as a single cached text block with a chosen
+    /// cache lifetime
+    pub fn system_cached_with_ttl(mut self, system: impl Into<String>, ttl: CacheTtl) -> Self {
+        self.request = self.request.system_cached_with_ttl(system, ttl);
+        self
+    }
+
+    /// Put a cache breakpoint at the end of the last user message added so far
+    pub fn cache_last_user_message(mut self) -> Self {
+        self.request = self.request.cache_last_user_message();
+        self
+    }
+
+    /// [`cache_last_user_message`](Self::cache_last_user_message) with a
+    /// chosen cache lifetime
+    pub fn cache_last_user_message_with_ttl(mut self, ttl: CacheTtl) -> Self {
+        self.request = self.request.cache_last_user_message_with_ttl(ttl);
+        self
+    }
+
     /// Set a structured system prompt from cacheable text blocks
     pub fn system_blocks(mut self, blocks: Vec<crate::models::message::SystemBlock>) -> Self {
         self.request = self.request.system_blocks(blocks);
@@ -88,6 +111,13 @@ impl MessageBuilder {
         self
     }
 
+    /// Sample greedily with the [`Deterministic`] preset, for reproducible
+    /// replies. Disables extended thinking set before this call.
+    pub fn deterministic(mut self) -> Self {
+        Deterministic::new().apply(&mut self.request);
+        self
+    }
+
     /// Enable streaming
     pub fn stream(mut self) -> Self {
         self.request.stream = Some(true);
@@ -484,33 +514,21 @@ impl MessageBuilder {
 
     /// Build and validate the message request
     pub fn build_validated(self) -> Result<MessageRequest, crate::error::AnthropicError> {
-        let request = self.request;
-
-        // Use common validation utilities
-        ValidationUtils::validate_messages_not_empty(request.messages.len(), "MessageRequest")?;
-        ValidationUtils::validate_max_tokens(request.max_tokens, "MessageRequest")?;
-
-        if let Some(temp) = request.temperature {
-            ValidationUtils::validate_temperature(temp)?;
-        }
-
-        if let Some(top_p) = request.top_p {
-            ValidationUtils::validate_top_p(top_p)?;
-        }
-
-        // Validate Claude 4 specific constraints
-        ValidationUtils::validate_claude_4_constraints(
-            &request.model,
-            request.temperature,
-            request.top_p,
-        )?;
-
-        // Validate thinking configuration
-        if let Some(thinking) = &request.thinking {
-            ValidationUtils::validate_thinking_config(&request.model, thinking.budget_tokens)?;
+        let issues = ValidationUtils::request_issues(&self.request, "MessageRequest");
+        if let Some(issue) = issues.into_iter().next() {
+            return Err(crate::error::AnthropicError::invalid_input(issue));
         }
+        Ok(self.request)
+    }
 
-        Ok(request)
+    /// The request as it would be sent right now, for logging.
+    ///
+    /// Base64 image and document payloads are replaced by a short
+    /// `<base64: N bytes>` placeholder so log lines stay readable.
+    pub fn to_partial_json(&self) -> serde_json::Value {
+        let mut value = serde_json::to_value(&self.request).unwrap_or_default();
+        elide_base64(&mut value);
+        value
     }
 
     /// Get a reference to the current request (for inspection)
@@ -538,8 +556,23 @@ impl ValidatedBuilder<MessageRequest> for MessageBuilder {
 }
 
 impl FluentBuilder for MessageBuilder {
-    fn inspect(&self) -> Option<&dyn std::fmt::Debug> {
-        Some(self)
+    fn inspect(&self) -> BuilderState {
+        BuilderState::for_request(&self.request)
+    }
+}
+
+fn elide_base64(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            if map.get("type").and_then(|t| t.as_str()) == Some("base64") {
+                if let Some(serde_json::Value::String(data)) = map.get_mut("data") {
+                    *data = format!("<base64: {} bytes>", data.len());
+                }
+            }
+            map.values_mut().for_each(elide_base64);
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(elide_base64),
+        _ => {}
     }
 }
 
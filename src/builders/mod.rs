@@ -3,12 +3,17 @@
 pub mod batch_builder;
 pub mod common;
 pub mod message_builder;
+pub mod spec;
+pub mod template;
 
 // Re-export builders for convenience
-pub use batch_builder::{BatchBuilder, BatchBuilderWithDefaults};
+pub use batch_builder::{BatchBuilder, BatchBuilderWithDefaults, BatchJoin, JoinedResult};
 pub use message_builder::MessageBuilder;
+pub use spec::{BatchJobSpec, BatchSpec, JobSpec, SpecFormat, SpecOptions};
+pub use template::{PromptTemplate, TemplateVars};
 
 // Re-export common traits and utilities
 pub use common::{
-    FluentBuilder, ParameterBuilder, PresetConfig, ValidatedBuilder, ValidationUtils,
+    BuilderState, Deterministic, FluentBuilder, ParameterBuilder, PresetConfig, ValidatedBuilder,
+    ValidationUtils,
 };
@@ -0,0 +1,311 @@
+//! Declarative job specs loaded from YAML, TOML or JSON
+//!
+//! A [`JobSpec`] describes one Messages request as data: the model, a system
+//! prompt, a [`PromptTemplate`] user prompt with its variables, and request
+//! options. A [`BatchSpec`] shares one template across many jobs, each with
+//! its own custom ID and variables. This lets people who don't write Rust
+//! define the jobs a Rust service runs:
+//!
+//! ```yaml
+//! model: claude-haiku-4-5
+//! system: Answer with one word.
+//! template: "Classify the sentiment of: {review}"
+//! variables:
+//!   review: Great battery life
+//! options:
+//!   max_tokens: 16
+//!   temperature: 0.0
+//! ```
+//!
+//! The format follows the file extension: `.json` always works, `.yaml` /
+//! `.yml` need the `yaml` feature and `.toml` the `toml` feature. Unknown
+//! keys are rejected so that typos don't silently fall back to defaults.
+
+use super::{batch_builder::BatchBuilder, template::PromptTemplate};
+use crate::{
+    error::{AnthropicError, Result},
+    models::{common::Metadata, message::MessageRequest},
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{collections::HashSet, path::Path};
+
+/// Serialization format of a spec file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpecFormat {
+    /// JSON
+    Json,
+    /// YAML (`yaml` feature)
+    Yaml,
+    /// TOML (`toml` feature)
+    Toml,
+}
+
+impl SpecFormat {
+    /// The format implied by `path`'s extension
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("json") => Ok(Self::Json),
+            Some("yaml" | "yml") => Ok(Self::Yaml),
+            Some("toml") => Ok(Self::Toml),
+            _ => Err(AnthropicError::invalid_input(format!(
+                "Cannot tell the spec format of {}: expected a .json, .yaml, .yml or .toml file",
+                path.display()
+            ))),
+        }
+    }
+
+    pub(crate) fn parse<T: DeserializeOwned>(self, text: &str) -> Result<T> {
+        let invalid = |e: &dyn std::fmt::Display| {
+            AnthropicError::invalid_input(format!("Invalid {:?} job spec: {}", self, e))
+        };
+        match self {
+            Self::Json => serde_json::from_str(text).map_err(|e| invalid(&e)),
+            #[cfg(feature = "yaml")]
+            Self::Yaml => serde_yaml_ng::from_str(text).map_err(|e| invalid(&e)),
+            #[cfg(feature = "toml")]
+            Self::Toml => toml::from_str(text).map_err(|e| invalid(&e)),
+            #[cfg(not(feature = "yaml"))]
+            Self::Yaml => Err(AnthropicError::config(
+                "YAML job specs need the `yaml` feature",
+            )),
+            #[cfg(not(feature = "toml"))]
+            Self::Toml => Err(AnthropicError::config(
+                "TOML job specs need the `toml` feature",
+            )),
+        }
+    }
+}
+
+pub(crate) fn read_spec<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let format = SpecFormat::from_path(path)?;
+    let text = std::fs::read_to_string(path).map_err(|e| {
+        AnthropicError::file_error(format!("Failed to read {}: {}", path.display(), e))
+    })?;
+    format
+        .parse(&text)
+        .map_err(|e| e.with_context(path.display().to_string()))
+}
+
+/// Request options a spec can set
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpecOptions {
+    /// Maximum tokens to generate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// Sampling temperature
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Nucleus sampling
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    /// Top-k sampling
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+    /// Stop sequences
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_sequences: Option<Vec<String>>,
+    /// Service tier (`auto` or `standard_only`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service_tier: Option<String>,
+    /// Extended thinking budget in tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thinking_budget: Option<u32>,
+    /// Request metadata
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Metadata>,
+}
+
+impl SpecOptions {
+    /// `request` with these options applied
+    pub fn apply(&self, mut request: MessageRequest) -> MessageRequest {
+        if let Some(max_tokens) = self.max_tokens {
+            request = request.max_tokens(max_tokens);
+        }
+        if let Some(temperature) = self.temperature {
+            request = request.temperature(temperature);
+        }
+        if let Some(top_p) = self.top_p {
+            request = request.top_p(top_p);
+        }
+        if let Some(top_k) = self.top_k {
+            request = request.top_k(top_k);
+        }
+        if let Some(stop_sequences) = &self.stop_sequences {
+            request.stop_sequences = Some(stop_sequences.clone());
+        }
+        if let Some(tier) = &self.service_tier {
+            request = request.service_tier(tier);
+        }
+        if let Some(budget) = self.thinking_budget {
+            request = request.thinking(budget);
+        }
+        if let Some(metadata) = &self.metadata {
+            request = request.metadata(metadata.clone());
+        }
+        request
+    }
+}
+
+fn template_for(
+    model: &Option<String>,
+    system: &Option<String>,
+    template: &str,
+    options: &SpecOptions,
+) -> PromptTemplate {
+    let mut base = MessageRequest::new();
+    if let Some(model) = model {
+        base = base.model(model);
+    }
+    let template = PromptTemplate::new(template).with_base_request(options.apply(base));
+    match system {
+        Some(system) => template.system(system),
+        None => template,
+    }
+}
+
+/// One Messages request described as data
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobSpec {
+    /// Model, the SDK default when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// System prompt, may use `{name}` placeholders
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    /// User prompt with `{name}` placeholders
+    pub template: String,
+    /// Values for the placeholders
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub variables: Map<String, Value>,
+    /// Request options
+    #[serde(default)]
+    pub options: SpecOptions,
+}
+
+impl JobSpec {
+    /// Parse a spec from `text`
+    pub fn from_str(text: &str, format: SpecFormat) -> Result<Self> {
+        format.parse(text)
+    }
+
+    /// Load a spec file, picking the format from its extension
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        read_spec(path.as_ref())
+    }
+
+    /// The spec's prompt template, for rendering it with other variables
+    pub fn prompt_template(&self) -> PromptTemplate {
+        template_for(&self.model, &self.system, &self.template, &self.options)
+    }
+
+    /// Render the request. Fails if a placeholder has no variable.
+    pub fn to_request(&self) -> Result<MessageRequest> {
+        self.prompt_template()
+            .render(&Value::Object(self.variables.clone()))
+    }
+}
+
+/// One job of a [`BatchSpec`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchJobSpec {
+    /// Custom ID of the batch request
+    pub custom_id: String,
+    /// Values for the placeholders, over the batch's shared variables
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub variables: Map<String, Value>,
+}
+
+/// A Message Batch described as data: one template and its options, and the
+/// variables of each job.
+///
+/// ```rust
+/// use threatflux_anthropic_sdk::builders::{BatchSpec, SpecFormat};
+///
+/// let spec = BatchSpec::from_str(
+///     r#"{
+///         "template": "Translate to {language}: {text}",
+///         "variables": {"text": "Good morning"},
+///         "options": {"max_tokens": 64},
+///         "jobs": [
+///             {"custom_id": "fr", "variables": {"language": "French"}},
+///             {"custom_id": "de", "variables": {"language": "German"}}
+///         ]
+///     }"#,
+///     SpecFormat::Json,
+/// )
+/// .unwrap();
+/// let batch = spec.to_batch().unwrap().build();
+/// assert_eq!(batch.requests.len(), 2);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchSpec {
+    /// Model, the SDK default when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// System prompt, may use `{name}` placeholders
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    /// User prompt with `{name}` placeholders
+    pub template: String,
+    /// Variables shared by every job
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub variables: Map<String, Value>,
+    /// Request options for every job
+    #[serde(default)]
+    pub options: SpecOptions,
+    /// The jobs
+    pub jobs: Vec<BatchJobSpec>,
+}
+
+impl BatchSpec {
+    /// Parse a spec from `text`
+    pub fn from_str(text: &str, format: SpecFormat) -> Result<Self> {
+        format.parse(text)
+    }
+
+    /// Load a spec file, picking the format from its extension
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        read_spec(path.as_ref())
+    }
+
+    /// Render every job's request into a [`BatchBuilder`]. Fails if a job
+    /// lacks a variable the template uses or two jobs share a custom ID.
+    pub fn to_batch(&self) -> Result<BatchBuilder> {
+        let template = template_for(&self.model, &self.system, &self.template, &self.options);
+        let mut seen = HashSet::new();
+        let mut batch = BatchBuilder::new();
+        for job in &self.jobs {
+            if !seen.insert(job.custom_id.as_str()) {
+                return Err(AnthropicError::invalid_input(format!(
+                    "Duplicate custom_id found: {}",
+                    job.custom_id
+                )));
+            }
+            let mut variables = self.variables.clone();
+            variables.extend(job.variables.clone());
+            let request = template
+                .render(&Value::Object(variables))
+                .map_err(|e| e.with_context(format!("Job {}", job.custom_id)))?;
+            batch = batch.add_request(job.custom_id.clone(), request);
+        }
+        Ok(batch)
+    }
+}
+
+impl MessageRequest {
+    /// Load a [`JobSpec`] file and render its request, picking the format
+    /// from the extension (`.json`, `.yaml`/`.yml` or `.toml`)
+    pub fn from_spec_file(path: impl AsRef<Path>) -> Result<Self> {
+        JobSpec::from_file(path)?.to_request()
+    }
+}
@@ -0,0 +1,260 @@
+//! Prompt templates rendered from per-row variables
+
+use crate::error::{AnthropicError, Result};
+use crate::models::message::MessageRequest;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::BuildHasher;
+
+/// A source of template variables, typically one row of a dataset.
+pub trait TemplateVars {
+    /// Value of the variable `name`, if the row has it
+    fn var(&self, name: &str) -> Option<String>;
+}
+
+impl<V: ToString, S: BuildHasher> TemplateVars for HashMap<String, V, S> {
+    fn var(&self, name: &str) -> Option<String> {
+        self.get(name).map(ToString::to_string)
+    }
+}
+
+impl<V: ToString, S: BuildHasher> TemplateVars for HashMap<&str, V, S> {
+    fn var(&self, name: &str) -> Option<String> {
+        self.get(name).map(ToString::to_string)
+    }
+}
+
+impl<V: ToString> TemplateVars for BTreeMap<String, V> {
+    fn var(&self, name: &str) -> Option<String> {
+        self.get(name).map(ToString::to_string)
+    }
+}
+
+impl<V: ToString> TemplateVars for BTreeMap<&str, V> {
+    fn var(&self, name: &str) -> Option<String> {
+        self.get(name).map(ToString::to_string)
+    }
+}
+
+/// JSON objects: strings are inserted as-is, other values as JSON text.
+impl TemplateVars for serde_json::Value {
+    fn var(&self, name: &str) -> Option<String> {
+        self.get(name).map(|value| match value {
+            serde_json::Value::String(text) => text.clone(),
+            other => other.to_string(),
+        })
+    }
+}
+
+impl<T: TemplateVars + ?Sized> TemplateVars for &T {
+    fn var(&self, name: &str) -> Option<String> {
+        (**self).var(name)
+    }
+}
+
+/// A user prompt (and optional system prompt) with `{name}` placeholders,
+/// rendered into a [`MessageRequest`] per row.
+///
+/// Placeholders use the same `{name}` syntax as
+/// [`BatchBuilder::add_from_template`](super::BatchBuilder::add_from_template);
+/// write `{{` and `}}` for literal braces. Rendering fails if a row lacks a
+/// variable the template uses.
+///
+/// ```rust
+/// use std::collections::HashMap;
+/// use threatflux_anthropic_sdk::builders::PromptTemplate;
+///
+/// let template = PromptTemplate::new("Classify the sentiment of: {review}")
+///     .system("Answer with one word.")
+///     .max_tokens(16);
+/// let row = HashMap::from([("review", "Great battery life")]);
+/// let request = template.render(&row).unwrap();
+/// assert_eq!(request.max_tokens, 16);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PromptTemplate {
+    user: String,
+    system: Option<String>,
+    base: MessageRequest,
+}
+
+impl PromptTemplate {
+    /// Template for the user turn
+    pub fn new(user: impl Into<String>) -> Self {
+        Self {
+            user: user.into(),
+            system: None,
+            base: MessageRequest::new(),
+        }
+    }
+
+    /// Template for the system prompt
+    pub fn system(mut self, system: impl Into<String>) -> Self {
+        self.system = Some(system.into());
+        self
+    }
+
+    /// Model for every rendered request
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.base = self.base.model(model);
+        self
+    }
+
+    /// Maximum tokens for every rendered request
+    pub fn max_tokens(mut self, max_tokens: u32) -> Self {
+        self.base = self.base.max_tokens(max_tokens);
+        self
+    }
+
+    /// Start every rendered request from `base` (model, sampling, tools, ...).
+    ///
+    /// Its messages are kept ahead of the rendered user turn, and its system
+    /// prompt is replaced when the template has one.
+    pub fn with_base_request(mut self, base: MessageRequest) -> Self {
+        self.base = base;
+        self
+    }
+
+    /// Names of the variables the template uses, in order of first use
+    pub fn variables(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        for text in std::iter::once(&self.user).chain(&self.system) {
+            for segment in parse(text).unwrap_or_default() {
+                if let Segment::Var(name) = segment {
+                    if !names.iter().any(|n| n == name) {
+                        names.push(name.to_string());
+                    }
+                }
+            }
+        }
+        names
+    }
+
+    /// Render the request for one row
+    pub fn render(&self, row: &impl TemplateVars) -> Result<MessageRequest> {
+        let mut request = self.base.clone();
+        if let Some(system) = &self.system {
+            request = request.system(render_text(system, row)?);
+        }
+        Ok(request.add_user_message(render_text(&self.user, row)?))
+    }
+}
+
+enum Segment<'a> {
+    Literal(&'a str),
+    Var(&'a str),
+}
+
+fn parse(template: &str) -> Result<Vec<Segment<'_>>> {
+    let mut segments = Vec::new();
+    let mut rest = template;
+    while let Some(pos) = rest.find(['{', '}']) {
+        segments.push(Segment::Literal(&rest[..pos]));
+        let tail = &rest[pos..];
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            segments.push(Segment::Literal(&tail[..1]));
+            rest = &tail[2..];
+        } else if tail.starts_with('}') {
+            return Err(AnthropicError::invalid_input(format!(
+                "Unmatched '}}' in prompt template at byte {}",
+                template.len() - tail.len()
+            )));
+        } else {
+            let end = tail
+                .find('}')
+                .ok_or_else(|| AnthropicError::invalid_input("Unclosed '{' in prompt template"))?;
+            let name = tail[1..end].trim();
+            if name.is_empty() || name.contains('{') {
+                return Err(AnthropicError::invalid_input(format!(
+                    "Invalid placeholder '{}' in prompt template",
+                    &tail[..=end]
+                )));
+            }
+            segments.push(Segment::Var(name));
+            rest = &tail[end + 1..];
+        }
+    }
+    segments.push(Segment::Literal(rest));
+    Ok(segments)
+}
+
+fn render_text(template: &str, row: &impl TemplateVars) -> Result<String> {
+    let mut out = String::with_capacity(template.len());
+    for segment in parse(template)? {
+        match segment {
+            Segment::Literal(text) => out.push_str(text),
+            Segment::Var(name) => {
+                let value = row.var(name).ok_or_else(|| {
+                    AnthropicError::invalid_input(format!(
+                        "Prompt template variable '{}' is missing from the row",
+                        name
+                    ))
+                })?;
+                out.push_str(&value);
+            }
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::common::ContentBlock;
+    use serde_json::json;
+
+    fn user_text(request: &MessageRequest) -> &str {
+        match &request.messages.last().unwrap().content[0] {
+            ContentBlock::Text { text, .. } => text,
+            other => panic!("unexpected block {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_render_substitutes_and_escapes() {
+        let template =
+            PromptTemplate::new("Summarize {title} as JSON {{\"summary\": ...}} ({ title })");
+        let request = template
+            .render(&json!({"title": "Dune", "year": 1965}))
+            .unwrap();
+        assert_eq!(
+            user_text(&request),
+            "Summarize Dune as JSON {\"summary\": ...} (Dune)"
+        );
+        assert_eq!(template.variables(), vec!["title"]);
+    }
+
+    #[test]
+    fn test_render_reports_missing_and_malformed() {
+        let template = PromptTemplate::new("{a} and {b}");
+        let err = template.render(&json!({"a": 1})).unwrap_err();
+        assert!(err.to_string().contains("'b'"));
+
+        assert!(PromptTemplate::new("open {a")
+            .render(&json!({"a": 1}))
+            .is_err());
+        assert!(PromptTemplate::new("stray } brace")
+            .render(&json!({}))
+            .is_err());
+        assert!(PromptTemplate::new("empty {}").render(&json!({})).is_err());
+    }
+
+    #[test]
+    fn test_render_uses_base_request_and_system() {
+        let base = MessageRequest::new()
+            .model("claude-haiku-4-5")
+            .temperature(0.0)
+            .add_user_message("Context first");
+        let template = PromptTemplate::new("Translate: {text}")
+            .system("Target language: {lang}")
+            .with_base_request(base);
+        let request = template
+            .render(&HashMap::from([("text", "hello"), ("lang", "French")]))
+            .unwrap();
+
+        assert_eq!(request.model, "claude-haiku-4-5");
+        assert_eq!(request.temperature, Some(0.0));
+        assert_eq!(request.messages.len(), 2);
+        assert_eq!(user_text(&request), "Translate: hello");
+        assert_eq!(template.variables(), vec!["text", "lang"]);
+    }
+}
@@ -51,22 +51,46 @@ use crate::{
         models::ModelsApi,
         skills::SkillsApi,
     },
-    config::Config,
+    config::{Config, SecretKey},
     error::{AnthropicError, Result},
+    models::message::MessageRequest,
+    priority::PriorityContext,
+    request_scope::RequestScope,
+    scope::{Scope, ScopedClient},
     types::{HttpMethod, RequestOptions},
-    utils::{http::HttpClient, retry::RetryClient},
+    user_context::UserContext,
+    utils::{
+        http::{read_error_text, HttpClient, MaybeAccepted},
+        rate_limit::{DetectedLimits, RateLimiter},
+        retry::RetryClient,
+    },
 };
-use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::header::{HeaderMap, HeaderValue, InvalidHeaderValue};
 use serde::de::DeserializeOwned;
-use std::sync::Arc;
+use std::{
+    future::Future,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 use url::Url;
+use zeroize::Zeroizing;
 
 /// Main client for the Anthropic API
 #[derive(Clone)]
 pub struct Client {
     config: Arc<Config>,
+    credentials: Arc<RwLock<Credentials>>,
     http_client: HttpClient,
     retry_client: RetryClient,
+    shadow_client: Option<Arc<Client>>,
+    rate_limiter: RateLimiter,
+}
+
+/// Keys in use, shared by every clone of a client so a rotation reaches
+/// them all at once
+struct Credentials {
+    api_key: Arc<SecretKey>,
+    admin_key: Option<Arc<SecretKey>>,
 }
 
 impl Client {
@@ -88,24 +112,194 @@ impl Client {
         let http_client = HttpClient::new(config.clone());
         let retry_client = RetryClient::new(config.clone());
 
+        // Mirrored requests to a separate endpoint get their own client so they
+        // never share failover or retry state with production traffic.
+        let shadow_client = match config.shadow.as_ref().and_then(|s| s.endpoint()) {
+            Some(endpoint) => {
+                let mut shadow_config = (*config).clone();
+                shadow_config.base_url = endpoint.clone();
+                shadow_config.failover = None;
+                shadow_config.shadow = None;
+                Some(Arc::new(Self::try_new(shadow_config)?))
+            }
+            None => None,
+        };
+
+        let rate_limiter = match config.rate_limit_tpm {
+            Some((input_tpm, output_tpm)) => RateLimiter::per_second(config.rate_limit_rps)
+                .with_token_limits(input_tpm, output_tpm),
+            None => RateLimiter::per_second(config.rate_limit_rps),
+        };
+
+        let credentials = Arc::new(RwLock::new(Credentials {
+            api_key: Arc::new(config.api_key.clone()),
+            admin_key: config.admin_key.clone().map(Arc::new),
+        }));
+
         Ok(Self {
             config,
+            credentials,
             http_client,
             retry_client,
+            shadow_client,
+            rate_limiter,
         })
     }
 
+    /// Switch to a new API key. Requests started afterwards, from this client
+    /// or any clone of it, use `api_key`; requests already sent are not
+    /// affected.
+    ///
+    /// ```rust,no_run
+    /// # fn example(client: threatflux_anthropic_sdk::Client) -> threatflux_anthropic_sdk::Result<()> {
+    /// let fresh = std::env::var("ANTHROPIC_API_KEY_NEXT").unwrap_or_default();
+    /// client.rotate_api_key(fresh)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn rotate_api_key(&self, api_key: impl Into<SecretKey>) -> Result<()> {
+        let api_key = Arc::new(api_key.into());
+        if api_key.is_empty() {
+            return Err(AnthropicError::config("API key cannot be empty"));
+        }
+        if let Some(shadow) = &self.shadow_client {
+            shadow.rotate_api_key(SecretKey::clone(&api_key))?;
+        }
+        self.credentials.write().unwrap().api_key = api_key;
+        Ok(())
+    }
+
+    /// Switch to a new admin key, as [`rotate_api_key`](Self::rotate_api_key)
+    /// does for the API key
+    pub fn rotate_admin_key(&self, admin_key: impl Into<SecretKey>) -> Result<()> {
+        let admin_key = Arc::new(admin_key.into());
+        if admin_key.is_empty() {
+            return Err(AnthropicError::config("Admin key cannot be empty"));
+        }
+        self.credentials.write().unwrap().admin_key = Some(admin_key);
+        Ok(())
+    }
+
+    /// The API key currently in use
+    pub(crate) fn api_key(&self) -> Arc<SecretKey> {
+        Arc::clone(&self.credentials.read().unwrap().api_key)
+    }
+
+    /// The admin key currently in use, if any
+    fn admin_key(&self) -> Option<Arc<SecretKey>> {
+        self.credentials.read().unwrap().admin_key.clone()
+    }
+
     /// Create a client from environment variables
     pub fn from_env() -> Result<Self> {
         let config = Config::from_env()?;
         Self::try_new(config)
     }
 
+    /// Create a client whose rate limits are sized from the organization's
+    /// actual limits: probe them with [`detect_rate_limits`](Self::detect_rate_limits)
+    /// and apply `fraction` (e.g. `0.8`) of them with
+    /// [`Config::with_detected_rate_limits`]. When the API reports no
+    /// limits, the client keeps `config`'s.
+    ///
+    /// ```rust,no_run
+    /// use threatflux_anthropic_sdk::{Client, Config};
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = Client::warm_start(Config::from_env()?, 0.8).await?;
+    /// println!("pacing at {} requests/s", client.config().rate_limit_rps);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn warm_start(config: Config, fraction: f32) -> Result<Self> {
+        let limits = Self::try_new(config.clone())?.detect_rate_limits().await?;
+        if limits.is_empty() {
+            tracing::warn!(
+                "No rate limit headers in the probe response; keeping configured limits"
+            );
+            return Self::try_new(config);
+        }
+        Self::try_new(config.with_detected_rate_limits(&limits, fraction))
+    }
+
+    /// Read the organization's current per-minute limits from the rate limit
+    /// headers of a probe request.
+    ///
+    /// The probe is a streaming Messages request for one output token with
+    /// the default model, dropped as soon as the headers arrive, so it costs
+    /// a handful of tokens. A `429` still reports the limits and is not
+    /// treated as an error.
+    pub async fn detect_rate_limits(&self) -> Result<DetectedLimits> {
+        let probe = MessageRequest::new()
+            .model(&self.config.default_model)
+            .max_tokens(1)
+            .add_user_message("Hi")
+            .stream(true);
+        let response = self
+            .request_stream(
+                HttpMethod::Post,
+                "/messages",
+                Some(serde_json::to_value(&probe)?),
+                None,
+            )
+            .await?;
+        let limits = DetectedLimits::from_headers(response.headers());
+        let status = response.status();
+        if limits.is_empty() && !status.is_success() {
+            let error_text = read_error_text(response, &self.config.response_limits)
+                .await
+                .unwrap_or_default();
+            return Err(AnthropicError::api_error(status.as_u16(), error_text, None)
+                .with_context("Rate limit probe"));
+        }
+        Ok(limits)
+    }
+
     /// Get the configuration
     pub fn config(&self) -> &Config {
         &self.config
     }
 
+    /// The limiter enforcing [`Config::rate_limit_rps`] (once
+    /// [`Config::pace_rate_limit_rps`] is set) and [`Config::rate_limit_tpm`]
+    /// on Messages requests, built from the final configuration and shared by
+    /// every clone of this client
+    pub fn rate_limiter(&self) -> &RateLimiter {
+        &self.rate_limiter
+    }
+
+    /// Receive the non-fatal [`Warning`](crate::warnings::Warning)s this
+    /// client (and every client sharing its config) reports from now on
+    pub fn warnings(&self) -> tokio::sync::broadcast::Receiver<crate::warnings::Warning> {
+        self.config.warnings.subscribe()
+    }
+
+    /// A handle restricted to the endpoint groups allowed by `scope`
+    /// (see [`crate::scope`])
+    pub fn scoped<S: Scope>(&self, _scope: S) -> ScopedClient<S> {
+        ScopedClient::new(self.clone())
+    }
+
+    /// Run `body` with a [`RequestScope`] for spawning concurrent requests.
+    ///
+    /// Returns once `body` and every task it spawned have finished; dropping
+    /// the returned future cancels them all (see [`crate::request_scope`]).
+    pub async fn scope<F, Fut, T>(&self, body: F) -> T
+    where
+        F: FnOnce(RequestScope) -> Fut,
+        Fut: Future<Output = T>,
+    {
+        RequestScope::run(self.clone(), body).await
+    }
+
+    /// Client used for mirrored (shadow) requests
+    pub(crate) fn shadow_client(&self) -> Client {
+        match &self.shadow_client {
+            Some(client) => (**client).clone(),
+            None => self.clone(),
+        }
+    }
+
     /// Access the Messages API
     pub fn messages(&self) -> MessagesApi {
         MessagesApi::new(self.clone())
@@ -168,7 +362,7 @@ impl Client {
 
     /// Access the Admin API (requires admin key)
     pub fn admin(&self) -> Result<AdminApi> {
-        if self.config.admin_key.is_none() {
+        if self.admin_key().is_none() {
             return Err(AnthropicError::auth(
                 "Admin key is required for admin operations",
             ));
@@ -189,20 +383,52 @@ impl Client {
     {
         let url = self.build_url(path)?;
         let headers = self.build_headers(&options)?;
-        let timeout = options
-            .as_ref()
-            .and_then(|o| o.timeout)
-            .unwrap_or(self.config.timeout);
+        bounded(&options, async {
+            self.dispatch(&options).await?;
+            let timeout = self.request_timeout(&options)?;
+
+            if options.as_ref().map(|o| o.no_retry).unwrap_or(false) {
+                self.http_client
+                    .request(method, &url, body, headers, timeout)
+                    .await
+            } else {
+                self.retry_client
+                    .request(method, &url, body, headers, timeout)
+                    .await
+            }
+        })
+        .await
+    }
 
-        if options.as_ref().map(|o| o.no_retry).unwrap_or(false) {
-            self.http_client
-                .request(method, &url, body, headers, timeout)
-                .await
-        } else {
-            self.retry_client
-                .request(method, &url, body, headers, timeout)
-                .await
-        }
+    /// Make a raw HTTP request the API may answer with `202 Accepted` before
+    /// the resource is ready
+    pub async fn request_accepting<T>(
+        &self,
+        method: HttpMethod,
+        path: &str,
+        body: Option<serde_json::Value>,
+        options: Option<RequestOptions>,
+    ) -> Result<MaybeAccepted<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let url = self.build_url(path)?;
+        let headers = self.build_headers(&options)?;
+        bounded(&options, async {
+            self.dispatch(&options).await?;
+            let timeout = self.request_timeout(&options)?;
+
+            if options.as_ref().map(|o| o.no_retry).unwrap_or(false) {
+                self.http_client
+                    .request_accepting(method, &url, body, headers, timeout)
+                    .await
+            } else {
+                self.retry_client
+                    .request_accepting(method, &url, body, headers, timeout)
+                    .await
+            }
+        })
+        .await
     }
 
     /// Make a raw HTTP request to Admin API endpoints using admin authentication.
@@ -218,20 +444,21 @@ impl Client {
     {
         let url = self.build_url(path)?;
         let headers = self.build_admin_headers(&options)?;
-        let timeout = options
-            .as_ref()
-            .and_then(|o| o.timeout)
-            .unwrap_or(self.config.timeout);
-
-        if options.as_ref().map(|o| o.no_retry).unwrap_or(false) {
-            self.http_client
-                .request(method, &url, body, headers, timeout)
-                .await
-        } else {
-            self.retry_client
-                .request(method, &url, body, headers, timeout)
-                .await
-        }
+        bounded(&options, async {
+            self.dispatch(&options).await?;
+            let timeout = self.request_timeout(&options)?;
+
+            if options.as_ref().map(|o| o.no_retry).unwrap_or(false) {
+                self.http_client
+                    .request(method, &url, body, headers, timeout)
+                    .await
+            } else {
+                self.retry_client
+                    .request(method, &url, body, headers, timeout)
+                    .await
+            }
+        })
+        .await
     }
 
     /// Make a streaming request
@@ -244,14 +471,49 @@ impl Client {
     ) -> Result<reqwest::Response> {
         let url = self.build_url(path)?;
         let headers = self.build_headers(&options)?;
+        bounded(&options, async {
+            self.dispatch(&options).await?;
+            let timeout = self.request_timeout(&options)?;
+
+            self.http_client
+                .request_stream(method, &url, body, headers, timeout)
+                .await
+        })
+        .await
+    }
+
+    /// Wait for the request's turn in the configured dispatch queue, giving
+    /// up at its deadline
+    async fn dispatch(&self, options: &Option<RequestOptions>) -> Result<()> {
+        let Some(queue) = self
+            .config
+            .dispatch_queue
+            .as_ref()
+            .filter(|_| self.config.enable_rate_limiting)
+        else {
+            return Ok(());
+        };
+        let context = PriorityContext::resolve(options);
+        let admit = queue.admit(context.priority.unwrap_or_default());
+        match context.remaining() {
+            Some(remaining) => tokio::time::timeout(remaining, admit).await.map_err(|_| {
+                AnthropicError::Timeout(remaining)
+                    .with_context("Request deadline passed while queued")
+            }),
+            None => {
+                admit.await;
+                Ok(())
+            }
+        }
+    }
+
+    /// The request's timeout, capped by its (possibly ambient) deadline
+    fn request_timeout(&self, options: &Option<RequestOptions>) -> Result<Duration> {
         let timeout = options
             .as_ref()
             .and_then(|o| o.timeout)
             .unwrap_or(self.config.timeout);
-
-        self.http_client
-            .request_stream(method, &url, body, headers, timeout)
-            .await
+        PriorityContext::resolve(options).timeout(timeout)
     }
 
     /// Build the full URL for an API endpoint
@@ -273,17 +535,18 @@ impl Client {
 
         // Add authentication header. Anthropic API keys (sk-ant-...) require the
         // `x-api-key` header; OAuth bearer tokens use `Authorization: Bearer ...`.
-        if self.config.api_key.starts_with("sk-ant-") {
+        let api_key = self.api_key();
+        if api_key.expose().starts_with("sk-ant-") {
             headers.insert(
                 "x-api-key",
-                HeaderValue::from_str(&self.config.api_key)
+                secret_header(api_key.expose())
                     .map_err(|e| Self::config_error("Invalid API key header", e))?,
             );
         } else {
-            let auth_value = format!("Bearer {}", self.config.api_key);
+            let auth_value = Zeroizing::new(format!("Bearer {}", api_key.expose()));
             headers.insert(
                 "Authorization",
-                HeaderValue::from_str(&auth_value)
+                secret_header(&auth_value)
                     .map_err(|e| Self::config_error("Invalid auth header", e))?,
             );
         }
@@ -294,10 +557,34 @@ impl Client {
         // Add user agent
         headers.insert(
             "User-Agent",
-            HeaderValue::from_str(&self.config.user_agent)
+            HeaderValue::from_str(&self.config.full_user_agent())
                 .map_err(|e| Self::config_error("Invalid user agent", e))?,
         );
 
+        // Add runtime metadata headers
+        for (name, value) in self.config.telemetry_headers() {
+            headers.insert(
+                name,
+                HeaderValue::from_str(&value)
+                    .map_err(|e| Self::config_error("Invalid telemetry header", e))?,
+            );
+        }
+
+        // Forward the end-user context when configured
+        if let Some(names) = &self.config.user_context_headers {
+            if let Some(context) = UserContext::resolve(options) {
+                for (name, value) in names.pairs(&context) {
+                    let header_name = reqwest::header::HeaderName::from_bytes(name.as_bytes())
+                        .map_err(|e| Self::config_error("Invalid user context header name", e))?;
+                    headers.insert(
+                        header_name,
+                        HeaderValue::from_str(value)
+                            .map_err(|e| Self::config_error("Invalid user context header", e))?,
+                    );
+                }
+            }
+        }
+
         // Add content type for JSON requests
         headers.insert("Content-Type", HeaderValue::from_static("application/json"));
 
@@ -363,17 +650,50 @@ impl Client {
 
         headers.remove("Authorization");
 
-        let admin_key =
-            self.config.admin_key.as_ref().ok_or_else(|| {
-                AnthropicError::auth("Admin key is required for admin operations")
-            })?;
+        let admin_key = self
+            .admin_key()
+            .ok_or_else(|| AnthropicError::auth("Admin key is required for admin operations"))?;
 
         headers.insert(
             "x-api-key",
-            HeaderValue::from_str(admin_key)
+            secret_header(admin_key.expose())
                 .map_err(|e| Self::config_error("Invalid admin API key header", e))?,
         );
 
         Ok(headers)
     }
 }
+
+/// Run `request`, failing with [`AnthropicError::Cancelled`] if the options'
+/// cancellation token fires first, or with [`AnthropicError::Timeout`] once the
+/// (possibly ambient) deadline passes. The deadline covers every retry and
+/// backoff sleep, not only a single attempt.
+pub(crate) async fn bounded<T>(
+    options: &Option<RequestOptions>,
+    request: impl std::future::Future<Output = Result<T>>,
+) -> Result<T> {
+    let request = async {
+        match PriorityContext::resolve(options).remaining() {
+            Some(remaining) => tokio::time::timeout(remaining, request)
+                .await
+                .unwrap_or(Err(AnthropicError::Timeout(remaining))),
+            None => request.await,
+        }
+    };
+    match options.as_ref().and_then(|o| o.cancel.as_ref()) {
+        Some(token) => tokio::select! {
+            biased;
+            _ = token.cancelled() => Err(AnthropicError::Cancelled),
+            result = request => result,
+        },
+        None => request.await,
+    }
+}
+
+/// A header value carrying a credential, marked sensitive so it stays out of
+/// `Debug` output
+pub(crate) fn secret_header(value: &str) -> std::result::Result<HeaderValue, InvalidHeaderValue> {
+    let mut header = HeaderValue::from_str(value)?;
+    header.set_sensitive(true);
+    Ok(header)
+}
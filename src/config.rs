@@ -1,8 +1,29 @@
 //! Configuration for the Anthropic API client
 
-use crate::error::{AnthropicError, Result};
-use std::time::Duration;
+use crate::{
+    builders::Deterministic,
+    error::{AnthropicError, Result},
+    metrics::MetricsRecorder,
+    streaming::MalformedEventPolicy,
+    user_context::UserContextHeaders,
+    utils::{
+        concurrency::ModelConcurrencyLimit,
+        dispatch::DispatchQueue,
+        failover::{EndpointFailover, FailoverPolicy},
+        http::ResponseLimits,
+        middleware::Middleware,
+        postprocess::{PostProcessor, PostProcessors},
+        rate_limit::{AdaptiveRateLimiter, DetectedLimits, RateLimitMiddleware},
+        retry::RetryPolicy,
+        shadow::ShadowTraffic,
+        signing::RequestSigner,
+        wire_log::RedactionPolicy,
+    },
+    warnings::Warnings,
+};
+use std::{fmt, sync::Arc, time::Duration};
 use url::Url;
+use zeroize::Zeroizing;
 
 /// Default model to use when none is specified.
 ///
@@ -99,6 +120,26 @@ pub mod models {
         )
     }
 
+    /// Models that accept native PDF `document` blocks.
+    ///
+    /// Unknown or retired ids return `false`; callers can rasterize pages
+    /// instead (see the `pdf-raster` feature).
+    pub fn supports_pdf_input(model: &str) -> bool {
+        matches!(
+            model,
+            FABLE_5
+                | MYTHOS_5
+                | OPUS_4_8
+                | OPUS_4_7
+                | OPUS_4_6
+                | SONNET_4_6
+                | HAIKU_4_5
+                | OPUS_4_5
+                | SONNET_4_5
+                | OPUS_4_1
+        )
+    }
+
     /// Get maximum extended-thinking tokens for a model.
     ///
     /// Returns `None` for adaptive-thinking models, where `budget_tokens` is
@@ -111,6 +152,56 @@ pub mod models {
         None
     }
 
+    /// List prices in USD per million tokens.
+    ///
+    /// Cache writes are billed at 1.25x (5-minute TTL) or 2x (1-hour TTL) the
+    /// input price and cache reads at 0.1x.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct ModelPricing {
+        /// USD per million input tokens
+        pub input_per_mtok: f64,
+        /// USD per million output tokens
+        pub output_per_mtok: f64,
+    }
+
+    impl ModelPricing {
+        /// Create pricing from per-million-token input and output prices
+        pub const fn new(input_per_mtok: f64, output_per_mtok: f64) -> Self {
+            Self {
+                input_per_mtok,
+                output_per_mtok,
+            }
+        }
+
+        /// Cost in USD of the given usage
+        pub fn cost(&self, usage: &crate::models::common::Usage) -> f64 {
+            let (write_5m, write_1h) = match &usage.cache_creation {
+                Some(breakdown) => (
+                    breakdown.ephemeral_5m_input_tokens,
+                    breakdown.ephemeral_1h_input_tokens,
+                ),
+                None => (usage.cache_creation_input_tokens, 0),
+            };
+            let input = usage.input_tokens as f64
+                + write_5m as f64 * 1.25
+                + write_1h as f64 * 2.0
+                + usage.cache_read_input_tokens as f64 * 0.1;
+            (input * self.input_per_mtok + usage.output_tokens as f64 * self.output_per_mtok)
+                / 1_000_000.0
+        }
+    }
+
+    /// Standard list pricing for a model, if known.
+    pub fn pricing(model: &str) -> Option<ModelPricing> {
+        match model {
+            OPUS_4_8 | OPUS_4_7 | OPUS_4_6 | OPUS_4_5 => Some(ModelPricing::new(5.0, 25.0)),
+            OPUS_4_1 => Some(ModelPricing::new(15.0, 75.0)),
+            SONNET_4_6 | SONNET_4_5 => Some(ModelPricing::new(3.0, 15.0)),
+            HAIKU_4_5 => Some(ModelPricing::new(1.0, 5.0)),
+            _ => None,
+        }
+    }
+
     /// Get all current (non-retired) models.
     pub fn all_models() -> &'static [&'static str] {
         &[
@@ -123,15 +214,128 @@ pub mod models {
     pub fn is_valid_model(model: &str) -> bool {
         !model.is_empty() && all_models().contains(&model)
     }
+
+    /// Check if a model is deprecated or already retired.
+    #[allow(deprecated)]
+    pub fn is_deprecated(model: &str) -> bool {
+        matches!(
+            model,
+            OPUS_4_1 | OPUS_4 | SONNET_4 | SONNET_3_7 | HAIKU_3_5 | SONNET_3_5 | OPUS_3
+        )
+    }
+}
+
+/// Documented request limits, checked client-side before sending.
+///
+/// Exceeding these gets an opaque `413` or `400` from the API; the builders'
+/// `build_validated` and [`MessagesApi::create`](crate::api::messages::MessagesApi::create)
+/// report them up front with the offending counts instead.
+pub mod limits {
+    /// Maximum serialized size of a Messages API request (32 MB)
+    pub const MAX_REQUEST_BYTES: usize = 32 * 1024 * 1024;
+
+    /// Maximum serialized size of a whole batch creation request (256 MB)
+    pub const MAX_BATCH_BYTES: usize = 256 * 1024 * 1024;
+
+    /// Maximum number of requests in one batch
+    pub const MAX_BATCH_REQUESTS: usize = 100_000;
+
+    /// Maximum number of messages in one request
+    pub const MAX_MESSAGES: usize = 100_000;
+
+    /// Maximum number of images in one request
+    pub const MAX_IMAGES: usize = 100;
+
+    /// Maximum size of one base64-encoded image (5 MB)
+    pub const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+
+    /// Maximum number of PDF pages in one request.
+    ///
+    /// Page counts are not known client-side, but every PDF document has at
+    /// least one page, so more PDF documents than this is always rejected.
+    pub const MAX_PDF_PAGES: usize = 100;
+}
+
+/// Application identifier appended to the SDK user agent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppInfo {
+    /// Application name
+    pub name: String,
+    /// Application version
+    pub version: String,
+}
+
+/// An API key: hidden from `Debug` output and wiped from memory when dropped
+#[derive(Clone, PartialEq, Eq)]
+pub struct SecretKey(Zeroizing<String>);
+
+impl SecretKey {
+    /// Wrap `key`
+    pub fn new(key: impl Into<String>) -> Self {
+        Self(Zeroizing::new(key.into()))
+    }
+
+    /// The key itself
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether the key is empty
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretKey([REDACTED])")
+    }
+}
+
+impl From<String> for SecretKey {
+    fn from(key: String) -> Self {
+        Self::new(key)
+    }
+}
+
+impl From<&String> for SecretKey {
+    fn from(key: &String) -> Self {
+        Self::new(key.as_str())
+    }
+}
+
+impl From<&str> for SecretKey {
+    fn from(key: &str) -> Self {
+        Self::new(key)
+    }
+}
+
+impl PartialEq<str> for SecretKey {
+    fn eq(&self, other: &str) -> bool {
+        self.expose() == other
+    }
+}
+
+impl PartialEq<&str> for SecretKey {
+    fn eq(&self, other: &&str) -> bool {
+        self.expose() == *other
+    }
+}
+
+impl PartialEq<String> for SecretKey {
+    fn eq(&self, other: &String) -> bool {
+        self.expose() == other
+    }
 }
 
 /// Configuration for the Anthropic API client
 #[derive(Debug, Clone)]
 pub struct Config {
-    /// API key for authentication
-    pub api_key: String,
+    /// API key for authentication; a client started with this config may
+    /// since have switched keys with [`Client::rotate_api_key`](crate::Client::rotate_api_key)
+    pub api_key: SecretKey,
     /// Admin API key for admin operations (optional)
-    pub admin_key: Option<String>,
+    pub admin_key: Option<SecretKey>,
     /// Base URL for the API
     pub base_url: Url,
     /// Request timeout duration
@@ -144,13 +348,75 @@ pub struct Config {
     pub default_model: String,
     /// Enable rate limiting
     pub enable_rate_limiting: bool,
-    /// Rate limit: requests per second
+    /// Rate limit: Messages requests per second, enforced by the limiter
+    /// each [`Client`](crate::Client) builds and shares across its clones
+    /// once [`pace_rate_limit_rps`](Self::pace_rate_limit_rps) is set
     pub rate_limit_rps: u32,
+    /// Pace Messages requests by `rate_limit_rps`; set when it is configured
+    /// explicitly
+    pub pace_rate_limit_rps: bool,
+    /// Input and output tokens-per-minute limits on Messages requests,
+    /// either unlimited when 0; enforced by the limiter each
+    /// [`Client`](crate::Client) builds and shares across its clones
+    pub rate_limit_tpm: Option<(u32, u32)>,
+    /// Per-model (and per-workspace) request rate limit pools for Messages
+    /// requests, shared across clones of this config
+    pub rate_limit_pools: Option<Arc<RateLimitMiddleware>>,
+    /// Request pacing adjusted by every response's rate limit headers,
+    /// shared across clones of this config
+    pub adaptive_rate_limit: Option<AdaptiveRateLimiter>,
+    /// Priority-ordered dispatch of requests under a rate limit, shared
+    /// across clones of this config
+    pub dispatch_queue: Option<DispatchQueue>,
+    /// Application identifier appended to the user agent
+    pub app_info: Option<AppInfo>,
+    /// Send `x-stainless-*` runtime metadata headers
+    pub telemetry_headers: bool,
+    /// Multi-region endpoint failover (shared across clones of this config)
+    pub failover: Option<Arc<EndpointFailover>>,
+    /// Mirror a sample of message requests to another model or endpoint
+    pub shadow: Option<Arc<ShadowTraffic>>,
+    /// Signs every outgoing request (see [`RequestSigner`])
+    pub request_signer: Option<Arc<dyn RequestSigner>>,
+    /// Interceptors run on every request and response (see [`Middleware`])
+    pub middleware: Middleware,
+    /// Processors run in order on every message response
+    pub postprocessors: PostProcessors,
+    /// Receives request, token, retry and stream measurements
+    pub metrics: Option<Arc<dyn MetricsRecorder>>,
+    /// Log full HTTP traffic, masked by this policy (see
+    /// [`wire_log`](crate::utils::wire_log))
+    pub wire_logging: Option<Arc<RedactionPolicy>>,
+    /// Retry count, backoff and circuit breaker; overrides `max_retries`
+    pub retry_policy: Option<RetryPolicy>,
+    /// Per-model caps on concurrent Messages requests, first match wins
+    pub model_concurrency: Vec<ModelConcurrencyLimit>,
+    /// Whether a stream event with unparseable data aborts the stream
+    pub malformed_stream_events: MalformedEventPolicy,
+    /// Fail a message stream after this long without data (pings count)
+    pub stream_idle_timeout: Option<Duration>,
+    /// Send non-streaming message requests with at least this `max_tokens`
+    /// over a stream instead
+    pub auto_stream_threshold: Option<u32>,
+    /// Forward each request's [`UserContext`](crate::user_context::UserContext)
+    /// in these headers
+    pub user_context_headers: Option<UserContextHeaders>,
+    /// Largest response bodies read, per kind of response
+    pub response_limits: ResponseLimits,
+    /// Sampling profile forced onto every message request, for reproducible
+    /// test runs
+    pub deterministic: Option<Deterministic>,
+    /// Channel for non-fatal warnings, shared across clones of this config
+    pub warnings: Warnings,
+    /// Stamp message responses with [`Provenance`](crate::models::Provenance)
+    pub provenance: bool,
+    /// Pick a message request's `service_tier` from its priority
+    pub priority_service_tiers: bool,
 }
 
 impl Config {
     /// Create a new configuration with the given API key
-    pub fn new(api_key: impl Into<String>) -> Result<Self> {
+    pub fn new(api_key: impl Into<SecretKey>) -> Result<Self> {
         let api_key = api_key.into();
         if api_key.is_empty() {
             return Err(AnthropicError::config("API key cannot be empty"));
@@ -166,6 +432,31 @@ impl Config {
             default_model: DEFAULT_MODEL.to_string(),
             enable_rate_limiting: true,
             rate_limit_rps: 50,
+            pace_rate_limit_rps: false,
+            rate_limit_tpm: None,
+            rate_limit_pools: None,
+            adaptive_rate_limit: None,
+            dispatch_queue: None,
+            app_info: None,
+            telemetry_headers: true,
+            failover: None,
+            shadow: None,
+            request_signer: None,
+            middleware: Middleware::default(),
+            postprocessors: PostProcessors::default(),
+            metrics: None,
+            wire_logging: None,
+            retry_policy: None,
+            model_concurrency: Vec::new(),
+            malformed_stream_events: MalformedEventPolicy::Abort,
+            stream_idle_timeout: None,
+            auto_stream_threshold: None,
+            user_context_headers: None,
+            response_limits: ResponseLimits::default(),
+            deterministic: None,
+            warnings: Warnings::default(),
+            provenance: false,
+            priority_service_tiers: false,
         })
     }
 
@@ -173,11 +464,15 @@ impl Config {
     pub fn from_env() -> Result<Self> {
         dotenvy::dotenv().ok(); // Ignore errors if .env file doesn't exist
 
-        let api_key = std::env::var("ANTHROPIC_API_KEY").map_err(|_| {
-            AnthropicError::config("ANTHROPIC_API_KEY environment variable not set")
-        })?;
+        let api_key = std::env::var("ANTHROPIC_API_KEY")
+            .map(SecretKey::new)
+            .map_err(|_| {
+                AnthropicError::config("ANTHROPIC_API_KEY environment variable not set")
+            })?;
 
-        let admin_key = std::env::var("ANTHROPIC_ADMIN_KEY").ok();
+        let admin_key = std::env::var("ANTHROPIC_ADMIN_KEY")
+            .ok()
+            .map(SecretKey::new);
 
         let base_url = match std::env::var("ANTHROPIC_BASE_URL") {
             Ok(url_str) => Url::parse(&url_str)
@@ -204,10 +499,9 @@ impl Config {
             .and_then(|v| v.parse().ok())
             .unwrap_or(true);
 
-        let rate_limit_rps = std::env::var("ANTHROPIC_RATE_LIMIT_RPS")
+        let configured_rps = std::env::var("ANTHROPIC_RATE_LIMIT_RPS")
             .ok()
-            .and_then(|v| v.parse().ok())
-            .unwrap_or(50);
+            .and_then(|v| v.parse().ok());
 
         Ok(Self {
             api_key,
@@ -218,12 +512,44 @@ impl Config {
             user_agent: Self::default_user_agent(),
             default_model,
             enable_rate_limiting,
-            rate_limit_rps,
+            rate_limit_rps: configured_rps.unwrap_or(50),
+            pace_rate_limit_rps: configured_rps.is_some(),
+            rate_limit_tpm: None,
+            rate_limit_pools: None,
+            adaptive_rate_limit: None,
+            dispatch_queue: None,
+            app_info: None,
+            telemetry_headers: true,
+            failover: None,
+            shadow: None,
+            request_signer: None,
+            middleware: Middleware::default(),
+            postprocessors: PostProcessors::default(),
+            metrics: None,
+            wire_logging: None,
+            retry_policy: None,
+            model_concurrency: Vec::new(),
+            malformed_stream_events: MalformedEventPolicy::Abort,
+            stream_idle_timeout: None,
+            auto_stream_threshold: None,
+            user_context_headers: None,
+            response_limits: ResponseLimits::default(),
+            deterministic: None,
+            warnings: Warnings::default(),
+            provenance: false,
+            priority_service_tiers: false,
         })
     }
 
+    /// Send each request's end-user context in `headers`, e.g. for a gateway
+    /// that attributes or routes traffic per user
+    pub fn with_user_context_headers(mut self, headers: UserContextHeaders) -> Self {
+        self.user_context_headers = Some(headers);
+        self
+    }
+
     /// Set the admin API key
-    pub fn with_admin_key(mut self, admin_key: impl Into<String>) -> Self {
+    pub fn with_admin_key(mut self, admin_key: impl Into<SecretKey>) -> Self {
         self.admin_key = Some(admin_key.into());
         self
     }
@@ -246,6 +572,13 @@ impl Config {
         self
     }
 
+    /// Retry with `policy`'s count and backoff, and its circuit breaker if
+    /// it has one, instead of `max_retries` and the default backoff
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
     /// Set the user agent string
     pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
         self.user_agent = user_agent.into();
@@ -264,12 +597,300 @@ impl Config {
         self
     }
 
-    /// Set the rate limit in requests per second
+    /// Pace Messages requests to at most `rps` a second. Applies while
+    /// [`enable_rate_limiting`](Self::enable_rate_limiting) is set.
     pub fn with_rate_limit_rps(mut self, rps: u32) -> Self {
         self.rate_limit_rps = rps;
+        self.pace_rate_limit_rps = true;
+        self
+    }
+
+    /// Limit Messages requests to `input_tpm` input and `output_tpm` output
+    /// tokens per minute, either unlimited when 0.
+    ///
+    /// Each request waits until its estimated input tokens and its
+    /// `max_tokens` fit, reserves them, and gives back what it did not use
+    /// once its usage is known. Applies while
+    /// [`enable_rate_limiting`](Self::enable_rate_limiting) is set.
+    pub fn with_rate_limit_tpm(mut self, input_tpm: u32, output_tpm: u32) -> Self {
+        self.rate_limit_tpm = Some((input_tpm, output_tpm));
+        self
+    }
+
+    /// Size the rate limits at `fraction` (e.g. `0.8`) of `limits` detected
+    /// from the API instead of fixed numbers: requests are paced by an
+    /// [`AdaptiveRateLimiter`] at that share of the requests-per-minute
+    /// limit (which keeps adjusting to later responses' headers), Messages
+    /// requests also by [`rate_limit_rps`](Self::rate_limit_rps) set to
+    /// match, and by tokens per minute when token limits were reported.
+    /// Limits that were not detected are left as they are.
+    pub fn with_detected_rate_limits(mut self, limits: &DetectedLimits, fraction: f32) -> Self {
+        if let Some(requests) = limits.request_config(fraction) {
+            self = self.with_rate_limit_rps((requests.max_requests.get() / 60).max(1));
+            self.adaptive_rate_limit = Some(AdaptiveRateLimiter::new(requests));
+        }
+        let (input_tpm, output_tpm) = limits.token_limits(fraction);
+        if input_tpm > 0 || output_tpm > 0 {
+            self = self.with_rate_limit_tpm(input_tpm, output_tpm);
+        }
+        self
+    }
+
+    /// Pace Messages requests through `pools`, which keeps separate limits
+    /// per model and optionally per workspace header. Applies while
+    /// [`enable_rate_limiting`](Self::enable_rate_limiting) is set; keep
+    /// the returned `Arc` to read stats or change limits at runtime.
+    pub fn with_rate_limit_pools(mut self, pools: RateLimitMiddleware) -> Self {
+        self.rate_limit_pools = Some(Arc::new(pools));
+        self
+    }
+
+    /// Pace every request with `limiter` and feed it the rate limit headers
+    /// of every response, so it slows down as the API's budget runs low.
+    /// Applies while [`enable_rate_limiting`](Self::enable_rate_limiting) is
+    /// set.
+    pub fn with_adaptive_rate_limit(mut self, limiter: AdaptiveRateLimiter) -> Self {
+        self.adaptive_rate_limit = Some(limiter);
+        self
+    }
+
+    /// Send requests through `queue`: once its rate limit is saturated,
+    /// [`RequestOptions::with_priority`](crate::types::RequestOptions::with_priority)
+    /// (or the ambient [`PriorityContext`](crate::priority::PriorityContext))
+    /// decides who goes next, and a request whose deadline passes while
+    /// queued fails with a timeout. Applies while
+    /// [`enable_rate_limiting`](Self::enable_rate_limiting) is set.
+    pub fn with_dispatch_queue(mut self, queue: DispatchQueue) -> Self {
+        self.dispatch_queue = Some(queue);
+        self
+    }
+
+    /// Fail over between base URLs (primary first) on consecutive connection
+    /// errors or 5xx responses, probing the primary periodically to fail back.
+    ///
+    /// The first endpoint becomes the [`base_url`](Self::base_url).
+    pub fn with_failover(
+        mut self,
+        endpoints: impl IntoIterator<Item = Url>,
+        policy: FailoverPolicy,
+    ) -> Self {
+        let endpoints: Vec<Url> = endpoints.into_iter().collect();
+        if let Some(primary) = endpoints.first() {
+            self.base_url = primary.clone();
+        }
+        self.failover = Some(Arc::new(EndpointFailover::new(endpoints, policy)));
+        self
+    }
+
+    /// Mirror a sample of `messages.create` requests in the background
+    /// (see [`ShadowTraffic`])
+    pub fn with_shadow_traffic(mut self, shadow: ShadowTraffic) -> Self {
+        self.shadow = Some(Arc::new(shadow));
+        self
+    }
+
+    /// Sign every outgoing request, e.g. with an HMAC or JWT required by an
+    /// internal gateway
+    pub fn with_request_signer(mut self, signer: impl RequestSigner + 'static) -> Self {
+        self.request_signer = Some(Arc::new(signer));
+        self
+    }
+
+    /// Add request and response interceptors, after any already installed
+    pub fn with_middleware(mut self, middleware: Middleware) -> Self {
+        self.middleware.extend(middleware);
+        self
+    }
+
+    /// Run `processor` on every message response, after those already
+    /// added. Requests can skip it by `name`; see
+    /// [`postprocess`](crate::utils::postprocess).
+    pub fn with_postprocessor(
+        mut self,
+        name: impl Into<String>,
+        processor: impl PostProcessor + 'static,
+    ) -> Self {
+        self.postprocessors.push(name, processor);
+        self
+    }
+
+    /// Report what the client does to `recorder`, e.g.
+    /// [`OtelMetrics`](crate::metrics::OtelMetrics) with the `otel` feature
+    pub fn with_metrics_recorder(mut self, recorder: impl MetricsRecorder + 'static) -> Self {
+        self.metrics = Some(Arc::new(recorder));
+        self
+    }
+
+    /// Log every request and response at DEBUG under the `anthropic::wire`
+    /// target, masked by `policy`. For troubleshooting; it is verbose even
+    /// with content redacted.
+    pub fn with_wire_logging(mut self, policy: RedactionPolicy) -> Self {
+        self.wire_logging = Some(Arc::new(policy));
+        self
+    }
+
+    /// Allow at most `limit` concurrent Messages requests (including open
+    /// streams) for models matching the glob `pattern`, e.g. `"claude-opus-*"`.
+    ///
+    /// Limits are checked in the order they were added; a model matching none
+    /// of them is not limited.
+    pub fn with_model_concurrency(mut self, pattern: impl Into<String>, limit: usize) -> Self {
+        self.model_concurrency
+            .push(ModelConcurrencyLimit::new(pattern, limit));
+        self
+    }
+
+    /// Skip stream events whose data cannot be parsed (logging a warning)
+    /// instead of ending the stream with an error
+    pub fn with_malformed_stream_events(mut self, policy: MalformedEventPolicy) -> Self {
+        self.malformed_stream_events = policy;
+        self
+    }
+
+    /// End message streams with an
+    /// [`AnthropicError::StreamIdle`] error after `timeout` without any
+    /// data. Server `ping` events reset the timer, so long thinking pauses
+    /// are not mistaken for a dead connection.
+    pub fn with_stream_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.stream_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Stream message requests with `max_tokens` of at least `tokens`, even
+    /// when they are made with [`create`](crate::api::messages::MessagesApi::create).
+    ///
+    /// A long generation sent as one plain request can outlast the request
+    /// timeout and idle connections dropped by proxies, so the API recommends
+    /// streaming for large outputs. Callers still get one complete
+    /// [`MessageResponse`](crate::models::message::MessageResponse). Around
+    /// 21,000 tokens, the output that takes ten minutes at typical speeds, is
+    /// a reasonable value.
+    pub fn with_auto_stream_threshold(mut self, tokens: u32) -> Self {
+        self.auto_stream_threshold = Some(tokens);
         self
     }
 
+    /// Cap the size of response bodies the client reads; see
+    /// [`ResponseLimits`] for the defaults
+    pub fn with_response_limits(mut self, limits: ResponseLimits) -> Self {
+        self.response_limits = limits;
+        self
+    }
+
+    /// Rewrite every outgoing message request, batches included, to sample
+    /// with the [`Deterministic`] preset, overriding what the request asked
+    /// for. Meant for snapshot-based tests; `false` turns it back off.
+    pub fn force_deterministic(mut self, enabled: bool) -> Self {
+        self.deterministic = enabled.then(Deterministic::new);
+        self
+    }
+
+    /// [`force_deterministic`](Self::force_deterministic) with a custom
+    /// profile, e.g. one pinning stop sequences
+    pub fn with_deterministic_profile(mut self, profile: Deterministic) -> Self {
+        self.deterministic = Some(profile);
+        self
+    }
+
+    /// Report warnings on `warnings`, e.g. to share one channel between
+    /// configs
+    pub fn with_warnings(mut self, warnings: Warnings) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
+    /// Stamp every returned message response with the model, request hash,
+    /// time and SDK version that produced it
+    pub fn with_provenance(mut self, enabled: bool) -> Self {
+        self.provenance = enabled;
+        self
+    }
+
+    /// Give message requests without an explicit `service_tier` one based on
+    /// their [`RequestPriority`](crate::types::RequestPriority): `"auto"`
+    /// (may use Priority Tier capacity) when high and `"standard_only"` when
+    /// low. Off by default, since it changes billing and capacity routing.
+    pub fn with_priority_service_tiers(mut self, enabled: bool) -> Self {
+        self.priority_service_tiers = enabled;
+        self
+    }
+
+    /// Base URL currently receiving traffic (the active failover endpoint, if any)
+    pub fn active_base_url(&self) -> Url {
+        self.failover
+            .as_ref()
+            .and_then(|f| f.active_endpoint().cloned())
+            .unwrap_or_else(|| self.base_url.clone())
+    }
+
+    /// Identify the calling application in the user agent
+    /// (`<sdk user agent> <name>/<version>`)
+    pub fn with_app_info(mut self, name: impl Into<String>, version: impl Into<String>) -> Self {
+        self.app_info = Some(AppInfo {
+            name: name.into(),
+            version: version.into(),
+        });
+        self
+    }
+
+    /// Enable or disable the `x-stainless-*` runtime metadata headers
+    pub fn with_telemetry_headers(mut self, enabled: bool) -> Self {
+        self.telemetry_headers = enabled;
+        self
+    }
+
+    /// User agent sent on the wire, including the application identifier.
+    pub fn full_user_agent(&self) -> String {
+        match &self.app_info {
+            Some(app) => format!("{} {}/{}", self.user_agent, app.name, app.version),
+            None => self.user_agent.clone(),
+        }
+    }
+
+    /// Runtime metadata headers in the `x-stainless-*` convention used by the
+    /// official SDKs, which some gateways use for routing and analytics.
+    ///
+    /// Returns an empty list when telemetry headers are disabled.
+    pub fn telemetry_headers(&self) -> Vec<(&'static str, String)> {
+        if !self.telemetry_headers {
+            return Vec::new();
+        }
+
+        let os = match std::env::consts::OS {
+            "linux" => "Linux",
+            "macos" => "MacOS",
+            "windows" => "Windows",
+            "freebsd" => "FreeBSD",
+            "openbsd" => "OpenBSD",
+            "android" => "Android",
+            "ios" => "iOS",
+            other => other,
+        };
+        let arch = match std::env::consts::ARCH {
+            "x86_64" => "x64",
+            "x86" => "x32",
+            "aarch64" => "arm64",
+            "arm" => "arm",
+            other => other,
+        };
+
+        vec![
+            ("x-stainless-lang", "rust".to_string()),
+            (
+                "x-stainless-package-version",
+                env!("CARGO_PKG_VERSION").to_string(),
+            ),
+            ("x-stainless-os", os.to_string()),
+            ("x-stainless-arch", arch.to_string()),
+            ("x-stainless-runtime", "rustc".to_string()),
+            (
+                "x-stainless-runtime-version",
+                // Set by the build script
+                env!("RUSTC_VERSION").to_string(),
+            ),
+        ]
+    }
+
     /// Get the default base URL
     fn default_base_url() -> Result<Url> {
         Url::parse("https://api.anthropic.com")
@@ -300,6 +921,21 @@ impl Config {
             return Err(AnthropicError::config("Default model cannot be empty"));
         }
 
+        if let Some(limit) = self.model_concurrency.iter().find(|l| l.limit() == 0) {
+            return Err(AnthropicError::config(format!(
+                "Model concurrency limit for '{}' must be greater than 0",
+                limit.pattern()
+            )));
+        }
+
+        if let Some(failover) = &self.failover {
+            if failover.endpoints().is_empty() {
+                return Err(AnthropicError::config(
+                    "Failover requires at least one endpoint",
+                ));
+            }
+        }
+
         Ok(())
     }
 }
@@ -307,7 +943,7 @@ impl Config {
 impl Default for Config {
     fn default() -> Self {
         Self {
-            api_key: "sk-ant-api03-placeholder".to_string(), // Placeholder key for default config
+            api_key: SecretKey::new("sk-ant-api03-placeholder"), // Placeholder key for default config
             admin_key: None,
             base_url: Url::parse("https://api.anthropic.com").unwrap(),
             timeout: Duration::from_secs(60),
@@ -316,6 +952,31 @@ impl Default for Config {
             default_model: DEFAULT_MODEL.to_string(),
             enable_rate_limiting: true,
             rate_limit_rps: 50,
+            pace_rate_limit_rps: false,
+            rate_limit_tpm: None,
+            rate_limit_pools: None,
+            adaptive_rate_limit: None,
+            dispatch_queue: None,
+            app_info: None,
+            telemetry_headers: true,
+            failover: None,
+            shadow: None,
+            request_signer: None,
+            middleware: Middleware::default(),
+            postprocessors: PostProcessors::default(),
+            metrics: None,
+            wire_logging: None,
+            retry_policy: None,
+            model_concurrency: Vec::new(),
+            malformed_stream_events: MalformedEventPolicy::Abort,
+            stream_idle_timeout: None,
+            auto_stream_threshold: None,
+            user_context_headers: None,
+            response_limits: ResponseLimits::default(),
+            deterministic: None,
+            warnings: Warnings::default(),
+            provenance: false,
+            priority_service_tiers: false,
         }
     }
 }
@@ -0,0 +1,82 @@
+//! Per-turn annotations for review tooling
+//!
+//! Annotations attach labels, scores and reviewer notes to individual
+//! messages of a [`Conversation`](super::Conversation). They are saved with
+//! the conversation but never sent to the API.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// One piece of review data about a turn
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Annotation {
+    /// Free-form tag, e.g. `"hallucination"` or `"golden"`
+    Label { label: String },
+    /// Named numeric score, e.g. a 1-5 helpfulness rating
+    Score { name: String, value: f64 },
+    /// Reviewer comment
+    ReviewerNote {
+        text: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reviewer: Option<String>,
+    },
+    /// Application-defined annotation
+    Custom {
+        kind: String,
+        #[serde(default)]
+        value: serde_json::Value,
+    },
+}
+
+impl Annotation {
+    /// A label
+    pub fn label(label: impl Into<String>) -> Self {
+        Self::Label {
+            label: label.into(),
+        }
+    }
+
+    /// A named score
+    pub fn score(name: impl Into<String>, value: f64) -> Self {
+        Self::Score {
+            name: name.into(),
+            value,
+        }
+    }
+
+    /// An unattributed reviewer note
+    pub fn note(text: impl Into<String>) -> Self {
+        Self::ReviewerNote {
+            text: text.into(),
+            reviewer: None,
+        }
+    }
+
+    /// A reviewer note signed by `reviewer`
+    pub fn reviewer_note(reviewer: impl Into<String>, text: impl Into<String>) -> Self {
+        Self::ReviewerNote {
+            text: text.into(),
+            reviewer: Some(reviewer.into()),
+        }
+    }
+
+    /// An application-defined annotation; `value` is any serializable data
+    pub fn custom(kind: impl Into<String>, value: impl Serialize) -> Self {
+        Self::Custom {
+            kind: kind.into(),
+            value: serde_json::to_value(value).unwrap_or_default(),
+        }
+    }
+}
+
+/// An annotation on the message at `message_index`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TurnAnnotation {
+    /// Index of the annotated message in the history
+    pub message_index: usize,
+    /// The annotation
+    pub annotation: Annotation,
+    /// When it was added
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,419 @@
+//! Keeping conversations inside the context window
+//!
+//! A [`ContextManager`] measures the next request of a [`Conversation`] and,
+//! once it passes a threshold of the model's context window, shortens the
+//! history with a [`TrimStrategy`] until it is back under a target size.
+//!
+//! History is only ever cut at turn boundaries: a kept history always starts
+//! with a user message that is not a tool result, so `tool_use` and
+//! `tool_result` blocks are never separated. Notes and annotations are
+//! re-indexed to match.
+//!
+//! ```rust,no_run
+//! use threatflux_anthropic_sdk::{
+//!     config::models,
+//!     conversation::{ContextManager, TrimStrategy},
+//!     Client, Conversation,
+//! };
+//!
+//! # async fn example(mut chat: Conversation) -> threatflux_anthropic_sdk::Result<()> {
+//! let client = Client::from_env()?;
+//! let context = ContextManager::new(200_000, TrimStrategy::summarize_with(models::HAIKU_4_5, 6));
+//! let report = context.fit(&client, &mut chat).await?;
+//! if report.trimmed() {
+//!     println!("{} -> {} tokens", report.tokens_before, report.tokens_after);
+//! }
+//! chat.send(&client, "Where were we?").await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use super::Conversation;
+use crate::{
+    client::Client,
+    error::Result,
+    models::{
+        common::{ContentBlock, Role},
+        message::{Message, MessageRequest, TokenCountRequest},
+    },
+};
+use std::ops::Range;
+
+/// Default fraction of the window at which trimming starts
+pub const DEFAULT_TRIM_THRESHOLD: f64 = 0.8;
+/// Default fraction of the window to trim down to
+pub const DEFAULT_TRIM_TARGET: f64 = 0.6;
+/// Default `max_tokens` for summaries
+pub const DEFAULT_SUMMARY_MAX_TOKENS: u32 = 1024;
+
+const SUMMARY_PROMPT: &str = "You compress chat transcripts. Summarize the conversation below \
+so that it can replace the original: keep facts, decisions, open questions, names, numbers and \
+tool results that later turns may rely on. Write plain prose, no preamble.";
+
+/// How to shorten a history that has grown too large
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrimStrategy {
+    /// Remove the oldest turns until the target is met
+    DropOldest,
+    /// Keep only the most recent turns spanning at least this many messages.
+    /// The system prompt lives outside the history and is always kept.
+    KeepSystemAndRecentN(usize),
+    /// Replace everything but the most recent turns with a summary written by
+    /// a (typically cheaper) model. The summary's usage is recorded on the
+    /// conversation.
+    SummarizeWithModel {
+        /// Model that writes the summary
+        model: String,
+        /// Messages to keep verbatim, rounded up to a turn boundary
+        keep_recent: usize,
+        /// `max_tokens` for the summary
+        max_summary_tokens: u32,
+    },
+}
+
+impl TrimStrategy {
+    /// [`TrimStrategy::SummarizeWithModel`] with the default summary length
+    pub fn summarize_with(model: impl Into<String>, keep_recent: usize) -> Self {
+        Self::SummarizeWithModel {
+            model: model.into(),
+            keep_recent,
+            max_summary_tokens: DEFAULT_SUMMARY_MAX_TOKENS,
+        }
+    }
+}
+
+/// How the manager measures a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TokenCounter {
+    /// [`MessageRequest::estimated_input_tokens`]; free but approximate
+    #[default]
+    Estimate,
+    /// The token counting endpoint, once per [`ContextManager::fit`]; exact.
+    /// Later sizes are extrapolated from the local estimate, scaled to match.
+    Api,
+}
+
+/// What [`ContextManager::fit`] did
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrimReport {
+    /// Size of the next request before trimming
+    pub tokens_before: u32,
+    /// Size after trimming (extrapolated when counting with the API)
+    pub tokens_after: u32,
+    /// Messages removed from the history
+    pub removed_messages: usize,
+    /// Whether a summary replaced the removed messages
+    pub summarized: bool,
+}
+
+impl TrimReport {
+    /// Whether the history was changed
+    pub fn trimmed(&self) -> bool {
+        self.removed_messages > 0
+    }
+}
+
+/// Trims conversations that approach the context window
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContextManager {
+    /// Context window of the conversation's model, in tokens
+    pub context_window: u32,
+    /// What to do when the history is too large
+    pub strategy: TrimStrategy,
+    /// Fraction of the window above which [`fit`](Self::fit) trims
+    pub threshold: f64,
+    /// Fraction of the window to trim down to
+    pub target: f64,
+    /// How requests are measured
+    pub counter: TokenCounter,
+}
+
+impl ContextManager {
+    /// Manage a `context_window`-token window with `strategy`
+    pub fn new(context_window: u32, strategy: TrimStrategy) -> Self {
+        Self {
+            context_window,
+            strategy,
+            threshold: DEFAULT_TRIM_THRESHOLD,
+            target: DEFAULT_TRIM_TARGET,
+            counter: TokenCounter::default(),
+        }
+    }
+
+    /// Start trimming above `threshold` of the window and trim down to
+    /// `target` of it
+    pub fn with_limits(mut self, threshold: f64, target: f64) -> Self {
+        self.threshold = threshold;
+        self.target = target.min(threshold);
+        self
+    }
+
+    /// Set how requests are measured
+    pub fn with_counter(mut self, counter: TokenCounter) -> Self {
+        self.counter = counter;
+        self
+    }
+
+    /// Size of the conversation's next request
+    pub async fn count(&self, client: &Client, conversation: &Conversation) -> Result<u32> {
+        let request = conversation.request();
+        match self.counter {
+            TokenCounter::Estimate => Ok(request.estimated_input_tokens()),
+            TokenCounter::Api => Ok(client
+                .messages()
+                .count_tokens(TokenCountRequest::from(&request), None)
+                .await?
+                .input_tokens),
+        }
+    }
+
+    /// Trim `conversation` if its next request is above the threshold
+    pub async fn fit(
+        &self,
+        client: &Client,
+        conversation: &mut Conversation,
+    ) -> Result<TrimReport> {
+        let tokens_before = self.count(client, conversation).await?;
+        let mut report = TrimReport {
+            tokens_before,
+            tokens_after: tokens_before,
+            removed_messages: 0,
+            summarized: false,
+        };
+        if f64::from(tokens_before) <= self.limit(self.threshold) {
+            return Ok(report);
+        }
+
+        // Scale local estimates so they agree with the measured size.
+        let estimate = conversation.request().estimated_input_tokens().max(1);
+        let scale = f64::from(tokens_before) / f64::from(estimate);
+        let measure = |c: &Conversation| f64::from(c.request().estimated_input_tokens()) * scale;
+
+        let starts = turn_starts(&conversation.messages);
+        let cut = match &self.strategy {
+            TrimStrategy::DropOldest => {
+                let target = self.limit(self.target);
+                let mut cut = 0;
+                for &start in starts.iter().skip(1) {
+                    if measure(conversation) - self.prefix_size(conversation, start, scale)
+                        <= target
+                    {
+                        cut = start;
+                        break;
+                    }
+                    cut = start;
+                }
+                cut
+            }
+            TrimStrategy::KeepSystemAndRecentN(n) => {
+                recent_cut(&starts, conversation.messages.len(), *n)
+            }
+            TrimStrategy::SummarizeWithModel { keep_recent, .. } => {
+                recent_cut(&starts, conversation.messages.len(), *keep_recent)
+            }
+        };
+        if cut == 0 {
+            return Ok(report);
+        }
+
+        if let TrimStrategy::SummarizeWithModel {
+            model,
+            max_summary_tokens,
+            ..
+        } = &self.strategy
+        {
+            let summary = summarize(
+                client,
+                model,
+                *max_summary_tokens,
+                &conversation.messages[..cut],
+            )
+            .await?;
+            conversation.record_usage(model, &summary.usage)?;
+            conversation.remove_messages(0..cut);
+            conversation.messages[0].content.insert(
+                0,
+                ContentBlock::text(format!(
+                    "Summary of the earlier conversation:\n{}",
+                    summary.text().trim()
+                )),
+            );
+            report.summarized = true;
+        } else {
+            conversation.remove_messages(0..cut);
+        }
+
+        report.removed_messages = cut;
+        report.tokens_after = measure(conversation).round() as u32;
+        Ok(report)
+    }
+
+    fn limit(&self, fraction: f64) -> f64 {
+        f64::from(self.context_window) * fraction
+    }
+
+    /// Scaled estimate of the first `end` messages
+    fn prefix_size(&self, conversation: &Conversation, end: usize, scale: f64) -> f64 {
+        let mut request = MessageRequest::new();
+        request.messages = conversation.messages[..end].to_vec();
+        f64::from(request.estimated_input_tokens()) * scale
+    }
+}
+
+impl Conversation {
+    /// Remove `range` from the history, dropping annotations and provenance
+    /// of the removed messages and shifting later ones and notes down
+    fn remove_messages(&mut self, range: Range<usize>) {
+        let removed = range.len();
+        self.messages.drain(range.clone());
+        self.annotations
+            .retain(|annotation| !range.contains(&annotation.message_index));
+        for annotation in &mut self.annotations {
+            if annotation.message_index >= range.end {
+                annotation.message_index -= removed;
+            }
+        }
+        self.provenance
+            .retain(|turn| !range.contains(&turn.message_index));
+        for turn in &mut self.provenance {
+            if turn.message_index >= range.end {
+                turn.message_index -= removed;
+            }
+        }
+        for note in &mut self.notes {
+            if note.at >= range.end {
+                note.at -= removed;
+            } else if note.at > range.start {
+                note.at = range.start;
+            }
+        }
+    }
+}
+
+/// Indices where a history may start: user messages that carry no tool
+/// results
+fn turn_starts(messages: &[Message]) -> Vec<usize> {
+    messages
+        .iter()
+        .enumerate()
+        .filter(|(_, message)| {
+            message.role == Role::User
+                && !message
+                    .content
+                    .iter()
+                    .any(|block| matches!(block, ContentBlock::ToolResult { .. }))
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Latest turn start that still leaves at least `keep` messages
+fn recent_cut(starts: &[usize], len: usize, keep: usize) -> usize {
+    starts
+        .iter()
+        .rev()
+        .copied()
+        .find(|&start| len - start >= keep)
+        .unwrap_or(0)
+}
+
+async fn summarize(
+    client: &Client,
+    model: &str,
+    max_tokens: u32,
+    messages: &[Message],
+) -> Result<crate::models::message::MessageResponse> {
+    let mut transcript = String::new();
+    for message in messages {
+        let speaker = match message.role {
+            Role::User => "User",
+            Role::Assistant => "Assistant",
+            Role::System => "System",
+        };
+        for block in &message.content {
+            let text = match block.as_text() {
+                Some(text) => text.to_string(),
+                None => serde_json::to_string(block)?,
+            };
+            transcript.push_str(&format!("{}: {}\n", speaker, text));
+        }
+    }
+
+    let request = MessageRequest::new()
+        .model(model)
+        .max_tokens(max_tokens)
+        .system(SUMMARY_PROMPT)
+        .add_user_message(transcript);
+    client.messages().create(request, None).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{conversation::Annotation, Config};
+    use serde_json::json;
+
+    fn conversation() -> Conversation {
+        let mut conversation = Conversation::new("claude-sonnet-4-6").with_system("Be helpful.");
+        for turn in 0..4 {
+            conversation.push_user(format!("question {} {}", turn, "x".repeat(400)));
+            conversation.push_message(Message::new(
+                Role::Assistant,
+                vec![ContentBlock::tool_use(
+                    format!("toolu_{}", turn),
+                    "search",
+                    json!({}),
+                )],
+            ));
+            conversation.push_user_blocks(vec![ContentBlock::tool_result(
+                format!("toolu_{}", turn),
+                Some("y".repeat(400)),
+            )]);
+            conversation.push_message(Message::assistant(format!("answer {}", turn)));
+        }
+        conversation
+    }
+
+    fn offline_client() -> Client {
+        Client::new(Config::new("sk-ant-test").unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_drop_oldest_cuts_at_turn_boundaries() {
+        let client = offline_client();
+        let mut chat = conversation();
+        chat.annotate(0, Annotation::label("gone")).unwrap();
+        chat.annotate(9, Annotation::label("kept")).unwrap();
+        let before = chat.request().estimated_input_tokens();
+
+        // Below the threshold nothing happens.
+        let roomy = ContextManager::new(before * 2, TrimStrategy::DropOldest);
+        assert!(!roomy.fit(&client, &mut chat).await.unwrap().trimmed());
+
+        let tight = ContextManager::new(before, TrimStrategy::DropOldest);
+        let report = tight.fit(&client, &mut chat).await.unwrap();
+        assert_eq!(report.tokens_before, before);
+        assert_eq!(report.removed_messages % 4, 0);
+        assert!(f64::from(report.tokens_after) <= f64::from(before) * DEFAULT_TRIM_TARGET);
+        assert!(chat.messages[0].content[0]
+            .as_text()
+            .unwrap()
+            .starts_with("question"));
+        assert_eq!(chat.annotations().len(), 1);
+        assert_eq!(
+            chat.annotations()[0].message_index,
+            9 - report.removed_messages
+        );
+    }
+
+    #[tokio::test]
+    async fn test_keep_recent_rounds_up_to_a_turn() {
+        let client = offline_client();
+        let mut chat = conversation();
+        let manager = ContextManager::new(100, TrimStrategy::KeepSystemAndRecentN(3));
+        let report = manager.fit(&client, &mut chat).await.unwrap();
+        assert_eq!(report.removed_messages, 12);
+        assert_eq!(chat.messages.len(), 4);
+        assert!(chat.request().system.is_some());
+    }
+}
@@ -0,0 +1,606 @@
+//! Multi-turn conversation state
+//!
+//! A [`Conversation`] holds the model settings and message history for a chat
+//! session, builds the next [`MessageRequest`], and records each response's
+//! usage so the session's token and cost totals (and an optional [`Budget`])
+//! are tracked across turns. [`Conversation::send`] and
+//! [`Conversation::send_stream`] run a whole turn against a
+//! [`Client`](crate::Client).
+
+pub mod annotation;
+pub mod context;
+mod session;
+pub mod store;
+pub mod transcript;
+pub mod usage;
+
+pub use annotation::{Annotation, TurnAnnotation};
+pub use context::{ContextManager, TokenCounter, TrimReport, TrimStrategy};
+pub use store::{ConversationStore, JsonFileStore, StoredConversation, Version};
+pub use transcript::TranscriptFormat;
+pub use usage::{Budget, UsageSummary};
+
+use crate::{
+    config::DEFAULT_MODEL,
+    error::{AnthropicError, Result},
+    models::{
+        common::{ContentBlock, Role, Usage},
+        message::{Message, MessageRequest, MessageResponse, SystemPrompt},
+        provenance::Provenance,
+    },
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Default `max_tokens` for conversation turns.
+pub const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+/// A chat session: settings, history and cumulative usage.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Conversation {
+    /// Conversation identifier
+    pub id: String,
+    /// Model used for each turn
+    pub model: String,
+    /// System prompt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<SystemPrompt>,
+    /// Maximum tokens per response
+    pub max_tokens: u32,
+    /// Message history
+    #[serde(default)]
+    pub messages: Vec<Message>,
+    /// Cumulative usage
+    #[serde(default)]
+    usage: UsageSummary,
+    /// Optional spending limits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    budget: Option<Budget>,
+    /// Where this conversation was forked from, if it is a branch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    parent: Option<ForkPoint>,
+    /// Annotations on the history; never sent to the model
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    notes: Vec<Note>,
+    /// Per-message review annotations; never sent to the model
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    annotations: Vec<TurnAnnotation>,
+    /// Generation metadata of stamped responses; never sent to the model
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    provenance: Vec<TurnProvenance>,
+}
+
+/// The conversation and history position a branch was forked from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForkPoint {
+    /// Id of the parent conversation
+    pub conversation_id: String,
+    /// Number of parent messages the branch started with
+    pub at: usize,
+}
+
+/// [`Provenance`] of the assistant message at `message_index`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TurnProvenance {
+    /// Index of the message in the history
+    pub message_index: usize,
+    /// How it was generated
+    pub provenance: Provenance,
+}
+
+/// An annotation attached to a point in a conversation's history
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Note {
+    /// Number of messages in the history when the note was added
+    pub at: usize,
+    /// Note text
+    pub text: String,
+    /// Branch the note reports on, for notes added with
+    /// [`Conversation::merge_note`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch_id: Option<String>,
+    /// When the note was added
+    pub created_at: DateTime<Utc>,
+}
+
+impl Default for Conversation {
+    fn default() -> Self {
+        Self::new(DEFAULT_MODEL)
+    }
+}
+
+impl Conversation {
+    /// Start an empty conversation with the given model
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            model: model.into(),
+            system: None,
+            max_tokens: DEFAULT_MAX_TOKENS,
+            messages: Vec::new(),
+            usage: UsageSummary::default(),
+            budget: None,
+            parent: None,
+            notes: Vec::new(),
+            annotations: Vec::new(),
+            provenance: Vec::new(),
+        }
+    }
+
+    /// Set the conversation id
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.id = id.into();
+        self
+    }
+
+    /// Set the system prompt
+    pub fn with_system(mut self, system: impl Into<String>) -> Self {
+        self.system = Some(SystemPrompt::Text(system.into()));
+        self
+    }
+
+    /// Set the maximum tokens per response
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// Enforce a per-session budget
+    pub fn with_budget(mut self, budget: Budget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// The session budget, if any
+    pub fn budget(&self) -> Option<&Budget> {
+        self.budget.as_ref()
+    }
+
+    /// Branch off a copy of the conversation at its current end.
+    ///
+    /// See [`fork_at`](Self::fork_at).
+    pub fn fork(&self) -> Self {
+        self.branch(self.messages.len())
+    }
+
+    /// Branch off a copy holding the first `at` messages of the history, to
+    /// explore another continuation from that point.
+    ///
+    /// The branch gets a new id, keeps the settings, budget limits and the
+    /// notes up to `at`, and records this conversation as its
+    /// [`parent`](Self::parent). Its usage starts from zero; what was spent
+    /// so far stays with this conversation.
+    pub fn fork_at(&self, at: usize) -> Result<Self> {
+        if at > self.messages.len() {
+            return Err(AnthropicError::invalid_input(format!(
+                "Cannot fork at message {}: conversation has {} messages",
+                at,
+                self.messages.len()
+            )));
+        }
+        Ok(self.branch(at))
+    }
+
+    fn branch(&self, at: usize) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            model: self.model.clone(),
+            system: self.system.clone(),
+            max_tokens: self.max_tokens,
+            messages: self.messages[..at].to_vec(),
+            usage: UsageSummary::default(),
+            budget: self.budget,
+            parent: Some(ForkPoint {
+                conversation_id: self.id.clone(),
+                at,
+            }),
+            notes: self
+                .notes
+                .iter()
+                .filter(|note| note.at <= at)
+                .cloned()
+                .collect(),
+            annotations: self
+                .annotations
+                .iter()
+                .filter(|annotation| annotation.message_index < at)
+                .cloned()
+                .collect(),
+            provenance: self
+                .provenance
+                .iter()
+                .filter(|turn| turn.message_index < at)
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// The conversation this one was forked from, if it is a branch
+    pub fn parent(&self) -> Option<&ForkPoint> {
+        self.parent.as_ref()
+    }
+
+    /// Attach a note to the current end of the history
+    pub fn add_note(&mut self, text: impl Into<String>) {
+        self.push_note(text.into(), None);
+    }
+
+    /// Attach a note about `branch` (typically a fork of this conversation)
+    /// to the current end of the history, e.g. a summary of where the branch
+    /// led.
+    pub fn merge_note(&mut self, branch: &Conversation, text: impl Into<String>) {
+        self.push_note(text.into(), Some(branch.id.clone()));
+    }
+
+    fn push_note(&mut self, text: String, branch_id: Option<String>) {
+        self.notes.push(Note {
+            at: self.messages.len(),
+            text,
+            branch_id,
+            created_at: Utc::now(),
+        });
+    }
+
+    /// All notes, oldest first
+    pub fn notes(&self) -> &[Note] {
+        &self.notes
+    }
+
+    /// Notes attached after exactly `at` messages
+    pub fn notes_at(&self, at: usize) -> impl Iterator<Item = &Note> {
+        self.notes.iter().filter(move |note| note.at == at)
+    }
+
+    /// Annotate the message at `message_index`
+    pub fn annotate(&mut self, message_index: usize, annotation: Annotation) -> Result<()> {
+        if message_index >= self.messages.len() {
+            return Err(AnthropicError::invalid_input(format!(
+                "Cannot annotate message {}: conversation has {} messages",
+                message_index,
+                self.messages.len()
+            )));
+        }
+        self.annotations.push(TurnAnnotation {
+            message_index,
+            annotation,
+            created_at: Utc::now(),
+        });
+        Ok(())
+    }
+
+    /// Annotate the most recent message
+    pub fn annotate_last(&mut self, annotation: Annotation) -> Result<()> {
+        self.annotate(self.messages.len().saturating_sub(1), annotation)
+    }
+
+    /// All annotations, oldest first
+    pub fn annotations(&self) -> &[TurnAnnotation] {
+        &self.annotations
+    }
+
+    /// Annotations on the message at `message_index`
+    pub fn annotations_for(&self, message_index: usize) -> impl Iterator<Item = &Annotation> {
+        self.annotations
+            .iter()
+            .filter(move |a| a.message_index == message_index)
+            .map(|a| &a.annotation)
+    }
+
+    /// Labels on the message at `message_index`
+    pub fn labels_for(&self, message_index: usize) -> impl Iterator<Item = &str> {
+        self.annotations_for(message_index)
+            .filter_map(|annotation| match annotation {
+                Annotation::Label { label } => Some(label.as_str()),
+                _ => None,
+            })
+    }
+
+    /// Drop every annotation on the message at `message_index`; returns how
+    /// many were removed
+    pub fn clear_annotations(&mut self, message_index: usize) -> usize {
+        let before = self.annotations.len();
+        self.annotations
+            .retain(|a| a.message_index != message_index);
+        before - self.annotations.len()
+    }
+
+    /// Provenance of every stamped response in the history, oldest first
+    pub fn provenance(&self) -> &[TurnProvenance] {
+        &self.provenance
+    }
+
+    /// Provenance of the message at `message_index`, if it was stamped
+    pub fn provenance_for(&self, message_index: usize) -> Option<&Provenance> {
+        self.provenance
+            .iter()
+            .find(|turn| turn.message_index == message_index)
+            .map(|turn| &turn.provenance)
+    }
+
+    /// Detach all provenance, e.g. before sharing a transcript without it
+    pub fn take_provenance(&mut self) -> Vec<TurnProvenance> {
+        std::mem::take(&mut self.provenance)
+    }
+
+    /// Cumulative token usage and estimated cost across all recorded turns
+    pub fn usage_summary(&self) -> &UsageSummary {
+        &self.usage
+    }
+
+    /// Append a user text message
+    pub fn push_user(&mut self, text: impl Into<String>) {
+        self.messages.push(Message::user(text));
+    }
+
+    /// Append a user message with arbitrary content blocks
+    pub fn push_user_blocks(&mut self, content: Vec<ContentBlock>) {
+        self.messages.push(Message::new(Role::User, content));
+    }
+
+    /// Append an arbitrary message
+    pub fn push_message(&mut self, message: Message) {
+        self.messages.push(message);
+    }
+
+    /// The most recent assistant message, if any
+    pub fn last_assistant(&self) -> Option<&Message> {
+        self.messages
+            .iter()
+            .rev()
+            .find(|m| m.role == Role::Assistant)
+    }
+
+    /// Fail with [`AnthropicError::BudgetExceeded`] if the budget is already
+    /// used up, so no further turn should be sent.
+    pub fn check_budget(&self) -> Result<()> {
+        match &self.budget {
+            Some(budget) => budget.check_available(&self.usage),
+            None => Ok(()),
+        }
+    }
+
+    /// Build the request for the next turn from the current history
+    pub fn request(&self) -> MessageRequest {
+        let mut request = MessageRequest::new()
+            .model(self.model.clone())
+            .max_tokens(self.max_tokens);
+        if let Some(system) = &self.system {
+            request = request.system_prompt(system.clone());
+        }
+        request.messages = self.messages.clone();
+        request
+    }
+
+    /// Record a response: append it to the history and add its usage.
+    ///
+    /// The response is always recorded; if the session budget is now exceeded
+    /// the returned error is [`AnthropicError::BudgetExceeded`].
+    pub fn record_response(&mut self, response: &MessageResponse) -> Result<()> {
+        if let Some(provenance) = &response.provenance {
+            self.provenance.push(TurnProvenance {
+                message_index: self.messages.len(),
+                provenance: provenance.clone(),
+            });
+        }
+        self.messages
+            .push(Message::new(Role::Assistant, response.content.clone()));
+        self.record_usage(&response.model, &response.usage)
+    }
+
+    /// Add usage for a turn without touching the history
+    pub fn record_usage(&mut self, model: &str, usage: &Usage) -> Result<()> {
+        self.usage.record(model, usage);
+        match &self.budget {
+            Some(budget) => budget.check(&self.usage),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::models, error::BudgetLimit};
+
+    fn response(model: &str, text: &str, input: u32, output: u32) -> MessageResponse {
+        serde_json::from_value(serde_json::json!({
+            "id": "msg_1",
+            "type": "message",
+            "role": "assistant",
+            "content": [{"type": "text", "text": text}],
+            "model": model,
+            "stop_reason": "end_turn",
+            "stop_sequence": null,
+            "usage": {"input_tokens": input, "output_tokens": output}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_request_from_history() {
+        let mut conversation = Conversation::new(models::HAIKU_4_5)
+            .with_system("Be brief")
+            .with_max_tokens(256);
+        conversation.push_user("Hi");
+        let request = conversation.request();
+        assert_eq!(request.model, models::HAIKU_4_5);
+        assert_eq!(request.max_tokens, 256);
+        assert_eq!(request.messages.len(), 1);
+        assert!(request.system.is_some());
+    }
+
+    #[test]
+    fn test_usage_summary_accumulates() {
+        let mut conversation = Conversation::new(models::SONNET_4_6);
+        conversation.push_user("Hi");
+        conversation
+            .record_response(&response(models::SONNET_4_6, "Hello", 1000, 100))
+            .unwrap();
+        conversation.push_user("Again");
+        conversation
+            .record_response(&response("custom-model", "Hello again", 2000, 200))
+            .unwrap();
+
+        let summary = conversation.usage_summary();
+        assert_eq!(summary.turns, 2);
+        assert_eq!(summary.total_tokens(), 3300);
+        assert!((summary.cost_usd - 0.0045).abs() < 1e-12);
+        assert_eq!(summary.unpriced_turns, 1);
+        assert!(!summary.cost_is_complete());
+        assert_eq!(conversation.messages.len(), 4);
+        assert_eq!(conversation.last_assistant().unwrap().text(), "Hello again");
+    }
+
+    #[test]
+    fn test_budget_exceeded() {
+        let mut conversation =
+            Conversation::new(models::SONNET_4_6).with_budget(Budget::tokens(1000));
+        conversation.push_user("Hi");
+        assert!(conversation.check_budget().is_ok());
+
+        let err = conversation
+            .record_usage(models::SONNET_4_6, &Usage::new(900, 200))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            AnthropicError::BudgetExceeded(BudgetLimit::Tokens {
+                limit: 1000,
+                used: 1100
+            })
+        ));
+        assert!(conversation.check_budget().is_err());
+    }
+
+    #[test]
+    fn test_budget_reached_blocks_next_turn() {
+        let mut conversation =
+            Conversation::new(models::SONNET_4_6).with_budget(Budget::cost_usd(0.0045));
+        conversation
+            .record_usage(models::SONNET_4_6, &Usage::new(1000, 100))
+            .unwrap();
+        assert!(matches!(
+            conversation.check_budget(),
+            Err(AnthropicError::BudgetExceeded(BudgetLimit::CostUsd { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_fork_branches_history_and_notes() {
+        let mut conversation = Conversation::new(models::SONNET_4_6)
+            .with_system("Be brief")
+            .with_budget(Budget::tokens(10_000));
+        conversation.push_user("Pick a color");
+        conversation
+            .record_response(&response(models::SONNET_4_6, "Blue", 10, 1))
+            .unwrap();
+        conversation.add_note("first answer");
+        conversation.push_user("Why?");
+
+        let mut branch = conversation.fork_at(2).unwrap();
+        assert_ne!(branch.id, conversation.id);
+        assert_eq!(
+            branch.parent(),
+            Some(&ForkPoint {
+                conversation_id: conversation.id.clone(),
+                at: 2
+            })
+        );
+        assert_eq!(branch.messages.len(), 2);
+        assert_eq!(branch.notes().len(), 1);
+        assert_eq!(branch.usage_summary().turns, 0);
+        assert_eq!(branch.budget(), conversation.budget());
+        assert!(branch.system.is_some());
+
+        branch.push_user("Pick another");
+        conversation.merge_note(&branch, "alternative explored");
+        let note = conversation.notes_at(3).next().unwrap();
+        assert_eq!(note.branch_id.as_deref(), Some(branch.id.as_str()));
+        // Notes stay out of the request.
+        assert_eq!(conversation.request().messages.len(), 3);
+
+        assert_eq!(conversation.fork().messages.len(), 3);
+        assert!(matches!(
+            conversation.fork_at(4),
+            Err(AnthropicError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_annotations_survive_serde_but_not_requests() {
+        let mut conversation = Conversation::new(models::SONNET_4_6);
+        assert!(conversation
+            .annotate_last(Annotation::label("empty"))
+            .is_err());
+
+        conversation.push_user("What is 2 + 2?");
+        conversation
+            .record_response(&response(models::SONNET_4_6, "5", 10, 1))
+            .unwrap();
+        conversation
+            .annotate_last(Annotation::label("wrong"))
+            .unwrap();
+        conversation
+            .annotate(1, Annotation::score("accuracy", 0.0))
+            .unwrap();
+        conversation
+            .annotate(1, Annotation::reviewer_note("sam", "Off by one"))
+            .unwrap();
+        conversation
+            .annotate(
+                0,
+                Annotation::custom("ticket", serde_json::json!({"id": 42})),
+            )
+            .unwrap();
+
+        assert_eq!(conversation.labels_for(1).collect::<Vec<_>>(), ["wrong"]);
+        assert_eq!(conversation.annotations_for(1).count(), 3);
+
+        let json = serde_json::to_value(&conversation).unwrap();
+        assert_eq!(json["annotations"][1]["annotation"]["type"], "score");
+        let restored: Conversation = serde_json::from_value(json).unwrap();
+        assert_eq!(restored.annotations(), conversation.annotations());
+
+        let request = serde_json::to_value(conversation.request()).unwrap();
+        assert!(!request.to_string().contains("Off by one"));
+
+        assert_eq!(conversation.fork_at(1).unwrap().annotations().len(), 1);
+        assert_eq!(conversation.clear_annotations(1), 3);
+    }
+
+    #[test]
+    fn test_provenance_kept_per_turn_in_transcripts() {
+        let mut conversation = Conversation::new(models::SONNET_4_6);
+        conversation.push_user("Hi");
+        let request = conversation.request();
+        let mut stamped = response(models::SONNET_4_6, "Hello", 1, 1);
+        stamped.stamp(&request).unwrap();
+        conversation.record_response(&stamped).unwrap();
+        conversation.push_user("Again");
+        conversation
+            .record_response(&response(models::SONNET_4_6, "Hello", 1, 1))
+            .unwrap();
+
+        assert_eq!(conversation.provenance_for(1), stamped.provenance.as_ref());
+        assert_eq!(conversation.provenance_for(3), None);
+        let restored = Conversation::from_jsonl(&conversation.to_jsonl().unwrap()).unwrap();
+        assert_eq!(restored.provenance(), conversation.provenance());
+        assert!(!serde_json::to_string(&conversation.request())
+            .unwrap()
+            .contains("sdk_version"));
+
+        assert_eq!(conversation.take_provenance().len(), 1);
+        assert!(!conversation.to_jsonl().unwrap().contains("provenance"));
+    }
+
+    #[test]
+    fn test_serde_round_trip_keeps_usage() {
+        let mut conversation =
+            Conversation::new(models::SONNET_4_6).with_budget(Budget::tokens(10));
+        conversation
+            .record_usage(models::SONNET_4_6, &Usage::new(1, 1))
+            .unwrap();
+        let json = serde_json::to_string(&conversation).unwrap();
+        let restored: Conversation = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, conversation);
+    }
+}
@@ -0,0 +1,96 @@
+//! Sending turns and saving session state
+//!
+//! [`Conversation`] stays plain data so it can be stored and cloned freely;
+//! the client is passed in per turn. [`Conversation::send`] and
+//! [`Conversation::send_stream`] append the user message, make the request
+//! and append the assistant reply, leaving the history untouched if the
+//! request fails.
+
+use super::Conversation;
+use crate::{
+    client::Client,
+    error::Result,
+    models::message::{MessageRequest, MessageResponse},
+    streaming::StreamEvent,
+};
+
+impl Conversation {
+    /// Send `text` as the next user turn and record the reply.
+    ///
+    /// Fails with
+    /// [`AnthropicError::BudgetExceeded`](crate::error::AnthropicError::BudgetExceeded)
+    /// before sending when the budget is used up. If the request fails, the
+    /// user message is removed again so the turn can be retried.
+    ///
+    /// ```rust,no_run
+    /// use threatflux_anthropic_sdk::{Client, Conversation};
+    ///
+    /// # async fn example() -> threatflux_anthropic_sdk::Result<()> {
+    /// let client = Client::from_env()?;
+    /// let mut chat = Conversation::new("claude-sonnet-4-6").with_system("Be brief.");
+    /// chat.send(&client, "Name a prime number.").await?;
+    /// let reply = chat.send(&client, "And the next one?").await?;
+    /// println!("{}", reply.text());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn send(&mut self, client: &Client, text: &str) -> Result<MessageResponse> {
+        let request = self.begin_turn(text)?;
+        match client.messages().create(request, None).await {
+            Ok(response) => {
+                self.record_response(&response)?;
+                Ok(response)
+            }
+            Err(err) => {
+                self.messages.pop();
+                Err(err)
+            }
+        }
+    }
+
+    /// Streaming form of [`send`](Self::send): `on_event` sees each event as
+    /// it arrives, and the complete reply is recorded once the stream ends.
+    pub async fn send_stream<F>(
+        &mut self,
+        client: &Client,
+        text: &str,
+        on_event: F,
+    ) -> Result<MessageResponse>
+    where
+        F: FnMut(&StreamEvent),
+    {
+        let request = self.begin_turn(text)?;
+        let result = match client.messages().create_stream(request, None).await {
+            Ok(stream) => stream.accumulate(on_event).await,
+            Err(err) => Err(err),
+        };
+        match result {
+            Ok(response) => {
+                self.record_response(&response)?;
+                Ok(response)
+            }
+            Err(err) => {
+                self.messages.pop();
+                Err(err)
+            }
+        }
+    }
+
+    /// Serialize the whole session (history, settings, usage, notes and
+    /// annotations) as JSON
+    pub fn snapshot(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Rebuild a session saved with [`snapshot`](Self::snapshot)
+    pub fn restore(snapshot: &str) -> Result<Self> {
+        Ok(serde_json::from_str(snapshot)?)
+    }
+
+    /// Check the budget, append the user message and build the request
+    fn begin_turn(&mut self, text: &str) -> Result<MessageRequest> {
+        self.check_budget()?;
+        self.push_user(text);
+        Ok(self.request())
+    }
+}
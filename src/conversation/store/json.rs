@@ -0,0 +1,158 @@
+//! One-JSON-file-per-conversation store
+
+use super::{check_version, conflict, ConversationStore, StoredConversation, Version};
+use crate::{
+    conversation::Conversation,
+    error::{AnthropicError, Result},
+};
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// Stores each conversation as `<dir>/<id>.json`.
+///
+/// Writes go through a temporary file and an atomic rename, and saves within
+/// one process are serialized. Concurrent writers in different processes are
+/// detected on a best-effort basis only; use the sled or SQLite store when
+/// several processes share a directory.
+#[derive(Debug)]
+pub struct JsonFileStore {
+    dir: PathBuf,
+    write_lock: Mutex<()>,
+}
+
+impl JsonFileStore {
+    /// Open a store in `dir`, creating the directory if needed
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            write_lock: Mutex::new(()),
+        })
+    }
+
+    /// Directory holding the conversation files
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path_for(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", encode_id(id)))
+    }
+
+    fn read(&self, id: &str) -> Result<Option<StoredConversation>> {
+        match fs::read(self.path_for(id)) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+impl ConversationStore for JsonFileStore {
+    fn load(&self, id: &str) -> Result<Option<StoredConversation>> {
+        self.read(id)
+    }
+
+    fn save(&self, conversation: &Conversation, expected: Option<Version>) -> Result<Version> {
+        let _guard = self.write_lock.lock().unwrap();
+        let id = &conversation.id;
+        let current = self.read(id)?.map(|stored| stored.version);
+        let version = check_version(id, expected, current)?;
+
+        let record = StoredConversation {
+            version,
+            conversation: conversation.clone(),
+        };
+        let path = self.path_for(id);
+        let tmp = path.with_extension(format!("json.{}.tmp", uuid::Uuid::new_v4()));
+        fs::write(&tmp, serde_json::to_vec_pretty(&record)?)?;
+        fs::rename(&tmp, &path).inspect_err(|_| {
+            let _ = fs::remove_file(&tmp);
+        })?;
+        Ok(version)
+    }
+
+    fn delete(&self, id: &str, expected: Option<Version>) -> Result<bool> {
+        let _guard = self.write_lock.lock().unwrap();
+        let Some(current) = self.read(id)?.map(|stored| stored.version) else {
+            return Ok(false);
+        };
+        if expected.is_some_and(|v| v != current) {
+            return Err(conflict(id, expected, Some(current)));
+        }
+        fs::remove_file(self.path_for(id))?;
+        Ok(true)
+    }
+
+    fn list_ids(&self) -> Result<Vec<String>> {
+        let mut ids = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let name = entry?.file_name();
+            let Some(stem) = name.to_str().and_then(|n| n.strip_suffix(".json")) else {
+                continue;
+            };
+            ids.push(decode_id(stem).ok_or_else(|| {
+                AnthropicError::file_error(format!("Unexpected file in store: {}", stem))
+            })?);
+        }
+        ids.sort();
+        Ok(ids)
+    }
+}
+
+/// Percent-encode everything except `[A-Za-z0-9_-]` so ids map to safe,
+/// reversible file names.
+fn encode_id(id: &str) -> String {
+    let mut out = String::with_capacity(id.len());
+    for byte in id.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+fn decode_id(name: &str) -> Option<String> {
+    let bytes = name.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = name.get(i + 1..i + 3)?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_file_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = JsonFileStore::new(dir.path().join("sessions")).unwrap();
+        crate::conversation::store::tests::exercise_store(&store);
+    }
+
+    #[test]
+    fn test_id_encoding_round_trip() {
+        for id in ["simple", "chat/1", "../etc", "ünï", "a%b"] {
+            let encoded = encode_id(id);
+            assert!(!encoded.contains('/') && !encoded.contains('.'));
+            assert_eq!(decode_id(&encoded).as_deref(), Some(id));
+        }
+    }
+}
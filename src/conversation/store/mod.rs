@@ -0,0 +1,161 @@
+//! Persistence adapters for [`Conversation`]s
+//!
+//! Every store keeps a monotonically increasing [`Version`] per conversation.
+//! Saves are optimistic: pass the version you loaded (or `None` to create a new
+//! record) and the save fails with [`AnthropicError::VersionConflict`] if
+//! another writer got there first.
+//!
+//! | Store | Feature |
+//! |-------|---------|
+//! | [`JsonFileStore`] | always available |
+//! | [`SledStore`] | `sled` |
+//! | [`SqliteStore`] | `sqlite` |
+//!
+//! The backends are local and synchronous; from async code, call them through
+//! `tokio::task::spawn_blocking` if saves may be slow.
+//!
+//! [`AnthropicError::VersionConflict`]: crate::error::AnthropicError::VersionConflict
+
+mod json;
+#[cfg(feature = "sled")]
+mod sled;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+#[cfg(feature = "sled")]
+pub use self::sled::SledStore;
+pub use json::JsonFileStore;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStore;
+
+use super::Conversation;
+use crate::error::{AnthropicError, Result};
+use serde::{Deserialize, Serialize};
+
+/// Optimistic-concurrency token for a stored conversation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Version(pub u64);
+
+impl Version {
+    /// Version assigned to a newly created record
+    pub const INITIAL: Version = Version(1);
+
+    /// The version following this one
+    pub fn next(self) -> Self {
+        Version(self.0 + 1)
+    }
+}
+
+/// A conversation together with its stored version.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredConversation {
+    /// Version to pass back to [`ConversationStore::save`]
+    pub version: Version,
+    /// The conversation
+    pub conversation: Conversation,
+}
+
+/// Storage backend for conversations.
+pub trait ConversationStore: Send + Sync {
+    /// Load a conversation by id
+    fn load(&self, id: &str) -> Result<Option<StoredConversation>>;
+
+    /// Save a conversation, returning its new version.
+    ///
+    /// `expected` must be the currently stored version, or `None` when the
+    /// conversation must not exist yet.
+    fn save(&self, conversation: &Conversation, expected: Option<Version>) -> Result<Version>;
+
+    /// Delete a conversation, returning whether it existed.
+    ///
+    /// When `expected` is given, the delete only succeeds at that version.
+    fn delete(&self, id: &str, expected: Option<Version>) -> Result<bool>;
+
+    /// Ids of all stored conversations
+    fn list_ids(&self) -> Result<Vec<String>>;
+}
+
+/// Build the error returned when a save or delete loses a race.
+pub(crate) fn conflict(
+    id: &str,
+    expected: Option<Version>,
+    actual: Option<Version>,
+) -> AnthropicError {
+    AnthropicError::VersionConflict {
+        id: id.to_string(),
+        expected: expected.map(|v| v.0),
+        actual: actual.map(|v| v.0),
+    }
+}
+
+/// Shared save-precondition check: the stored version must equal `expected`.
+pub(crate) fn check_version(
+    id: &str,
+    expected: Option<Version>,
+    actual: Option<Version>,
+) -> Result<Version> {
+    if expected != actual {
+        return Err(conflict(id, expected, actual));
+    }
+    Ok(actual.map_or(Version::INITIAL, Version::next))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::error::AnthropicError;
+
+    /// Behaviour every store must satisfy.
+    pub(crate) fn exercise_store(store: &dyn ConversationStore) {
+        let mut conversation = Conversation::new("claude-sonnet-4-6").with_id("chat/1");
+        conversation.push_user("Hello");
+
+        assert!(store.load("chat/1").unwrap().is_none());
+        let v1 = store.save(&conversation, None).unwrap();
+        assert_eq!(v1, Version::INITIAL);
+
+        // Creating again must conflict.
+        assert!(matches!(
+            store.save(&conversation, None),
+            Err(AnthropicError::VersionConflict {
+                actual: Some(1),
+                ..
+            })
+        ));
+
+        let loaded = store.load("chat/1").unwrap().unwrap();
+        assert_eq!(loaded.version, v1);
+        assert_eq!(loaded.conversation, conversation);
+
+        conversation.push_user("Again");
+        let v2 = store.save(&conversation, Some(v1)).unwrap();
+        assert_eq!(v2, Version(2));
+
+        // A writer holding the stale version loses.
+        assert!(matches!(
+            store.save(&conversation, Some(v1)),
+            Err(AnthropicError::VersionConflict {
+                expected: Some(1),
+                actual: Some(2),
+                ..
+            })
+        ));
+
+        assert_eq!(store.list_ids().unwrap(), vec!["chat/1".to_string()]);
+        assert!(store.delete("chat/1", Some(v1)).is_err());
+        assert!(store.delete("chat/1", Some(v2)).unwrap());
+        assert!(!store.delete("chat/1", None).unwrap());
+        assert!(store.load("chat/1").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_check_version() {
+        assert_eq!(check_version("a", None, None).unwrap(), Version(1));
+        assert_eq!(
+            check_version("a", Some(Version(3)), Some(Version(3))).unwrap(),
+            Version(4)
+        );
+        assert!(check_version("a", Some(Version(1)), None).is_err());
+    }
+}
@@ -0,0 +1,136 @@
+//! Embedded sled store (feature `sled`)
+
+use super::{conflict, ConversationStore, StoredConversation, Version};
+use crate::{
+    conversation::Conversation,
+    error::{AnthropicError, Result},
+};
+use std::path::Path;
+
+/// Stores conversations in a sled tree keyed by id.
+///
+/// Saves use sled's compare-and-swap, so concurrent writers sharing the same
+/// database handle are detected exactly.
+#[derive(Debug, Clone)]
+pub struct SledStore {
+    tree: sled::Tree,
+}
+
+impl SledStore {
+    /// Open (or create) a database at `path`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let db = sled::open(path).map_err(sled_error)?;
+        Self::from_tree(db.open_tree("conversations").map_err(sled_error)?)
+    }
+
+    /// Use an existing tree
+    pub fn from_tree(tree: sled::Tree) -> Result<Self> {
+        Ok(Self { tree })
+    }
+
+    fn decode(bytes: &[u8]) -> Result<StoredConversation> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+}
+
+impl ConversationStore for SledStore {
+    fn load(&self, id: &str) -> Result<Option<StoredConversation>> {
+        self.tree
+            .get(id)
+            .map_err(sled_error)?
+            .map(|bytes| Self::decode(&bytes))
+            .transpose()
+    }
+
+    fn save(&self, conversation: &Conversation, expected: Option<Version>) -> Result<Version> {
+        let id = conversation.id.as_str();
+        let current = self.tree.get(id).map_err(sled_error)?;
+        let current_version = current
+            .as_deref()
+            .map(Self::decode)
+            .transpose()?
+            .map(|stored| stored.version);
+        let version = super::check_version(id, expected, current_version)?;
+
+        let record = serde_json::to_vec(&StoredConversation {
+            version,
+            conversation: conversation.clone(),
+        })?;
+        match self
+            .tree
+            .compare_and_swap(id, current, Some(record))
+            .map_err(sled_error)?
+        {
+            Ok(()) => {
+                self.tree.flush().map_err(sled_error)?;
+                Ok(version)
+            }
+            Err(race) => {
+                let actual = race
+                    .current
+                    .as_deref()
+                    .map(Self::decode)
+                    .transpose()?
+                    .map(|stored| stored.version);
+                Err(conflict(id, expected, actual))
+            }
+        }
+    }
+
+    fn delete(&self, id: &str, expected: Option<Version>) -> Result<bool> {
+        let Some(current) = self.tree.get(id).map_err(sled_error)? else {
+            return Ok(false);
+        };
+        let current_version = Self::decode(&current)?.version;
+        if expected.is_some_and(|v| v != current_version) {
+            return Err(conflict(id, expected, Some(current_version)));
+        }
+        match self
+            .tree
+            .compare_and_swap(id, Some(current), None as Option<&[u8]>)
+            .map_err(sled_error)?
+        {
+            Ok(()) => {
+                self.tree.flush().map_err(sled_error)?;
+                Ok(true)
+            }
+            Err(race) => {
+                let actual = race
+                    .current
+                    .as_deref()
+                    .map(Self::decode)
+                    .transpose()?
+                    .map(|stored| stored.version);
+                Err(conflict(id, expected, actual))
+            }
+        }
+    }
+
+    fn list_ids(&self) -> Result<Vec<String>> {
+        self.tree
+            .iter()
+            .keys()
+            .map(|key| {
+                let key = key.map_err(sled_error)?;
+                String::from_utf8(key.to_vec())
+                    .map_err(|e| AnthropicError::file_error(format!("Invalid key in store: {}", e)))
+            })
+            .collect()
+    }
+}
+
+fn sled_error(err: sled::Error) -> AnthropicError {
+    AnthropicError::file_error(format!("sled store error: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sled_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SledStore::open(dir.path().join("db")).unwrap();
+        crate::conversation::store::tests::exercise_store(&store);
+    }
+}
@@ -0,0 +1,161 @@
+//! SQLite store (feature `sqlite`)
+
+use super::{conflict, ConversationStore, StoredConversation, Version};
+use crate::{
+    conversation::Conversation,
+    error::{AnthropicError, Result},
+};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::{path::Path, sync::Mutex};
+
+/// Stores conversations in a `conversations` table.
+///
+/// Updates are conditional on the stored version (`UPDATE ... WHERE version =
+/// ?`), so writers in other processes sharing the database file are detected.
+#[derive(Debug)]
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    /// Open (or create) a database file
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_connection(Connection::open(path).map_err(sqlite_error)?)
+    }
+
+    /// Open a private in-memory database
+    pub fn in_memory() -> Result<Self> {
+        Self::from_connection(Connection::open_in_memory().map_err(sqlite_error)?)
+    }
+
+    /// Use an existing connection, creating the table if needed
+    pub fn from_connection(conn: Connection) -> Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS conversations (
+                id         TEXT PRIMARY KEY,
+                version    INTEGER NOT NULL,
+                data       TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )",
+        )
+        .map_err(sqlite_error)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn current_version(conn: &Connection, id: &str) -> Result<Option<Version>> {
+        conn.query_row(
+            "SELECT version FROM conversations WHERE id = ?1",
+            params![id],
+            |row| row.get::<_, i64>(0),
+        )
+        .optional()
+        .map(|v| v.map(|v| Version(v as u64)))
+        .map_err(sqlite_error)
+    }
+}
+
+impl ConversationStore for SqliteStore {
+    fn load(&self, id: &str) -> Result<Option<StoredConversation>> {
+        let conn = self.conn.lock().unwrap();
+        let row = conn
+            .query_row(
+                "SELECT version, data FROM conversations WHERE id = ?1",
+                params![id],
+                |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
+            )
+            .optional()
+            .map_err(sqlite_error)?;
+        row.map(|(version, data)| {
+            Ok(StoredConversation {
+                version: Version(version as u64),
+                conversation: serde_json::from_str(&data)?,
+            })
+        })
+        .transpose()
+    }
+
+    fn save(&self, conversation: &Conversation, expected: Option<Version>) -> Result<Version> {
+        let conn = self.conn.lock().unwrap();
+        let id = conversation.id.as_str();
+        let data = serde_json::to_string(conversation)?;
+        let now = chrono::Utc::now().to_rfc3339();
+
+        let (version, changed) = match expected {
+            None => {
+                let changed = conn
+                    .execute(
+                        "INSERT INTO conversations (id, version, data, updated_at)
+                         VALUES (?1, ?2, ?3, ?4) ON CONFLICT(id) DO NOTHING",
+                        params![id, Version::INITIAL.0 as i64, data, now],
+                    )
+                    .map_err(sqlite_error)?;
+                (Version::INITIAL, changed)
+            }
+            Some(expected) => {
+                let next = expected.next();
+                let changed = conn
+                    .execute(
+                        "UPDATE conversations SET version = ?2, data = ?3, updated_at = ?4
+                         WHERE id = ?1 AND version = ?5",
+                        params![id, next.0 as i64, data, now, expected.0 as i64],
+                    )
+                    .map_err(sqlite_error)?;
+                (next, changed)
+            }
+        };
+
+        if changed == 0 {
+            return Err(conflict(id, expected, Self::current_version(&conn, id)?));
+        }
+        Ok(version)
+    }
+
+    fn delete(&self, id: &str, expected: Option<Version>) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let changed = match expected {
+            None => conn.execute("DELETE FROM conversations WHERE id = ?1", params![id]),
+            Some(v) => conn.execute(
+                "DELETE FROM conversations WHERE id = ?1 AND version = ?2",
+                params![id, v.0 as i64],
+            ),
+        }
+        .map_err(sqlite_error)?;
+
+        if changed == 0 && expected.is_some() {
+            if let Some(actual) = Self::current_version(&conn, id)? {
+                return Err(conflict(id, expected, Some(actual)));
+            }
+        }
+        Ok(changed > 0)
+    }
+
+    fn list_ids(&self) -> Result<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT id FROM conversations ORDER BY id")
+            .map_err(sqlite_error)?;
+        let ids = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(sqlite_error)?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(sqlite_error)?;
+        Ok(ids)
+    }
+}
+
+fn sqlite_error(err: rusqlite::Error) -> AnthropicError {
+    AnthropicError::file_error(format!("SQLite store error: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sqlite_store() {
+        let store = SqliteStore::in_memory().unwrap();
+        crate::conversation::store::tests::exercise_store(&store);
+    }
+}
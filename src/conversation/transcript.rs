@@ -0,0 +1,285 @@
+//! Saving conversations to disk
+//!
+//! Two formats are supported:
+//!
+//! * **JSON**: the whole [`Conversation`] as one document, the same shape the
+//!   [stores](super::store) use.
+//! * **JSONL transcript**: one record per line. `message` records hold the
+//!   history in order; `session` records hold everything else (settings,
+//!   usage totals, budget, notes, annotations), and the last one wins. That
+//!   makes the file append-only: [`Conversation::append_jsonl`] writes the
+//!   new messages and a fresh `session` record without rewriting the file.
+//!
+//! Message content is stored verbatim, so tool calls, tool results and
+//! thinking blocks (signatures included) survive a round trip.
+
+use super::Conversation;
+use crate::{
+    error::{AnthropicError, Result},
+    models::message::Message,
+    utils::object_store::ObjectStore,
+};
+use serde_json::Value;
+use std::{fs, io::Write, path::Path};
+
+/// On-disk format for [`Conversation::save`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranscriptFormat {
+    /// Single JSON document
+    Json,
+    /// One JSON record per line
+    Jsonl,
+}
+
+impl TranscriptFormat {
+    /// `Jsonl` for `.jsonl` files, `Json` otherwise
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("jsonl") => Self::Jsonl,
+            _ => Self::Json,
+        }
+    }
+}
+
+const SESSION_RECORD: &str = "session";
+const MESSAGE_RECORD: &str = "message";
+
+impl Conversation {
+    /// Write the conversation to `path`, as JSONL when the extension is
+    /// `.jsonl` and as JSON otherwise
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        self.save_as(path, TranscriptFormat::from_path(path))
+    }
+
+    /// Write the conversation to `path` in `format`, replacing the file
+    pub fn save_as(&self, path: impl AsRef<Path>, format: TranscriptFormat) -> Result<()> {
+        let contents = match format {
+            TranscriptFormat::Json => serde_json::to_string_pretty(self)?,
+            TranscriptFormat::Jsonl => self.to_jsonl()?,
+        };
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    /// Read a conversation written by [`save`](Self::save), picking the format
+    /// from the extension
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)?;
+        match TranscriptFormat::from_path(path) {
+            TranscriptFormat::Json => Ok(serde_json::from_str(&contents)?),
+            TranscriptFormat::Jsonl => Self::from_jsonl(&contents),
+        }
+    }
+
+    /// Write the conversation to `key` in `store`, as JSONL when the key
+    /// ends in `.jsonl` and as JSON otherwise
+    pub async fn export_to(&self, store: &dyn ObjectStore, key: &str) -> Result<()> {
+        let contents = match TranscriptFormat::from_path(Path::new(key)) {
+            TranscriptFormat::Json => serde_json::to_string_pretty(self)?,
+            TranscriptFormat::Jsonl => self.to_jsonl()?,
+        };
+        store.put(key, contents.into_bytes()).await
+    }
+
+    /// Read a conversation written by [`export_to`](Self::export_to)
+    pub async fn import_from(store: &dyn ObjectStore, key: &str) -> Result<Self> {
+        let bytes = store.get(key).await?;
+        let contents = String::from_utf8(bytes).map_err(|e| {
+            AnthropicError::invalid_input(format!("Transcript {} is not UTF-8: {}", key, e))
+        })?;
+        match TranscriptFormat::from_path(Path::new(key)) {
+            TranscriptFormat::Json => Ok(serde_json::from_str(&contents)?),
+            TranscriptFormat::Jsonl => Self::from_jsonl(&contents),
+        }
+    }
+
+    /// The whole conversation as a JSONL transcript
+    pub fn to_jsonl(&self) -> Result<String> {
+        let mut out = self.session_record()?;
+        out.push('\n');
+        for message in &self.messages {
+            out.push_str(&message_record(message)?);
+            out.push('\n');
+        }
+        Ok(out)
+    }
+
+    /// Parse a JSONL transcript. Blank lines are skipped, messages are kept in
+    /// file order and the last `session` record supplies everything else.
+    pub fn from_jsonl(transcript: &str) -> Result<Self> {
+        let mut session: Option<Conversation> = None;
+        let mut messages = Vec::new();
+
+        for (number, line) in transcript.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let invalid = |reason: String| {
+                AnthropicError::invalid_input(format!(
+                    "Invalid transcript line {}: {}",
+                    number + 1,
+                    reason
+                ))
+            };
+            let mut record: Value =
+                serde_json::from_str(line).map_err(|e| invalid(e.to_string()))?;
+            let kind = record
+                .as_object_mut()
+                .and_then(|object| object.remove("type"))
+                .and_then(|kind| kind.as_str().map(str::to_string));
+            match kind.as_deref() {
+                Some(SESSION_RECORD) => {
+                    session =
+                        Some(serde_json::from_value(record).map_err(|e| invalid(e.to_string()))?);
+                }
+                Some(MESSAGE_RECORD) => {
+                    messages
+                        .push(serde_json::from_value(record).map_err(|e| invalid(e.to_string()))?);
+                }
+                other => {
+                    return Err(invalid(format!("unknown record type {:?}", other)));
+                }
+            }
+        }
+
+        let mut conversation = session
+            .ok_or_else(|| AnthropicError::invalid_input("Transcript has no session record"))?;
+        conversation.messages = messages;
+        Ok(conversation)
+    }
+
+    /// Append `messages[since..]` and a fresh `session` record to the JSONL
+    /// transcript at `path`, creating it if needed.
+    ///
+    /// Pass the message count from the previous append (or 0 for a new file)
+    /// to keep a transcript up to date turn by turn.
+    pub fn append_jsonl(&self, path: impl AsRef<Path>, since: usize) -> Result<()> {
+        let mut out = String::new();
+        for message in self.messages.iter().skip(since) {
+            out.push_str(&message_record(message)?);
+            out.push('\n');
+        }
+        out.push_str(&self.session_record()?);
+        out.push('\n');
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        file.write_all(out.as_bytes())?;
+        Ok(())
+    }
+
+    /// Everything except the messages, tagged as a `session` record
+    fn session_record(&self) -> Result<String> {
+        let mut value = serde_json::to_value(self)?;
+        if let Some(object) = value.as_object_mut() {
+            object.remove("messages");
+            object.insert("type".to_string(), SESSION_RECORD.into());
+        }
+        Ok(serde_json::to_string(&value)?)
+    }
+}
+
+fn message_record(message: &Message) -> Result<String> {
+    let mut value = serde_json::to_value(message)?;
+    if let Some(object) = value.as_object_mut() {
+        object.insert("type".to_string(), MESSAGE_RECORD.into());
+    }
+    Ok(serde_json::to_string(&value)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::models,
+        models::common::{ContentBlock, Role, Usage},
+    };
+    use serde_json::json;
+
+    fn conversation() -> Conversation {
+        let mut conversation = Conversation::new(models::SONNET_4_6).with_system("Use tools.");
+        conversation.push_user("What's 6 * 7?");
+        conversation.push_message(Message::new(
+            Role::Assistant,
+            vec![
+                ContentBlock::Thinking {
+                    thinking: "Use the calculator.".to_string(),
+                    signature: Some("sig_abc".to_string()),
+                },
+                ContentBlock::tool_use("toolu_1", "calc", json!({"expr": "6*7"})),
+            ],
+        ));
+        conversation.push_user_blocks(vec![ContentBlock::tool_result_json(
+            "toolu_1",
+            json!({"value": 42}),
+        )]);
+        conversation
+            .record_usage(
+                models::SONNET_4_6,
+                &Usage {
+                    input_tokens: 30,
+                    output_tokens: 12,
+                    ..Usage::default()
+                },
+            )
+            .unwrap();
+        conversation.add_note("checked by hand");
+        conversation
+    }
+
+    #[test]
+    fn test_json_and_jsonl_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = conversation();
+
+        for name in ["chat.json", "chat.jsonl"] {
+            let path = dir.path().join(name);
+            original.save(&path).unwrap();
+            assert_eq!(Conversation::load(&path).unwrap(), original, "{}", name);
+        }
+
+        let transcript = original.to_jsonl().unwrap();
+        assert_eq!(transcript.lines().count(), 4);
+        assert!(transcript
+            .lines()
+            .next()
+            .unwrap()
+            .contains("\"type\":\"session\""));
+        assert!(transcript.contains("\"signature\":\"sig_abc\""));
+    }
+
+    #[test]
+    fn test_append_jsonl_keeps_latest_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chat.jsonl");
+
+        let mut conversation = conversation();
+        conversation.append_jsonl(&path, 0).unwrap();
+        let written = conversation.messages.len();
+        conversation.push_user("Thanks!");
+        conversation
+            .record_usage(
+                models::SONNET_4_6,
+                &Usage {
+                    input_tokens: 5,
+                    ..Usage::default()
+                },
+            )
+            .unwrap();
+        conversation.append_jsonl(&path, written).unwrap();
+
+        let loaded = Conversation::load(&path).unwrap();
+        assert_eq!(loaded, conversation);
+        assert_eq!(loaded.usage_summary().total_tokens(), 47);
+
+        let err = Conversation::from_jsonl(
+            "{\"type\":\"message\",\"role\":\"user\",\"content\":[]}\n{\"type\":\"oops\"}",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+    }
+}
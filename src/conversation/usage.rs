@@ -0,0 +1,150 @@
+//! Session-level token and cost accounting
+
+use crate::{
+    config::models,
+    error::{AnthropicError, BudgetLimit, Result},
+    models::common::Usage,
+};
+use serde::{Deserialize, Serialize};
+
+/// Cumulative token usage and estimated cost across a conversation's turns.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct UsageSummary {
+    /// Number of responses recorded
+    pub turns: u32,
+    /// Uncached input tokens
+    pub input_tokens: u64,
+    /// Output tokens
+    pub output_tokens: u64,
+    /// Input tokens written to the prompt cache
+    pub cache_creation_input_tokens: u64,
+    /// Input tokens read from the prompt cache
+    pub cache_read_input_tokens: u64,
+    /// Estimated cost in USD of turns whose model has known pricing
+    pub cost_usd: f64,
+    /// Turns whose model has no known pricing (excluded from `cost_usd`)
+    pub unpriced_turns: u32,
+}
+
+impl UsageSummary {
+    /// Add one response's usage
+    pub fn record(&mut self, model: &str, usage: &Usage) {
+        self.turns += 1;
+        self.input_tokens += usage.input_tokens as u64;
+        self.output_tokens += usage.output_tokens as u64;
+        self.cache_creation_input_tokens += usage.cache_creation_input_tokens as u64;
+        self.cache_read_input_tokens += usage.cache_read_input_tokens as u64;
+        match models::pricing(model) {
+            Some(pricing) => self.cost_usd += pricing.cost(usage),
+            None => self.unpriced_turns += 1,
+        }
+    }
+
+    /// All input tokens, cached or not
+    pub fn total_input_tokens(&self) -> u64 {
+        self.input_tokens + self.cache_creation_input_tokens + self.cache_read_input_tokens
+    }
+
+    /// All input and output tokens
+    pub fn total_tokens(&self) -> u64 {
+        self.total_input_tokens() + self.output_tokens
+    }
+
+    /// Whether `cost_usd` covers every turn
+    pub fn cost_is_complete(&self) -> bool {
+        self.unpriced_turns == 0
+    }
+}
+
+/// Per-session spending limits.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Budget {
+    /// Maximum total tokens (input, cache and output)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u64>,
+    /// Maximum estimated cost in USD
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_cost_usd: Option<f64>,
+}
+
+impl Budget {
+    /// Budget limited by total tokens
+    pub fn tokens(max_tokens: u64) -> Self {
+        Self {
+            max_tokens: Some(max_tokens),
+            max_cost_usd: None,
+        }
+    }
+
+    /// Budget limited by estimated cost
+    pub fn cost_usd(max_cost_usd: f64) -> Self {
+        Self {
+            max_tokens: None,
+            max_cost_usd: Some(max_cost_usd),
+        }
+    }
+
+    /// Add a token limit
+    pub fn with_max_tokens(mut self, max_tokens: u64) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// Add a cost limit
+    pub fn with_max_cost_usd(mut self, max_cost_usd: f64) -> Self {
+        self.max_cost_usd = Some(max_cost_usd);
+        self
+    }
+
+    /// Check a usage summary against this budget.
+    ///
+    /// Usage equal to a limit is allowed; going past it is not.
+    pub fn check(&self, usage: &UsageSummary) -> Result<()> {
+        if let Some(limit) = self.max_tokens {
+            let used = usage.total_tokens();
+            if used > limit {
+                return Err(AnthropicError::BudgetExceeded(BudgetLimit::Tokens {
+                    limit,
+                    used,
+                }));
+            }
+        }
+        if let Some(limit) = self.max_cost_usd {
+            if usage.cost_usd > limit {
+                return Err(AnthropicError::BudgetExceeded(BudgetLimit::CostUsd {
+                    limit,
+                    used: usage.cost_usd,
+                }));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether usage has reached a limit, so no further turn should be sent
+    pub fn is_exhausted(&self, usage: &UsageSummary) -> bool {
+        self.max_tokens
+            .is_some_and(|limit| usage.total_tokens() >= limit)
+            || self
+                .max_cost_usd
+                .is_some_and(|limit| usage.cost_usd >= limit)
+    }
+
+    /// Fail with [`AnthropicError::BudgetExceeded`] if usage has reached a
+    /// limit, so no further request should be sent
+    pub(crate) fn check_available(&self, usage: &UsageSummary) -> Result<()> {
+        if !self.is_exhausted(usage) {
+            return Ok(());
+        }
+        let limit = match self.max_tokens {
+            Some(limit) if usage.total_tokens() >= limit => BudgetLimit::Tokens {
+                limit,
+                used: usage.total_tokens(),
+            },
+            _ => BudgetLimit::CostUsd {
+                limit: self.max_cost_usd.unwrap_or_default(),
+                used: usage.cost_usd,
+            },
+        };
+        Err(AnthropicError::BudgetExceeded(limit))
+    }
+}
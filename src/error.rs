@@ -23,6 +23,9 @@ pub enum AnthropicError {
         status: u16,
         message: String,
         error_type: Option<String>,
+        /// How long the API asked clients to wait, from `retry-after` or the
+        /// rate limit reset headers
+        retry_after: Option<Duration>,
     },
 
     /// Configuration error
@@ -57,6 +60,10 @@ pub enum AnthropicError {
     #[error("Request timeout: {0:?}")]
     Timeout(Duration),
 
+    /// A stream received no events, not even `ping`s, for this long
+    #[error("Stream idle for {0:?}")]
+    StreamIdle(Duration),
+
     /// I/O error
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
@@ -65,11 +72,162 @@ pub enum AnthropicError {
     #[error("Base64 decode error: {0}")]
     Base64Decode(#[from] base64::DecodeError),
 
+    /// Optimistic-concurrency check failed when saving a stored record
+    #[error("Version conflict for {id}: expected {}, found {}", fmt_version(*expected), fmt_version(*actual))]
+    VersionConflict {
+        id: String,
+        expected: Option<u64>,
+        actual: Option<u64>,
+    },
+
+    /// Session token or cost budget exceeded
+    #[error("Budget exceeded: {0}")]
+    BudgetExceeded(BudgetLimit),
+
+    /// A tool-use run hit one of its
+    /// [`RunLimits`](crate::tools::registry::RunLimits); carries the partial
+    /// progress
+    #[error("Run limit exceeded: {}", .0.limit)]
+    RunLimitExceeded(Box<crate::tools::registry::RunLimitExceeded>),
+
+    /// Transferred content does not match its checksum
+    #[error("Integrity check failed: {0}")]
+    Integrity(IntegrityError),
+
+    /// A response body passed its configured size limit and was abandoned
+    #[error("Response too large: {class} body exceeds the {limit} byte limit")]
+    ResponseTooLarge {
+        /// Which limit applied
+        class: ResponseClass,
+        /// The limit, in bytes
+        limit: u64,
+    },
+
+    /// The retry client's circuit breaker is open after repeated failures
+    #[error("Circuit breaker open; retry after {retry_after:?}")]
+    CircuitOpen {
+        /// Time until a probe request will be let through
+        retry_after: Duration,
+    },
+
+    /// The request or stream was cancelled through its
+    /// [`CancellationToken`](tokio_util::sync::CancellationToken)
+    #[error("Request cancelled")]
+    Cancelled,
+
     /// Generic error
     #[error("Unknown error: {0}")]
     Unknown(#[from] anyhow::Error),
 }
 
+fn fmt_version(version: Option<u64>) -> String {
+    version.map_or_else(|| "none".to_string(), |v| format!("v{}", v))
+}
+
+/// The budget limit that was exceeded, with the amount used so far
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BudgetLimit {
+    /// Total (input + output + cache) token limit
+    Tokens {
+        /// Configured limit
+        limit: u64,
+        /// Tokens used
+        used: u64,
+    },
+    /// Estimated cost limit in USD
+    CostUsd {
+        /// Configured limit
+        limit: f64,
+        /// Estimated spend
+        used: f64,
+    },
+}
+
+impl std::fmt::Display for BudgetLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tokens { limit, used } => {
+                write!(f, "used {} tokens of a {} token budget", used, limit)
+            }
+            Self::CostUsd { limit, used } => {
+                write!(f, "spent ${:.4} of a ${:.4} budget", used, limit)
+            }
+        }
+    }
+}
+
+/// Checksum algorithms used for transfer integrity checks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChecksumAlgorithm {
+    /// MD5, as sent in `Content-MD5`
+    Md5,
+    /// SHA-256, as sent in `Content-Digest` / `Repr-Digest`
+    Sha256,
+}
+
+impl std::fmt::Display for ChecksumAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Md5 => "MD5",
+            Self::Sha256 => "SHA-256",
+        })
+    }
+}
+
+/// Kinds of response body, each with its own size limit in
+/// [`ResponseLimits`](crate::utils::http::ResponseLimits)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResponseClass {
+    /// JSON body of an ordinary API call
+    Api,
+    /// Body of an error response
+    Error,
+    /// File download or raw batch results
+    Download,
+    /// One server-sent event, or one line of streamed batch results
+    StreamEvent,
+}
+
+impl std::fmt::Display for ResponseClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Api => "API response",
+            Self::Error => "error response",
+            Self::Download => "download",
+            Self::StreamEvent => "stream event",
+        })
+    }
+}
+
+/// A checksum mismatch on transferred content
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntegrityError {
+    /// What was being transferred, e.g. `file file_123`
+    pub resource: String,
+    /// Algorithm of the failing checksum
+    pub algorithm: ChecksumAlgorithm,
+    /// Checksum the other side declared (base64, as received)
+    pub expected: String,
+    /// Checksum of the bytes actually received (base64)
+    pub actual: String,
+}
+
+impl std::fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} mismatch for {}: expected {}, got {}",
+            self.algorithm, self.resource, self.expected, self.actual
+        )
+    }
+}
+
+impl From<IntegrityError> for AnthropicError {
+    fn from(err: IntegrityError) -> Self {
+        Self::Integrity(err)
+    }
+}
+
 impl AnthropicError {
     /// Create a new API error
     pub fn api_error(status: u16, message: String, error_type: Option<String>) -> Self {
@@ -77,6 +235,7 @@ impl AnthropicError {
             status,
             message,
             error_type,
+            retry_after: None,
         }
     }
 
@@ -129,10 +288,11 @@ impl AnthropicError {
     pub fn is_retryable(&self) -> bool {
         match self {
             Self::Http(e) => e.is_timeout() || e.is_connect(),
-            Self::Api { status, .. } => matches!(status, 429 | 500 | 502 | 503 | 504),
+            Self::Api { status, .. } => matches!(status, 429 | 500 | 502 | 503 | 504 | 529),
             Self::RateLimit(_) => true,
             Self::Network(_) => true,
             Self::Timeout(_) => true,
+            Self::StreamIdle(_) => true,
             _ => false,
         }
     }
@@ -153,6 +313,24 @@ impl AnthropicError {
         }
     }
 
+    /// Record the delay the API advised before retrying
+    pub fn with_retry_after(mut self, delay: Option<Duration>) -> Self {
+        if let Self::Api { retry_after, .. } = &mut self {
+            *retry_after = delay;
+        }
+        self
+    }
+
+    /// How long to wait before retrying, when the API or the circuit breaker
+    /// said so
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::Api { retry_after, .. } => *retry_after,
+            Self::CircuitOpen { retry_after } => Some(*retry_after),
+            _ => None,
+        }
+    }
+
     /// Get the HTTP status code if available
     pub fn status_code(&self) -> Option<u16> {
         match self {
@@ -178,10 +356,12 @@ impl AnthropicError {
                 status,
                 message,
                 error_type,
+                retry_after,
             } => Self::Api {
                 status,
                 message: format!("{}: {}", context, message),
                 error_type,
+                retry_after,
             },
             other => other, // For variants without string messages, return as-is
         }
@@ -215,6 +395,7 @@ mod tests {
             status,
             message,
             error_type,
+            ..
         } = error
         {
             assert_eq!(status, 404);
@@ -233,6 +414,7 @@ mod tests {
             status,
             message,
             error_type,
+            ..
         } = error
         {
             assert_eq!(status, 500);
@@ -517,6 +699,7 @@ mod tests {
             status,
             message,
             error_type,
+            ..
         } = api_error
         {
             assert_eq!(status, 400);
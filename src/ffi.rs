@@ -0,0 +1,259 @@
+//! C ABI for embedding the SDK in other languages, enabled by the `ffi`
+//! feature
+//!
+//! Requests and responses cross the boundary as JSON strings in the shapes of
+//! the Messages API, so a binding only needs a JSON library:
+//!
+//! ```c
+//! #include "threatflux_anthropic.h"
+//!
+//! AnthropicClient *client = anthropic_client_new(NULL, NULL); // ANTHROPIC_API_KEY
+//! char *response = anthropic_messages_create(client,
+//!     "{\"model\":\"claude-haiku-4-5\",\"max_tokens\":256,"
+//!     "\"messages\":[{\"role\":\"user\","
+//!     "\"content\":[{\"type\":\"text\",\"text\":\"Hello\"}]}]}");
+//! if (response == NULL) {
+//!     fprintf(stderr, "%s\n", anthropic_last_error());
+//! }
+//! anthropic_string_free(response);
+//! anthropic_client_free(client);
+//! ```
+//!
+//! Build a shared or static library with
+//! `cargo rustc --release --features ffi --crate-type cdylib` (or
+//! `staticlib`); the header in `include/` is generated from this module with
+//! `cbindgen --config cbindgen.toml --output include/threatflux_anthropic.h src/ffi.rs`.
+//!
+//! Every function catches panics, so none unwinds into the caller. Failures
+//! return `NULL` or a negative status and leave a message for
+//! [`anthropic_last_error`] on the calling thread.
+
+use crate::{
+    client::Client,
+    config::Config,
+    error::{AnthropicError, Result},
+    models::message::MessageRequest,
+};
+use futures::StreamExt;
+use std::{
+    cell::RefCell,
+    ffi::{c_char, c_int, c_void, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Status returned on success
+pub const ANTHROPIC_OK: c_int = 0;
+/// Status returned when a call failed; see [`anthropic_last_error`]
+pub const ANTHROPIC_ERROR: c_int = -1;
+
+/// A client and the runtime its calls are driven on. Opaque to C.
+pub struct AnthropicClient {
+    client: Client,
+    runtime: tokio::runtime::Runtime,
+}
+
+/// Called with each stream event as a JSON string, valid only during the
+/// call. Return non-zero to stop the stream early.
+pub type AnthropicStreamCallback =
+    Option<unsafe extern "C" fn(event_json: *const c_char, user_data: *mut c_void) -> c_int>;
+
+fn set_last_error(message: impl Into<String>) {
+    let message = message.into().replace('\0', "\\0");
+    let message = CString::new(message).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Run `call`, turning an error or a panic into `fallback` plus a last error
+fn guard<T>(fallback: T, call: impl FnOnce() -> Result<T>) -> T {
+    match panic::catch_unwind(AssertUnwindSafe(call)) {
+        Ok(Ok(value)) => value,
+        Ok(Err(error)) => {
+            set_last_error(error.to_string());
+            fallback
+        }
+        Err(payload) => {
+            let reason = payload
+                .downcast_ref::<&str>()
+                .map(|reason| reason.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            set_last_error(format!("panic in SDK: {}", reason));
+            fallback
+        }
+    }
+}
+
+/// # Safety
+///
+/// `value` must be null or a valid NUL-terminated string.
+unsafe fn optional_str<'a>(value: *const c_char, name: &str) -> Result<Option<&'a str>> {
+    if value.is_null() {
+        return Ok(None);
+    }
+    CStr::from_ptr(value)
+        .to_str()
+        .map(Some)
+        .map_err(|_| AnthropicError::invalid_input(format!("{} is not valid UTF-8", name)))
+}
+
+/// # Safety
+///
+/// `value` must be null or a valid NUL-terminated string.
+unsafe fn required_str<'a>(value: *const c_char, name: &str) -> Result<&'a str> {
+    optional_str(value, name)?
+        .ok_or_else(|| AnthropicError::invalid_input(format!("{} is null", name)))
+}
+
+/// # Safety
+///
+/// `client` must be null or a pointer from [`anthropic_client_new`] that has
+/// not been freed.
+unsafe fn client_ref<'a>(client: *const AnthropicClient) -> Result<&'a AnthropicClient> {
+    client
+        .as_ref()
+        .ok_or_else(|| AnthropicError::invalid_input("client is null"))
+}
+
+fn into_c_string(value: String) -> Result<*mut c_char> {
+    CString::new(value)
+        .map(CString::into_raw)
+        .map_err(|_| AnthropicError::json("response contains a NUL byte"))
+}
+
+/// Create a client for `api_key`, or from the `ANTHROPIC_*` environment
+/// variables when it is null. `base_url` overrides the API endpoint when not
+/// null. Returns null on failure.
+///
+/// # Safety
+///
+/// `api_key` and `base_url` must each be null or a valid NUL-terminated
+/// string.
+#[no_mangle]
+pub unsafe extern "C" fn anthropic_client_new(
+    api_key: *const c_char,
+    base_url: *const c_char,
+) -> *mut AnthropicClient {
+    guard(ptr::null_mut(), || {
+        let mut config = match optional_str(api_key, "api_key")? {
+            Some(api_key) => Config::new(api_key)?,
+            None => Config::from_env()?,
+        };
+        if let Some(base_url) = optional_str(base_url, "base_url")? {
+            config = config.with_base_url(base_url.parse()?);
+        }
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| AnthropicError::config(format!("Failed to start runtime: {}", e)))?;
+        let client = Client::try_new(config)?;
+        Ok(Box::into_raw(Box::new(AnthropicClient { client, runtime })))
+    })
+}
+
+/// Free a client. Null is ignored.
+///
+/// # Safety
+///
+/// `client` must be null or a pointer from [`anthropic_client_new`] that has
+/// not been freed, and no other call may be using it.
+#[no_mangle]
+pub unsafe extern "C" fn anthropic_client_free(client: *mut AnthropicClient) {
+    guard((), || {
+        if !client.is_null() {
+            drop(Box::from_raw(client));
+        }
+        Ok(())
+    })
+}
+
+/// Send a message request, given as Messages API JSON, and wait for the
+/// response JSON. Returns null on failure; free the result with
+/// [`anthropic_string_free`].
+///
+/// # Safety
+///
+/// `client` must come from [`anthropic_client_new`] and `request_json` must be
+/// a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn anthropic_messages_create(
+    client: *const AnthropicClient,
+    request_json: *const c_char,
+) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        let client = client_ref(client)?;
+        let request: MessageRequest =
+            serde_json::from_str(required_str(request_json, "request_json")?)?;
+        let response = client
+            .runtime
+            .block_on(client.client.messages().create(request, None))?;
+        into_c_string(serde_json::to_string(&response)?)
+    })
+}
+
+/// Stream a message request, calling `callback` with each event's JSON until
+/// the message ends, the callback returns non-zero, or an error occurs.
+/// Returns [`ANTHROPIC_OK`] or [`ANTHROPIC_ERROR`].
+///
+/// # Safety
+///
+/// `client` must come from [`anthropic_client_new`], `request_json` must be a
+/// valid NUL-terminated string, and `callback` must be safe to call with
+/// `user_data` from the calling thread.
+#[no_mangle]
+pub unsafe extern "C" fn anthropic_messages_stream(
+    client: *const AnthropicClient,
+    request_json: *const c_char,
+    callback: AnthropicStreamCallback,
+    user_data: *mut c_void,
+) -> c_int {
+    guard(ANTHROPIC_ERROR, || {
+        let client = client_ref(client)?;
+        let callback = callback.ok_or_else(|| AnthropicError::invalid_input("callback is null"))?;
+        let request: MessageRequest =
+            serde_json::from_str(required_str(request_json, "request_json")?)?;
+        client.runtime.block_on(async {
+            let mut stream = client
+                .client
+                .messages()
+                .create_stream(request, None)
+                .await?;
+            while let Some(event) = stream.next().await {
+                let event = CString::new(serde_json::to_string(&event?)?)
+                    .map_err(|_| AnthropicError::json("event contains a NUL byte"))?;
+                if callback(event.as_ptr(), user_data) != 0 {
+                    break;
+                }
+            }
+            Ok(ANTHROPIC_OK)
+        })
+    })
+}
+
+/// The last error on this thread, or null if there was none. The string
+/// stays valid until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn anthropic_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Free a string returned by this library. Null is ignored.
+///
+/// # Safety
+///
+/// `value` must be null or a string returned by this library that has not
+/// been freed.
+#[no_mangle]
+pub unsafe extern "C" fn anthropic_string_free(value: *mut c_char) {
+    if !value.is_null() {
+        drop(CString::from_raw(value));
+    }
+}
@@ -58,7 +58,7 @@
 //!     while let Some(event) = stream.next().await {
 //!         match event? {
 //!             threatflux_anthropic_sdk::models::StreamEvent::ContentBlockDelta { delta, .. } => {
-//!                 if let Some(text) = delta.text {
+//!                 if let Some(text) = delta.as_text() {
 //!                     print!("{}", text);
 //!                 }
 //!             }
@@ -88,19 +88,46 @@
 //! ```
 
 pub mod api;
+#[cfg(feature = "blocking")]
+pub mod blocking;
 pub mod builders;
 pub mod client;
 pub mod config;
+pub mod conversation;
 pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod metrics;
 pub mod models;
+pub mod pipelines;
+pub mod priority;
+pub mod prompt_cache;
+pub mod request_scope;
+pub mod scope;
+#[cfg(feature = "tower")]
+pub mod service;
 pub mod streaming;
+pub mod tools;
 pub mod types;
+pub mod user_context;
 pub mod utils;
+pub mod warnings;
 
 // Re-export main types for convenience
 pub use client::Client;
-pub use config::{Config, DEFAULT_MODEL};
-pub use error::{AnthropicError, Result};
+pub use config::{AppInfo, Config, SecretKey, DEFAULT_MODEL};
+pub use conversation::{
+    Annotation, Budget, Conversation, ConversationStore, TurnAnnotation, UsageSummary,
+};
+pub use error::{
+    AnthropicError, BudgetLimit, ChecksumAlgorithm, IntegrityError, ResponseClass, Result,
+};
+pub use priority::{PriorityContext, PriorityLayer};
+pub use prompt_cache::{CacheTtl, CachedPrefix};
+pub use request_scope::{RequestScope, ScopedTask};
+pub use scope::ScopedClient;
+#[cfg(feature = "tower")]
+pub use service::MessagesService;
 
 // Re-export commonly used model types
 pub use models::{
@@ -191,9 +218,12 @@ pub use models::{
     ModelSize,
     // Admin types
     Organization,
+    // Refusal handling
+    Outcome,
     OutputConfig,
     OutputEffort,
     OutputFormat,
+    RefusalPolicy,
     Role,
     SendEvent,
     Session,
@@ -223,6 +253,7 @@ pub use models::{
     SystemPrompt,
     TaskBudget,
     ThinkingConfig,
+    TimeRange,
     TokenCountRequest,
     TokenCountResponse,
     Tool,
@@ -256,12 +287,12 @@ pub use models::{
 
 // Re-export utility types
 pub use types::{
-    ApiErrorResponse, HttpMethod, ModelCapability, PaginatedResponse, Pagination, RequestOptions,
-    RequestPriority,
+    ApiErrorResponse, Concurrency, Cursor, HttpMethod, ModelCapability, PaginatedResponse,
+    Pagination, RequestOptions, RequestPriority,
 };
 
 // Re-export streaming types
-pub use streaming::{EventParser, MessageStream, SessionEventStream};
+pub use streaming::{EventParser, MessageAccumulator, MessageStream, SessionEventStream};
 
 // Re-export builders
 pub use builders::{batch_builder::BatchBuilder, message_builder::MessageBuilder};
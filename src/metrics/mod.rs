@@ -0,0 +1,206 @@
+//! Metrics about what the SDK is doing
+//!
+//! A [`MetricsRecorder`] installed with
+//! [`Config::with_metrics_recorder`](crate::config::Config::with_metrics_recorder)
+//! is told about every HTTP attempt, token usage, retry, rate-limit wait and
+//! message stream; with the `otel` feature, [`OtelMetrics`] reports them to
+//! OpenTelemetry.
+//!
+//! Separately, streams and downloads hold response bytes in memory while
+//! they parse them. Each one reports how much it is holding, so a service
+//! running hundreds of concurrent generations can see how much memory they
+//! take together and at peak:
+//!
+//! ```rust
+//! use threatflux_anthropic_sdk::metrics::{self, BufferKind};
+//!
+//! let streams = metrics::buffer_stats(BufferKind::MessageStream);
+//! println!(
+//!     "{} streams holding {} bytes (peak {}, largest single {})",
+//!     streams.active, streams.current_bytes, streams.peak_bytes, streams.largest_bytes
+//! );
+//! ```
+//!
+//! A single [`MessageStream`](crate::streaming::MessageStream) also reports
+//! its own peak in [`StreamActivity`](crate::streaming::StreamActivity).
+
+#[cfg(feature = "otel")]
+mod otel;
+mod recorder;
+
+#[cfg(feature = "otel")]
+pub use otel::OtelMetrics;
+pub(crate) use recorder::endpoint;
+pub use recorder::MetricsRecorder;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// What is holding response bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BufferKind {
+    /// Message streams, including streams behind non-streaming helpers
+    MessageStream,
+    /// Managed Agents session event streams
+    SessionStream,
+    /// Batch results, downloaded whole or streamed line by line
+    BatchResults,
+    /// File downloads
+    FileDownload,
+}
+
+impl BufferKind {
+    /// Every kind
+    pub const ALL: [BufferKind; 4] = [
+        Self::MessageStream,
+        Self::SessionStream,
+        Self::BatchResults,
+        Self::FileDownload,
+    ];
+
+    fn counters(self) -> &'static Counters {
+        &COUNTERS[self as usize]
+    }
+}
+
+/// Buffering totals for one [`BufferKind`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BufferStats {
+    /// Buffers currently open
+    pub active: u64,
+    /// Bytes held by all open buffers now
+    pub current_bytes: u64,
+    /// Highest `current_bytes` seen since the last [`reset_peaks`]
+    pub peak_bytes: u64,
+    /// Most bytes a single buffer held at once since the last
+    /// [`reset_peaks`]
+    pub largest_bytes: u64,
+}
+
+struct Counters {
+    active: AtomicU64,
+    current: AtomicU64,
+    peak: AtomicU64,
+    largest: AtomicU64,
+}
+
+impl Counters {
+    const fn new() -> Self {
+        Self {
+            active: AtomicU64::new(0),
+            current: AtomicU64::new(0),
+            peak: AtomicU64::new(0),
+            largest: AtomicU64::new(0),
+        }
+    }
+}
+
+static COUNTERS: [Counters; 4] = [
+    Counters::new(),
+    Counters::new(),
+    Counters::new(),
+    Counters::new(),
+];
+
+/// Current totals for `kind`
+pub fn buffer_stats(kind: BufferKind) -> BufferStats {
+    let counters = kind.counters();
+    BufferStats {
+        active: counters.active.load(Ordering::Relaxed),
+        current_bytes: counters.current.load(Ordering::Relaxed),
+        peak_bytes: counters.peak.load(Ordering::Relaxed),
+        largest_bytes: counters.largest.load(Ordering::Relaxed),
+    }
+}
+
+/// Start the peaks of every kind again from the current values, e.g. at the
+/// start of each reporting interval
+pub fn reset_peaks() {
+    for kind in BufferKind::ALL {
+        let counters = kind.counters();
+        counters
+            .peak
+            .store(counters.current.load(Ordering::Relaxed), Ordering::Relaxed);
+        counters.largest.store(0, Ordering::Relaxed);
+    }
+}
+
+/// One open buffer's contribution to the totals; removed when dropped
+#[derive(Debug)]
+pub(crate) struct BufferGauge {
+    kind: BufferKind,
+    bytes: u64,
+    peak: u64,
+}
+
+impl BufferGauge {
+    pub(crate) fn new(kind: BufferKind) -> Self {
+        kind.counters().active.fetch_add(1, Ordering::Relaxed);
+        Self {
+            kind,
+            bytes: 0,
+            peak: 0,
+        }
+    }
+
+    /// Record that the buffer now holds `bytes`
+    pub(crate) fn set(&mut self, bytes: usize) {
+        let bytes = bytes as u64;
+        let counters = self.kind.counters();
+        if bytes >= self.bytes {
+            let total = counters
+                .current
+                .fetch_add(bytes - self.bytes, Ordering::Relaxed)
+                + (bytes - self.bytes);
+            counters.peak.fetch_max(total, Ordering::Relaxed);
+        } else {
+            counters
+                .current
+                .fetch_sub(self.bytes - bytes, Ordering::Relaxed);
+        }
+        counters.largest.fetch_max(bytes, Ordering::Relaxed);
+        self.bytes = bytes;
+        self.peak = self.peak.max(bytes);
+    }
+
+    /// Most bytes this buffer has held
+    pub(crate) fn peak(&self) -> u64 {
+        self.peak
+    }
+}
+
+impl Drop for BufferGauge {
+    fn drop(&mut self) {
+        let counters = self.kind.counters();
+        counters.current.fetch_sub(self.bytes, Ordering::Relaxed);
+        counters.active.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gauges_track_current_and_peak() {
+        // Other tests download files concurrently, so only this kind's
+        // counters are asserted on
+        let kind = BufferKind::FileDownload;
+        let before = buffer_stats(kind);
+
+        let mut first = BufferGauge::new(kind);
+        let mut second = BufferGauge::new(kind);
+        first.set(1000);
+        second.set(500);
+        first.set(200);
+        assert_eq!(first.peak(), 1000);
+
+        let during = buffer_stats(kind);
+        assert!(during.active >= before.active + 2);
+        assert!(during.peak_bytes >= 1500);
+        assert!(during.largest_bytes >= 1000);
+
+        drop(first);
+        drop(second);
+        assert_eq!(buffer_stats(kind).active, before.active);
+    }
+}
@@ -0,0 +1,133 @@
+//! OpenTelemetry instruments for [`MetricsRecorder`]
+
+use super::MetricsRecorder;
+use crate::models::common::Usage;
+use opentelemetry::{
+    metrics::{Counter, Histogram, Meter},
+    KeyValue,
+};
+use std::time::Duration;
+
+/// [`MetricsRecorder`] reporting to OpenTelemetry instruments:
+///
+/// | Instrument | Kind | Attributes |
+/// |---|---|---|
+/// | `anthropic.requests` | counter | `endpoint`, `status` (0 without a response) |
+/// | `anthropic.request.duration` | histogram, s | `endpoint` |
+/// | `anthropic.tokens` | counter | `model`, `type` (`input`, `output`, `cache_read`, `cache_creation`) |
+/// | `anthropic.retries` | counter | `endpoint` |
+/// | `anthropic.rate_limit.wait` | histogram, s | |
+/// | `anthropic.stream.duration` | histogram, s | `model` |
+///
+/// ```rust,no_run
+/// use threatflux_anthropic_sdk::{metrics::OtelMetrics, Client, Config};
+///
+/// # fn example() -> threatflux_anthropic_sdk::Result<()> {
+/// let meter = opentelemetry::global::meter("my-service");
+/// let config = Config::from_env()?.with_metrics_recorder(OtelMetrics::new(&meter));
+/// let client = Client::new(config);
+/// # let _ = client;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct OtelMetrics {
+    requests: Counter<u64>,
+    request_duration: Histogram<f64>,
+    tokens: Counter<u64>,
+    retries: Counter<u64>,
+    rate_limit_wait: Histogram<f64>,
+    stream_duration: Histogram<f64>,
+}
+
+impl OtelMetrics {
+    /// Instruments created on `meter`
+    pub fn new(meter: &Meter) -> Self {
+        Self {
+            requests: meter
+                .u64_counter("anthropic.requests")
+                .with_description("HTTP attempts made to the Anthropic API")
+                .build(),
+            request_duration: meter
+                .f64_histogram("anthropic.request.duration")
+                .with_description("Time until the response headers arrived")
+                .with_unit("s")
+                .build(),
+            tokens: meter
+                .u64_counter("anthropic.tokens")
+                .with_description("Tokens used by messages")
+                .build(),
+            retries: meter
+                .u64_counter("anthropic.retries")
+                .with_description("Requests retried after a failure")
+                .build(),
+            rate_limit_wait: meter
+                .f64_histogram("anthropic.rate_limit.wait")
+                .with_description("Time spent waiting on rate limits")
+                .with_unit("s")
+                .build(),
+            stream_duration: meter
+                .f64_histogram("anthropic.stream.duration")
+                .with_description("Time message streams were open")
+                .with_unit("s")
+                .build(),
+        }
+    }
+
+    /// Instruments created on the global meter provider's
+    /// `threatflux-anthropic-sdk` meter
+    pub fn global() -> Self {
+        Self::new(&opentelemetry::global::meter("threatflux-anthropic-sdk"))
+    }
+}
+
+impl MetricsRecorder for OtelMetrics {
+    fn request(&self, endpoint: &str, status: Option<u16>, latency: Duration) {
+        let endpoint = KeyValue::new("endpoint", endpoint.to_string());
+        self.requests.add(
+            1,
+            &[
+                endpoint.clone(),
+                KeyValue::new("status", i64::from(status.unwrap_or(0))),
+            ],
+        );
+        self.request_duration
+            .record(latency.as_secs_f64(), &[endpoint]);
+    }
+
+    fn tokens(&self, model: &str, usage: &Usage) {
+        let counts = [
+            ("input", usage.input_tokens),
+            ("output", usage.output_tokens),
+            ("cache_read", usage.cache_read_input_tokens),
+            ("cache_creation", usage.cache_creation_input_tokens),
+        ];
+        for (kind, count) in counts {
+            if count > 0 {
+                self.tokens.add(
+                    u64::from(count),
+                    &[
+                        KeyValue::new("model", model.to_string()),
+                        KeyValue::new("type", kind),
+                    ],
+                );
+            }
+        }
+    }
+
+    fn retry(&self, endpoint: &str, _attempt: u32) {
+        self.retries
+            .add(1, &[KeyValue::new("endpoint", endpoint.to_string())]);
+    }
+
+    fn rate_limit_wait(&self, waited: Duration) {
+        self.rate_limit_wait.record(waited.as_secs_f64(), &[]);
+    }
+
+    fn stream(&self, model: &str, duration: Duration) {
+        self.stream_duration.record(
+            duration.as_secs_f64(),
+            &[KeyValue::new("model", model.to_string())],
+        );
+    }
+}
@@ -0,0 +1,90 @@
+//! Pluggable recording of request, token, retry and stream measurements
+
+use crate::models::common::Usage;
+use std::{fmt, time::Duration};
+use url::Url;
+
+/// Receives measurements of what a [`Client`](crate::Client) does.
+///
+/// Install one with
+/// [`Config::with_metrics_recorder`](crate::config::Config::with_metrics_recorder)
+/// to feed your metrics system; with the `otel` feature,
+/// [`OtelMetrics`](super::OtelMetrics) forwards to OpenTelemetry. Every
+/// method does nothing by default, so implement only what you need. Methods
+/// are called inline on the request path and should return quickly.
+pub trait MetricsRecorder: Send + Sync {
+    /// An HTTP attempt finished: `status` is `None` when no response arrived.
+    /// `endpoint` is the URL path with ids replaced by `{id}`, e.g.
+    /// `/v1/messages/batches/{id}`.
+    fn request(&self, endpoint: &str, status: Option<u16>, latency: Duration) {
+        let _ = (endpoint, status, latency);
+    }
+
+    /// A message finished and used `usage`
+    fn tokens(&self, model: &str, usage: &Usage) {
+        let _ = (model, usage);
+    }
+
+    /// A request to `endpoint` is being retried; `attempt` is the attempt
+    /// about to be made, starting at 2
+    fn retry(&self, endpoint: &str, attempt: u32) {
+        let _ = (endpoint, attempt);
+    }
+
+    /// A request waited `waited` because of a rate limit, either the
+    /// client-side limiter or the API's `retry-after`
+    fn rate_limit_wait(&self, waited: Duration) {
+        let _ = waited;
+    }
+
+    /// A message stream for `model` was open for `duration`
+    fn stream(&self, model: &str, duration: Duration) {
+        let _ = (model, duration);
+    }
+}
+
+impl fmt::Debug for dyn MetricsRecorder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("MetricsRecorder")
+    }
+}
+
+/// `url`'s path with every segment that holds an id replaced by `{id}`, to
+/// keep the endpoint label's cardinality low
+pub(crate) fn endpoint(url: &Url) -> String {
+    let mut endpoint = String::new();
+    for segment in url.path().split('/').filter(|segment| !segment.is_empty()) {
+        let version = segment
+            .strip_prefix('v')
+            .is_some_and(|rest| rest.chars().all(|c| c.is_ascii_digit()));
+        endpoint.push('/');
+        if !version && segment.chars().any(|c| c.is_ascii_digit()) {
+            endpoint.push_str("{id}");
+        } else {
+            endpoint.push_str(segment);
+        }
+    }
+    endpoint
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint_hides_ids() {
+        let endpoint = |url: &str| endpoint(&Url::parse(url).unwrap());
+        assert_eq!(
+            endpoint("https://api.anthropic.com/v1/messages/count_tokens"),
+            "/v1/messages/count_tokens"
+        );
+        assert_eq!(
+            endpoint("https://api.anthropic.com/v1/messages/batches/msgbatch_01abc/results"),
+            "/v1/messages/batches/{id}/results"
+        );
+        assert_eq!(
+            endpoint("https://api.anthropic.com/v1/models/claude-haiku-4-5?limit=1"),
+            "/v1/models/{id}"
+        );
+    }
+}
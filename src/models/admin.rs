@@ -1,8 +1,9 @@
 //! Admin API data models
 
 use super::common::VecPush;
+use crate::error::{AnthropicError, Result};
 use crate::types::PaginatedResponse;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, NaiveTime, Offset, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -23,8 +24,10 @@ pub struct Organization {
     /// Organization settings
     pub settings: Option<OrganizationSettings>,
     /// When the organization was created
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub created_at: Option<DateTime<Utc>>,
     /// When the organization was last updated
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub updated_at: Option<DateTime<Utc>>,
     /// Additional fields not yet modeled explicitly.
     #[serde(flatten, default)]
@@ -58,6 +61,7 @@ pub struct User {
     /// Organization role.
     pub role: UserRole,
     /// Time this user was added to the organization.
+    #[serde(with = "crate::utils::timestamp")]
     pub added_at: DateTime<Utc>,
 }
 
@@ -170,13 +174,13 @@ pub struct Member {
     /// Member status
     pub status: MemberStatus,
     /// When the member was invited
-    #[serde(default)]
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub invited_at: Option<DateTime<Utc>>,
     /// When the member joined
-    #[serde(default)]
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub joined_at: Option<DateTime<Utc>>,
     /// When the member was last active
-    #[serde(default)]
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub last_active_at: Option<DateTime<Utc>>,
 }
 
@@ -304,8 +308,10 @@ pub struct Invite {
     /// Invitee email.
     pub email: String,
     /// Invite expiration timestamp.
+    #[serde(with = "crate::utils::timestamp")]
     pub expires_at: DateTime<Utc>,
     /// Invite creation timestamp.
+    #[serde(with = "crate::utils::timestamp")]
     pub invited_at: DateTime<Utc>,
     /// Role granted when accepted.
     pub role: UserRole,
@@ -460,10 +466,13 @@ pub struct Workspace {
     #[serde(default)]
     pub data_residency: Option<WorkspaceDataResidency>,
     /// When the workspace was created
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub created_at: Option<DateTime<Utc>>,
     /// When the workspace was last updated
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub updated_at: Option<DateTime<Utc>>,
     /// When the workspace was archived (if applicable)
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub archived_at: Option<DateTime<Utc>>,
     /// Additional fields not yet modeled explicitly.
     #[serde(flatten, default)]
@@ -834,10 +843,13 @@ pub struct ApiKey {
     /// Rate limits for this key
     pub rate_limits: Option<HashMap<String, u32>>,
     /// When the key was created
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub created_at: Option<DateTime<Utc>>,
     /// When the key was last used
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub last_used_at: Option<DateTime<Utc>>,
     /// When the key expires
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub expires_at: Option<DateTime<Utc>>,
     /// Additional fields not yet modeled explicitly.
     #[serde(flatten, default)]
@@ -856,6 +868,7 @@ pub struct ApiKeyCreateRequest {
     /// Rate limits
     pub rate_limits: Option<HashMap<String, u32>>,
     /// Expiration date
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub expires_at: Option<DateTime<Utc>>,
 }
 
@@ -1040,8 +1053,10 @@ pub struct UsageReport {
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct UsagePeriod {
     /// Period start time
+    #[serde(with = "crate::utils::timestamp")]
     pub period_start: DateTime<Utc>,
     /// Period end time
+    #[serde(with = "crate::utils::timestamp")]
     pub period_end: DateTime<Utc>,
     /// Input tokens used in this period
     pub input_tokens: u64,
@@ -1077,12 +1092,151 @@ pub struct CostInfo {
     pub currency: String,
 }
 
+/// A half-open `[start, end)` reporting window for usage and cost reports.
+///
+/// Ranges are always non-empty: [`TimeRange::between`] rejects an end that
+/// does not come after the start. Each range remembers the UTC offset it was
+/// built in, which decides the calendar days reported by
+/// [`start_date`](TimeRange::start_date) and [`end_date`](TimeRange::end_date)
+/// (used by the date-based Claude Code report).
+///
+/// ```rust
+/// use chrono::FixedOffset;
+/// use threatflux_anthropic_sdk::models::{MessageUsageReportParams, TimeRange};
+///
+/// let pacific = FixedOffset::west_opt(8 * 3600).unwrap();
+/// let params = MessageUsageReportParams::for_range(TimeRange::month_to_date_in(&pacific))
+///     .bucket_width("1d");
+/// assert!(params.ending_at.unwrap() > params.starting_at);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeRange {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    offset: FixedOffset,
+}
+
+impl TimeRange {
+    /// Range from `start` (inclusive) to `end` (exclusive), in `start`'s time
+    /// zone.
+    pub fn between<Tz: TimeZone>(start: DateTime<Tz>, end: DateTime<Tz>) -> Result<Self> {
+        let offset = start.offset().fix();
+        let (start, end) = (start.with_timezone(&Utc), end.with_timezone(&Utc));
+        if end <= start {
+            return Err(AnthropicError::invalid_input(format!(
+                "Time range end ({}) must be after its start ({})",
+                end.to_rfc3339(),
+                start.to_rfc3339()
+            )));
+        }
+        Ok(Self { start, end, offset })
+    }
+
+    /// The last `days` days up to now
+    pub fn last_days(days: u32) -> Self {
+        let end = Utc::now();
+        let start = end - chrono::Duration::days(i64::from(days.max(1)));
+        Self {
+            start,
+            end,
+            offset: Utc.fix(),
+        }
+    }
+
+    /// The last seven days up to now
+    pub fn last_7_days() -> Self {
+        Self::last_days(7)
+    }
+
+    /// From midnight UTC on the first of the current month up to now
+    pub fn month_to_date() -> Self {
+        Self::month_to_date_in(&Utc)
+    }
+
+    /// From local midnight on the first of the current month in `tz` up to now
+    pub fn month_to_date_in<Tz: TimeZone>(tz: &Tz) -> Self {
+        let now = Utc::now().with_timezone(tz);
+        let first = now
+            .date_naive()
+            .with_day(1)
+            .expect("every month has a first day")
+            .and_time(NaiveTime::MIN);
+        // Midnight can fall in a DST gap; the UTC reading of the wall clock is
+        // close enough for a report window in that case.
+        let start = tz
+            .from_local_datetime(&first)
+            .earliest()
+            .unwrap_or_else(|| tz.from_utc_datetime(&first))
+            .with_timezone(&Utc);
+        let end = now
+            .with_timezone(&Utc)
+            .max(start + chrono::Duration::seconds(1));
+        Self {
+            start,
+            end,
+            offset: now.offset().fix(),
+        }
+    }
+
+    /// Same instants, with calendar dates computed in `tz`
+    pub fn in_timezone<Tz: TimeZone>(self, tz: &Tz) -> Self {
+        Self {
+            offset: tz.offset_from_utc_datetime(&self.start.naive_utc()).fix(),
+            ..self
+        }
+    }
+
+    /// Inclusive start
+    pub fn start(&self) -> DateTime<Utc> {
+        self.start
+    }
+
+    /// Exclusive end
+    pub fn end(&self) -> DateTime<Utc> {
+        self.end
+    }
+
+    /// UTC offset used for calendar dates
+    pub fn offset(&self) -> FixedOffset {
+        self.offset
+    }
+
+    /// Length of the window
+    pub fn duration(&self) -> chrono::Duration {
+        self.end - self.start
+    }
+
+    /// Whether `instant` falls inside the window
+    pub fn contains(&self, instant: DateTime<Utc>) -> bool {
+        self.start <= instant && instant < self.end
+    }
+
+    /// Calendar day of the start
+    pub fn start_date(&self) -> NaiveDate {
+        self.start.with_timezone(&self.offset).date_naive()
+    }
+
+    /// First calendar day not covered by the window (exclusive); a window
+    /// ending part-way through a day includes that whole day.
+    pub fn end_date(&self) -> NaiveDate {
+        let end = self.end.with_timezone(&self.offset);
+        let date = end.date_naive();
+        if end.time() == NaiveTime::MIN {
+            date
+        } else {
+            date.succ_opt().unwrap_or(date)
+        }
+    }
+}
+
 /// Usage query parameters
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct UsageQuery {
     /// Start date for the query
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub start_date: Option<DateTime<Utc>>,
     /// End date for the query
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub end_date: Option<DateTime<Utc>>,
     /// Granularity for the report
     pub granularity: Option<String>,
@@ -1119,6 +1273,13 @@ impl UsageQuery {
         self
     }
 
+    /// Set start and end dates from a range
+    pub fn time_range(mut self, range: TimeRange) -> Self {
+        self.start_date = Some(range.start());
+        self.end_date = Some(range.end());
+        self
+    }
+
     /// Set granularity
     pub fn granularity(mut self, granularity: impl Into<String>) -> Self {
         self.granularity = Some(granularity.into());
@@ -1157,9 +1318,14 @@ pub type UsageReportListResponse = PaginatedResponse<UsageReport>;
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MessageUsageReportParams {
     /// Inclusive start timestamp for the report window.
+    #[serde(with = "crate::utils::timestamp")]
     pub starting_at: DateTime<Utc>,
     /// Exclusive end timestamp for the report window.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::utils::timestamp::option"
+    )]
     pub ending_at: Option<DateTime<Utc>>,
     /// Filter by API key IDs.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -1216,12 +1382,24 @@ impl MessageUsageReportParams {
         }
     }
 
+    /// Create usage-report parameters covering `range`.
+    pub fn for_range(range: TimeRange) -> Self {
+        Self::new(range.start()).time_range(range)
+    }
+
     /// Set report ending timestamp.
     pub fn ending_at(mut self, ending_at: DateTime<Utc>) -> Self {
         self.ending_at = Some(ending_at);
         self
     }
 
+    /// Set both report timestamps from `range`.
+    pub fn time_range(mut self, range: TimeRange) -> Self {
+        self.starting_at = range.start();
+        self.ending_at = Some(range.end());
+        self
+    }
+
     /// Filter by API key IDs.
     pub fn api_key_ids(mut self, api_key_ids: impl IntoIterator<Item = impl Into<String>>) -> Self {
         self.api_key_ids = Some(api_key_ids.into_iter().map(|v| v.into()).collect());
@@ -1305,9 +1483,14 @@ impl MessageUsageReportParams {
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MessageCostReportParams {
     /// Inclusive start timestamp for the report window.
+    #[serde(with = "crate::utils::timestamp")]
     pub starting_at: DateTime<Utc>,
     /// Exclusive end timestamp for the report window.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::utils::timestamp::option"
+    )]
     pub ending_at: Option<DateTime<Utc>>,
     /// Optional report granularity / bucket width.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -1348,12 +1531,24 @@ impl MessageCostReportParams {
         }
     }
 
+    /// Create cost-report parameters covering `range`.
+    pub fn for_range(range: TimeRange) -> Self {
+        Self::new(range.start()).time_range(range)
+    }
+
     /// Set report ending timestamp.
     pub fn ending_at(mut self, ending_at: DateTime<Utc>) -> Self {
         self.ending_at = Some(ending_at);
         self
     }
 
+    /// Set both report timestamps from `range`.
+    pub fn time_range(mut self, range: TimeRange) -> Self {
+        self.starting_at = range.start();
+        self.ending_at = Some(range.end());
+        self
+    }
+
     /// Set bucket width (for example `1h` or `1d`).
     pub fn bucket_width(mut self, bucket_width: impl Into<String>) -> Self {
         self.bucket_width = Some(bucket_width.into());
@@ -1407,10 +1602,10 @@ impl MessageCostReportParams {
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub struct MessageUsageReportBucket {
     /// Bucket start timestamp.
-    #[serde(default)]
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub starting_at: Option<DateTime<Utc>>,
     /// Bucket end timestamp.
-    #[serde(default)]
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub ending_at: Option<DateTime<Utc>>,
     /// Message request count.
     #[serde(default)]
@@ -1436,10 +1631,10 @@ pub struct MessageUsageReportBucket {
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub struct MessageCostReportBucket {
     /// Bucket start timestamp.
-    #[serde(default)]
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub starting_at: Option<DateTime<Utc>>,
     /// Bucket end timestamp.
-    #[serde(default)]
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub ending_at: Option<DateTime<Utc>>,
     /// Additional dynamic cost breakdown fields.
     #[serde(flatten)]
@@ -1485,12 +1680,25 @@ impl ClaudeCodeUsageReportParams {
         }
     }
 
+    /// Create params covering the calendar days touched by `range`.
+    pub fn for_range(range: TimeRange) -> Self {
+        Self::new(range.start_date()).time_range(range)
+    }
+
     /// Set report end date.
     pub fn ending_at(mut self, ending_at: chrono::NaiveDate) -> Self {
         self.ending_at = Some(ending_at);
         self
     }
 
+    /// Set both report dates from the calendar days touched by `range`, in
+    /// the range's time zone.
+    pub fn time_range(mut self, range: TimeRange) -> Self {
+        self.starting_at = range.start_date();
+        self.ending_at = Some(range.end_date());
+        self
+    }
+
     /// Set pagination size.
     pub fn limit(mut self, limit: u32) -> Self {
         self.limit = Some(limit);
@@ -1684,6 +1892,69 @@ mod tests {
         assert_eq!(params.limit, Some(100));
     }
 
+    #[test]
+    fn test_time_range_rejects_inverted_and_empty_windows() {
+        let a = Utc.with_ymd_and_hms(2026, 3, 1, 0, 0, 0).unwrap();
+        let b = Utc.with_ymd_and_hms(2026, 3, 8, 0, 0, 0).unwrap();
+
+        let range = TimeRange::between(a, b).unwrap();
+        assert_eq!(range.duration(), chrono::Duration::days(7));
+        assert!(range.contains(a));
+        assert!(!range.contains(b));
+        assert!(TimeRange::between(b, a).is_err());
+        assert!(TimeRange::between(a, a).is_err());
+    }
+
+    #[test]
+    fn test_time_range_calendar_dates_follow_offset() {
+        let tokyo = FixedOffset::east_opt(9 * 3600).unwrap();
+        let start = tokyo.with_ymd_and_hms(2026, 3, 1, 0, 0, 0).unwrap();
+        let end = tokyo.with_ymd_and_hms(2026, 3, 3, 12, 0, 0).unwrap();
+        let range = TimeRange::between(start, end).unwrap();
+
+        // Midnight in Tokyo is still the previous day in UTC.
+        assert_eq!(
+            range.start().date_naive(),
+            NaiveDate::from_ymd_opt(2026, 2, 28).unwrap()
+        );
+        assert_eq!(
+            range.start_date(),
+            NaiveDate::from_ymd_opt(2026, 3, 1).unwrap()
+        );
+        assert_eq!(
+            range.end_date(),
+            NaiveDate::from_ymd_opt(2026, 3, 4).unwrap()
+        );
+
+        let utc = range.in_timezone(&Utc);
+        assert_eq!(utc.start(), range.start());
+        assert_eq!(
+            utc.start_date(),
+            NaiveDate::from_ymd_opt(2026, 2, 28).unwrap()
+        );
+
+        let params = ClaudeCodeUsageReportParams::for_range(range);
+        assert_eq!(params.starting_at, range.start_date());
+        assert_eq!(params.ending_at, Some(range.end_date()));
+    }
+
+    #[test]
+    fn test_time_range_relative_constructors() {
+        let week = TimeRange::last_7_days();
+        assert_eq!(week.duration(), chrono::Duration::days(7));
+
+        let pacific = FixedOffset::west_opt(8 * 3600).unwrap();
+        let month = TimeRange::month_to_date_in(&pacific);
+        let local_start = month.start().with_timezone(&pacific);
+        assert_eq!(local_start.day(), 1);
+        assert_eq!(local_start.time(), NaiveTime::MIN);
+        assert!(month.end() > month.start());
+
+        let params = MessageCostReportParams::for_range(month);
+        assert_eq!(params.starting_at, month.start());
+        assert_eq!(params.ending_at, Some(month.end()));
+    }
+
     #[test]
     fn test_message_usage_report_response_deserialization() {
         let response: MessageUsageReportResponse = serde_json::from_value(json!({
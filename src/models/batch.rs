@@ -4,7 +4,11 @@ use super::message::{MessageRequest, MessageResponse};
 use crate::types::PaginatedResponse;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+/// How long after a batch is created its results can be downloaded
+pub const RESULTS_RETENTION: std::time::Duration =
+    std::time::Duration::from_secs(29 * 24 * 60 * 60);
 
 /// Status of a message batch
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -36,20 +40,22 @@ pub struct MessageBatch {
     /// Number of requests in the batch
     pub request_counts: RequestCounts,
     /// When the batch was created
+    #[serde(with = "crate::utils::timestamp")]
     pub created_at: DateTime<Utc>,
     /// When the batch processing started
-    #[serde(default)]
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub in_progress_at: Option<DateTime<Utc>>,
     /// When the batch processing completed
-    #[serde(default, alias = "ended_at")]
+    #[serde(default, alias = "ended_at", with = "crate::utils::timestamp::option")]
     pub completed_at: Option<DateTime<Utc>>,
     /// When the batch was cancelled
-    #[serde(default)]
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub cancelled_at: Option<DateTime<Utc>>,
     /// When the batch failed
-    #[serde(default)]
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub failed_at: Option<DateTime<Utc>>,
     /// When the batch expires (if not processed)
+    #[serde(with = "crate::utils::timestamp")]
     pub expires_at: DateTime<Utc>,
     /// Error information if the batch failed
     #[serde(default)]
@@ -248,6 +254,425 @@ pub struct BatchResultError {
     pub message: String,
 }
 
+/// Results of a batch together with the requests that produced them, so
+/// failed entries can be inspected and resubmitted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchResults {
+    requests: HashMap<String, MessageRequest>,
+    entries: Vec<MessageBatchResultEntry>,
+    /// Position of each custom ID in `entries`
+    index: HashMap<String, usize>,
+}
+
+impl BatchResults {
+    /// Pair the submitted `requests` with the `entries` returned for them
+    pub fn new(
+        requests: impl IntoIterator<Item = BatchRequestItem>,
+        entries: Vec<MessageBatchResultEntry>,
+    ) -> Self {
+        let index = entries
+            .iter()
+            .enumerate()
+            .map(|(position, entry)| (entry.custom_id.clone(), position))
+            .collect();
+        Self {
+            requests: requests
+                .into_iter()
+                .map(|item| (item.custom_id, item.params))
+                .collect(),
+            entries,
+            index,
+        }
+    }
+
+    /// The result returned for `custom_id`
+    pub fn get(&self, custom_id: &str) -> Option<&MessageBatchResult> {
+        self.index
+            .get(custom_id)
+            .map(|&position| &self.entries[position].result)
+    }
+
+    /// All result entries, in the order returned
+    pub fn entries(&self) -> &[MessageBatchResultEntry] {
+        &self.entries
+    }
+
+    /// The request submitted under `custom_id`
+    pub fn request(&self, custom_id: &str) -> Option<&MessageRequest> {
+        self.requests.get(custom_id)
+    }
+
+    /// Entries that succeeded
+    pub fn succeeded(&self) -> impl Iterator<Item = &MessageBatchResultEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.result.is_success())
+    }
+
+    /// Entries that did not succeed (errored, canceled or expired)
+    pub fn failed(&self) -> impl Iterator<Item = &MessageBatchResultEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| !entry.result.is_success())
+    }
+
+    /// Entries that failed with an error, as opposed to being canceled or
+    /// expiring
+    pub fn errored(&self) -> impl Iterator<Item = (&str, &BatchResultError)> {
+        self.entries.iter().filter_map(|entry| {
+            entry
+                .result
+                .error()
+                .map(|error| (entry.custom_id.as_str(), error))
+        })
+    }
+
+    /// Results keyed by custom ID
+    pub fn into_map(self) -> HashMap<String, MessageBatchResult> {
+        self.entries
+            .into_iter()
+            .map(|entry| (entry.custom_id, entry.result))
+            .collect()
+    }
+
+    /// A batch resubmitting every failed entry under its original custom ID,
+    /// or `None` if none failed.
+    ///
+    /// Unlike [`retry_plan`](Self::retry_plan) nothing is filtered or
+    /// renamed, so the new batch's results line up with the original
+    /// requests directly. Entries whose original request is not known are
+    /// skipped, as are repeated entries for a custom ID already included.
+    pub fn retry_batch(&self) -> Option<MessageBatchCreateRequest> {
+        let mut seen = HashSet::new();
+        let requests: Vec<_> = self
+            .failed()
+            .filter(|entry| seen.insert(entry.custom_id.as_str()))
+            .filter_map(|entry| {
+                let params = self.requests.get(&entry.custom_id)?;
+                Some(BatchRequestItem::new(
+                    entry.custom_id.clone(),
+                    params.clone(),
+                ))
+            })
+            .collect();
+        (!requests.is_empty()).then_some(MessageBatchCreateRequest { requests })
+    }
+
+    /// Build the follow-up batch `policy` calls for, or `None` if nothing
+    /// qualifies for a retry.
+    ///
+    /// Returns the request and a map from each original custom ID to the ID
+    /// used in the follow-up batch, each unique within it. Entries whose
+    /// original request is not known are skipped, as are repeated entries for
+    /// a custom ID already included.
+    pub fn retry_plan(
+        &self,
+        policy: &BatchRetryPolicy,
+    ) -> Option<(MessageBatchCreateRequest, HashMap<String, String>)> {
+        let mut request = MessageBatchCreateRequest::new();
+        let mut id_map = HashMap::new();
+        let mut taken = HashSet::new();
+        for entry in self
+            .entries
+            .iter()
+            .filter(|e| policy.should_retry(&e.result))
+        {
+            if id_map.contains_key(&entry.custom_id) {
+                continue;
+            }
+            let Some(params) = self.requests.get(&entry.custom_id) else {
+                continue;
+            };
+            let params = match &policy.adjust {
+                Some(adjust) => adjust(&entry.custom_id, params.clone()),
+                None => params.clone(),
+            };
+            let new_id = policy.retry_id(&entry.custom_id, &taken);
+            taken.insert(new_id.clone());
+            request = request.add_request_item(BatchRequestItem::new(new_id.clone(), params));
+            id_map.insert(entry.custom_id.clone(), new_id);
+        }
+        (!id_map.is_empty()).then_some((request, id_map))
+    }
+}
+
+/// Which failed batch entries [`BatchResults::retry_failed`] resubmits, and
+/// how.
+///
+/// By default errored and expired entries are retried, except those that
+/// failed as invalid requests (which would fail again unchanged), and each
+/// retry's custom ID gets a `-retry` suffix.
+///
+/// ```rust
+/// use threatflux_anthropic_sdk::models::batch::BatchRetryPolicy;
+///
+/// // Retry with half the output budget, in case the failures were timeouts
+/// let policy = BatchRetryPolicy::new().scale_max_tokens(0.5);
+/// ```
+#[derive(Clone)]
+pub struct BatchRetryPolicy {
+    /// Retry entries that errored
+    pub retry_errored: bool,
+    /// Retry entries that expired before processing
+    pub retry_expired: bool,
+    /// Retry entries that were canceled
+    pub retry_canceled: bool,
+    /// Also retry `invalid_request_error` failures
+    pub retry_invalid_requests: bool,
+    /// Appended to each custom ID. IDs that would exceed the 64-byte limit
+    /// are truncated and tagged with a short hash of the full original ID so
+    /// that they stay distinct; a suffix too long to fit beside the tag is
+    /// itself cut short.
+    pub id_suffix: String,
+    adjust: Option<RequestAdjustment>,
+}
+
+type RequestAdjustment =
+    std::sync::Arc<dyn Fn(&str, MessageRequest) -> MessageRequest + Send + Sync>;
+
+impl std::fmt::Debug for BatchRetryPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BatchRetryPolicy")
+            .field("retry_errored", &self.retry_errored)
+            .field("retry_expired", &self.retry_expired)
+            .field("retry_canceled", &self.retry_canceled)
+            .field("retry_invalid_requests", &self.retry_invalid_requests)
+            .field("id_suffix", &self.id_suffix)
+            .field("adjusts_requests", &self.adjust.is_some())
+            .finish()
+    }
+}
+
+impl Default for BatchRetryPolicy {
+    fn default() -> Self {
+        Self {
+            retry_errored: true,
+            retry_expired: true,
+            retry_canceled: false,
+            retry_invalid_requests: false,
+            id_suffix: "-retry".to_string(),
+            adjust: None,
+        }
+    }
+}
+
+impl BatchRetryPolicy {
+    /// Default policy
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also retry canceled entries
+    pub fn include_canceled(mut self, include: bool) -> Self {
+        self.retry_canceled = include;
+        self
+    }
+
+    /// Also retry entries rejected as invalid requests (useful together with
+    /// a request adjustment)
+    pub fn include_invalid_requests(mut self, include: bool) -> Self {
+        self.retry_invalid_requests = include;
+        self
+    }
+
+    /// Set the suffix appended to retried custom IDs
+    pub fn with_id_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.id_suffix = suffix.into();
+        self
+    }
+
+    /// Rewrite each request before it is resubmitted; receives the original
+    /// custom ID
+    pub fn with_request_adjustment(
+        mut self,
+        adjust: impl Fn(&str, MessageRequest) -> MessageRequest + Send + Sync + 'static,
+    ) -> Self {
+        self.adjust = Some(std::sync::Arc::new(adjust));
+        self
+    }
+
+    /// Multiply each retried request's `max_tokens` by `factor` (keeping at
+    /// least one token)
+    pub fn scale_max_tokens(self, factor: f32) -> Self {
+        self.with_request_adjustment(move |_, request| {
+            let max_tokens = ((request.max_tokens as f32) * factor).floor().max(1.0) as u32;
+            request.max_tokens(max_tokens)
+        })
+    }
+
+    fn should_retry(&self, result: &MessageBatchResult) -> bool {
+        match result {
+            MessageBatchResult::Succeeded { .. } => false,
+            MessageBatchResult::Errored { error } => {
+                self.retry_errored
+                    && (self.retry_invalid_requests || error.error_type != "invalid_request_error")
+            }
+            MessageBatchResult::Canceled {} => self.retry_canceled,
+            MessageBatchResult::Expired {} => self.retry_expired,
+        }
+    }
+
+    /// Custom ID for the retry of `custom_id`, distinct from every ID in
+    /// `taken`
+    fn retry_id(&self, custom_id: &str, taken: &HashSet<String>) -> String {
+        const MAX_CUSTOM_ID_LEN: usize = 64;
+        let plain = format!("{}{}", custom_id, self.id_suffix);
+        if plain.len() <= MAX_CUSTOM_ID_LEN && !taken.contains(&plain) {
+            return plain;
+        }
+
+        // Truncating alone would map IDs sharing a prefix to the same retry
+        // ID, so tag the prefix with a hash of the whole original ID
+        let hash =
+            crate::utils::integrity::ContentDigest::compute(custom_id.as_bytes()).sha256_hex();
+        (0..)
+            .map(|n| {
+                let tag = match n {
+                    0 => format!("-{}", &hash[..8]),
+                    n => format!("-{}-{}", &hash[..8], n),
+                };
+                // Limits are in bytes; cut on char boundaries, shortening a
+                // suffix too long to leave room for the tag
+                let room = MAX_CUSTOM_ID_LEN - tag.len();
+                let suffix = &self.id_suffix[..self.id_suffix.floor_char_boundary(room)];
+                let base = &custom_id[..custom_id.floor_char_boundary(room - suffix.len())];
+                format!("{}{}{}", base, tag, suffix)
+            })
+            .find(|id| !taken.contains(id))
+            .expect("unbounded counter")
+    }
+}
+
+/// A follow-up batch submitted by [`BatchResults::retry_failed`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchRetry {
+    /// The new batch
+    pub batch: MessageBatch,
+    /// Original custom ID to the custom ID used in `batch`
+    pub id_map: HashMap<String, String>,
+}
+
+/// How [`MessageBatchesApi::wait_for_completion`](crate::api::message_batches::MessageBatchesApi::wait_for_completion)
+/// polls a batch
+///
+/// The delay between polls starts at `initial_interval` and grows by
+/// `multiplier` up to `max_interval`, since most batches take minutes to
+/// hours and early polls are the ones most likely to find a quick one done.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use threatflux_anthropic_sdk::models::batch::PollOptions;
+///
+/// let options = PollOptions::new()
+///     .with_timeout(Duration::from_secs(6 * 60 * 60))
+///     .on_progress(|counts| println!("{}/{} done", counts.completed, counts.total));
+/// ```
+#[derive(Clone)]
+pub struct PollOptions {
+    /// Delay before the second poll
+    pub initial_interval: std::time::Duration,
+    /// Longest delay between polls
+    pub max_interval: std::time::Duration,
+    /// Growth factor of the delay after each poll
+    pub multiplier: f64,
+    /// Give up with [`AnthropicError::Timeout`](crate::error::AnthropicError::Timeout)
+    /// after this long
+    pub timeout: Option<std::time::Duration>,
+    /// Stop waiting when cancelled; the batch itself keeps running
+    pub cancel: Option<tokio_util::sync::CancellationToken>,
+    progress: Option<ProgressCallback>,
+}
+
+type ProgressCallback = std::sync::Arc<dyn Fn(&RequestCounts) + Send + Sync>;
+
+impl std::fmt::Debug for PollOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PollOptions")
+            .field("initial_interval", &self.initial_interval)
+            .field("max_interval", &self.max_interval)
+            .field("multiplier", &self.multiplier)
+            .field("timeout", &self.timeout)
+            .field("cancellable", &self.cancel.is_some())
+            .field("reports_progress", &self.progress.is_some())
+            .finish()
+    }
+}
+
+impl Default for PollOptions {
+    fn default() -> Self {
+        Self {
+            initial_interval: std::time::Duration::from_secs(5),
+            max_interval: std::time::Duration::from_secs(120),
+            multiplier: 1.5,
+            timeout: None,
+            cancel: None,
+            progress: None,
+        }
+    }
+}
+
+impl PollOptions {
+    /// Back off from 5 seconds to 2 minutes, with no timeout
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Poll every `interval`
+    pub fn fixed(interval: std::time::Duration) -> Self {
+        Self::new().with_backoff(interval, interval, 1.0)
+    }
+
+    /// Set the backoff between polls
+    pub fn with_backoff(
+        mut self,
+        initial_interval: std::time::Duration,
+        max_interval: std::time::Duration,
+        multiplier: f64,
+    ) -> Self {
+        self.initial_interval = initial_interval;
+        self.max_interval = max_interval.max(initial_interval);
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Give up after `timeout`
+    pub fn with_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Stop waiting when `token` is cancelled
+    pub fn with_cancel(mut self, token: tokio_util::sync::CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Call `callback` with the request counts after every poll
+    pub fn on_progress(
+        mut self,
+        callback: impl Fn(&RequestCounts) + Send + Sync + 'static,
+    ) -> Self {
+        self.progress = Some(std::sync::Arc::new(callback));
+        self
+    }
+
+    pub(crate) fn report(&self, counts: &RequestCounts) {
+        if let Some(progress) = &self.progress {
+            progress(counts);
+        }
+    }
+
+    /// The delay after `current`
+    pub(crate) fn next_interval(&self, current: std::time::Duration) -> std::time::Duration {
+        let multiplier = if self.multiplier.is_finite() && self.multiplier >= 1.0 {
+            self.multiplier
+        } else {
+            1.0
+        };
+        current.mul_f64(multiplier).min(self.max_interval)
+    }
+}
+
 impl MessageBatch {
     /// Check if the batch is complete
     pub fn is_complete(&self) -> bool {
@@ -296,9 +721,23 @@ impl MessageBatch {
         (self.request_counts.completed as f64 / total_processed as f64) * 100.0
     }
 
-    /// Check if the batch has expired
+    /// Check if the batch has expired, allowing for
+    /// [`DEFAULT_CLOCK_SKEW`](crate::utils::timestamp::DEFAULT_CLOCK_SKEW)
+    /// between this host and the API
     pub fn is_expired(&self) -> bool {
-        Utc::now() > self.expires_at
+        self.is_expired_with_skew(crate::utils::timestamp::DEFAULT_CLOCK_SKEW)
+    }
+
+    /// Check if the batch has expired, allowing `skew` of clock difference
+    pub fn is_expired_with_skew(&self, skew: std::time::Duration) -> bool {
+        crate::utils::timestamp::is_past(self.expires_at, skew)
+    }
+
+    /// When the batch's results stop being downloadable
+    /// ([`RESULTS_RETENTION`] after creation)
+    pub fn results_archive_at(&self) -> DateTime<Utc> {
+        self.created_at
+            + chrono::Duration::from_std(RESULTS_RETENTION).unwrap_or(chrono::Duration::MAX)
     }
 
     /// Get processing duration
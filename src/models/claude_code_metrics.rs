@@ -0,0 +1,504 @@
+//! Aggregation of Claude Code usage report rows
+//!
+//! The Claude Code usage report returns one row per actor and day. Feed the
+//! rows of one or more pages into [`ClaudeCodeMetrics`] and read back totals
+//! per actor, per day and per tool, leaderboards, and an adoption summary.
+//!
+//! ```rust,no_run
+//! use threatflux_anthropic_sdk::{
+//!     models::{
+//!         claude_code_metrics::{ClaudeCodeLeaderboardMetric, ClaudeCodeMetrics},
+//!         ClaudeCodeUsageReportParams, TimeRange,
+//!     },
+//!     Client,
+//! };
+//!
+//! # async fn example() -> threatflux_anthropic_sdk::Result<()> {
+//! let client = Client::from_env()?;
+//! let mut params = ClaudeCodeUsageReportParams::for_range(TimeRange::last_7_days());
+//! let mut metrics = ClaudeCodeMetrics::new();
+//! loop {
+//!     let page = client
+//!         .admin()?
+//!         .usage()
+//!         .get_claude_code_usage_report(params.clone(), None)
+//!         .await?;
+//!     metrics.extend(&page.data);
+//!     match page.next_page {
+//!         Some(next) if page.has_more => params = params.page(next),
+//!         _ => break,
+//!     }
+//! }
+//!
+//! for entry in metrics.leaderboard(ClaudeCodeLeaderboardMetric::Commits, 10) {
+//!     println!("{}. {} ({})", entry.rank, entry.actor, entry.value);
+//! }
+//! println!("{} active users", metrics.adoption().active_actors);
+//! # Ok(())
+//! # }
+//! ```
+
+use super::admin::{
+    ClaudeCodeCoreMetrics, ClaudeCodeToolMetric, ClaudeCodeUsageActor, ClaudeCodeUsageReportRow,
+};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Actor name used for rows without an email address or API key name
+pub const UNKNOWN_ACTOR: &str = "unknown";
+
+/// Summed Claude Code metrics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct ClaudeCodeTotals {
+    /// Sessions started
+    pub sessions: u64,
+    /// Lines of code added
+    pub lines_added: u64,
+    /// Lines of code removed
+    pub lines_removed: u64,
+    /// Commits made by Claude Code
+    pub commits: u64,
+    /// Pull requests created by Claude Code
+    pub pull_requests: u64,
+    /// Tool suggestions accepted, across all tools
+    pub accepted: u64,
+    /// Tool suggestions rejected, across all tools
+    pub rejected: u64,
+}
+
+impl ClaudeCodeTotals {
+    /// Lines added plus lines removed
+    pub fn lines_changed(&self) -> u64 {
+        self.lines_added + self.lines_removed
+    }
+
+    /// Share of tool suggestions that were accepted, if there were any
+    pub fn acceptance_rate(&self) -> Option<f64> {
+        acceptance_rate(self.accepted, self.rejected)
+    }
+
+    fn add_core(&mut self, core: &ClaudeCodeCoreMetrics) {
+        self.sessions += core.num_sessions.unwrap_or(0);
+        self.lines_added += core.num_lines_of_code_added.unwrap_or(0);
+        self.lines_removed += core.num_lines_of_code_removed.unwrap_or(0);
+        self.commits += core.num_commits_by_claude_code.unwrap_or(0);
+        self.pull_requests += core.num_pull_requests_created_by_claude_code.unwrap_or(0);
+    }
+
+    fn add_tool(&mut self, tool: &ClaudeCodeToolMetric) {
+        self.accepted += tool.accepted_count.unwrap_or(0);
+        self.rejected += tool.rejected_count.unwrap_or(0);
+    }
+}
+
+/// Totals for one actor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClaudeCodeActorMetrics {
+    /// Email address or API key name
+    pub actor: String,
+    /// Actor type reported by the API, e.g. `user_actor`
+    pub actor_type: Option<String>,
+    /// Days with at least one row for this actor
+    pub active_days: usize,
+    /// Summed metrics
+    pub totals: ClaudeCodeTotals,
+}
+
+/// Totals for one day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClaudeCodeDailyMetrics {
+    /// Report date
+    pub date: NaiveDate,
+    /// Distinct actors with a row on this day
+    pub active_actors: usize,
+    /// Summed metrics
+    pub totals: ClaudeCodeTotals,
+}
+
+/// Accept/reject totals for one tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClaudeCodeToolAdoption {
+    /// Tool name as reported, e.g. `edit_tool`
+    pub tool: String,
+    /// Suggestions accepted
+    pub accepted: u64,
+    /// Suggestions rejected
+    pub rejected: u64,
+    /// Distinct actors who accepted or rejected at least one suggestion
+    pub actors: usize,
+}
+
+impl ClaudeCodeToolAdoption {
+    /// Share of suggestions that were accepted, if there were any
+    pub fn acceptance_rate(&self) -> Option<f64> {
+        acceptance_rate(self.accepted, self.rejected)
+    }
+}
+
+/// What a leaderboard ranks actors by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaudeCodeLeaderboardMetric {
+    /// Sessions started
+    Sessions,
+    /// Lines added
+    LinesAdded,
+    /// Lines added plus removed
+    LinesChanged,
+    /// Commits by Claude Code
+    Commits,
+    /// Pull requests by Claude Code
+    PullRequests,
+    /// Accepted tool suggestions
+    AcceptedSuggestions,
+    /// Days with activity
+    ActiveDays,
+}
+
+impl ClaudeCodeLeaderboardMetric {
+    fn value(self, actor: &ClaudeCodeActorMetrics) -> u64 {
+        let totals = &actor.totals;
+        match self {
+            Self::Sessions => totals.sessions,
+            Self::LinesAdded => totals.lines_added,
+            Self::LinesChanged => totals.lines_changed(),
+            Self::Commits => totals.commits,
+            Self::PullRequests => totals.pull_requests,
+            Self::AcceptedSuggestions => totals.accepted,
+            Self::ActiveDays => actor.active_days as u64,
+        }
+    }
+}
+
+/// One leaderboard position
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClaudeCodeLeaderboardEntry {
+    /// 1-based rank; tied actors share a rank
+    pub rank: usize,
+    /// Email address or API key name
+    pub actor: String,
+    /// Value of the ranked metric
+    pub value: u64,
+}
+
+/// How widely Claude Code is used over the aggregated rows
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClaudeCodeAdoptionSummary {
+    /// Distinct actors
+    pub active_actors: usize,
+    /// Distinct report dates
+    pub active_days: usize,
+    /// Most actors active on a single day
+    pub peak_daily_actors: usize,
+    /// Mean sessions per actor
+    pub sessions_per_actor: f64,
+    /// Share of tool suggestions accepted, if there were any
+    pub acceptance_rate: Option<f64>,
+    /// Organisation-wide totals
+    pub totals: ClaudeCodeTotals,
+    /// Tools, most used first
+    pub tools: Vec<ClaudeCodeToolAdoption>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct ActorEntry {
+    actor_type: Option<String>,
+    days: BTreeSet<NaiveDate>,
+    totals: ClaudeCodeTotals,
+}
+
+#[derive(Debug, Clone, Default)]
+struct DayEntry {
+    actors: BTreeSet<String>,
+    totals: ClaudeCodeTotals,
+}
+
+#[derive(Debug, Clone, Default)]
+struct ToolEntry {
+    accepted: u64,
+    rejected: u64,
+    actors: BTreeSet<String>,
+}
+
+/// Running aggregation of Claude Code usage report rows
+#[derive(Debug, Clone, Default)]
+pub struct ClaudeCodeMetrics {
+    totals: ClaudeCodeTotals,
+    actors: BTreeMap<String, ActorEntry>,
+    days: BTreeMap<NaiveDate, DayEntry>,
+    tools: BTreeMap<String, ToolEntry>,
+}
+
+impl ClaudeCodeMetrics {
+    /// Empty aggregation
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Aggregate `rows`
+    pub fn from_rows<'a>(rows: impl IntoIterator<Item = &'a ClaudeCodeUsageReportRow>) -> Self {
+        let mut metrics = Self::new();
+        metrics.extend(rows);
+        metrics
+    }
+
+    /// Add more rows, e.g. the next page of a report
+    pub fn extend<'a>(&mut self, rows: impl IntoIterator<Item = &'a ClaudeCodeUsageReportRow>) {
+        for row in rows {
+            self.add(row);
+        }
+    }
+
+    /// Add one row
+    pub fn add(&mut self, row: &ClaudeCodeUsageReportRow) {
+        let actor = actor_name(row.actor.as_ref());
+        let mut totals = ClaudeCodeTotals::default();
+        if let Some(core) = &row.core_metrics {
+            totals.add_core(core);
+        }
+        for (name, metric) in row.tool_metrics.iter().flatten() {
+            totals.add_tool(metric);
+            let tool = self.tools.entry(name.clone()).or_default();
+            tool.accepted += metric.accepted_count.unwrap_or(0);
+            tool.rejected += metric.rejected_count.unwrap_or(0);
+            if metric.accepted_count.unwrap_or(0) + metric.rejected_count.unwrap_or(0) > 0 {
+                tool.actors.insert(actor.clone());
+            }
+        }
+
+        merge(&mut self.totals, &totals);
+
+        let entry = self.actors.entry(actor.clone()).or_default();
+        merge(&mut entry.totals, &totals);
+        if entry.actor_type.is_none() {
+            entry.actor_type = row.actor.as_ref().and_then(|a| a.actor_type.clone());
+        }
+        if let Some(date) = row.date {
+            entry.days.insert(date);
+            let day = self.days.entry(date).or_default();
+            day.actors.insert(actor);
+            merge(&mut day.totals, &totals);
+        }
+    }
+
+    /// Totals over every row
+    pub fn totals(&self) -> ClaudeCodeTotals {
+        self.totals
+    }
+
+    /// Per-actor totals, ordered by actor
+    pub fn by_actor(&self) -> Vec<ClaudeCodeActorMetrics> {
+        self.actors
+            .iter()
+            .map(|(actor, entry)| ClaudeCodeActorMetrics {
+                actor: actor.clone(),
+                actor_type: entry.actor_type.clone(),
+                active_days: entry.days.len(),
+                totals: entry.totals,
+            })
+            .collect()
+    }
+
+    /// Per-day totals, oldest first. Rows without a date are left out.
+    pub fn by_day(&self) -> Vec<ClaudeCodeDailyMetrics> {
+        self.days
+            .iter()
+            .map(|(date, entry)| ClaudeCodeDailyMetrics {
+                date: *date,
+                active_actors: entry.actors.len(),
+                totals: entry.totals,
+            })
+            .collect()
+    }
+
+    /// Per-tool totals, most used first
+    pub fn by_tool(&self) -> Vec<ClaudeCodeToolAdoption> {
+        let mut tools: Vec<_> = self
+            .tools
+            .iter()
+            .map(|(tool, entry)| ClaudeCodeToolAdoption {
+                tool: tool.clone(),
+                accepted: entry.accepted,
+                rejected: entry.rejected,
+                actors: entry.actors.len(),
+            })
+            .collect();
+        tools.sort_by_key(|tool| std::cmp::Reverse(tool.accepted + tool.rejected));
+        tools
+    }
+
+    /// The top `limit` actors by `metric`, leaving out actors at zero.
+    /// Ties share a rank and are ordered by actor.
+    pub fn leaderboard(
+        &self,
+        metric: ClaudeCodeLeaderboardMetric,
+        limit: usize,
+    ) -> Vec<ClaudeCodeLeaderboardEntry> {
+        let mut scored: Vec<_> = self
+            .by_actor()
+            .into_iter()
+            .map(|actor| (metric.value(&actor), actor.actor))
+            .filter(|(value, _)| *value > 0)
+            .collect();
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+
+        let mut entries: Vec<ClaudeCodeLeaderboardEntry> = Vec::new();
+        for (position, (value, actor)) in scored.into_iter().take(limit).enumerate() {
+            let rank = match entries.last() {
+                Some(previous) if previous.value == value => previous.rank,
+                _ => position + 1,
+            };
+            entries.push(ClaudeCodeLeaderboardEntry { rank, actor, value });
+        }
+        entries
+    }
+
+    /// Adoption across all aggregated rows
+    pub fn adoption(&self) -> ClaudeCodeAdoptionSummary {
+        let active_actors = self.actors.len();
+        ClaudeCodeAdoptionSummary {
+            active_actors,
+            active_days: self.days.len(),
+            peak_daily_actors: self
+                .days
+                .values()
+                .map(|day| day.actors.len())
+                .max()
+                .unwrap_or(0),
+            sessions_per_actor: if active_actors == 0 {
+                0.0
+            } else {
+                self.totals.sessions as f64 / active_actors as f64
+            },
+            acceptance_rate: self.totals.acceptance_rate(),
+            totals: self.totals,
+            tools: self.by_tool(),
+        }
+    }
+}
+
+fn actor_name(actor: Option<&ClaudeCodeUsageActor>) -> String {
+    actor
+        .and_then(|actor| {
+            actor
+                .email_address
+                .as_deref()
+                .or(actor.api_key_name.as_deref())
+        })
+        .unwrap_or(UNKNOWN_ACTOR)
+        .to_string()
+}
+
+fn merge(into: &mut ClaudeCodeTotals, from: &ClaudeCodeTotals) {
+    into.sessions += from.sessions;
+    into.lines_added += from.lines_added;
+    into.lines_removed += from.lines_removed;
+    into.commits += from.commits;
+    into.pull_requests += from.pull_requests;
+    into.accepted += from.accepted;
+    into.rejected += from.rejected;
+}
+
+fn acceptance_rate(accepted: u64, rejected: u64) -> Option<f64> {
+    let total = accepted + rejected;
+    (total > 0).then(|| accepted as f64 / total as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rows() -> Vec<ClaudeCodeUsageReportRow> {
+        serde_json::from_value(json!([
+            {
+                "date": "2026-03-01",
+                "actor": {"type": "user_actor", "email_address": "ada@example.com"},
+                "core_metrics": {"num_sessions": 3, "num_lines_of_code_added": 100,
+                                 "num_lines_of_code_removed": 20, "num_commits_by_claude_code": 2},
+                "tool_metrics": {"edit_tool": {"accepted": 9, "rejected": 1}}
+            },
+            {
+                "date": "2026-03-02",
+                "actor": {"type": "user_actor", "email_address": "ada@example.com"},
+                "core_metrics": {"num_sessions": 1, "num_commits_by_claude_code": 1},
+                "tool_metrics": {"write_tool": {"accepted": 2, "rejected": 2}}
+            },
+            {
+                "date": "2026-03-01",
+                "actor": {"type": "api_actor", "api_key_name": "ci"},
+                "core_metrics": {"num_sessions": 6, "num_commits_by_claude_code": 3},
+                "tool_metrics": {"edit_tool": {"accepted": 4, "rejected": 0}}
+            },
+            {"date": "2026-03-02", "core_metrics": {"num_sessions": 2}}
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_rolls_up_by_actor_day_and_tool() {
+        let metrics = ClaudeCodeMetrics::from_rows(&rows());
+
+        let totals = metrics.totals();
+        assert_eq!(totals.sessions, 12);
+        assert_eq!(totals.commits, 6);
+        assert_eq!(totals.lines_changed(), 120);
+        assert_eq!(totals.acceptance_rate(), Some(15.0 / 18.0));
+
+        let actors = metrics.by_actor();
+        assert_eq!(
+            actors.iter().map(|a| a.actor.as_str()).collect::<Vec<_>>(),
+            ["ada@example.com", "ci", UNKNOWN_ACTOR]
+        );
+        assert_eq!(actors[0].active_days, 2);
+        assert_eq!(actors[0].totals.sessions, 4);
+        assert_eq!(actors[1].actor_type.as_deref(), Some("api_actor"));
+
+        let days = metrics.by_day();
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0].active_actors, 2);
+        assert_eq!(days[0].totals.sessions, 9);
+
+        let tools = metrics.by_tool();
+        assert_eq!(tools[0].tool, "edit_tool");
+        assert_eq!((tools[0].accepted, tools[0].actors), (13, 2));
+        assert_eq!(tools[1].acceptance_rate(), Some(0.5));
+    }
+
+    #[test]
+    fn test_leaderboard_and_adoption() {
+        let mut metrics = ClaudeCodeMetrics::new();
+        let rows = rows();
+        metrics.extend(&rows[..2]);
+        metrics.extend(&rows[2..]);
+
+        let board = metrics.leaderboard(ClaudeCodeLeaderboardMetric::Commits, 10);
+        assert_eq!(
+            board,
+            vec![
+                ClaudeCodeLeaderboardEntry {
+                    rank: 1,
+                    actor: "ada@example.com".to_string(),
+                    value: 3
+                },
+                ClaudeCodeLeaderboardEntry {
+                    rank: 1,
+                    actor: "ci".to_string(),
+                    value: 3
+                },
+            ]
+        );
+        assert_eq!(
+            metrics.leaderboard(ClaudeCodeLeaderboardMetric::Sessions, 1)[0].actor,
+            "ci"
+        );
+
+        let adoption = metrics.adoption();
+        assert_eq!(adoption.active_actors, 3);
+        assert_eq!(adoption.active_days, 2);
+        assert_eq!(adoption.peak_daily_actors, 2);
+        assert_eq!(adoption.sessions_per_actor, 4.0);
+        assert_eq!(adoption.tools.len(), 2);
+    }
+}
@@ -0,0 +1,378 @@
+//! Code blocks in responses, and checking them with an external command
+//!
+//! [`MessageResponse::code_blocks`] pulls the fenced code blocks of one
+//! language out of a response. [`MessagesApi::create_code`] goes further: it
+//! runs the code through a [`CodeCheck`] command such as a compiler, and
+//! when the command fails shows its output to the model and asks for a fix,
+//! up to [`CodeCheck::max_fixes`] times.
+//!
+//! ```rust,no_run
+//! use threatflux_anthropic_sdk::{models::code::CodeCheck, models::message::MessageRequest, Client};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let client = Client::from_env()?;
+//! let request = MessageRequest::new()
+//!     .max_tokens(2000)
+//!     .add_user_message("Write a Rust function that parses an ISO 8601 date.");
+//! let check = CodeCheck::new("rust").with_command(
+//!     "rustc",
+//!     ["--edition", "2021", "--crate-type", "lib", "--emit=metadata", "{file}"],
+//! );
+//!
+//! let run = client.messages().create_code(request, &check, None).await?;
+//! if run.passed {
+//!     println!("{}", run.code);
+//! } else {
+//!     eprintln!("still failing after {} attempts:\n{}", run.attempts, run.diagnostics.unwrap_or_default());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The command runs with the code written to a file in a fresh temporary
+//! directory, which is also its working directory and is removed afterwards.
+//! It is a program and arguments, not a shell line: `{file}` in an argument
+//! is replaced with the file's path, which is appended as the last argument
+//! when no argument mentions it.
+//!
+//! [`MessagesApi::create_code`]: crate::api::messages::MessagesApi::create_code
+
+use crate::{
+    error::{AnthropicError, Result},
+    models::{
+        common::{Role, Usage},
+        message::{Message, MessageResponse},
+    },
+    utils::text::truncate_chars,
+};
+use std::{path::PathBuf, process::Stdio, time::Duration};
+
+/// Most characters of command output shown to the model
+const MAX_DIAGNOSTIC_CHARS: usize = 8_000;
+
+/// A fenced code block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeBlock {
+    /// First word of the fence's info string, e.g. `rust`
+    pub language: Option<String>,
+    /// The code between the fences
+    pub code: String,
+}
+
+impl CodeBlock {
+    /// Whether the block is tagged as `language`, allowing common aliases
+    /// such as `rs` for `rust` or `py` for `python`
+    pub fn is_language(&self, language: &str) -> bool {
+        self.language
+            .as_deref()
+            .is_some_and(|tag| canonical_language(tag) == canonical_language(language))
+    }
+}
+
+fn canonical_language(language: &str) -> String {
+    let language = language.trim().to_ascii_lowercase();
+    let canonical = match language.as_str() {
+        "rs" => "rust",
+        "py" | "python3" => "python",
+        "js" | "node" => "javascript",
+        "ts" => "typescript",
+        "sh" | "bash" | "zsh" | "shell" => "shell",
+        "golang" => "go",
+        "c++" | "cc" | "cxx" => "cpp",
+        "yml" => "yaml",
+        _ => return language,
+    };
+    canonical.to_string()
+}
+
+/// Fenced code blocks in Markdown `text`, in order.
+///
+/// Fences are three or more backticks or tildes; a block left open by a
+/// truncated response runs to the end of the text.
+pub fn extract_code_blocks(text: &str) -> Vec<CodeBlock> {
+    let mut blocks = Vec::new();
+    let mut open: Option<(char, usize, Option<String>, Vec<&str>)> = None;
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        let fence_char = trimmed.chars().next().filter(|c| *c == '`' || *c == '~');
+        let fence_len = fence_char.map_or(0, |c| trimmed.chars().take_while(|x| *x == c).count());
+
+        match &mut open {
+            Some((c, len, _, lines)) => {
+                let closes = fence_char == Some(*c)
+                    && fence_len >= *len
+                    && trimmed[fence_len..].trim().is_empty();
+                if closes {
+                    let (_, _, language, lines) = open.take().expect("block is open");
+                    blocks.push(CodeBlock {
+                        language,
+                        code: lines.join("\n"),
+                    });
+                } else {
+                    lines.push(line);
+                }
+            }
+            None if fence_len >= 3 => {
+                let info = trimmed[fence_len..].trim();
+                let language = info
+                    .split(|c: char| c.is_whitespace() || c == ',' || c == '{')
+                    .next()
+                    .filter(|tag| !tag.is_empty())
+                    .map(str::to_string);
+                open = Some((fence_char.expect("fence"), fence_len, language, Vec::new()));
+            }
+            None => {}
+        }
+    }
+    if let Some((_, _, language, lines)) = open {
+        blocks.push(CodeBlock {
+            language,
+            code: lines.join("\n"),
+        });
+    }
+    blocks
+}
+
+impl MessageResponse {
+    /// Code blocks tagged as `language` in the response text
+    pub fn code_blocks(&self, language: &str) -> Vec<CodeBlock> {
+        extract_code_blocks(&self.text())
+            .into_iter()
+            .filter(|block| block.is_language(language))
+            .collect()
+    }
+}
+
+/// How [`MessagesApi::create_code`](crate::api::messages::MessagesApi::create_code)
+/// finds and checks the code in a response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeCheck {
+    /// Language of the code blocks to use
+    pub language: String,
+    /// Program and arguments that check the code, if any
+    pub command: Option<(String, Vec<String>)>,
+    /// Name of the file the code is written to
+    pub file_name: String,
+    /// How long the command may run
+    pub timeout: Duration,
+    /// Follow-up requests asking the model to fix failing code
+    pub max_fixes: u32,
+}
+
+impl CodeCheck {
+    /// Use the `language` code blocks of the response, without a command
+    pub fn new(language: impl Into<String>) -> Self {
+        let language = language.into();
+        let extension = match canonical_language(&language).as_str() {
+            "rust" => "rs",
+            "python" => "py",
+            "javascript" => "js",
+            "typescript" => "ts",
+            "shell" => "sh",
+            "cpp" => "cpp",
+            "go" => "go",
+            "c" => "c",
+            "java" => "java",
+            "json" => "json",
+            "yaml" => "yaml",
+            _ => "txt",
+        };
+        Self {
+            file_name: format!("snippet.{}", extension),
+            language,
+            command: None,
+            timeout: Duration::from_secs(60),
+            max_fixes: 2,
+        }
+    }
+
+    /// Check the code with `program` and `args`; see the
+    /// [module docs](self) for `{file}`
+    pub fn with_command(
+        mut self,
+        program: impl Into<String>,
+        args: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.command = Some((program.into(), args.into_iter().map(Into::into).collect()));
+        self
+    }
+
+    /// Write the code to a file called `name`, e.g. `main.rs`
+    pub fn with_file_name(mut self, name: impl Into<String>) -> Self {
+        self.file_name = name.into();
+        self
+    }
+
+    /// Stop the command after `timeout` and count it as failed
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Ask the model to fix failing code at most `max_fixes` times
+    pub fn with_max_fixes(mut self, max_fixes: u32) -> Self {
+        self.max_fixes = max_fixes;
+        self
+    }
+
+    /// The response's code in this language, blocks joined by a blank line
+    pub fn code(&self, response: &MessageResponse) -> Option<String> {
+        let blocks = response.code_blocks(&self.language);
+        (!blocks.is_empty()).then(|| {
+            blocks
+                .into_iter()
+                .map(|block| block.code)
+                .collect::<Vec<_>>()
+                .join("\n\n")
+        })
+    }
+
+    /// Run the command on `code`: `Ok(None)` when it passes (or there is no
+    /// command), `Ok(Some(output))` when it fails, and an error when it
+    /// cannot be started.
+    pub async fn check(&self, code: &str) -> Result<Option<String>> {
+        let Some((program, args)) = &self.command else {
+            return Ok(None);
+        };
+        let dir = ScratchDir::new()?;
+        let file = dir.0.join(&self.file_name);
+        tokio::fs::write(&file, code).await.map_err(|e| {
+            AnthropicError::file_error(format!("Failed to write {}: {}", file.display(), e))
+        })?;
+
+        let path = file.display().to_string();
+        let mut args: Vec<String> = args.iter().map(|a| a.replace("{file}", &path)).collect();
+        if !self.command_mentions_file() {
+            args.push(path);
+        }
+        let child = tokio::process::Command::new(program)
+            .args(&args)
+            .current_dir(&dir.0)
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .output();
+        let output = match tokio::time::timeout(self.timeout, child).await {
+            Ok(output) => output
+                .map_err(|e| AnthropicError::config(format!("Failed to run {}: {}", program, e)))?,
+            Err(_) => {
+                return Ok(Some(format!(
+                    "{} timed out after {:?}",
+                    program, self.timeout
+                )))
+            }
+        };
+        if output.status.success() {
+            return Ok(None);
+        }
+
+        let mut diagnostics = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if !stdout.trim().is_empty() {
+            if !diagnostics.is_empty() {
+                diagnostics.push('\n');
+            }
+            diagnostics.push_str(stdout.trim());
+        }
+        if diagnostics.is_empty() {
+            diagnostics = format!("{} exited with {}", program, output.status);
+        }
+        Ok(Some(
+            truncate_chars(
+                &diagnostics,
+                MAX_DIAGNOSTIC_CHARS,
+                Some("\n[output truncated]"),
+            )
+            .into_owned(),
+        ))
+    }
+
+    fn command_mentions_file(&self) -> bool {
+        self.command
+            .as_ref()
+            .is_some_and(|(_, args)| args.iter().any(|a| a.contains("{file}")))
+    }
+
+    /// The messages asking the model to fix `response`, given why it failed
+    pub(crate) fn correction(&self, response: &MessageResponse, problem: &str) -> [Message; 2] {
+        let assistant = Message::new(Role::Assistant, response.content.clone());
+        let user = Message::user(format!(
+            "{}\n\nFix the code and reply with the complete corrected version in a ```{} code block.",
+            problem, self.language
+        ));
+        [assistant, user]
+    }
+
+    /// Description of the command, for feedback to the model
+    pub(crate) fn command_line(&self) -> String {
+        match &self.command {
+            Some((program, args)) => std::iter::once(program.as_str())
+                .chain(args.iter().map(String::as_str))
+                .collect::<Vec<_>>()
+                .join(" "),
+            None => String::new(),
+        }
+    }
+}
+
+/// Result of [`MessagesApi::create_code`](crate::api::messages::MessagesApi::create_code)
+#[derive(Debug, Clone)]
+pub struct CodeRun {
+    /// Code from the last response, empty if it had none
+    pub code: String,
+    /// Whether that code was found and passed the check
+    pub passed: bool,
+    /// Why it did not pass, e.g. the compiler's errors
+    pub diagnostics: Option<String>,
+    /// The last response
+    pub response: MessageResponse,
+    /// Requests sent
+    pub attempts: u32,
+    /// Tokens used across all attempts
+    pub usage: Usage,
+}
+
+/// A temporary directory removed on drop
+struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    fn new() -> Result<Self> {
+        let dir = std::env::temp_dir().join(format!("anthropic-code-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).map_err(|e| {
+            AnthropicError::file_error(format!("Failed to create {}: {}", dir.display(), e))
+        })?;
+        Ok(Self(dir))
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_code_blocks() {
+        let text = "Here:\n```rust\nfn main() {}\n```\nand\n~~~~py title=x\nprint(1)\n```\nstill py\n~~~~\n```\nplain\n```\n````rs\nunterminated";
+        let blocks = extract_code_blocks(text);
+        assert_eq!(blocks.len(), 4);
+        assert_eq!(blocks[0].code, "fn main() {}");
+        assert_eq!(blocks[1].language.as_deref(), Some("py"));
+        assert_eq!(blocks[1].code, "print(1)\n```\nstill py");
+        assert!(blocks[1].is_language("python"));
+        assert_eq!(blocks[2].language, None);
+        assert!(blocks[3].is_language("rust"));
+        assert_eq!(blocks[3].code, "unterminated");
+    }
+
+    #[test]
+    fn test_command_line_and_file_name() {
+        let check = CodeCheck::new("Rust").with_command("rustc", ["--emit=metadata", "{file}"]);
+        assert_eq!(check.file_name, "snippet.rs");
+        assert_eq!(check.command_line(), "rustc --emit=metadata {file}");
+        assert!(check.command_mentions_file());
+    }
+}
@@ -236,6 +236,17 @@ impl DocumentSource {
     pub fn content(content: Vec<serde_json::Value>) -> Self {
         Self::Content { content }
     }
+
+    /// Create a text document from CSV/TSV data, rendered as a compact markdown
+    /// or TSV table with an optional schema summary and row sampling.
+    #[cfg(feature = "csv")]
+    pub fn from_csv<R: std::io::Read>(
+        reader: R,
+        options: &crate::utils::table::CsvOptions,
+    ) -> crate::error::Result<Self> {
+        let table = crate::utils::table::render_csv(reader, options)?;
+        Ok(Self::text("text/plain", table.text))
+    }
 }
 
 /// Tool result content representation.
@@ -263,7 +274,11 @@ pub enum ContentBlock {
         cache_control: Option<CacheControl>,
     },
     /// Image content.
-    Image { source: ImageSource },
+    Image {
+        source: ImageSource,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cache_control: Option<CacheControl>,
+    },
     /// Document content.
     Document {
         source: DocumentSource,
@@ -273,6 +288,8 @@ pub enum ContentBlock {
         context: Option<String>,
         #[serde(skip_serializing_if = "Option::is_none")]
         citations: Option<DocumentCitations>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cache_control: Option<CacheControl>,
     },
     /// Client tool use content.
     ToolUse {
@@ -295,6 +312,8 @@ pub enum ContentBlock {
         content: Option<ToolResultContent>,
         #[serde(skip_serializing_if = "Option::is_none")]
         is_error: Option<bool>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cache_control: Option<CacheControl>,
     },
     /// Built-in web-search tool result.
     WebSearchToolResult {
@@ -356,18 +375,45 @@ impl ContentBlock {
         }
     }
 
-    /// Attach a cache-control breakpoint to a text content block (no-op on
-    /// other block types).
+    /// Attach a cache-control breakpoint to a text, image, document or tool
+    /// result block (no-op on other block types).
     pub fn with_cache_control(mut self, cc: CacheControl) -> Self {
-        if let Self::Text { cache_control, .. } = &mut self {
-            *cache_control = Some(cc);
-        }
+        self.set_cache_control(Some(cc));
         self
     }
 
+    /// Set or clear the cache-control breakpoint. Returns `false` if this
+    /// block type cannot carry one.
+    pub fn set_cache_control(&mut self, cc: Option<CacheControl>) -> bool {
+        match self {
+            Self::Text { cache_control, .. }
+            | Self::Image { cache_control, .. }
+            | Self::Document { cache_control, .. }
+            | Self::ToolResult { cache_control, .. } => {
+                *cache_control = cc;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The cache-control breakpoint on this block, if any
+    pub fn cache_control(&self) -> Option<&CacheControl> {
+        match self {
+            Self::Text { cache_control, .. }
+            | Self::Image { cache_control, .. }
+            | Self::Document { cache_control, .. }
+            | Self::ToolResult { cache_control, .. } => cache_control.as_ref(),
+            _ => None,
+        }
+    }
+
     /// Create an image content block.
     pub fn image(source: ImageSource) -> Self {
-        Self::Image { source }
+        Self::Image {
+            source,
+            cache_control: None,
+        }
     }
 
     /// Create a document content block.
@@ -377,6 +423,7 @@ impl ContentBlock {
             title: None,
             context: None,
             citations: None,
+            cache_control: None,
         }
     }
 
@@ -408,6 +455,7 @@ impl ContentBlock {
             tool_use_id: tool_use_id.into(),
             content: content.map(ToolResultContent::Text),
             is_error: Some(false),
+            cache_control: None,
         }
     }
 
@@ -417,6 +465,7 @@ impl ContentBlock {
             tool_use_id: tool_use_id.into(),
             content: Some(ToolResultContent::Json(content)),
             is_error: Some(false),
+            cache_control: None,
         }
     }
 
@@ -426,6 +475,7 @@ impl ContentBlock {
             tool_use_id: tool_use_id.into(),
             content: Some(ToolResultContent::Text(content.into())),
             is_error: Some(true),
+            cache_control: None,
         }
     }
 
@@ -437,10 +487,18 @@ impl ContentBlock {
         }
     }
 
+    /// Get the reasoning text if this is a thinking block.
+    pub fn as_thinking(&self) -> Option<&str> {
+        match self {
+            Self::Thinking { thinking, .. } => Some(thinking),
+            _ => None,
+        }
+    }
+
     /// Get image source if this is an image block.
     pub fn as_image(&self) -> Option<&ImageSource> {
         match self {
-            Self::Image { source } => Some(source),
+            Self::Image { source, .. } => Some(source),
             _ => None,
         }
     }
@@ -452,6 +510,23 @@ impl ContentBlock {
             _ => None,
         }
     }
+
+    /// Tool input with every number replaced by its text, if this is a tool
+    /// use block.
+    ///
+    /// The text is exact only with the `arbitrary-precision` feature; see
+    /// [`crate::utils::json_number`].
+    pub fn tool_input_with_raw_numbers(&self) -> Option<serde_json::Value> {
+        match self {
+            Self::ToolUse { input, .. } => {
+                Some(crate::utils::json_number::stringify_numbers(input))
+            }
+            Self::ServerToolUse { input, .. } => input
+                .as_ref()
+                .map(crate::utils::json_number::stringify_numbers),
+            _ => None,
+        }
+    }
 }
 
 /// Usage statistics.
@@ -640,7 +715,7 @@ impl Tool {
 
 /// Tool choice options
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
-#[serde(untagged)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum ToolChoice {
     /// Auto tool selection
     #[default]
@@ -780,6 +855,25 @@ mod tests {
         assert_eq!(choice, ToolChoice::Auto);
     }
 
+    #[test]
+    fn test_tool_choice_serialization() {
+        assert_eq!(
+            serde_json::to_value(ToolChoice::Auto).unwrap(),
+            serde_json::json!({"type": "auto"})
+        );
+        let forced = ToolChoice::Tool {
+            name: "emit".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_value(&forced).unwrap(),
+            serde_json::json!({"type": "tool", "name": "emit"})
+        );
+        assert_eq!(
+            serde_json::from_value::<ToolChoice>(serde_json::json!({"type": "any"})).unwrap(),
+            ToolChoice::Any
+        );
+    }
+
     #[test]
     fn test_metadata_creation() {
         let metadata = Metadata::new().with_user_id("user123").with_custom(
@@ -857,6 +951,7 @@ mod tests {
             tool_use_id,
             content,
             is_error,
+            ..
         } = tool_result
         {
             assert_eq!(tool_use_id, "tool1");
@@ -871,6 +966,7 @@ mod tests {
             tool_use_id,
             content,
             is_error,
+            ..
         } = error_result
         {
             assert_eq!(tool_use_id, "tool1");
@@ -955,6 +1051,33 @@ mod tests {
         assert_eq!(parsed, block);
     }
 
+    #[test]
+    fn test_media_and_tool_result_blocks_carry_cache_control() {
+        let blocks = [
+            ContentBlock::image(ImageSource::url("https://example.com/a.png")),
+            ContentBlock::document(DocumentSource::url("https://example.com/a.pdf")),
+            ContentBlock::tool_result("toolu_1", Some("ok".to_string())),
+        ];
+        for block in blocks {
+            assert!(serde_json::to_value(&block)
+                .unwrap()
+                .get("cache_control")
+                .is_none());
+            let cached = block.with_cache_control(CacheControl::ephemeral());
+            let value = serde_json::to_value(&cached).unwrap();
+            assert_eq!(
+                value["cache_control"],
+                serde_json::json!({"type": "ephemeral"})
+            );
+            let parsed: ContentBlock = serde_json::from_value(value).unwrap();
+            assert_eq!(parsed.cache_control(), Some(&CacheControl::ephemeral()));
+        }
+
+        let mut tool_use = ContentBlock::tool_use("toolu_1", "calc", serde_json::json!({}));
+        assert!(!tool_use.set_cache_control(Some(CacheControl::ephemeral())));
+        assert!(tool_use.cache_control().is_none());
+    }
+
     #[test]
     fn test_fallback_content_block_parses() {
         let block: ContentBlock = serde_json::from_value(serde_json::json!({
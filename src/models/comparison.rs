@@ -0,0 +1,108 @@
+//! Side-by-side comparison of two models on the same request
+
+use crate::{
+    config::models,
+    models::message::MessageResponse,
+    utils::diff::{response_diff, ResponseDiff},
+};
+use std::time::Duration;
+
+/// One model's result in a [`Comparison`].
+#[derive(Debug, Clone)]
+pub struct ComparisonSide {
+    /// Model that produced the response
+    pub model: String,
+    /// Full response
+    pub response: MessageResponse,
+    /// Wall-clock latency of the request
+    pub latency: Duration,
+    /// Estimated cost in USD, if list pricing for the model is known
+    pub cost_usd: Option<f64>,
+}
+
+impl ComparisonSide {
+    /// Build a side from a response and its measured latency
+    pub fn new(model: impl Into<String>, response: MessageResponse, latency: Duration) -> Self {
+        let model = model.into();
+        let cost_usd = models::pricing(&model).map(|p| p.cost(&response.usage));
+        Self {
+            model,
+            response,
+            latency,
+            cost_usd,
+        }
+    }
+
+    /// Total input plus output tokens
+    pub fn total_tokens(&self) -> u32 {
+        self.response.usage.total_tokens()
+    }
+
+    /// Output tokens
+    pub fn output_tokens(&self) -> u32 {
+        self.response.usage.output_tokens
+    }
+}
+
+/// Result of running the same request against two models.
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    /// Result for the first model
+    pub a: ComparisonSide,
+    /// Result for the second model
+    pub b: ComparisonSide,
+    /// Structured diff from `a`'s response to `b`'s response
+    pub diff: ResponseDiff,
+}
+
+impl Comparison {
+    /// Compare two results
+    pub fn new(a: ComparisonSide, b: ComparisonSide) -> Self {
+        let diff = response_diff(&a.response, &b.response);
+        Self { a, b, diff }
+    }
+
+    /// Whether both models returned the same text
+    pub fn text_identical(&self) -> bool {
+        self.diff.text.is_identical()
+    }
+
+    /// Line similarity of the two texts in `0.0..=1.0`
+    pub fn similarity(&self) -> f64 {
+        self.diff.text.similarity()
+    }
+
+    /// Latency of `b` minus latency of `a`, in seconds (negative when `b` is faster)
+    pub fn latency_delta_secs(&self) -> f64 {
+        self.b.latency.as_secs_f64() - self.a.latency.as_secs_f64()
+    }
+
+    /// Total tokens of `b` minus total tokens of `a`
+    pub fn token_delta(&self) -> i64 {
+        self.b.total_tokens() as i64 - self.a.total_tokens() as i64
+    }
+
+    /// Cost of `b` minus cost of `a` in USD, when both are known
+    pub fn cost_delta_usd(&self) -> Option<f64> {
+        Some(self.b.cost_usd? - self.a.cost_usd?)
+    }
+
+    /// Short human-readable summary
+    pub fn summary(&self) -> String {
+        let cost = |c: Option<f64>| c.map_or("n/a".to_string(), |c| format!("${:.6}", c));
+        format!(
+            "{}: {:.2}s, {} tokens, {}\n{}: {:.2}s, {} tokens, {}\ntext similarity: {:.1}% (+{} / -{} lines)",
+            self.a.model,
+            self.a.latency.as_secs_f64(),
+            self.a.total_tokens(),
+            cost(self.a.cost_usd),
+            self.b.model,
+            self.b.latency.as_secs_f64(),
+            self.b.total_tokens(),
+            cost(self.b.cost_usd),
+            self.similarity() * 100.0,
+            self.diff.text.insertions(),
+            self.diff.text.deletions()
+        )
+    }
+}
@@ -21,8 +21,10 @@ pub struct File {
     /// Purpose of the file
     pub purpose: String,
     /// When the file was uploaded
+    #[serde(with = "crate::utils::timestamp")]
     pub created_at: DateTime<Utc>,
     /// When the file was last modified
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub updated_at: Option<DateTime<Utc>>,
     /// File status
     pub status: Option<FileStatus>,
@@ -201,7 +201,11 @@ pub struct Agent {
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub metadata: HashMap<String, String>,
     /// ISO 8601 creation timestamp.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::utils::timestamp::option"
+    )]
     pub created_at: Option<DateTime<Utc>>,
     /// Additional fields not yet modeled explicitly.
     #[serde(flatten, default)]
@@ -57,7 +57,11 @@ pub struct Environment {
     /// Environment configuration.
     pub config: EnvironmentConfig,
     /// ISO 8601 creation timestamp.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::utils::timestamp::option"
+    )]
     pub created_at: Option<DateTime<Utc>>,
     /// Additional fields not yet modeled explicitly.
     #[serde(flatten, default)]
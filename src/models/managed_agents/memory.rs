@@ -19,7 +19,11 @@ pub struct MemoryStore {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     /// ISO 8601 creation timestamp.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::utils::timestamp::option"
+    )]
     pub created_at: Option<DateTime<Utc>>,
     /// Additional fields not yet modeled explicitly.
     #[serde(flatten, default)]
@@ -41,10 +45,18 @@ pub struct Memory {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub version: Option<String>,
     /// ISO 8601 creation timestamp.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::utils::timestamp::option"
+    )]
     pub created_at: Option<DateTime<Utc>>,
     /// ISO 8601 update timestamp.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::utils::timestamp::option"
+    )]
     pub updated_at: Option<DateTime<Utc>>,
     /// Additional fields not yet modeled explicitly.
     #[serde(flatten, default)]
@@ -66,7 +78,11 @@ pub struct MemoryVersion {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub content: Option<String>,
     /// ISO 8601 creation timestamp.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::utils::timestamp::option"
+    )]
     pub created_at: Option<DateTime<Utc>>,
     /// Additional fields not yet modeled explicitly.
     #[serde(flatten, default)]
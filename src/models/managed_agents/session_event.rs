@@ -13,6 +13,7 @@ pub struct SessionEventMeta {
     /// Unique event identifier.
     pub id: String,
     /// When the event was processed.
+    #[serde(with = "crate::utils::timestamp")]
     pub processed_at: DateTime<Utc>,
 }
 
@@ -4,12 +4,16 @@ use super::common::{
     CacheControl, ContentBlock, Metadata, Role, StopDetails, StopReason, TextCitation, Tool,
     ToolChoice, Usage, VecPush,
 };
+use super::redaction::Redacted;
+use crate::prompt_cache::CacheTtl;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt};
 
 /// A message in a conversation
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+///
+/// Its `Debug` output redacts content; see [`redaction`](super::redaction).
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct Message {
     /// Message role
     pub role: Role,
@@ -20,6 +24,22 @@ pub struct Message {
     pub metadata: Option<Metadata>,
 }
 
+impl fmt::Debug for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            role,
+            content,
+            metadata,
+        } = self;
+        let content: Vec<_> = content.iter().map(Redacted).collect();
+        f.debug_struct("Message")
+            .field("role", role)
+            .field("content", &content)
+            .field("metadata", metadata)
+            .finish()
+    }
+}
+
 impl Message {
     /// Create a new message
     pub fn new(role: Role, content: Vec<ContentBlock>) -> Self {
@@ -40,6 +60,15 @@ impl Message {
         Self::new(Role::Assistant, vec![ContentBlock::text(text)])
     }
 
+    /// Put a cache breakpoint on the last block that can carry one. Returns
+    /// `false` if no block can.
+    pub fn cache_last_block(&mut self, cache_control: CacheControl) -> bool {
+        self.content
+            .iter_mut()
+            .rev()
+            .any(|block| block.set_cache_control(Some(cache_control.clone())))
+    }
+
     /// Create a system message with text
     pub fn system(text: impl Into<String>) -> Self {
         Self::new(Role::System, vec![ContentBlock::text(text)])
@@ -336,7 +365,9 @@ impl Fallback {
 }
 
 /// Request to create a message
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+///
+/// Its `Debug` output redacts prompts; see [`redaction`](super::redaction).
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct MessageRequest {
     /// Model to use for the message
     pub model: String,
@@ -400,6 +431,57 @@ pub struct MessageRequest {
     pub fallbacks: Option<Vec<Fallback>>,
 }
 
+impl fmt::Debug for MessageRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            model,
+            max_tokens,
+            messages,
+            system,
+            temperature,
+            top_p,
+            top_k,
+            stop_sequences,
+            stream,
+            tools,
+            tool_choice,
+            thinking,
+            metadata,
+            service_tier,
+            inference_geo,
+            output_config,
+            container,
+            context_management,
+            mcp_servers,
+            cache_control,
+            fallbacks,
+        } = self;
+        f.debug_struct("MessageRequest")
+            .field("model", model)
+            .field("max_tokens", max_tokens)
+            .field("messages", messages)
+            .field("system", &system.as_ref().map(Redacted))
+            .field("temperature", temperature)
+            .field("top_p", top_p)
+            .field("top_k", top_k)
+            .field("stop_sequences", stop_sequences)
+            .field("stream", stream)
+            .field("tools", tools)
+            .field("tool_choice", tool_choice)
+            .field("thinking", thinking)
+            .field("metadata", metadata)
+            .field("service_tier", service_tier)
+            .field("inference_geo", inference_geo)
+            .field("output_config", output_config)
+            .field("container", container)
+            .field("context_management", context_management)
+            .field("mcp_servers", mcp_servers)
+            .field("cache_control", cache_control)
+            .field("fallbacks", fallbacks)
+            .finish()
+    }
+}
+
 impl MessageRequest {
     /// Create a new message request
     pub fn new() -> Self {
@@ -458,6 +540,35 @@ impl MessageRequest {
         self
     }
 
+    /// [`system_cached`](Self::system_cached) with a chosen cache lifetime
+    pub fn system_cached_with_ttl(mut self, system: impl Into<String>, ttl: CacheTtl) -> Self {
+        self.system = Some(SystemPrompt::Blocks(vec![
+            SystemBlock::text(system).with_cache_control(ttl.into())
+        ]));
+        self
+    }
+
+    /// Put a 5-minute cache breakpoint at the end of the last user message,
+    /// so the whole conversation so far is cached for the next turn
+    pub fn cache_last_user_message(self) -> Self {
+        self.cache_last_user_message_with_ttl(CacheTtl::FiveMinutes)
+    }
+
+    /// [`cache_last_user_message`](Self::cache_last_user_message) with a
+    /// chosen cache lifetime. One-hour breakpoints must come before
+    /// five-minute ones in the prompt.
+    pub fn cache_last_user_message_with_ttl(mut self, ttl: CacheTtl) -> Self {
+        if let Some(message) = self
+            .messages
+            .iter_mut()
+            .rev()
+            .find(|message| message.role == Role::User)
+        {
+            message.cache_last_block(ttl.into());
+        }
+        self
+    }
+
     /// Set the system prompt directly
     pub fn system_prompt(mut self, system: SystemPrompt) -> Self {
         self.system = Some(system);
@@ -631,6 +742,89 @@ impl MessageRequest {
         self.thinking = Some(config);
         self
     }
+
+    /// Serialize as deterministic canonical JSON: sorted keys, no whitespace,
+    /// unset optional fields omitted and stable float formatting.
+    ///
+    /// Suitable for hashing, cache keys, signatures and audit logs. See
+    /// [`crate::utils::canonical`] for the exact rules.
+    pub fn canonical_json(&self) -> crate::error::Result<String> {
+        crate::utils::canonical::to_canonical_json(self)
+    }
+
+    /// Hex SHA-256 of [`canonical_json`](Self::canonical_json), identifying
+    /// the request independently of field order and formatting
+    pub fn fingerprint(&self) -> crate::error::Result<String> {
+        let canonical = self.canonical_json()?;
+        Ok(crate::utils::integrity::ContentDigest::compute(canonical.as_bytes()).sha256_hex())
+    }
+
+    /// Rough estimate of the input tokens this request will use.
+    ///
+    /// Counts about four characters per token over the system prompt,
+    /// messages and tool definitions, plus a flat
+    /// [`MEDIA_BLOCK_TOKEN_ESTIMATE`] per image or binary document. Use
+    /// [`MessagesApi::count_tokens`](crate::api::messages::MessagesApi::count_tokens)
+    /// when an exact figure matters.
+    pub fn estimated_input_tokens(&self) -> u32 {
+        let mut tally = TokenTally::default();
+        if let Some(system) = &self.system {
+            tally.add(&serde_json::to_value(system).unwrap_or_default());
+        }
+        for message in &self.messages {
+            tally.add(&serde_json::to_value(&message.content).unwrap_or_default());
+        }
+        if let Some(tools) = &self.tools {
+            tally.add(&serde_json::to_value(tools).unwrap_or_default());
+        }
+        tally.tokens()
+    }
+}
+
+/// Flat token estimate for one image or binary document block, used by
+/// [`MessageRequest::estimated_input_tokens`]
+pub const MEDIA_BLOCK_TOKEN_ESTIMATE: u32 = 1_600;
+
+#[derive(Default)]
+struct TokenTally {
+    chars: usize,
+    media_blocks: u32,
+}
+
+impl TokenTally {
+    fn add(&mut self, value: &serde_json::Value) {
+        match value {
+            serde_json::Value::String(text) => self.chars += text.chars().count(),
+            serde_json::Value::Array(items) => items.iter().for_each(|item| self.add(item)),
+            serde_json::Value::Object(map) => {
+                let kind = map.get("type").and_then(|t| t.as_str());
+                let source_kind = map
+                    .get("source")
+                    .and_then(|s| s.get("type"))
+                    .and_then(|t| t.as_str());
+                let is_media = match kind {
+                    Some("image") => true,
+                    Some("document") => !matches!(source_kind, Some("text") | Some("content")),
+                    _ => false,
+                };
+                if is_media {
+                    self.media_blocks += 1;
+                    return;
+                }
+                for (key, value) in map {
+                    self.chars += key.len();
+                    self.add(value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn tokens(&self) -> u32 {
+        let text = u32::try_from(self.chars.div_ceil(crate::utils::text::CHARS_PER_TOKEN))
+            .unwrap_or(u32::MAX);
+        text.saturating_add(self.media_blocks.saturating_mul(MEDIA_BLOCK_TOKEN_ESTIMATE))
+    }
 }
 
 impl Default for MessageRequest {
@@ -666,8 +860,13 @@ pub struct MessageResponse {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub container: Option<serde_json::Value>,
     /// When the message was created (synthesized if absent from the response)
-    #[serde(default = "Utc::now")]
+    #[serde(default = "Utc::now", with = "crate::utils::timestamp")]
     pub created_at: DateTime<Utc>,
+    /// Generation metadata stamped by the client (see
+    /// [`provenance`](crate::models::provenance)); not part of the API
+    /// response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<crate::models::provenance::Provenance>,
 }
 
 impl MessageResponse {
@@ -686,6 +885,25 @@ impl MessageResponse {
             .collect::<Vec<_>>()
             .join(" ")
     }
+
+    /// Get the extended-thinking text of the response, one block per line.
+    ///
+    /// Redacted thinking is encrypted and not included; see
+    /// [`has_redacted_thinking`](Self::has_redacted_thinking).
+    pub fn thinking_text(&self) -> String {
+        self.content
+            .iter()
+            .filter_map(|c| c.as_thinking())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Whether any thinking was returned redacted
+    pub fn has_redacted_thinking(&self) -> bool {
+        self.content
+            .iter()
+            .any(|c| matches!(c, ContentBlock::RedactedThinking { .. }))
+    }
 }
 
 /// Request to count tokens in a message
@@ -751,6 +969,18 @@ impl Default for TokenCountRequest {
     }
 }
 
+/// Count the tokens of the input a message request would send
+impl From<&MessageRequest> for TokenCountRequest {
+    fn from(request: &MessageRequest) -> Self {
+        Self {
+            model: request.model.clone(),
+            messages: request.messages.clone(),
+            system: request.system.clone(),
+            tools: request.tools.clone(),
+        }
+    }
+}
+
 /// Response from counting tokens
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TokenCountResponse {
@@ -774,31 +1004,79 @@ pub struct MessageDelta {
 
 /// Content block delta for streaming
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub struct ContentBlockDelta {
-    /// Type of content block
-    #[serde(rename = "type")]
-    pub block_type: String,
-    /// Text delta (for text blocks)
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub text: Option<String>,
-    /// Partial JSON delta (for tool/server tool input streaming)
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub partial_json: Option<String>,
-    /// Thinking text delta
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub thinking: Option<String>,
-    /// Signature delta for thinking blocks
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub signature: Option<String>,
-    /// Citation delta (for text citations during streaming)
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub citation: Option<TextCitation>,
-    /// Additional delta fields for forward compatibility
-    #[serde(flatten, default)]
-    pub extra: HashMap<String, serde_json::Value>,
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentBlockDelta {
+    /// Text appended to a text block
+    TextDelta { text: String },
+    /// Partial JSON appended to a tool or server tool input
+    InputJsonDelta { partial_json: String },
+    /// Text appended to a thinking block
+    ThinkingDelta { thinking: String },
+    /// Signature for a thinking block
+    SignatureDelta { signature: String },
+    /// Citation added to a text block
+    CitationsDelta { citation: TextCitation },
+    /// Delta type this SDK does not know yet
+    #[serde(other)]
+    Unknown,
+}
+
+impl ContentBlockDelta {
+    /// Wire name of the delta type, e.g. `"text_delta"`
+    pub fn delta_type(&self) -> &'static str {
+        match self {
+            Self::TextDelta { .. } => "text_delta",
+            Self::InputJsonDelta { .. } => "input_json_delta",
+            Self::ThinkingDelta { .. } => "thinking_delta",
+            Self::SignatureDelta { .. } => "signature_delta",
+            Self::CitationsDelta { .. } => "citations_delta",
+            Self::Unknown => "unknown",
+        }
+    }
+
+    /// Text of a `text_delta`
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            Self::TextDelta { text } => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Partial JSON of an `input_json_delta`
+    pub fn as_partial_json(&self) -> Option<&str> {
+        match self {
+            Self::InputJsonDelta { partial_json } => Some(partial_json),
+            _ => None,
+        }
+    }
+
+    /// Thinking text of a `thinking_delta`
+    pub fn as_thinking(&self) -> Option<&str> {
+        match self {
+            Self::ThinkingDelta { thinking } => Some(thinking),
+            _ => None,
+        }
+    }
+
+    /// Signature of a `signature_delta`
+    pub fn as_signature(&self) -> Option<&str> {
+        match self {
+            Self::SignatureDelta { signature } => Some(signature),
+            _ => None,
+        }
+    }
+
+    /// Citation of a `citations_delta`
+    pub fn as_citation(&self) -> Option<&TextCitation> {
+        match self {
+            Self::CitationsDelta { citation } => Some(citation),
+            _ => None,
+        }
+    }
 }
 
 /// Streaming event types
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum StreamEvent {
@@ -878,7 +1156,33 @@ mod tests {
         }))
         .unwrap();
 
-        assert!(delta.citation.is_some());
+        assert!(delta.as_citation().is_some());
+        assert_eq!(delta.delta_type(), "citations_delta");
+    }
+
+    #[test]
+    fn test_content_block_delta_variants() {
+        let parse = |value: serde_json::Value| -> ContentBlockDelta {
+            serde_json::from_value(value).unwrap()
+        };
+
+        let delta = parse(json!({"type": "input_json_delta", "partial_json": "{\"a\":"}));
+        assert_eq!(delta.as_partial_json(), Some("{\"a\":"));
+        assert_eq!(delta.as_text(), None);
+
+        let delta = parse(json!({"type": "thinking_delta", "thinking": "hmm"}));
+        assert_eq!(delta.as_thinking(), Some("hmm"));
+        let delta = parse(json!({"type": "signature_delta", "signature": "sig"}));
+        assert_eq!(delta.as_signature(), Some("sig"));
+
+        let delta = parse(json!({"type": "hologram_delta", "frames": 3}));
+        assert_eq!(delta, ContentBlockDelta::Unknown);
+
+        let value = serde_json::to_value(ContentBlockDelta::TextDelta {
+            text: "hi".to_string(),
+        })
+        .unwrap();
+        assert_eq!(value, json!({"type": "text_delta", "text": "hi"}));
     }
 
     #[test]
@@ -927,6 +1231,44 @@ mod tests {
         assert_eq!(plain_value["system"], "you are helpful");
     }
 
+    #[test]
+    fn test_cache_breakpoints_with_ttl() {
+        let request = MessageRequest::new()
+            .system_cached_with_ttl("policy manual", CacheTtl::OneHour)
+            .add_message(Message::new(
+                Role::User,
+                vec![
+                    ContentBlock::text("see the attached result"),
+                    ContentBlock::tool_result("toolu_1", Some("42".to_string())),
+                ],
+            ))
+            .add_message(Message::assistant("noted"))
+            .add_message(Message::new(Role::User, vec![ContentBlock::Unknown]))
+            .cache_last_user_message();
+        let value = serde_json::to_value(&request).unwrap();
+        assert_eq!(
+            value["system"][0]["cache_control"],
+            json!({"type": "ephemeral", "ttl": "1h"})
+        );
+
+        // The last user message has no cacheable block, so nothing is marked.
+        assert!(request.messages.iter().all(|message| message
+            .content
+            .iter()
+            .all(|block| block.cache_control().is_none())));
+
+        let mut request = request;
+        request.messages.pop();
+        let value = serde_json::to_value(request.cache_last_user_message()).unwrap();
+        let blocks = &value["messages"][0]["content"];
+        assert!(blocks[0].get("cache_control").is_none());
+        assert_eq!(blocks[1]["type"], "tool_result");
+        assert_eq!(blocks[1]["cache_control"], json!({"type": "ephemeral"}));
+        assert!(value["messages"][1]["content"][0]
+            .get("cache_control")
+            .is_none());
+    }
+
     #[test]
     fn test_top_level_cache_control_and_fallbacks() {
         let request = MessageRequest::new()
@@ -2,13 +2,20 @@
 
 pub mod admin;
 pub mod batch;
+pub mod claude_code_metrics;
+pub mod code;
 pub mod common;
+pub mod comparison;
 pub mod completion;
 pub mod file;
 pub mod managed_agents;
 pub mod message;
 pub mod model;
+pub mod provenance;
+pub mod redaction;
+pub mod refusal;
 pub mod skill;
+pub mod structured;
 
 // Re-export commonly used types
 pub use admin::{
@@ -19,7 +26,7 @@ pub use admin::{
     Member, MemberCreateRequest, MemberRole, MemberStatus, MemberUpdateRequest,
     MessageCostReportBucket, MessageCostReportParams, MessageCostReportResponse,
     MessageUsageReportBucket, MessageUsageReportParams, MessageUsageReportResponse, ModelUsage,
-    Organization, UsageQuery, UsageReport, User, UserDeleteResponse, UserListParams,
+    Organization, TimeRange, UsageQuery, UsageReport, User, UserDeleteResponse, UserListParams,
     UserListResponse, UserRole, UserUpdateRequest, UserUpdateRole, Workspace,
     WorkspaceCreateRequest, WorkspaceDataResidency, WorkspaceListParams, WorkspaceMember,
     WorkspaceMemberCreateRequest, WorkspaceMemberCreateRole, WorkspaceMemberDeleteResponse,
@@ -27,10 +34,17 @@ pub use admin::{
     WorkspaceMemberUpdateRequest, WorkspaceStatus, WorkspaceUpdateRequest,
 };
 pub use batch::{
-    BatchResult, MessageBatch, MessageBatchCreateRequest, MessageBatchListResponse,
-    MessageBatchRequest, MessageBatchResult, MessageBatchResultEntry, MessageBatchStatus,
+    BatchResult, BatchResults, BatchRetry, BatchRetryPolicy, MessageBatch,
+    MessageBatchCreateRequest, MessageBatchListResponse, MessageBatchRequest, MessageBatchResult,
+    MessageBatchResultEntry, MessageBatchStatus, PollOptions,
+};
+pub use claude_code_metrics::{
+    ClaudeCodeActorMetrics, ClaudeCodeAdoptionSummary, ClaudeCodeDailyMetrics,
+    ClaudeCodeLeaderboardEntry, ClaudeCodeLeaderboardMetric, ClaudeCodeMetrics,
+    ClaudeCodeToolAdoption, ClaudeCodeTotals,
 };
 pub use common::*;
+pub use comparison::{Comparison, ComparisonSide};
 pub use completion::{
     CompletionRequest, CompletionResponse, CompletionStopReason, DEFAULT_COMPLETION_MODEL,
 };
@@ -59,8 +73,11 @@ pub use message::{
     ThinkingConfig, TokenCountRequest, TokenCountResponse,
 };
 pub use model::{Model, ModelFamily, ModelListResponse, ModelSize};
+pub use provenance::Provenance;
+pub use refusal::{Outcome, Refusal, RefusalFallback, RefusalPolicy};
 pub use skill::{
     Skill, SkillCreateRequest, SkillDeleteResponse, SkillFileUpload, SkillLatestVersion,
     SkillListParams, SkillListResponse, SkillVersion, SkillVersionCreateRequest,
     SkillVersionDeleteResponse, SkillVersionListParams, SkillVersionListResponse,
 };
+pub use structured::{StructuredMode, StructuredOutput};
@@ -43,16 +43,16 @@ pub struct Model {
     #[serde(default, deserialize_with = "deserialize_capabilities")]
     pub capabilities: Option<Vec<String>>,
     /// When the model was created (synthesized if absent)
-    #[serde(default = "Utc::now")]
+    #[serde(default = "Utc::now", with = "crate::utils::timestamp")]
     pub created_at: DateTime<Utc>,
     /// When the model was last updated (synthesized if absent)
-    #[serde(default = "Utc::now")]
+    #[serde(default = "Utc::now", with = "crate::utils::timestamp")]
     pub updated_at: DateTime<Utc>,
     /// Whether the model is deprecated
     #[serde(default)]
     pub deprecated: Option<bool>,
     /// Deprecation date if applicable
-    #[serde(default)]
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub deprecation_date: Option<DateTime<Utc>>,
 }
 
@@ -155,6 +155,7 @@ pub struct BenchmarkResult {
     /// Units for the score
     pub units: Option<String>,
     /// When the benchmark was run
+    #[serde(default, with = "crate::utils::timestamp::option")]
     pub run_date: Option<DateTime<Utc>>,
 }
 
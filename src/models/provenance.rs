@@ -0,0 +1,124 @@
+//! Generation metadata for provenance tracking
+//!
+//! With [`Config::with_provenance`](crate::config::Config::with_provenance)
+//! enabled, every [`MessageResponse`] the client returns carries a
+//! [`Provenance`] saying which model produced it, from which request, when,
+//! and through which SDK version. It lives in its own
+//! [`MessageResponse::provenance`] field, serialized alongside the response
+//! but never sent back to the API, and
+//! [`Conversation::record_response`](crate::conversation::Conversation::record_response)
+//! keeps it per turn so exported transcripts carry it too.
+//!
+//! ```rust,no_run
+//! use threatflux_anthropic_sdk::{models::MessageRequest, Client, Config};
+//!
+//! # async fn example() -> threatflux_anthropic_sdk::Result<()> {
+//! let client = Client::new(Config::from_env()?.with_provenance(true));
+//! let request = MessageRequest::new().max_tokens(256).add_user_message("Hello!");
+//! let mut response = client.messages().create(request, None).await?;
+//! if let Some(provenance) = response.take_provenance() {
+//!     println!(
+//!         "{} generated by {} from request {}",
+//!         response.id, provenance.model, provenance.request_hash
+//!     );
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    error::Result,
+    models::message::{MessageRequest, MessageResponse},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Version of this SDK, as recorded in [`Provenance::sdk_version`]
+pub const SDK_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Where and how a response was generated
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Provenance {
+    /// Model that generated the response, as reported by the API
+    pub model: String,
+    /// [`MessageRequest::fingerprint`] of the request as it was sent
+    pub request_hash: String,
+    /// When the response was received
+    pub generated_at: DateTime<Utc>,
+    /// SDK version that made the request
+    pub sdk_version: String,
+}
+
+impl Provenance {
+    /// Metadata for `response` to `request`, stamped now
+    pub fn new(request: &MessageRequest, response: &MessageResponse) -> Result<Self> {
+        Ok(Self::from_request_hash(request.fingerprint()?, response))
+    }
+
+    /// Metadata for `response` to the request with fingerprint
+    /// `request_hash`, stamped now
+    pub fn from_request_hash(request_hash: String, response: &MessageResponse) -> Self {
+        Self {
+            model: response.model.clone(),
+            request_hash,
+            generated_at: Utc::now(),
+            sdk_version: SDK_VERSION.to_string(),
+        }
+    }
+}
+
+impl MessageResponse {
+    /// Attach [`Provenance`] for `request` to this response
+    pub fn stamp(&mut self, request: &MessageRequest) -> Result<()> {
+        self.provenance = Some(Provenance::new(request, self)?);
+        Ok(())
+    }
+
+    /// Detach the response's provenance, leaving it unstamped
+    pub fn take_provenance(&mut self) -> Option<Provenance> {
+        self.provenance.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stamp_and_detach() {
+        let request = MessageRequest::new()
+            .model("claude-haiku-4-5")
+            .max_tokens(16)
+            .add_user_message("Hi");
+        let mut response: MessageResponse = serde_json::from_value(serde_json::json!({
+            "id": "msg_1",
+            "type": "message",
+            "role": "assistant",
+            "content": [{"type": "text", "text": "Hello"}],
+            "model": "claude-haiku-4-5-20251001",
+            "stop_reason": "end_turn",
+            "stop_sequence": null,
+            "usage": {"input_tokens": 1, "output_tokens": 1}
+        }))
+        .unwrap();
+        assert!(response.provenance.is_none());
+
+        response.stamp(&request).unwrap();
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(
+            json["provenance"]["request_hash"],
+            request.fingerprint().unwrap()
+        );
+        assert_eq!(json["provenance"]["sdk_version"], SDK_VERSION);
+        let restored: MessageResponse = serde_json::from_value(json).unwrap();
+        assert_eq!(restored.provenance, response.provenance);
+
+        let provenance = response.take_provenance().unwrap();
+        assert_eq!(provenance.model, "claude-haiku-4-5-20251001");
+        assert!(response.provenance.is_none());
+        assert!(serde_json::to_value(&response)
+            .unwrap()
+            .get("provenance")
+            .is_none());
+    }
+}
@@ -0,0 +1,104 @@
+//! Prompt redaction in `Debug` output
+//!
+//! `{:?}` on a [`Message`](super::Message) or
+//! [`MessageRequest`](super::MessageRequest) shows each content block and the
+//! system prompt as a short preview with its length and a SHA-256 prefix, so
+//! logging a request by accident does not leak the whole prompt while two
+//! log lines can still be matched up:
+//!
+//! ```text
+//! Message { role: User, content: [text("Summarize the attached f"… (4096 bytes, sha256:3f2a9c01d4e7))], metadata: None }
+//! ```
+//!
+//! Build with the `debug-full` feature, or call [`set_full_debug`], to print
+//! everything.
+
+use crate::utils::integrity::ContentDigest;
+use serde::Serialize;
+use std::{
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+static FULL_DEBUG: AtomicBool = AtomicBool::new(cfg!(feature = "debug-full"));
+
+/// Characters of content shown before it is cut off
+const PREVIEW_CHARS: usize = 24;
+
+/// Print prompt content in full (`true`) or redacted (`false`) from now on,
+/// process-wide
+pub fn set_full_debug(enabled: bool) {
+    FULL_DEBUG.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether prompt content is printed in full
+pub fn full_debug() -> bool {
+    FULL_DEBUG.load(Ordering::Relaxed)
+}
+
+/// Debug-formats the wrapped value redacted unless [`full_debug`] is on
+pub(crate) struct Redacted<'a, T>(pub &'a T);
+
+impl<T: fmt::Debug + Serialize> fmt::Debug for Redacted<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if full_debug() {
+            return self.0.fmt(f);
+        }
+        let value = serde_json::to_value(self.0).unwrap_or_default();
+        let (kind, content) = match &value {
+            serde_json::Value::String(text) => (None, text.clone()),
+            serde_json::Value::Object(fields) => (
+                fields.get("type").and_then(|kind| kind.as_str()),
+                match fields.get("text").and_then(|text| text.as_str()) {
+                    Some(text) => text.to_string(),
+                    None => value.to_string(),
+                },
+            ),
+            other => (None, other.to_string()),
+        };
+
+        let preview: String = content.chars().take(PREVIEW_CHARS).collect();
+        let ellipsis = if preview.len() < content.len() {
+            "…"
+        } else {
+            ""
+        };
+        let hash = ContentDigest::compute(content.as_bytes()).sha256_hex();
+        let summary = format!(
+            "{:?}{} ({} bytes, sha256:{})",
+            preview,
+            ellipsis,
+            content.len(),
+            &hash[..12]
+        );
+        match kind {
+            Some(kind) => write!(f, "{}({})", kind, summary),
+            None => f.write_str(&summary),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::message::{Message, MessageRequest};
+
+    #[test]
+    fn test_prompts_are_redacted_unless_enabled() {
+        let secret = "The launch code is 0000 and the vault combination is 1234";
+        let request = MessageRequest::new()
+            .system(secret)
+            .add_message(Message::user(secret));
+
+        set_full_debug(false);
+        let redacted = format!("{:?}", request);
+        assert!(!redacted.contains("vault combination"), "{}", redacted);
+        assert!(redacted.contains("text(\"The launch code is 0000 \"… (57 bytes, sha256:"));
+        assert!(redacted.contains("system: Some(\"The launch code"));
+
+        set_full_debug(true);
+        let full = format!("{:?}", request);
+        set_full_debug(cfg!(feature = "debug-full"));
+        assert!(full.contains(&format!("{:?}", secret)));
+    }
+}
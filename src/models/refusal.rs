@@ -0,0 +1,294 @@
+//! Typed refusal outcomes and client-side refusal fallbacks
+//!
+//! A response whose `stop_reason` is `refusal` (or whose `stop_details` are
+//! typed `refusal`) is still a successful HTTP response, so callers used to
+//! inspect it by hand. [`MessageResponse::outcome`] sorts a response into an
+//! [`Outcome`], and
+//! [`MessagesApi::create_outcome`](crate::api::messages::MessagesApi::create_outcome)
+//! additionally works through a [`RefusalPolicy`]'s fallbacks, such as
+//! retrying on another model or with a rephrased prompt, before reporting a
+//! refusal.
+
+use crate::models::{
+    common::StopReason,
+    message::{MessageRequest, MessageResponse},
+};
+use std::{fmt, sync::Arc};
+
+/// Why a response was declined
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Refusal {
+    /// The API's explanation, or the text the model produced instead
+    pub reason_text: Option<String>,
+    /// Policy category from `stop_details`, e.g. `"cyber"`
+    pub category: Option<String>,
+    /// Model the API suggests retrying with
+    pub recommended_model: Option<String>,
+}
+
+impl Refusal {
+    /// The refusal carried by `response`, if it was declined
+    pub fn from_response(response: &MessageResponse) -> Option<Self> {
+        let details = response.stop_details.as_ref();
+        let refused = matches!(response.stop_reason, Some(StopReason::Refusal))
+            || details.and_then(|d| d.detail_type.as_deref()) == Some("refusal");
+        if !refused {
+            return None;
+        }
+
+        let text = response.text();
+        let reason_text = details
+            .and_then(|d| d.explanation.clone())
+            .or_else(|| (!text.trim().is_empty()).then(|| text.trim().to_string()));
+        Some(Self {
+            reason_text,
+            category: details.and_then(|d| d.category.clone()),
+            recommended_model: details.and_then(|d| d.recommended_model.clone()),
+        })
+    }
+}
+
+/// Result of a message request, with refusals split out
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    /// The model answered
+    Completed(MessageResponse),
+    /// The model declined, and no fallback produced an answer
+    Refused {
+        /// The API's explanation, or the text the model produced instead
+        reason_text: Option<String>,
+        /// Policy category from `stop_details`
+        category: Option<String>,
+        /// The (last) declining response
+        response: MessageResponse,
+    },
+}
+
+impl Outcome {
+    /// Whether the model declined
+    pub fn is_refused(&self) -> bool {
+        matches!(self, Self::Refused { .. })
+    }
+
+    /// The underlying response, answered or not
+    pub fn response(&self) -> &MessageResponse {
+        match self {
+            Self::Completed(response) | Self::Refused { response, .. } => response,
+        }
+    }
+
+    /// The underlying response, answered or not
+    pub fn into_response(self) -> MessageResponse {
+        match self {
+            Self::Completed(response) | Self::Refused { response, .. } => response,
+        }
+    }
+
+    /// The answer, or `None` if the model declined
+    pub fn completed(self) -> Option<MessageResponse> {
+        match self {
+            Self::Completed(response) => Some(response),
+            Self::Refused { .. } => None,
+        }
+    }
+}
+
+impl MessageResponse {
+    /// Details of the refusal, if the response was declined
+    pub fn refusal(&self) -> Option<Refusal> {
+        Refusal::from_response(self)
+    }
+
+    /// Classify this response as answered or refused
+    pub fn outcome(self) -> Outcome {
+        match Refusal::from_response(&self) {
+            None => Outcome::Completed(self),
+            Some(refusal) => Outcome::Refused {
+                reason_text: refusal.reason_text,
+                category: refusal.category,
+                response: self,
+            },
+        }
+    }
+}
+
+/// Builds a replacement request after a refusal; `None` skips the step
+pub type RephraseFn = dyn Fn(&MessageRequest, &Refusal) -> Option<MessageRequest> + Send + Sync;
+
+/// One client-side step to try after a refusal
+#[derive(Clone)]
+pub enum RefusalFallback {
+    /// Resend the request on another model
+    Model(String),
+    /// Resend on `stop_details.recommended_model`; skipped when absent
+    RecommendedModel,
+    /// Resend a rewritten request
+    Rephrase(Arc<RephraseFn>),
+}
+
+impl RefusalFallback {
+    /// Rewrite the request with a closure
+    pub fn rephrase<F>(rephrase: F) -> Self
+    where
+        F: Fn(&MessageRequest, &Refusal) -> Option<MessageRequest> + Send + Sync + 'static,
+    {
+        Self::Rephrase(Arc::new(rephrase))
+    }
+
+    /// The request to send for this step, derived from the original request
+    pub fn next_request(
+        &self,
+        original: &MessageRequest,
+        refusal: &Refusal,
+    ) -> Option<MessageRequest> {
+        match self {
+            Self::Model(model) => Some(original.clone().model(model.clone())),
+            Self::RecommendedModel => refusal
+                .recommended_model
+                .as_ref()
+                .filter(|model| **model != original.model)
+                .map(|model| original.clone().model(model.clone())),
+            Self::Rephrase(rephrase) => rephrase(original, refusal),
+        }
+    }
+}
+
+impl fmt::Debug for RefusalFallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Model(model) => f.debug_tuple("Model").field(model).finish(),
+            Self::RecommendedModel => f.write_str("RecommendedModel"),
+            Self::Rephrase(_) => f.write_str("Rephrase(..)"),
+        }
+    }
+}
+
+/// Ordered client-side fallbacks to try when a request is refused.
+///
+/// Each step starts again from the original request, and the first answered
+/// response wins. The default policy has no steps.
+#[derive(Debug, Clone, Default)]
+pub struct RefusalPolicy {
+    /// Steps, tried in order
+    pub fallbacks: Vec<RefusalFallback>,
+}
+
+impl RefusalPolicy {
+    /// Create a policy with no fallbacks
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a fallback step
+    pub fn with_fallback(mut self, fallback: RefusalFallback) -> Self {
+        self.fallbacks.push(fallback);
+        self
+    }
+
+    /// Retry on `model`
+    pub fn with_fallback_model(self, model: impl Into<String>) -> Self {
+        self.with_fallback(RefusalFallback::Model(model.into()))
+    }
+
+    /// Retry on the model the API recommends, when it names one
+    pub fn with_recommended_model(self) -> Self {
+        self.with_fallback(RefusalFallback::RecommendedModel)
+    }
+
+    /// Retry with a request rewritten by `rephrase`
+    pub fn with_rephrase<F>(self, rephrase: F) -> Self
+    where
+        F: Fn(&MessageRequest, &Refusal) -> Option<MessageRequest> + Send + Sync + 'static,
+    {
+        self.with_fallback(RefusalFallback::rephrase(rephrase))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn response(stop_reason: &str, stop_details: serde_json::Value) -> MessageResponse {
+        serde_json::from_value(json!({
+            "id": "msg_1",
+            "type": "message",
+            "role": "assistant",
+            "model": "claude-haiku-4-5",
+            "content": [{"type": "text", "text": " I can't help with that. "}],
+            "stop_reason": stop_reason,
+            "stop_sequence": null,
+            "stop_details": stop_details,
+            "usage": {"input_tokens": 5, "output_tokens": 6}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_outcome_classifies_refusals() {
+        assert!(!response("end_turn", json!(null)).outcome().is_refused());
+
+        let outcome = response("refusal", json!(null)).outcome();
+        let Outcome::Refused {
+            reason_text,
+            category,
+            ..
+        } = &outcome
+        else {
+            panic!("expected refusal");
+        };
+        assert_eq!(reason_text.as_deref(), Some("I can't help with that."));
+        assert_eq!(*category, None);
+
+        // Refusal-typed stop details count even without the stop reason.
+        let refusal = response(
+            "end_turn",
+            json!({"type": "refusal", "category": "cyber", "explanation": "Policy"}),
+        )
+        .refusal()
+        .unwrap();
+        assert_eq!(refusal.reason_text.as_deref(), Some("Policy"));
+        assert_eq!(refusal.category.as_deref(), Some("cyber"));
+    }
+
+    #[test]
+    fn test_fallback_steps_derive_from_original_request() {
+        let original = MessageRequest::new()
+            .model("claude-haiku-4-5")
+            .add_user_message("question");
+        let refusal = Refusal {
+            recommended_model: Some("claude-opus-4-8".to_string()),
+            ..Refusal::default()
+        };
+
+        let policy = RefusalPolicy::new()
+            .with_recommended_model()
+            .with_fallback_model("claude-sonnet-4-6")
+            .with_rephrase(|request, _| {
+                Some(
+                    request
+                        .clone()
+                        .system("Answer at a high level, for a general audience."),
+                )
+            });
+        let models: Vec<_> = policy
+            .fallbacks
+            .iter()
+            .filter_map(|step| step.next_request(&original, &refusal))
+            .map(|request| request.model)
+            .collect();
+        assert_eq!(
+            models,
+            ["claude-opus-4-8", "claude-sonnet-4-6", "claude-haiku-4-5"]
+        );
+
+        // No recommendation means the step is skipped.
+        assert!(RefusalFallback::RecommendedModel
+            .next_request(&original, &Refusal::default())
+            .is_none());
+        assert_eq!(
+            format!("{:?}", policy.fallbacks[2]),
+            "Rephrase(..)".to_string()
+        );
+    }
+}
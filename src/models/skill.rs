@@ -35,7 +35,11 @@ pub struct Skill {
     /// Skill ID.
     pub id: String,
     /// ISO 8601 creation timestamp.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::utils::timestamp::option"
+    )]
     pub created_at: Option<DateTime<Utc>>,
     /// Human-friendly display title.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -47,7 +51,11 @@ pub struct Skill {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub source: Option<String>,
     /// ISO 8601 update timestamp.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::utils::timestamp::option"
+    )]
     pub updated_at: Option<DateTime<Utc>>,
     /// Additional fields not yet modeled explicitly.
     #[serde(flatten, default)]
@@ -63,7 +71,11 @@ pub struct SkillVersion {
     /// Unique identifier for the skill version.
     pub id: String,
     /// ISO 8601 creation timestamp.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::utils::timestamp::option"
+    )]
     pub created_at: Option<DateTime<Utc>>,
     /// Description extracted from `SKILL.md`.
     #[serde(skip_serializing_if = "Option::is_none")]
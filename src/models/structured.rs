@@ -0,0 +1,277 @@
+//! Structured output: responses deserialized into Rust types
+//!
+//! [`MessagesApi::create_structured`](crate::api::messages::MessagesApi::create_structured)
+//! (with the `schemars` feature) derives a JSON Schema from the target type,
+//! constrains the response to it, and returns the parsed value. When the
+//! model's JSON does not deserialize, the error is shown to the model and the
+//! request is retried, up to [`StructuredOutput::max_retries`] times.
+//!
+//! Two ways of constraining the response are supported:
+//!
+//! * [`StructuredMode::OutputFormat`] uses the API's `output_config.format`
+//!   JSON Schema setting, and the JSON arrives as the response text.
+//! * [`StructuredMode::ToolForcing`] defines a single tool whose input schema
+//!   is the type's schema and forces the model to call it, for models
+//!   without structured output support.
+//!
+//! Without `schemars`, pass a hand-written schema to
+//! [`create_structured_with_schema`](crate::api::messages::MessagesApi::create_structured_with_schema).
+
+use crate::{
+    error::{AnthropicError, Result},
+    models::{
+        common::{ContentBlock, Role, Tool, ToolChoice},
+        message::{Message, MessageRequest, MessageResponse, OutputConfig, OutputFormat},
+    },
+};
+use serde::de::DeserializeOwned;
+
+/// Default name of the tool used by [`StructuredMode::ToolForcing`]
+pub const STRUCTURED_TOOL_NAME: &str = "structured_output";
+
+/// How the response is constrained to the schema
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum StructuredMode {
+    /// `output_config.format` with a JSON Schema
+    #[default]
+    OutputFormat,
+    /// A forced call to a tool taking the schema as its input
+    ToolForcing {
+        /// Tool name shown to the model
+        tool_name: String,
+    },
+}
+
+/// Settings for a structured request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructuredOutput {
+    /// How the schema is applied
+    pub mode: StructuredMode,
+    /// Further attempts after a response fails to parse
+    pub max_retries: u32,
+}
+
+impl Default for StructuredOutput {
+    fn default() -> Self {
+        Self {
+            mode: StructuredMode::default(),
+            max_retries: 2,
+        }
+    }
+}
+
+impl StructuredOutput {
+    /// Output-format mode with two retries
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use tool forcing with the default tool name
+    pub fn tool_forcing() -> Self {
+        Self::new().with_mode(StructuredMode::ToolForcing {
+            tool_name: STRUCTURED_TOOL_NAME.to_string(),
+        })
+    }
+
+    /// Set the mode
+    pub fn with_mode(mut self, mode: StructuredMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Set how many times to retry after a parse failure
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// `request` constrained to `schema`
+    pub fn apply(&self, mut request: MessageRequest, schema: serde_json::Value) -> MessageRequest {
+        match &self.mode {
+            StructuredMode::OutputFormat => {
+                let config = request.output_config.take().unwrap_or_default();
+                request.output_config = Some(config.with_format(OutputFormat::json_schema(schema)));
+            }
+            StructuredMode::ToolForcing { tool_name } => {
+                let tool = Tool::new(
+                    tool_name.clone(),
+                    "Respond by calling this tool with the answer as its input.",
+                    schema,
+                );
+                let tools = request.tools.get_or_insert_with(Vec::new);
+                tools.retain(|existing| existing.name != *tool_name);
+                tools.push(tool);
+                request.tool_choice = Some(ToolChoice::Tool {
+                    name: tool_name.clone(),
+                });
+            }
+        }
+        request
+    }
+
+    /// The JSON the model produced in `response`
+    pub fn extract(&self, response: &MessageResponse) -> Result<serde_json::Value> {
+        match &self.mode {
+            StructuredMode::OutputFormat => {
+                let text = response.text();
+                serde_json::from_str(strip_code_fence(&text)).map_err(|e| {
+                    AnthropicError::invalid_input(format!("Response is not valid JSON: {}", e))
+                })
+            }
+            StructuredMode::ToolForcing { tool_name } => response
+                .content
+                .iter()
+                .find_map(|block| match block {
+                    ContentBlock::ToolUse { name, input, .. } if name == tool_name => {
+                        Some(input.clone())
+                    }
+                    _ => None,
+                })
+                .ok_or_else(|| {
+                    AnthropicError::invalid_input(format!(
+                        "Response did not call the {} tool",
+                        tool_name
+                    ))
+                }),
+        }
+    }
+
+    /// `T` parsed from `response`
+    pub fn parse<T: DeserializeOwned>(&self, response: &MessageResponse) -> Result<T> {
+        let value = self.extract(response)?;
+        serde_json::from_value(value).map_err(|e| {
+            AnthropicError::invalid_input(format!("Response does not match the schema: {}", e))
+        })
+    }
+
+    /// The follow-up turns asking the model to correct `response`, which
+    /// failed with `error`
+    pub(crate) fn correction(
+        &self,
+        response: &MessageResponse,
+        error: &AnthropicError,
+    ) -> [Message; 2] {
+        let assistant = Message::new(Role::Assistant, response.content.clone());
+        let feedback = format!(
+            "{}. Respond again with output that matches the schema exactly.",
+            error
+        );
+        let tool_use_id = match &self.mode {
+            StructuredMode::ToolForcing { tool_name } => {
+                response.content.iter().find_map(|block| match block {
+                    ContentBlock::ToolUse { id, name, .. } if name == tool_name => Some(id.clone()),
+                    _ => None,
+                })
+            }
+            StructuredMode::OutputFormat => None,
+        };
+        let user = match tool_use_id {
+            Some(id) => Message::new(Role::User, vec![ContentBlock::tool_error(id, feedback)]),
+            None => Message::user(feedback),
+        };
+        [assistant, user]
+    }
+}
+
+impl OutputConfig {
+    /// The JSON Schema set by [`OutputConfig::json_schema`], if any
+    pub fn schema(&self) -> Option<&serde_json::Value> {
+        match &self.format {
+            Some(OutputFormat::JsonSchema { schema }) => Some(schema),
+            None => None,
+        }
+    }
+}
+
+/// `text` without a surrounding Markdown code fence
+fn strip_code_fence(text: &str) -> &str {
+    let trimmed = text.trim();
+    let Some(body) = trimmed.strip_prefix("```") else {
+        return trimmed;
+    };
+    let body = body.strip_suffix("```").unwrap_or(body);
+    // Skip the info string, e.g. "json"
+    match body.find('\n') {
+        Some(newline) => body[newline + 1..].trim(),
+        None => body.trim(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Verdict {
+        label: String,
+        score: f64,
+    }
+
+    fn response(content: serde_json::Value) -> MessageResponse {
+        serde_json::from_value(json!({
+            "id": "msg_1",
+            "type": "message",
+            "role": "assistant",
+            "model": "claude-haiku-4-5",
+            "content": content,
+            "stop_reason": "end_turn",
+            "stop_sequence": null,
+            "usage": {"input_tokens": 1, "output_tokens": 1}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_output_format_parses_fenced_text() {
+        let structured = StructuredOutput::new();
+        let schema = json!({"type": "object"});
+        let request = structured.apply(MessageRequest::new(), schema.clone());
+        assert_eq!(request.output_config.unwrap().schema(), Some(&schema));
+
+        let fenced = response(json!([
+            {"type": "text", "text": "```json\n{\"label\": \"spam\", \"score\": 0.9}\n```"}
+        ]));
+        assert_eq!(
+            structured.parse::<Verdict>(&fenced).unwrap(),
+            Verdict {
+                label: "spam".to_string(),
+                score: 0.9
+            }
+        );
+
+        let wrong = response(json!([{"type": "text", "text": "{\"label\": \"spam\"}"}]));
+        let err = structured.parse::<Verdict>(&wrong).unwrap_err();
+        assert!(err.to_string().contains("missing field `score`"));
+        let [_, user] = structured.correction(&wrong, &err);
+        assert!(user.text().contains("missing field `score`"));
+    }
+
+    #[test]
+    fn test_tool_forcing_reads_tool_input() {
+        let structured = StructuredOutput::tool_forcing();
+        let request = structured.apply(MessageRequest::new(), json!({"type": "object"}));
+        assert_eq!(request.tools.as_ref().unwrap().len(), 1);
+        assert_eq!(
+            request.tool_choice,
+            Some(ToolChoice::Tool {
+                name: STRUCTURED_TOOL_NAME.to_string()
+            })
+        );
+
+        let called = response(json!([{
+            "type": "tool_use",
+            "id": "toolu_1",
+            "name": STRUCTURED_TOOL_NAME,
+            "input": {"label": "ham", "score": "high"}
+        }]));
+        let err = structured.parse::<Verdict>(&called).unwrap_err();
+        let [assistant, user] = structured.correction(&called, &err);
+        assert_eq!(assistant.role, Role::Assistant);
+        assert!(matches!(
+            &user.content[0],
+            ContentBlock::ToolResult { tool_use_id, is_error: Some(true), .. } if tool_use_id == "toolu_1"
+        ));
+    }
+}
@@ -0,0 +1,339 @@
+//! Archiving batch results before they stop being downloadable
+//!
+//! A batch's results can only be downloaded for
+//! [`RESULTS_RETENTION`](crate::models::batch::RESULTS_RETENTION) after
+//! the batch is created; after that they are lost without notice.
+//!
+//! [`archive_batches`] walks the account's batches and, for each ended one
+//! not archived on an earlier run, downloads its results to a temporary file
+//! and hands that file to an [`ArchiveSink`]: a [`DirectorySink`], an
+//! [`ObjectStoreSink`] uploading to a bucket, or your own. A JSON manifest
+//! records what went where, so running it on a schedule only downloads new
+//! batches.
+//!
+//! ```rust,no_run
+//! use threatflux_anthropic_sdk::{
+//!     pipelines::{archive_batches, ArchiveOptions, DirectorySink},
+//!     Client,
+//! };
+//!
+//! # async fn example() -> threatflux_anthropic_sdk::Result<()> {
+//! let client = Client::from_env()?;
+//! let sink = DirectorySink::new("/var/lib/batches");
+//! let options = ArchiveOptions::new("/var/lib/batches/manifest.json");
+//!
+//! let report = archive_batches(&client, &sink, &options).await?;
+//! println!("archived {} batches", report.archived.len());
+//! for id in &report.expired {
+//!     eprintln!("results of {} expired before they were archived", id);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    client::Client,
+    error::{AnthropicError, Result},
+    models::batch::MessageBatch,
+    types::Pagination,
+    utils::object_store::ObjectStore,
+};
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+/// Where archived batch results are kept
+pub trait ArchiveSink: Send + Sync {
+    /// Store the results of `batch`, downloaded to `file`, and return where
+    /// they were put. `file` may be removed once this returns.
+    fn store<'a>(
+        &'a self,
+        batch: &'a MessageBatch,
+        file: &'a Path,
+    ) -> BoxFuture<'a, Result<String>>;
+}
+
+/// Keeps results as `<batch id>.jsonl` files in a directory
+#[derive(Debug, Clone)]
+pub struct DirectorySink {
+    dir: PathBuf,
+}
+
+impl DirectorySink {
+    /// Store results in `dir`, created if missing
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+impl ArchiveSink for DirectorySink {
+    fn store<'a>(
+        &'a self,
+        batch: &'a MessageBatch,
+        file: &'a Path,
+    ) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let target = self.dir.join(format!("{}.jsonl", batch.id));
+            let partial = target.with_extension("partial");
+            tokio::fs::create_dir_all(&self.dir)
+                .await
+                .map_err(|e| file_error("create", &self.dir, e))?;
+            tokio::fs::copy(file, &partial)
+                .await
+                .map_err(|e| file_error("write", &partial, e))?;
+            tokio::fs::rename(&partial, &target)
+                .await
+                .map_err(|e| file_error("rename", &partial, e))?;
+            Ok(target.display().to_string())
+        })
+    }
+}
+
+/// Uploads results to an [`ObjectStore`] as `<prefix><batch id>.jsonl`
+#[derive(Clone)]
+pub struct ObjectStoreSink {
+    store: Arc<dyn ObjectStore>,
+    prefix: String,
+}
+
+impl ObjectStoreSink {
+    /// Store results in `store` under keys starting with `prefix`, e.g.
+    /// `batches/`
+    pub fn new(store: impl ObjectStore + 'static, prefix: impl Into<String>) -> Self {
+        Self {
+            store: Arc::new(store),
+            prefix: prefix.into(),
+        }
+    }
+}
+
+impl ArchiveSink for ObjectStoreSink {
+    fn store<'a>(
+        &'a self,
+        batch: &'a MessageBatch,
+        file: &'a Path,
+    ) -> BoxFuture<'a, Result<String>> {
+        Box::pin(async move {
+            let bytes = tokio::fs::read(file)
+                .await
+                .map_err(|e| file_error("read", file, e))?;
+            let key = format!("{}{}.jsonl", self.prefix, batch.id);
+            self.store.put(&key, bytes).await?;
+            Ok(key)
+        })
+    }
+}
+
+fn file_error(action: &str, path: &Path, error: std::io::Error) -> AnthropicError {
+    AnthropicError::file_error(format!(
+        "Failed to {} {}: {}",
+        action,
+        path.display(),
+        error
+    ))
+}
+
+/// A batch whose results were archived
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchivedBatch {
+    /// Where the sink put the results
+    pub location: String,
+    /// Size of the results in bytes
+    pub bytes: u64,
+    /// When they were archived
+    pub archived_at: DateTime<Utc>,
+    /// When they stop being downloadable from the API
+    pub results_archive_at: DateTime<Utc>,
+}
+
+/// Record of archived batches, by batch id
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    /// Archived batches
+    pub batches: BTreeMap<String, ArchivedBatch>,
+}
+
+impl ArchiveManifest {
+    /// Read the manifest at `path`; a missing file is an empty manifest
+    pub async fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        match tokio::fs::read(path).await {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| {
+                AnthropicError::invalid_input(format!(
+                    "Invalid archive manifest {}: {}",
+                    path.display(),
+                    e
+                ))
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(file_error("read", path, e)),
+        }
+    }
+
+    /// Write the manifest to `path`, replacing it atomically
+    pub async fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| file_error("create", parent, e))?;
+        }
+        let partial = path.with_extension("partial");
+        tokio::fs::write(&partial, serde_json::to_vec_pretty(self)?)
+            .await
+            .map_err(|e| file_error("write", &partial, e))?;
+        tokio::fs::rename(&partial, path)
+            .await
+            .map_err(|e| file_error("rename", &partial, e))
+    }
+
+    /// Whether `batch_id` was archived
+    pub fn contains(&self, batch_id: &str) -> bool {
+        self.batches.contains_key(batch_id)
+    }
+}
+
+/// Settings for [`archive_batches`]
+#[derive(Debug, Clone)]
+pub struct ArchiveOptions {
+    /// Path of the JSON manifest of archived batches
+    pub manifest: PathBuf,
+    /// Directory results are downloaded to before the sink stores them
+    pub temp_dir: PathBuf,
+    /// Leave the downloaded files in `temp_dir` instead of deleting them
+    pub keep_temp_files: bool,
+    /// Only archive batches whose results expire within this long
+    pub due_within: Option<Duration>,
+}
+
+impl ArchiveOptions {
+    /// Track archived batches in the manifest at `manifest`
+    pub fn new(manifest: impl Into<PathBuf>) -> Self {
+        Self {
+            manifest: manifest.into(),
+            temp_dir: std::env::temp_dir(),
+            keep_temp_files: false,
+            due_within: None,
+        }
+    }
+
+    /// Download results into `dir` before storing them
+    pub fn with_temp_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.temp_dir = dir.into();
+        self
+    }
+
+    /// Keep the downloaded files after the sink has stored them
+    pub fn keep_temp_files(mut self, keep: bool) -> Self {
+        self.keep_temp_files = keep;
+        self
+    }
+
+    /// Leave batches alone until their results expire within `window`,
+    /// e.g. to archive only what is about to be lost
+    pub fn with_due_within(mut self, window: Duration) -> Self {
+        self.due_within = Some(window);
+        self
+    }
+}
+
+/// Outcome of [`archive_batches`]
+#[derive(Debug, Default)]
+pub struct ArchiveReport {
+    /// Batches archived on this run
+    pub archived: Vec<String>,
+    /// Batches already in the manifest, still processing, or not yet due
+    pub skipped: usize,
+    /// Ended batches whose results expired before they were archived
+    pub expired: Vec<String>,
+    /// Batches that could not be archived, with why
+    pub failed: Vec<(String, AnthropicError)>,
+}
+
+/// Archive the results of every ended batch not yet in the manifest.
+///
+/// The manifest is saved after each archived batch, so an interrupted run
+/// loses nothing. A batch that fails to download or store is reported in
+/// [`ArchiveReport::failed`] and retried on the next run; listing batches
+/// or saving the manifest failing ends the run with an error.
+pub async fn archive_batches(
+    client: &Client,
+    sink: &dyn ArchiveSink,
+    options: &ArchiveOptions,
+) -> Result<ArchiveReport> {
+    let batches = client.message_batches();
+    let mut manifest = ArchiveManifest::load(&options.manifest).await?;
+    let mut report = ArchiveReport::default();
+    let mut page = Some(Pagination::new().with_limit(100));
+    while let Some(pagination) = page.take() {
+        let response = batches.list(Some(pagination), None).await?;
+        page = response.next_page_params();
+        for batch in response.data {
+            let archive_at = batch.results_archive_at();
+            let now = Utc::now();
+            let not_due = options.due_within.is_some_and(|window| {
+                chrono::Duration::from_std(window).is_ok_and(|window| archive_at - now > window)
+            });
+            if manifest.contains(&batch.id) || !batch.is_complete() || not_due {
+                report.skipped += 1;
+                continue;
+            }
+            if archive_at <= now {
+                report.expired.push(batch.id);
+                continue;
+            }
+
+            match archive_one(client, sink, options, &batch).await {
+                Ok((location, bytes)) => {
+                    manifest.batches.insert(
+                        batch.id.clone(),
+                        ArchivedBatch {
+                            location,
+                            bytes,
+                            archived_at: Utc::now(),
+                            results_archive_at: archive_at,
+                        },
+                    );
+                    manifest.save(&options.manifest).await?;
+                    report.archived.push(batch.id);
+                }
+                Err(error) => {
+                    tracing::warn!("Failed to archive batch {}: {}", batch.id, error);
+                    report.failed.push((batch.id, error));
+                }
+            }
+        }
+    }
+    Ok(report)
+}
+
+async fn archive_one(
+    client: &Client,
+    sink: &dyn ArchiveSink,
+    options: &ArchiveOptions,
+    batch: &MessageBatch,
+) -> Result<(String, u64)> {
+    tokio::fs::create_dir_all(&options.temp_dir)
+        .await
+        .map_err(|e| file_error("create", &options.temp_dir, e))?;
+    let file = options.temp_dir.join(format!("{}.jsonl", batch.id));
+    let result = async {
+        let bytes = client
+            .message_batches()
+            .results_to_file(&batch.id, &file, None)
+            .await?;
+        let location = sink.store(batch, &file).await?;
+        Ok((location, bytes))
+    }
+    .await;
+    if result.is_err() || !options.keep_temp_files {
+        let _ = tokio::fs::remove_file(&file).await;
+    }
+    result
+}
@@ -0,0 +1,422 @@
+//! CSV in, batch results CSV out (feature `csv`)
+
+use crate::{
+    builders::{BatchBuilder, PromptTemplate},
+    client::Client,
+    error::{AnthropicError, Result},
+    models::batch::{MessageBatchResult, PollOptions},
+};
+use std::{collections::BTreeMap, path::Path, time::Duration};
+
+/// Options for [`csv_batch`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvBatchOptions {
+    /// Field delimiter of the input (`b','` for CSV, `b'\t'` for TSV)
+    pub delimiter: u8,
+    /// Column whose value becomes the request's `custom_id`; rows are
+    /// numbered `row-0`, `row-1`, ... when unset. Characters the API does
+    /// not allow in custom IDs are replaced with `_`.
+    pub id_column: Option<String>,
+    /// Copy the input columns into the results file
+    pub keep_input_columns: bool,
+    /// Maximum rows per submitted batch
+    pub rows_per_batch: usize,
+    /// How often to poll for batch completion
+    pub poll_interval: Duration,
+    /// Give up waiting for a batch after this long
+    pub max_wait: Duration,
+}
+
+impl Default for CsvBatchOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            id_column: None,
+            keep_input_columns: true,
+            rows_per_batch: 10_000,
+            poll_interval: Duration::from_secs(30),
+            max_wait: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+}
+
+impl CsvBatchOptions {
+    /// Take custom IDs from `column`
+    pub fn with_id_column(mut self, column: impl Into<String>) -> Self {
+        self.id_column = Some(column.into());
+        self
+    }
+
+    /// Set the polling interval and overall wait limit
+    pub fn with_polling(mut self, poll_interval: Duration, max_wait: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self.max_wait = max_wait;
+        self
+    }
+
+    /// Set the maximum rows per submitted batch (at least one)
+    pub fn with_rows_per_batch(mut self, rows: usize) -> Self {
+        self.rows_per_batch = rows.max(1);
+        self
+    }
+}
+
+/// What a [`csv_batch`] run did.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CsvBatchSummary {
+    /// IDs of the submitted batches, in submission order
+    pub batch_ids: Vec<String>,
+    /// Data rows read from the input
+    pub rows: usize,
+    /// Rows whose request succeeded
+    pub succeeded: usize,
+    /// Rows that errored, expired, were canceled or got no result
+    pub failed: usize,
+    /// Input tokens across successful rows
+    pub input_tokens: u64,
+    /// Output tokens across successful rows
+    pub output_tokens: u64,
+}
+
+/// One input row, keyed by column header
+type Row = BTreeMap<String, String>;
+
+/// Columns appended after the input columns in the results file
+const RESULT_COLUMNS: [&str; 5] = ["status", "output", "input_tokens", "output_tokens", "error"];
+
+/// Apply `template` to every row of the CSV at `input` through the Message
+/// Batches API and write one result row per input row to `output`.
+///
+/// Columns are available to the template by header name. The input is split
+/// into batches of [`CsvBatchOptions::rows_per_batch`] rows, each submitted
+/// and awaited in turn. The results file has a `custom_id` column, the input
+/// columns (unless disabled), then `status` (`succeeded`, `errored`,
+/// `canceled`, `expired` or `missing`), `output`, `input_tokens`,
+/// `output_tokens` and `error`, in input order.
+///
+/// ```rust,no_run
+/// use threatflux_anthropic_sdk::{
+///     builders::PromptTemplate,
+///     pipelines::{csv_batch, CsvBatchOptions},
+///     Client,
+/// };
+///
+/// # async fn example() -> threatflux_anthropic_sdk::Result<()> {
+/// let client = Client::from_env()?;
+/// let template = PromptTemplate::new("Classify this support ticket: {body}").max_tokens(32);
+/// let summary = csv_batch(
+///     &client,
+///     "tickets.csv",
+///     &template,
+///     "tickets.results.csv",
+///     CsvBatchOptions::default().with_id_column("ticket_id"),
+/// )
+/// .await?;
+/// println!("{} of {} rows succeeded", summary.succeeded, summary.rows);
+/// # Ok(())
+/// # }
+/// ```
+pub async fn csv_batch(
+    client: &Client,
+    input: impl AsRef<Path>,
+    template: &PromptTemplate,
+    output: impl AsRef<Path>,
+    options: CsvBatchOptions,
+) -> Result<CsvBatchSummary> {
+    let input = input.as_ref();
+    let bytes = tokio::fs::read(input).await.map_err(|e| {
+        AnthropicError::file_error(format!("Failed to read {}: {}", input.display(), e))
+    })?;
+    let (headers, rows) = read_rows(&bytes, &options)?;
+    if let Some(column) = &options.id_column {
+        if !headers.contains(column) {
+            return Err(AnthropicError::invalid_input(format!(
+                "ID column '{}' not found in {}",
+                column,
+                input.display()
+            )));
+        }
+    }
+
+    let mut summary = CsvBatchSummary {
+        rows: rows.len(),
+        ..Default::default()
+    };
+    let mut writer = csv::WriterBuilder::new().from_writer(Vec::new());
+    let mut header_row = vec!["custom_id"];
+    if options.keep_input_columns {
+        header_row.extend(headers.iter().map(String::as_str));
+    }
+    header_row.extend(RESULT_COLUMNS);
+    writer.write_record(&header_row).map_err(csv_error)?;
+
+    let batches = client.message_batches();
+    let mut offset = 0;
+    for chunk in rows.chunks(options.rows_per_batch) {
+        let (builder, join) =
+            BatchBuilder::new().add_templated(chunk, template, |index, row| {
+                match &options.id_column {
+                    Some(column) => sanitize_custom_id(&row[column]),
+                    None => format!("row-{}", offset + index),
+                }
+            })?;
+        offset += chunk.len();
+
+        let batch = batches.create(builder.build_validated()?, None).await?;
+        summary.batch_ids.push(batch.id.clone());
+        batches
+            .wait_for_completion(
+                &batch.id,
+                PollOptions::fixed(options.poll_interval).with_timeout(options.max_wait),
+            )
+            .await?;
+        let results = batches.results(&batch.id, None).await?;
+
+        for joined in join.join(results) {
+            let (status, text, input_tokens, output_tokens, error) = match &joined.result {
+                Some(MessageBatchResult::Succeeded { message }) => {
+                    summary.succeeded += 1;
+                    summary.input_tokens += u64::from(message.usage.input_tokens);
+                    summary.output_tokens += u64::from(message.usage.output_tokens);
+                    (
+                        "succeeded",
+                        message.text(),
+                        message.usage.input_tokens.to_string(),
+                        message.usage.output_tokens.to_string(),
+                        String::new(),
+                    )
+                }
+                other => {
+                    summary.failed += 1;
+                    let (status, error) = match other {
+                        Some(MessageBatchResult::Errored { error }) => (
+                            "errored",
+                            format!("{}: {}", error.error_type, error.message),
+                        ),
+                        Some(MessageBatchResult::Canceled {}) => ("canceled", String::new()),
+                        Some(MessageBatchResult::Expired {}) => ("expired", String::new()),
+                        _ => ("missing", "no result returned for this row".to_string()),
+                    };
+                    (status, String::new(), String::new(), String::new(), error)
+                }
+            };
+
+            let mut record = vec![joined.custom_id.as_str()];
+            if options.keep_input_columns {
+                record.extend(headers.iter().map(|h| joined.row[h].as_str()));
+            }
+            record.extend([
+                status,
+                text.as_str(),
+                input_tokens.as_str(),
+                output_tokens.as_str(),
+                error.as_str(),
+            ]);
+            writer.write_record(&record).map_err(csv_error)?;
+        }
+    }
+
+    let data = writer
+        .into_inner()
+        .map_err(|e| AnthropicError::file_error(format!("Failed to write results CSV: {}", e)))?;
+    let output = output.as_ref();
+    tokio::fs::write(output, data).await.map_err(|e| {
+        AnthropicError::file_error(format!("Failed to write {}: {}", output.display(), e))
+    })?;
+    Ok(summary)
+}
+
+/// Read the header and every data row; short rows are padded with empty
+/// cells so every column is present in every row.
+fn read_rows(bytes: &[u8], options: &CsvBatchOptions) -> Result<(Vec<String>, Vec<Row>)> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(options.delimiter)
+        .flexible(true)
+        .from_reader(bytes);
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(csv_error)?
+        .iter()
+        .map(str::to_string)
+        .collect();
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(csv_error)?;
+        let row = headers
+            .iter()
+            .enumerate()
+            .map(|(i, header)| (header.clone(), record.get(i).unwrap_or("").to_string()))
+            .collect();
+        rows.push(row);
+    }
+    Ok((headers, rows))
+}
+
+/// Custom IDs must match `^[a-zA-Z0-9_-]{1,64}$`.
+fn sanitize_custom_id(value: &str) -> String {
+    let id: String = value
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(64)
+        .collect();
+    if id.is_empty() {
+        "_".to_string()
+    } else {
+        id
+    }
+}
+
+fn csv_error(err: csv::Error) -> AnthropicError {
+    AnthropicError::invalid_input(format!("Failed to parse CSV: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use serde_json::json;
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    fn batch_json(status: &str) -> serde_json::Value {
+        json!({
+            "id": "msgbatch_1",
+            "type": "message_batch",
+            "processing_status": status,
+            "request_counts": {"processing": 0, "succeeded": 1, "errored": 1, "canceled": 0, "expired": 0},
+            "created_at": "2026-01-01T00:00:00Z",
+            "expires_at": "2026-01-02T00:00:00Z"
+        })
+    }
+
+    #[test]
+    fn test_sanitize_custom_id() {
+        assert_eq!(sanitize_custom_id("T-100"), "T-100");
+        assert_eq!(sanitize_custom_id("a b/c"), "a_b_c");
+        assert_eq!(sanitize_custom_id(""), "_");
+        assert_eq!(sanitize_custom_id(&"x".repeat(80)).len(), 64);
+    }
+
+    #[tokio::test]
+    async fn test_csv_batch_end_to_end() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/messages/batches"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(batch_json("in_progress")))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/messages/batches/msgbatch_1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(batch_json("ended")))
+            .mount(&server)
+            .await;
+        let results = [
+            json!({"custom_id": "T-1", "result": {"type": "succeeded", "message": {
+                "id": "msg_1", "type": "message", "role": "assistant", "model": "claude-haiku-4-5",
+                "content": [{"type": "text", "text": "billing, \"urgent\""}],
+                "stop_reason": "end_turn", "usage": {"input_tokens": 12, "output_tokens": 3}
+            }}}),
+            json!({"custom_id": "T-2", "result": {"type": "errored", "error": {
+                "type": "overloaded_error", "message": "try later"
+            }}}),
+        ]
+        .iter()
+        .map(|line| line.to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+        Mock::given(method("GET"))
+            .and(path("/v1/messages/batches/msgbatch_1/results"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(results))
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("tickets.csv");
+        let output = dir.path().join("results.csv");
+        std::fs::write(
+            &input,
+            "id,body\nT-1,Card declined twice\nT-2,App crashes\n",
+        )
+        .unwrap();
+
+        let client = Client::new(
+            Config::new("test-key")
+                .unwrap()
+                .with_base_url(server.uri().parse().unwrap()),
+        );
+        let template = PromptTemplate::new("Classify: {body}").max_tokens(16);
+        let summary = csv_batch(
+            &client,
+            &input,
+            &template,
+            &output,
+            CsvBatchOptions::default()
+                .with_id_column("id")
+                .with_polling(Duration::from_millis(10), Duration::from_secs(5)),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(summary.batch_ids, vec!["msgbatch_1"]);
+        assert_eq!((summary.rows, summary.succeeded, summary.failed), (2, 1, 1));
+        assert_eq!((summary.input_tokens, summary.output_tokens), (12, 3));
+
+        let mut reader = csv::Reader::from_path(&output).unwrap();
+        assert_eq!(
+            reader.headers().unwrap().iter().collect::<Vec<_>>(),
+            vec![
+                "custom_id",
+                "id",
+                "body",
+                "status",
+                "output",
+                "input_tokens",
+                "output_tokens",
+                "error"
+            ]
+        );
+        let rows: Vec<csv::StringRecord> = reader.records().map(|r| r.unwrap()).collect();
+        assert_eq!(&rows[0][3], "succeeded");
+        assert_eq!(&rows[0][4], "billing, \"urgent\"");
+        assert_eq!(&rows[0][5], "12");
+        assert_eq!(&rows[1][2], "App crashes");
+        assert_eq!(&rows[1][3], "errored");
+        assert_eq!(&rows[1][7], "overloaded_error: try later");
+
+        let requests = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(body["requests"][1]["custom_id"], "T-2");
+        assert_eq!(
+            body["requests"][1]["params"]["messages"][0]["content"][0]["text"],
+            "Classify: App crashes"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_csv_batch_rejects_unknown_id_column() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in.csv");
+        std::fs::write(&input, "a,b\n1,2\n").unwrap();
+        let client = Client::new(Config::new("test-key").unwrap());
+        let error = csv_batch(
+            &client,
+            &input,
+            &PromptTemplate::new("{a}"),
+            dir.path().join("out.csv"),
+            CsvBatchOptions::default().with_id_column("missing"),
+        )
+        .await
+        .unwrap_err();
+        assert!(error.to_string().contains("missing"));
+    }
+}
@@ -0,0 +1,21 @@
+//! End-to-end pipelines built from the lower-level APIs
+//!
+//! Each pipeline wires together request building, submission, polling and
+//! result handling for one common workflow, so scripts do not have to.
+
+mod batch_archive;
+#[cfg(feature = "csv")]
+mod csv_batch;
+pub mod runner;
+
+pub use batch_archive::{
+    archive_batches, ArchiveManifest, ArchiveOptions, ArchiveReport, ArchiveSink, ArchivedBatch,
+    DirectorySink, ObjectStoreSink,
+};
+
+#[cfg(feature = "csv")]
+pub use csv_batch::{csv_batch, CsvBatchOptions, CsvBatchSummary};
+pub use runner::{
+    run_pipeline, FailurePolicy, PipelineRun, PipelineSpec, StepKind, StepReport, StepSpec,
+    StepStatus,
+};
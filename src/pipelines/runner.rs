@@ -0,0 +1,433 @@
+//! Config-driven multi-step workflows
+//!
+//! A [`PipelineSpec`] is a DAG of prompt steps. Each step renders a
+//! [`PromptTemplate`] from the pipeline's inputs and the outputs of earlier
+//! steps, sends it with its own model and options, and publishes its output
+//! under its ID. A `generate` step outputs the response text; an `extract`
+//! step outputs the JSON the model produced, constrained to `schema` when one
+//! is given. Placeholders reach into JSON with dots, so `{facts.title}` is
+//! the `title` field of the `facts` step's output.
+//!
+//! ```yaml
+//! model: claude-haiku-4-5
+//! options:
+//!   max_tokens: 512
+//! steps:
+//!   - id: draft
+//!     template: "Write a short product announcement for {product}."
+//!   - id: facts
+//!     kind: extract
+//!     template: "List the product name and launch date in: {draft}"
+//!     schema: { type: object, properties: { name: { type: string }, date: { type: string } } }
+//!     on_failure: { retry: 2 }
+//!   - id: tweet
+//!     model: claude-sonnet-4-6
+//!     template: "Write a tweet announcing {facts.name} on {facts.date}."
+//!     on_failure: skip
+//! ```
+//!
+//! Dependencies are the steps a template refers to plus any listed in
+//! `depends_on`. Steps whose dependencies are done run concurrently.
+
+use crate::{
+    builders::{
+        spec::{read_spec, SpecFormat, SpecOptions},
+        PromptTemplate, TemplateVars,
+    },
+    client::Client,
+    error::{AnthropicError, Result},
+    models::{common::Usage, message::MessageRequest, structured::StructuredOutput},
+    tools::registry::add_usage,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+/// What a step does with its response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepKind {
+    /// Output the response text
+    #[default]
+    Generate,
+    /// Output the JSON in the response
+    Extract,
+}
+
+/// What to do when a step fails
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailurePolicy {
+    /// Stop the pipeline with the step's error
+    #[default]
+    Fail,
+    /// Leave the step without output and skip the steps depending on it
+    Skip,
+    /// Try this many more times, then fail
+    Retry(u32),
+    /// Use this value as the step's output
+    Fallback(Value),
+}
+
+/// One step of a [`PipelineSpec`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StepSpec {
+    /// Name the step's output is published under
+    pub id: String,
+    /// What the step does with its response
+    #[serde(default)]
+    pub kind: StepKind,
+    /// Steps to wait for besides those the templates refer to
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// Model, the pipeline's when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// System prompt, may use placeholders
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    /// User prompt with placeholders
+    pub template: String,
+    /// Options set here override the pipeline's
+    #[serde(default)]
+    pub options: SpecOptions,
+    /// JSON Schema the output of an `extract` step must follow
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<Value>,
+    /// What to do when the step fails
+    #[serde(default)]
+    pub on_failure: FailurePolicy,
+}
+
+impl StepSpec {
+    fn prompt_template(&self, pipeline: &PipelineSpec) -> PromptTemplate {
+        let mut base = MessageRequest::new();
+        if let Some(model) = self.model.as_ref().or(pipeline.model.as_ref()) {
+            base = base.model(model);
+        }
+        base = self.options.apply(pipeline.options.apply(base));
+        let template = PromptTemplate::new(&self.template).with_base_request(base);
+        match &self.system {
+            Some(system) => template.system(system),
+            None => template,
+        }
+    }
+
+    /// IDs of the steps this one waits for
+    fn dependencies(&self, pipeline: &PipelineSpec) -> Vec<String> {
+        let ids: HashSet<&str> = pipeline.steps.iter().map(|s| s.id.as_str()).collect();
+        let mut dependencies = self.depends_on.clone();
+        for name in self.prompt_template(pipeline).variables() {
+            let head = name.split('.').next().unwrap_or_default();
+            if ids.contains(head) && !dependencies.iter().any(|d| d == head) {
+                dependencies.push(head.to_string());
+            }
+        }
+        dependencies
+    }
+}
+
+/// A DAG of prompt steps described as data
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PipelineSpec {
+    /// Default model of the steps
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Default options of the steps
+    #[serde(default)]
+    pub options: SpecOptions,
+    /// Inputs available to every step, under the ones passed to
+    /// [`run_pipeline`]
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub variables: Map<String, Value>,
+    /// The steps
+    pub steps: Vec<StepSpec>,
+}
+
+impl PipelineSpec {
+    /// Parse a spec from `text`
+    pub fn from_str(text: &str, format: SpecFormat) -> Result<Self> {
+        let spec: Self = format.parse(text)?;
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    /// Load a spec file, picking the format from its extension
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let spec: Self = read_spec(path.as_ref())?;
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    /// Check that step IDs are unique, dependencies exist and there are no
+    /// cycles
+    pub fn validate(&self) -> Result<()> {
+        self.order().map(|_| ())
+    }
+
+    /// The steps in waves: each wave only depends on earlier ones
+    fn order(&self) -> Result<Vec<Vec<usize>>> {
+        let mut index = HashMap::new();
+        for (i, step) in self.steps.iter().enumerate() {
+            if index.insert(step.id.as_str(), i).is_some() {
+                return Err(AnthropicError::invalid_input(format!(
+                    "Duplicate pipeline step id: {}",
+                    step.id
+                )));
+            }
+        }
+        let mut pending: Vec<(usize, Vec<usize>)> = Vec::new();
+        for (i, step) in self.steps.iter().enumerate() {
+            let mut dependencies = Vec::new();
+            for id in step.dependencies(self) {
+                let dependency = index.get(id.as_str()).ok_or_else(|| {
+                    AnthropicError::invalid_input(format!(
+                        "Pipeline step {} depends on unknown step {}",
+                        step.id, id
+                    ))
+                })?;
+                dependencies.push(*dependency);
+            }
+            pending.push((i, dependencies));
+        }
+
+        let mut done = vec![false; self.steps.len()];
+        let mut waves = Vec::new();
+        while !pending.is_empty() {
+            let (ready, rest): (Vec<_>, Vec<_>) = pending
+                .into_iter()
+                .partition(|(_, dependencies)| dependencies.iter().all(|d| done[*d]));
+            if ready.is_empty() {
+                let ids: Vec<&str> = rest
+                    .iter()
+                    .map(|(i, _)| self.steps[*i].id.as_str())
+                    .collect();
+                return Err(AnthropicError::invalid_input(format!(
+                    "Pipeline steps form a cycle: {}",
+                    ids.join(", ")
+                )));
+            }
+            let wave: Vec<usize> = ready.into_iter().map(|(i, _)| i).collect();
+            for i in &wave {
+                done[*i] = true;
+            }
+            waves.push(wave);
+            pending = rest;
+        }
+        Ok(waves)
+    }
+}
+
+/// How a step ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepStatus {
+    /// The step produced its output
+    Succeeded,
+    /// The step failed and its fallback value was used
+    FellBack,
+    /// The step failed or a dependency was skipped; it has no output
+    Skipped,
+}
+
+/// What happened to one step
+#[derive(Debug, Clone)]
+pub struct StepReport {
+    /// The step's ID
+    pub id: String,
+    /// How it ended
+    pub status: StepStatus,
+    /// Requests sent for it
+    pub attempts: u32,
+    /// Tokens used across its attempts
+    pub usage: Usage,
+    /// The last error, when it did not succeed
+    pub error: Option<String>,
+}
+
+/// Result of [`run_pipeline`]
+#[derive(Debug, Clone)]
+pub struct PipelineRun {
+    /// Output of every step that has one, by step ID
+    pub outputs: Map<String, Value>,
+    /// One report per step, in the order the steps ran
+    pub steps: Vec<StepReport>,
+    /// Tokens used by all steps
+    pub usage: Usage,
+}
+
+impl PipelineRun {
+    /// Output of step `id`
+    pub fn output(&self, id: &str) -> Option<&Value> {
+        self.outputs.get(id)
+    }
+
+    /// Text output of step `id`
+    pub fn text(&self, id: &str) -> Option<&str> {
+        self.output(id).and_then(Value::as_str)
+    }
+}
+
+/// Template variables of a running pipeline: step outputs over inputs, with
+/// dotted paths into JSON values
+struct Scope<'a> {
+    inputs: &'a Map<String, Value>,
+    outputs: &'a Map<String, Value>,
+}
+
+impl TemplateVars for Scope<'_> {
+    fn var(&self, name: &str) -> Option<String> {
+        let mut path = name.split('.');
+        let head = path.next()?;
+        let mut value = self.outputs.get(head).or_else(|| self.inputs.get(head))?;
+        for segment in path {
+            value = match value {
+                Value::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+                other => other.get(segment)?,
+            };
+        }
+        Some(match value {
+            Value::String(text) => text.clone(),
+            other => other.to_string(),
+        })
+    }
+}
+
+/// Run `spec` with `inputs` (over the spec's own variables).
+///
+/// Fails with the first error of a step whose policy is
+/// [`FailurePolicy::Fail`] (or [`FailurePolicy::Retry`] once out of
+/// attempts); steps running alongside it still finish.
+pub async fn run_pipeline(
+    client: &Client,
+    spec: &PipelineSpec,
+    inputs: Map<String, Value>,
+) -> Result<PipelineRun> {
+    let waves = spec.order()?;
+    let mut variables = spec.variables.clone();
+    variables.extend(inputs);
+
+    let mut run = PipelineRun {
+        outputs: Map::new(),
+        steps: Vec::new(),
+        usage: Usage::default(),
+    };
+    let mut skipped = HashSet::new();
+    for wave in waves {
+        let scope = Scope {
+            inputs: &variables,
+            outputs: &run.outputs,
+        };
+        let mut runnable = Vec::new();
+        for i in wave {
+            let step = &spec.steps[i];
+            if step.dependencies(spec).iter().any(|d| skipped.contains(d)) {
+                skipped.insert(step.id.clone());
+                run.steps.push(StepReport {
+                    id: step.id.clone(),
+                    status: StepStatus::Skipped,
+                    attempts: 0,
+                    usage: Usage::default(),
+                    error: Some("A dependency was skipped".to_string()),
+                });
+            } else {
+                runnable.push(run_step(client, spec, step, &scope));
+            }
+        }
+
+        let mut failure = None;
+        for (report, output) in futures::future::join_all(runnable).await {
+            add_usage(&mut run.usage, &report.usage);
+            match output {
+                Ok(Some(output)) => {
+                    run.outputs.insert(report.id.clone(), output);
+                }
+                Ok(None) => {
+                    skipped.insert(report.id.clone());
+                }
+                Err(err) => {
+                    failure.get_or_insert(err.with_context(format!("Step {}", report.id)));
+                }
+            }
+            run.steps.push(report);
+        }
+        if let Some(err) = failure {
+            return Err(err);
+        }
+    }
+    Ok(run)
+}
+
+/// Run one step under its failure policy. The output is `None` when the
+/// step was skipped.
+async fn run_step(
+    client: &Client,
+    spec: &PipelineSpec,
+    step: &StepSpec,
+    scope: &Scope<'_>,
+) -> (StepReport, Result<Option<Value>>) {
+    let mut report = StepReport {
+        id: step.id.clone(),
+        status: StepStatus::Succeeded,
+        attempts: 0,
+        usage: Usage::default(),
+        error: None,
+    };
+    let retries = match step.on_failure {
+        FailurePolicy::Retry(retries) => retries,
+        _ => 0,
+    };
+
+    let request = match step.prompt_template(spec).render(scope) {
+        Ok(request) => request,
+        Err(err) => return settle(report, step, err),
+    };
+    let structured = StructuredOutput::new();
+    let request = match (&step.kind, &step.schema) {
+        (StepKind::Extract, Some(schema)) => structured.apply(request, schema.clone()),
+        _ => request,
+    };
+    loop {
+        report.attempts += 1;
+        let result = client
+            .messages()
+            .create(request.clone(), None)
+            .await
+            .and_then(|response| {
+                add_usage(&mut report.usage, &response.usage);
+                match step.kind {
+                    StepKind::Generate => Ok(Value::String(response.text())),
+                    StepKind::Extract => structured.extract(&response),
+                }
+            });
+        match result {
+            Ok(output) => return (report, Ok(Some(output))),
+            Err(err) if report.attempts > retries => return settle(report, step, err),
+            Err(err) => tracing::debug!("Pipeline step {} failed ({}); retrying", step.id, err),
+        }
+    }
+}
+
+/// Apply `step`'s failure policy to `err`
+fn settle(
+    mut report: StepReport,
+    step: &StepSpec,
+    err: AnthropicError,
+) -> (StepReport, Result<Option<Value>>) {
+    report.error = Some(err.to_string());
+    match &step.on_failure {
+        FailurePolicy::Skip => {
+            report.status = StepStatus::Skipped;
+            (report, Ok(None))
+        }
+        FailurePolicy::Fallback(value) => {
+            report.status = StepStatus::FellBack;
+            (report, Ok(Some(value.clone())))
+        }
+        FailurePolicy::Fail | FailurePolicy::Retry(_) => (report, Err(err)),
+    }
+}
@@ -0,0 +1,321 @@
+//! Ambient request priority and deadline
+//!
+//! A web framework usually knows once, at the edge, that a whole incoming
+//! request is urgent or has to answer within a few seconds. Rather than
+//! threading [`RequestOptions`] through every call, set a [`PriorityContext`]
+//! around the handler, either as a task-local with [`PriorityContext::scope`]
+//! or as fields on a tracing span when [`PriorityLayer`] is installed:
+//!
+//! ```rust,no_run
+//! use tracing_subscriber::prelude::*;
+//! use threatflux_anthropic_sdk::priority::PriorityLayer;
+//!
+//! tracing_subscriber::registry()
+//!     .with(PriorityLayer::new())
+//!     .with(tracing_subscriber::fmt::layer())
+//!     .init();
+//!
+//! let span = tracing::info_span!(
+//!     "checkout",
+//!     anthropic.priority = "high",
+//!     anthropic.deadline_ms = 5_000u64,
+//! );
+//! ```
+//!
+//! Every request made inside picks the context up, and explicit
+//! [`RequestOptions::with_priority`] / [`RequestOptions::with_deadline`]
+//! still win. The task-local wins over span fields, and inner spans over
+//! outer ones.
+//!
+//! The effects are:
+//!
+//! * the deadline caps each request's timeout and bounds its retries, and a
+//!   request whose deadline has already passed fails with
+//!   [`AnthropicError::Timeout`] unsent;
+//! * with [`Config::with_priority_service_tiers`] enabled, a message request
+//!   without an explicit `service_tier` gets `"auto"` (may use Priority Tier
+//!   capacity) when high priority and `"standard_only"` when low.
+//!
+//! [`Config::with_priority_service_tiers`]: crate::config::Config::with_priority_service_tiers
+
+use crate::{
+    error::{AnthropicError, Result},
+    types::{RequestOptions, RequestPriority},
+};
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+    Subscriber,
+};
+use tracing_subscriber::{
+    layer::Context,
+    registry::{LookupSpan, Registry},
+    Layer,
+};
+
+/// Span field holding a [`RequestPriority`] name: `low`, `normal` or `high`
+pub const PRIORITY_FIELD: &str = "anthropic.priority";
+/// Span field holding a deadline in milliseconds from the span's creation
+pub const DEADLINE_FIELD: &str = "anthropic.deadline_ms";
+
+tokio::task_local! {
+    static CURRENT: PriorityContext;
+}
+
+/// Priority and deadline shared by every request made within it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PriorityContext {
+    /// How urgent the requests are
+    pub priority: Option<RequestPriority>,
+    /// When the requests must be done by
+    pub deadline: Option<Instant>,
+}
+
+impl PriorityContext {
+    /// Empty context
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the priority
+    pub fn with_priority(mut self, priority: RequestPriority) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Set the deadline
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Set the deadline `budget` from now
+    pub fn with_time_budget(self, budget: Duration) -> Self {
+        self.with_deadline(Instant::now() + budget)
+    }
+
+    /// Run `future` with this context as the ambient one
+    pub fn scope<F: Future>(self, future: F) -> impl Future<Output = F::Output> {
+        CURRENT.scope(self, future)
+    }
+
+    /// The ambient context: the enclosing [`scope`](Self::scope), filled in
+    /// from the current span's fields when [`PriorityLayer`] is installed
+    pub fn current() -> Self {
+        let local = CURRENT.try_with(|context| *context).unwrap_or_default();
+        local.or(from_current_span())
+    }
+
+    /// Time left before the deadline, `None` without one
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// The context for a request: its options' fields, else the ambient ones
+    pub(crate) fn resolve(options: &Option<RequestOptions>) -> Self {
+        let explicit = options
+            .as_ref()
+            .map(|options| Self {
+                priority: options.priority,
+                deadline: options.deadline,
+            })
+            .unwrap_or_default();
+        explicit.or(Self::current())
+    }
+
+    /// `timeout` shortened to the time left before the deadline
+    pub(crate) fn timeout(&self, timeout: Duration) -> Result<Duration> {
+        match self.remaining() {
+            Some(remaining) if remaining.is_zero() => {
+                Err(AnthropicError::Timeout(Duration::ZERO)
+                    .with_context("Request deadline has passed"))
+            }
+            Some(remaining) => Ok(timeout.min(remaining)),
+            None => Ok(timeout),
+        }
+    }
+
+    /// The `service_tier` this priority asks for
+    pub(crate) fn service_tier(&self) -> Option<&'static str> {
+        match self.priority? {
+            RequestPriority::High => Some("auto"),
+            RequestPriority::Low => Some("standard_only"),
+            RequestPriority::Normal => None,
+        }
+    }
+
+    fn or(self, fallback: Self) -> Self {
+        Self {
+            priority: self.priority.or(fallback.priority),
+            deadline: self.deadline.or(fallback.deadline),
+        }
+    }
+}
+
+/// Tracing layer recording [`PRIORITY_FIELD`] and [`DEADLINE_FIELD`] on
+/// spans so [`PriorityContext::current`] can find them.
+///
+/// It must sit on a [`Registry`], as in the module example.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PriorityLayer {
+    _private: (),
+}
+
+impl PriorityLayer {
+    /// New layer
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// What a span's fields set, stored in its extensions
+#[derive(Debug, Clone, Copy)]
+struct SpanPriority {
+    opened: Instant,
+    context: PriorityContext,
+}
+
+impl Visit for SpanPriority {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == PRIORITY_FIELD {
+            self.context.priority = parse_priority(value);
+        }
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        if field.name() == DEADLINE_FIELD {
+            self.context.deadline = Some(self.opened + Duration::from_millis(value));
+        }
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.record_u64(field, value.max(0) as u64);
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == PRIORITY_FIELD {
+            self.record_str(field, format!("{:?}", value).trim_matches('"'));
+        }
+    }
+}
+
+fn parse_priority(value: &str) -> Option<RequestPriority> {
+    match value.to_ascii_lowercase().as_str() {
+        "low" => Some(RequestPriority::Low),
+        "normal" => Some(RequestPriority::Normal),
+        "high" => Some(RequestPriority::High),
+        _ => None,
+    }
+}
+
+impl<S> Layer<S> for PriorityLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let fields = attrs.metadata().fields();
+        if fields.field(PRIORITY_FIELD).is_none() && fields.field(DEADLINE_FIELD).is_none() {
+            return;
+        }
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut recorded = SpanPriority {
+            opened: Instant::now(),
+            context: PriorityContext::default(),
+        };
+        attrs.record(&mut recorded);
+        span.extensions_mut().insert(recorded);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        // Spans declaring either field, even as `Empty`, got an entry when
+        // they were created
+        let mut extensions = span.extensions_mut();
+        if let Some(recorded) = extensions.get_mut::<SpanPriority>() {
+            values.record(recorded);
+        }
+    }
+}
+
+/// Fields set on the current span and its ancestors, innermost first
+fn from_current_span() -> PriorityContext {
+    tracing::Span::current()
+        .with_subscriber(|(id, dispatch)| {
+            let registry = dispatch.downcast_ref::<Registry>()?;
+            let span = registry.span(id)?;
+            Some(
+                span.scope()
+                    .fold(PriorityContext::default(), |found, span| {
+                        match span.extensions().get::<SpanPriority>() {
+                            Some(recorded) => found.or(recorded.context),
+                            None => found,
+                        }
+                    }),
+            )
+        })
+        .flatten()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::prelude::*;
+
+    #[test]
+    fn test_span_fields_are_inherited() {
+        let subscriber = tracing_subscriber::registry().with(PriorityLayer::new());
+        tracing::subscriber::with_default(subscriber, || {
+            assert_eq!(PriorityContext::current(), PriorityContext::default());
+
+            let outer = tracing::info_span!(
+                "request",
+                anthropic.priority = "high",
+                anthropic.deadline_ms = 60_000u64
+            );
+            let _outer = outer.enter();
+            let inner = tracing::info_span!("handler", anthropic.priority = "low");
+            let _inner = inner.enter();
+
+            let context = PriorityContext::current();
+            assert_eq!(context.priority, Some(RequestPriority::Low));
+            let remaining = context.remaining().unwrap();
+            assert!(remaining > Duration::from_secs(59) && remaining <= Duration::from_secs(60));
+            assert_eq!(context.service_tier(), Some("standard_only"));
+        });
+    }
+
+    #[tokio::test]
+    async fn test_options_override_task_local() {
+        PriorityContext::new()
+            .with_priority(RequestPriority::High)
+            .with_time_budget(Duration::from_secs(2))
+            .scope(async {
+                let ambient = PriorityContext::resolve(&None);
+                assert_eq!(ambient.priority, Some(RequestPriority::High));
+                assert!(
+                    ambient.timeout(Duration::from_secs(60)).unwrap() <= Duration::from_secs(2)
+                );
+
+                let options = RequestOptions::new()
+                    .with_priority(RequestPriority::Normal)
+                    .with_deadline(Instant::now());
+                let resolved = PriorityContext::resolve(&Some(options));
+                assert_eq!(resolved.service_tier(), None);
+                assert!(matches!(
+                    resolved.timeout(Duration::from_secs(60)),
+                    Err(AnthropicError::Timeout(_))
+                ));
+            })
+            .await;
+    }
+}
@@ -0,0 +1,471 @@
+//! Managed prompt-cache prefixes
+//!
+//! Marking a block with `cache_control` only asks the API to cache the
+//! prompt up to that point; keeping the entry alive is left to the caller.
+//! A [`CachedPrefix`] owns a large static prefix (system instructions and
+//! reference documents), puts it in front of every request sent through it
+//! with a single cache breakpoint at its end, and tracks when the cache entry
+//! will expire from the usage each response reports. [`CachedPrefix::refresh`]
+//! re-writes the entry with a one-token request, and
+//! [`CachedPrefix::spawn_keepalive`] does so in the background shortly before
+//! every expiry.
+//!
+//! ```rust,no_run
+//! use threatflux_anthropic_sdk::{
+//!     models::MessageRequest,
+//!     prompt_cache::{CacheTtl, CachedPrefix},
+//!     Client,
+//! };
+//!
+//! # async fn example() -> threatflux_anthropic_sdk::Result<()> {
+//! let client = Client::from_env()?;
+//! let handbook = std::fs::read_to_string("handbook.md")?;
+//!
+//! let prefix = CachedPrefix::new("claude-sonnet-4-6")
+//!     .with_text("Answer questions using only the employee handbook below.")
+//!     .with_document("Employee handbook", handbook)
+//!     .with_ttl(CacheTtl::OneHour);
+//! prefix.refresh(&client).await?;
+//! let keepalive = prefix.spawn_keepalive(client.clone());
+//!
+//! let request = MessageRequest::new()
+//!     .max_tokens(512)
+//!     .add_user_message("How many vacation days do I get?");
+//! let response = prefix.create(&client, request, None).await?;
+//! println!("{:?}", prefix.stats());
+//! # keepalive.abort();
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    client::Client,
+    error::Result,
+    models::{
+        common::{CacheControl, Usage},
+        message::{MessageRequest, MessageResponse, SystemBlock, SystemPrompt},
+    },
+    types::RequestOptions,
+    warnings::Warning,
+};
+use std::{
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+use tokio::task::JoinHandle;
+
+/// How long before expiry a cached prefix is refreshed by default
+pub const DEFAULT_REFRESH_MARGIN: Duration = Duration::from_secs(30);
+
+/// Lifetime of a prompt-cache entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CacheTtl {
+    /// Five minutes, the API default
+    #[default]
+    FiveMinutes,
+    /// One hour; writes cost more than with the 5-minute TTL
+    OneHour,
+}
+
+impl CacheTtl {
+    /// Length of the TTL
+    pub fn duration(self) -> Duration {
+        match self {
+            Self::FiveMinutes => Duration::from_secs(5 * 60),
+            Self::OneHour => Duration::from_secs(60 * 60),
+        }
+    }
+
+    /// `cache_control` value requesting this TTL
+    pub fn cache_control(self) -> CacheControl {
+        match self {
+            Self::FiveMinutes => CacheControl::ephemeral(),
+            Self::OneHour => CacheControl::ephemeral_1h(),
+        }
+    }
+}
+
+impl From<CacheTtl> for CacheControl {
+    fn from(ttl: CacheTtl) -> Self {
+        ttl.cache_control()
+    }
+}
+
+/// Cache activity recorded by a [`CachedPrefix`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PrefixCacheStats {
+    /// Responses that wrote the prefix into the cache
+    pub writes: u32,
+    /// Responses that read the prefix from the cache
+    pub reads: u32,
+    /// Refresh requests sent by [`CachedPrefix::refresh`]
+    pub refreshes: u32,
+    /// Tokens written into the cache, summed over all writes
+    pub cache_creation_input_tokens: u64,
+    /// Tokens read from the cache, summed over all reads
+    pub cache_read_input_tokens: u64,
+    /// When the cache entry is expected to expire, if it is known to exist
+    pub expires_at: Option<Instant>,
+}
+
+/// A static prompt prefix kept warm in the prompt cache.
+///
+/// Clones share their cache state, so one prefix can be used from many
+/// tasks.
+#[derive(Debug, Clone)]
+pub struct CachedPrefix {
+    model: String,
+    blocks: Vec<SystemBlock>,
+    ttl: CacheTtl,
+    refresh_margin: Duration,
+    stats: Arc<Mutex<PrefixCacheStats>>,
+}
+
+impl CachedPrefix {
+    /// Start an empty prefix for `model`; cache entries are per model
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            model: model.into(),
+            blocks: Vec::new(),
+            ttl: CacheTtl::default(),
+            refresh_margin: DEFAULT_REFRESH_MARGIN,
+            stats: Arc::new(Mutex::new(PrefixCacheStats::default())),
+        }
+    }
+
+    /// Append a block of system text
+    pub fn with_text(mut self, text: impl Into<String>) -> Self {
+        self.blocks.push(SystemBlock::text(text));
+        self
+    }
+
+    /// Append a reference document, wrapped in a `<document>` tag
+    pub fn with_document(mut self, title: impl AsRef<str>, text: impl AsRef<str>) -> Self {
+        self.blocks.push(SystemBlock::text(format!(
+            "<document title=\"{}\">\n{}\n</document>",
+            title.as_ref().replace('"', "&quot;"),
+            text.as_ref()
+        )));
+        self
+    }
+
+    /// Set the cache TTL
+    pub fn with_ttl(mut self, ttl: CacheTtl) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Set how long before expiry the entry counts as due for a refresh
+    pub fn with_refresh_margin(mut self, margin: Duration) -> Self {
+        self.refresh_margin = margin;
+        self
+    }
+
+    /// Model the prefix is cached for
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Cache TTL
+    pub fn ttl(&self) -> CacheTtl {
+        self.ttl
+    }
+
+    /// Prefix blocks as sent, with the cache breakpoint on the last one
+    pub fn system_blocks(&self) -> Vec<SystemBlock> {
+        let mut blocks = self.blocks.clone();
+        if let Some(last) = blocks.last_mut() {
+            last.cache_control = Some(self.ttl.cache_control());
+        }
+        blocks
+    }
+
+    /// Put the prefix in front of `request`'s system prompt and switch it to
+    /// the prefix's model.
+    ///
+    /// The request's own system prompt follows the prefix uncached, so it can
+    /// vary per request without invalidating the cache.
+    pub fn apply(&self, mut request: MessageRequest) -> MessageRequest {
+        let mut system = self.system_blocks();
+        match request.system.take() {
+            Some(SystemPrompt::Text(text)) if !text.is_empty() => {
+                system.push(SystemBlock::text(text))
+            }
+            Some(SystemPrompt::Blocks(blocks)) => system.extend(blocks),
+            _ => {}
+        }
+        request.model = self.model.clone();
+        request.system = Some(SystemPrompt::Blocks(system));
+        request
+    }
+
+    /// Send `request` with the prefix applied and record its cache usage
+    pub async fn create(
+        &self,
+        client: &Client,
+        request: MessageRequest,
+        options: Option<RequestOptions>,
+    ) -> Result<MessageResponse> {
+        self.warn_if_expired(client);
+        let response = client
+            .messages()
+            .create(self.apply(request), options)
+            .await?;
+        self.record_usage(&response.usage);
+        Ok(response)
+    }
+
+    /// Record the cache usage of a response to a request built with
+    /// [`apply`](Self::apply).
+    ///
+    /// Both writes and reads restart the entry's TTL.
+    pub fn record_usage(&self, usage: &Usage) {
+        self.record_usage_at(usage, Instant::now());
+    }
+
+    fn record_usage_at(&self, usage: &Usage, now: Instant) {
+        let mut stats = self.lock_stats();
+        if usage.cache_creation_input_tokens > 0 {
+            stats.writes += 1;
+            stats.cache_creation_input_tokens += u64::from(usage.cache_creation_input_tokens);
+        }
+        if usage.cache_read_input_tokens > 0 {
+            stats.reads += 1;
+            stats.cache_read_input_tokens += u64::from(usage.cache_read_input_tokens);
+        }
+        if usage.cache_creation_input_tokens > 0 || usage.cache_read_input_tokens > 0 {
+            stats.expires_at = Some(now + self.ttl.duration());
+        }
+    }
+
+    /// Snapshot of the recorded cache activity
+    pub fn stats(&self) -> PrefixCacheStats {
+        *self.lock_stats()
+    }
+
+    /// Whether the entry is believed to be cached right now
+    pub fn is_warm(&self) -> bool {
+        self.stats()
+            .expires_at
+            .is_some_and(|expires_at| Instant::now() < expires_at)
+    }
+
+    /// Whether the entry is missing or expires within the refresh margin
+    pub fn needs_refresh(&self) -> bool {
+        self.refresh_due_in() == Duration::ZERO
+    }
+
+    /// Time until the entry is due for a refresh (zero if due now)
+    pub fn refresh_due_in(&self) -> Duration {
+        self.stats()
+            .expires_at
+            .map_or(Duration::ZERO, |expires_at| {
+                expires_at
+                    .saturating_duration_since(Instant::now())
+                    .saturating_sub(self.refresh_margin)
+            })
+    }
+
+    /// Write (or re-read) the prefix into the cache with a one-token request
+    pub async fn refresh(&self, client: &Client) -> Result<Usage> {
+        let request = MessageRequest::new().max_tokens(1).add_user_message(".");
+        let response = client.messages().create(self.apply(request), None).await?;
+        self.lock_stats().refreshes += 1;
+        self.record_usage(&response.usage);
+        Ok(response.usage)
+    }
+
+    /// [`refresh`](Self::refresh) if the entry is due; returns whether a
+    /// request was sent
+    pub async fn refresh_if_needed(&self, client: &Client) -> Result<bool> {
+        if !self.needs_refresh() {
+            return Ok(false);
+        }
+        self.refresh(client).await?;
+        Ok(true)
+    }
+
+    /// Keep the entry warm in the background until the handle is aborted.
+    ///
+    /// Each refresh happens when the entry comes within the refresh margin
+    /// of expiring. A failed refresh is reported as a
+    /// [`Warning::BackgroundRefreshFailed`] and retried after the margin.
+    pub fn spawn_keepalive(&self, client: Client) -> JoinHandle<()> {
+        let prefix = self.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(prefix.refresh_due_in()).await;
+                if let Err(err) = prefix.refresh_if_needed(&client).await {
+                    client
+                        .config()
+                        .warnings
+                        .emit(Warning::BackgroundRefreshFailed {
+                            task: "prompt_cache".to_string(),
+                            error: err.to_string(),
+                        });
+                    tokio::time::sleep(prefix.refresh_margin.max(Duration::from_secs(1))).await;
+                }
+            }
+        })
+    }
+
+    /// Report an entry that expired unrefreshed; the next request writes it
+    /// again at full price
+    fn warn_if_expired(&self, client: &Client) {
+        let now = Instant::now();
+        let expired = {
+            let mut stats = self.lock_stats();
+            match stats.expires_at {
+                Some(expires_at) if expires_at <= now => {
+                    stats.expires_at = None;
+                    Some(now - expires_at)
+                }
+                _ => None,
+            }
+        };
+        if let Some(expired_for) = expired {
+            client.config().warnings.emit(Warning::CacheExpired {
+                model: self.model.clone(),
+                expired_for,
+            });
+        }
+    }
+
+    fn lock_stats(&self) -> MutexGuard<'_, PrefixCacheStats> {
+        self.stats
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use serde_json::json;
+    use wiremock::{
+        matchers::{body_partial_json, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    fn usage(created: u32, read: u32) -> Usage {
+        Usage {
+            cache_creation_input_tokens: created,
+            cache_read_input_tokens: read,
+            ..Usage::new(10, 1)
+        }
+    }
+
+    #[test]
+    fn test_apply_puts_single_breakpoint_after_prefix() {
+        let prefix = CachedPrefix::new("claude-haiku-4-5")
+            .with_text("Be brief.")
+            .with_document("Manual \"v2\"", "Press the red button.")
+            .with_ttl(CacheTtl::OneHour);
+        let request = MessageRequest::new()
+            .model("other-model")
+            .system("Today is Monday.")
+            .add_user_message("What do I press?");
+
+        let value = serde_json::to_value(prefix.apply(request)).unwrap();
+        assert_eq!(value["model"], "claude-haiku-4-5");
+        let system = value["system"].as_array().unwrap();
+        assert_eq!(system.len(), 3);
+        assert!(system[0].get("cache_control").is_none());
+        assert_eq!(
+            system[1]["text"],
+            "<document title=\"Manual &quot;v2&quot;\">\nPress the red button.\n</document>"
+        );
+        assert_eq!(
+            system[1]["cache_control"],
+            json!({"type": "ephemeral", "ttl": "1h"})
+        );
+        assert_eq!(system[2]["text"], "Today is Monday.");
+        assert!(system[2].get("cache_control").is_none());
+    }
+
+    #[test]
+    fn test_usage_tracks_expiry_and_refresh_window() {
+        let prefix = CachedPrefix::new("claude-haiku-4-5").with_text("prefix");
+        assert!(!prefix.is_warm());
+        assert!(prefix.needs_refresh());
+
+        // A response that neither wrote nor read the cache says nothing.
+        prefix.record_usage(&usage(0, 0));
+        assert!(prefix.needs_refresh());
+
+        let now = Instant::now();
+        prefix.record_usage_at(&usage(2048, 0), now);
+        prefix.record_usage_at(&usage(0, 2048), now);
+        let stats = prefix.stats();
+        assert_eq!((stats.writes, stats.reads), (1, 1));
+        assert_eq!(stats.cache_read_input_tokens, 2048);
+        assert_eq!(stats.expires_at, Some(now + Duration::from_secs(300)));
+        assert!(prefix.is_warm());
+        assert!(!prefix.needs_refresh());
+        assert!(prefix.refresh_due_in() <= Duration::from_secs(270));
+
+        let eager = prefix.clone().with_refresh_margin(Duration::from_secs(600));
+        assert!(eager.needs_refresh());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_then_create_reuses_prefix() {
+        let mock_server = MockServer::start().await;
+        let response = |created: u32, read: u32| {
+            ResponseTemplate::new(200).set_body_json(json!({
+                "id": "msg_1",
+                "type": "message",
+                "role": "assistant",
+                "model": "claude-haiku-4-5",
+                "content": [{"type": "text", "text": "ok"}],
+                "stop_reason": "end_turn",
+                "usage": {
+                    "input_tokens": 3,
+                    "output_tokens": 1,
+                    "cache_creation_input_tokens": created,
+                    "cache_read_input_tokens": read
+                }
+            }))
+        };
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .and(body_partial_json(json!({"max_tokens": 1})))
+            .respond_with(response(4096, 0))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .and(body_partial_json(json!({
+                "max_tokens": 64,
+                "system": [{"text": "big prefix", "cache_control": {"type": "ephemeral"}}]
+            })))
+            .respond_with(response(0, 4096))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = Client::new(
+            Config::new("sk-ant-test-key")
+                .unwrap()
+                .with_base_url(mock_server.uri().parse().unwrap()),
+        );
+        let prefix = CachedPrefix::new("claude-haiku-4-5").with_text("big prefix");
+
+        assert!(prefix.refresh_if_needed(&client).await.unwrap());
+        assert!(!prefix.refresh_if_needed(&client).await.unwrap());
+        prefix
+            .create(
+                &client,
+                MessageRequest::new().max_tokens(64).add_user_message("hi"),
+                None,
+            )
+            .await
+            .unwrap();
+
+        let stats = prefix.stats();
+        assert_eq!(stats.refreshes, 1);
+        assert_eq!((stats.writes, stats.reads), (1, 1));
+    }
+}
@@ -0,0 +1,266 @@
+//! Structured concurrency for requests
+//!
+//! [`Client::scope`] runs a body that can spawn requests onto a
+//! [`RequestScope`]. The scope does not return until every spawned request
+//! has finished, and if the scope itself is dropped (say a server handler
+//! is cancelled because its client hung up) every request still in flight is
+//! cancelled with it. Nothing outlives the scope in the background.
+//!
+//! ```rust,no_run
+//! use threatflux_anthropic_sdk::{models::MessageRequest, Client};
+//!
+//! # async fn example() -> threatflux_anthropic_sdk::Result<()> {
+//! let client = Client::from_env()?;
+//! let (summary, title) = client
+//!     .scope(|s| async move {
+//!         let summary = s.spawn_message(
+//!             MessageRequest::new().max_tokens(512).add_user_message("Summarize ..."),
+//!         );
+//!         let title = s.spawn_message(
+//!             MessageRequest::new().max_tokens(32).add_user_message("Title for ..."),
+//!         );
+//!         Ok::<_, threatflux_anthropic_sdk::AnthropicError>((summary.await?, title.await?))
+//!     })
+//!     .await?;
+//! # let _ = (summary, title);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Spawned tasks inherit the ambient [`UserContext`] and [`PriorityContext`].
+//! A task that panics makes the scope panic when it exits, as
+//! [`std::thread::scope`] does.
+
+use crate::{
+    client::Client,
+    error::{AnthropicError, Result},
+    models::message::{MessageRequest, MessageResponse},
+    priority::PriorityContext,
+    user_context::UserContext,
+};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+use tokio::{
+    sync::{oneshot, Notify},
+    task::{AbortHandle, JoinSet},
+};
+
+/// Handle for spawning requests tied to a [`Client::scope`]
+#[derive(Clone)]
+pub struct RequestScope {
+    client: Client,
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    /// `None` once the scope has exited
+    tasks: Mutex<Option<JoinSet<()>>>,
+    /// Signalled whenever a task ends, including by panicking
+    task_ended: Notify,
+}
+
+struct TaskEnded(Arc<Shared>);
+
+impl Drop for TaskEnded {
+    fn drop(&mut self) {
+        self.0.task_ended.notify_one();
+    }
+}
+
+impl RequestScope {
+    /// Run `body` with a fresh scope, then wait for everything it spawned
+    pub(crate) async fn run<F, Fut, T>(client: Client, body: F) -> T
+    where
+        F: FnOnce(RequestScope) -> Fut,
+        Fut: Future<Output = T>,
+    {
+        let shared = Arc::new(Shared {
+            tasks: Mutex::new(Some(JoinSet::new())),
+            task_ended: Notify::new(),
+        });
+        // Dropping the guard (normally or because the scope future was
+        // dropped) closes the scope and aborts whatever is left.
+        let guard = CloseGuard(Arc::clone(&shared));
+        let output = body(Self { client, shared }).await;
+
+        loop {
+            let task_ended = guard.0.task_ended.notified();
+            let next = {
+                let mut tasks = guard.0.tasks.lock().unwrap();
+                let tasks = tasks.as_mut().expect("scope is open until the guard drops");
+                if tasks.is_empty() {
+                    break;
+                }
+                // Running tasks may spawn more, so the lock is never held
+                // across an await.
+                tasks.try_join_next()
+            };
+            match next {
+                Some(Err(err)) if err.is_panic() => std::panic::resume_unwind(err.into_panic()),
+                Some(_) => {}
+                None => task_ended.await,
+            }
+        }
+        drop(guard);
+        output
+    }
+
+    /// The client requests in this scope are made with
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Run `future` as a task of this scope
+    pub fn spawn<F, T>(&self, future: F) -> ScopedTask<T>
+    where
+        F: Future<Output = Result<T>> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+        let ended = TaskEnded(Arc::clone(&self.shared));
+        let task = PriorityContext::current().scope(async move {
+            let _ended = ended;
+            let _ = sender.send(future.await);
+        });
+        let abort =
+            self.shared
+                .tasks
+                .lock()
+                .unwrap()
+                .as_mut()
+                .map(|tasks| match UserContext::current() {
+                    Some(context) => tasks.spawn(context.scope(task)),
+                    None => tasks.spawn(task),
+                });
+        ScopedTask { receiver, abort }
+    }
+
+    /// Send a message as a task of this scope
+    pub fn spawn_message(&self, request: MessageRequest) -> ScopedTask<MessageResponse> {
+        let messages = self.client.messages();
+        self.spawn(async move { messages.create(request, None).await })
+    }
+
+    /// Cancel every task still running in this scope
+    pub fn cancel_all(&self) {
+        if let Some(tasks) = self.shared.tasks.lock().unwrap().as_mut() {
+            tasks.abort_all();
+        }
+    }
+}
+
+impl std::fmt::Debug for RequestScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let tasks = self.shared.tasks.lock().unwrap();
+        f.debug_struct("RequestScope")
+            .field("open", &tasks.is_some())
+            .field("tasks", &tasks.as_ref().map_or(0, JoinSet::len))
+            .finish()
+    }
+}
+
+struct CloseGuard(Arc<Shared>);
+
+impl Drop for CloseGuard {
+    fn drop(&mut self) {
+        // JoinSet aborts its tasks when dropped
+        let tasks = self.0.tasks.lock().map(|mut tasks| tasks.take());
+        drop(tasks);
+    }
+}
+
+/// Result of a task spawned on a [`RequestScope`]
+///
+/// Awaiting it is optional: the scope waits for the task either way. A task
+/// cancelled before it finished resolves to [`AnthropicError::Cancelled`].
+#[derive(Debug)]
+pub struct ScopedTask<T> {
+    receiver: oneshot::Receiver<Result<T>>,
+    abort: Option<AbortHandle>,
+}
+
+impl<T> ScopedTask<T> {
+    /// Cancel the task
+    pub fn abort(&self) {
+        if let Some(abort) = &self.abort {
+            abort.abort();
+        }
+    }
+}
+
+impl<T> Future for ScopedTask<T> {
+    type Output = Result<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.receiver)
+            .poll(cx)
+            .map(|result| result.unwrap_or(Err(AnthropicError::Cancelled)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    fn client() -> Client {
+        Client::new(Config::new("test-key").unwrap())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scope_waits_for_unawaited_tasks() {
+        let finished = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&finished);
+        let value = client()
+            .scope(|s| async move {
+                for delay in [1, 5, 3] {
+                    let counter = Arc::clone(&counter);
+                    s.spawn(async move {
+                        tokio::time::sleep(Duration::from_secs(delay)).await;
+                        counter.fetch_add(1, Ordering::SeqCst);
+                        Ok(())
+                    });
+                }
+                s.spawn(async { Ok(7) }).await.unwrap()
+            })
+            .await;
+        assert_eq!(value, 7);
+        assert_eq!(finished.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_dropped_scope_cancels_tasks() {
+        let finished = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&finished);
+        let client = client();
+        let scope = client.scope(|s| async move {
+            s.spawn(async move {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            });
+        });
+        assert!(tokio::time::timeout(Duration::from_secs(1), scope)
+            .await
+            .is_err());
+        tokio::time::sleep(Duration::from_secs(20)).await;
+        assert_eq!(finished.load(Ordering::SeqCst), 0);
+
+        let cancelled = client
+            .scope(|s| async move {
+                let task = s.spawn(std::future::pending::<Result<()>>());
+                s.cancel_all();
+                task.await
+            })
+            .await;
+        assert!(matches!(cancelled, Err(AnthropicError::Cancelled)));
+    }
+}
@@ -0,0 +1,213 @@
+//! Least-privilege client handles
+//!
+//! [`Client::scoped`] wraps a client in a [`ScopedClient`] that only exposes
+//! the endpoint groups its [`Scope`] allows. The restriction is checked by the
+//! compiler: a `ScopedClient<MessagesOnly>` has no `files()` or `admin()`
+//! method, and there is no way to get the underlying [`Client`] back out, so
+//! it can be handed to a plugin as a narrow capability.
+//!
+//! ```rust,no_run
+//! use threatflux_anthropic_sdk::{scope::MessagesOnly, Client, ScopedClient};
+//!
+//! fn load_plugin(client: ScopedClient<MessagesOnly>) {
+//!     let _messages = client.messages();
+//!     // client.files(); // does not compile
+//! }
+//!
+//! # fn main() -> threatflux_anthropic_sdk::Result<()> {
+//! let client = Client::from_env()?;
+//! load_plugin(client.scoped(MessagesOnly));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Custom scopes are unit structs implementing [`Scope`] plus the `Allows*`
+//! marker traits for each permitted group.
+
+use crate::{
+    api::{
+        admin::AdminApi,
+        completions::CompletionsApi,
+        files::FilesApi,
+        managed_agents::{
+            AgentsApi, DeploymentsApi, EnvironmentsApi, MemoryStoresApi, SessionsApi, VaultsApi,
+        },
+        message_batches::MessageBatchesApi,
+        messages::MessagesApi,
+        models::ModelsApi,
+        skills::SkillsApi,
+    },
+    client::Client,
+    error::Result,
+};
+use std::{fmt, marker::PhantomData};
+
+/// A set of permitted endpoint groups.
+pub trait Scope: Send + Sync + 'static {}
+
+/// Scope permits the Messages API (including token counting)
+pub trait AllowsMessages: Scope {}
+/// Scope permits the legacy Text Completions API
+pub trait AllowsCompletions: Scope {}
+/// Scope permits listing and retrieving models
+pub trait AllowsModels: Scope {}
+/// Scope permits Message Batches
+pub trait AllowsBatches: Scope {}
+/// Scope permits the Files API
+pub trait AllowsFiles: Scope {}
+/// Scope permits the Skills API
+pub trait AllowsSkills: Scope {}
+/// Scope permits the Managed Agents APIs
+pub trait AllowsManagedAgents: Scope {}
+/// Scope permits the Admin API
+pub trait AllowsAdmin: Scope {}
+
+/// Messages API only.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MessagesOnly;
+impl Scope for MessagesOnly {}
+impl AllowsMessages for MessagesOnly {}
+
+/// Model inference: messages, completions, models and batches. No file,
+/// skill, agent or admin access.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Inference;
+impl Scope for Inference {}
+impl AllowsMessages for Inference {}
+impl AllowsCompletions for Inference {}
+impl AllowsModels for Inference {}
+impl AllowsBatches for Inference {}
+
+/// Everything except the Admin API.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoAdmin;
+impl Scope for NoAdmin {}
+impl AllowsMessages for NoAdmin {}
+impl AllowsCompletions for NoAdmin {}
+impl AllowsModels for NoAdmin {}
+impl AllowsBatches for NoAdmin {}
+impl AllowsFiles for NoAdmin {}
+impl AllowsSkills for NoAdmin {}
+impl AllowsManagedAgents for NoAdmin {}
+
+/// A [`Client`] restricted to the endpoint groups allowed by `S`.
+///
+/// Calling an endpoint group outside the scope is a compile error:
+///
+/// ```compile_fail
+/// use threatflux_anthropic_sdk::{scope::MessagesOnly, Client, Config};
+///
+/// let client = Client::new(Config::new("sk-ant-test-key").unwrap());
+/// let _files = client.scoped(MessagesOnly).files();
+/// ```
+pub struct ScopedClient<S: Scope> {
+    client: Client,
+    _scope: PhantomData<fn() -> S>,
+}
+
+impl<S: Scope> Clone for ScopedClient<S> {
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            _scope: PhantomData,
+        }
+    }
+}
+
+impl<S: Scope> fmt::Debug for ScopedClient<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScopedClient")
+            .field("scope", &std::any::type_name::<S>())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: Scope> ScopedClient<S> {
+    pub(crate) fn new(client: Client) -> Self {
+        Self {
+            client,
+            _scope: PhantomData,
+        }
+    }
+}
+
+impl<S: AllowsMessages> ScopedClient<S> {
+    /// Access the Messages API
+    pub fn messages(&self) -> MessagesApi {
+        self.client.messages()
+    }
+}
+
+impl<S: AllowsCompletions> ScopedClient<S> {
+    /// Access the Text Completions API
+    pub fn completions(&self) -> CompletionsApi {
+        self.client.completions()
+    }
+}
+
+impl<S: AllowsModels> ScopedClient<S> {
+    /// Access the Models API
+    pub fn models(&self) -> ModelsApi {
+        self.client.models()
+    }
+}
+
+impl<S: AllowsBatches> ScopedClient<S> {
+    /// Access the Message Batches API
+    pub fn message_batches(&self) -> MessageBatchesApi {
+        self.client.message_batches()
+    }
+}
+
+impl<S: AllowsFiles> ScopedClient<S> {
+    /// Access the Files API
+    pub fn files(&self) -> FilesApi {
+        self.client.files()
+    }
+}
+
+impl<S: AllowsSkills> ScopedClient<S> {
+    /// Access the Skills API
+    pub fn skills(&self) -> SkillsApi {
+        self.client.skills()
+    }
+}
+
+impl<S: AllowsManagedAgents> ScopedClient<S> {
+    /// Access the Managed Agents — Agents API
+    pub fn agents(&self) -> AgentsApi {
+        self.client.agents()
+    }
+
+    /// Access the Managed Agents — Environments API
+    pub fn environments(&self) -> EnvironmentsApi {
+        self.client.environments()
+    }
+
+    /// Access the Managed Agents — Sessions API
+    pub fn sessions(&self) -> SessionsApi {
+        self.client.sessions()
+    }
+
+    /// Access the Managed Agents — Vaults API
+    pub fn vaults(&self) -> VaultsApi {
+        self.client.vaults()
+    }
+
+    /// Access the Managed Agents — Memory Stores API
+    pub fn memory_stores(&self) -> MemoryStoresApi {
+        self.client.memory_stores()
+    }
+
+    /// Access the Managed Agents — Deployments API
+    pub fn deployments(&self) -> DeploymentsApi {
+        self.client.deployments()
+    }
+}
+
+impl<S: AllowsAdmin> ScopedClient<S> {
+    /// Access the Admin API (requires admin key)
+    pub fn admin(&self) -> Result<AdminApi> {
+        self.client.admin()
+    }
+}
@@ -0,0 +1,93 @@
+//! [`tower::Service`](tower_service::Service) for the Messages API, enabled
+//! by the `tower` feature
+//!
+//! [`MessagesService`] sends each [`MessageRequest`] with
+//! [`MessagesApi::create`](crate::api::messages::MessagesApi::create), so any
+//! tower middleware can be stacked around Anthropic calls:
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use threatflux_anthropic_sdk::{models::message::MessageRequest, Client, Config};
+//! use tower::{ServiceBuilder, ServiceExt};
+//!
+//! # async fn example() -> Result<(), tower::BoxError> {
+//! // Let tower own retries so they are not multiplied by the client's
+//! let client = Client::new(Config::from_env()?.with_max_retries(0));
+//! let service = ServiceBuilder::new()
+//!     .load_shed()
+//!     .concurrency_limit(8)
+//!     .timeout(Duration::from_secs(30))
+//!     .service(client.messages_service());
+//!
+//! let request = MessageRequest::new()
+//!     .max_tokens(256)
+//!     .add_user_message("Hello, Claude!");
+//! let response = service.oneshot(request).await?;
+//! println!("{}", response.text());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The service is always ready; backpressure comes from the layers around
+//! it. Errors are [`AnthropicError`]s, and
+//! [`is_retryable`](AnthropicError::is_retryable) is the natural test for a
+//! `tower::retry::Policy`.
+
+use crate::{
+    client::Client,
+    error::{AnthropicError, Result},
+    models::message::{MessageRequest, MessageResponse},
+    types::RequestOptions,
+};
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tower_service::Service;
+
+/// A [`Client`] as a `tower::Service<MessageRequest>`
+#[derive(Clone)]
+pub struct MessagesService {
+    client: Client,
+    options: Option<RequestOptions>,
+}
+
+impl MessagesService {
+    /// A service sending requests with `client`
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            options: None,
+        }
+    }
+
+    /// Send every request with `options`
+    pub fn with_options(mut self, options: RequestOptions) -> Self {
+        self.options = Some(options);
+        self
+    }
+}
+
+impl Service<MessageRequest> for MessagesService {
+    type Response = MessageResponse;
+    type Error = AnthropicError;
+    type Future = Pin<Box<dyn Future<Output = Result<MessageResponse>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: MessageRequest) -> Self::Future {
+        let messages = self.client.messages();
+        let options = self.options.clone();
+        Box::pin(async move { messages.create(request, options).await })
+    }
+}
+
+impl Client {
+    /// This client as a `tower::Service<MessageRequest>`
+    pub fn messages_service(&self) -> MessagesService {
+        MessagesService::new(self.clone())
+    }
+}
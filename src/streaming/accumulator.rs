@@ -0,0 +1,401 @@
+//! Rebuilding a full message from stream events
+//!
+//! [`MessageAccumulator`] is the state machine behind
+//! [`MessageStream::collect_message`](crate::streaming::MessageStream::collect_message):
+//! feed it every [`StreamEvent`] and it assembles the [`MessageResponse`] the
+//! non-streaming endpoint would have returned, including tool inputs built
+//! from `input_json_delta` fragments, thinking text and signatures,
+//! citations, the stop reason and merged usage. Use it directly when the
+//! events are also needed as they arrive, or when they come from somewhere
+//! other than a [`MessageStream`](crate::streaming::MessageStream).
+//!
+//! ```rust
+//! use threatflux_anthropic_sdk::{
+//!     models::{ContentBlock, ContentBlockDelta, MessageResponse, StreamEvent},
+//!     streaming::MessageAccumulator,
+//! };
+//!
+//! # fn main() -> threatflux_anthropic_sdk::Result<()> {
+//! let start: MessageResponse = serde_json::from_value(serde_json::json!({
+//!     "id": "msg_1", "type": "message", "role": "assistant", "content": [],
+//!     "model": "claude-haiku-4-5", "stop_reason": null, "stop_sequence": null,
+//!     "usage": {"input_tokens": 12, "output_tokens": 1}
+//! }))?;
+//!
+//! let mut accumulator = MessageAccumulator::new();
+//! accumulator.push(StreamEvent::MessageStart { message: start })?;
+//! accumulator.push(StreamEvent::ContentBlockStart {
+//!     index: 0,
+//!     content_block: ContentBlock::text(""),
+//! })?;
+//! for text in ["Hello", ", world"] {
+//!     accumulator.push(StreamEvent::ContentBlockDelta {
+//!         index: 0,
+//!         delta: ContentBlockDelta::TextDelta { text: text.to_string() },
+//!     })?;
+//! }
+//! accumulator.push(StreamEvent::ContentBlockStop { index: 0 })?;
+//!
+//! assert_eq!(accumulator.finish()?.text(), "Hello, world");
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    error::{AnthropicError, Result},
+    models::{
+        common::{
+            CacheCreationUsage, ContentBlock, ServerToolUsage, StopDetails, ToolResultContent,
+            Usage,
+        },
+        message::{ContentBlockDelta, MessageDelta, MessageResponse, StreamEvent},
+    },
+};
+use std::collections::HashMap;
+
+/// Incrementally rebuilds a [`MessageResponse`] from stream events
+#[derive(Debug, Clone, Default)]
+pub struct MessageAccumulator {
+    message: Option<MessageResponse>,
+    content_blocks: Vec<Option<ContentBlock>>,
+    input_json_buffers: HashMap<usize, String>,
+    stopped: bool,
+}
+
+impl MessageAccumulator {
+    /// Create an empty accumulator
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply one event.
+    ///
+    /// Fails on a stream `error` event; the state collected so far is kept.
+    pub fn push(&mut self, event: StreamEvent) -> Result<()> {
+        match event {
+            StreamEvent::MessageStart { message } => {
+                self.content_blocks = message.content.iter().cloned().map(Some).collect();
+                self.message = Some(message);
+            }
+            StreamEvent::ContentBlockStart {
+                index,
+                content_block,
+            } => {
+                if self.content_blocks.len() <= index {
+                    self.content_blocks.resize(index + 1, None);
+                }
+                self.content_blocks[index] = Some(content_block);
+            }
+            StreamEvent::ContentBlockDelta { index, delta } => self.apply_delta(index, delta),
+            StreamEvent::ContentBlockStop { index } => self.finish_block(index),
+            StreamEvent::MessageDelta { delta, usage } => self.apply_message_delta(delta, usage),
+            StreamEvent::MessageStop => self.stopped = true,
+            StreamEvent::Ping => {}
+            StreamEvent::Error { error } => {
+                return Err(AnthropicError::stream(format!("Stream error: {:?}", error))
+                    .with_context("Message streaming"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `message_stop` has been seen
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// The message as assembled so far, or `None` before `message_start`.
+    ///
+    /// Tool inputs whose JSON is still streaming are not filled in yet.
+    pub fn snapshot(&self) -> Option<MessageResponse> {
+        let mut message = self.message.clone()?;
+        message.content = self.content_blocks.iter().flatten().cloned().collect();
+        Some(message)
+    }
+
+    /// Finish any blocks left open and return the assembled message
+    pub fn finish(mut self) -> Result<MessageResponse> {
+        let mut open: Vec<usize> = self.input_json_buffers.keys().copied().collect();
+        open.sort_unstable();
+        for index in open {
+            self.finish_block(index);
+        }
+
+        let mut message = self.message.ok_or_else(|| {
+            AnthropicError::stream("No message_start event received")
+                .with_context("Stream message collection")
+        })?;
+        message.content = self.content_blocks.into_iter().flatten().collect();
+        Ok(message)
+    }
+
+    fn apply_delta(&mut self, index: usize, delta: ContentBlockDelta) {
+        let block = self.content_blocks.get_mut(index).and_then(Option::as_mut);
+        match delta {
+            ContentBlockDelta::TextDelta { text } => {
+                if let Some(ContentBlock::Text {
+                    text: block_text, ..
+                }) = block
+                {
+                    block_text.push_str(&text);
+                }
+            }
+            ContentBlockDelta::ThinkingDelta { thinking } => {
+                if let Some(ContentBlock::Thinking {
+                    thinking: block_thinking,
+                    ..
+                }) = block
+                {
+                    block_thinking.push_str(&thinking);
+                }
+            }
+            ContentBlockDelta::SignatureDelta {
+                signature: signature_delta,
+            } => {
+                if let Some(ContentBlock::Thinking { signature, .. }) = block {
+                    signature
+                        .get_or_insert_with(String::new)
+                        .push_str(&signature_delta);
+                }
+            }
+            ContentBlockDelta::InputJsonDelta { partial_json } => {
+                self.input_json_buffers
+                    .entry(index)
+                    .or_default()
+                    .push_str(&partial_json);
+            }
+            ContentBlockDelta::CitationsDelta { citation } => {
+                if let Some(ContentBlock::Text { citations, .. }) = block {
+                    citations.get_or_insert_with(Vec::new).push(citation);
+                }
+            }
+            ContentBlockDelta::Unknown => {}
+        }
+    }
+
+    fn finish_block(&mut self, index: usize) {
+        let Some(partial_json) = self.input_json_buffers.remove(&index) else {
+            return;
+        };
+        // An empty buffer means the tool was called without arguments.
+        let parsed = if partial_json.trim().is_empty() {
+            serde_json::Value::Object(Default::default())
+        } else {
+            serde_json::from_str::<serde_json::Value>(&partial_json)
+                .unwrap_or(serde_json::Value::String(partial_json))
+        };
+
+        match self.content_blocks.get_mut(index).and_then(Option::as_mut) {
+            Some(ContentBlock::ToolUse { input, .. }) => *input = parsed,
+            Some(ContentBlock::ServerToolUse { input, .. }) => *input = Some(parsed),
+            Some(ContentBlock::ToolResult { content, .. }) => {
+                *content = Some(ToolResultContent::Json(parsed))
+            }
+            _ => {}
+        }
+    }
+
+    fn apply_message_delta(&mut self, delta: MessageDelta, usage: Usage) {
+        let Some(message) = self.message.as_mut() else {
+            return;
+        };
+        merge_usage(&mut message.usage, usage);
+
+        if let Some(stop_reason) = delta.stop_reason {
+            message.stop_reason = Some(stop_reason);
+        }
+        if let Some(stop_sequence) = delta.stop_sequence {
+            message.stop_sequence = Some(stop_sequence);
+        }
+        if let Some(stop_details) = delta
+            .extra
+            .get("stop_details")
+            .filter(|value| !value.is_null())
+            .and_then(|value| serde_json::from_value::<StopDetails>(value.clone()).ok())
+        {
+            message.stop_details = Some(stop_details);
+        }
+    }
+}
+
+/// Fold a `message_delta` usage payload into the running totals.
+///
+/// Streaming usage payloads can be partial, so the maximum observed value of
+/// each counter is kept.
+pub(super) fn merge_usage(total: &mut Usage, usage: Usage) {
+    total.input_tokens = total.input_tokens.max(usage.input_tokens);
+    total.output_tokens = total.output_tokens.max(usage.output_tokens);
+    total.cache_creation_input_tokens = total
+        .cache_creation_input_tokens
+        .max(usage.cache_creation_input_tokens);
+    total.cache_read_input_tokens = total
+        .cache_read_input_tokens
+        .max(usage.cache_read_input_tokens);
+
+    if let Some(incoming) = usage.cache_creation {
+        let cache_creation = total
+            .cache_creation
+            .get_or_insert_with(CacheCreationUsage::default);
+        cache_creation.ephemeral_5m_input_tokens = cache_creation
+            .ephemeral_5m_input_tokens
+            .max(incoming.ephemeral_5m_input_tokens);
+        cache_creation.ephemeral_1h_input_tokens = cache_creation
+            .ephemeral_1h_input_tokens
+            .max(incoming.ephemeral_1h_input_tokens);
+    }
+
+    if let Some(incoming) = usage.server_tool_use {
+        let server_tool_use = total
+            .server_tool_use
+            .get_or_insert_with(ServerToolUsage::default);
+        server_tool_use.web_search_requests = server_tool_use
+            .web_search_requests
+            .max(incoming.web_search_requests);
+    }
+
+    if usage.inference_geo.is_some() {
+        total.inference_geo = usage.inference_geo;
+    }
+    if usage.service_tier.is_some() {
+        total.service_tier = usage.service_tier;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::common::StopReason;
+    use serde_json::json;
+
+    fn event(value: serde_json::Value) -> StreamEvent {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn start() -> StreamEvent {
+        event(json!({
+            "type": "message_start",
+            "message": {
+                "id": "msg_1", "type": "message", "role": "assistant", "content": [],
+                "model": "claude-haiku-4-5", "stop_reason": null, "stop_sequence": null,
+                "usage": {"input_tokens": 25, "output_tokens": 1, "cache_read_input_tokens": 7}
+            }
+        }))
+    }
+
+    #[test]
+    fn test_rebuilds_thinking_text_and_tool_use() {
+        let events = [
+            start(),
+            event(json!({"type": "content_block_start", "index": 0,
+                "content_block": {"type": "thinking", "thinking": ""}})),
+            event(json!({"type": "content_block_delta", "index": 0,
+                "delta": {"type": "thinking_delta", "thinking": "Need the "}})),
+            event(json!({"type": "content_block_delta", "index": 0,
+                "delta": {"type": "thinking_delta", "thinking": "weather."}})),
+            event(json!({"type": "content_block_delta", "index": 0,
+                "delta": {"type": "signature_delta", "signature": "sig"}})),
+            event(json!({"type": "content_block_stop", "index": 0})),
+            event(json!({"type": "content_block_start", "index": 1,
+                "content_block": {"type": "text", "text": ""}})),
+            event(json!({"type": "content_block_delta", "index": 1,
+                "delta": {"type": "text_delta", "text": "Checking."}})),
+            event(json!({"type": "content_block_stop", "index": 1})),
+            event(json!({"type": "content_block_start", "index": 2,
+                "content_block": {"type": "tool_use", "id": "toolu_1", "name": "weather", "input": {}}})),
+            event(json!({"type": "content_block_delta", "index": 2,
+                "delta": {"type": "input_json_delta", "partial_json": "{\"city\": "}})),
+            event(json!({"type": "content_block_delta", "index": 2,
+                "delta": {"type": "input_json_delta", "partial_json": "\"Paris\"}"}})),
+            event(json!({"type": "content_block_stop", "index": 2})),
+            event(json!({"type": "message_delta",
+                "delta": {"stop_reason": "tool_use", "stop_sequence": null},
+                "usage": {"output_tokens": 42}})),
+            event(json!({"type": "message_stop"})),
+        ];
+
+        let mut accumulator = MessageAccumulator::new();
+        for event in events {
+            accumulator.push(event).unwrap();
+        }
+        assert!(accumulator.is_stopped());
+        let message = accumulator.finish().unwrap();
+
+        assert_eq!(message.content.len(), 3);
+        assert!(matches!(
+            &message.content[0],
+            ContentBlock::Thinking { thinking, signature: Some(sig) }
+                if thinking == "Need the weather." && sig == "sig"
+        ));
+        assert_eq!(message.text(), "Checking.");
+        assert!(matches!(
+            &message.content[2],
+            ContentBlock::ToolUse { input, .. } if input == &json!({"city": "Paris"})
+        ));
+        assert_eq!(message.stop_reason, Some(StopReason::ToolUse));
+        assert_eq!(message.usage.input_tokens, 25);
+        assert_eq!(message.usage.output_tokens, 42);
+        assert_eq!(message.usage.cache_read_input_tokens, 7);
+    }
+
+    #[test]
+    fn test_finish_closes_open_tool_input_and_reads_stop_details() {
+        let mut accumulator = MessageAccumulator::new();
+        accumulator.push(start()).unwrap();
+        accumulator
+            .push(event(json!({"type": "content_block_start", "index": 0,
+                "content_block": {"type": "tool_use", "id": "toolu_1", "name": "now", "input": {}}})))
+            .unwrap();
+        accumulator
+            .push(event(json!({"type": "content_block_start", "index": 1,
+                "content_block": {"type": "tool_use", "id": "toolu_2", "name": "sum", "input": {}}})))
+            .unwrap();
+        accumulator
+            .push(event(json!({"type": "content_block_delta", "index": 0,
+                "delta": {"type": "input_json_delta", "partial_json": ""}})))
+            .unwrap();
+        accumulator
+            .push(event(json!({"type": "content_block_delta", "index": 1,
+                "delta": {"type": "input_json_delta", "partial_json": "[1, 2]"}})))
+            .unwrap();
+        accumulator
+            .push(event(json!({"type": "message_delta",
+                "delta": {"stop_reason": "refusal", "stop_details": {"type": "refusal", "category": "cyber"}},
+                "usage": {"output_tokens": 3}})))
+            .unwrap();
+
+        // Input JSON is only parsed once the block closes.
+        let snapshot = accumulator.snapshot().unwrap();
+        assert!(matches!(
+            &snapshot.content[1],
+            ContentBlock::ToolUse { input, .. } if input == &json!({})
+        ));
+
+        let message = accumulator.finish().unwrap();
+        assert!(matches!(
+            &message.content[0],
+            ContentBlock::ToolUse { input, .. } if input == &json!({})
+        ));
+        assert!(matches!(
+            &message.content[1],
+            ContentBlock::ToolUse { input, .. } if input == &json!([1, 2])
+        ));
+        assert_eq!(
+            message.stop_details.unwrap().category.as_deref(),
+            Some("cyber")
+        );
+    }
+
+    #[test]
+    fn test_errors_without_start_or_on_error_event() {
+        assert!(MessageAccumulator::new().finish().is_err());
+
+        let mut accumulator = MessageAccumulator::new();
+        accumulator.push(start()).unwrap();
+        let err = accumulator
+            .push(event(json!({"type": "error",
+                "error": {"type": "overloaded_error", "message": "Overloaded"}})))
+            .unwrap_err();
+        assert!(matches!(err, AnthropicError::Stream(_)));
+        assert!(accumulator.snapshot().is_some());
+    }
+}
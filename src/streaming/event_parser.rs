@@ -1,12 +1,33 @@
 //! Server-Sent Events (SSE) parser for streaming responses
 
 use crate::error::{AnthropicError, Result};
+use crate::utils::text::{truncate_chars, ELLIPSIS};
 use std::collections::HashMap;
+use std::ops::Range;
+
+/// Characters of event data quoted in parse errors
+const ERROR_SNIPPET_CHARS: usize = 160;
+
+/// What to do with an event whose data cannot be parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MalformedEventPolicy {
+    /// Fail the stream with a [`AnthropicError::Stream`] error
+    #[default]
+    Abort,
+    /// Log a warning, drop the event and keep reading
+    Skip,
+}
 
 /// Parser for Server-Sent Events (SSE) streams
 #[derive(Debug)]
 pub struct EventParser {
     current_event: Option<ParsedEvent>,
+    policy: MalformedEventPolicy,
+    /// Bytes of the stream consumed so far
+    position: usize,
+    /// Stream bytes of the event being decoded, for error messages
+    frame: Option<Range<usize>>,
+    skipped: usize,
 }
 
 #[derive(Debug)]
@@ -15,6 +36,7 @@ struct ParsedEvent {
     data: Vec<String>,
     id: Option<String>,
     retry: Option<u32>,
+    start: usize,
 }
 
 impl EventParser {
@@ -22,19 +44,46 @@ impl EventParser {
     pub fn new() -> Self {
         Self {
             current_event: None,
+            policy: MalformedEventPolicy::default(),
+            position: 0,
+            frame: None,
+            skipped: 0,
         }
     }
 
+    /// Choose whether a malformed event aborts parsing or is skipped
+    pub fn with_malformed_policy(mut self, policy: MalformedEventPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Number of malformed events dropped under [`MalformedEventPolicy::Skip`]
+    pub fn skipped_events(&self) -> usize {
+        self.skipped
+    }
+
     /// Parse a line from the SSE stream
     pub fn parse_line(
         &mut self,
         line: &str,
     ) -> Result<Option<crate::models::message::StreamEvent>> {
+        self.parse_line_with_len(line, line.len() + 1)
+    }
+
+    /// Parse a line that took `raw_len` bytes of the stream, including its
+    /// line terminator, so error offsets match the raw bytes
+    pub fn parse_line_with_len(
+        &mut self,
+        line: &str,
+        raw_len: usize,
+    ) -> Result<Option<crate::models::message::StreamEvent>> {
+        let line_start = self.position;
+        self.position += raw_len;
         let line = line.trim();
 
         // Empty line indicates end of event
         if line.is_empty() {
-            return self.finish_event();
+            return self.finish_event(line_start);
         }
 
         // Comments start with ':'
@@ -49,6 +98,7 @@ impl EventParser {
                 data: Vec::new(),
                 id: None,
                 retry: None,
+                start: line_start,
             });
         }
 
@@ -92,7 +142,18 @@ impl EventParser {
         T: serde::de::DeserializeOwned,
     {
         serde_json::from_str(data).map_err(|e| {
-            AnthropicError::stream(format!("Failed to parse {} event: {}", event_type, e))
+            let location = self
+                .frame
+                .as_ref()
+                .map(|frame| format!(" at bytes {}..{}", frame.start, frame.end))
+                .unwrap_or_default();
+            AnthropicError::stream(format!(
+                "Failed to parse {} event{}: {}; data: {:?}",
+                event_type,
+                location,
+                e,
+                truncate_chars(data, ERROR_SNIPPET_CHARS, Some(ELLIPSIS))
+            ))
         })
     }
 
@@ -150,13 +211,32 @@ impl EventParser {
         }
     }
 
-    /// Finish parsing the current event
-    fn finish_event(&mut self) -> Result<Option<crate::models::message::StreamEvent>> {
+    /// Finish the current event at the blank line starting at byte `end`,
+    /// applying the malformed-event policy
+    fn finish_event(&mut self, end: usize) -> Result<Option<crate::models::message::StreamEvent>> {
         let event = match self.current_event.take() {
             Some(event) => event,
             None => return Ok(None), // No event to finish
         };
+        self.frame = Some(event.start..end);
+        let result = self.decode_event(event);
+        self.frame = None;
 
+        match result {
+            Err(error) if self.policy == MalformedEventPolicy::Skip => {
+                self.skipped += 1;
+                tracing::warn!("Skipping malformed stream event: {}", error);
+                Ok(None)
+            }
+            other => other,
+        }
+    }
+
+    /// Decode a complete event
+    fn decode_event(
+        &mut self,
+        event: ParsedEvent,
+    ) -> Result<Option<crate::models::message::StreamEvent>> {
         // Join data lines with newlines
         let data = event.data.join("\n");
         if data.is_empty() {
@@ -303,7 +383,7 @@ mod tests {
         match event {
             StreamEvent::ContentBlockDelta { delta, .. } => {
                 assert!(matches!(
-                    delta.citation,
+                    delta.as_citation(),
                     Some(TextCitation::SearchResultLocation { .. })
                 ));
             }
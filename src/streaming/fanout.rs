@@ -0,0 +1,550 @@
+//! Fan-out of stream events to several consumers
+//!
+//! A gateway often relays one Claude stream to several places at once: the
+//! client's WebSocket, an audit logger, a metrics accumulator. They consume
+//! at different speeds, and a slow one must not stall the others for long.
+//! [`StreamFanout`] gives every sink its own bounded queue and worker task,
+//! and a [`SinkPolicy`] decides what happens when that queue is full:
+//!
+//! * [`Overflow::Wait`]: apply backpressure, holding up the producer (and so
+//!   every sink) until there is room, optionally evicting the sink after
+//!   [`SinkPolicy::max_wait`].
+//! * [`Overflow::DropOldest`] / [`Overflow::DropNewest`]: lose events for this
+//!   sink only, optionally evicting it after [`SinkPolicy::max_dropped`].
+//! * [`Overflow::Evict`]: disconnect the sink at once.
+//!
+//! Evicted sinks are cancelled and their queued events discarded. A sink whose
+//! `send` fails is removed too. [`StreamFanout::stats`] reports what happened
+//! to each.
+//!
+//! ```rust,no_run
+//! use futures::StreamExt;
+//! use threatflux_anthropic_sdk::{
+//!     models::MessageRequest,
+//!     streaming::{SinkPolicy, StreamFanout},
+//!     Client,
+//! };
+//!
+//! # async fn example() -> threatflux_anthropic_sdk::Result<()> {
+//! let client = Client::from_env()?;
+//! let mut fanout = StreamFanout::new();
+//! let mut client_events = fanout.subscribe("client", SinkPolicy::wait(64));
+//! let mut audit_events = fanout.subscribe("audit", SinkPolicy::drop_oldest(1024));
+//! tokio::spawn(async move { while let Some(_event) = client_events.next().await {} });
+//! tokio::spawn(async move { while let Some(_event) = audit_events.next().await {} });
+//!
+//! let request = MessageRequest::new().max_tokens(256).add_user_message("Hello!");
+//! let stream = client.messages().create_stream(request, None).await?;
+//! let message = fanout.run(stream).await?;
+//! for sink in fanout.stats() {
+//!     println!("{}: {} delivered, {} dropped, {:?}", sink.name, sink.delivered, sink.dropped, sink.state);
+//! }
+//! # let _ = message;
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{accumulator::MessageAccumulator, message_stream::MessageStream};
+use crate::{
+    error::Result,
+    models::message::{MessageResponse, StreamEvent},
+};
+use futures::{channel::mpsc, Sink, SinkExt, StreamExt};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Display,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use tokio::{sync::Notify, task::JoinHandle, time::Instant};
+
+/// What to do with an event when a sink's queue is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// Wait for room, holding up the producer
+    Wait,
+    /// Discard the oldest queued event to make room
+    DropOldest,
+    /// Discard the new event
+    DropNewest,
+    /// Evict the sink
+    Evict,
+}
+
+/// Buffering and eviction rules for one sink
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SinkPolicy {
+    /// Events queued for the sink before [`overflow`](Self::overflow) applies
+    pub capacity: usize,
+    /// Behaviour when the queue is full
+    pub overflow: Overflow,
+    /// With [`Overflow::Wait`], evict the sink if no room frees up in time
+    pub max_wait: Option<Duration>,
+    /// With the drop policies, evict the sink after this many lost events
+    pub max_dropped: Option<u64>,
+}
+
+impl SinkPolicy {
+    fn new(capacity: usize, overflow: Overflow) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            overflow,
+            max_wait: None,
+            max_dropped: None,
+        }
+    }
+
+    /// Backpressure with a `capacity`-event buffer
+    pub fn wait(capacity: usize) -> Self {
+        Self::new(capacity, Overflow::Wait)
+    }
+
+    /// Keep the newest `capacity` events
+    pub fn drop_oldest(capacity: usize) -> Self {
+        Self::new(capacity, Overflow::DropOldest)
+    }
+
+    /// Keep the oldest `capacity` events
+    pub fn drop_newest(capacity: usize) -> Self {
+        Self::new(capacity, Overflow::DropNewest)
+    }
+
+    /// Evict the sink once `capacity` events are waiting
+    pub fn evict(capacity: usize) -> Self {
+        Self::new(capacity, Overflow::Evict)
+    }
+
+    /// Set [`max_wait`](Self::max_wait)
+    pub fn with_max_wait(mut self, max_wait: Duration) -> Self {
+        self.max_wait = Some(max_wait);
+        self
+    }
+
+    /// Set [`max_dropped`](Self::max_dropped)
+    pub fn with_max_dropped(mut self, max_dropped: u64) -> Self {
+        self.max_dropped = Some(max_dropped);
+        self
+    }
+}
+
+/// Where a sink stands
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SinkState {
+    /// Receiving events
+    Active,
+    /// All events were delivered and the sink was closed
+    Finished,
+    /// Removed for falling behind
+    Evicted {
+        /// Why
+        reason: String,
+    },
+    /// Removed because `send` failed
+    Failed {
+        /// The sink's error
+        error: String,
+    },
+}
+
+/// Delivery statistics for one sink
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SinkStats {
+    /// Name given when the sink was added
+    pub name: String,
+    /// Events the sink accepted
+    pub delivered: u64,
+    /// Events discarded by a drop policy or on eviction
+    pub dropped: u64,
+    /// Current state
+    pub state: SinkState,
+}
+
+struct SinkSlot<T> {
+    policy: SinkPolicy,
+    queue: Mutex<VecDeque<T>>,
+    item_ready: Notify,
+    space_ready: Notify,
+    closed: AtomicBool,
+    stats: Mutex<SinkStats>,
+    worker: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl<T> SinkSlot<T> {
+    fn is_active(&self) -> bool {
+        self.stats.lock().unwrap().state == SinkState::Active
+    }
+
+    /// Leave the active state (first transition wins) and stop the worker
+    fn retire(&self, state: SinkState) {
+        let discarded = {
+            let mut queue = self.queue.lock().unwrap();
+            let discarded = queue.len() as u64;
+            queue.clear();
+            discarded
+        };
+        {
+            let mut stats = self.stats.lock().unwrap();
+            if stats.state != SinkState::Active {
+                return;
+            }
+            stats.dropped += discarded;
+            stats.state = state;
+        }
+        if let Some(worker) = self.worker.lock().unwrap().take() {
+            worker.abort();
+        }
+    }
+
+    fn evict(&self, reason: impl Into<String>) {
+        self.retire(SinkState::Evicted {
+            reason: reason.into(),
+        });
+    }
+
+    fn record_drop(&self) {
+        let dropped = {
+            let mut stats = self.stats.lock().unwrap();
+            stats.dropped += 1;
+            stats.dropped
+        };
+        if let Some(limit) = self.policy.max_dropped {
+            if dropped > limit {
+                self.evict(format!("dropped more than {} events", limit));
+            }
+        }
+    }
+}
+
+/// Forwards events to several sinks, each with its own buffer
+pub struct StreamFanout<T = StreamEvent> {
+    slots: Vec<Arc<SinkSlot<T>>>,
+    drain_timeout: Option<Duration>,
+}
+
+impl<T: Clone + Send + 'static> Default for StreamFanout<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone + Send + 'static> StreamFanout<T> {
+    /// Fan-out with no sinks
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            drain_timeout: None,
+        }
+    }
+
+    /// Bound how long [`finish`](Self::finish) waits for sinks to drain;
+    /// sinks still busy afterwards are evicted
+    pub fn with_drain_timeout(mut self, timeout: Duration) -> Self {
+        self.drain_timeout = Some(timeout);
+        self
+    }
+
+    /// Add a sink. Its worker task starts right away.
+    pub fn add_sink<S>(&mut self, name: impl Into<String>, sink: S, policy: SinkPolicy)
+    where
+        S: Sink<T> + Send + Unpin + 'static,
+        S::Error: Display,
+    {
+        let slot = Arc::new(SinkSlot {
+            policy,
+            queue: Mutex::new(VecDeque::with_capacity(policy.capacity)),
+            item_ready: Notify::new(),
+            space_ready: Notify::new(),
+            closed: AtomicBool::new(false),
+            stats: Mutex::new(SinkStats {
+                name: name.into(),
+                delivered: 0,
+                dropped: 0,
+                state: SinkState::Active,
+            }),
+            worker: Mutex::new(None),
+        });
+        let worker = tokio::spawn(deliver(Arc::clone(&slot), sink));
+        *slot.worker.lock().unwrap() = Some(worker);
+        self.slots.push(slot);
+    }
+
+    /// Add a sink read as a stream by the caller, e.g. a WebSocket handler
+    pub fn subscribe(&mut self, name: impl Into<String>, policy: SinkPolicy) -> mpsc::Receiver<T> {
+        let (sender, receiver) = mpsc::channel(0);
+        self.add_sink(name, sender, policy);
+        receiver
+    }
+
+    /// Queue `event` for every active sink, applying each sink's policy
+    pub async fn publish(&self, event: T) {
+        for slot in &self.slots {
+            if slot.is_active() {
+                enqueue(slot, event.clone()).await;
+            }
+        }
+    }
+
+    /// Number of sinks still receiving events
+    pub fn active_sinks(&self) -> usize {
+        self.slots.iter().filter(|slot| slot.is_active()).count()
+    }
+
+    /// Statistics for every sink, in the order they were added
+    pub fn stats(&self) -> Vec<SinkStats> {
+        self.slots
+            .iter()
+            .map(|slot| slot.stats.lock().unwrap().clone())
+            .collect()
+    }
+
+    /// Stop accepting events and wait for sinks to deliver what they have
+    /// queued and close
+    pub async fn finish(&mut self) -> Vec<SinkStats> {
+        let deadline = self.drain_timeout.map(|timeout| Instant::now() + timeout);
+        for slot in &self.slots {
+            slot.closed.store(true, Ordering::SeqCst);
+            slot.item_ready.notify_one();
+        }
+        for slot in &self.slots {
+            let Some(worker) = slot.worker.lock().unwrap().take() else {
+                continue;
+            };
+            let abort = worker.abort_handle();
+            let drained = match deadline {
+                Some(deadline) => tokio::time::timeout_at(deadline, worker).await.is_ok(),
+                None => {
+                    let _ = worker.await;
+                    true
+                }
+            };
+            if !drained {
+                abort.abort();
+                slot.evict("did not drain in time");
+            }
+        }
+        self.stats()
+    }
+}
+
+impl StreamFanout<StreamEvent> {
+    /// Forward every event of `stream` and return the complete message.
+    ///
+    /// If the stream fails, sinks receive an `error` event describing the
+    /// failure before the error is returned. Sinks are drained and closed
+    /// either way.
+    pub async fn run(&mut self, mut stream: MessageStream) -> Result<MessageResponse> {
+        let mut accumulator = MessageAccumulator::new();
+        let outcome = loop {
+            let event = match stream.next().await {
+                Some(Ok(event)) => event,
+                Some(Err(err)) => break Err(err),
+                None => break accumulator.finish(),
+            };
+            self.publish(event.clone()).await;
+            if let Err(err) = accumulator.push(event) {
+                break Err(err);
+            }
+            if accumulator.is_stopped() {
+                break accumulator.finish();
+            }
+        };
+
+        if let Err(err) = &outcome {
+            let error = HashMap::from([
+                ("type".to_string(), "sdk_error".into()),
+                ("message".to_string(), err.to_string().into()),
+            ]);
+            self.publish(StreamEvent::Error { error }).await;
+        }
+        self.finish().await;
+        outcome
+    }
+}
+
+impl<T> std::fmt::Debug for StreamFanout<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StreamFanout")
+            .field(
+                "sinks",
+                &self
+                    .slots
+                    .iter()
+                    .map(|slot| slot.stats.lock().unwrap().clone())
+                    .collect::<Vec<_>>(),
+            )
+            .field("drain_timeout", &self.drain_timeout)
+            .finish()
+    }
+}
+
+async fn enqueue<T>(slot: &SinkSlot<T>, event: T) {
+    let policy = slot.policy;
+    let deadline = policy.max_wait.map(|wait| Instant::now() + wait);
+    let mut event = Some(event);
+    loop {
+        let space = slot.space_ready.notified();
+        {
+            let mut queue = slot.queue.lock().unwrap();
+            if queue.len() < policy.capacity {
+                queue.extend(event.take());
+                drop(queue);
+                slot.item_ready.notify_one();
+                return;
+            }
+            match policy.overflow {
+                Overflow::Wait => {}
+                Overflow::DropOldest => {
+                    queue.pop_front();
+                    queue.extend(event.take());
+                    drop(queue);
+                    slot.item_ready.notify_one();
+                    slot.record_drop();
+                    return;
+                }
+                Overflow::DropNewest => {
+                    drop(queue);
+                    slot.record_drop();
+                    return;
+                }
+                Overflow::Evict => {
+                    drop(queue);
+                    slot.evict(format!("queue of {} events is full", policy.capacity));
+                    return;
+                }
+            }
+        }
+
+        match deadline {
+            Some(deadline) => {
+                if tokio::time::timeout_at(deadline, space).await.is_err() {
+                    slot.evict(format!(
+                        "no room within {:?}",
+                        policy.max_wait.unwrap_or_default()
+                    ));
+                    return;
+                }
+            }
+            None => space.await,
+        }
+        if !slot.is_active() {
+            return;
+        }
+    }
+}
+
+async fn deliver<T, S>(slot: Arc<SinkSlot<T>>, mut sink: S)
+where
+    S: Sink<T> + Unpin,
+    S::Error: Display,
+{
+    loop {
+        let next = slot.queue.lock().unwrap().pop_front();
+        match next {
+            Some(event) => {
+                slot.space_ready.notify_one();
+                if let Err(err) = sink.send(event).await {
+                    slot.retire(SinkState::Failed {
+                        error: err.to_string(),
+                    });
+                    return;
+                }
+                slot.stats.lock().unwrap().delivered += 1;
+            }
+            None if slot.closed.load(Ordering::SeqCst) => break,
+            None => slot.item_ready.notified().await,
+        }
+    }
+    let _ = sink.close().await;
+    slot.retire(SinkState::Finished);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+
+    fn slow_sink(delay: Duration) -> impl Sink<u32, Error = Infallible> + Send + Unpin {
+        Box::pin(futures::sink::unfold(
+            (),
+            move |(), _event: u32| async move {
+                tokio::time::sleep(delay).await;
+                Ok::<_, Infallible>(())
+            },
+        ))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_policies_isolate_slow_sinks() {
+        let mut fanout = StreamFanout::new();
+        let mut fast = fanout.subscribe("fast", SinkPolicy::wait(4));
+        let collector = tokio::spawn(async move {
+            let mut seen = Vec::new();
+            while let Some(event) = fast.next().await {
+                seen.push(event);
+            }
+            seen
+        });
+        fanout.add_sink(
+            "lossy",
+            slow_sink(Duration::from_secs(1)),
+            SinkPolicy::drop_oldest(2),
+        );
+        fanout.add_sink(
+            "stuck",
+            slow_sink(Duration::from_secs(3600)),
+            SinkPolicy::evict(1),
+        );
+
+        for event in 0..10u32 {
+            fanout.publish(event).await;
+        }
+        assert_eq!(fanout.active_sinks(), 2);
+        let stats = fanout.finish().await;
+
+        assert_eq!(collector.await.unwrap(), (0..10).collect::<Vec<_>>());
+        assert_eq!(stats[0].state, SinkState::Finished);
+        assert_eq!(stats[0].delivered, 10);
+
+        assert_eq!(stats[1].state, SinkState::Finished);
+        assert!(stats[1].dropped > 0);
+        assert_eq!(stats[1].delivered + stats[1].dropped, 10);
+
+        assert!(matches!(stats[2].state, SinkState::Evicted { .. }));
+        assert_eq!(stats[2].delivered, 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_wait_policy_evicts_after_max_wait_and_drain_timeout() {
+        let mut fanout = StreamFanout::new().with_drain_timeout(Duration::from_secs(5));
+        fanout.add_sink(
+            "stalled",
+            slow_sink(Duration::from_secs(60)),
+            SinkPolicy::wait(1).with_max_wait(Duration::from_secs(2)),
+        );
+        fanout.add_sink(
+            "sluggish",
+            slow_sink(Duration::from_secs(60)),
+            SinkPolicy::drop_newest(8),
+        );
+
+        for event in 0..3u32 {
+            fanout.publish(event).await;
+        }
+        let stats = fanout.finish().await;
+        assert_eq!(
+            stats[0].state,
+            SinkState::Evicted {
+                reason: "no room within 2s".to_string()
+            }
+        );
+        assert_eq!(
+            stats[1].state,
+            SinkState::Evicted {
+                reason: "did not drain in time".to_string()
+            }
+        );
+    }
+}
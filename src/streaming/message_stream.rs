@@ -1,43 +1,300 @@
 //! Streaming message responses
 
 use crate::{
-    error::{AnthropicError, Result},
-    models::common::{CacheCreationUsage, ContentBlock, ServerToolUsage, ToolResultContent},
-    models::message::{MessageResponse, StreamEvent},
-    streaming::event_parser::EventParser,
+    api::messages::MessagesApi,
+    error::{AnthropicError, ResponseClass, Result},
+    metrics::{BufferGauge, BufferKind, MetricsRecorder},
+    models::{
+        common::Usage,
+        message::{ContentBlockDelta, MessageRequest, MessageResponse, StreamEvent},
+        provenance::Provenance,
+    },
+    streaming::{
+        accumulator::MessageAccumulator,
+        event_parser::{EventParser, MalformedEventPolicy},
+        resume::{self, ResumeContext},
+    },
+    types::RequestOptions,
+    utils::{
+        http::{read_error_text, HttpClient, ResponseLimits},
+        instrumentation,
+        rate_limit::{RateLimiter, TokenReservation},
+    },
 };
-use futures::{Stream, StreamExt};
-use std::collections::HashMap;
+use futures::{FutureExt, Stream, StreamExt};
 use std::pin::Pin;
+use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
-use tokio::sync::mpsc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, OwnedSemaphorePermit};
+use tokio_util::sync::{CancellationToken, WaitForCancellationFutureOwned};
+
+/// How a [`MessageStream`] reads the response body
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StreamOptions {
+    /// What to do with an event whose data cannot be parsed
+    pub malformed_events: MalformedEventPolicy,
+    /// Fail the stream with [`AnthropicError::StreamIdle`] after this long
+    /// without receiving any bytes
+    pub idle_timeout: Option<Duration>,
+    /// Size limits; a single event larger than `stream_event` ends the stream
+    pub limits: ResponseLimits,
+}
+
+impl StreamOptions {
+    /// Abort on malformed events, no idle timeout
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the malformed-event policy
+    pub fn with_malformed_events(mut self, policy: MalformedEventPolicy) -> Self {
+        self.malformed_events = policy;
+        self
+    }
+
+    /// Fail the stream after `timeout` without data.
+    ///
+    /// `ping` events count as data, so a model thinking quietly for longer
+    /// than `timeout` does not trip it as long as the API keeps pinging.
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the size limits
+    pub fn with_limits(mut self, limits: ResponseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+}
+
+/// Heartbeat and progress counters of a [`MessageStream`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamActivity {
+    /// When the stream was opened
+    pub opened_at: Instant,
+    /// When bytes last arrived, pings included
+    pub last_activity: Instant,
+    /// When the last `ping` event arrived
+    pub last_ping: Option<Instant>,
+    /// `ping` events received
+    pub pings: u64,
+    /// Events received, pings included
+    pub events: u64,
+    /// Malformed events dropped under [`MalformedEventPolicy::Skip`]
+    pub skipped_events: usize,
+    /// Bytes received but not yet parsed into events
+    pub buffered_bytes: u64,
+    /// Most bytes buffered at once, also counted in
+    /// [`metrics`](crate::metrics) as [`BufferKind::MessageStream`]
+    pub peak_buffered_bytes: u64,
+}
+
+impl StreamActivity {
+    fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            opened_at: now,
+            last_activity: now,
+            last_ping: None,
+            pings: 0,
+            events: 0,
+            skipped_events: 0,
+            buffered_bytes: 0,
+            peak_buffered_bytes: 0,
+        }
+    }
+
+    /// Time since bytes last arrived
+    pub fn idle_for(&self) -> Duration {
+        self.last_activity.elapsed()
+    }
+}
+
+fn record(activity: &Mutex<StreamActivity>, update: impl FnOnce(&mut StreamActivity)) {
+    if let Ok(mut activity) = activity.lock() {
+        update(&mut activity);
+    }
+}
 
 /// Stream of message events from the Anthropic API
 pub struct MessageStream {
     receiver: mpsc::Receiver<Result<StreamEvent>>,
-    _handle: tokio::task::JoinHandle<()>,
+    handle: tokio::task::JoinHandle<()>,
+    /// Fires when the stream's cancellation token is cancelled
+    cancel: Option<Pin<Box<WaitForCancellationFutureOwned>>>,
+    watchdog: Option<Watchdog>,
+    /// Ended early by cancellation or the watchdog
+    ended: bool,
+    /// The request behind the stream, for [`with_auto_resume`](Self::with_auto_resume)
+    resume: Option<ResumeContext>,
+    /// Per-model concurrency slot, held until the stream is dropped
+    _permit: Option<OwnedSemaphorePermit>,
+    activity: Arc<Mutex<StreamActivity>>,
+    metrics: Option<StreamMetrics>,
+    tokens: Option<StreamTokens>,
+    /// Fingerprint of the request, when collected messages are stamped with
+    /// [`Provenance`]
+    provenance: Option<String>,
+}
+
+/// Ends a stream that has gone too long between events
+struct Watchdog {
+    idle: Duration,
+    last_event: tokio::time::Instant,
+    timer: Pin<Box<tokio::time::Sleep>>,
+}
+
+impl Watchdog {
+    fn new(idle: Duration) -> Self {
+        let now = tokio::time::Instant::now();
+        Self {
+            idle,
+            last_event: now,
+            timer: Box::pin(tokio::time::sleep_until(now + idle)),
+        }
+    }
+
+    /// Whether `idle` has passed since the last event; otherwise arms the
+    /// timer to wake the task when it would
+    fn expired(&mut self, cx: &mut Context<'_>) -> bool {
+        let due = self.last_event + self.idle;
+        if self.timer.deadline() != due {
+            self.timer.as_mut().reset(due);
+        }
+        self.timer.poll_unpin(cx).is_ready()
+    }
+}
+
+/// What a stream reports to a [`MetricsRecorder`] when it is dropped
+struct StreamMetrics {
+    recorder: Arc<dyn MetricsRecorder>,
+    model: String,
+    opened_at: Instant,
+    usage: Option<Usage>,
+}
+
+impl StreamMetrics {
+    fn observe(&mut self, event: &StreamEvent) {
+        match event {
+            StreamEvent::MessageStart { message } => {
+                self.model.clone_from(&message.model);
+                self.usage = Some(message.usage.clone());
+            }
+            StreamEvent::MessageDelta { usage, .. } => {
+                if let Some(total) = &mut self.usage {
+                    total.output_tokens = usage.output_tokens;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Drop for StreamMetrics {
+    fn drop(&mut self) {
+        self.recorder.stream(&self.model, self.opened_at.elapsed());
+        if let Some(usage) = &self.usage {
+            self.recorder.tokens(&self.model, usage);
+        }
+    }
+}
+
+/// A token rate limit reservation, settled against the stream's usage when
+/// it is dropped
+struct StreamTokens {
+    limiter: RateLimiter,
+    reservation: TokenReservation,
+    usage: Option<Usage>,
+}
+
+impl StreamTokens {
+    fn observe(&mut self, event: &StreamEvent) {
+        match event {
+            StreamEvent::MessageStart { message } => self.usage = Some(message.usage.clone()),
+            StreamEvent::MessageDelta { usage, .. } => {
+                if let Some(total) = &mut self.usage {
+                    total.output_tokens = usage.output_tokens;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Drop for StreamTokens {
+    fn drop(&mut self) {
+        match &self.usage {
+            Some(usage) => self.limiter.reconcile(self.reservation, usage),
+            None => self.limiter.release(self.reservation),
+        }
+    }
 }
 
 impl MessageStream {
     /// Create a new message stream from an HTTP response
     pub async fn new(response: reqwest::Response) -> Result<Self> {
+        Self::new_with_options(response, StreamOptions::default()).await
+    }
+
+    /// Create a message stream with the given malformed-event policy and
+    /// idle timeout
+    pub async fn new_with_options(
+        response: reqwest::Response,
+        options: StreamOptions,
+    ) -> Result<Self> {
         let status = response.status();
         if !status.is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(AnthropicError::api_error(status.as_u16(), error_text, None));
+            let retry_after =
+                HttpClient::parse_rate_limit_headers(response.headers()).recommended_delay();
+            let error_text = read_error_text(response, &options.limits)
+                .await
+                .unwrap_or_default();
+            return Err(AnthropicError::api_error(status.as_u16(), error_text, None)
+                .with_retry_after(retry_after));
         }
 
         let (sender, receiver) = mpsc::channel(100);
         let mut bytes_stream = response.bytes_stream();
-        let mut parser = EventParser::new();
+        let mut parser = EventParser::new().with_malformed_policy(options.malformed_events);
+        let activity = Arc::new(Mutex::new(StreamActivity::new()));
+        let tracker = Arc::clone(&activity);
 
-        let handle = tokio::spawn(async move {
+        let handle = tokio::spawn(instrumentation::stream(async move {
             let mut buffer = Vec::with_capacity(8192); // Pre-allocate buffer for better performance
+            let mut gauge = BufferGauge::new(BufferKind::MessageStream);
+            let mut deadline = options.idle_timeout.map(|idle| Instant::now() + idle);
+
+            loop {
+                let next = match (deadline, options.idle_timeout) {
+                    (Some(at), Some(idle)) => {
+                        match tokio::time::timeout_at(at.into(), bytes_stream.next()).await {
+                            Ok(next) => next,
+                            Err(_) => {
+                                let _ = sender.send(Err(AnthropicError::StreamIdle(idle))).await;
+                                return;
+                            }
+                        }
+                    }
+                    _ => bytes_stream.next().await,
+                };
+                let Some(chunk_result) = next else {
+                    break;
+                };
 
-            while let Some(chunk_result) = bytes_stream.next().await {
                 match chunk_result {
                     Ok(chunk) => {
+                        let now = Instant::now();
+                        record(&tracker, |activity| activity.last_activity = now);
+                        deadline = options.idle_timeout.map(|idle| now + idle);
                         buffer.extend_from_slice(&chunk);
+                        gauge.set(buffer.len());
+                        let (buffered, peak) = (buffer.len() as u64, gauge.peak());
+                        record(&tracker, |activity| {
+                            activity.buffered_bytes = buffered;
+                            activity.peak_buffered_bytes = peak;
+                        });
 
                         // Process complete lines
                         while let Some(newline_pos) = buffer.iter().position(|&b| b == b'\n') {
@@ -50,14 +307,27 @@ impl MessageStream {
                             };
                             let line_str = String::from_utf8_lossy(&line[..line_len]);
 
-                            match parser.parse_line(&line_str) {
+                            let parsed = parser.parse_line_with_len(&line_str, line.len());
+                            let skipped = parser.skipped_events();
+                            match parsed {
                                 Ok(Some(event)) => {
+                                    instrumentation::record_stream_event(&event);
+                                    let ping = matches!(event, StreamEvent::Ping);
+                                    record(&tracker, |activity| {
+                                        activity.events += 1;
+                                        activity.skipped_events = skipped;
+                                        if ping {
+                                            activity.pings += 1;
+                                            activity.last_ping = Some(now);
+                                        }
+                                    });
                                     if sender.send(Ok(event)).await.is_err() {
                                         return; // Receiver dropped, exit cleanly
                                     }
                                 }
                                 Ok(None) => {
                                     // Continue processing (comment, empty line, or partial event)
+                                    record(&tracker, |activity| activity.skipped_events = skipped);
                                 }
                                 Err(e) => {
                                     let _ = sender.send(Err(e)).await;
@@ -65,188 +335,199 @@ impl MessageStream {
                                 }
                             }
                         }
+
+                        gauge.set(buffer.len());
+                        let buffered = buffer.len() as u64;
+                        record(&tracker, |activity| activity.buffered_bytes = buffered);
+
+                        // A line still without its newline past the limit
+                        // would otherwise grow without bound
+                        let limit = options.limits.stream_event;
+                        if buffer.len() as u64 > limit {
+                            let error = AnthropicError::ResponseTooLarge {
+                                class: ResponseClass::StreamEvent,
+                                limit,
+                            };
+                            let _ = sender.send(Err(error)).await;
+                            return;
+                        }
                     }
                     Err(e) => {
-                        let error = AnthropicError::stream(format!("Stream chunk error: {}", e))
-                            .with_context("HTTP stream processing");
+                        // A stalled body hit the request's read timeout
+                        let error = if e.is_timeout() {
+                            AnthropicError::Http(e)
+                        } else {
+                            AnthropicError::stream(format!("Stream chunk error: {}", e))
+                                .with_context("HTTP stream processing")
+                        };
                         let _ = sender.send(Err(error)).await;
                         return; // Exit on stream error
                     }
                 }
             }
-        });
+        }));
 
         Ok(Self {
             receiver,
-            _handle: handle,
+            handle,
+            cancel: None,
+            watchdog: None,
+            ended: false,
+            resume: None,
+            _permit: None,
+            activity,
+            metrics: None,
+            tokens: None,
+            provenance: None,
         })
     }
 
-    /// Collect all events into a complete message response
-    pub async fn collect_message(mut self) -> Result<MessageResponse> {
-        let mut message_response = None;
-        let mut content_blocks = Vec::new();
-        let mut input_json_buffers: HashMap<usize, String> = HashMap::new();
+    /// Heartbeat and progress counters so far
+    pub fn activity(&self) -> StreamActivity {
+        self.activity
+            .lock()
+            .map(|activity| *activity)
+            .unwrap_or_else(|poisoned| *poisoned.into_inner())
+    }
 
-        while let Some(event_result) = self.next().await {
-            let event = event_result?;
+    /// When data (an event, ping or partial frame) last arrived
+    pub fn last_activity(&self) -> Instant {
+        self.activity().last_activity
+    }
 
-            match event {
-                StreamEvent::MessageStart { message } => {
-                    message_response = Some(message);
-                }
-                StreamEvent::ContentBlockStart {
-                    index,
-                    content_block,
-                } => {
-                    // Ensure we have enough space
-                    while content_blocks.len() <= index {
-                        content_blocks.push(None);
-                    }
-                    content_blocks[index] = Some(content_block);
-                }
-                StreamEvent::ContentBlockDelta { index, delta } => {
-                    if let Some(text) = delta.text {
-                        if let Some(Some(ContentBlock::Text {
-                            text: ref mut block_text,
-                            ..
-                        })) = content_blocks.get_mut(index)
-                        {
-                            block_text.push_str(&text);
-                        }
-                    }
+    /// Malformed events dropped so far under [`MalformedEventPolicy::Skip`]
+    pub fn skipped_events(&self) -> usize {
+        self.activity().skipped_events
+    }
 
-                    if let Some(thinking_delta) = delta.thinking {
-                        if let Some(Some(ContentBlock::Thinking {
-                            thinking: ref mut block_thinking,
-                            ..
-                        })) = content_blocks.get_mut(index)
-                        {
-                            block_thinking.push_str(&thinking_delta);
-                        }
-                    }
+    /// End the stream with [`AnthropicError::Cancelled`] once `token` is
+    /// cancelled. The connection is closed and events not yet read are
+    /// discarded; the stream yields nothing after the error.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(Box::pin(token.cancelled_owned()));
+        self
+    }
 
-                    if let Some(signature_delta) = delta.signature {
-                        if let Some(Some(ContentBlock::Thinking { signature, .. })) =
-                            content_blocks.get_mut(index)
-                        {
-                            signature
-                                .get_or_insert_with(String::new)
-                                .push_str(&signature_delta);
-                        }
-                    }
+    /// Fail with [`AnthropicError::StreamIdle`] once `timeout` passes without
+    /// an event, `ping`s included, instead of waiting on a stalled connection
+    /// forever. Unlike [`StreamOptions::with_idle_timeout`], partial events
+    /// do not reset the timer. The error is retryable.
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.watchdog = Some(Watchdog::new(timeout));
+        self
+    }
 
-                    if let Some(partial_json) = delta.partial_json {
-                        input_json_buffers
-                            .entry(index)
-                            .and_modify(|buffer| buffer.push_str(&partial_json))
-                            .or_insert(partial_json);
-                    }
+    /// Recover from a dropped connection by sending the request again with
+    /// the text received so far as an assistant prefill, and carry on
+    /// yielding the new request's deltas as if nothing had happened. Gives
+    /// up after [`with_max_resumes`](Self::with_max_resumes) attempts (3 by
+    /// default), yielding the error as before; a stream that merely ended
+    /// before `message_stop` then fails with a stream error instead of
+    /// ending quietly.
+    ///
+    /// Only text can be continued: a stream that drops while producing
+    /// thinking or tool calls, or after its stop reason, fails as usual.
+    /// Trailing whitespace is trimmed from the prefill as the API requires.
+    /// Streams not opened by
+    /// [`MessagesApi::create_stream`](crate::api::messages::MessagesApi::create_stream)
+    /// cannot be resumed; for them this does nothing.
+    pub fn with_auto_resume(self) -> Self {
+        self.with_max_resumes(resume::DEFAULT_MAX_RESUMES)
+    }
 
-                    if let Some(citation_delta) = delta.citation {
-                        if let Some(Some(ContentBlock::Text { citations, .. })) =
-                            content_blocks.get_mut(index)
-                        {
-                            citations.get_or_insert_with(Vec::new).push(citation_delta);
-                        }
-                    }
-                }
-                StreamEvent::MessageDelta { delta, usage } => {
-                    if let Some(ref mut message) = message_response {
-                        // Streaming usage payloads can be partial; keep the max observed values.
-                        message.usage.input_tokens =
-                            message.usage.input_tokens.max(usage.input_tokens);
-                        message.usage.output_tokens =
-                            message.usage.output_tokens.max(usage.output_tokens);
-                        message.usage.cache_creation_input_tokens = message
-                            .usage
-                            .cache_creation_input_tokens
-                            .max(usage.cache_creation_input_tokens);
-                        message.usage.cache_read_input_tokens = message
-                            .usage
-                            .cache_read_input_tokens
-                            .max(usage.cache_read_input_tokens);
-
-                        if let Some(incoming_cache_creation) = usage.cache_creation {
-                            let cache_creation = message
-                                .usage
-                                .cache_creation
-                                .get_or_insert_with(CacheCreationUsage::default);
-                            cache_creation.ephemeral_5m_input_tokens = cache_creation
-                                .ephemeral_5m_input_tokens
-                                .max(incoming_cache_creation.ephemeral_5m_input_tokens);
-                            cache_creation.ephemeral_1h_input_tokens = cache_creation
-                                .ephemeral_1h_input_tokens
-                                .max(incoming_cache_creation.ephemeral_1h_input_tokens);
-                        }
+    /// [`with_auto_resume`](Self::with_auto_resume), resuming at most
+    /// `max_resumes` times
+    pub fn with_max_resumes(mut self, max_resumes: u32) -> Self {
+        let Some(context) = self.resume.take() else {
+            return self;
+        };
+        let (sender, receiver) = mpsc::channel(100);
+        let dropped = std::mem::replace(&mut self.receiver, receiver);
+        self.handle = tokio::spawn(resume::forward(dropped, context, max_resumes, sender));
+        self
+    }
 
-                        if let Some(incoming_server_tool_use) = usage.server_tool_use {
-                            let server_tool_use = message
-                                .usage
-                                .server_tool_use
-                                .get_or_insert_with(ServerToolUsage::default);
-                            server_tool_use.web_search_requests = server_tool_use
-                                .web_search_requests
-                                .max(incoming_server_tool_use.web_search_requests);
-                        }
+    /// Remember the request the stream was opened with, so it can be resumed
+    pub(crate) fn with_resume_context(
+        mut self,
+        messages: MessagesApi,
+        request: MessageRequest,
+        options: Option<RequestOptions>,
+    ) -> Self {
+        self.resume = Some(ResumeContext {
+            messages,
+            request,
+            options,
+        });
+        self
+    }
 
-                        if usage.inference_geo.is_some() {
-                            message.usage.inference_geo = usage.inference_geo;
-                        }
-                        if usage.service_tier.is_some() {
-                            message.usage.service_tier = usage.service_tier;
-                        }
+    /// Hold a concurrency slot for the lifetime of the stream
+    pub(crate) fn with_permit(mut self, permit: Option<OwnedSemaphorePermit>) -> Self {
+        self._permit = permit;
+        self
+    }
 
-                        if let Some(stop_reason) = delta.stop_reason {
-                            message.stop_reason = Some(stop_reason);
-                        }
-                        if let Some(stop_sequence) = delta.stop_sequence {
-                            message.stop_sequence = Some(stop_sequence);
-                        }
-                    }
-                }
-                StreamEvent::MessageStop => {
-                    break;
-                }
-                StreamEvent::ContentBlockStop { index } => {
-                    if let Some(partial_json) = input_json_buffers.remove(&index) {
-                        let parsed = serde_json::from_str::<serde_json::Value>(&partial_json)
-                            .unwrap_or(serde_json::Value::String(partial_json));
-
-                        if let Some(Some(ContentBlock::ToolUse { input, .. })) =
-                            content_blocks.get_mut(index)
-                        {
-                            *input = parsed.clone();
-                        } else if let Some(Some(ContentBlock::ServerToolUse { input, .. })) =
-                            content_blocks.get_mut(index)
-                        {
-                            *input = Some(parsed.clone());
-                        } else if let Some(Some(ContentBlock::ToolResult { content, .. })) =
-                            content_blocks.get_mut(index)
-                        {
-                            *content = Some(ToolResultContent::Json(parsed));
-                        }
-                    }
-                }
-                StreamEvent::Ping => {
-                    // Keep-alive ping, ignore
-                }
-                StreamEvent::Error { error } => {
-                    return Err(AnthropicError::stream(format!("Stream error: {:?}", error))
-                        .with_context("Message streaming"));
-                }
-            }
-        }
+    /// Settle a token rate limit reservation against the stream's usage when
+    /// it is dropped
+    pub(crate) fn with_token_reservation(
+        mut self,
+        reservation: Option<(RateLimiter, TokenReservation)>,
+    ) -> Self {
+        self.tokens = reservation.map(|(limiter, reservation)| StreamTokens {
+            limiter,
+            reservation,
+            usage: None,
+        });
+        self
+    }
 
-        let mut message = message_response.ok_or_else(|| {
-            AnthropicError::stream("No message_start event received")
-                .with_context("Stream message collection")
-        })?;
+    /// Report the stream's duration and token usage to `recorder` when it
+    /// is dropped
+    pub(crate) fn with_metrics(mut self, recorder: Arc<dyn MetricsRecorder>, model: &str) -> Self {
+        let opened_at = self.activity().opened_at;
+        self.metrics = Some(StreamMetrics {
+            recorder,
+            model: model.to_string(),
+            opened_at,
+            usage: None,
+        });
+        self
+    }
 
-        // Update content with streamed content
-        message.content = content_blocks.into_iter().flatten().collect();
+    /// Stamp messages collected from the stream as generated from the
+    /// request with fingerprint `request_hash`
+    pub(crate) fn with_provenance(mut self, request_hash: Option<String>) -> Self {
+        self.provenance = request_hash;
+        self
+    }
 
-        Ok(message)
+    /// Collect all events into a complete message response
+    pub async fn collect_message(self) -> Result<MessageResponse> {
+        self.accumulate(|_| {}).await
+    }
+
+    /// Consume the stream, passing each event to `on_event` as it arrives
+    /// (e.g. to print text live), and return the complete message rebuilt by
+    /// a [`MessageAccumulator`]
+    pub async fn accumulate<F>(mut self, mut on_event: F) -> Result<MessageResponse>
+    where
+        F: FnMut(&StreamEvent),
+    {
+        let mut accumulator = MessageAccumulator::new();
+        while let Some(event) = self.next().await {
+            let event = event?;
+            on_event(&event);
+            accumulator.push(event)?;
+            if accumulator.is_stopped() {
+                break;
+            }
+        }
+        let mut response = accumulator.finish()?;
+        if let Some(request_hash) = self.provenance.take() {
+            response.provenance = Some(Provenance::from_request_hash(request_hash, &response));
+        }
+        Ok(response)
     }
 
     /// Collect only text content from the stream
@@ -257,10 +538,11 @@ impl MessageStream {
             let event = event_result?;
 
             match event {
-                StreamEvent::ContentBlockDelta { delta, .. } => {
-                    if let Some(delta_text) = delta.text {
-                        text.push_str(&delta_text);
-                    }
+                StreamEvent::ContentBlockDelta {
+                    delta: ContentBlockDelta::TextDelta { text: delta_text },
+                    ..
+                } => {
+                    text.push_str(&delta_text);
                 }
                 StreamEvent::MessageStop => {
                     break;
@@ -278,9 +560,15 @@ impl MessageStream {
         Ok(text)
     }
 
+    /// Yield each `tool_use` block as a [`ToolCall`](super::ToolCall) once
+    /// its input is complete; see [`tool_calls`](super::tool_calls)
+    pub fn tool_calls(self) -> super::ToolCallStream<Self> {
+        super::ToolCallStream::new(self)
+    }
+
     /// Check if the stream is done
     pub fn is_done(&self) -> bool {
-        self.receiver.is_closed()
+        self.ended || self.receiver.is_closed()
     }
 }
 
@@ -288,12 +576,180 @@ impl Stream for MessageStream {
     type Item = Result<StreamEvent>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        self.receiver.poll_recv(cx)
+        if self.ended {
+            return Poll::Ready(None);
+        }
+        if let Some(cancel) = &mut self.cancel {
+            if cancel.poll_unpin(cx).is_ready() {
+                return self.end(AnthropicError::Cancelled);
+            }
+        }
+        let poll = self.receiver.poll_recv(cx);
+        if let Some(watchdog) = &mut self.watchdog {
+            match &poll {
+                Poll::Ready(Some(_)) => watchdog.last_event = tokio::time::Instant::now(),
+                Poll::Pending if watchdog.expired(cx) => {
+                    let idle = watchdog.idle;
+                    return self.end(AnthropicError::StreamIdle(idle));
+                }
+                _ => {}
+            }
+        }
+        if let Poll::Ready(Some(Ok(event))) = &poll {
+            if let Some(metrics) = &mut self.metrics {
+                metrics.observe(event);
+            }
+            if let Some(tokens) = &mut self.tokens {
+                tokens.observe(event);
+            }
+        }
+        poll
+    }
+}
+
+impl MessageStream {
+    /// Stop reading and yield `error` as the last item
+    fn end(&mut self, error: AnthropicError) -> Poll<Option<Result<StreamEvent>>> {
+        self.ended = true;
+        self.cancel = None;
+        self.watchdog = None;
+        self.receiver.close();
+        self.handle.abort();
+        Poll::Ready(Some(Err(error)))
     }
 }
 
 impl futures::stream::FusedStream for MessageStream {
     fn is_terminated(&self) -> bool {
-        self.receiver.is_closed()
+        self.is_done()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+
+    /// Serve one SSE response, writing each frame after its delay
+    async fn drip_server(frames: Vec<(u64, &'static str)>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = tokio::io::AsyncReadExt::read(&mut socket, &mut request).await;
+            socket
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\nconnection: close\r\n\r\n",
+                )
+                .await
+                .unwrap();
+            for (delay_ms, frame) in frames {
+                tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                if socket.write_all(frame.as_bytes()).await.is_err() {
+                    return;
+                }
+            }
+        });
+        url
+    }
+
+    const PING: &str = "event: ping\ndata: {\"type\":\"ping\"}\n\n";
+    const DELTA: &str = "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"done\"}}\n\n";
+    const STOP: &str = "event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n";
+
+    #[tokio::test]
+    async fn test_pings_keep_idle_timeout_from_firing() {
+        let url = drip_server(vec![
+            (0, PING),
+            (120, PING),
+            (120, PING),
+            (120, PING),
+            (0, DELTA),
+            (0, STOP),
+        ])
+        .await;
+        let response = reqwest::get(url).await.unwrap();
+        let options = StreamOptions::new().with_idle_timeout(Duration::from_millis(250));
+        let mut stream = MessageStream::new_with_options(response, options)
+            .await
+            .unwrap();
+
+        let mut text = String::new();
+        while let Some(event) = stream.next().await {
+            if let StreamEvent::ContentBlockDelta { delta, .. } = event.unwrap() {
+                text.push_str(delta.as_text().unwrap_or_default());
+            }
+        }
+        assert_eq!(text, "done");
+
+        let activity = stream.activity();
+        assert_eq!(activity.pings, 4);
+        assert_eq!(activity.events, 6);
+        assert!(activity.last_ping.unwrap() <= activity.last_activity);
+        assert!(activity.last_activity >= activity.opened_at + Duration::from_millis(300));
+        assert!(activity.peak_buffered_bytes >= PING.len() as u64);
+        assert_eq!(activity.buffered_bytes, 0);
+    }
+
+    #[tokio::test]
+    async fn test_idle_timeout_ends_silent_stream() {
+        let url = drip_server(vec![(0, PING), (1_000, STOP)]).await;
+        let response = reqwest::get(url).await.unwrap();
+        let options = StreamOptions::new().with_idle_timeout(Duration::from_millis(100));
+        let mut stream = MessageStream::new_with_options(response, options)
+            .await
+            .unwrap();
+
+        assert!(matches!(stream.next().await, Some(Ok(StreamEvent::Ping))));
+        assert!(matches!(
+            stream.next().await,
+            Some(Err(AnthropicError::StreamIdle(idle))) if idle == Duration::from_millis(100)
+        ));
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_watchdog_ends_stalled_stream() {
+        let url = drip_server(vec![(0, PING), (60, PING), (60, PING), (2_000, STOP)]).await;
+        let response = reqwest::get(url).await.unwrap();
+        let idle = Duration::from_millis(150);
+        let mut stream = MessageStream::new(response)
+            .await
+            .unwrap()
+            .with_idle_timeout(idle);
+
+        for _ in 0..3 {
+            assert!(matches!(stream.next().await, Some(Ok(StreamEvent::Ping))));
+        }
+        let error = stream.next().await.unwrap().unwrap_err();
+        assert!(matches!(error, AnthropicError::StreamIdle(d) if d == idle));
+        assert!(error.is_retryable());
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_ends_stream() {
+        let url = drip_server(vec![(0, PING), (2_000, STOP)]).await;
+        let response = reqwest::get(url).await.unwrap();
+        let token = CancellationToken::new();
+        let mut stream = MessageStream::new(response)
+            .await
+            .unwrap()
+            .with_cancellation(token.clone());
+
+        assert!(matches!(stream.next().await, Some(Ok(StreamEvent::Ping))));
+        let cancel = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            token.cancel();
+        });
+        assert!(matches!(
+            stream.next().await,
+            Some(Err(AnthropicError::Cancelled))
+        ));
+        assert!(stream.next().await.is_none());
+        assert!(stream.is_done());
+        cancel.await.unwrap();
     }
 }
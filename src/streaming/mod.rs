@@ -1,10 +1,25 @@
 //! Streaming support for real-time API responses
 
+pub mod accumulator;
 pub mod event_parser;
+pub mod fanout;
 pub mod message_stream;
+pub mod raw;
+mod resume;
 pub mod session_event_stream;
+pub mod stream_pool;
+pub mod tool_calls;
+#[cfg(feature = "websocket")]
+pub mod websocket;
 
 // Re-export main streaming types
-pub use event_parser::{EventParser, StreamEvent};
-pub use message_stream::MessageStream;
+pub use accumulator::MessageAccumulator;
+pub use event_parser::{EventParser, MalformedEventPolicy, StreamEvent};
+pub use fanout::{Overflow, SinkPolicy, SinkState, SinkStats, StreamFanout};
+pub use message_stream::{MessageStream, StreamActivity, StreamOptions};
+pub use raw::RawEventStream;
 pub use session_event_stream::SessionEventStream;
+pub use stream_pool::{PoolEvent, PoolEventKind, StreamPool};
+pub use tool_calls::{PartialToolCall, ToolCall, ToolCallStream};
+#[cfg(feature = "websocket")]
+pub use websocket::{forward_to_websocket, DeltaKind, WsEnvelope};
@@ -0,0 +1,122 @@
+//! Unparsed message streams for proxies
+//!
+//! A backend that relays Claude's server-sent events to a browser has no use
+//! for parsed events: it only re-serializes them. [`RawEventStream`] hands
+//! over the response body's bytes exactly as they arrive, after the request
+//! went through authentication, connection retries and rate limiting like
+//! any other, so they can be written straight into the proxy's own
+//! `text/event-stream` response.
+//!
+//! ```rust,no_run
+//! use futures::StreamExt;
+//! use threatflux_anthropic_sdk::{models::MessageRequest, Client};
+//!
+//! # async fn example() -> threatflux_anthropic_sdk::Result<()> {
+//! let client = Client::from_env()?;
+//! let request = MessageRequest::new().max_tokens(256).add_user_message("Hello!");
+//! let mut sse = client.messages().create_stream_raw(request, None).await?;
+//! println!("request id: {:?}", sse.request_id());
+//! while let Some(chunk) = sse.next().await {
+//!     let chunk = chunk?;
+//!     // write `chunk` to the browser's response body
+//!     # let _ = chunk;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Chunk boundaries are wherever the network put them, not event
+//! boundaries. `error` events the API sends mid-stream are passed through
+//! like any other.
+
+use crate::{
+    error::{AnthropicError, Result},
+    utils::http::{read_error_text, HttpClient, ResponseLimits},
+};
+use bytes::Bytes;
+use futures::{stream::BoxStream, Stream, StreamExt};
+use reqwest::header::HeaderMap;
+use std::{
+    fmt,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::sync::OwnedSemaphorePermit;
+use tokio_util::sync::CancellationToken;
+
+/// The body of a streaming Messages response, unparsed
+pub struct RawEventStream {
+    headers: HeaderMap,
+    body: BoxStream<'static, Result<Bytes>>,
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl RawEventStream {
+    /// Wrap a streaming response, failing with the API's error if it has an
+    /// error status
+    pub async fn new(response: reqwest::Response, limits: &ResponseLimits) -> Result<Self> {
+        let status = response.status();
+        if !status.is_success() {
+            let retry_after =
+                HttpClient::parse_rate_limit_headers(response.headers()).recommended_delay();
+            let error_text = read_error_text(response, limits).await.unwrap_or_default();
+            return Err(AnthropicError::api_error(status.as_u16(), error_text, None)
+                .with_retry_after(retry_after));
+        }
+        Ok(Self {
+            headers: response.headers().clone(),
+            body: response
+                .bytes_stream()
+                .map(|chunk| chunk.map_err(AnthropicError::from))
+                .boxed(),
+            _permit: None,
+        })
+    }
+
+    /// Hold a concurrency permit until the stream is dropped
+    pub(crate) fn with_permit(mut self, permit: Option<OwnedSemaphorePermit>) -> Self {
+        self._permit = permit;
+        self
+    }
+
+    /// End the stream quietly once `token` is cancelled
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.body = self.body.take_until(token.cancelled_owned()).boxed();
+        self
+    }
+
+    /// Response headers, e.g. to forward `request-id` or rate limit headers
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// The API's `request-id` for this response
+    pub fn request_id(&self) -> Option<&str> {
+        self.headers
+            .get("request-id")
+            .and_then(|value| value.to_str().ok())
+    }
+
+    /// `content-type` of the response, normally `text/event-stream`
+    pub fn content_type(&self) -> Option<&str> {
+        self.headers
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+    }
+}
+
+impl Stream for RawEventStream {
+    type Item = Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.body.poll_next_unpin(cx)
+    }
+}
+
+impl fmt::Debug for RawEventStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RawEventStream")
+            .field("request_id", &self.request_id())
+            .finish_non_exhaustive()
+    }
+}
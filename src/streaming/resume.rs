@@ -0,0 +1,468 @@
+//! Re-issuing a dropped message stream with its partial output as prefill
+//!
+//! See [`MessageStream::with_auto_resume`](super::MessageStream::with_auto_resume).
+//! The events of the resumed request are renumbered to carry on where the
+//! dropped stream stopped: its `message_start` is swallowed, and its first
+//! text block continues the block that was cut off, so the consumer sees a
+//! single uninterrupted message. The resumed request only gets the part of
+//! `max_tokens` the dropped segments left over, and the usage it reports is
+//! added to theirs.
+
+use super::{
+    accumulator::{merge_usage, MessageAccumulator},
+    MessageStream,
+};
+use crate::{
+    api::messages::MessagesApi,
+    error::{AnthropicError, Result},
+    models::{
+        common::{CacheCreationUsage, ContentBlock, Role, ServerToolUsage, Usage},
+        message::{ContentBlockDelta, Message, MessageRequest, StreamEvent},
+    },
+    types::RequestOptions,
+    utils::text::estimate_tokens,
+};
+use futures::StreamExt;
+use tokio::sync::mpsc;
+
+/// Resumes a stream gets by default
+pub(crate) const DEFAULT_MAX_RESUMES: u32 = 3;
+
+/// The request a stream was opened with, to send again
+#[derive(Clone)]
+pub(crate) struct ResumeContext {
+    pub(crate) messages: MessagesApi,
+    pub(crate) request: MessageRequest,
+    pub(crate) options: Option<RequestOptions>,
+}
+
+/// Forward `receiver`'s events to `sender`, re-issuing the request when the
+/// stream fails with a transport error or ends before `message_stop`, up to
+/// `max_resumes` times
+pub(crate) async fn forward(
+    mut receiver: mpsc::Receiver<Result<StreamEvent>>,
+    context: ResumeContext,
+    max_resumes: u32,
+    sender: mpsc::Sender<Result<StreamEvent>>,
+) {
+    let mut state = ResumeState::default();
+    let mut resumed: Option<MessageStream> = None;
+    let mut resumes = 0;
+    loop {
+        let next = match &mut resumed {
+            Some(stream) => stream.next().await,
+            None => receiver.recv().await,
+        };
+        let mut error = match next {
+            None if state.stopped => return,
+            // The connection closed cleanly, but mid-message
+            None => AnthropicError::stream("Stream ended before message_stop"),
+            Some(Ok(event)) => {
+                if let Some(event) = state.translate(event) {
+                    state.observe(&event);
+                    if sender.send(Ok(event)).await.is_err() {
+                        return;
+                    }
+                }
+                continue;
+            }
+            Some(Err(error)) => error,
+        };
+
+        resumed = loop {
+            let request = match state.resume_request(&context.request) {
+                Some(request) if resumes < max_resumes && is_transient(&error) => request,
+                _ => {
+                    let _ = sender.send(Err(error)).await;
+                    return;
+                }
+            };
+            resumes += 1;
+            tracing::debug!(
+                "Message stream dropped ({}); resuming ({}/{})",
+                error,
+                resumes,
+                max_resumes
+            );
+            match context
+                .messages
+                .create_stream(request, context.options.clone())
+                .await
+            {
+                Ok(stream) => {
+                    state.begin_segment();
+                    break Some(stream);
+                }
+                Err(err) => error = err,
+            }
+        };
+    }
+}
+
+/// Failures of the connection rather than of the request or response
+fn is_transient(error: &AnthropicError) -> bool {
+    matches!(
+        error,
+        AnthropicError::Stream(_)
+            | AnthropicError::Network(_)
+            | AnthropicError::Http(_)
+            | AnthropicError::Timeout(_)
+            | AnthropicError::StreamIdle(_)
+    ) || error.is_retryable()
+}
+
+/// What has been forwarded so far, and how to renumber the current segment
+#[derive(Default)]
+struct ResumeState {
+    accumulator: MessageAccumulator,
+    /// Index of the block still streaming when the stream dropped
+    open: Option<usize>,
+    /// One past the highest block index forwarded
+    next_index: usize,
+    /// Whether the message has a stop reason, after which nothing is resumed
+    stopped: bool,
+    /// Renumbering of the current segment
+    segment: Segment,
+    /// Usage of the segments before the current one, summed
+    carried: Usage,
+    /// Usage the current segment has reported so far
+    segment_usage: Usage,
+}
+
+#[derive(Default)]
+struct Segment {
+    /// Drop the resumed request's `message_start`
+    skip_start: bool,
+    /// Added to the resumed request's block indices
+    offset: usize,
+    /// The resumed request's block 0 continues block `offset`
+    continues_open: bool,
+    /// Strip leading whitespace from the continuation; trailing whitespace
+    /// was trimmed from the prefill and the model usually repeats it
+    trim_leading: bool,
+}
+
+impl ResumeState {
+    fn observe(&mut self, event: &StreamEvent) {
+        // The consumer sees stream error events; they change nothing here
+        let _ = self.accumulator.push(event.clone());
+        match event {
+            StreamEvent::ContentBlockStart { index, .. } => {
+                self.open = Some(*index);
+                self.next_index = self.next_index.max(index + 1);
+            }
+            StreamEvent::ContentBlockStop { index } if self.open == Some(*index) => {
+                self.open = None;
+            }
+            StreamEvent::MessageDelta { delta, .. } if delta.stop_reason.is_some() => {
+                self.stopped = true;
+            }
+            StreamEvent::MessageStop => self.stopped = true,
+            _ => {}
+        }
+    }
+
+    /// `original` with the output so far appended as an assistant prefill
+    /// and `max_tokens` reduced by the output already generated, or `None` if
+    /// that output cannot be continued
+    fn resume_request(&mut self, original: &MessageRequest) -> Option<MessageRequest> {
+        if self.stopped {
+            return None;
+        }
+        let Some(partial) = self.accumulator.snapshot() else {
+            // Nothing reached the consumer yet: start over
+            self.segment = Segment::default();
+            return Some(original.clone());
+        };
+
+        let mut prefill = Vec::with_capacity(partial.content.len());
+        for block in &partial.content {
+            match block {
+                ContentBlock::Text { text, .. } => prefill.push(text.clone()),
+                _ => return None,
+            }
+        }
+        // The API rejects a prefill ending in whitespace
+        let mut trimmed = false;
+        if let Some(last) = prefill.last_mut() {
+            let kept = last.trim_end().len();
+            trimmed = kept < last.len();
+            last.truncate(kept);
+        }
+        prefill.retain(|text| !text.is_empty());
+
+        // Streams usually report output tokens only at the end, so fall back
+        // to an estimate from the text received
+        let reported = self
+            .carried
+            .output_tokens
+            .saturating_add(self.segment_usage.output_tokens);
+        let generated = reported.max(estimate_tokens(&partial.text()));
+        let max_tokens = original
+            .max_tokens
+            .checked_sub(generated)
+            .filter(|&left| left > 0)?;
+
+        let continues_open = self
+            .open
+            .is_some_and(|open| open + 1 == self.next_index && partial.content.len() == open + 1);
+        self.segment = Segment {
+            skip_start: true,
+            offset: if continues_open {
+                self.next_index - 1
+            } else {
+                self.next_index
+            },
+            continues_open,
+            trim_leading: continues_open && trimmed,
+        };
+
+        let mut request = original.clone();
+        request.max_tokens = max_tokens;
+        let blocks: Vec<ContentBlock> = prefill.into_iter().map(ContentBlock::text).collect();
+        match request.messages.last_mut() {
+            _ if blocks.is_empty() => {}
+            Some(last) if last.role == Role::Assistant => last.content.extend(blocks),
+            _ => request.messages.push(Message::new(Role::Assistant, blocks)),
+        }
+        Some(request)
+    }
+
+    /// The resumed request's stream is open
+    fn begin_segment(&mut self) {
+        self.open = None;
+        let dropped = std::mem::take(&mut self.segment_usage);
+        self.carried = add_usage(&self.carried, &dropped);
+    }
+
+    /// Renumber an event of the current segment, `None` to drop it
+    fn translate(&mut self, event: StreamEvent) -> Option<StreamEvent> {
+        let segment = &mut self.segment;
+        Some(match event {
+            StreamEvent::MessageStart { mut message } => {
+                merge_usage(&mut self.segment_usage, message.usage);
+                if segment.skip_start {
+                    return None;
+                }
+                message.usage = add_usage(&self.carried, &self.segment_usage);
+                StreamEvent::MessageStart { message }
+            }
+            StreamEvent::MessageDelta { delta, usage } => {
+                merge_usage(&mut self.segment_usage, usage);
+                StreamEvent::MessageDelta {
+                    delta,
+                    usage: add_usage(&self.carried, &self.segment_usage),
+                }
+            }
+            StreamEvent::ContentBlockStart { index: 0, .. } if segment.continues_open => {
+                self.open = Some(segment.offset);
+                return None;
+            }
+            StreamEvent::ContentBlockStart {
+                index,
+                content_block,
+            } => StreamEvent::ContentBlockStart {
+                index: index + segment.offset,
+                content_block,
+            },
+            StreamEvent::ContentBlockDelta { index, mut delta } => {
+                if index == 0 && segment.trim_leading {
+                    if let ContentBlockDelta::TextDelta { text } = &mut delta {
+                        let rest = text.trim_start();
+                        if rest.is_empty() {
+                            return None;
+                        }
+                        *text = rest.to_string();
+                        segment.trim_leading = false;
+                    }
+                }
+                StreamEvent::ContentBlockDelta {
+                    index: index + segment.offset,
+                    delta,
+                }
+            }
+            StreamEvent::ContentBlockStop { index } => StreamEvent::ContentBlockStop {
+                index: index + segment.offset,
+            },
+            event => event,
+        })
+    }
+}
+
+/// Usage of two requests together
+fn add_usage(a: &Usage, b: &Usage) -> Usage {
+    let cache_creation = match (&a.cache_creation, &b.cache_creation) {
+        (None, None) => None,
+        (a, b) => {
+            let (a, b) = (a.clone().unwrap_or_default(), b.clone().unwrap_or_default());
+            Some(CacheCreationUsage {
+                ephemeral_5m_input_tokens: a.ephemeral_5m_input_tokens
+                    + b.ephemeral_5m_input_tokens,
+                ephemeral_1h_input_tokens: a.ephemeral_1h_input_tokens
+                    + b.ephemeral_1h_input_tokens,
+            })
+        }
+    };
+    let server_tool_use = match (&a.server_tool_use, &b.server_tool_use) {
+        (None, None) => None,
+        (a, b) => Some(ServerToolUsage {
+            web_search_requests: a.as_ref().map_or(0, |u| u.web_search_requests)
+                + b.as_ref().map_or(0, |u| u.web_search_requests),
+        }),
+    };
+    Usage {
+        input_tokens: a.input_tokens + b.input_tokens,
+        output_tokens: a.output_tokens + b.output_tokens,
+        cache_creation_input_tokens: a.cache_creation_input_tokens + b.cache_creation_input_tokens,
+        cache_read_input_tokens: a.cache_read_input_tokens + b.cache_read_input_tokens,
+        cache_creation,
+        server_tool_use,
+        inference_geo: b.inference_geo.clone().or_else(|| a.inference_geo.clone()),
+        service_tier: b.service_tier.clone().or_else(|| a.service_tier.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::message::MessageResponse;
+
+    fn start() -> StreamEvent {
+        let message: MessageResponse = serde_json::from_value(serde_json::json!({
+            "id": "msg_1", "type": "message", "role": "assistant", "content": [],
+            "model": "claude-haiku-4-5", "stop_reason": null, "stop_sequence": null,
+            "usage": {"input_tokens": 12, "output_tokens": 1}
+        }))
+        .unwrap();
+        StreamEvent::MessageStart { message }
+    }
+
+    fn delta(index: usize, text: &str) -> StreamEvent {
+        StreamEvent::ContentBlockDelta {
+            index,
+            delta: ContentBlockDelta::TextDelta {
+                text: text.to_string(),
+            },
+        }
+    }
+
+    fn feed(state: &mut ResumeState, events: Vec<StreamEvent>) -> Vec<StreamEvent> {
+        events
+            .into_iter()
+            .filter_map(|event| {
+                let event = state.translate(event)?;
+                state.observe(&event);
+                Some(event)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_resume_continues_open_text_block() {
+        let mut state = ResumeState::default();
+        let block = || StreamEvent::ContentBlockStart {
+            index: 0,
+            content_block: ContentBlock::text(""),
+        };
+        feed(&mut state, vec![start(), block(), delta(0, "Once upon ")]);
+
+        let original = MessageRequest::new().add_user_message("Tell a story");
+        let request = state.resume_request(&original).unwrap();
+        assert_eq!(request.messages.len(), 2);
+        assert_eq!(request.messages[1].role, Role::Assistant);
+        assert_eq!(
+            request.messages[1].content,
+            vec![ContentBlock::text("Once upon")]
+        );
+        state.begin_segment();
+
+        let forwarded = feed(
+            &mut state,
+            vec![
+                start(),
+                block(),
+                delta(0, " a time"),
+                StreamEvent::ContentBlockStop { index: 0 },
+            ],
+        );
+        assert_eq!(forwarded.len(), 2);
+        assert!(matches!(
+            &forwarded[0],
+            StreamEvent::ContentBlockDelta { index: 0, delta: ContentBlockDelta::TextDelta { text } } if text == "a time"
+        ));
+        assert_eq!(
+            state.accumulator.snapshot().unwrap().text(),
+            "Once upon a time"
+        );
+    }
+
+    #[test]
+    fn test_resume_spends_max_tokens_and_sums_usage() {
+        let mut state = ResumeState::default();
+        let block = || StreamEvent::ContentBlockStart {
+            index: 0,
+            content_block: ContentBlock::text(""),
+        };
+        let stop = |output_tokens| StreamEvent::MessageDelta {
+            delta: serde_json::from_value(serde_json::json!({"stop_reason": "end_turn"})).unwrap(),
+            usage: Usage::new(0, output_tokens),
+        };
+        // 40 characters, about 10 tokens
+        feed(
+            &mut state,
+            vec![start(), block(), delta(0, &"word ".repeat(8))],
+        );
+
+        let original = MessageRequest::new()
+            .add_user_message("Tell a story")
+            .max_tokens(100);
+        let request = state.resume_request(&original).unwrap();
+        assert_eq!(request.max_tokens, 90);
+        state.begin_segment();
+
+        let forwarded = feed(&mut state, vec![start(), block(), delta(0, "end"), stop(5)]);
+        let Some(StreamEvent::MessageDelta { usage, .. }) = forwarded.last() else {
+            panic!("expected message_delta, got {:?}", forwarded);
+        };
+        assert_eq!(usage.input_tokens, 24);
+        assert_eq!(usage.output_tokens, 6);
+        let message = state.accumulator.snapshot().unwrap();
+        assert_eq!(message.usage.input_tokens, 24);
+        assert_eq!(message.usage.output_tokens, 6);
+
+        // Nothing left of the budget: the stream cannot be resumed
+        let mut state = ResumeState::default();
+        feed(
+            &mut state,
+            vec![start(), block(), delta(0, &"word ".repeat(8))],
+        );
+        assert!(state
+            .resume_request(&original.clone().max_tokens(10))
+            .is_none());
+    }
+
+    #[test]
+    fn test_resume_refuses_tool_calls_and_finished_messages() {
+        let mut state = ResumeState::default();
+        feed(
+            &mut state,
+            vec![
+                start(),
+                StreamEvent::ContentBlockStart {
+                    index: 0,
+                    content_block: ContentBlock::ToolUse {
+                        id: "toolu_1".to_string(),
+                        name: "lookup".to_string(),
+                        input: serde_json::json!({}),
+                    },
+                },
+            ],
+        );
+        assert!(state.resume_request(&MessageRequest::new()).is_none());
+
+        let mut state = ResumeState::default();
+        assert!(state.resume_request(&MessageRequest::new()).is_some());
+        feed(&mut state, vec![start(), StreamEvent::MessageStop]);
+        assert!(state.resume_request(&MessageRequest::new()).is_none());
+    }
+}
@@ -8,7 +8,9 @@
 
 use crate::{
     error::{AnthropicError, Result},
+    metrics::{BufferGauge, BufferKind},
     models::managed_agents::session_event::SessionEvent,
+    utils::http::{read_error_text, ResponseLimits},
 };
 use futures::{Stream, StreamExt};
 use std::pin::Pin;
@@ -26,7 +28,9 @@ impl SessionEventStream {
     pub async fn new(response: reqwest::Response) -> Result<Self> {
         let status = response.status();
         if !status.is_success() {
-            let error_text = response.text().await.unwrap_or_default();
+            let error_text = read_error_text(response, &ResponseLimits::default())
+                .await
+                .unwrap_or_default();
             return Err(AnthropicError::api_error(status.as_u16(), error_text, None));
         }
 
@@ -53,17 +57,25 @@ where
     B: AsRef<[u8]> + Send + 'static,
 {
     let mut buffer = String::new();
+    let mut gauge = BufferGauge::new(BufferKind::SessionStream);
     while let Some(chunk_result) = bytes_stream.next().await {
         match chunk_result {
             Ok(chunk) => {
                 buffer.push_str(&String::from_utf8_lossy(chunk.as_ref()));
+                gauge.set(buffer.len());
                 if !drain_frames(&mut buffer, &sender).await {
                     return; // Receiver dropped or parse error — stop.
                 }
+                gauge.set(buffer.len());
             }
             Err(e) => {
-                let error = AnthropicError::stream(format!("Stream chunk error: {}", e))
-                    .with_context("Session event stream processing");
+                // A stalled body hit the request's read timeout
+                let error = if e.is_timeout() {
+                    AnthropicError::Http(e)
+                } else {
+                    AnthropicError::stream(format!("Stream chunk error: {}", e))
+                        .with_context("Session event stream processing")
+                };
                 let _ = sender.send(Err(error)).await;
                 return;
             }
@@ -0,0 +1,340 @@
+//! Concurrent streaming sessions with a shared concurrency limit
+
+use crate::{
+    client::Client,
+    error::{AnthropicError, Result},
+    models::message::{MessageRequest, StreamEvent},
+    types::RequestOptions,
+};
+use futures::{Stream, StreamExt};
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::{
+    sync::{mpsc, Semaphore},
+    time::Instant,
+};
+
+/// What happened to one pooled stream.
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum PoolEventKind {
+    /// The stream was opened and events will follow
+    Started,
+    /// A server-sent event
+    Event(StreamEvent),
+    /// Opening the stream was rate limited; the whole pool backs off before
+    /// this attempt is retried
+    RateLimited {
+        /// Retry attempt about to be made (1-based)
+        attempt: u32,
+        /// Back-off before the retry
+        retry_in: Duration,
+    },
+    /// The stream finished normally
+    Completed,
+    /// The stream could not be opened or broke off
+    Failed(AnthropicError),
+}
+
+impl PoolEventKind {
+    /// Whether this is the last event for its stream
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Self::Completed | Self::Failed(_))
+    }
+}
+
+/// An event from a [`StreamPool`], tagged with the id of its request.
+#[derive(Debug)]
+pub struct PoolEvent {
+    /// Id returned by [`StreamPool::submit`]
+    pub id: String,
+    /// The event
+    pub kind: PoolEventKind,
+}
+
+/// Runs many streaming message requests with at most `max_concurrent` open at
+/// once, queuing the rest, and multiplexes their events into one stream.
+///
+/// When opening a stream is rate limited (HTTP 429), every queued request
+/// waits out the back-off before trying again, so the pool slows down as a
+/// whole instead of each request hammering the API independently. Stream
+/// opens are not retried by the client itself, so these are the only retries
+/// a rate-limited open gets.
+///
+/// ```rust,no_run
+/// use futures::StreamExt;
+/// use threatflux_anthropic_sdk::{
+///     models::MessageRequest,
+///     streaming::{PoolEventKind, StreamPool},
+///     Client,
+/// };
+///
+/// # async fn example() -> threatflux_anthropic_sdk::Result<()> {
+/// let client = Client::from_env()?;
+/// let mut pool = StreamPool::new(client, 4);
+/// for prompt in ["Write a haiku", "Write a limerick"] {
+///     pool.submit(MessageRequest::new().add_user_message(prompt), None);
+/// }
+///
+/// while let Some(event) = pool.next().await {
+///     if let PoolEventKind::Completed = event.kind {
+///         println!("{} done", event.id);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The pool stream ends once every submitted request has reached a terminal
+/// event; submitting more requests afterwards makes it yield again.
+pub struct StreamPool {
+    shared: Arc<Shared>,
+    sender: mpsc::UnboundedSender<PoolEvent>,
+    receiver: mpsc::UnboundedReceiver<PoolEvent>,
+    next_id: AtomicU64,
+    submitted: AtomicU64,
+    finished: u64,
+    retries: RateLimitRetries,
+}
+
+struct Shared {
+    client: Client,
+    permits: Semaphore,
+    max_concurrent: usize,
+    paused_until: Mutex<Option<Instant>>,
+}
+
+/// Retry settings captured by each submission
+#[derive(Debug, Clone, Copy)]
+struct RateLimitRetries {
+    max_retries: u32,
+    backoff: Duration,
+}
+
+impl RateLimitRetries {
+    /// Back-off before retry `attempt` (1-based): the server's `retry-after`
+    /// if it sent one, else the initial back-off doubled per attempt
+    fn delay(&self, error: &AnthropicError, attempt: u32) -> Duration {
+        error
+            .retry_after()
+            .unwrap_or_else(|| self.backoff * 2u32.saturating_pow(attempt - 1))
+    }
+}
+
+impl Shared {
+    async fn wait_for_backoff(&self) {
+        loop {
+            let until = *self.paused_until.lock().unwrap();
+            match until {
+                Some(until) if until > Instant::now() => tokio::time::sleep_until(until).await,
+                _ => return,
+            }
+        }
+    }
+
+    fn back_off(&self, delay: Duration) {
+        let until = Instant::now() + delay;
+        let mut paused = self.paused_until.lock().unwrap();
+        if paused.is_none_or(|current| current < until) {
+            *paused = Some(until);
+        }
+    }
+}
+
+impl StreamPool {
+    /// Pool allowing `max_concurrent` open streams (at least one)
+    pub fn new(client: Client, max_concurrent: usize) -> Self {
+        let max_concurrent = max_concurrent.max(1);
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let config = client.config();
+        let retries = RateLimitRetries {
+            max_retries: config
+                .retry_policy
+                .as_ref()
+                .map_or(config.max_retries, |policy| policy.max_retries),
+            backoff: Duration::from_secs(1),
+        };
+        Self {
+            shared: Arc::new(Shared {
+                client,
+                permits: Semaphore::new(max_concurrent),
+                max_concurrent,
+                paused_until: Mutex::new(None),
+            }),
+            sender,
+            receiver,
+            next_id: AtomicU64::new(0),
+            submitted: AtomicU64::new(0),
+            finished: 0,
+            retries,
+        }
+    }
+
+    /// Set how often a rate-limited open is retried (default: the client's
+    /// retry budget) and the initial back-off, doubled on each attempt
+    /// (default 1s). A `retry-after` sent with the 429 takes precedence over
+    /// the back-off.
+    ///
+    /// Applies to requests submitted afterwards.
+    pub fn with_rate_limit_retries(mut self, max_retries: u32, backoff: Duration) -> Self {
+        self.retries = RateLimitRetries {
+            max_retries,
+            backoff,
+        };
+        self
+    }
+
+    /// Queue a streaming request, returning its generated id (`stream-N`)
+    pub fn submit(&self, request: MessageRequest, options: Option<RequestOptions>) -> String {
+        let id = format!("stream-{}", self.next_id.fetch_add(1, Ordering::Relaxed));
+        self.submit_with_id(id.clone(), request, options);
+        id
+    }
+
+    /// Queue a streaming request under a caller-chosen id
+    pub fn submit_with_id(
+        &self,
+        id: impl Into<String>,
+        request: MessageRequest,
+        options: Option<RequestOptions>,
+    ) {
+        self.submitted.fetch_add(1, Ordering::Relaxed);
+        tokio::spawn(run_stream(
+            self.shared.clone(),
+            id.into(),
+            request,
+            options,
+            self.retries,
+            self.sender.clone(),
+        ));
+    }
+
+    /// Number of streams currently open
+    pub fn active(&self) -> usize {
+        self.shared.max_concurrent - self.shared.permits.available_permits()
+    }
+
+    /// Number of submitted requests that have not reached a terminal event
+    /// yet (open, queued or backing off)
+    pub fn pending(&self) -> u64 {
+        self.submitted.load(Ordering::Relaxed) - self.finished
+    }
+
+    /// Drain the pool, returning each request's concatenated text (or error)
+    /// in completion order
+    pub async fn collect_text(mut self) -> Vec<(String, Result<String>)> {
+        let mut texts: HashMap<String, String> = HashMap::new();
+        let mut results = Vec::new();
+        while let Some(event) = self.next().await {
+            match event.kind {
+                PoolEventKind::Event(StreamEvent::ContentBlockDelta { delta, .. }) => {
+                    if let Some(text) = delta.as_text() {
+                        texts.entry(event.id).or_default().push_str(text);
+                    }
+                }
+                PoolEventKind::Completed => {
+                    let text = texts.remove(&event.id).unwrap_or_default();
+                    results.push((event.id, Ok(text)));
+                }
+                PoolEventKind::Failed(error) => {
+                    texts.remove(&event.id);
+                    results.push((event.id, Err(error)));
+                }
+                _ => {}
+            }
+        }
+        results
+    }
+}
+
+impl Stream for StreamPool {
+    type Item = PoolEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.pending() == 0 {
+            return Poll::Ready(None);
+        }
+        match self.receiver.poll_recv(cx) {
+            Poll::Ready(Some(event)) => {
+                if event.kind.is_terminal() {
+                    self.finished += 1;
+                }
+                Poll::Ready(Some(event))
+            }
+            // The pool holds a sender, so the channel never closes.
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+fn is_rate_limited(error: &AnthropicError) -> bool {
+    matches!(error, AnthropicError::RateLimit(_)) || error.status_code() == Some(429)
+}
+
+async fn run_stream(
+    shared: Arc<Shared>,
+    id: String,
+    request: MessageRequest,
+    options: Option<RequestOptions>,
+    retries: RateLimitRetries,
+    events: mpsc::UnboundedSender<PoolEvent>,
+) {
+    let emit = |kind| {
+        let _ = events.send(PoolEvent {
+            id: id.clone(),
+            kind,
+        });
+    };
+
+    let _permit = shared.permits.acquire().await.expect("semaphore closed");
+    let mut attempt = 0;
+    let mut stream = loop {
+        shared.wait_for_backoff().await;
+        let result = shared
+            .client
+            .messages()
+            .create_stream(request.clone(), options.clone())
+            .await;
+        match result {
+            Ok(stream) => break stream,
+            Err(error) if is_rate_limited(&error) && attempt < retries.max_retries => {
+                attempt += 1;
+                let retry_in = retries.delay(&error, attempt);
+                shared.back_off(retry_in);
+                emit(PoolEventKind::RateLimited { attempt, retry_in });
+            }
+            Err(error) => {
+                emit(PoolEventKind::Failed(error));
+                return;
+            }
+        }
+    };
+
+    emit(PoolEventKind::Started);
+    while let Some(event) = stream.next().await {
+        match event {
+            Ok(StreamEvent::Error { error }) => {
+                emit(PoolEventKind::Failed(AnthropicError::stream(format!(
+                    "Stream error: {:?}",
+                    error
+                ))));
+                return;
+            }
+            Ok(event) => emit(PoolEventKind::Event(event)),
+            Err(error) => {
+                emit(PoolEventKind::Failed(error));
+                return;
+            }
+        }
+    }
+    emit(PoolEventKind::Completed);
+}
@@ -0,0 +1,343 @@
+//! Tool calls assembled from a message stream
+//!
+//! A streamed `tool_use` block carries its input as `input_json_delta`
+//! fragments that are only valid JSON once the block ends. [`ToolCallStream`]
+//! buffers the fragments per content block and yields a [`ToolCall`] as each
+//! block stops, so tools can start running before the message is over:
+//!
+//! ```rust,no_run
+//! use futures::StreamExt;
+//! use threatflux_anthropic_sdk::{models::MessageRequest, Client};
+//!
+//! # async fn example() -> threatflux_anthropic_sdk::Result<()> {
+//! let client = Client::from_env()?;
+//! let request = MessageRequest::new()
+//!     .max_tokens(1024)
+//!     .add_user_message("What's the weather in Paris and in Rome?");
+//! let mut calls = client
+//!     .messages()
+//!     .create_stream(request, None)
+//!     .await?
+//!     .tool_calls()
+//!     .on_partial(|call| {
+//!         // Best-effort view of the input so far, for progressive UIs
+//!         println!("{} {:?}", call.name, call.input());
+//!     });
+//! while let Some(call) = calls.next().await {
+//!     let call = call?;
+//!     println!("run {} with {}", call.name, call.input);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    error::{AnthropicError, Result},
+    models::{
+        common::ContentBlock,
+        message::{ContentBlockDelta, StreamEvent},
+    },
+};
+use futures::Stream;
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// A complete `tool_use` block from a stream
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolCall {
+    /// Content block index within the message
+    pub index: usize,
+    /// Tool use id, to answer with a tool result
+    pub id: String,
+    /// Name of the tool
+    pub name: String,
+    /// Parsed input; an object with no fields when the tool takes none
+    pub input: Value,
+}
+
+/// A `tool_use` block still streaming its input
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialToolCall {
+    /// Content block index within the message
+    pub index: usize,
+    /// Tool use id
+    pub id: String,
+    /// Name of the tool
+    pub name: String,
+    /// Input JSON received so far
+    pub json: String,
+}
+
+impl PartialToolCall {
+    /// The input received so far, with open strings, arrays and objects
+    /// closed and an incomplete trailing key or value dropped; `None` until
+    /// the first complete token arrives
+    pub fn input(&self) -> Option<Value> {
+        parse_partial_json(&self.json)
+    }
+}
+
+type PartialCallback = Box<dyn FnMut(&PartialToolCall) + Send>;
+
+/// Stream of [`ToolCall`]s assembled from a stream of events; see the
+/// [module docs](self)
+pub struct ToolCallStream<S> {
+    events: S,
+    open: HashMap<usize, PartialToolCall>,
+    on_partial: Option<PartialCallback>,
+    done: bool,
+}
+
+impl<S> ToolCallStream<S>
+where
+    S: Stream<Item = Result<StreamEvent>> + Unpin,
+{
+    /// Assemble the tool calls in `events`
+    pub fn new(events: S) -> Self {
+        Self {
+            events,
+            open: HashMap::new(),
+            on_partial: None,
+            done: false,
+        }
+    }
+
+    /// Call `callback` with the call in progress after each input fragment
+    pub fn on_partial(mut self, callback: impl FnMut(&PartialToolCall) + Send + 'static) -> Self {
+        self.on_partial = Some(Box::new(callback));
+        self
+    }
+
+    /// Take in one event, returning a finished call or a failure
+    fn push(&mut self, event: StreamEvent) -> Option<Result<ToolCall>> {
+        match event {
+            StreamEvent::ContentBlockStart {
+                index,
+                content_block: ContentBlock::ToolUse { id, name, input },
+            } => {
+                // Inputs are normally empty here and streamed; keep one that is not
+                let json = match input {
+                    Value::Object(fields) if fields.is_empty() => String::new(),
+                    Value::Null => String::new(),
+                    input => input.to_string(),
+                };
+                self.open.insert(
+                    index,
+                    PartialToolCall {
+                        index,
+                        id,
+                        name,
+                        json,
+                    },
+                );
+            }
+            StreamEvent::ContentBlockDelta {
+                index,
+                delta: ContentBlockDelta::InputJsonDelta { partial_json },
+            } => {
+                if let Some(call) = self.open.get_mut(&index) {
+                    call.json.push_str(&partial_json);
+                    if let Some(callback) = &mut self.on_partial {
+                        callback(call);
+                    }
+                }
+            }
+            StreamEvent::ContentBlockStop { index } => {
+                let call = self.open.remove(&index)?;
+                return Some(finish(call));
+            }
+            StreamEvent::MessageStop => self.done = true,
+            StreamEvent::Error { error } => {
+                self.done = true;
+                return Some(Err(AnthropicError::stream(format!(
+                    "Stream error: {:?}",
+                    error
+                ))));
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+fn finish(call: PartialToolCall) -> Result<ToolCall> {
+    let input = if call.json.trim().is_empty() {
+        Value::Object(Default::default())
+    } else {
+        serde_json::from_str(&call.json).map_err(|e| {
+            AnthropicError::stream(format!(
+                "Input of tool call {} ({}) is not valid JSON: {}",
+                call.id, call.name, e
+            ))
+        })?
+    };
+    Ok(ToolCall {
+        index: call.index,
+        id: call.id,
+        name: call.name,
+        input,
+    })
+}
+
+impl<S> Stream for ToolCallStream<S>
+where
+    S: Stream<Item = Result<StreamEvent>> + Unpin,
+{
+    type Item = Result<ToolCall>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        while !this.done {
+            match Pin::new(&mut this.events).poll_next(cx) {
+                Poll::Ready(Some(Ok(event))) => {
+                    if let Some(item) = this.push(event) {
+                        return Poll::Ready(Some(item));
+                    }
+                }
+                Poll::Ready(Some(Err(error))) => {
+                    this.done = true;
+                    return Poll::Ready(Some(Err(error)));
+                }
+                Poll::Ready(None) => this.done = true,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        Poll::Ready(None)
+    }
+}
+
+/// Most characters dropped from the end of partial JSON while looking for a
+/// parseable prefix
+const MAX_BACKOFF: usize = 64;
+
+/// Parse the longest prefix of truncated JSON `text` that can be completed
+/// by closing its open strings, arrays and objects
+pub fn parse_partial_json(text: &str) -> Option<Value> {
+    let text = text.trim_end();
+    if let Ok(value) = serde_json::from_str(text) {
+        return Some(value);
+    }
+    let mut end = text.len();
+    for _ in 0..MAX_BACKOFF {
+        if end == 0 {
+            return None;
+        }
+        if let Ok(value) = serde_json::from_str(&close(&text[..end])) {
+            return Some(value);
+        }
+        end = text[..end].char_indices().next_back().map_or(0, |(i, _)| i);
+    }
+    None
+}
+
+/// `prefix` followed by whatever closes its open string and brackets
+fn close(prefix: &str) -> String {
+    let mut stack = Vec::new();
+    let (mut in_string, mut escaped) = (false, false);
+    for c in prefix.chars() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' => stack.push('}'),
+            '[' => stack.push(']'),
+            '}' | ']' => {
+                stack.pop();
+            }
+            _ => {}
+        }
+    }
+    let mut closed = prefix.to_string();
+    if in_string {
+        if escaped {
+            closed.pop();
+        }
+        closed.push('"');
+    }
+    closed.extend(stack.iter().rev());
+    closed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_partial_json() {
+        assert_eq!(parse_partial_json(""), None);
+        assert_eq!(parse_partial_json("{"), Some(json!({})));
+        assert_eq!(parse_partial_json("{\"ci"), Some(json!({})));
+        assert_eq!(
+            parse_partial_json("{\"city\": \"Par"),
+            Some(json!({"city": "Par"}))
+        );
+        assert_eq!(
+            parse_partial_json("{\"city\": \"Paris\", \"days\": [1, 2"),
+            Some(json!({"city": "Paris", "days": [1, 2]}))
+        );
+        assert_eq!(
+            parse_partial_json("{\"a\": tru"),
+            Some(json!({})),
+            "incomplete literals are dropped"
+        );
+        assert_eq!(
+            parse_partial_json("{\"q\": \"say \\"),
+            Some(json!({"q": "say "}))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls_assembled_per_block() {
+        use futures::StreamExt;
+        use std::sync::{Arc, Mutex};
+
+        let events: Vec<StreamEvent> = [
+            json!({"type": "content_block_start", "index": 0, "content_block": {"type": "text", "text": ""}}),
+            json!({"type": "content_block_start", "index": 1,
+                "content_block": {"type": "tool_use", "id": "toolu_1", "name": "weather", "input": {}}}),
+            json!({"type": "content_block_delta", "index": 1,
+                "delta": {"type": "input_json_delta", "partial_json": "{\"city\": \"Pa"}}),
+            json!({"type": "content_block_delta", "index": 1,
+                "delta": {"type": "input_json_delta", "partial_json": "ris\"}"}}),
+            json!({"type": "content_block_stop", "index": 1}),
+            json!({"type": "content_block_start", "index": 2,
+                "content_block": {"type": "tool_use", "id": "toolu_2", "name": "time", "input": {}}}),
+            json!({"type": "content_block_stop", "index": 2}),
+            json!({"type": "message_stop"}),
+        ]
+        .into_iter()
+        .map(|event| serde_json::from_value(event).unwrap())
+        .collect();
+
+        let partials = Arc::new(Mutex::new(Vec::new()));
+        let seen = partials.clone();
+        let calls: Vec<ToolCall> =
+            ToolCallStream::new(futures::stream::iter(events.into_iter().map(Ok)))
+                .on_partial(move |call| seen.lock().unwrap().push(call.input()))
+                .map(Result::unwrap)
+                .collect()
+                .await;
+
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].index, 1);
+        assert_eq!(calls[0].input, json!({"city": "Paris"}));
+        assert_eq!(calls[1].name, "time");
+        assert_eq!(calls[1].input, json!({}));
+        assert_eq!(
+            *partials.lock().unwrap(),
+            [Some(json!({"city": "Pa"})), Some(json!({"city": "Paris"}))]
+        );
+    }
+}
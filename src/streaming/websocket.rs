@@ -0,0 +1,259 @@
+//! Forwarding message streams to WebSocket clients
+//!
+//! Chat backends usually receive Claude's reply over SSE and relay it to a
+//! browser over a WebSocket. [`forward_to_websocket`] does the relaying with
+//! a small JSON envelope protocol, one text frame per envelope:
+//!
+//! ```json
+//! {"type": "delta", "index": 0, "kind": "text", "text": "Hel"}
+//! {"type": "done", "message": { ...the complete MessageResponse... }}
+//! {"type": "error", "error": "Stream error: ..."}
+//! ```
+//!
+//! `delta` carries text, thinking and tool-input JSON fragments; `done` and
+//! `error` are always the last envelope of a stream.
+//!
+//! ```rust,no_run
+//! use threatflux_anthropic_sdk::{models::MessageRequest, Client};
+//!
+//! # async fn relay(
+//! #     socket: tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>,
+//! # ) -> threatflux_anthropic_sdk::Result<()> {
+//! use futures::StreamExt;
+//!
+//! let client = Client::from_env()?;
+//! let (mut outgoing, _incoming) = socket.split();
+//! let request = MessageRequest::new()
+//!     .max_tokens(1024)
+//!     .add_user_message("Tell me a story.");
+//! let stream = client.messages().create_stream(request, None).await?;
+//! let message = stream.forward_to_websocket(&mut outgoing).await?;
+//! println!("sent {} output tokens", message.usage.output_tokens);
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{accumulator::MessageAccumulator, message_stream::MessageStream};
+use crate::{
+    error::{AnthropicError, Result},
+    models::message::{ContentBlockDelta, MessageResponse, StreamEvent},
+};
+use futures::{Sink, SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use tokio_tungstenite::tungstenite::Message;
+
+/// What a `delta` envelope appends to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeltaKind {
+    /// Visible reply text
+    Text,
+    /// Extended-thinking text
+    Thinking,
+    /// Partial JSON of a tool call's input
+    InputJson,
+}
+
+/// One frame of the forwarding protocol
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WsEnvelope {
+    /// A fragment of content block `index`
+    Delta {
+        /// Content block index
+        index: usize,
+        /// What the fragment belongs to
+        kind: DeltaKind,
+        /// The fragment
+        text: String,
+    },
+    /// The stream finished; `message` is the complete reply
+    Done {
+        /// The assembled message, including usage and stop reason
+        message: MessageResponse,
+    },
+    /// The stream failed
+    Error {
+        /// Error description
+        error: String,
+    },
+}
+
+impl WsEnvelope {
+    /// The `delta` envelope for `event`, if it carries a fragment
+    pub fn delta(event: &StreamEvent) -> Option<Self> {
+        let StreamEvent::ContentBlockDelta { index, delta } = event else {
+            return None;
+        };
+        let (kind, text) = match delta {
+            ContentBlockDelta::TextDelta { text } => (DeltaKind::Text, text),
+            ContentBlockDelta::ThinkingDelta { thinking } => (DeltaKind::Thinking, thinking),
+            ContentBlockDelta::InputJsonDelta { partial_json } => {
+                (DeltaKind::InputJson, partial_json)
+            }
+            _ => return None,
+        };
+        Some(Self::Delta {
+            index: *index,
+            kind,
+            text: text.clone(),
+        })
+    }
+
+    /// This envelope as a WebSocket text frame
+    pub fn to_message(&self) -> Result<Message> {
+        Ok(Message::text(serde_json::to_string(self)?))
+    }
+}
+
+/// Relay `stream` to `sink` as [`WsEnvelope`] frames and return the
+/// complete message.
+///
+/// A failing stream is reported to the client with an `error` envelope and
+/// then returned as the error. If the sink fails (the client went away), the
+/// stream is dropped, which cancels the request.
+pub async fn forward_to_websocket<S>(
+    mut stream: MessageStream,
+    sink: &mut S,
+) -> Result<MessageResponse>
+where
+    S: Sink<Message> + Unpin,
+    S::Error: Display,
+{
+    let mut accumulator = MessageAccumulator::new();
+    let outcome = loop {
+        let Some(event) = stream.next().await else {
+            break accumulator.finish();
+        };
+        let event = match event {
+            Ok(event) => event,
+            Err(err) => break Err(err),
+        };
+        if let Some(envelope) = WsEnvelope::delta(&event) {
+            send(sink, &envelope).await?;
+        }
+        if let Err(err) = accumulator.push(event) {
+            break Err(err);
+        }
+        if accumulator.is_stopped() {
+            break accumulator.finish();
+        }
+    };
+
+    match outcome {
+        Ok(message) => {
+            let done = WsEnvelope::Done {
+                message: message.clone(),
+            };
+            send(sink, &done).await?;
+            Ok(message)
+        }
+        Err(err) => {
+            send(
+                sink,
+                &WsEnvelope::Error {
+                    error: err.to_string(),
+                },
+            )
+            .await?;
+            Err(err)
+        }
+    }
+}
+
+async fn send<S>(sink: &mut S, envelope: &WsEnvelope) -> Result<()>
+where
+    S: Sink<Message> + Unpin,
+    S::Error: Display,
+{
+    sink.send(envelope.to_message()?)
+        .await
+        .map_err(|e| AnthropicError::stream(format!("WebSocket send failed: {}", e)))
+}
+
+impl MessageStream {
+    /// Relay this stream to a WebSocket; see [`forward_to_websocket`]
+    pub async fn forward_to_websocket<S>(self, sink: &mut S) -> Result<MessageResponse>
+    where
+        S: Sink<Message> + Unpin,
+        S::Error: Display,
+    {
+        forward_to_websocket(self, sink).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::channel::mpsc;
+    use serde_json::Value;
+
+    async fn stream(body: String) -> MessageStream {
+        let response = http::Response::builder()
+            .header("content-type", "text/event-stream")
+            .body(body)
+            .unwrap();
+        MessageStream::new(reqwest::Response::from(response))
+            .await
+            .unwrap()
+    }
+
+    fn frames(mut receiver: mpsc::UnboundedReceiver<Message>) -> Vec<Value> {
+        let mut frames = Vec::new();
+        while let Ok(message) = receiver.try_recv() {
+            frames.push(serde_json::from_str(message.to_text().unwrap()).unwrap());
+        }
+        frames
+    }
+
+    const START: &str = "event: message_start\ndata: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_1\",\"type\":\"message\",\"role\":\"assistant\",\"model\":\"claude-haiku-4-5\",\"content\":[],\"stop_reason\":null,\"stop_sequence\":null,\"usage\":{\"input_tokens\":3,\"output_tokens\":0}}}\n\n";
+
+    #[tokio::test]
+    async fn test_forwards_deltas_then_done() {
+        let body = format!(
+                "{}{}{}{}{}",
+                START,
+                "event: content_block_start\ndata: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+                "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hi\"}}\n\n",
+                "event: ping\ndata: {\"type\":\"ping\"}\n\n",
+                "event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n",
+            );
+        let (mut sender, receiver) = mpsc::unbounded();
+
+        let message = stream(body)
+            .await
+            .forward_to_websocket(&mut sender)
+            .await
+            .unwrap();
+        assert_eq!(message.text(), "Hi");
+
+        let frames = frames(receiver);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(
+            frames[0],
+            serde_json::json!({"type": "delta", "index": 0, "kind": "text", "text": "Hi"})
+        );
+        assert_eq!(frames[1]["type"], "done");
+        assert_eq!(frames[1]["message"]["id"], "msg_1");
+    }
+
+    #[tokio::test]
+    async fn test_stream_error_is_sent_as_error_envelope() {
+        let body = format!(
+                "{}event: error\ndata: {{\"type\":\"error\",\"error\":{{\"type\":\"overloaded_error\",\"message\":\"Overloaded\"}}}}\n\n",
+                START
+            );
+        let (mut sender, receiver) = mpsc::unbounded();
+
+        let err = forward_to_websocket(stream(body).await, &mut sender)
+            .await
+            .unwrap_err();
+        let frames = frames(receiver);
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0]["type"], "error");
+        assert_eq!(frames[0]["error"], err.to_string());
+        assert!(err.to_string().contains("Overloaded"));
+    }
+}
@@ -0,0 +1,240 @@
+//! Human-in-the-loop approval for tool calls
+//!
+//! An [`ApprovalPolicy`] names the tools (exact names or `*`/`?` globs) that
+//! must not run without sign-off. When the model calls one of them,
+//! [`MessagesApi::run_tools`] asks the policy's [`Approver`] first and only
+//! executes the handler on [`ApprovalDecision::Approve`].
+//!
+//! The policy fails closed: a gated call is denied when no approver is set,
+//! when the approver does not answer within [`ApprovalPolicy::timeout`], or
+//! when it answers with [`ApprovalDecision::Deny`]. Denials reach the model as
+//! error tool results, so it can explain itself or pick another route.
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use threatflux_anthropic_sdk::tools::{
+//!     ApprovalDecision, ApprovalPolicy, ApprovalRequest, RunToolsOptions,
+//! };
+//!
+//! let policy = ApprovalPolicy::new()
+//!     .require("run_shell")
+//!     .require("payments_*")
+//!     .with_timeout(Duration::from_secs(120))
+//!     .with_approver(|call: ApprovalRequest| async move {
+//!         println!("Approve {} with {}? (auto-denying in this example)", call.name, call.input);
+//!         ApprovalDecision::deny("operator declined")
+//!     });
+//! let options = RunToolsOptions::new().with_approval(policy);
+//! ```
+//!
+//! [`MessagesApi::run_tools`]: crate::api::messages::MessagesApi::run_tools
+
+use crate::utils::concurrency::glob_match;
+use futures::future::{BoxFuture, FutureExt};
+use std::{fmt, future::Future, sync::Arc, time::Duration};
+
+/// How long an approver gets before a call is denied
+pub const DEFAULT_APPROVAL_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// A tool call waiting for approval
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApprovalRequest {
+    /// `tool_use` block id
+    pub tool_use_id: String,
+    /// Tool name
+    pub name: String,
+    /// Model-supplied input
+    pub input: serde_json::Value,
+}
+
+/// An approver's answer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApprovalDecision {
+    /// Run the tool
+    Approve,
+    /// Do not run the tool; the reason is shown to the model
+    Deny {
+        /// Why the call was refused
+        reason: Option<String>,
+    },
+}
+
+impl ApprovalDecision {
+    /// Deny with a reason
+    pub fn deny(reason: impl Into<String>) -> Self {
+        Self::Deny {
+            reason: Some(reason.into()),
+        }
+    }
+
+    /// Whether the call may run
+    pub fn is_approved(&self) -> bool {
+        matches!(self, Self::Approve)
+    }
+
+    /// Message sent to the model for a denied call of `name`
+    pub(crate) fn denial_message(&self, name: &str) -> String {
+        match self {
+            Self::Deny {
+                reason: Some(reason),
+            } => format!("Call to {} was not approved: {}", name, reason),
+            _ => format!("Call to {} was not approved", name),
+        }
+    }
+}
+
+/// Async approval callback.
+///
+/// Implemented for closures `Fn(ApprovalRequest) -> impl Future<Output =
+/// ApprovalDecision>`.
+pub trait Approver: Send + Sync {
+    /// Decide whether `request` may run
+    fn review(&self, request: ApprovalRequest) -> BoxFuture<'static, ApprovalDecision>;
+}
+
+impl<F, Fut> Approver for F
+where
+    F: Fn(ApprovalRequest) -> Fut + Send + Sync,
+    Fut: Future<Output = ApprovalDecision> + Send + 'static,
+{
+    fn review(&self, request: ApprovalRequest) -> BoxFuture<'static, ApprovalDecision> {
+        self(request).boxed()
+    }
+}
+
+/// Which tools need approval, and who grants it
+#[derive(Clone)]
+pub struct ApprovalPolicy {
+    /// Tool names or `*`/`?` globs that need approval
+    pub patterns: Vec<String>,
+    /// Time allowed for each decision before the call is denied
+    pub timeout: Duration,
+    approver: Option<Arc<dyn Approver>>,
+}
+
+impl Default for ApprovalPolicy {
+    fn default() -> Self {
+        Self {
+            patterns: Vec::new(),
+            timeout: DEFAULT_APPROVAL_TIMEOUT,
+            approver: None,
+        }
+    }
+}
+
+impl ApprovalPolicy {
+    /// Create a policy that gates nothing
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gate every tool
+    pub fn all() -> Self {
+        Self::new().require("*")
+    }
+
+    /// Gate tools whose name matches `pattern`
+    pub fn require(mut self, pattern: impl Into<String>) -> Self {
+        self.patterns.push(pattern.into());
+        self
+    }
+
+    /// Set the approval callback
+    pub fn with_approver(mut self, approver: impl Approver + 'static) -> Self {
+        self.approver = Some(Arc::new(approver));
+        self
+    }
+
+    /// Set how long each decision may take
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Whether calls to `name` need approval
+    pub fn requires_approval(&self, name: &str) -> bool {
+        self.patterns
+            .iter()
+            .any(|pattern| glob_match(pattern, name))
+    }
+
+    /// Decide on one call. Ungated tools are approved without asking.
+    pub async fn check(&self, request: ApprovalRequest) -> ApprovalDecision {
+        if !self.requires_approval(&request.name) {
+            return ApprovalDecision::Approve;
+        }
+        let Some(approver) = &self.approver else {
+            return ApprovalDecision::deny("no approver is configured");
+        };
+
+        let name = request.name.clone();
+        match tokio::time::timeout(self.timeout, approver.review(request)).await {
+            Ok(decision) => decision,
+            Err(_) => {
+                tracing::debug!("Approval for {} timed out after {:?}", name, self.timeout);
+                ApprovalDecision::deny(format!(
+                    "approval timed out after {}s",
+                    self.timeout.as_secs_f64()
+                ))
+            }
+        }
+    }
+}
+
+impl fmt::Debug for ApprovalPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApprovalPolicy")
+            .field("patterns", &self.patterns)
+            .field("timeout", &self.timeout)
+            .field("has_approver", &self.approver.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn call(name: &str) -> ApprovalRequest {
+        ApprovalRequest {
+            tool_use_id: "toolu_1".to_string(),
+            name: name.to_string(),
+            input: json!({}),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_policy_fails_closed() {
+        let unattended = ApprovalPolicy::new().require("payments_*");
+        assert!(unattended.check(call("search")).await.is_approved());
+        assert_eq!(
+            unattended.check(call("payments_refund")).await,
+            ApprovalDecision::deny("no approver is configured")
+        );
+
+        let slow = ApprovalPolicy::all()
+            .with_timeout(Duration::from_millis(10))
+            .with_approver(|_call| async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                ApprovalDecision::Approve
+            });
+        let decision = slow.check(call("anything")).await;
+        assert!(decision
+            .denial_message("anything")
+            .contains("approval timed out"));
+
+        let picky = ApprovalPolicy::all().with_approver(|call: ApprovalRequest| async move {
+            if call.name == "read_file" {
+                ApprovalDecision::Approve
+            } else {
+                ApprovalDecision::Deny { reason: None }
+            }
+        });
+        assert!(picky.check(call("read_file")).await.is_approved());
+        assert_eq!(
+            picky.check(call("rm")).await.denial_message("rm"),
+            "Call to rm was not approved"
+        );
+    }
+}
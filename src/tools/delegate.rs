@@ -0,0 +1,288 @@
+//! Sub-agents: tools that are themselves tool-use loops
+//!
+//! A [`SubAgent`] is registered in a [`ToolRegistry`] like any other tool.
+//! When the supervising model calls it with a `task`, the sub-agent runs its
+//! own [`MessagesApi::run_tools`] loop (its own model, system prompt and
+//! tools, which may include further sub-agents) and returns its final answer
+//! as the tool result.
+//!
+//! Two safeguards apply across the whole tree of agents:
+//!
+//! * **Depth**: each sub-agent refuses to start when it would run more than
+//!   [`SubAgent::max_depth`] levels below the top-level loop, so agents that
+//!   delegate to each other cannot recurse without bound.
+//! * **Budget**: a [`SharedBudget`] set on the top-level
+//!   [`RunToolsOptions`] is inherited by every nested loop. Usage from all
+//!   levels is added to it and any loop stops with
+//!   [`AnthropicError::BudgetExceeded`] once it is used up.
+//!
+//! ```rust,no_run
+//! use threatflux_anthropic_sdk::{
+//!     conversation::Budget,
+//!     models::MessageRequest,
+//!     tools::{RunToolsOptions, SharedBudget, SubAgent, ToolRegistry},
+//!     Client,
+//! };
+//!
+//! # async fn example() -> threatflux_anthropic_sdk::Result<()> {
+//! let client = Client::from_env()?;
+//! let researcher = SubAgent::new(
+//!     client.clone(),
+//!     "researcher",
+//!     "Answers factual questions in depth",
+//!     MessageRequest::new()
+//!         .model("claude-haiku-4-5")
+//!         .max_tokens(2048)
+//!         .system("You are a meticulous researcher."),
+//! );
+//! let tools = ToolRegistry::new().with_agent(researcher);
+//!
+//! let budget = SharedBudget::new(Budget::tokens(200_000));
+//! let request = MessageRequest::new()
+//!     .max_tokens(1024)
+//!     .add_user_message("Write a briefing on the history of RSA.");
+//! let run = client
+//!     .messages()
+//!     .run_tools(request, &tools, RunToolsOptions::new().with_budget(budget.clone()))
+//!     .await?;
+//! println!("{}\n{} tokens in total", run.response.text(), budget.usage().total_tokens());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`MessagesApi::run_tools`]: crate::api::messages::MessagesApi::run_tools
+//! [`AnthropicError::BudgetExceeded`]: crate::error::AnthropicError::BudgetExceeded
+
+use super::registry::{RunToolsOptions, ToolHandler, ToolOutput, ToolRegistry, ToolRun};
+use crate::{
+    client::Client,
+    conversation::{Budget, UsageSummary},
+    error::{AnthropicError, Result},
+    models::{common::Tool, common::Usage, message::MessageRequest},
+};
+use futures::future::{BoxFuture, FutureExt};
+use serde_json::json;
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+/// Default for [`SubAgent::max_depth`]
+pub const DEFAULT_MAX_AGENT_DEPTH: usize = 3;
+
+/// Usage and an optional limit shared by a supervisor and its sub-agents.
+///
+/// Clones share the same totals.
+#[derive(Debug, Clone, Default)]
+pub struct SharedBudget {
+    usage: Arc<Mutex<UsageSummary>>,
+    limit: Option<Budget>,
+}
+
+impl SharedBudget {
+    /// Shared accounting with `limit`
+    pub fn new(limit: Budget) -> Self {
+        Self {
+            usage: Arc::default(),
+            limit: Some(limit),
+        }
+    }
+
+    /// Shared accounting without a limit
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// The limit, if any
+    pub fn limit(&self) -> Option<&Budget> {
+        self.limit.as_ref()
+    }
+
+    /// Usage recorded so far across all agents
+    pub fn usage(&self) -> UsageSummary {
+        self.usage.lock().unwrap().clone()
+    }
+
+    /// Fail with `BudgetExceeded` if the limit has been reached
+    pub fn check(&self) -> Result<()> {
+        match &self.limit {
+            Some(limit) => limit.check_available(&self.usage.lock().unwrap()),
+            None => Ok(()),
+        }
+    }
+
+    /// Add one response's usage
+    pub fn record(&self, model: &str, usage: &Usage) {
+        self.usage.lock().unwrap().record(model, usage);
+    }
+}
+
+/// Where a tool-use loop sits in the agent tree
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AgentFrame {
+    /// 0 for a top-level loop, 1 for its sub-agents, and so on
+    pub(crate) depth: usize,
+    /// Budget inherited by nested loops
+    pub(crate) budget: Option<SharedBudget>,
+}
+
+tokio::task_local! {
+    static FRAME: AgentFrame;
+}
+
+impl AgentFrame {
+    /// Frame of the loop about to start: one level below the loop whose tool
+    /// call is running, or the top level
+    pub(crate) fn enter(budget: Option<&SharedBudget>) -> Self {
+        match FRAME.try_with(Clone::clone) {
+            Ok(parent) => Self {
+                depth: parent.depth + 1,
+                budget: budget.cloned().or(parent.budget),
+            },
+            Err(_) => Self {
+                depth: 0,
+                budget: budget.cloned(),
+            },
+        }
+    }
+
+    /// Run tool calls with this frame visible to any sub-agents among them
+    pub(crate) fn scope<F: Future>(self, calls: F) -> impl Future<Output = F::Output> {
+        FRAME.scope(self, calls)
+    }
+}
+
+/// A tool that delegates a task to its own tool-use loop
+#[derive(Clone)]
+pub struct SubAgent {
+    client: Client,
+    definition: Tool,
+    request: MessageRequest,
+    tools: ToolRegistry,
+    options: RunToolsOptions,
+    /// Deepest level, counting the top-level loop as 0, this agent may run at
+    pub max_depth: usize,
+}
+
+impl SubAgent {
+    /// Create a sub-agent named `name`.
+    ///
+    /// `request` supplies the model, system prompt and other settings; each
+    /// task is appended to its messages as a user turn.
+    pub fn new(
+        client: Client,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        request: MessageRequest,
+    ) -> Self {
+        let definition = Tool::new(
+            name,
+            description,
+            json!({
+                "type": "object",
+                "properties": {
+                    "task": {
+                        "type": "string",
+                        "description": "Self-contained description of the work to delegate"
+                    }
+                },
+                "required": ["task"]
+            }),
+        );
+        Self {
+            client,
+            definition,
+            request,
+            tools: ToolRegistry::new(),
+            options: RunToolsOptions::default(),
+            max_depth: DEFAULT_MAX_AGENT_DEPTH,
+        }
+    }
+
+    /// Tools the sub-agent may use, including further sub-agents
+    pub fn with_tools(mut self, tools: ToolRegistry) -> Self {
+        self.tools = tools;
+        self
+    }
+
+    /// Loop settings for the sub-agent's runs
+    pub fn with_options(mut self, options: RunToolsOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Set [`max_depth`](Self::max_depth)
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Tool definition the supervisor sees
+    pub fn definition(&self) -> &Tool {
+        &self.definition
+    }
+
+    /// Run one task to completion
+    pub async fn run(&self, task: &str) -> Result<ToolRun> {
+        let request = self.request.clone().add_user_message(task);
+        self.client
+            .messages()
+            .run_tools(request, &self.tools, self.options.clone())
+            .await
+    }
+
+    async fn delegate(self, input: serde_json::Value) -> Result<ToolOutput> {
+        let depth = FRAME.try_with(|frame| frame.depth + 1).unwrap_or(0);
+        if depth > self.max_depth {
+            return Err(AnthropicError::invalid_input(format!(
+                "Sub-agent {} was not started: depth limit {} reached",
+                self.definition.name, self.max_depth
+            )));
+        }
+        let Some(task) = input.get("task").and_then(|task| task.as_str()) else {
+            return Err(AnthropicError::invalid_input(
+                "Sub-agent input needs a \"task\" string",
+            ));
+        };
+
+        let run = self.run(task).await?;
+        let answer = run.response.text();
+        if answer.trim().is_empty() {
+            return Err(AnthropicError::invalid_input(format!(
+                "Sub-agent {} finished without an answer",
+                self.definition.name
+            )));
+        }
+        Ok(ToolOutput::text(answer))
+    }
+}
+
+impl ToolHandler for SubAgent {
+    fn call(&self, input: serde_json::Value) -> BoxFuture<'static, Result<ToolOutput>> {
+        self.clone().delegate(input).boxed()
+    }
+}
+
+impl std::fmt::Debug for SubAgent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SubAgent")
+            .field("name", &self.definition.name)
+            .field("model", &self.request.model)
+            .field("tools", &self.tools)
+            .field("max_depth", &self.max_depth)
+            .finish()
+    }
+}
+
+impl ToolRegistry {
+    /// Register a sub-agent under its own name
+    pub fn register_agent(&mut self, agent: SubAgent) {
+        self.register(agent.definition.clone(), agent);
+    }
+
+    /// Builder form of [`register_agent`](Self::register_agent)
+    pub fn with_agent(mut self, agent: SubAgent) -> Self {
+        self.register_agent(agent);
+        self
+    }
+}
@@ -0,0 +1,191 @@
+//! Declarative tool definitions
+//!
+//! [`AnthropicTool`] describes a tool whose arguments are a Rust type: its
+//! name, description and input schema. With the `derive` feature,
+//! `#[derive(AnthropicTool)]` writes the impl from the struct itself: the
+//! struct's doc comment becomes the description, each field's doc comment
+//! describes that property, and `Option` or `#[serde(default)]` fields are
+//! optional. Adding `#[tool(handler = ...)]` also implements
+//! [`AnthropicToolHandler`], so the tool can be registered in one line.
+//!
+//! ```rust,ignore
+//! use threatflux_anthropic_sdk::tools::{derived::AnthropicTool, ToolOutput, ToolRegistry};
+//!
+//! /// Current weather for a city
+//! #[derive(serde::Deserialize, AnthropicTool)]
+//! #[tool(name = "get_weather", handler = get_weather)]
+//! struct GetWeather {
+//!     /// City name, e.g. "Paris"
+//!     city: String,
+//!     /// Temperature unit, celsius when omitted
+//!     unit: Option<Unit>,
+//! }
+//!
+//! #[derive(serde::Deserialize, AnthropicTool)]
+//! #[serde(rename_all = "lowercase")]
+//! enum Unit {
+//!     Celsius,
+//!     Fahrenheit,
+//! }
+//!
+//! async fn get_weather(input: GetWeather) -> threatflux_anthropic_sdk::Result<ToolOutput> {
+//!     Ok(ToolOutput::text(format!("Sunny in {}", input.city)))
+//! }
+//!
+//! let tools = ToolRegistry::new().with_derived::<GetWeather>();
+//! ```
+//!
+//! Unlike the `schemars`-based `typed` registration, the schema is
+//! assembled from [`ToolSchema`] impls, which cover the standard scalar,
+//! collection and map types plus any other derived type; implement it by hand
+//! for anything else.
+
+use super::registry::{parse_tool_input, ToolOutput, ToolRegistry};
+use crate::{error::Result, models::common::Tool};
+use futures::future::{BoxFuture, FutureExt};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+#[cfg(feature = "derive")]
+pub use threatflux_anthropic_sdk_derive::AnthropicTool;
+
+/// A tool whose input is `Self`
+pub trait AnthropicTool: DeserializeOwned + Send + 'static {
+    /// Tool name
+    const NAME: &'static str;
+    /// Description shown to the model
+    const DESCRIPTION: &'static str;
+
+    /// JSON Schema for the input object
+    fn input_schema() -> Value;
+
+    /// Tool definition for requests
+    fn definition() -> Tool {
+        Tool::new(Self::NAME, Self::DESCRIPTION, Self::input_schema())
+    }
+}
+
+/// An [`AnthropicTool`] that knows how to run itself
+pub trait AnthropicToolHandler: AnthropicTool {
+    /// Run the tool on this input
+    fn run(self) -> BoxFuture<'static, Result<ToolOutput>>;
+}
+
+/// JSON Schema for a value used as a tool input property
+pub trait ToolSchema {
+    /// Whether the property must be present; `false` for `Option`
+    const REQUIRED: bool = true;
+
+    /// Schema for the value
+    fn schema() -> Value;
+}
+
+macro_rules! tool_schema {
+    ($schema:expr => $($ty:ty),+) => {
+        $(impl ToolSchema for $ty {
+            fn schema() -> Value {
+                $schema
+            }
+        })+
+    };
+}
+
+tool_schema!(json!({"type": "string"}) => String, char);
+tool_schema!(json!({"type": "boolean"}) => bool);
+tool_schema!(json!({"type": "integer"}) => i8, i16, i32, i64, i128, isize);
+tool_schema!(json!({"type": "integer", "minimum": 0}) => u8, u16, u32, u64, u128, usize);
+tool_schema!(json!({"type": "number"}) => f32, f64);
+tool_schema!(json!({}) => Value);
+
+impl<T: ToolSchema> ToolSchema for Option<T> {
+    const REQUIRED: bool = false;
+
+    fn schema() -> Value {
+        T::schema()
+    }
+}
+
+impl<T: ToolSchema> ToolSchema for Box<T> {
+    const REQUIRED: bool = T::REQUIRED;
+
+    fn schema() -> Value {
+        T::schema()
+    }
+}
+
+impl<T: ToolSchema> ToolSchema for Vec<T> {
+    fn schema() -> Value {
+        json!({"type": "array", "items": T::schema()})
+    }
+}
+
+impl<T: ToolSchema> ToolSchema for HashSet<T> {
+    fn schema() -> Value {
+        json!({"type": "array", "items": T::schema(), "uniqueItems": true})
+    }
+}
+
+impl<T: ToolSchema> ToolSchema for BTreeSet<T> {
+    fn schema() -> Value {
+        json!({"type": "array", "items": T::schema(), "uniqueItems": true})
+    }
+}
+
+impl<T: ToolSchema> ToolSchema for HashMap<String, T> {
+    fn schema() -> Value {
+        json!({"type": "object", "additionalProperties": T::schema()})
+    }
+}
+
+impl<T: ToolSchema> ToolSchema for BTreeMap<String, T> {
+    fn schema() -> Value {
+        json!({"type": "object", "additionalProperties": T::schema()})
+    }
+}
+
+impl ToolRegistry {
+    /// Register a derived tool that runs itself
+    pub fn register_derived<T: AnthropicToolHandler>(&mut self) {
+        self.register(T::definition(), |input: Value| {
+            match parse_tool_input::<T>(T::NAME, input) {
+                Ok(input) => input.run(),
+                Err(err) => futures::future::ready(Err(err)).boxed(),
+            }
+        });
+    }
+
+    /// Builder form of [`register_derived`](Self::register_derived)
+    pub fn with_derived<T: AnthropicToolHandler>(mut self) -> Self {
+        self.register_derived::<T>();
+        self
+    }
+}
+
+/// Support code for the derive macro; not public API
+#[doc(hidden)]
+pub mod __private {
+    pub use futures::future::BoxFuture;
+    pub use serde_json::Value;
+
+    /// Object schema from `(name, schema, description, required)` properties
+    pub fn object_schema(properties: Vec<(&str, Value, Option<&str>, bool)>) -> Value {
+        let mut map = serde_json::Map::new();
+        let mut required = Vec::new();
+        for (name, mut schema, description, is_required) in properties {
+            if let (Some(description), Some(object)) = (description, schema.as_object_mut()) {
+                object.insert("description".to_string(), description.into());
+            }
+            if is_required {
+                required.push(Value::from(name));
+            }
+            map.insert(name.to_string(), schema);
+        }
+        serde_json::json!({"type": "object", "properties": map, "required": required})
+    }
+
+    /// String schema limited to `values`
+    pub fn enum_schema(values: &[&str]) -> Value {
+        serde_json::json!({"type": "string", "enum": values})
+    }
+}
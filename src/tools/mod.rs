@@ -0,0 +1,34 @@
+//! Client-side tool support
+//!
+//! Building blocks for running the tools a model asks for: [`registry`]
+//! maps tool names to async handlers and drives the tool-use loop,
+//! [`approval`] holds back sensitive calls until a human signs off,
+//! [`delegate`] lets a whole sub-agent act as one tool,
+//! [`derived`] and `typed` (with the `schemars` feature) register handlers
+//! over typed inputs with generated schemas, [`trace`] records runs for
+//! inspection and replay, and [`sandbox`] executes shell or code tools inside
+//! restricted environments rather than directly on the host.
+
+pub mod approval;
+pub mod delegate;
+pub mod derived;
+pub mod registry;
+pub mod sandbox;
+pub mod trace;
+#[cfg(feature = "schemars")]
+pub mod typed;
+
+pub use approval::{ApprovalDecision, ApprovalPolicy, ApprovalRequest, Approver};
+pub use delegate::{SharedBudget, SubAgent};
+pub use derived::{AnthropicTool, AnthropicToolHandler, ToolSchema};
+pub use registry::{
+    RunLimit, RunLimitExceeded, RunLimits, RunToolsOptions, ToolHandler, ToolOutput, ToolRegistry,
+    ToolRun,
+};
+pub use trace::{AgentIteration, AgentTrace, Divergence, ReplayReport, ToolCallTrace};
+
+#[cfg(feature = "wasmtime")]
+pub use sandbox::WasmSandbox;
+pub use sandbox::{ExecOutput, ExecRequest, SandboxLimits, SubprocessSandbox, ToolSandbox};
+#[cfg(feature = "schemars")]
+pub use typed::{input_schema, TypedTool};
@@ -0,0 +1,619 @@
+//! Tool registry and the automatic tool-use loop
+//!
+//! A [`ToolRegistry`] pairs each [`Tool`] definition with an async Rust
+//! handler. [`MessagesApi::run_tools`] sends the request with the registered
+//! tools, runs every `tool_use` block the model returns through its handler,
+//! appends the `tool_result` blocks and repeats until the model stops asking
+//! for tools or [`RunToolsOptions::max_iterations`] is reached.
+//!
+//! Handler failures and calls to unknown tools are reported back to the model
+//! as error results instead of aborting the loop, so it can correct itself.
+//! Calls denied by an [`ApprovalPolicy`](super::approval::ApprovalPolicy) are
+//! reported the same way.
+//!
+//! [`RunLimits`] cap a single run by total tokens, estimated cost, wall time
+//! and tool calls. A run that hits one fails with
+//! [`AnthropicError::RunLimitExceeded`], which carries the progress made so
+//! far as a [`RunLimitExceeded`].
+//!
+//! ```rust,no_run
+//! use serde_json::json;
+//! use threatflux_anthropic_sdk::{
+//!     models::{MessageRequest, Tool},
+//!     tools::{RunToolsOptions, ToolOutput, ToolRegistry},
+//!     Client,
+//! };
+//!
+//! # async fn example() -> threatflux_anthropic_sdk::Result<()> {
+//! let client = Client::from_env()?;
+//! let tools = ToolRegistry::new().with_tool(
+//!     Tool::new(
+//!         "get_weather",
+//!         "Current weather for a city",
+//!         json!({"type": "object", "properties": {"city": {"type": "string"}}, "required": ["city"]}),
+//!     ),
+//!     |input: serde_json::Value| async move {
+//!         let city = input["city"].as_str().unwrap_or("unknown");
+//!         Ok(ToolOutput::text(format!("Sunny and 22°C in {}", city)))
+//!     },
+//! );
+//!
+//! let request = MessageRequest::new()
+//!     .max_tokens(1024)
+//!     .add_user_message("What's the weather in Paris?");
+//! let run = client
+//!     .messages()
+//!     .run_tools(request, &tools, RunToolsOptions::default())
+//!     .await?;
+//! println!("{} ({} iterations)", run.response.text(), run.iterations);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`MessagesApi::run_tools`]: crate::api::messages::MessagesApi::run_tools
+
+use super::{
+    approval::{ApprovalDecision, ApprovalPolicy, ApprovalRequest},
+    delegate::SharedBudget,
+};
+use crate::{
+    conversation::UsageSummary,
+    error::{AnthropicError, Result},
+    models::{
+        common::{ContentBlock, StopReason, Tool, Usage},
+        message::{Message, MessageResponse},
+    },
+    types::RequestOptions,
+};
+use futures::future::{join_all, BoxFuture, FutureExt};
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Default cap on model round trips in [`MessagesApi::run_tools`](crate::api::messages::MessagesApi::run_tools)
+pub const DEFAULT_MAX_TOOL_ITERATIONS: usize = 10;
+
+/// What a tool handler hands back to the model
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToolOutput {
+    /// Plain-text result
+    Text(String),
+    /// Structured result
+    Json(serde_json::Value),
+    /// Failure the model should see and may recover from
+    Error(String),
+}
+
+impl ToolOutput {
+    /// Plain-text result
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::Text(text.into())
+    }
+
+    /// Structured result
+    pub fn json(value: serde_json::Value) -> Self {
+        Self::Json(value)
+    }
+
+    /// Error result
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::Error(message.into())
+    }
+
+    /// Render as a `tool_result` block
+    pub fn into_tool_result(self, tool_use_id: impl Into<String>) -> ContentBlock {
+        match self {
+            Self::Text(text) => ContentBlock::tool_result(tool_use_id, Some(text)),
+            Self::Json(value) => ContentBlock::tool_result_json(tool_use_id, value),
+            Self::Error(message) => ContentBlock::tool_error(tool_use_id, message),
+        }
+    }
+}
+
+/// Async handler for one tool.
+///
+/// Implemented for closures `Fn(serde_json::Value) -> impl Future<Output =
+/// Result<ToolOutput>>`. Returning `Err` is reported to the model as an error
+/// result carrying the error message.
+pub trait ToolHandler: Send + Sync {
+    /// Run the tool on the model-supplied input
+    fn call(&self, input: serde_json::Value) -> BoxFuture<'static, Result<ToolOutput>>;
+}
+
+impl<F, Fut> ToolHandler for F
+where
+    F: Fn(serde_json::Value) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<ToolOutput>> + Send + 'static,
+{
+    fn call(&self, input: serde_json::Value) -> BoxFuture<'static, Result<ToolOutput>> {
+        self(input).boxed()
+    }
+}
+
+/// Tool definitions and their handlers
+#[derive(Clone, Default)]
+pub struct ToolRegistry {
+    tools: Vec<Tool>,
+    handlers: HashMap<String, Arc<dyn ToolHandler>>,
+}
+
+impl ToolRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `tool` with its handler, replacing any tool of the same name
+    pub fn register(&mut self, tool: Tool, handler: impl ToolHandler + 'static) {
+        self.tools.retain(|existing| existing.name != tool.name);
+        self.handlers.insert(tool.name.clone(), Arc::new(handler));
+        self.tools.push(tool);
+    }
+
+    /// Builder form of [`register`](Self::register)
+    pub fn with_tool(mut self, tool: Tool, handler: impl ToolHandler + 'static) -> Self {
+        self.register(tool, handler);
+        self
+    }
+
+    /// Registered tool definitions, in registration order
+    pub fn definitions(&self) -> &[Tool] {
+        &self.tools
+    }
+
+    /// Owned copies of the definitions, e.g. for
+    /// [`MessageBuilder::tools`](crate::builders::MessageBuilder::tools)
+    pub fn tools(&self) -> Vec<Tool> {
+        self.tools.clone()
+    }
+
+    /// Whether a tool named `name` is registered
+    pub fn contains(&self, name: &str) -> bool {
+        self.handlers.contains_key(name)
+    }
+
+    /// Number of registered tools
+    pub fn len(&self) -> usize {
+        self.tools.len()
+    }
+
+    /// Whether no tools are registered
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty()
+    }
+
+    /// Run one tool call and render its `tool_result` block
+    pub async fn call(
+        &self,
+        tool_use_id: &str,
+        name: &str,
+        input: serde_json::Value,
+    ) -> ContentBlock {
+        let Some(handler) = self.handlers.get(name) else {
+            return ContentBlock::tool_error(tool_use_id, format!("Unknown tool: {}", name));
+        };
+        match handler.call(input).await {
+            Ok(output) => output.into_tool_result(tool_use_id),
+            Err(err) => {
+                tracing::debug!("Tool {} failed: {}", name, err);
+                ContentBlock::tool_error(tool_use_id, err.to_string())
+            }
+        }
+    }
+
+    /// Run every `tool_use` block in `content` concurrently and return their
+    /// results in the same order
+    pub async fn call_all(&self, content: &[ContentBlock]) -> Vec<ContentBlock> {
+        self.run_calls(content, None)
+            .await
+            .into_iter()
+            .map(|(result, _)| result)
+            .collect()
+    }
+
+    /// Like [`call_all`](Self::call_all), but calls gated by `policy` only
+    /// run once approved; denied calls become error results
+    pub async fn call_all_approved(
+        &self,
+        content: &[ContentBlock],
+        policy: &ApprovalPolicy,
+    ) -> Vec<ContentBlock> {
+        self.run_calls(content, Some(policy))
+            .await
+            .into_iter()
+            .map(|(result, _)| result)
+            .collect()
+    }
+
+    /// Run the `tool_use` blocks in `content` concurrently, through
+    /// `approval` when given, returning each result with the time it took
+    pub(crate) async fn run_calls(
+        &self,
+        content: &[ContentBlock],
+        approval: Option<&ApprovalPolicy>,
+    ) -> Vec<(ContentBlock, Duration)> {
+        join_all(content.iter().filter_map(|block| match block {
+            ContentBlock::ToolUse { id, name, input } => Some(async move {
+                let started = Instant::now();
+                let decision = match approval {
+                    Some(policy) => {
+                        policy
+                            .check(ApprovalRequest {
+                                tool_use_id: id.clone(),
+                                name: name.clone(),
+                                input: input.clone(),
+                            })
+                            .await
+                    }
+                    None => ApprovalDecision::Approve,
+                };
+                let result = if decision.is_approved() {
+                    self.call(id, name, input.clone()).await
+                } else {
+                    ContentBlock::tool_error(id.clone(), decision.denial_message(name))
+                };
+                (result, started.elapsed())
+            }),
+            _ => None,
+        }))
+        .await
+    }
+}
+
+impl fmt::Debug for ToolRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ToolRegistry")
+            .field(
+                "tools",
+                &self
+                    .tools
+                    .iter()
+                    .map(|t| t.name.as_str())
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+/// Settings for [`MessagesApi::run_tools`](crate::api::messages::MessagesApi::run_tools)
+#[derive(Debug, Clone)]
+pub struct RunToolsOptions {
+    /// Maximum number of requests sent to the model
+    pub max_iterations: usize,
+    /// Options applied to every request in the loop
+    pub request_options: Option<RequestOptions>,
+    /// Approval gate for tool calls; `None` runs every call directly
+    pub approval: Option<ApprovalPolicy>,
+    /// Usage limit shared with nested sub-agent loops; inherited from the
+    /// enclosing loop when `None`
+    pub budget: Option<SharedBudget>,
+    /// Caps on this run alone; not inherited by sub-agents
+    pub limits: RunLimits,
+}
+
+impl Default for RunToolsOptions {
+    fn default() -> Self {
+        Self {
+            max_iterations: DEFAULT_MAX_TOOL_ITERATIONS,
+            request_options: None,
+            approval: None,
+            budget: None,
+            limits: RunLimits::default(),
+        }
+    }
+}
+
+impl RunToolsOptions {
+    /// Create options with the defaults
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of requests sent to the model
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Set options applied to every request in the loop
+    pub fn with_request_options(mut self, options: RequestOptions) -> Self {
+        self.request_options = Some(options);
+        self
+    }
+
+    /// Require approval for the tool calls selected by `policy`
+    pub fn with_approval(mut self, policy: ApprovalPolicy) -> Self {
+        self.approval = Some(policy);
+        self
+    }
+
+    /// Account usage against `budget`, shared with any sub-agents, and stop
+    /// once it is used up
+    pub fn with_budget(mut self, budget: SharedBudget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Stop the run with [`AnthropicError::RunLimitExceeded`] once it passes
+    /// one of `limits`
+    pub fn with_limits(mut self, limits: RunLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+}
+
+/// Per-run caps for [`MessagesApi::run_tools`](crate::api::messages::MessagesApi::run_tools).
+///
+/// Unlike a [`SharedBudget`], these apply only to the run they are set on.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RunLimits {
+    /// Maximum total tokens (input, cache and output) over all requests
+    pub max_total_tokens: Option<u64>,
+    /// Maximum estimated cost in USD; responses from models without known
+    /// pricing count as free
+    pub max_cost_usd: Option<f64>,
+    /// Maximum time from the start of the run, including tool execution
+    pub max_wall_time: Option<Duration>,
+    /// Maximum number of tool calls executed
+    pub max_tool_calls: Option<usize>,
+}
+
+impl RunLimits {
+    /// No limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set [`max_total_tokens`](Self::max_total_tokens)
+    pub fn with_max_total_tokens(mut self, max_total_tokens: u64) -> Self {
+        self.max_total_tokens = Some(max_total_tokens);
+        self
+    }
+
+    /// Set [`max_cost_usd`](Self::max_cost_usd)
+    pub fn with_max_cost_usd(mut self, max_cost_usd: f64) -> Self {
+        self.max_cost_usd = Some(max_cost_usd);
+        self
+    }
+
+    /// Set [`max_wall_time`](Self::max_wall_time)
+    pub fn with_max_wall_time(mut self, max_wall_time: Duration) -> Self {
+        self.max_wall_time = Some(max_wall_time);
+        self
+    }
+
+    /// Set [`max_tool_calls`](Self::max_tool_calls)
+    pub fn with_max_tool_calls(mut self, max_tool_calls: usize) -> Self {
+        self.max_tool_calls = Some(max_tool_calls);
+        self
+    }
+
+    /// The token or cost limit `spent` breaks: one that was exceeded, or one
+    /// that was reached when the run would send another request
+    pub(crate) fn check_usage(&self, spent: &UsageSummary, continuing: bool) -> Option<RunLimit> {
+        let past = |used: f64, limit: f64| used > limit || (continuing && used >= limit);
+        if let Some(limit) = self.max_total_tokens {
+            let used = spent.total_tokens();
+            if past(used as f64, limit as f64) {
+                return Some(RunLimit::Tokens { limit, used });
+            }
+        }
+        if let Some(limit) = self.max_cost_usd {
+            if past(spent.cost_usd, limit) {
+                return Some(RunLimit::CostUsd {
+                    limit,
+                    used: spent.cost_usd,
+                });
+            }
+        }
+        None
+    }
+
+    /// The tool call limit, if running `round` more calls after `executed`
+    /// would pass it
+    pub(crate) fn check_tool_calls(&self, executed: usize, round: usize) -> Option<RunLimit> {
+        let limit = self.max_tool_calls?;
+        (executed + round > limit).then_some(RunLimit::ToolCalls {
+            limit,
+            requested: executed + round,
+        })
+    }
+}
+
+/// The [`RunLimits`] entry a run hit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RunLimit {
+    /// Total token limit
+    Tokens {
+        /// Configured limit
+        limit: u64,
+        /// Tokens used
+        used: u64,
+    },
+    /// Estimated cost limit in USD
+    CostUsd {
+        /// Configured limit
+        limit: f64,
+        /// Estimated spend
+        used: f64,
+    },
+    /// Wall-time limit
+    WallTime {
+        /// Configured limit
+        limit: Duration,
+    },
+    /// Tool call limit
+    ToolCalls {
+        /// Configured limit
+        limit: usize,
+        /// Calls executed plus those in the round that was refused
+        requested: usize,
+    },
+}
+
+impl fmt::Display for RunLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tokens { limit, used } => {
+                write!(f, "used {} tokens of a {} token run limit", used, limit)
+            }
+            Self::CostUsd { limit, used } => {
+                write!(f, "spent ${:.4} of a ${:.4} run limit", used, limit)
+            }
+            Self::WallTime { limit } => write!(f, "ran past the {:?} wall-time limit", limit),
+            Self::ToolCalls { limit, requested } => {
+                write!(
+                    f,
+                    "{} tool calls requested with a limit of {}",
+                    requested, limit
+                )
+            }
+        }
+    }
+}
+
+/// A tool-use loop stopped by its [`RunLimits`], with the progress it made.
+///
+/// Returned boxed in [`AnthropicError::RunLimitExceeded`].
+#[derive(Debug, Clone)]
+pub struct RunLimitExceeded {
+    /// The limit that stopped the run
+    pub limit: RunLimit,
+    /// Last complete response, if any arrived
+    pub response: Option<MessageResponse>,
+    /// Conversation so far. When a round of tool calls was refused or cut
+    /// short, it ends with the assistant turn requesting them.
+    pub messages: Vec<Message>,
+    /// Requests that completed
+    pub iterations: usize,
+    /// Tool calls executed
+    pub tool_calls: usize,
+    /// Usage summed over completed requests
+    pub usage: Usage,
+    /// Time from the start of the run until it stopped
+    pub elapsed: Duration,
+}
+
+/// Result of a tool-use loop
+#[derive(Debug, Clone)]
+pub struct ToolRun {
+    /// Last response from the model
+    pub response: MessageResponse,
+    /// Full conversation: the request's messages, every assistant turn and
+    /// every round of tool results
+    pub messages: Vec<Message>,
+    /// Requests sent to the model
+    pub iterations: usize,
+    /// Tool calls executed
+    pub tool_calls: usize,
+    /// Usage summed over all requests
+    pub usage: Usage,
+}
+
+impl ToolRun {
+    /// Whether the loop stopped because of `max_iterations` while the model
+    /// was still asking for tools
+    pub fn hit_iteration_limit(&self) -> bool {
+        self.response.stop_reason == Some(StopReason::ToolUse)
+    }
+}
+
+/// Deserialize a `tool_use` input for the typed tool `name`
+pub(crate) fn parse_tool_input<I: serde::de::DeserializeOwned>(
+    name: &str,
+    input: serde_json::Value,
+) -> Result<I> {
+    serde_json::from_value(input).map_err(|e| {
+        AnthropicError::invalid_input(format!("Invalid input for tool {}: {}", name, e))
+    })
+}
+
+/// Add `usage` to the running `total`
+pub(crate) fn add_usage(total: &mut Usage, usage: &Usage) {
+    total.input_tokens += usage.input_tokens;
+    total.output_tokens += usage.output_tokens;
+    total.cache_creation_input_tokens += usage.cache_creation_input_tokens;
+    total.cache_read_input_tokens += usage.cache_read_input_tokens;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::common::ToolResultContent;
+    use serde_json::json;
+
+    fn registry() -> ToolRegistry {
+        ToolRegistry::new()
+            .with_tool(
+                Tool::new("add", "Add two numbers", json!({"type": "object"})),
+                |input: serde_json::Value| async move {
+                    let sum = input["a"].as_i64().unwrap_or(0) + input["b"].as_i64().unwrap_or(0);
+                    Ok(ToolOutput::json(json!({"sum": sum})))
+                },
+            )
+            .with_tool(
+                Tool::new("fail", "Always fails", json!({"type": "object"})),
+                |_input: serde_json::Value| async move {
+                    Err(AnthropicError::invalid_input("disk on fire"))
+                },
+            )
+    }
+
+    #[tokio::test]
+    async fn test_call_all_keeps_order_and_reports_failures() {
+        let content = vec![
+            ContentBlock::text("Let me check."),
+            ContentBlock::tool_use("toolu_1", "add", json!({"a": 2, "b": 3})),
+            ContentBlock::tool_use("toolu_2", "fail", json!({})),
+            ContentBlock::tool_use("toolu_3", "missing", json!({})),
+        ];
+        let results = registry().call_all(&content).await;
+        assert_eq!(results.len(), 3);
+
+        let ContentBlock::ToolResult {
+            tool_use_id,
+            content,
+            is_error,
+            ..
+        } = &results[0]
+        else {
+            panic!("expected tool result");
+        };
+        assert_eq!(tool_use_id, "toolu_1");
+        assert_eq!(content, &Some(ToolResultContent::Json(json!({"sum": 5}))));
+        assert_eq!(*is_error, Some(false));
+
+        assert!(matches!(
+            &results[1],
+            ContentBlock::ToolResult { content: Some(ToolResultContent::Text(text)), is_error: Some(true), .. }
+                if text.contains("disk on fire")
+        ));
+        assert!(matches!(
+            &results[2],
+            ContentBlock::ToolResult { content: Some(ToolResultContent::Text(text)), is_error: Some(true), .. }
+                if text == "Unknown tool: missing"
+        ));
+    }
+
+    #[test]
+    fn test_register_replaces_same_name() {
+        let mut tools = registry();
+        tools.register(
+            Tool::new("add", "Add, v2", json!({"type": "object"})),
+            |_input: serde_json::Value| async move { Ok(ToolOutput::text("4")) },
+        );
+        assert_eq!(tools.len(), 2);
+        assert_eq!(
+            tools.definitions()[1].description.as_deref(),
+            Some("Add, v2")
+        );
+        assert_eq!(
+            format!("{:?}", tools),
+            "ToolRegistry { tools: [\"fail\", \"add\"] }"
+        );
+    }
+}
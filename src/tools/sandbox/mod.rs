@@ -0,0 +1,196 @@
+//! Sandboxed execution for shell and code tools
+//!
+//! A [`ToolSandbox`] is a preconfigured program — a shell, an interpreter, a
+//! WASI module — that each tool call runs with its own arguments and stdin.
+//! Every sandbox enforces [`SandboxLimits`]: a wall-clock timeout and a cap on
+//! captured output, plus backend-specific resource limits. Hitting a limit is
+//! reported in the [`ExecOutput`] rather than as an error, so the model can be
+//! told what happened.
+//!
+//! | Backend | Isolation | Feature |
+//! |---------|-----------|---------|
+//! | [`SubprocessSandbox`] | none: cleared environment, scratch directory, rlimits on Unix | always available |
+//! | [`WasmSandbox`] | WASI module in wasmtime, no filesystem or network | `wasmtime` |
+//!
+//! The subprocess backend is a resource-limited runner, not an isolation
+//! boundary: the program can read and write whatever the host user can and
+//! reach the network. Run it inside a container or user namespace when the
+//! tool input is untrusted.
+//!
+//! [`WasmSandbox`]: crate::tools::sandbox::WasmSandbox
+
+mod subprocess;
+#[cfg(feature = "wasmtime")]
+mod wasm;
+
+pub use subprocess::SubprocessSandbox;
+#[cfg(feature = "wasmtime")]
+pub use wasm::WasmSandbox;
+
+use crate::{error::Result, models::common::ContentBlock};
+use futures::future::BoxFuture;
+use std::time::Duration;
+
+/// Resource limits applied to each execution.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SandboxLimits {
+    /// Wall-clock limit; the execution is killed when it expires
+    pub timeout: Duration,
+    /// Maximum bytes captured from each of stdout and stderr
+    pub max_output_bytes: usize,
+    /// Maximum memory in bytes (address space for subprocesses, linear
+    /// memory for WASM)
+    pub max_memory_bytes: Option<u64>,
+    /// Maximum CPU time in seconds (subprocess only)
+    pub max_cpu_secs: Option<u64>,
+    /// Maximum size of any file written, in bytes (subprocess only)
+    pub max_file_size_bytes: Option<u64>,
+    /// Maximum number of open file descriptors (subprocess only)
+    pub max_open_files: Option<u64>,
+}
+
+impl Default for SandboxLimits {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            max_output_bytes: 64 * 1024,
+            max_memory_bytes: Some(512 * 1024 * 1024),
+            max_cpu_secs: Some(30),
+            max_file_size_bytes: Some(16 * 1024 * 1024),
+            max_open_files: Some(64),
+        }
+    }
+}
+
+impl SandboxLimits {
+    /// Set the wall-clock timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the per-stream output cap
+    pub fn with_max_output_bytes(mut self, bytes: usize) -> Self {
+        self.max_output_bytes = bytes;
+        self
+    }
+
+    /// Set the memory limit
+    pub fn with_max_memory_bytes(mut self, bytes: u64) -> Self {
+        self.max_memory_bytes = Some(bytes);
+        self
+    }
+
+    /// Set the CPU time limit
+    pub fn with_max_cpu_secs(mut self, secs: u64) -> Self {
+        self.max_cpu_secs = Some(secs);
+        self
+    }
+}
+
+/// Per-call input for a sandboxed program.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExecRequest {
+    /// Arguments appended to the sandbox's base arguments
+    pub args: Vec<String>,
+    /// Bytes written to stdin
+    pub stdin: Vec<u8>,
+    /// Extra environment variables
+    pub env: Vec<(String, String)>,
+}
+
+impl ExecRequest {
+    /// Request with the given arguments
+    pub fn new<I, S>(args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            args: args.into_iter().map(Into::into).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Set stdin
+    pub fn with_stdin(mut self, stdin: impl Into<Vec<u8>>) -> Self {
+        self.stdin = stdin.into();
+        self
+    }
+
+    /// Add an environment variable
+    pub fn with_env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.push((key.into(), value.into()));
+        self
+    }
+}
+
+/// Result of one sandboxed execution.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecOutput {
+    /// Exit status, or `None` when the program was killed
+    pub exit_code: Option<i32>,
+    /// Captured stdout (lossy UTF-8)
+    pub stdout: String,
+    /// Captured stderr (lossy UTF-8)
+    pub stderr: String,
+    /// Whether either stream exceeded the output cap
+    pub truncated: bool,
+    /// Whether the execution was killed by the timeout
+    pub timed_out: bool,
+    /// Wall-clock run time
+    pub duration: Duration,
+}
+
+impl ExecOutput {
+    /// Whether the program exited with status 0 within its limits
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0) && !self.timed_out
+    }
+
+    /// Render as a `tool_result` block for the model; failures are marked as
+    /// errors.
+    pub fn to_tool_result(&self, tool_use_id: impl Into<String>) -> ContentBlock {
+        let mut text = self.stdout.clone();
+        if !self.stderr.is_empty() {
+            if !text.is_empty() && !text.ends_with('\n') {
+                text.push('\n');
+            }
+            text.push_str("[stderr]\n");
+            text.push_str(&self.stderr);
+        }
+        if self.truncated {
+            text.push_str("\n[output truncated]");
+        }
+        if self.timed_out {
+            text.push_str(&format!(
+                "\n[killed after {:.1}s timeout]",
+                self.duration.as_secs_f64()
+            ));
+        } else if let Some(code) = self.exit_code.filter(|c| *c != 0) {
+            text.push_str(&format!("\n[exit code {}]", code));
+        }
+
+        if self.success() {
+            ContentBlock::tool_result(tool_use_id, Some(text))
+        } else {
+            ContentBlock::tool_error(tool_use_id, text)
+        }
+    }
+}
+
+/// A restricted environment that runs one program per tool call.
+pub trait ToolSandbox: Send + Sync {
+    /// Run the program with the request's arguments and stdin
+    fn execute(&self, request: ExecRequest) -> BoxFuture<'_, Result<ExecOutput>>;
+
+    /// Limits enforced on each execution
+    fn limits(&self) -> &SandboxLimits;
+}
+
+/// Keep at most `cap` bytes; returns whether anything was dropped.
+pub(crate) fn cap_output(mut bytes: Vec<u8>, cap: usize) -> (String, bool) {
+    let truncated = bytes.len() > cap;
+    bytes.truncate(cap);
+    (String::from_utf8_lossy(&bytes).into_owned(), truncated)
+}
@@ -0,0 +1,345 @@
+//! Child-process sandbox
+
+use super::{cap_output, ExecOutput, ExecRequest, SandboxLimits, ToolSandbox};
+use crate::error::{AnthropicError, Result};
+use futures::future::BoxFuture;
+use std::{
+    path::PathBuf,
+    process::Stdio,
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWriteExt},
+    process::Command,
+};
+
+/// How long to wait for output pipes to close after the process is gone
+const PIPE_GRACE: Duration = Duration::from_secs(1);
+
+/// Runs a program as a child process with a cleared environment, a scratch
+/// working directory and, on Unix, rlimits and its own process group.
+///
+/// This limits resources only; it is not an isolation boundary. The program
+/// runs as the host user, with that user's access to the filesystem (the
+/// scratch directory is just its starting point) and to the network. Wrap it
+/// in a container or user namespace, or use a WASI sandbox, for untrusted
+/// input.
+///
+/// ```rust,no_run
+/// use threatflux_anthropic_sdk::tools::{ExecRequest, SubprocessSandbox, ToolSandbox};
+///
+/// # async fn example() -> threatflux_anthropic_sdk::Result<()> {
+/// let shell = SubprocessSandbox::new("/bin/sh").arg("-c");
+/// let output = shell.execute(ExecRequest::new(["echo hello"])).await?;
+/// assert_eq!(output.stdout, "hello\n");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SubprocessSandbox {
+    program: PathBuf,
+    args: Vec<String>,
+    env: Vec<(String, String)>,
+    working_dir: Option<PathBuf>,
+    limits: SandboxLimits,
+}
+
+impl SubprocessSandbox {
+    /// Sandbox for `program` with default limits and `PATH=/usr/bin:/bin`
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            env: vec![("PATH".to_string(), "/usr/bin:/bin".to_string())],
+            working_dir: None,
+            limits: SandboxLimits::default(),
+        }
+    }
+
+    /// Add a base argument passed before each request's arguments
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Set an environment variable (the host environment is never inherited)
+    pub fn env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let key = key.into();
+        self.env.retain(|(k, _)| *k != key);
+        self.env.push((key, value.into()));
+        self
+    }
+
+    /// Run in `dir` instead of a fresh scratch directory per execution
+    pub fn working_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.working_dir = Some(dir.into());
+        self
+    }
+
+    /// Set the resource limits
+    pub fn with_limits(mut self, limits: SandboxLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    async fn run(&self, request: ExecRequest) -> Result<ExecOutput> {
+        let scratch = match &self.working_dir {
+            Some(_) => None,
+            None => Some(ScratchDir::create()?),
+        };
+        let dir = self
+            .working_dir
+            .as_deref()
+            .or(scratch.as_ref().map(|s| s.0.as_path()))
+            .expect("working directory");
+
+        let mut command = Command::new(&self.program);
+        command
+            .args(&self.args)
+            .args(&request.args)
+            .env_clear()
+            .envs(self.env.iter().cloned())
+            .envs(request.env)
+            .current_dir(dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        #[cfg(unix)]
+        {
+            command.process_group(0);
+            let limits = self.limits.clone();
+            // SAFETY: `apply_rlimits` only calls `setrlimit`, which is
+            // async-signal-safe, and does not allocate.
+            unsafe {
+                command.pre_exec(move || apply_rlimits(&limits));
+            }
+        }
+
+        let started = Instant::now();
+        let mut child = command.spawn().map_err(|e| {
+            AnthropicError::invalid_input(format!(
+                "Failed to start sandboxed program {}: {}",
+                self.program.display(),
+                e
+            ))
+        })?;
+
+        let cap = self.limits.max_output_bytes;
+        let stdout = tokio::spawn(read_capped(child.stdout.take().expect("piped"), cap));
+        let stderr = tokio::spawn(read_capped(child.stderr.take().expect("piped"), cap));
+        if let Some(mut stdin) = child.stdin.take() {
+            let input = request.stdin;
+            tokio::spawn(async move {
+                // The program may exit without reading its input.
+                let _ = stdin.write_all(&input).await;
+            });
+        }
+
+        let pid = child.id();
+        let (exit_code, timed_out) =
+            match tokio::time::timeout(self.limits.timeout, child.wait()).await {
+                Ok(status) => (status?.code(), false),
+                Err(_) => {
+                    // Kill the group while its unreaped leader still holds
+                    // the group ID, so the signal cannot reach a recycled one
+                    #[cfg(unix)]
+                    if let Some(pid) = pid {
+                        kill_group(pid);
+                    }
+                    let _ = child.start_kill();
+                    let _ = child.wait().await;
+                    (None, true)
+                }
+            };
+        let duration = started.elapsed();
+        // Take down anything the program left running in its group so the
+        // output pipes close. The group ID stays reserved while any member
+        // is alive.
+        #[cfg(unix)]
+        if let (Some(pid), false) = (pid, timed_out) {
+            kill_group(pid);
+        }
+        #[cfg(not(unix))]
+        let _ = pid;
+
+        let (stdout, out_truncated) = collect(stdout, cap).await;
+        let (stderr, err_truncated) = collect(stderr, cap).await;
+        Ok(ExecOutput {
+            exit_code,
+            stdout,
+            stderr,
+            truncated: out_truncated || err_truncated,
+            timed_out,
+            duration,
+        })
+    }
+}
+
+impl ToolSandbox for SubprocessSandbox {
+    fn execute(&self, request: ExecRequest) -> BoxFuture<'_, Result<ExecOutput>> {
+        Box::pin(self.run(request))
+    }
+
+    fn limits(&self) -> &SandboxLimits {
+        &self.limits
+    }
+}
+
+/// Read everything, keeping at most `cap + 1` bytes so truncation is visible.
+async fn read_capped(mut reader: impl AsyncRead + Unpin, cap: usize) -> Vec<u8> {
+    let mut kept = Vec::new();
+    let mut buf = [0u8; 8192];
+    while let Ok(n) = reader.read(&mut buf).await {
+        if n == 0 {
+            break;
+        }
+        let room = (cap + 1).saturating_sub(kept.len());
+        kept.extend_from_slice(&buf[..n.min(room)]);
+    }
+    kept
+}
+
+async fn collect(task: tokio::task::JoinHandle<Vec<u8>>, cap: usize) -> (String, bool) {
+    let abort = task.abort_handle();
+    match tokio::time::timeout(PIPE_GRACE, task).await {
+        Ok(Ok(bytes)) => cap_output(bytes, cap),
+        _ => {
+            abort.abort();
+            (String::new(), true)
+        }
+    }
+}
+
+#[cfg(unix)]
+fn apply_rlimits(limits: &SandboxLimits) -> std::io::Result<()> {
+    let settings = [
+        (libc::RLIMIT_AS, limits.max_memory_bytes),
+        (libc::RLIMIT_CPU, limits.max_cpu_secs),
+        (libc::RLIMIT_FSIZE, limits.max_file_size_bytes),
+        (libc::RLIMIT_NOFILE, limits.max_open_files),
+    ];
+    for (resource, value) in settings {
+        let Some(value) = value else { continue };
+        let limit = libc::rlimit {
+            rlim_cur: value as libc::rlim_t,
+            rlim_max: value as libc::rlim_t,
+        };
+        // SAFETY: `limit` is a valid rlimit for the duration of the call.
+        if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn kill_group(pid: u32) {
+    // SAFETY: signalling a process group has no memory-safety requirements;
+    // errors (e.g. the group is already gone) are ignored.
+    unsafe {
+        libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+    }
+}
+
+/// Per-execution scratch directory, removed on drop.
+struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    fn create() -> Result<Self> {
+        let path = std::env::temp_dir().join(format!("anthropic-sandbox-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&path)?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn shell() -> SubprocessSandbox {
+        SubprocessSandbox::new("/bin/sh").arg("-c")
+    }
+
+    #[tokio::test]
+    async fn test_runs_with_clean_environment() {
+        let output = shell()
+            .env("GREETING", "hi")
+            .execute(
+                ExecRequest::new(["echo $GREETING ${HOME:-none}; cat; pwd"])
+                    .with_stdin("from stdin\n"),
+            )
+            .await
+            .unwrap();
+        assert!(output.success());
+        let lines: Vec<&str> = output.stdout.lines().collect();
+        assert_eq!(lines[0], "hi none");
+        assert_eq!(lines[1], "from stdin");
+        assert!(lines[2].contains("anthropic-sandbox-"));
+        assert!(!std::path::Path::new(lines[2]).exists());
+    }
+
+    #[tokio::test]
+    async fn test_timeout_and_output_cap() {
+        let sandbox = shell().with_limits(
+            SandboxLimits::default()
+                .with_timeout(Duration::from_millis(300))
+                .with_max_output_bytes(10),
+        );
+
+        let output = sandbox
+            .execute(ExecRequest::new([
+                "sleep 0 & echo 0123456789abcdef; sleep 5",
+            ]))
+            .await
+            .unwrap();
+        assert!(output.timed_out);
+        assert!(output.truncated);
+        assert_eq!(output.stdout, "0123456789");
+        assert!(output.duration < Duration::from_secs(3));
+
+        let block = output.to_tool_result("toolu_1");
+        assert!(matches!(
+            block,
+            crate::models::common::ContentBlock::ToolResult {
+                is_error: Some(true),
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_timeout_kills_process_group() {
+        let dir = tempfile::tempdir().unwrap();
+        let sandbox = shell()
+            .working_dir(dir.path())
+            .with_limits(SandboxLimits::default().with_timeout(Duration::from_millis(300)));
+
+        let output = sandbox
+            .execute(ExecRequest::new([
+                "(sleep 1; touch survived) >/dev/null & sleep 5",
+            ]))
+            .await
+            .unwrap();
+        assert!(output.timed_out);
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert!(!dir.path().join("survived").exists());
+    }
+
+    #[tokio::test]
+    async fn test_exit_code_reported() {
+        let output = shell()
+            .execute(ExecRequest::new(["echo oops >&2; exit 3"]))
+            .await
+            .unwrap();
+        assert_eq!(output.exit_code, Some(3));
+        assert_eq!(output.stderr, "oops\n");
+        assert!(!output.success());
+    }
+}
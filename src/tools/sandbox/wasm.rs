@@ -0,0 +1,267 @@
+//! WASI sandbox on wasmtime (feature `wasmtime`)
+
+use super::{cap_output, ExecOutput, ExecRequest, SandboxLimits, ToolSandbox};
+use crate::error::{AnthropicError, Result};
+use futures::future::BoxFuture;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use wasmtime::{Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Trap};
+use wasmtime_wasi::{
+    pipe::{MemoryInputPipe, MemoryOutputPipe},
+    preview1::{self, WasiP1Ctx},
+    I32Exit, WasiCtxBuilder,
+};
+
+/// Granularity of the timeout; executions are interrupted within one tick
+const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// Runs a WASI (preview 1) command module in wasmtime.
+///
+/// The guest gets its arguments, environment variables and stdin from the
+/// [`ExecRequest`] and nothing else: no preopened directories, no sockets and
+/// no host clock beyond what WASI exposes. Memory is capped by
+/// [`SandboxLimits::max_memory_bytes`]; the CPU, file-size and open-file
+/// limits do not apply.
+///
+/// A guest that writes more than the output cap is stopped and the output is
+/// marked as truncated.
+#[derive(Clone)]
+pub struct WasmSandbox {
+    engine: Engine,
+    module: Module,
+    program_name: String,
+    limits: SandboxLimits,
+    _ticker: Arc<EpochTicker>,
+}
+
+impl std::fmt::Debug for WasmSandbox {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WasmSandbox")
+            .field("program_name", &self.program_name)
+            .field("limits", &self.limits)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WasmSandbox {
+    /// Compile a module from WASM binary (or, with wasmtime's default
+    /// features, WAT text)
+    pub fn new(module: impl AsRef<[u8]>) -> Result<Self> {
+        let mut config = wasmtime::Config::new();
+        config.epoch_interruption(true);
+        let engine = Engine::new(&config)?;
+        let module = Module::new(&engine, module)?;
+        let ticker = Arc::new(EpochTicker::start(engine.clone()));
+        Ok(Self {
+            engine,
+            module,
+            program_name: "tool.wasm".to_string(),
+            limits: SandboxLimits::default(),
+            _ticker: ticker,
+        })
+    }
+
+    /// Compile a module from a file
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let bytes = std::fs::read(path.as_ref()).map_err(|e| {
+            AnthropicError::file_error(format!(
+                "Failed to read WASM module {}: {}",
+                path.as_ref().display(),
+                e
+            ))
+        })?;
+        Self::new(bytes)
+    }
+
+    /// Name passed to the guest as `argv[0]`
+    pub fn program_name(mut self, name: impl Into<String>) -> Self {
+        self.program_name = name.into();
+        self
+    }
+
+    /// Set the resource limits
+    pub fn with_limits(mut self, limits: SandboxLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    fn run_blocking(&self, request: ExecRequest) -> Result<ExecOutput> {
+        // One spare byte of capacity distinguishes "exactly at the cap" from
+        // "over it".
+        let stdout = MemoryOutputPipe::new(self.limits.max_output_bytes + 1);
+        let stderr = MemoryOutputPipe::new(self.limits.max_output_bytes + 1);
+
+        let mut args = vec![self.program_name.clone()];
+        args.extend(request.args);
+        let wasi = WasiCtxBuilder::new()
+            .args(&args)
+            .envs(&request.env)
+            .stdin(MemoryInputPipe::new(request.stdin))
+            .stdout(stdout.clone())
+            .stderr(stderr.clone())
+            .build_p1();
+
+        let mut store_limits = StoreLimitsBuilder::new();
+        if let Some(bytes) = self.limits.max_memory_bytes {
+            store_limits = store_limits.memory_size(usize::try_from(bytes).unwrap_or(usize::MAX));
+        }
+        let mut store = Store::new(
+            &self.engine,
+            GuestState {
+                wasi,
+                limits: store_limits.build(),
+            },
+        );
+        store.limiter(|state| &mut state.limits);
+        let ticks = self.limits.timeout.as_millis() / EPOCH_TICK.as_millis();
+        store.set_epoch_deadline(u64::try_from(ticks).unwrap_or(u64::MAX).max(1));
+
+        let mut linker = Linker::new(&self.engine);
+        preview1::add_to_linker_sync(&mut linker, |state: &mut GuestState| &mut state.wasi)?;
+
+        let started = Instant::now();
+        let outcome = linker
+            .instantiate(&mut store, &self.module)
+            .and_then(|instance| instance.get_typed_func::<(), ()>(&mut store, "_start"))
+            .and_then(|start| start.call(&mut store, ()));
+        let duration = started.elapsed();
+
+        let (mut exit_code, mut timed_out, mut overflow, mut trap) = (Some(0), false, false, None);
+        if let Err(err) = outcome {
+            exit_code = None;
+            if let Some(exit) = err.downcast_ref::<I32Exit>() {
+                exit_code = Some(exit.0);
+            } else if matches!(err.downcast_ref::<Trap>(), Some(Trap::Interrupt)) {
+                timed_out = true;
+            } else if err
+                .chain()
+                .any(|cause| cause.to_string().contains("beyond capacity"))
+            {
+                overflow = true;
+            } else {
+                trap = Some(format!("{:#}", err));
+            }
+        }
+        drop(store);
+
+        let cap = self.limits.max_output_bytes;
+        let (stdout, out_truncated) = cap_output(stdout.contents().to_vec(), cap);
+        let (mut stderr, err_truncated) = cap_output(stderr.contents().to_vec(), cap);
+        if let Some(trap) = trap {
+            if !stderr.is_empty() && !stderr.ends_with('\n') {
+                stderr.push('\n');
+            }
+            stderr.push_str(&format!("[wasm trap] {}", trap));
+        }
+        Ok(ExecOutput {
+            exit_code,
+            stdout,
+            stderr,
+            truncated: overflow || out_truncated || err_truncated,
+            timed_out,
+            duration,
+        })
+    }
+}
+
+impl ToolSandbox for WasmSandbox {
+    fn execute(&self, request: ExecRequest) -> BoxFuture<'_, Result<ExecOutput>> {
+        let sandbox = self.clone();
+        Box::pin(async move {
+            tokio::task::spawn_blocking(move || sandbox.run_blocking(request))
+                .await
+                .map_err(|e| AnthropicError::Unknown(e.into()))?
+        })
+    }
+
+    fn limits(&self) -> &SandboxLimits {
+        &self.limits
+    }
+}
+
+struct GuestState {
+    wasi: WasiP1Ctx,
+    limits: StoreLimits,
+}
+
+/// Background thread advancing the engine epoch so deadlines can fire.
+struct EpochTicker {
+    stop: Arc<AtomicBool>,
+}
+
+impl EpochTicker {
+    fn start(engine: Engine) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let flag = stop.clone();
+        std::thread::Builder::new()
+            .name("wasm-sandbox-epoch".to_string())
+            .spawn(move || {
+                while !flag.load(Ordering::Relaxed) {
+                    std::thread::sleep(EPOCH_TICK);
+                    engine.increment_epoch();
+                }
+            })
+            .expect("failed to spawn epoch ticker thread");
+        Self { stop }
+    }
+}
+
+impl Drop for EpochTicker {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes "hi\n" to stdout
+    const HELLO: &str = r#"
+        (module
+          (import "wasi_snapshot_preview1" "fd_write"
+            (func $fd_write (param i32 i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 16) "hi\n")
+          (func (export "_start")
+            (i32.store (i32.const 0) (i32.const 16))
+            (i32.store (i32.const 4) (i32.const 3))
+            (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))))
+    "#;
+
+    const SPIN: &str = r#"(module (func (export "_start") (loop (br 0))))"#;
+
+    #[tokio::test]
+    async fn test_runs_module() {
+        let sandbox = WasmSandbox::new(HELLO).unwrap();
+        let output = sandbox.execute(ExecRequest::default()).await.unwrap();
+        assert!(output.success());
+        assert_eq!(output.stdout, "hi\n");
+    }
+
+    #[tokio::test]
+    async fn test_output_cap() {
+        let sandbox = WasmSandbox::new(HELLO)
+            .unwrap()
+            .with_limits(SandboxLimits::default().with_max_output_bytes(2));
+        let output = sandbox.execute(ExecRequest::default()).await.unwrap();
+        assert!(output.truncated);
+        assert!(output.stdout.len() <= 2);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_interrupts_guest() {
+        let sandbox = WasmSandbox::new(SPIN)
+            .unwrap()
+            .with_limits(SandboxLimits::default().with_timeout(Duration::from_millis(100)));
+        let output = sandbox.execute(ExecRequest::default()).await.unwrap();
+        assert!(output.timed_out);
+        assert_eq!(output.exit_code, None);
+        assert!(output.duration < Duration::from_secs(5));
+    }
+}
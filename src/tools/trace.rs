@@ -0,0 +1,307 @@
+//! Structured traces of tool-use runs
+//!
+//! [`MessagesApi::run_tools_traced`] records every iteration of the tool-use
+//! loop into an [`AgentTrace`]: a fingerprint of the request sent, the
+//! response, each tool call with its result, and timings. A trace is kept
+//! even when the run fails, can be saved as JSON, printed as a
+//! [`timeline`](AgentTrace::timeline), and [replayed](AgentTrace::replay)
+//! offline: the recorded responses stand in for the model while the current
+//! tool handlers run again, and every point where the run no longer matches
+//! the recording is reported.
+//!
+//! ```rust,no_run
+//! use threatflux_anthropic_sdk::{
+//!     models::MessageRequest,
+//!     tools::{AgentTrace, RunToolsOptions, ToolRegistry},
+//!     Client,
+//! };
+//!
+//! # async fn example(tools: ToolRegistry) -> threatflux_anthropic_sdk::Result<()> {
+//! let client = Client::from_env()?;
+//! let request = MessageRequest::new()
+//!     .max_tokens(1024)
+//!     .add_user_message("Book me a table for two");
+//! let (trace, result) = client
+//!     .messages()
+//!     .run_tools_traced(request, &tools, RunToolsOptions::default())
+//!     .await;
+//! if result.is_err() {
+//!     std::fs::write("failed_run.json", trace.to_json()?)?;
+//! }
+//!
+//! // Later, after fixing a handler:
+//! let trace = AgentTrace::from_json(&std::fs::read_to_string("failed_run.json")?)?;
+//! println!("{}", trace.timeline());
+//! let report = trace.replay(&tools, None).await?;
+//! for divergence in &report.divergences {
+//!     println!("{:?}", divergence);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`MessagesApi::run_tools_traced`]: crate::api::messages::MessagesApi::run_tools_traced
+
+use super::{approval::ApprovalPolicy, registry::ToolRegistry};
+use crate::{
+    error::Result,
+    models::{
+        common::{ContentBlock, Role},
+        message::{Message, MessageRequest, MessageResponse},
+    },
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// One executed tool call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCallTrace {
+    /// `tool_use` block id
+    pub tool_use_id: String,
+    /// Tool name
+    pub name: String,
+    /// Model-supplied input
+    pub input: serde_json::Value,
+    /// `tool_result` block sent back to the model
+    pub result: ContentBlock,
+    /// Wall time of the call, including any approval wait
+    pub duration_ms: u64,
+}
+
+impl ToolCallTrace {
+    /// Whether the result was an error
+    pub fn is_error(&self) -> bool {
+        matches!(
+            self.result,
+            ContentBlock::ToolResult {
+                is_error: Some(true),
+                ..
+            }
+        )
+    }
+}
+
+/// One model round trip and the tool calls it triggered
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentIteration {
+    /// [`MessageRequest::fingerprint`] of the request sent
+    pub request_hash: String,
+    /// When the request was sent
+    pub started_at: DateTime<Utc>,
+    /// Time until the response arrived
+    pub latency_ms: u64,
+    /// The model's response
+    pub response: MessageResponse,
+    /// Tool calls run on the response, in block order
+    #[serde(default)]
+    pub tool_calls: Vec<ToolCallTrace>,
+}
+
+/// Record of a tool-use run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentTrace {
+    /// The first request, with the registry's tool definitions merged in
+    pub request: MessageRequest,
+    /// When the run started
+    pub started_at: DateTime<Utc>,
+    /// When the run ended
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Iterations, in order
+    #[serde(default)]
+    pub iterations: Vec<AgentIteration>,
+    /// Error that ended the run, if it failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl AgentTrace {
+    /// Start a trace for `request`
+    pub fn new(request: MessageRequest) -> Self {
+        Self {
+            request,
+            started_at: Utc::now(),
+            finished_at: None,
+            iterations: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Whether the run ended with an error
+    pub fn is_failed(&self) -> bool {
+        self.error.is_some()
+    }
+
+    /// Every tool call across all iterations
+    pub fn tool_calls(&self) -> impl Iterator<Item = &ToolCallTrace> {
+        self.iterations
+            .iter()
+            .flat_map(|iteration| &iteration.tool_calls)
+    }
+
+    /// Serialize as pretty-printed JSON
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Load a trace saved with [`to_json`](Self::to_json)
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Human-readable timeline, one line per model call and per tool call
+    pub fn timeline(&self) -> String {
+        let mut out = format!(
+            "run started {} ({} iterations, {} tool calls)\n",
+            self.started_at.to_rfc3339(),
+            self.iterations.len(),
+            self.tool_calls().count()
+        );
+        for (index, iteration) in self.iterations.iter().enumerate() {
+            let offset = (iteration.started_at - self.started_at).num_milliseconds();
+            let response = &iteration.response;
+            let _ = writeln!(
+                out,
+                "[{}] +{}ms {} {}ms stop={} tokens={}/{} request={}",
+                index + 1,
+                offset,
+                response.model,
+                iteration.latency_ms,
+                response
+                    .stop_reason
+                    .as_ref()
+                    .map(|reason| format!("{:?}", reason))
+                    .unwrap_or_else(|| "none".to_string()),
+                response.usage.input_tokens,
+                response.usage.output_tokens,
+                &iteration.request_hash[..iteration.request_hash.len().min(12)]
+            );
+            for call in &iteration.tool_calls {
+                let _ = writeln!(
+                    out,
+                    "    {} {} {}ms {}",
+                    call.name,
+                    call.tool_use_id,
+                    call.duration_ms,
+                    if call.is_error() { "error" } else { "ok" }
+                );
+            }
+        }
+        match (&self.error, self.finished_at) {
+            (Some(error), _) => {
+                let _ = writeln!(out, "failed: {}", error);
+            }
+            (None, Some(finished)) => {
+                let _ = writeln!(
+                    out,
+                    "finished after {}ms",
+                    (finished - self.started_at).num_milliseconds()
+                );
+            }
+            (None, None) => {}
+        }
+        out
+    }
+
+    /// Re-run the recorded tool calls against `tools` without calling the
+    /// API.
+    ///
+    /// The recorded responses play the model. Each rebuilt request is
+    /// fingerprinted and compared with the recording, and each tool result is
+    /// compared with the recorded one. The recorded results, not the new
+    /// ones, are fed forward, so a single changed tool does not make every
+    /// later request diverge.
+    pub async fn replay(
+        &self,
+        tools: &ToolRegistry,
+        approval: Option<&ApprovalPolicy>,
+    ) -> Result<ReplayReport> {
+        let mut request = self.request.clone();
+        let mut divergences = Vec::new();
+
+        for (index, iteration) in self.iterations.iter().enumerate() {
+            let hash = request.fingerprint()?;
+            if hash != iteration.request_hash {
+                divergences.push(Divergence::Request {
+                    iteration: index + 1,
+                    expected: iteration.request_hash.clone(),
+                    actual: hash,
+                });
+            }
+            request.messages.push(Message::new(
+                Role::Assistant,
+                iteration.response.content.clone(),
+            ));
+            if iteration.tool_calls.is_empty() {
+                continue;
+            }
+
+            let results = tools.run_calls(&iteration.response.content, approval).await;
+            for (call, (result, _)) in iteration.tool_calls.iter().zip(results) {
+                if result != call.result {
+                    divergences.push(Divergence::ToolResult {
+                        iteration: index + 1,
+                        tool_use_id: call.tool_use_id.clone(),
+                        expected: Box::new(call.result.clone()),
+                        actual: Box::new(result),
+                    });
+                }
+            }
+            request.messages.push(Message::new(
+                Role::User,
+                iteration
+                    .tool_calls
+                    .iter()
+                    .map(|call| call.result.clone())
+                    .collect(),
+            ));
+        }
+
+        Ok(ReplayReport {
+            iterations: self.iterations.len(),
+            divergences,
+        })
+    }
+}
+
+/// A point where a replay no longer matches its trace
+#[derive(Debug, Clone, PartialEq)]
+pub enum Divergence {
+    /// The rebuilt request differs from the one recorded
+    Request {
+        /// 1-based iteration
+        iteration: usize,
+        /// Recorded fingerprint
+        expected: String,
+        /// Fingerprint of the rebuilt request
+        actual: String,
+    },
+    /// A tool returned something other than the recorded result
+    ToolResult {
+        /// 1-based iteration
+        iteration: usize,
+        /// `tool_use` block id
+        tool_use_id: String,
+        /// Recorded result
+        expected: Box<ContentBlock>,
+        /// Result from the current handler
+        actual: Box<ContentBlock>,
+    },
+}
+
+/// Outcome of [`AgentTrace::replay`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayReport {
+    /// Iterations replayed
+    pub iterations: usize,
+    /// Mismatches, in order
+    pub divergences: Vec<Divergence>,
+}
+
+impl ReplayReport {
+    /// Whether the replay matched the trace exactly
+    pub fn is_faithful(&self) -> bool {
+        self.divergences.is_empty()
+    }
+}
@@ -0,0 +1,262 @@
+//! Strongly typed tools with derived input schemas
+//!
+//! With the `schemars` feature, a tool's input can be a plain Rust struct
+//! deriving `Deserialize` and [`JsonSchema`]. The SDK builds `input_schema`
+//! from the type and deserializes each `tool_use` input into it before calling
+//! the handler, so handlers never touch raw JSON. Inputs that do not match the
+//! type are answered with an error result and the handler is not called.
+//!
+//! ```rust,no_run
+//! use schemars::JsonSchema;
+//! use serde::Deserialize;
+//! use threatflux_anthropic_sdk::{
+//!     builders::MessageBuilder,
+//!     tools::{ToolOutput, ToolRegistry},
+//! };
+//!
+//! /// Arguments for `get_weather`
+//! #[derive(Deserialize, JsonSchema)]
+//! struct WeatherInput {
+//!     /// City name, e.g. "Paris"
+//!     city: String,
+//!     /// Temperature unit; defaults to celsius
+//!     unit: Option<String>,
+//! }
+//!
+//! let tools = ToolRegistry::new().with_typed(
+//!     "get_weather",
+//!     "Current weather for a city",
+//!     |input: WeatherInput| async move {
+//!         let unit = input.unit.as_deref().unwrap_or("celsius");
+//!         Ok(ToolOutput::text(format!("22 degrees {} in {}", unit, input.city)))
+//!     },
+//! );
+//!
+//! let request = MessageBuilder::new()
+//!     .user("What's the weather in Paris?")
+//!     .tools(tools.tools())
+//!     .build();
+//! ```
+
+use super::registry::{parse_tool_input, ToolOutput, ToolRegistry};
+use crate::{error::Result, models::common::Tool};
+use futures::future::{BoxFuture, FutureExt};
+use schemars::{generate::SchemaSettings, JsonSchema};
+use serde::de::DeserializeOwned;
+use std::{future::Future, sync::Arc};
+
+/// JSON Schema for `I`, shaped for a tool's `input_schema`.
+///
+/// Subschemas are inlined where possible and the `$schema` and `title`
+/// keywords are dropped, since the API only needs the object description.
+pub fn input_schema<I: JsonSchema>() -> serde_json::Value {
+    let schema = SchemaSettings::draft2020_12()
+        .with(|settings| settings.inline_subschemas = true)
+        .into_generator()
+        .into_root_schema_for::<I>();
+    let mut value = schema.to_value();
+    if let Some(object) = value.as_object_mut() {
+        object.remove("$schema");
+        object.remove("title");
+    }
+    value
+}
+
+impl Tool {
+    /// Custom tool whose `input_schema` is derived from `I`
+    pub fn typed<I: JsonSchema>(name: impl Into<String>, description: impl Into<String>) -> Self {
+        Self::new(name, description, input_schema::<I>())
+    }
+}
+
+/// A tool implemented as a type rather than a closure
+pub trait TypedTool: Send + Sync + 'static {
+    /// Input the model must supply
+    type Input: DeserializeOwned + JsonSchema + Send + 'static;
+
+    /// Tool name
+    fn name(&self) -> String;
+
+    /// Description shown to the model
+    fn description(&self) -> String;
+
+    /// Run the tool
+    fn call(&self, input: Self::Input) -> BoxFuture<'static, Result<ToolOutput>>;
+
+    /// Tool definition with the derived input schema
+    fn definition(&self) -> Tool {
+        Tool::typed::<Self::Input>(self.name(), self.description())
+    }
+}
+
+impl ToolRegistry {
+    /// Register an async closure taking a typed input; the tool's schema is
+    /// derived from `I`
+    pub fn register_typed<I, F, Fut>(
+        &mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        handler: F,
+    ) where
+        I: DeserializeOwned + JsonSchema + Send + 'static,
+        F: Fn(I) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<ToolOutput>> + Send + 'static,
+    {
+        let tool = Tool::typed::<I>(name, description);
+        let name = tool.name.clone();
+        self.register(
+            tool,
+            move |input: serde_json::Value| match parse_tool_input::<I>(&name, input) {
+                Ok(input) => handler(input).boxed(),
+                Err(err) => futures::future::ready(Err(err)).boxed(),
+            },
+        );
+    }
+
+    /// Builder form of [`register_typed`](Self::register_typed)
+    pub fn with_typed<I, F, Fut>(
+        mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        handler: F,
+    ) -> Self
+    where
+        I: DeserializeOwned + JsonSchema + Send + 'static,
+        F: Fn(I) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<ToolOutput>> + Send + 'static,
+    {
+        self.register_typed(name, description, handler);
+        self
+    }
+
+    /// Register a [`TypedTool`] implementation
+    pub fn register_tool<T: TypedTool>(&mut self, tool: T) {
+        let definition = tool.definition();
+        let name = definition.name.clone();
+        let tool = Arc::new(tool);
+        self.register(
+            definition,
+            move |input: serde_json::Value| match parse_tool_input::<T::Input>(&name, input) {
+                Ok(input) => tool.call(input),
+                Err(err) => futures::future::ready(Err(err)).boxed(),
+            },
+        );
+    }
+
+    /// Builder form of [`register_tool`](Self::register_tool)
+    pub fn with_typed_tool<T: TypedTool>(mut self, tool: T) -> Self {
+        self.register_tool(tool);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::common::{ContentBlock, ToolResultContent};
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[allow(dead_code)]
+    #[derive(Deserialize, JsonSchema)]
+    #[serde(rename_all = "snake_case")]
+    enum Op {
+        Add,
+        Multiply,
+    }
+
+    /// Arithmetic on two integers
+    #[derive(Deserialize, JsonSchema)]
+    struct CalcInput {
+        /// Operation to apply
+        op: Op,
+        a: i64,
+        b: i64,
+    }
+
+    #[derive(Deserialize, JsonSchema)]
+    struct ShoutInput {
+        text: String,
+    }
+
+    struct Calculator;
+
+    impl TypedTool for Calculator {
+        type Input = CalcInput;
+
+        fn name(&self) -> String {
+            "calc".to_string()
+        }
+
+        fn description(&self) -> String {
+            "Integer arithmetic".to_string()
+        }
+
+        fn call(&self, input: CalcInput) -> BoxFuture<'static, Result<ToolOutput>> {
+            let value = match input.op {
+                Op::Add => input.a + input.b,
+                Op::Multiply => input.a * input.b,
+            };
+            async move { Ok(ToolOutput::json(json!({ "value": value }))) }.boxed()
+        }
+    }
+
+    #[test]
+    fn test_schema_is_derived_from_input_type() {
+        let schema = input_schema::<CalcInput>();
+        assert_eq!(schema["type"], "object");
+        assert!(schema.get("$schema").is_none());
+        assert!(schema.get("title").is_none());
+        assert_eq!(
+            schema["properties"]["op"]["enum"],
+            json!(["add", "multiply"])
+        );
+        assert_eq!(
+            schema["properties"]["op"]["description"],
+            "Operation to apply"
+        );
+        assert_eq!(schema["required"], json!(["op", "a", "b"]));
+    }
+
+    #[tokio::test]
+    async fn test_typed_dispatch_deserializes_input() {
+        let tools = ToolRegistry::new().with_typed_tool(Calculator).with_typed(
+            "shout",
+            "Upper-case text",
+            |input: ShoutInput| async move { Ok(ToolOutput::text(input.text.to_uppercase())) },
+        );
+        assert_eq!(tools.tools().len(), 2);
+
+        let results = tools
+            .call_all(&[
+                ContentBlock::tool_use("t1", "calc", json!({"op": "multiply", "a": 6, "b": 7})),
+                ContentBlock::tool_use("t2", "shout", json!({"text": "hi"})),
+                ContentBlock::tool_use("t3", "calc", json!({"op": "divide", "a": 1, "b": 0})),
+            ])
+            .await;
+        let contents: Vec<_> = results
+            .iter()
+            .map(|block| match block {
+                ContentBlock::ToolResult {
+                    content, is_error, ..
+                } => (content.clone(), *is_error),
+                _ => panic!("expected tool result"),
+            })
+            .collect();
+        assert_eq!(
+            contents[0],
+            (
+                Some(ToolResultContent::Json(json!({"value": 42}))),
+                Some(false)
+            )
+        );
+        assert_eq!(
+            contents[1],
+            (Some(ToolResultContent::Text("HI".to_string())), Some(false))
+        );
+        assert!(matches!(
+            &contents[2],
+            (Some(ToolResultContent::Text(text)), Some(true)) if text.contains("Invalid input for tool calc")
+        ));
+    }
+}
@@ -1,7 +1,7 @@
 //! Common types and utilities
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt, marker::PhantomData};
 
 /// HTTP method enum
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -48,6 +48,19 @@ pub struct RequestOptions {
     pub enable_skills_api: bool,
     /// Additional beta features to enable (will be comma-joined)
     pub beta_features: Vec<String>,
+    /// End user this request is made for; overrides the ambient context
+    pub user_context: Option<crate::user_context::UserContext>,
+    /// Priority of this request; overrides the ambient
+    /// [`PriorityContext`](crate::priority::PriorityContext)
+    pub priority: Option<RequestPriority>,
+    /// Time this request must finish by, retries included
+    pub deadline: Option<std::time::Instant>,
+    /// Skip every configured response post-processor
+    pub skip_postprocessing: bool,
+    /// Names of configured response post-processors to skip
+    pub skip_postprocessors: Vec<String>,
+    /// Abort the request, its retries and its stream when cancelled
+    pub cancel: Option<tokio_util::sync::CancellationToken>,
 }
 
 impl RequestOptions {
@@ -68,6 +81,53 @@ impl RequestOptions {
         self
     }
 
+    /// Make this request on behalf of `context` instead of the ambient
+    /// [`UserContext`](crate::user_context::UserContext)
+    pub fn with_user_context(mut self, context: crate::user_context::UserContext) -> Self {
+        self.user_context = Some(context);
+        self
+    }
+
+    /// Set the priority, instead of the ambient one
+    pub fn with_priority(mut self, priority: RequestPriority) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Set a deadline, instead of the ambient one. It bounds the whole call,
+    /// including retries and the backoff between them.
+    pub fn with_deadline(mut self, deadline: std::time::Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Set the deadline `budget` from now; the call fails with
+    /// [`AnthropicError::Timeout`](crate::error::AnthropicError::Timeout) if
+    /// all its attempts have not finished by then
+    pub fn with_time_budget(self, budget: std::time::Duration) -> Self {
+        self.with_deadline(std::time::Instant::now() + budget)
+    }
+
+    /// Fail with [`AnthropicError::Cancelled`](crate::error::AnthropicError::Cancelled)
+    /// as soon as `token` is cancelled, whether the request is queued, in
+    /// flight, backing off between retries or streaming
+    pub fn with_cancellation(mut self, token: tokio_util::sync::CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Return the response without running the configured post-processors
+    pub fn without_postprocessing(mut self) -> Self {
+        self.skip_postprocessing = true;
+        self
+    }
+
+    /// Skip the configured post-processor called `name`
+    pub fn without_postprocessor(mut self, name: impl Into<String>) -> Self {
+        self.skip_postprocessors.push(name.into());
+        self
+    }
+
     /// Disable retries
     pub fn no_retry(mut self) -> Self {
         self.no_retry = true;
@@ -161,18 +221,136 @@ impl RequestOptions {
     }
 }
 
-/// Pagination parameters
-#[derive(Debug, Clone, Serialize)]
-pub struct Pagination {
+/// Pagination cursor for listings of `T`
+///
+/// Cursors are the IDs of listed items. Tying each to its resource type means
+/// a cursor taken from a file listing cannot be passed to a batch listing;
+/// cursors kept as plain strings convert with [`Cursor::new`] or `into()`.
+pub struct Cursor<T> {
+    id: String,
+    resource: PhantomData<fn() -> T>,
+}
+
+impl<T> Cursor<T> {
+    /// Cursor for the item with `id`
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            resource: PhantomData,
+        }
+    }
+
+    /// The raw cursor string
+    pub fn as_str(&self) -> &str {
+        &self.id
+    }
+
+    /// The raw cursor string, consuming the cursor
+    pub fn into_inner(self) -> String {
+        self.id
+    }
+
+    /// The same cursor for a listing that names the resource differently
+    pub(crate) fn cast<U>(self) -> Cursor<U> {
+        Cursor::new(self.id)
+    }
+}
+
+// Manual impls so `T` needs none of these traits
+
+impl<T> Clone for Cursor<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.id.clone())
+    }
+}
+
+impl<T> PartialEq for Cursor<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T> Eq for Cursor<T> {}
+
+impl<T> std::hash::Hash for Cursor<T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl<T> Default for Cursor<T> {
+    fn default() -> Self {
+        Self::new(String::new())
+    }
+}
+
+impl<T> fmt::Debug for Cursor<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Cursor").field(&self.id).finish()
+    }
+}
+
+impl<T> fmt::Display for Cursor<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.id)
+    }
+}
+
+impl<T> From<String> for Cursor<T> {
+    fn from(id: String) -> Self {
+        Self::new(id)
+    }
+}
+
+impl<T> From<&str> for Cursor<T> {
+    fn from(id: &str) -> Self {
+        Self::new(id)
+    }
+}
+
+impl<T> From<Cursor<T>> for String {
+    fn from(cursor: Cursor<T>) -> Self {
+        cursor.id
+    }
+}
+
+impl<T> PartialEq<str> for Cursor<T> {
+    fn eq(&self, other: &str) -> bool {
+        self.id == other
+    }
+}
+
+impl<T> PartialEq<&str> for Cursor<T> {
+    fn eq(&self, other: &&str) -> bool {
+        self.id == *other
+    }
+}
+
+impl<T> Serialize for Cursor<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.id)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Cursor<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::new)
+    }
+}
+
+/// Pagination parameters for listings of `T`
+#[derive(Serialize)]
+#[serde(bound = "")]
+pub struct Pagination<T> {
     /// Number of items to return per page
     pub limit: Option<u32>,
     /// Cursor for pagination
-    pub after: Option<String>,
+    pub after: Option<Cursor<T>>,
     /// Cursor for reverse pagination
-    pub before: Option<String>,
+    pub before: Option<Cursor<T>>,
 }
 
-impl Default for Pagination {
+impl<T> Default for Pagination<T> {
     fn default() -> Self {
         Self {
             limit: Some(20),
@@ -182,7 +360,27 @@ impl Default for Pagination {
     }
 }
 
-impl Pagination {
+impl<T> Clone for Pagination<T> {
+    fn clone(&self) -> Self {
+        Self {
+            limit: self.limit,
+            after: self.after.clone(),
+            before: self.before.clone(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for Pagination<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pagination")
+            .field("limit", &self.limit)
+            .field("after", &self.after)
+            .field("before", &self.before)
+            .finish()
+    }
+}
+
+impl<T> Pagination<T> {
     /// Create new pagination with default limit
     pub fn new() -> Self {
         Self::default()
@@ -195,13 +393,13 @@ impl Pagination {
     }
 
     /// Set the after cursor
-    pub fn with_after(mut self, after: impl Into<String>) -> Self {
+    pub fn with_after(mut self, after: impl Into<Cursor<T>>) -> Self {
         self.after = Some(after.into());
         self
     }
 
     /// Set the before cursor
-    pub fn with_before(mut self, before: impl Into<String>) -> Self {
+    pub fn with_before(mut self, before: impl Into<Cursor<T>>) -> Self {
         self.before = Some(before.into());
         self
     }
@@ -215,9 +413,20 @@ pub struct PaginatedResponse<T> {
     /// Whether there are more items available
     pub has_more: bool,
     /// The cursor for the first item
-    pub first_id: Option<String>,
+    pub first_id: Option<Cursor<T>>,
     /// The cursor for the last item
-    pub last_id: Option<String>,
+    pub last_id: Option<Cursor<T>>,
+}
+
+impl<T> PaginatedResponse<T> {
+    /// Parameters for the page after this one, or `None` on the last page
+    pub fn next_page_params(&self) -> Option<Pagination<T>> {
+        if !self.has_more {
+            return None;
+        }
+        let last_id = self.last_id.clone()?;
+        Some(Pagination::new().with_after(last_id))
+    }
 }
 
 /// API error response structure
@@ -256,6 +465,19 @@ pub enum RequestPriority {
     High,
 }
 
+/// How many requests a fan-out helper such as
+/// [`MessagesApi::create_many`](crate::api::messages::MessagesApi::create_many)
+/// keeps in flight at once (at least one)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Concurrency(pub usize);
+
+impl Concurrency {
+    /// The limit, treating zero as one
+    pub fn get(self) -> usize {
+        self.0.max(1)
+    }
+}
+
 /// Stream event type
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum StreamEventType {
@@ -0,0 +1,276 @@
+//! End-user context passthrough
+//!
+//! Services that call Claude on behalf of their own users usually want the
+//! caller's identity on every request: as `metadata.user_id` for Anthropic's
+//! abuse detection, and as headers for a gateway or proxy in front of the API.
+//! A [`UserContext`] carries the user id, organisation id and geography.
+//!
+//! A context is picked up from, in order:
+//!
+//! 1. [`RequestOptions::with_user_context`](crate::types::RequestOptions::with_user_context)
+//!    on a single call, or
+//! 2. the ambient context set with [`UserContext::scope`], so middleware can
+//!    set it once per incoming request.
+//!
+//! Messages requests without a `metadata.user_id` get the context's user id.
+//! Headers are only sent when the client is configured with
+//! [`Config::with_user_context_headers`](crate::Config::with_user_context_headers).
+//!
+//! ```rust,no_run
+//! use threatflux_anthropic_sdk::{
+//!     models::MessageRequest,
+//!     user_context::{UserContext, UserContextHeaders},
+//!     Client, Config,
+//! };
+//!
+//! # async fn handle(request: http::Request<()>) -> threatflux_anthropic_sdk::Result<()> {
+//! let config = Config::from_env()?.with_user_context_headers(UserContextHeaders::default());
+//! let client = Client::new(config);
+//!
+//! // Set by an auth layer as a request extension, or forwarded by a gateway
+//! // in the x-end-user-* headers.
+//! let user = UserContext::from_request(&request, &UserContextHeaders::default())
+//!     .unwrap_or_default();
+//!
+//! user.scope(async {
+//!     let message = MessageRequest::new().max_tokens(256).add_user_message("Hi!");
+//!     client.messages().create(message, None).await
+//! })
+//! .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! gRPC servers can use [`UserContext::from_headers`] on the request's
+//! metadata converted to an [`http::HeaderMap`].
+
+use crate::{models::common::Metadata, types::RequestOptions};
+use http::{request::Parts, Extensions, HeaderMap, Request};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+
+/// Default header for [`UserContext::user_id`]
+pub const USER_ID_HEADER: &str = "x-end-user-id";
+/// Default header for [`UserContext::org_id`]
+pub const ORG_ID_HEADER: &str = "x-end-user-org-id";
+/// Default header for [`UserContext::geo`]
+pub const GEO_HEADER: &str = "x-end-user-geo";
+
+tokio::task_local! {
+    static CURRENT: UserContext;
+}
+
+/// Who a request is made on behalf of
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct UserContext {
+    /// Opaque end-user identifier; avoid names, emails and other PII
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    /// The user's organisation or tenant
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org_id: Option<String>,
+    /// Geography, e.g. an ISO 3166 country or region code
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub geo: Option<String>,
+}
+
+impl UserContext {
+    /// Empty context
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the user id
+    pub fn with_user_id(mut self, user_id: impl Into<String>) -> Self {
+        self.user_id = Some(user_id.into());
+        self
+    }
+
+    /// Set the organisation id
+    pub fn with_org_id(mut self, org_id: impl Into<String>) -> Self {
+        self.org_id = Some(org_id.into());
+        self
+    }
+
+    /// Set the geography
+    pub fn with_geo(mut self, geo: impl Into<String>) -> Self {
+        self.geo = Some(geo.into());
+        self
+    }
+
+    /// Whether no field is set
+    pub fn is_empty(&self) -> bool {
+        self.user_id.is_none() && self.org_id.is_none() && self.geo.is_none()
+    }
+
+    /// The ambient context set by an enclosing [`scope`](Self::scope)
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(Clone::clone).ok()
+    }
+
+    /// Run `future` with this context as the ambient one. Requests made
+    /// inside it, including from spawned tool calls awaited within, use it
+    /// unless their options carry their own.
+    pub fn scope<F: Future>(self, future: F) -> impl Future<Output = F::Output> {
+        CURRENT.scope(self, future)
+    }
+
+    /// A context stored as a request extension, e.g. by an auth middleware
+    /// in axum, tower or hyper
+    pub fn from_extensions(extensions: &Extensions) -> Option<Self> {
+        extensions.get::<Self>().cloned()
+    }
+
+    /// A context forwarded in headers, or `None` when none of them is present
+    pub fn from_headers(headers: &HeaderMap, names: &UserContextHeaders) -> Option<Self> {
+        let read = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+        let context = Self {
+            user_id: read(&names.user_id),
+            org_id: read(&names.org_id),
+            geo: read(&names.geo),
+        };
+        (!context.is_empty()).then_some(context)
+    }
+
+    /// The context of an incoming request: its extension if one was set,
+    /// otherwise its forwarded headers
+    pub fn from_request_parts(parts: &Parts, names: &UserContextHeaders) -> Option<Self> {
+        Self::from_extensions(&parts.extensions)
+            .or_else(|| Self::from_headers(&parts.headers, names))
+    }
+
+    /// [`from_request_parts`](Self::from_request_parts) for a whole request
+    pub fn from_request<B>(request: &Request<B>, names: &UserContextHeaders) -> Option<Self> {
+        Self::from_extensions(request.extensions())
+            .or_else(|| Self::from_headers(request.headers(), names))
+    }
+
+    /// Messages API metadata carrying the user id
+    pub fn metadata(&self) -> Option<Metadata> {
+        self.user_id
+            .as_ref()
+            .map(|user_id| Metadata::new().with_user_id(user_id))
+    }
+
+    /// The context for a request: its own, else the ambient one
+    pub(crate) fn resolve(options: &Option<RequestOptions>) -> Option<Self> {
+        options
+            .as_ref()
+            .and_then(|options| options.user_context.clone())
+            .or_else(Self::current)
+    }
+}
+
+/// Header names used to forward a [`UserContext`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserContextHeaders {
+    /// Header for the user id
+    pub user_id: String,
+    /// Header for the organisation id
+    pub org_id: String,
+    /// Header for the geography
+    pub geo: String,
+}
+
+impl Default for UserContextHeaders {
+    fn default() -> Self {
+        Self {
+            user_id: USER_ID_HEADER.to_string(),
+            org_id: ORG_ID_HEADER.to_string(),
+            geo: GEO_HEADER.to_string(),
+        }
+    }
+}
+
+impl UserContextHeaders {
+    /// Set the user id header
+    pub fn with_user_id_header(mut self, name: impl Into<String>) -> Self {
+        self.user_id = name.into();
+        self
+    }
+
+    /// Set the organisation id header
+    pub fn with_org_id_header(mut self, name: impl Into<String>) -> Self {
+        self.org_id = name.into();
+        self
+    }
+
+    /// Set the geography header
+    pub fn with_geo_header(mut self, name: impl Into<String>) -> Self {
+        self.geo = name.into();
+        self
+    }
+
+    /// Header name and value for each field set in `context`
+    pub(crate) fn pairs<'a>(&'a self, context: &'a UserContext) -> Vec<(&'a str, &'a str)> {
+        [
+            (&self.user_id, &context.user_id),
+            (&self.org_id, &context.org_id),
+            (&self.geo, &context.geo),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name.as_str(), value.as_deref()?)))
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_request_prefers_extension() {
+        let names = UserContextHeaders::default();
+        let mut request = Request::builder()
+            .header(USER_ID_HEADER, "u-header")
+            .header(GEO_HEADER, " US-CA ")
+            .body(())
+            .unwrap();
+
+        let forwarded = UserContext::from_request(&request, &names).unwrap();
+        assert_eq!(forwarded.user_id.as_deref(), Some("u-header"));
+        assert_eq!(forwarded.geo.as_deref(), Some("US-CA"));
+        assert!(forwarded.org_id.is_none());
+
+        request.extensions_mut().insert(
+            UserContext::new()
+                .with_user_id("u-auth")
+                .with_org_id("acme"),
+        );
+        let (parts, ()) = request.into_parts();
+        let context = UserContext::from_request_parts(&parts, &names).unwrap();
+        assert_eq!(context.user_id.as_deref(), Some("u-auth"));
+        assert_eq!(
+            names.pairs(&context),
+            [(USER_ID_HEADER, "u-auth"), (ORG_ID_HEADER, "acme")]
+        );
+
+        let empty = Request::builder().body(()).unwrap();
+        assert!(UserContext::from_request(&empty, &names).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_options_override_ambient_context() {
+        assert!(UserContext::current().is_none());
+        let ambient = UserContext::new().with_user_id("ambient");
+        ambient
+            .clone()
+            .scope(async {
+                assert_eq!(UserContext::resolve(&None), Some(ambient.clone()));
+                let options =
+                    RequestOptions::new().with_user_context(UserContext::new().with_geo("EU"));
+                assert_eq!(
+                    UserContext::resolve(&Some(options)).unwrap().geo.as_deref(),
+                    Some("EU")
+                );
+            })
+            .await;
+    }
+}
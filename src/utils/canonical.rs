@@ -0,0 +1,136 @@
+//! Deterministic canonical JSON
+//!
+//! The output is stable across runs, platforms and serde_json feature flags,
+//! so it can be hashed, signed, used as a cache key or written to an audit
+//! log. The rules are:
+//!
+//! - no insignificant whitespace;
+//! - object keys sorted by Unicode code point;
+//! - object members whose value is `null` are omitted (an unset optional
+//!   field and an explicit `null` canonicalize the same way); `null` array
+//!   elements are kept;
+//! - integral floats within ±2^53 are written as integers (`1.0` → `1`);
+//! - other floats that are exactly representable as `f32` use the shortest
+//!   `f32` representation, since sampling parameters are `f32` in this crate
+//!   (`0.7f32` → `0.7`, not `0.699999988079071`);
+//! - remaining floats use the shortest round-trip `f64` representation,
+//!   never exponent notation.
+
+use crate::error::Result;
+use serde::Serialize;
+use serde_json::{Number, Value};
+
+/// Largest integer magnitude exactly representable in an `f64`
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_992.0;
+
+/// Serialize `value` as canonical JSON.
+pub fn to_canonical_json<T: Serialize + ?Sized>(value: &T) -> Result<String> {
+    Ok(canonicalize(&serde_json::to_value(value)?))
+}
+
+/// Render an already-built JSON value as canonical JSON.
+pub fn canonicalize(value: &Value) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value);
+    out
+}
+
+fn write_value(out: &mut String, value: &Value) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => write_number(out, n),
+        Value::String(s) => write_string(out, s),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(out, item);
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut members: Vec<_> = map.iter().filter(|(_, v)| !v.is_null()).collect();
+            members.sort_by_key(|(key, _)| *key);
+            out.push('{');
+            for (i, (key, item)) in members.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(out, key);
+                out.push(':');
+                write_value(out, item);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_number(out: &mut String, n: &Number) {
+    if let Some(i) = n.as_i64() {
+        out.push_str(&i.to_string());
+    } else if let Some(u) = n.as_u64() {
+        out.push_str(&u.to_string());
+    } else if let Some(f) = n.as_f64() {
+        out.push_str(&format_float(f));
+    } else {
+        out.push_str(&n.to_string());
+    }
+}
+
+fn format_float(f: f64) -> String {
+    if f == 0.0 {
+        // Covers -0.0 as well
+        return "0".to_string();
+    }
+    if f.fract() == 0.0 && f.abs() < MAX_SAFE_INTEGER {
+        return format!("{}", f as i64);
+    }
+    let narrowed = f as f32;
+    if narrowed as f64 == f {
+        return format!("{}", narrowed);
+    }
+    format!("{}", f)
+}
+
+fn write_string(out: &mut String, s: &str) {
+    // serde_json's string escaping is fixed (short escapes for control
+    // characters, `\u00XX` otherwise, no escaping of non-ASCII), so it can be
+    // reused as-is.
+    out.push_str(&serde_json::to_string(s).expect("string serialization is infallible"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sorted_keys_and_null_omission() {
+        let value = json!({"b": 1, "a": {"d": null, "c": [null, true]}, "e": null});
+        assert_eq!(canonicalize(&value), r#"{"a":{"c":[null,true]},"b":1}"#);
+    }
+
+    #[test]
+    fn test_float_formatting() {
+        assert_eq!(format_float(1.0), "1");
+        assert_eq!(format_float(-0.0), "0");
+        assert_eq!(format_float(0.7f32 as f64), "0.7");
+        assert_eq!(format_float(0.1), "0.1");
+        assert_eq!(format_float(1e300).len(), 301);
+        assert_eq!(
+            canonicalize(&json!({"t": 0.5, "n": -3, "big": u64::MAX})),
+            r#"{"big":18446744073709551615,"n":-3,"t":0.5}"#
+        );
+    }
+
+    #[test]
+    fn test_string_escaping() {
+        assert_eq!(
+            canonicalize(&json!("a\"b\\c\n\u{1}é")),
+            r#""a\"b\\c\n\u0001é""#
+        );
+    }
+}
@@ -0,0 +1,417 @@
+//! Splitting long documents into prompt-sized chunks
+//!
+//! A [`Chunker`] cuts text on sentence, paragraph or markdown section
+//! boundaries, or into plain word windows, packing neighbouring pieces
+//! together up to a token budget. Consecutive chunks can share an overlap so
+//! context is not lost at the seams. Every [`Chunk`] borrows from the source
+//! and records its byte offsets, which is what retrieval and map-reduce
+//! pipelines need to cite or stitch results back together.
+//!
+//! ```rust
+//! use threatflux_anthropic_sdk::utils::chunking::{ChunkStrategy, Chunker};
+//!
+//! let text = "First paragraph.\n\nSecond paragraph.\n\nThird paragraph.";
+//! let chunks = Chunker::new(ChunkStrategy::Paragraph)
+//!     .with_max_tokens(10)
+//!     .chunk(text);
+//! assert_eq!(chunks.len(), 2);
+//! assert_eq!(chunks[0].text, "First paragraph.\n\nSecond paragraph.");
+//! assert_eq!(&text[chunks[1].start..chunks[1].end], "Third paragraph.");
+//! ```
+//!
+//! Token counts use the [`estimate_tokens`] heuristic, so leave some
+//! headroom below hard limits.
+
+use super::text::{estimate_tokens, CHARS_PER_TOKEN};
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Where a [`Chunker`] may cut
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkStrategy {
+    /// Between sentences
+    Sentence,
+    /// At blank lines
+    Paragraph,
+    /// At markdown headings; chunks never span two sections and carry their
+    /// heading path. Oversized sections are cut at blank lines.
+    MarkdownHeader,
+    /// Between words, filling each chunk to the budget
+    TokenWindow,
+}
+
+/// One piece of a chunked document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk<'a> {
+    /// Position among the document's chunks
+    pub index: usize,
+    /// The chunk, with surrounding whitespace trimmed; always
+    /// `&source[start..end]`
+    pub text: &'a str,
+    /// Byte offset of the chunk in the source
+    pub start: usize,
+    /// Byte offset just past the chunk in the source
+    pub end: usize,
+    /// Estimated tokens in `text`
+    pub estimated_tokens: u32,
+    /// Enclosing markdown headings, outermost first (only for
+    /// [`ChunkStrategy::MarkdownHeader`])
+    pub headings: Vec<&'a str>,
+}
+
+/// Splits text into chunks of at most a given number of tokens.
+///
+/// A single word longer than the budget is cut on grapheme boundaries.
+#[derive(Debug, Clone)]
+pub struct Chunker {
+    strategy: ChunkStrategy,
+    max_tokens: u32,
+    overlap_tokens: u32,
+}
+
+impl Chunker {
+    /// Default chunk budget
+    pub const DEFAULT_MAX_TOKENS: u32 = 512;
+
+    /// Chunker with the default budget and no overlap
+    pub fn new(strategy: ChunkStrategy) -> Self {
+        Self {
+            strategy,
+            max_tokens: Self::DEFAULT_MAX_TOKENS,
+            overlap_tokens: 0,
+        }
+    }
+
+    /// Maximum estimated tokens per chunk (at least 1)
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens.max(1);
+        self
+    }
+
+    /// Tokens of trailing context to repeat at the start of the next chunk.
+    ///
+    /// The overlap is made of whole sentences, paragraphs or words depending
+    /// on the strategy, so it can come out shorter than asked.
+    pub fn with_overlap_tokens(mut self, overlap_tokens: u32) -> Self {
+        self.overlap_tokens = overlap_tokens;
+        self
+    }
+
+    /// Split `text` into chunks
+    pub fn chunk<'a>(&self, text: &'a str) -> Vec<Chunk<'a>> {
+        let mut chunks = Vec::new();
+        match self.strategy {
+            ChunkStrategy::Sentence => {
+                let units = offset_units(text.split_sentence_bound_indices(), 0);
+                self.pack(text, &units, &[], &mut chunks);
+            }
+            ChunkStrategy::Paragraph => {
+                self.pack(
+                    text,
+                    &paragraph_units(text, 0..text.len()),
+                    &[],
+                    &mut chunks,
+                );
+            }
+            ChunkStrategy::MarkdownHeader => {
+                for section in markdown_sections(text) {
+                    let units = paragraph_units(text, section.range);
+                    self.pack(text, &units, &section.headings, &mut chunks);
+                }
+            }
+            ChunkStrategy::TokenWindow => {
+                let units = offset_units(text.split_word_bound_indices(), 0);
+                self.pack(text, &units, &[], &mut chunks);
+            }
+        }
+        chunks
+    }
+
+    fn budget_chars(&self) -> usize {
+        to_chars(self.max_tokens)
+    }
+
+    /// Greedily merge consecutive units into chunks within the budget
+    fn pack<'a>(
+        &self,
+        text: &'a str,
+        units: &[Range<usize>],
+        headings: &[&'a str],
+        chunks: &mut Vec<Chunk<'a>>,
+    ) {
+        let budget = self.budget_chars();
+        let overlap = to_chars(self.overlap_tokens).min(budget / 2);
+        let units = fit_units(text, units, budget);
+        let sizes: Vec<usize> = units
+            .iter()
+            .map(|unit| text[unit.clone()].chars().count())
+            .collect();
+
+        let mut first = 0;
+        while first < units.len() {
+            let mut next = first;
+            let mut size = 0;
+            while next < units.len() && (next == first || size + sizes[next] <= budget) {
+                size += sizes[next];
+                next += 1;
+            }
+            push_chunk(
+                text,
+                units[first].start..units[next - 1].end,
+                headings,
+                chunks,
+            );
+            if next == units.len() {
+                break;
+            }
+
+            let mut resume = next;
+            let mut repeated = 0;
+            while resume > first + 1 && repeated + sizes[resume - 1] <= overlap {
+                repeated += sizes[resume - 1];
+                resume -= 1;
+            }
+            first = resume;
+        }
+    }
+}
+
+fn to_chars(tokens: u32) -> usize {
+    usize::try_from(tokens)
+        .unwrap_or(usize::MAX)
+        .saturating_mul(CHARS_PER_TOKEN)
+}
+
+fn push_chunk<'a>(
+    text: &'a str,
+    range: Range<usize>,
+    headings: &[&'a str],
+    chunks: &mut Vec<Chunk<'a>>,
+) {
+    let raw = &text[range.clone()];
+    let trimmed = raw.trim_start();
+    let start = range.start + (raw.len() - trimmed.len());
+    let trimmed = trimmed.trim_end();
+    if trimmed.is_empty() {
+        return;
+    }
+    chunks.push(Chunk {
+        index: chunks.len(),
+        text: trimmed,
+        start,
+        end: start + trimmed.len(),
+        estimated_tokens: estimate_tokens(trimmed),
+        headings: headings.to_vec(),
+    });
+}
+
+fn offset_units<'a>(
+    pieces: impl Iterator<Item = (usize, &'a str)>,
+    base: usize,
+) -> Vec<Range<usize>> {
+    pieces
+        .map(|(offset, piece)| base + offset..base + offset + piece.len())
+        .collect()
+}
+
+/// Re-split any unit over `budget` characters into words, and any word over
+/// it into grapheme-aligned pieces
+fn fit_units(text: &str, units: &[Range<usize>], budget: usize) -> Vec<Range<usize>> {
+    let mut fitted = Vec::with_capacity(units.len());
+    for unit in units {
+        let slice = &text[unit.clone()];
+        if slice.chars().count() <= budget {
+            fitted.push(unit.clone());
+            continue;
+        }
+        for word in offset_units(slice.split_word_bound_indices(), unit.start) {
+            let word_text = &text[word.clone()];
+            if word_text.chars().count() <= budget {
+                fitted.push(word);
+                continue;
+            }
+            let mut piece_start = word.start;
+            let mut piece_chars = 0;
+            for (offset, grapheme) in word_text.grapheme_indices(true) {
+                let chars = grapheme.chars().count();
+                if piece_chars > 0 && piece_chars + chars > budget {
+                    fitted.push(piece_start..word.start + offset);
+                    piece_start = word.start + offset;
+                    piece_chars = 0;
+                }
+                piece_chars += chars;
+            }
+            fitted.push(piece_start..word.end);
+        }
+    }
+    fitted
+}
+
+/// Paragraphs of `text[range]`, each followed by the blank lines after it
+fn paragraph_units(text: &str, range: Range<usize>) -> Vec<Range<usize>> {
+    let mut units = Vec::new();
+    let mut start = range.start;
+    let mut offset = range.start;
+    let mut after_blank = false;
+    for line in text[range.clone()].split_inclusive('\n') {
+        let blank = line.trim().is_empty();
+        if !blank && after_blank && offset > start {
+            units.push(start..offset);
+            start = offset;
+        }
+        after_blank = blank;
+        offset += line.len();
+    }
+    if start < range.end {
+        units.push(start..range.end);
+    }
+    units
+}
+
+struct Section<'a> {
+    range: Range<usize>,
+    headings: Vec<&'a str>,
+}
+
+/// Markdown sections, each starting at an ATX heading (`#` to `######`).
+/// Headings inside fenced code blocks are ignored.
+fn markdown_sections(text: &str) -> Vec<Section<'_>> {
+    let mut sections = Vec::new();
+    let mut stack: Vec<(usize, &str)> = Vec::new();
+    let mut current = Section {
+        range: 0..0,
+        headings: Vec::new(),
+    };
+    let mut fence: Option<&str> = None;
+    let mut offset = 0;
+
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        if indent <= 3 {
+            if let Some(marker) = fence {
+                if trimmed.starts_with(marker) {
+                    fence = None;
+                }
+            } else if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                fence = Some(&trimmed[..3]);
+            } else if let Some((level, title)) = heading(trimmed) {
+                current.range.end = offset;
+                sections.push(current);
+                stack.retain(|&(open, _)| open < level);
+                stack.push((level, title));
+                current = Section {
+                    range: offset..offset,
+                    headings: stack.iter().map(|&(_, title)| title).collect(),
+                };
+            }
+        }
+        offset += line.len();
+    }
+    current.range.end = text.len();
+    sections.push(current);
+    sections.retain(|section| !section.range.is_empty());
+    sections
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.bytes().take_while(|&b| b == b'#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &line[level..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t', '\n', '\r']) {
+        return None;
+    }
+    Some((level, rest.trim().trim_end_matches('#').trim_end()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts<'a>(chunks: &[Chunk<'a>]) -> Vec<&'a str> {
+        chunks.iter().map(|chunk| chunk.text).collect()
+    }
+
+    #[test]
+    fn test_sentence_chunks_with_offsets() {
+        let text = "One two three. Four five six. Seven eight nine.";
+        let chunks = Chunker::new(ChunkStrategy::Sentence)
+            .with_max_tokens(8)
+            .chunk(text);
+        assert_eq!(
+            texts(&chunks),
+            ["One two three. Four five six.", "Seven eight nine."]
+        );
+        for (index, chunk) in chunks.iter().enumerate() {
+            assert_eq!(chunk.index, index);
+            assert_eq!(&text[chunk.start..chunk.end], chunk.text);
+            assert!(chunk.headings.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_overlap_repeats_trailing_units() {
+        let text = "Alpha beta. Gamma delta. Epsilon zeta. Eta theta.";
+        let chunks = Chunker::new(ChunkStrategy::Sentence)
+            .with_max_tokens(7)
+            .with_overlap_tokens(4)
+            .chunk(text);
+        assert_eq!(
+            texts(&chunks),
+            [
+                "Alpha beta. Gamma delta.",
+                "Gamma delta. Epsilon zeta.",
+                "Epsilon zeta. Eta theta."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_token_window_splits_long_words_safely() {
+        let text = format!("short {} end", "é".repeat(10));
+        let chunks = Chunker::new(ChunkStrategy::TokenWindow)
+            .with_max_tokens(1)
+            .chunk(&text);
+        assert!(chunks.iter().all(|chunk| chunk.text.chars().count() <= 4));
+        assert_eq!(
+            chunks.iter().map(|c| c.text).collect::<String>(),
+            format!("short{}end", "é".repeat(10))
+        );
+    }
+
+    #[test]
+    fn test_markdown_sections_carry_heading_path() {
+        let text = "Intro text.\n\n# Guide\n\nOverview.\n\n## Install\n\n```sh\n# not a heading\n```\n\n## Usage ##\n\nRun it.\n\n# Appendix\n";
+        let chunks = Chunker::new(ChunkStrategy::MarkdownHeader).chunk(text);
+        let summary: Vec<_> = chunks
+            .iter()
+            .map(|chunk| (chunk.headings.clone(), chunk.text.lines().next().unwrap()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (vec![], "Intro text."),
+                (vec!["Guide"], "# Guide"),
+                (vec!["Guide", "Install"], "## Install"),
+                (vec!["Guide", "Usage"], "## Usage ##"),
+                (vec!["Appendix"], "# Appendix"),
+            ]
+        );
+        assert!(chunks[2].text.contains("# not a heading"));
+    }
+
+    #[test]
+    fn test_empty_and_whitespace_input() {
+        for strategy in [
+            ChunkStrategy::Sentence,
+            ChunkStrategy::Paragraph,
+            ChunkStrategy::MarkdownHeader,
+            ChunkStrategy::TokenWindow,
+        ] {
+            assert!(Chunker::new(strategy).chunk("").is_empty());
+            assert!(Chunker::new(strategy).chunk(" \n\n ").is_empty());
+        }
+    }
+}
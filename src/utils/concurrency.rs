@@ -0,0 +1,129 @@
+//! Per-model concurrency caps
+//!
+//! Limits are matched against the request's model with simple globs (`*`
+//! matches any run of characters, `?` a single one). The first matching
+//! limit applies; models that match none are not limited.
+
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// A cap on concurrent in-flight requests for models matching a pattern.
+///
+/// Clones share the same permits, so every client built from one
+/// [`Config`](crate::config::Config) observes the same limit.
+#[derive(Debug, Clone)]
+pub struct ModelConcurrencyLimit {
+    pattern: String,
+    limit: usize,
+    semaphore: Arc<Semaphore>,
+}
+
+impl ModelConcurrencyLimit {
+    /// Allow at most `limit` concurrent requests for models matching `pattern`
+    pub fn new(pattern: impl Into<String>, limit: usize) -> Self {
+        Self {
+            pattern: pattern.into(),
+            limit,
+            semaphore: Arc::new(Semaphore::new(limit)),
+        }
+    }
+
+    /// Glob pattern
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Maximum concurrent requests
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Requests currently holding a slot
+    pub fn in_flight(&self) -> usize {
+        self.limit - self.semaphore.available_permits()
+    }
+
+    /// Whether `model` falls under this limit
+    pub fn matches(&self, model: &str) -> bool {
+        glob_match(&self.pattern, model)
+    }
+}
+
+/// Wait for a slot under the first limit matching `model`.
+///
+/// Returns `None` when no limit applies. The slot is released when the
+/// permit is dropped.
+pub(crate) async fn acquire(
+    limits: &[ModelConcurrencyLimit],
+    model: &str,
+) -> Option<OwnedSemaphorePermit> {
+    let limit = limits.iter().find(|l| l.matches(model))?;
+    limit.semaphore.clone().acquire_owned().await.ok()
+}
+
+/// Match `text` against a glob with `*` and `?` wildcards.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text index it is currently covering up to
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, covered)) => {
+                    p = star + 1;
+                    t = covered + 1;
+                    backtrack = Some((star, covered + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("claude-opus-*", "claude-opus-4-6"));
+        assert!(!glob_match("claude-opus-*", "claude-sonnet-4-6"));
+        assert!(glob_match("*haiku*", "claude-haiku-4-5"));
+        assert!(glob_match("claude-?onnet-*", "claude-sonnet-4-6"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("exact", "exact"));
+        assert!(!glob_match("exact", "exactly"));
+        assert!(glob_match("a*b*c", "a-x-b-y-b-c"));
+    }
+
+    #[tokio::test]
+    async fn test_first_matching_limit_applies() {
+        let limits = vec![
+            ModelConcurrencyLimit::new("claude-opus-4-1*", 1),
+            ModelConcurrencyLimit::new("claude-opus-*", 2),
+        ];
+
+        let first = acquire(&limits, "claude-opus-4-1").await.unwrap();
+        assert_eq!(limits[0].in_flight(), 1);
+        assert_eq!(limits[1].in_flight(), 0);
+
+        let _second = acquire(&limits, "claude-opus-4-6").await.unwrap();
+        assert_eq!(limits[1].in_flight(), 1);
+        assert!(acquire(&limits, "claude-haiku-4-5").await.is_none());
+
+        drop(first);
+        assert_eq!(limits[0].in_flight(), 0);
+    }
+}
@@ -0,0 +1,464 @@
+//! Diffing for comparing model outputs
+//!
+//! [`TextDiff`] is a plain line diff; [`response_diff`] compares two whole
+//! [`MessageResponse`]s, including tool calls and token usage.
+
+use crate::models::{
+    common::{ContentBlock, StopReason, Usage},
+    message::MessageResponse,
+};
+use serde::{Deserialize, Serialize};
+
+/// A single line-level diff operation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", content = "line", rename_all = "snake_case")]
+pub enum DiffOp {
+    /// Line present in both texts
+    Equal(String),
+    /// Line only in the second text
+    Insert(String),
+    /// Line only in the first text
+    Delete(String),
+}
+
+/// Line-level diff between two texts.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct TextDiff {
+    /// Diff operations in order
+    pub ops: Vec<DiffOp>,
+}
+
+impl TextDiff {
+    /// Diff two texts line by line (longest common subsequence).
+    pub fn lines(a: &str, b: &str) -> Self {
+        let a: Vec<&str> = a.lines().collect();
+        let b: Vec<&str> = b.lines().collect();
+
+        // Trim the common prefix and suffix so the quadratic table only covers
+        // the region that actually differs.
+        let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+        let suffix = a[prefix..]
+            .iter()
+            .rev()
+            .zip(b[prefix..].iter().rev())
+            .take_while(|(x, y)| x == y)
+            .count();
+        let a_mid = &a[prefix..a.len() - suffix];
+        let b_mid = &b[prefix..b.len() - suffix];
+
+        let mut ops: Vec<DiffOp> = a[..prefix]
+            .iter()
+            .map(|l| DiffOp::Equal(l.to_string()))
+            .collect();
+
+        let (n, m) = (a_mid.len(), b_mid.len());
+        let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i][j] = if a_mid[i] == b_mid[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n && j < m {
+            if a_mid[i] == b_mid[j] {
+                ops.push(DiffOp::Equal(a_mid[i].to_string()));
+                i += 1;
+                j += 1;
+            } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+                ops.push(DiffOp::Delete(a_mid[i].to_string()));
+                i += 1;
+            } else {
+                ops.push(DiffOp::Insert(b_mid[j].to_string()));
+                j += 1;
+            }
+        }
+        ops.extend(a_mid[i..].iter().map(|l| DiffOp::Delete(l.to_string())));
+        ops.extend(b_mid[j..].iter().map(|l| DiffOp::Insert(l.to_string())));
+        ops.extend(
+            a[a.len() - suffix..]
+                .iter()
+                .map(|l| DiffOp::Equal(l.to_string())),
+        );
+
+        Self { ops }
+    }
+
+    /// Whether both texts are identical
+    pub fn is_identical(&self) -> bool {
+        self.ops.iter().all(|op| matches!(op, DiffOp::Equal(_)))
+    }
+
+    /// Number of inserted lines
+    pub fn insertions(&self) -> usize {
+        self.ops
+            .iter()
+            .filter(|op| matches!(op, DiffOp::Insert(_)))
+            .count()
+    }
+
+    /// Number of deleted lines
+    pub fn deletions(&self) -> usize {
+        self.ops
+            .iter()
+            .filter(|op| matches!(op, DiffOp::Delete(_)))
+            .count()
+    }
+
+    /// Line similarity ratio in `0.0..=1.0` (`2 * equal / total lines`)
+    pub fn similarity(&self) -> f64 {
+        let equal = self.ops.len() - self.insertions() - self.deletions();
+        let total = self.ops.len() + equal;
+        if total == 0 {
+            1.0
+        } else {
+            (2 * equal) as f64 / total as f64
+        }
+    }
+
+    /// Render as unified-style text (` `, `-`, `+` line prefixes)
+    pub fn to_unified(&self) -> String {
+        let mut out = String::new();
+        for op in &self.ops {
+            let (prefix, line) = match op {
+                DiffOp::Equal(line) => (' ', line),
+                DiffOp::Delete(line) => ('-', line),
+                DiffOp::Insert(line) => ('+', line),
+            };
+            out.push(prefix);
+            out.push_str(line);
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Group changes into hunks with up to `context` unchanged lines around
+    /// each change. Changes separated by at most `2 * context` equal lines
+    /// share a hunk.
+    pub fn hunks(&self, context: usize) -> Vec<DiffHunk> {
+        let changed: Vec<usize> = self
+            .ops
+            .iter()
+            .enumerate()
+            .filter(|(_, op)| !matches!(op, DiffOp::Equal(_)))
+            .map(|(i, _)| i)
+            .collect();
+        let Some(&first) = changed.first() else {
+            return Vec::new();
+        };
+
+        // Op index ranges to emit, merged when their context overlaps
+        let mut ranges = vec![(first.saturating_sub(context), first + 1)];
+        for &i in &changed[1..] {
+            let last = ranges.last_mut().unwrap();
+            if i <= last.1 + 2 * context {
+                last.1 = i + 1;
+            } else {
+                ranges.push((i.saturating_sub(context), i + 1));
+            }
+        }
+
+        let mut hunks = Vec::with_capacity(ranges.len());
+        let (mut a_line, mut b_line, mut pos) = (0, 0, 0);
+        for (start, end) in ranges {
+            let end = (end + context).min(self.ops.len());
+            for op in &self.ops[pos..start] {
+                advance(op, &mut a_line, &mut b_line);
+            }
+            let mut hunk = DiffHunk {
+                a_start: a_line + 1,
+                a_len: 0,
+                b_start: b_line + 1,
+                b_len: 0,
+                ops: self.ops[start..end].to_vec(),
+            };
+            for op in &hunk.ops {
+                advance(op, &mut hunk.a_len, &mut hunk.b_len);
+            }
+            a_line += hunk.a_len;
+            b_line += hunk.b_len;
+            pos = end;
+            hunks.push(hunk);
+        }
+        hunks
+    }
+}
+
+fn advance(op: &DiffOp, a: &mut usize, b: &mut usize) {
+    match op {
+        DiffOp::Equal(_) => {
+            *a += 1;
+            *b += 1;
+        }
+        DiffOp::Delete(_) => *a += 1,
+        DiffOp::Insert(_) => *b += 1,
+    }
+}
+
+/// A contiguous group of changes with surrounding context lines.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffHunk {
+    /// First line of the hunk in the first text (1-based)
+    pub a_start: usize,
+    /// Number of first-text lines covered
+    pub a_len: usize,
+    /// First line of the hunk in the second text (1-based)
+    pub b_start: usize,
+    /// Number of second-text lines covered
+    pub b_len: usize,
+    /// Operations in the hunk, including context
+    pub ops: Vec<DiffOp>,
+}
+
+impl DiffHunk {
+    /// The `@@ -a,n +b,m @@` header line
+    pub fn header(&self) -> String {
+        format!(
+            "@@ -{},{} +{},{} @@",
+            self.a_start, self.a_len, self.b_start, self.b_len
+        )
+    }
+}
+
+/// A difference between the tool calls of two responses.
+///
+/// Calls are matched by tool name and position among calls to that tool;
+/// `tool_use` ids are ignored since they never match across responses.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum ToolCallDiff {
+    /// Call only made in the second response
+    Added {
+        /// Tool name
+        name: String,
+        /// Tool input
+        input: serde_json::Value,
+    },
+    /// Call only made in the first response
+    Removed {
+        /// Tool name
+        name: String,
+        /// Tool input
+        input: serde_json::Value,
+    },
+    /// Same tool called with different input
+    InputChanged {
+        /// Tool name
+        name: String,
+        /// Input in the first response
+        a: serde_json::Value,
+        /// Input in the second response
+        b: serde_json::Value,
+    },
+}
+
+/// Token usage of the second response minus the first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct UsageDelta {
+    /// Input token difference
+    pub input_tokens: i64,
+    /// Output token difference
+    pub output_tokens: i64,
+    /// Cache write token difference
+    pub cache_creation_input_tokens: i64,
+    /// Cache read token difference
+    pub cache_read_input_tokens: i64,
+}
+
+impl UsageDelta {
+    /// Compute `b - a`
+    pub fn between(a: &Usage, b: &Usage) -> Self {
+        let d = |x: u32, y: u32| y as i64 - x as i64;
+        Self {
+            input_tokens: d(a.input_tokens, b.input_tokens),
+            output_tokens: d(a.output_tokens, b.output_tokens),
+            cache_creation_input_tokens: d(
+                a.cache_creation_input_tokens,
+                b.cache_creation_input_tokens,
+            ),
+            cache_read_input_tokens: d(a.cache_read_input_tokens, b.cache_read_input_tokens),
+        }
+    }
+
+    /// Input plus output token difference
+    pub fn total_tokens(&self) -> i64 {
+        self.input_tokens + self.output_tokens
+    }
+}
+
+/// Structured diff of two message responses.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResponseDiff {
+    /// Line diff of the concatenated text content
+    pub text: TextDiff,
+    /// Tool call differences, in order of first appearance
+    pub tool_calls: Vec<ToolCallDiff>,
+    /// Usage of `b` minus usage of `a`
+    pub usage: UsageDelta,
+    /// Stop reasons `(a, b)` when they differ
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_reason: Option<(Option<StopReason>, Option<StopReason>)>,
+}
+
+impl ResponseDiff {
+    /// Whether text, tool calls and stop reason all match (usage is ignored)
+    pub fn is_equivalent(&self) -> bool {
+        self.text.is_identical() && self.tool_calls.is_empty() && self.stop_reason.is_none()
+    }
+
+    /// Text hunks with `context` lines of context
+    pub fn text_hunks(&self, context: usize) -> Vec<DiffHunk> {
+        self.text.hunks(context)
+    }
+}
+
+/// Compare two responses: text hunks, tool call differences and usage delta.
+pub fn response_diff(a: &MessageResponse, b: &MessageResponse) -> ResponseDiff {
+    let stop_reason =
+        (a.stop_reason != b.stop_reason).then(|| (a.stop_reason.clone(), b.stop_reason.clone()));
+    ResponseDiff {
+        text: TextDiff::lines(&a.text(), &b.text()),
+        tool_calls: diff_tool_calls(&tool_calls(a), &tool_calls(b)),
+        usage: UsageDelta::between(&a.usage, &b.usage),
+        stop_reason,
+    }
+}
+
+fn tool_calls(response: &MessageResponse) -> Vec<(&str, &serde_json::Value)> {
+    response
+        .content
+        .iter()
+        .filter_map(|block| match block {
+            ContentBlock::ToolUse { name, input, .. } => Some((name.as_str(), input)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn diff_tool_calls(
+    a: &[(&str, &serde_json::Value)],
+    b: &[(&str, &serde_json::Value)],
+) -> Vec<ToolCallDiff> {
+    let mut matched_b = vec![false; b.len()];
+    let mut diffs = Vec::new();
+    for &(name, input) in a {
+        let partner = b
+            .iter()
+            .enumerate()
+            .find(|(j, (other, _))| !matched_b[*j] && *other == name);
+        match partner {
+            Some((j, (_, other_input))) => {
+                matched_b[j] = true;
+                if input != *other_input {
+                    diffs.push(ToolCallDiff::InputChanged {
+                        name: name.to_string(),
+                        a: input.clone(),
+                        b: (*other_input).clone(),
+                    });
+                }
+            }
+            None => diffs.push(ToolCallDiff::Removed {
+                name: name.to_string(),
+                input: input.clone(),
+            }),
+        }
+    }
+    diffs.extend(b.iter().zip(matched_b).filter(|(_, matched)| !matched).map(
+        |((name, input), _)| ToolCallDiff::Added {
+            name: name.to_string(),
+            input: (*input).clone(),
+        },
+    ));
+    diffs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identical_texts() {
+        let diff = TextDiff::lines("a\nb", "a\nb");
+        assert!(diff.is_identical());
+        assert_eq!(diff.similarity(), 1.0);
+        assert_eq!(TextDiff::lines("", "").similarity(), 1.0);
+    }
+
+    #[test]
+    fn test_line_changes() {
+        let diff = TextDiff::lines("one\ntwo\nthree\nfour", "one\n2\nthree\nfour\nfive");
+        assert_eq!(diff.insertions(), 2);
+        assert_eq!(diff.deletions(), 1);
+        assert_eq!(diff.to_unified(), " one\n-two\n+2\n three\n four\n+five\n");
+        assert!((diff.similarity() - 6.0 / 9.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_hunks() {
+        let a = "1\n2\n3\n4\n5\n6\n7\n8\n9";
+        let b = "1\nX\n3\n4\n5\n6\n7\n8\nY";
+        let hunks = TextDiff::lines(a, b).hunks(1);
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(hunks[0].header(), "@@ -1,3 +1,3 @@");
+        assert_eq!(hunks[1].header(), "@@ -8,2 +8,2 @@");
+
+        // Wide enough context merges both changes
+        assert_eq!(TextDiff::lines(a, b).hunks(3).len(), 1);
+        assert!(TextDiff::lines(a, a).hunks(3).is_empty());
+    }
+
+    fn response(content: serde_json::Value, output_tokens: u32) -> MessageResponse {
+        serde_json::from_value(serde_json::json!({
+            "id": "msg_1",
+            "type": "message",
+            "role": "assistant",
+            "content": content,
+            "model": "claude-sonnet-4-6",
+            "stop_reason": "tool_use",
+            "stop_sequence": null,
+            "usage": {"input_tokens": 10, "output_tokens": output_tokens}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_response_diff() {
+        let a = response(
+            serde_json::json!([
+                {"type": "text", "text": "Checking weather"},
+                {"type": "tool_use", "id": "toolu_a1", "name": "weather", "input": {"city": "Paris"}},
+                {"type": "tool_use", "id": "toolu_a2", "name": "clock", "input": {}}
+            ]),
+            20,
+        );
+        let b = response(
+            serde_json::json!([
+                {"type": "text", "text": "Checking weather"},
+                {"type": "tool_use", "id": "toolu_b1", "name": "weather", "input": {"city": "Lyon"}},
+                {"type": "tool_use", "id": "toolu_b2", "name": "search", "input": {"q": "x"}}
+            ]),
+            25,
+        );
+
+        let diff = response_diff(&a, &b);
+        assert!(diff.text.is_identical());
+        assert!(diff.stop_reason.is_none());
+        assert_eq!(diff.usage.output_tokens, 5);
+        assert_eq!(diff.usage.total_tokens(), 5);
+        assert_eq!(diff.tool_calls.len(), 3);
+        assert!(
+            matches!(&diff.tool_calls[0], ToolCallDiff::InputChanged { name, .. } if name == "weather")
+        );
+        assert!(
+            matches!(&diff.tool_calls[1], ToolCallDiff::Removed { name, .. } if name == "clock")
+        );
+        assert!(
+            matches!(&diff.tool_calls[2], ToolCallDiff::Added { name, .. } if name == "search")
+        );
+        assert!(!diff.is_equivalent());
+        assert!(response_diff(&a, &a).is_equivalent());
+    }
+}
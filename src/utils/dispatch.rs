@@ -0,0 +1,163 @@
+//! Priority-ordered dispatch under a request rate limit
+//!
+//! While the limiter has room, requests go straight through. Once it is
+//! saturated they queue, and each slot that frees up goes to the most urgent
+//! waiting request by [`RequestPriority`], oldest first among equals. To keep
+//! a steady stream of high-priority work from starving the rest, a waiting
+//! request is promoted one level for every [aging](DispatchQueue::with_aging)
+//! interval it has waited.
+
+use crate::{types::RequestPriority, utils::rate_limit::RateLimiter};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{sync::oneshot, time::Instant};
+
+/// Default time a queued request waits before being promoted a level
+pub const DEFAULT_AGING: Duration = Duration::from_secs(5);
+
+/// A rate limit whose free slots go to the most urgent request first.
+///
+/// Clones share the same limit and queue, so every client built from one
+/// [`Config`](crate::config::Config) observes the same order.
+#[derive(Debug, Clone)]
+pub struct DispatchQueue {
+    limiter: RateLimiter,
+    aging: Duration,
+    state: Arc<Mutex<QueueState>>,
+}
+
+#[derive(Debug, Default)]
+struct QueueState {
+    waiting: Vec<Waiter>,
+    /// Whether a task is handing out slots to `waiting`
+    dispatching: bool,
+    next_seq: u64,
+}
+
+#[derive(Debug)]
+struct Waiter {
+    priority: RequestPriority,
+    queued: Instant,
+    seq: u64,
+    admit: oneshot::Sender<()>,
+}
+
+impl Waiter {
+    /// Priority rank after promotion for time spent waiting
+    fn rank(&self, now: Instant, aging: Duration) -> u32 {
+        let base = match self.priority {
+            RequestPriority::Low => 0,
+            RequestPriority::Normal => 1,
+            RequestPriority::High => 2,
+        };
+        let promotions = if aging.is_zero() {
+            u128::from(u32::MAX)
+        } else {
+            now.duration_since(self.queued).as_nanos() / aging.as_nanos()
+        };
+        (base + promotions.min(2) as u32).min(2)
+    }
+}
+
+impl DispatchQueue {
+    /// Dispatch requests at the pace of `limiter`
+    pub fn new(limiter: RateLimiter) -> Self {
+        Self {
+            limiter,
+            aging: DEFAULT_AGING,
+            state: Arc::default(),
+        }
+    }
+
+    /// Dispatch at most `requests_per_second` requests a second
+    pub fn per_second(requests_per_second: u32) -> Self {
+        Self::new(RateLimiter::per_second(requests_per_second))
+    }
+
+    /// Promote a waiting request one level per `aging` waited
+    /// (default [`DEFAULT_AGING`]); zero makes the queue plain FIFO
+    pub fn with_aging(mut self, aging: Duration) -> Self {
+        self.aging = aging;
+        self
+    }
+
+    /// The limiter pacing dispatch
+    pub fn limiter(&self) -> &RateLimiter {
+        &self.limiter
+    }
+
+    /// Requests currently waiting for a slot
+    pub fn queued(&self) -> usize {
+        self.lock().waiting.len()
+    }
+
+    /// Wait until a request of `priority` may be sent
+    pub async fn admit(&self, priority: RequestPriority) {
+        let admitted = {
+            let mut state = self.lock();
+            if !state.dispatching && state.waiting.is_empty() && self.limiter.try_acquire().is_ok()
+            {
+                return;
+            }
+            let (admit, admitted) = oneshot::channel();
+            let seq = state.next_seq;
+            state.next_seq += 1;
+            state.waiting.push(Waiter {
+                priority,
+                queued: Instant::now(),
+                seq,
+                admit,
+            });
+            if !state.dispatching {
+                state.dispatching = true;
+                tokio::spawn(self.clone().dispatch());
+            }
+            admitted
+        };
+        // Only fails if the runtime dropped the dispatcher; go ahead then
+        let _ = admitted.await;
+    }
+
+    /// Hand out slots to waiting requests until none are left
+    async fn dispatch(self) {
+        loop {
+            {
+                let mut state = self.lock();
+                if state.waiting.is_empty() {
+                    state.dispatching = false;
+                    return;
+                }
+            }
+            // Waiting never fails
+            let _ = self.limiter.acquire().await;
+            loop {
+                let mut state = self.lock();
+                let now = Instant::now();
+                let next = state
+                    .waiting
+                    .iter()
+                    .enumerate()
+                    .max_by_key(|(_, waiter)| {
+                        (waiter.rank(now, self.aging), std::cmp::Reverse(waiter.seq))
+                    })
+                    .map(|(index, _)| index);
+                let Some(next) = next else {
+                    state.dispatching = false;
+                    return;
+                };
+                // A waiter that gave up passes its slot on to the next
+                if state.waiting.swap_remove(next).admit.send(()).is_ok() {
+                    break;
+                }
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, QueueState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
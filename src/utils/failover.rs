@@ -0,0 +1,224 @@
+//! Multi-region endpoint failover
+//!
+//! [`EndpointFailover`] tracks an ordered list of base URLs (primary first).
+//! Consecutive connection errors or 5xx responses against the active endpoint
+//! trip traffic over to the next one; while a secondary is active, a single
+//! request is periodically routed to the primary as a probe, and a successful
+//! probe moves traffic back.
+
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use url::Url;
+
+/// Policy controlling when traffic fails over and when the primary is probed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FailoverPolicy {
+    /// Consecutive failures on the active endpoint before switching
+    pub failure_threshold: u32,
+    /// How long to wait before routing a probe request back to the primary
+    pub probe_interval: Duration,
+}
+
+impl Default for FailoverPolicy {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 3,
+            probe_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+impl FailoverPolicy {
+    /// Create a policy with default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the consecutive failure threshold
+    pub fn with_failure_threshold(mut self, threshold: u32) -> Self {
+        self.failure_threshold = threshold.max(1);
+        self
+    }
+
+    /// Set the primary probe interval
+    pub fn with_probe_interval(mut self, interval: Duration) -> Self {
+        self.probe_interval = interval;
+        self
+    }
+}
+
+#[derive(Debug)]
+struct FailoverState {
+    active: usize,
+    consecutive_failures: u32,
+    last_switch: Option<Instant>,
+}
+
+/// Shared failover state for a set of endpoints.
+#[derive(Debug)]
+pub struct EndpointFailover {
+    endpoints: Vec<Url>,
+    policy: FailoverPolicy,
+    state: Mutex<FailoverState>,
+}
+
+impl EndpointFailover {
+    /// Create failover state for the given endpoints (primary first)
+    pub fn new(endpoints: Vec<Url>, policy: FailoverPolicy) -> Self {
+        Self {
+            endpoints,
+            policy,
+            state: Mutex::new(FailoverState {
+                active: 0,
+                consecutive_failures: 0,
+                last_switch: None,
+            }),
+        }
+    }
+
+    /// Configured endpoints, primary first
+    pub fn endpoints(&self) -> &[Url] {
+        &self.endpoints
+    }
+
+    /// Failover policy
+    pub fn policy(&self) -> FailoverPolicy {
+        self.policy
+    }
+
+    /// Index of the endpoint currently receiving traffic
+    pub fn active_index(&self) -> usize {
+        self.state.lock().unwrap().active
+    }
+
+    /// Endpoint currently receiving traffic
+    pub fn active_endpoint(&self) -> Option<&Url> {
+        self.endpoints.get(self.active_index())
+    }
+
+    /// Pick the endpoint for the next request.
+    ///
+    /// Returns the active endpoint, or the primary when a probe is due.
+    pub fn select(&self) -> Option<(usize, &Url)> {
+        let mut state = self.state.lock().unwrap();
+        if state.active != 0 {
+            let probe_due = state
+                .last_switch
+                .is_none_or(|at| at.elapsed() >= self.policy.probe_interval);
+            if probe_due {
+                // Reset the timer so only one request probes per interval.
+                state.last_switch = Some(Instant::now());
+                return self.endpoints.first().map(|url| (0, url));
+            }
+        }
+        self.endpoints
+            .get(state.active)
+            .map(|url| (state.active, url))
+    }
+
+    /// Record a successful request against endpoint `index`
+    pub fn record_success(&self, index: usize) {
+        let mut state = self.state.lock().unwrap();
+        if index == 0 && state.active != 0 {
+            tracing::info!("Primary endpoint recovered, failing back");
+            state.active = 0;
+            state.last_switch = Some(Instant::now());
+        }
+        if index == state.active {
+            state.consecutive_failures = 0;
+        }
+    }
+
+    /// Record a connection error or 5xx response against endpoint `index`
+    pub fn record_failure(&self, index: usize) {
+        let mut state = self.state.lock().unwrap();
+        if index != state.active {
+            return;
+        }
+        state.consecutive_failures += 1;
+        if state.consecutive_failures >= self.policy.failure_threshold && self.endpoints.len() > 1 {
+            let next = (state.active + 1) % self.endpoints.len();
+            tracing::warn!(
+                "Endpoint {} failed {} times in a row, failing over to {}",
+                self.endpoints[state.active],
+                state.consecutive_failures,
+                self.endpoints[next]
+            );
+            state.active = next;
+            state.consecutive_failures = 0;
+            state.last_switch = Some(Instant::now());
+        }
+    }
+
+    /// Rewrite a URL built against `base` so it targets `endpoint` instead
+    pub fn rewrite(url: &Url, base: &Url, endpoint: &Url) -> Url {
+        let base = base.as_str().trim_end_matches('/');
+        match url.as_str().strip_prefix(base) {
+            Some(rest) => {
+                let target = format!("{}{}", endpoint.as_str().trim_end_matches('/'), rest);
+                Url::parse(&target).unwrap_or_else(|_| url.clone())
+            }
+            None => url.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failover(threshold: u32, probe: Duration) -> EndpointFailover {
+        EndpointFailover::new(
+            vec![
+                Url::parse("https://primary.example.com").unwrap(),
+                Url::parse("https://secondary.example.com").unwrap(),
+            ],
+            FailoverPolicy::new()
+                .with_failure_threshold(threshold)
+                .with_probe_interval(probe),
+        )
+    }
+
+    #[test]
+    fn test_trips_after_consecutive_failures() {
+        let failover = failover(2, Duration::from_secs(60));
+        failover.record_failure(0);
+        failover.record_success(0);
+        failover.record_failure(0);
+        assert_eq!(failover.active_index(), 0);
+        failover.record_failure(0);
+        assert_eq!(failover.active_index(), 1);
+        assert_eq!(failover.select().unwrap().0, 1);
+    }
+
+    #[test]
+    fn test_probe_back_to_primary() {
+        let failover = failover(1, Duration::ZERO);
+        failover.record_failure(0);
+        assert_eq!(failover.active_index(), 1);
+
+        // Probe fails: traffic stays on the secondary.
+        let (index, _) = failover.select().unwrap();
+        assert_eq!(index, 0);
+        failover.record_failure(index);
+        assert_eq!(failover.active_index(), 1);
+
+        // Probe succeeds: traffic returns to the primary.
+        let (index, _) = failover.select().unwrap();
+        failover.record_success(index);
+        assert_eq!(failover.active_index(), 0);
+    }
+
+    #[test]
+    fn test_rewrite_url() {
+        let base = Url::parse("https://api.anthropic.com").unwrap();
+        let endpoint = Url::parse("https://eu.example.com/proxy/").unwrap();
+        let url = Url::parse("https://api.anthropic.com/v1/messages?x=1").unwrap();
+        assert_eq!(
+            EndpointFailover::rewrite(&url, &base, &endpoint).as_str(),
+            "https://eu.example.com/proxy/v1/messages?x=1"
+        );
+    }
+}
@@ -0,0 +1,566 @@
+//! HTML-to-text preprocessing for scraped web content.
+//!
+//! Raw HTML spends most of its tokens on markup, scripts and navigation chrome.
+//! [`html_to_text`] strips those, keeps the readable text with light markdown
+//! structure (headings, list items, paragraphs), and can restrict output to an
+//! allowlist of elements and a character/token budget before the page is sent
+//! to the model as text or as a content-based document block.
+
+use crate::models::common::{ContentBlock, DocumentSource};
+
+/// Elements whose whole subtree is removed by default.
+pub const DEFAULT_DROPPED_TAGS: &[&str] = &[
+    "script", "style", "noscript", "template", "svg", "canvas", "iframe", "object", "head", "nav",
+    "header", "footer", "aside", "form", "button", "select",
+];
+
+/// Elements whose content is raw text that may contain `<`.
+const RAW_TEXT_TAGS: &[&str] = &["script", "style", "textarea", "title"];
+
+/// Elements that never have a closing tag.
+const VOID_TAGS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// Elements that start a new line of text.
+const BLOCK_TAGS: &[&str] = &[
+    "address",
+    "article",
+    "blockquote",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "li",
+    "main",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "tr",
+    "ul",
+];
+
+/// Approximate characters per token used by [`HtmlToTextOptions::with_max_tokens`].
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Options for [`html_to_text`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HtmlToTextOptions {
+    /// Elements whose subtree is removed entirely.
+    pub drop_tags: Vec<String>,
+    /// When set, only text inside at least one of these elements is kept
+    /// (e.g. `["article", "main"]`).
+    pub allow_tags: Option<Vec<String>>,
+    /// Maximum characters of output text; truncation happens on a paragraph or
+    /// line boundary where possible.
+    pub max_chars: Option<usize>,
+}
+
+impl Default for HtmlToTextOptions {
+    fn default() -> Self {
+        Self {
+            drop_tags: DEFAULT_DROPPED_TAGS.iter().map(|t| t.to_string()).collect(),
+            allow_tags: None,
+            max_chars: None,
+        }
+    }
+}
+
+impl HtmlToTextOptions {
+    /// Create default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drop an additional element's subtree
+    pub fn drop_tag(mut self, tag: impl Into<String>) -> Self {
+        self.drop_tags.push(tag.into().to_ascii_lowercase());
+        self
+    }
+
+    /// Keep only text inside the given elements
+    pub fn with_allow_tags<I, S>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allow_tags = Some(
+            tags.into_iter()
+                .map(|t| t.into().to_ascii_lowercase())
+                .collect(),
+        );
+        self
+    }
+
+    /// Limit output to a number of characters
+    pub fn with_max_chars(mut self, max_chars: usize) -> Self {
+        self.max_chars = Some(max_chars);
+        self
+    }
+
+    /// Limit output to an approximate token budget
+    pub fn with_max_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_chars = Some(max_tokens.saturating_mul(CHARS_PER_TOKEN));
+        self
+    }
+}
+
+/// Cleaned text extracted from an HTML page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HtmlText {
+    /// Contents of the `<title>` element, if any.
+    pub title: Option<String>,
+    /// Readable text.
+    pub text: String,
+    /// Whether `text` was cut to fit the budget.
+    pub truncated: bool,
+}
+
+impl HtmlText {
+    /// Plain text content block.
+    pub fn to_text_block(&self) -> ContentBlock {
+        ContentBlock::text(self.text.clone())
+    }
+
+    /// Content-based document block, one text block per paragraph, titled with
+    /// the page title.
+    pub fn to_document_block(&self) -> ContentBlock {
+        let content = self
+            .text
+            .split("\n\n")
+            .filter(|p| !p.trim().is_empty())
+            .map(|p| serde_json::json!({"type": "text", "text": p}))
+            .collect();
+        match ContentBlock::document(DocumentSource::content(content)) {
+            ContentBlock::Document {
+                source,
+                context,
+                citations,
+                cache_control,
+                ..
+            } => ContentBlock::Document {
+                source,
+                title: self.title.clone(),
+                context,
+                citations,
+                cache_control,
+            },
+            other => other,
+        }
+    }
+}
+
+/// Strip markup, scripts and boilerplate from HTML and return readable text.
+pub fn html_to_text(html: &str, options: &HtmlToTextOptions) -> HtmlText {
+    let mut out = TextSink::default();
+    let mut title: Option<String> = None;
+    let mut open: Vec<String> = Vec::new();
+    let mut dropped = 0usize;
+    let mut allowed = 0usize;
+    let allow_all = options.allow_tags.is_none();
+    let is_dropped = |name: &str| options.drop_tags.iter().any(|t| t == name);
+    let is_allowed = |name: &str| {
+        options
+            .allow_tags
+            .as_ref()
+            .is_some_and(|tags| tags.iter().any(|t| t == name))
+    };
+
+    let mut rest = html;
+    while !rest.is_empty() {
+        let Some(lt) = rest.find('<') else {
+            if dropped == 0 && (allow_all || allowed > 0) {
+                out.push_text(rest);
+            }
+            break;
+        };
+        if dropped == 0 && (allow_all || allowed > 0) {
+            out.push_text(&rest[..lt]);
+        }
+        rest = &rest[lt..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+            continue;
+        }
+
+        let Some(tag) = parse_tag(rest) else {
+            // A stray '<' that does not start a tag is literal text.
+            if dropped == 0 && (allow_all || allowed > 0) {
+                out.push_text("<");
+            }
+            rest = &rest[1..];
+            continue;
+        };
+        rest = &rest[tag.len..];
+        let name = tag.name.as_str();
+
+        if tag.closing {
+            if let Some(pos) = open.iter().rposition(|t| t == name) {
+                for closed in open.drain(pos..) {
+                    if is_dropped(&closed) {
+                        dropped -= 1;
+                    }
+                    if is_allowed(&closed) {
+                        allowed -= 1;
+                    }
+                    if closed == "pre" {
+                        out.pre = out.pre.saturating_sub(1);
+                    }
+                }
+            }
+            if BLOCK_TAGS.contains(&name) {
+                out.paragraph_break(name);
+            }
+            continue;
+        }
+
+        if RAW_TEXT_TAGS.contains(&name) && !tag.self_closing {
+            let (raw, after) = split_raw_text(rest, name);
+            if name == "title" && title.is_none() {
+                let text = collapse_whitespace(&decode_entities(raw));
+                if !text.is_empty() {
+                    title = Some(text);
+                }
+            } else if name == "textarea" && dropped == 0 && (allow_all || allowed > 0) {
+                out.push_text(raw);
+            }
+            rest = after;
+            continue;
+        }
+
+        if name == "br" {
+            out.hard_break();
+            continue;
+        }
+        if BLOCK_TAGS.contains(&name) {
+            out.paragraph_break(name);
+            if dropped == 0 && (allow_all || allowed > 0) {
+                out.block_prefix(name);
+            }
+        }
+        if VOID_TAGS.contains(&name) || tag.self_closing {
+            continue;
+        }
+        if is_dropped(name) {
+            dropped += 1;
+        }
+        if is_allowed(name) {
+            allowed += 1;
+        }
+        if name == "pre" {
+            out.pre += 1;
+        }
+        open.push(tag.name);
+    }
+
+    let text = out.finish();
+    match options.max_chars {
+        Some(max) if text.chars().count() > max => HtmlText {
+            title,
+            text: truncate_on_boundary(&text, max),
+            truncated: true,
+        },
+        _ => HtmlText {
+            title,
+            text,
+            truncated: false,
+        },
+    }
+}
+
+struct Tag {
+    name: String,
+    closing: bool,
+    self_closing: bool,
+    len: usize,
+}
+
+/// Parse a tag at the start of `input` (which begins with `<`), honouring
+/// quoted attribute values that contain `>`.
+fn parse_tag(input: &str) -> Option<Tag> {
+    let bytes = input.as_bytes();
+    let mut i = 1;
+    let closing = bytes.get(i) == Some(&b'/');
+    if closing {
+        i += 1;
+    }
+    let name_start = i;
+    while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'-') {
+        i += 1;
+    }
+    if i == name_start {
+        return None;
+    }
+    let name = input[name_start..i].to_ascii_lowercase();
+
+    let mut quote: Option<u8> = None;
+    while i < bytes.len() {
+        match (quote, bytes[i]) {
+            (Some(q), b) if b == q => quote = None,
+            (None, b'"') | (None, b'\'') => quote = Some(bytes[i]),
+            (None, b'>') => {
+                return Some(Tag {
+                    name,
+                    closing,
+                    self_closing: bytes[i - 1] == b'/',
+                    len: i + 1,
+                })
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Split raw element content from the remainder after its closing tag.
+fn split_raw_text<'a>(input: &'a str, name: &str) -> (&'a str, &'a str) {
+    let needle = format!("</{}", name);
+    let lower = input.to_ascii_lowercase();
+    match lower.find(&needle) {
+        Some(start) => {
+            let after = &input[start..];
+            let end = after.find('>').map_or(after.len(), |e| e + 1);
+            (&input[..start], &after[end..])
+        }
+        None => (input, ""),
+    }
+}
+
+#[derive(Default)]
+struct TextSink {
+    out: String,
+    pending_space: bool,
+    pre: usize,
+}
+
+impl TextSink {
+    fn push_text(&mut self, raw: &str) {
+        let decoded = decode_entities(raw);
+        if self.pre > 0 {
+            self.out.push_str(&decoded);
+            return;
+        }
+        for c in decoded.chars() {
+            if c.is_whitespace() {
+                self.pending_space = true;
+            } else {
+                if self.pending_space && !self.out.is_empty() && !self.out.ends_with('\n') {
+                    self.out.push(' ');
+                }
+                self.pending_space = false;
+                self.out.push(c);
+            }
+        }
+    }
+
+    fn line_break(&mut self) {
+        self.newline(false);
+    }
+
+    fn hard_break(&mut self) {
+        self.newline(true);
+    }
+
+    fn newline(&mut self, force: bool) {
+        self.pending_space = false;
+        let trimmed = self.out.trim_end_matches(' ').len();
+        self.out.truncate(trimmed);
+        if !self.out.is_empty() && (force || !self.out.ends_with('\n')) {
+            self.out.push('\n');
+        }
+    }
+
+    fn paragraph_break(&mut self, tag: &str) {
+        self.line_break();
+        // List items and table rows only need a line break between them.
+        if !matches!(tag, "li" | "tr" | "dt" | "dd") && !self.out.is_empty() {
+            while !self.out.ends_with("\n\n") {
+                self.out.push('\n');
+            }
+        }
+    }
+
+    fn block_prefix(&mut self, tag: &str) {
+        let prefix = match tag {
+            "h1" => "# ",
+            "h2" => "## ",
+            "h3" => "### ",
+            "h4" | "h5" | "h6" => "#### ",
+            "li" => "- ",
+            _ => return,
+        };
+        self.out.push_str(prefix);
+    }
+
+    fn finish(self) -> String {
+        let mut text = String::with_capacity(self.out.len());
+        for line in self.out.lines() {
+            let line = line.trim_end();
+            let is_marker = matches!(line, "-" | "#" | "##" | "###" | "####");
+            if is_marker {
+                continue;
+            }
+            if line.is_empty() && (text.is_empty() || text.ends_with("\n\n")) {
+                continue;
+            }
+            text.push_str(line);
+            text.push('\n');
+        }
+        text.trim_end().to_string()
+    }
+}
+
+/// Decode the common named and numeric HTML entities.
+pub fn decode_entities(input: &str) -> String {
+    if !input.contains('&') {
+        return input.to_string();
+    }
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                "mdash" => Some('—'),
+                "ndash" => Some('–'),
+                "hellip" => Some('…'),
+                "copy" => Some('©'),
+                _ => entity.strip_prefix('#').and_then(|num| {
+                    let code = match num.strip_prefix(['x', 'X']) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                        None => num.parse().ok(),
+                    };
+                    code.and_then(char::from_u32)
+                }),
+            };
+            c.map(|c| (c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn collapse_whitespace(input: &str) -> String {
+    input.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Cut `text` to at most `max` characters, preferring a paragraph, then line,
+/// then word boundary.
+fn truncate_on_boundary(text: &str, max: usize) -> String {
+    const MARKER: &str = "\n[... truncated]";
+    let budget = max.saturating_sub(MARKER.chars().count());
+    let head = super::text::truncate_chars(text, budget, None);
+    let end = head.len();
+    let cut = head
+        .rfind("\n\n")
+        .or_else(|| head.rfind('\n'))
+        .or_else(|| head.rfind(' '))
+        .filter(|&i| i > 0)
+        .unwrap_or(end);
+    format!("{}{}", head[..cut].trim_end(), MARKER)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html><head><title>Release &amp; Notes</title><style>p { color: red }</style></head>
+<body>
+<nav><a href="/">Home</a> | <a href="/docs">Docs</a></nav>
+<script>if (a < b) { alert("x"); }</script>
+<main>
+  <h1>Version 2.0</h1>
+  <p>The   new release&nbsp;adds <b>streaming</b>.<br>See below.</p>
+  <ul><li>Faster</li><li>Smaller</li></ul>
+</main>
+<!-- tracking pixel -->
+<footer>Copyright 2026</footer>
+</body></html>"#;
+
+    #[test]
+    fn test_strips_scripts_and_boilerplate() {
+        let result = html_to_text(PAGE, &HtmlToTextOptions::default());
+        assert_eq!(result.title.as_deref(), Some("Release & Notes"));
+        assert_eq!(
+            result.text,
+            "# Version 2.0\n\nThe new release adds streaming.\nSee below.\n\n- Faster\n- Smaller"
+        );
+        assert!(!result.truncated);
+    }
+
+    #[test]
+    fn test_allowlist_keeps_only_selected_elements() {
+        let html = "<div>skip me</div><article><p>keep <a href='x>y'>this</a></p></article>";
+        let options = HtmlToTextOptions::new().with_allow_tags(["article"]);
+        assert_eq!(html_to_text(html, &options).text, "keep this");
+    }
+
+    #[test]
+    fn test_truncation_on_paragraph_boundary() {
+        let html = "<p>first paragraph here</p><p>second paragraph that is long</p>";
+        let result = html_to_text(html, &HtmlToTextOptions::new().with_max_chars(40));
+        assert!(result.truncated);
+        assert_eq!(result.text, "first paragraph here\n[... truncated]");
+    }
+
+    #[test]
+    fn test_decode_entities() {
+        assert_eq!(
+            decode_entities("a &lt;b&gt; &#65;&#x42; &bogus; &"),
+            "a <b> AB &bogus; &"
+        );
+    }
+
+    #[test]
+    fn test_document_block_has_title_and_paragraphs() {
+        let result = html_to_text(PAGE, &HtmlToTextOptions::default());
+        match result.to_document_block() {
+            ContentBlock::Document {
+                source: DocumentSource::Content { content },
+                title,
+                ..
+            } => {
+                assert_eq!(title.as_deref(), Some("Release & Notes"));
+                assert_eq!(content.len(), 3);
+            }
+            other => panic!("unexpected block: {:?}", other),
+        }
+    }
+}
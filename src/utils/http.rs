@@ -2,28 +2,218 @@
 
 use crate::{
     config::Config,
-    error::{AnthropicError, Result},
+    error::{AnthropicError, ResponseClass, Result},
+    metrics::{endpoint, BufferGauge, BufferKind},
     types::{ApiErrorResponse, HttpMethod},
+    utils::{
+        canonical::canonicalize, failover::EndpointFailover, instrumentation,
+        signing::SignableRequest,
+    },
+    warnings::DEFAULT_RATE_LIMIT_THRESHOLD,
+};
+use futures::{stream, StreamExt};
+use reqwest::{
+    header::{HeaderMap, HeaderValue, CONTENT_LENGTH, CONTENT_TYPE, LOCATION},
+    multipart::Form,
+    Client, ClientBuilder, ResponseBuilderExt, StatusCode,
 };
-use reqwest::{header::HeaderMap, multipart::Form, Client, ClientBuilder};
 use serde::de::DeserializeOwned;
-use std::{sync::Arc, time::Duration};
+use std::{io, sync::Arc, time::Duration};
 use url::Url;
 
+/// Result of a request the API may finish asynchronously
+#[derive(Debug, Clone, PartialEq)]
+pub enum MaybeAccepted<T> {
+    /// The resource, parsed from the response body
+    Ready(T),
+    /// `202 Accepted` without a parseable resource; fetch it later
+    Accepted(AcceptedResponse),
+}
+
+/// Details of a `202 Accepted` response
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AcceptedResponse {
+    /// `Location` header, pointing at the resource being created
+    pub location: Option<String>,
+    /// Suggested wait before fetching it (`retry-after`)
+    pub retry_after: Option<Duration>,
+    /// Whatever JSON the body held, if any
+    pub body: Option<serde_json::Value>,
+}
+
+/// Largest response bodies the client will read, per [`ResponseClass`].
+///
+/// A misbehaving gateway or proxy can answer with an endless or enormous
+/// body; past these limits the read is abandoned with
+/// [`AnthropicError::ResponseTooLarge`] instead of buffering it all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResponseLimits {
+    /// Ordinary JSON responses (64 MiB)
+    pub api: u64,
+    /// Error responses (1 MiB); longer ones are cut off rather than failing
+    pub error: u64,
+    /// File downloads and raw batch results (1 GiB)
+    pub download: u64,
+    /// A single server-sent event or streamed results line (16 MiB)
+    pub stream_event: u64,
+}
+
+impl Default for ResponseLimits {
+    fn default() -> Self {
+        Self {
+            api: 64 << 20,
+            error: 1 << 20,
+            download: 1 << 30,
+            stream_event: 16 << 20,
+        }
+    }
+}
+
+impl ResponseLimits {
+    /// Default limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the limit for `class`
+    pub fn with_limit(mut self, class: ResponseClass, bytes: u64) -> Self {
+        match class {
+            ResponseClass::Api => self.api = bytes,
+            ResponseClass::Error => self.error = bytes,
+            ResponseClass::Download => self.download = bytes,
+            ResponseClass::StreamEvent => self.stream_event = bytes,
+        }
+        self
+    }
+
+    /// The limit for `class`
+    pub fn limit(&self, class: ResponseClass) -> u64 {
+        match class {
+            ResponseClass::Api => self.api,
+            ResponseClass::Error => self.error,
+            ResponseClass::Download => self.download,
+            ResponseClass::StreamEvent => self.stream_event,
+        }
+    }
+}
+
+/// Read `response`'s body, failing once it passes the limit for `class`
+pub(crate) async fn read_body(
+    response: reqwest::Response,
+    limits: &ResponseLimits,
+    class: ResponseClass,
+) -> Result<Vec<u8>> {
+    read_limited(response, limits, class, None).await
+}
+
+/// A download's body, counted towards the buffering metrics of `kind`
+pub(crate) async fn read_download(
+    response: reqwest::Response,
+    limits: &ResponseLimits,
+    kind: BufferKind,
+) -> Result<Vec<u8>> {
+    let mut gauge = BufferGauge::new(kind);
+    read_limited(response, limits, ResponseClass::Download, Some(&mut gauge)).await
+}
+
+async fn read_limited(
+    mut response: reqwest::Response,
+    limits: &ResponseLimits,
+    class: ResponseClass,
+    mut gauge: Option<&mut BufferGauge>,
+) -> Result<Vec<u8>> {
+    let limit = limits.limit(class);
+    let too_large = || AnthropicError::ResponseTooLarge { class, limit };
+    let declared = response.content_length().or_else(|| {
+        response
+            .headers()
+            .get(CONTENT_LENGTH)?
+            .to_str()
+            .ok()?
+            .parse()
+            .ok()
+    });
+    if declared.is_some_and(|length| length > limit) {
+        return Err(too_large());
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if (body.len() + chunk.len()) as u64 > limit {
+            return Err(too_large());
+        }
+        body.extend_from_slice(&chunk);
+        if let Some(gauge) = gauge.as_deref_mut() {
+            gauge.set(body.len());
+        }
+    }
+    Ok(body)
+}
+
+/// An error response's body as text, cut off at the error limit
+pub(crate) async fn read_error_text(
+    mut response: reqwest::Response,
+    limits: &ResponseLimits,
+) -> Result<String> {
+    let limit = usize::try_from(limits.error).unwrap_or(usize::MAX);
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        let room = limit - body.len();
+        body.extend_from_slice(&chunk[..chunk.len().min(room)]);
+        if chunk.len() >= room {
+            break;
+        }
+    }
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+/// `response` with each wait for the next body chunk bounded by `timeout`.
+///
+/// A stall fails the read with an [`io::ErrorKind::TimedOut`] error, which
+/// [`reqwest::Error::is_timeout`] reports, and ends the body.
+fn with_read_timeout(response: reqwest::Response, timeout: Duration) -> reqwest::Response {
+    let mut builder = http::Response::builder()
+        .status(response.status())
+        .version(response.version())
+        .url(response.url().clone());
+    if let Some(headers) = builder.headers_mut() {
+        *headers = response.headers().clone();
+    }
+    let body = stream::unfold(Some(response.bytes_stream()), move |body| async move {
+        let mut body = body?;
+        match tokio::time::timeout(timeout, body.next()).await {
+            Ok(Some(chunk)) => Some((chunk.map_err(BoxError::from), Some(body))),
+            Ok(None) => None,
+            Err(_) => {
+                let stalled = io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("no response data received for {:?}", timeout),
+                );
+                Some((Err(BoxError::from(stalled)), None))
+            }
+        }
+    });
+    builder
+        .body(reqwest::Body::wrap_stream(body))
+        .expect("parts come from a valid response")
+        .into()
+}
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
 /// HTTP client wrapper for making API requests
 #[derive(Clone)]
 pub struct HttpClient {
     client: Client,
-    #[allow(dead_code)]
     config: Arc<Config>,
 }
 
 impl HttpClient {
     /// Create a new HTTP client
     pub fn new(config: Arc<Config>) -> Self {
-        let mut builder = ClientBuilder::new()
-            .timeout(config.timeout)
-            .user_agent(&config.user_agent);
+        // Timeouts are set per request; a client-wide one would also cut off
+        // long-running streams, whose bodies get a read timeout instead (see
+        // `request_stream`).
+        let mut builder = ClientBuilder::new().user_agent(&config.user_agent);
 
         // Configure TLS
         #[cfg(feature = "native-tls")]
@@ -41,13 +231,16 @@ impl HttpClient {
         Self { client, config }
     }
 
-    /// Helper method to build request with common configuration
+    /// Helper method to build request with common configuration.
+    ///
+    /// `timeout` bounds the whole exchange, body included; `None` leaves it
+    /// unbounded.
     fn build_request_builder(
         &self,
         method: HttpMethod,
         url: &Url,
         headers: HeaderMap,
-        timeout: Duration,
+        timeout: Option<Duration>,
     ) -> reqwest::RequestBuilder {
         let request_builder = match method {
             HttpMethod::Get => self.client.get(url.clone()),
@@ -57,7 +250,131 @@ impl HttpClient {
             HttpMethod::Delete => self.client.delete(url.clone()),
         };
 
-        request_builder.headers(headers).timeout(timeout)
+        let request_builder = request_builder.headers(headers);
+        match timeout {
+            Some(timeout) => request_builder.timeout(timeout),
+            None => request_builder,
+        }
+    }
+
+    /// Build a request with an optional JSON body, signing it when a
+    /// [`RequestSigner`](crate::utils::signing::RequestSigner) is configured
+    fn build_json_request(
+        &self,
+        method: HttpMethod,
+        url: &Url,
+        mut headers: HeaderMap,
+        body: Option<serde_json::Value>,
+        timeout: Option<Duration>,
+    ) -> Result<reqwest::RequestBuilder> {
+        let Some(signer) = &self.config.request_signer else {
+            let request_builder = self.build_request_builder(method, url, headers, timeout);
+            return Ok(match body {
+                Some(body) => request_builder.json(&body),
+                None => request_builder,
+            });
+        };
+
+        let body = body.map(|body| canonicalize(&body).into_bytes());
+        if body.is_some() {
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        }
+        let signature = signer.sign(&SignableRequest {
+            method,
+            url,
+            headers: &headers,
+            body: body.as_deref(),
+        })?;
+        headers.extend(signature);
+
+        let request_builder = self.build_request_builder(method, url, headers, timeout);
+        Ok(match body {
+            Some(body) => request_builder.body(body),
+            None => request_builder,
+        })
+    }
+
+    /// Send a request, routing it through the configured failover endpoints
+    async fn send<F>(&self, url: &Url, build: F) -> Result<reqwest::Response>
+    where
+        F: FnOnce(&Url) -> Result<reqwest::RequestBuilder>,
+    {
+        let Some((index, endpoint)) = self.config.failover.as_ref().and_then(|f| f.select()) else {
+            return self.execute(build(url)?).await;
+        };
+        let failover = self.config.failover.as_ref().expect("failover selected");
+        let target = EndpointFailover::rewrite(url, &self.config.base_url, endpoint);
+
+        match self.execute(build(&target)?).await {
+            Ok(response) => {
+                if response.status().is_server_error() {
+                    failover.record_failure(index);
+                } else {
+                    failover.record_success(index);
+                }
+                Ok(response)
+            }
+            Err(AnthropicError::Http(error)) => {
+                if error.is_connect() || error.is_timeout() {
+                    failover.record_failure(index);
+                }
+                Err(AnthropicError::Http(error))
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Send a request through the configured [`Middleware`](crate::utils::middleware::Middleware)
+    async fn execute(&self, builder: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let middleware = &self.config.middleware;
+        let mut request = builder.build()?;
+        let adaptive = self
+            .config
+            .adaptive_rate_limit
+            .as_ref()
+            .filter(|_| self.config.enable_rate_limiting);
+        if let Some(limiter) = adaptive {
+            let started = std::time::Instant::now();
+            // Adaptive limiters only ever wait, they never fail
+            let _ = limiter.acquire().await;
+            if let Some(metrics) = &self.config.metrics {
+                metrics.rate_limit_wait(started.elapsed());
+            }
+        }
+        let (method, url) = (request.method().clone(), request.url().clone());
+        let started = std::time::Instant::now();
+        let wire_logging = self.config.wire_logging.as_ref();
+        let result = instrumentation::http(&method, &url, async {
+            let mut response = match middleware.on_request(&mut request)? {
+                Some(response) => response,
+                None => {
+                    if let Some(policy) = wire_logging {
+                        policy.log_request(&request);
+                    }
+                    self.client.execute(request).await?
+                }
+            };
+            middleware.on_response(&mut response)?;
+            Ok(match wire_logging {
+                Some(policy) => policy.log_response(response),
+                None => response,
+            })
+        })
+        .await;
+        if let Ok(response) = &result {
+            let info = Self::parse_rate_limit_headers(response.headers());
+            if let Some(limiter) = adaptive {
+                limiter.update_from_headers(&info);
+            }
+            self.config
+                .warnings
+                .check_rate_limit(&info, DEFAULT_RATE_LIMIT_THRESHOLD);
+        }
+        if let Some(metrics) = &self.config.metrics {
+            let status = result.as_ref().ok().map(|r| r.status().as_u16());
+            metrics.request(&endpoint(&url), status, started.elapsed());
+        }
+        result
     }
 
     /// Make an HTTP request and parse the JSON response
@@ -72,18 +389,62 @@ impl HttpClient {
     where
         T: DeserializeOwned,
     {
-        let request_builder = self.build_request_builder(method, url, headers, timeout);
-        let request_builder = if let Some(body) = body {
-            request_builder.json(&body)
-        } else {
-            request_builder
-        };
-
-        let response = request_builder.send().await.map_err(AnthropicError::Http)?;
+        let response = self
+            .send(url, |url| {
+                self.build_json_request(method, url, headers, body, Some(timeout))
+            })
+            .await?;
         self.handle_response(response).await
     }
 
-    /// Make a streaming HTTP request
+    /// Make an HTTP request whose response may be `202 Accepted` with an
+    /// empty or partial body instead of the finished resource
+    pub async fn request_accepting<T>(
+        &self,
+        method: HttpMethod,
+        url: &Url,
+        body: Option<serde_json::Value>,
+        headers: HeaderMap,
+        timeout: Duration,
+    ) -> Result<MaybeAccepted<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let response = self
+            .send(url, |url| {
+                self.build_json_request(method, url, headers, body, Some(timeout))
+            })
+            .await?;
+        if response.status() != StatusCode::ACCEPTED {
+            return self
+                .handle_response(response)
+                .await
+                .map(MaybeAccepted::Ready);
+        }
+
+        let headers = response.headers().clone();
+        let body = read_body(response, &self.config.response_limits, ResponseClass::Api).await?;
+        let text = String::from_utf8_lossy(&body);
+        if let Ok(resource) = serde_json::from_str::<T>(&text) {
+            return Ok(MaybeAccepted::Ready(resource));
+        }
+        Ok(MaybeAccepted::Accepted(AcceptedResponse {
+            location: headers
+                .get(LOCATION)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string),
+            retry_after: Self::parse_rate_limit_headers(&headers).retry_after,
+            body: serde_json::from_str(&text).ok(),
+        }))
+    }
+
+    /// Make a streaming HTTP request.
+    ///
+    /// `timeout` covers waiting for the response headers and then each wait
+    /// for more of the body, so a long generation or download is not cut off
+    /// part-way while data (SSE pings included) keeps arriving, but a stalled
+    /// body fails with a timeout instead of hanging.
+    /// [`Config::stream_idle_timeout`] can end message streams sooner.
     pub async fn request_stream(
         &self,
         method: HttpMethod,
@@ -92,14 +453,13 @@ impl HttpClient {
         headers: HeaderMap,
         timeout: Duration,
     ) -> Result<reqwest::Response> {
-        let request_builder = self.build_request_builder(method, url, headers, timeout);
-        let request_builder = if let Some(body) = body {
-            request_builder.json(&body)
-        } else {
-            request_builder
-        };
-
-        request_builder.send().await.map_err(AnthropicError::Http)
+        let send = self.send(url, |url| {
+            self.build_json_request(method, url, headers, body, None)
+        });
+        let response = tokio::time::timeout(timeout, send)
+            .await
+            .map_err(|_| AnthropicError::Timeout(timeout))??;
+        Ok(with_read_timeout(response, timeout))
     }
 
     /// Make a multipart form request (for file uploads)
@@ -124,10 +484,48 @@ impl HttpClient {
             ));
         }
 
-        let request_builder = self.build_request_builder(method, url, headers, timeout);
-        let request_builder = request_builder.multipart(form);
+        let Some(signer) = &self.config.request_signer else {
+            let response = self
+                .send(url, |url| {
+                    Ok(self
+                        .build_request_builder(method, url, headers, Some(timeout))
+                        .multipart(form))
+                })
+                .await?;
+            return self.handle_response(response).await;
+        };
+
+        // Encode the form up front so the signer covers the exact bytes (and
+        // boundary) that go on the wire
+        let mut encoded = self.client.post(url.clone()).multipart(form).build()?;
+        let content_type = encoded.headers().get(CONTENT_TYPE).cloned();
+        let body = match encoded.body_mut().take() {
+            Some(body) => {
+                reqwest::Response::from(http::Response::new(body))
+                    .bytes()
+                    .await?
+            }
+            None => bytes::Bytes::new(),
+        };
 
-        let response = request_builder.send().await.map_err(AnthropicError::Http)?;
+        let response = self
+            .send(url, |url| {
+                let mut headers = headers;
+                if let Some(content_type) = content_type {
+                    headers.insert(CONTENT_TYPE, content_type);
+                }
+                let signature = signer.sign(&SignableRequest {
+                    method,
+                    url,
+                    headers: &headers,
+                    body: Some(&body),
+                })?;
+                headers.extend(signature);
+                Ok(self
+                    .build_request_builder(method, url, headers, Some(timeout))
+                    .body(body.clone()))
+            })
+            .await?;
         self.handle_response(response).await
     }
 
@@ -139,35 +537,41 @@ impl HttpClient {
         let status = response.status();
 
         if status.is_success() {
-            let json = response.json().await?;
-            Ok(json)
+            let body =
+                read_body(response, &self.config.response_limits, ResponseClass::Api).await?;
+            Ok(serde_json::from_slice(&body)?)
         } else {
             let status_code = status.as_u16();
+            let retry_after =
+                Self::parse_rate_limit_headers(response.headers()).recommended_delay();
+            Err(self
+                .api_error(status_code, response)
+                .await
+                .with_retry_after(retry_after))
+        }
+    }
 
-            // Try to parse error response
-            match response.text().await {
-                Ok(error_text) => {
-                    // Try to parse as API error response
-                    if let Ok(api_error) = serde_json::from_str::<ApiErrorResponse>(&error_text) {
-                        Err(AnthropicError::api_error(
-                            status_code,
-                            api_error.message,
-                            Some(api_error.error_type),
-                        ))
-                    } else {
-                        // Fallback to raw error text
-                        Err(AnthropicError::api_error(status_code, error_text, None))
-                    }
-                }
-                Err(_) => {
-                    // Can't read response body
-                    Err(AnthropicError::api_error(
+    /// The error for a failed `response`
+    async fn api_error(&self, status_code: u16, response: reqwest::Response) -> AnthropicError {
+        // Try to parse error response
+        match read_error_text(response, &self.config.response_limits).await {
+            Ok(error_text) => {
+                // Try to parse as API error response
+                if let Ok(api_error) = serde_json::from_str::<ApiErrorResponse>(&error_text) {
+                    AnthropicError::api_error(
                         status_code,
-                        format!("HTTP {}", status_code),
-                        None,
-                    ))
+                        api_error.message,
+                        Some(api_error.error_type),
+                    )
+                } else {
+                    // Fallback to raw error text
+                    AnthropicError::api_error(status_code, error_text, None)
                 }
             }
+            Err(_) => {
+                // Can't read response body
+                AnthropicError::api_error(status_code, format!("HTTP {}", status_code), None)
+            }
         }
     }
 
@@ -183,34 +587,44 @@ impl HttpClient {
 
     /// Check if a request should be retried based on status code
     pub fn should_retry(status_code: u16) -> bool {
-        matches!(status_code, 429 | 500 | 502 | 503 | 504)
+        matches!(status_code, 429 | 500 | 502 | 503 | 504 | 529)
     }
 
-    /// Get rate limit headers from response
+    /// Get rate limit headers from response.
+    ///
+    /// Reads the generic `x-ratelimit-*` headers, falling back to the API's
+    /// `anthropic-ratelimit-requests-*` ones, and `retry-after` in seconds or
+    /// as an HTTP date.
     pub fn parse_rate_limit_headers(headers: &HeaderMap) -> RateLimitInfo {
-        let remaining = headers
-            .get("x-ratelimit-remaining")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|s| s.parse().ok());
+        let header = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|name| headers.get(*name).and_then(|v| v.to_str().ok()))
+        };
+
+        let remaining = header(&[
+            "x-ratelimit-remaining",
+            "anthropic-ratelimit-requests-remaining",
+        ])
+        .and_then(|s| s.parse().ok());
 
-        let limit = headers
-            .get("x-ratelimit-limit")
-            .and_then(|v| v.to_str().ok())
+        let limit = header(&["x-ratelimit-limit", "anthropic-ratelimit-requests-limit"])
             .and_then(|s| s.parse().ok());
 
-        let reset = headers
-            .get("x-ratelimit-reset")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|s| s.parse::<i64>().ok())
-            .map(|timestamp| {
-                chrono::DateTime::from_timestamp(timestamp, 0).unwrap_or_else(chrono::Utc::now)
-            });
+        let reset = header(&["x-ratelimit-reset", "anthropic-ratelimit-requests-reset"])
+            .and_then(crate::utils::timestamp::parse_timestamp);
 
-        let retry_after = headers
-            .get("retry-after")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|s| s.parse::<u64>().ok())
-            .map(Duration::from_secs);
+        let retry_after = header(&["retry-after"]).and_then(|value| {
+            if let Ok(seconds) = value.trim().parse::<u64>() {
+                return Some(Duration::from_secs(seconds));
+            }
+            let date = chrono::DateTime::parse_from_rfc2822(value.trim()).ok()?;
+            Some(
+                (date.with_timezone(&chrono::Utc) - chrono::Utc::now())
+                    .to_std()
+                    .unwrap_or(Duration::ZERO),
+            )
+        });
 
         RateLimitInfo {
             remaining,
@@ -254,17 +668,23 @@ impl RateLimitInfo {
 
         // If we're close to the limit and have reset time, calculate delay
         if self.is_approaching_limit(0.8) {
-            if let Some(reset_time) = self.reset {
-                let now = chrono::Utc::now();
-                if reset_time > now {
-                    let delay = (reset_time - now)
-                        .to_std()
-                        .unwrap_or(Duration::from_secs(1));
-                    return Some(delay.min(Duration::from_secs(60))); // Cap at 1 minute
-                }
+            if let Some(delay) = self.time_until_reset() {
+                return Some(delay.min(Duration::from_secs(60))); // Cap at 1 minute
             }
         }
 
         None
     }
+
+    /// Time until the window resets, or `None` if unknown or already reset.
+    ///
+    /// The reset time comes from the server's clock, so a host clock that
+    /// runs a little fast is allowed for
+    /// ([`DEFAULT_CLOCK_SKEW`](crate::utils::timestamp::DEFAULT_CLOCK_SKEW))
+    /// rather than treating the window as already reset.
+    pub fn time_until_reset(&self) -> Option<Duration> {
+        let reset = self.reset?;
+        let skew = crate::utils::timestamp::DEFAULT_CLOCK_SKEW;
+        crate::utils::timestamp::time_until(reset, skew)
+    }
 }
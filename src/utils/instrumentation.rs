@@ -0,0 +1,148 @@
+//! `tracing` spans and events for API calls, enabled by the `tracing`
+//! feature
+//!
+//! With the feature on:
+//!
+//! * Messages, Files and Message Batches calls each run in an INFO span
+//!   named after the call, e.g. `anthropic.messages.create` with the model
+//!   and, once the response is in, `message_id`, token usage and
+//!   `stop_reason`;
+//! * every HTTP attempt runs in a DEBUG `anthropic.http` span recording the
+//!   status, the API's `request-id` and `latency_ms`, so retries show up as
+//!   sibling attempts;
+//! * a message stream runs in an INFO `anthropic.stream` span for as long as
+//!   it is read, recording the message id, model and token usage as the
+//!   events arrive.
+//!
+//! Without the feature the helpers here do nothing.
+
+use crate::{
+    error::Result,
+    models::message::{MessageResponse, StreamEvent},
+};
+use std::future::Future;
+
+/// Run one HTTP attempt, `send`, in an `anthropic.http` span
+#[cfg(feature = "tracing")]
+pub(crate) async fn http<F>(
+    method: &reqwest::Method,
+    url: &url::Url,
+    send: F,
+) -> Result<reqwest::Response>
+where
+    F: Future<Output = Result<reqwest::Response>>,
+{
+    use tracing::{field::Empty, Instrument};
+
+    let span = tracing::debug_span!(
+        "anthropic.http",
+        method = %method,
+        path = url.path(),
+        status = Empty,
+        request_id = Empty,
+        latency_ms = Empty,
+    );
+    let started = std::time::Instant::now();
+    let result = send.instrument(span.clone()).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    span.record("latency_ms", latency_ms);
+    match &result {
+        Ok(response) => {
+            let status = response.status().as_u16();
+            let request_id = response
+                .headers()
+                .get("request-id")
+                .and_then(|value| value.to_str().ok());
+            span.record("status", status);
+            if let Some(request_id) = request_id {
+                span.record("request_id", request_id);
+            }
+            tracing::debug!(parent: &span, status, request_id, latency_ms, "Response received");
+        }
+        Err(error) => {
+            tracing::debug!(parent: &span, latency_ms, %error, "Request failed");
+        }
+    }
+    result
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) async fn http<F>(_: &reqwest::Method, _: &url::Url, send: F) -> Result<reqwest::Response>
+where
+    F: Future<Output = Result<reqwest::Response>>,
+{
+    send.await
+}
+
+/// Record `value` as `field` on the current span
+pub(crate) fn record<V: tracing::Value>(field: &str, value: V) {
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record(field, value);
+    #[cfg(not(feature = "tracing"))]
+    let _ = (field, value);
+}
+
+/// Record `response`'s id, usage and stop reason on the current span
+pub(crate) fn record_message(response: &MessageResponse) {
+    #[cfg(feature = "tracing")]
+    {
+        let span = tracing::Span::current();
+        span.record("message_id", response.id.as_str());
+        span.record("input_tokens", response.usage.input_tokens);
+        span.record("output_tokens", response.usage.output_tokens);
+        span.record(
+            "cache_read_input_tokens",
+            response.usage.cache_read_input_tokens,
+        );
+        if let Some(stop_reason) = &response.stop_reason {
+            span.record("stop_reason", tracing::field::debug(stop_reason));
+        }
+    }
+    #[cfg(not(feature = "tracing"))]
+    let _ = response;
+}
+
+/// Run a message stream's reader task in an `anthropic.stream` span
+pub(crate) fn stream<F: Future>(task: F) -> impl Future<Output = F::Output> {
+    #[cfg(feature = "tracing")]
+    {
+        use tracing::{field::Empty, Instrument};
+
+        let span = tracing::info_span!(
+            "anthropic.stream",
+            message_id = Empty,
+            model = Empty,
+            input_tokens = Empty,
+            output_tokens = Empty,
+            stop_reason = Empty,
+        );
+        task.instrument(span)
+    }
+    #[cfg(not(feature = "tracing"))]
+    task
+}
+
+/// Record what `event` tells about the message on the stream's span
+pub(crate) fn record_stream_event(event: &StreamEvent) {
+    #[cfg(feature = "tracing")]
+    {
+        let span = tracing::Span::current();
+        match event {
+            StreamEvent::MessageStart { message } => {
+                span.record("message_id", message.id.as_str());
+                span.record("model", message.model.as_str());
+                span.record("input_tokens", message.usage.input_tokens);
+            }
+            StreamEvent::MessageDelta { delta, usage } => {
+                span.record("output_tokens", usage.output_tokens);
+                if let Some(stop_reason) = &delta.stop_reason {
+                    span.record("stop_reason", tracing::field::debug(stop_reason));
+                }
+            }
+            StreamEvent::MessageStop => tracing::debug!("Stream finished"),
+            _ => {}
+        }
+    }
+    #[cfg(not(feature = "tracing"))]
+    let _ = event;
+}
@@ -0,0 +1,218 @@
+//! Content checksums for file transfers
+//!
+//! [`FilesApi::upload`] sends a `Content-MD5` (RFC 1864) and a
+//! `Content-Digest` (RFC 9530, `sha-256`) header on the file part, so a
+//! gateway or the API can reject content that was corrupted in transit.
+//! [`FilesApi::download`] checks the bytes it receives against whichever of
+//! `Content-MD5`, `Content-Digest` or `Repr-Digest` the response carries and
+//! fails with [`AnthropicError::Integrity`] on a mismatch. Responses without
+//! checksum headers are accepted as before.
+//!
+//! ```rust
+//! use threatflux_anthropic_sdk::utils::integrity::ContentDigest;
+//!
+//! let digest = ContentDigest::compute(b"abc");
+//! assert_eq!(digest.md5_base64(), "kAFQmDzST7DWlj99KOF/cg==");
+//! assert_eq!(
+//!     digest.sha256_hex(),
+//!     "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+//! );
+//! ```
+//!
+//! [`FilesApi::upload`]: crate::api::files::FilesApi::upload
+//! [`FilesApi::download`]: crate::api::files::FilesApi::download
+//! [`AnthropicError::Integrity`]: crate::error::AnthropicError::Integrity
+
+use crate::error::{ChecksumAlgorithm, IntegrityError};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use md5::{Digest, Md5};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use sha2::Sha256;
+
+/// `Content-MD5` header name
+pub const CONTENT_MD5: &str = "content-md5";
+
+/// `Content-Digest` header name
+pub const CONTENT_DIGEST: &str = "content-digest";
+
+/// `Repr-Digest` header name
+pub const REPR_DIGEST: &str = "repr-digest";
+
+/// MD5 and SHA-256 digests of a byte payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentDigest {
+    md5: [u8; 16],
+    sha256: [u8; 32],
+}
+
+impl ContentDigest {
+    /// Hash `content`
+    pub fn compute(content: &[u8]) -> Self {
+        Self {
+            md5: Md5::digest(content).into(),
+            sha256: Sha256::digest(content).into(),
+        }
+    }
+
+    /// Raw MD5 digest
+    pub fn md5(&self) -> &[u8; 16] {
+        &self.md5
+    }
+
+    /// Raw SHA-256 digest
+    pub fn sha256(&self) -> &[u8; 32] {
+        &self.sha256
+    }
+
+    /// MD5 digest in base64, the `Content-MD5` encoding
+    pub fn md5_base64(&self) -> String {
+        STANDARD.encode(self.md5)
+    }
+
+    /// SHA-256 digest in base64, the `Content-Digest` encoding
+    pub fn sha256_base64(&self) -> String {
+        STANDARD.encode(self.sha256)
+    }
+
+    /// SHA-256 digest as lowercase hex, for logs and audit records
+    pub fn sha256_hex(&self) -> String {
+        self.sha256.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// `Content-MD5` and `Content-Digest` headers describing the payload
+    pub fn headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        // Base64 output is always a valid header value.
+        if let Ok(value) = HeaderValue::from_str(&self.md5_base64()) {
+            headers.insert(HeaderName::from_static(CONTENT_MD5), value);
+        }
+        if let Ok(value) = HeaderValue::from_str(&format!("sha-256=:{}:", self.sha256_base64())) {
+            headers.insert(HeaderName::from_static(CONTENT_DIGEST), value);
+        }
+        headers
+    }
+
+    /// Check the payload against any checksum headers in `headers`.
+    ///
+    /// Returns how many checksums were compared; `Ok(0)` means the server
+    /// sent none this module understands. Digest algorithms other than
+    /// `sha-256` in `Content-Digest` / `Repr-Digest` are ignored.
+    pub fn verify(
+        &self,
+        headers: &HeaderMap,
+        resource: &str,
+    ) -> std::result::Result<usize, IntegrityError> {
+        let mut checked = 0;
+
+        if let Some(expected) = header_str(headers, CONTENT_MD5) {
+            self.check(ChecksumAlgorithm::Md5, expected, resource)?;
+            checked += 1;
+        }
+        for name in [CONTENT_DIGEST, REPR_DIGEST] {
+            for value in headers.get_all(name).iter().filter_map(|v| v.to_str().ok()) {
+                for (algorithm, expected) in parse_digest_header(value) {
+                    if algorithm.eq_ignore_ascii_case("sha-256") {
+                        self.check(ChecksumAlgorithm::Sha256, expected, resource)?;
+                        checked += 1;
+                    }
+                }
+            }
+        }
+        Ok(checked)
+    }
+
+    fn check(
+        &self,
+        algorithm: ChecksumAlgorithm,
+        expected: &str,
+        resource: &str,
+    ) -> std::result::Result<(), IntegrityError> {
+        let actual: &[u8] = match algorithm {
+            ChecksumAlgorithm::Md5 => &self.md5,
+            ChecksumAlgorithm::Sha256 => &self.sha256,
+        };
+        if STANDARD.decode(expected).is_ok_and(|bytes| bytes == actual) {
+            return Ok(());
+        }
+        Err(IntegrityError {
+            resource: resource.to_string(),
+            algorithm,
+            expected: expected.to_string(),
+            actual: STANDARD.encode(actual),
+        })
+    }
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+}
+
+/// `(algorithm, base64)` pairs of an RFC 9530 digest field such as
+/// `sha-256=:X48E9q...=:, sha-512=:WZDP...=:`
+fn parse_digest_header(value: &str) -> impl Iterator<Item = (&str, &str)> {
+    value.split(',').filter_map(|member| {
+        let (algorithm, digest) = member.split_once('=')?;
+        let digest = digest.trim();
+        let digest = digest.strip_prefix(':')?.strip_suffix(':')?;
+        Some((algorithm.trim(), digest))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_headers_round_trip() {
+        let digest = ContentDigest::compute(b"hello world");
+        let headers = digest.headers();
+        assert_eq!(headers[CONTENT_MD5], "XrY7u+Ae7tCTyyK7j1rNww==");
+        assert_eq!(
+            headers[CONTENT_DIGEST],
+            "sha-256=:uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek=:"
+        );
+        assert_eq!(digest.verify(&headers, "test"), Ok(2));
+    }
+
+    #[test]
+    fn test_verify_without_headers_checks_nothing() {
+        let digest = ContentDigest::compute(b"data");
+        assert_eq!(digest.verify(&HeaderMap::new(), "test"), Ok(0));
+    }
+
+    #[test]
+    fn test_verify_reports_mismatch() {
+        let digest = ContentDigest::compute(b"tampered");
+        let mut headers = ContentDigest::compute(b"original").headers();
+        headers.remove(CONTENT_MD5);
+
+        let err = digest.verify(&headers, "file file_1").unwrap_err();
+        assert_eq!(err.algorithm, ChecksumAlgorithm::Sha256);
+        assert_eq!(err.actual, digest.sha256_base64());
+        assert!(err
+            .to_string()
+            .starts_with("SHA-256 mismatch for file file_1"));
+    }
+
+    #[test]
+    fn test_parse_digest_header_skips_other_algorithms() {
+        let digest = ContentDigest::compute(b"abc");
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            REPR_DIGEST,
+            HeaderValue::from_str(&format!(
+                "sha-512=:AAAA:, SHA-256=:{}:, unixsum=:bogus:",
+                digest.sha256_base64()
+            ))
+            .unwrap(),
+        );
+        assert_eq!(digest.verify(&headers, "test"), Ok(1));
+
+        headers.insert(CONTENT_MD5, HeaderValue::from_static("not base64!"));
+        let err = digest.verify(&headers, "test").unwrap_err();
+        assert_eq!(err.algorithm, ChecksumAlgorithm::Md5);
+    }
+}
@@ -0,0 +1,180 @@
+//! Exact handling of JSON numbers in tool inputs
+//!
+//! By default serde_json stores numbers as `u64`, `i64` or `f64`, so a tool
+//! input like `{"account": 123456789012345678901234, "amount": 0.1000000000000000055}`
+//! comes back rounded. Building with the `arbitrary-precision` feature turns
+//! on serde_json's `arbitrary_precision`, which keeps every number's original
+//! text in tool `input` values, both from plain responses and from streamed
+//! `input_json` deltas.
+//!
+//! The helpers here read that text back out. Without the feature they still
+//! work, but only see the already-rounded value; check [`EXACT`] when it
+//! matters.
+//!
+//! ```rust
+//! use serde::Deserialize;
+//! use serde_json::json;
+//! use threatflux_anthropic_sdk::utils::json_number;
+//!
+//! #[derive(Deserialize)]
+//! struct Transfer {
+//!     #[serde(deserialize_with = "json_number::as_string")]
+//!     amount: String,
+//! }
+//!
+//! let input = json!({"amount": 12.5, "memo": "rent"});
+//! let transfer: Transfer = serde_json::from_value(input.clone()).unwrap();
+//! assert_eq!(transfer.amount, "12.5");
+//! assert_eq!(
+//!     json_number::stringify_numbers(&input),
+//!     json!({"amount": "12.5", "memo": "rent"})
+//! );
+//! ```
+//!
+//! Note that `arbitrary_precision` is a global serde_json switch: enabling
+//! it changes number handling for every crate in the build.
+
+use serde::de::{self, Deserializer};
+use serde_json::Value;
+use std::fmt;
+
+/// Whether numbers keep their exact source text (the `arbitrary-precision`
+/// feature is enabled)
+pub const EXACT: bool = cfg!(feature = "arbitrary-precision");
+
+/// Key of the single-entry map serde_json hands to `deserialize_any` for
+/// numbers it cannot represent natively when `arbitrary_precision` is on
+pub(crate) const ARBITRARY_PRECISION_NUMBER: &str = "$serde_json::private::Number";
+
+/// Text of `value` if it is a number
+pub fn number_text(value: &Value) -> Option<String> {
+    match value {
+        Value::Number(number) => Some(number.to_string()),
+        _ => None,
+    }
+}
+
+/// Copy of `value` with every number replaced by a string of its text
+pub fn stringify_numbers(value: &Value) -> Value {
+    match value {
+        Value::Number(number) => Value::String(number.to_string()),
+        Value::Array(items) => Value::Array(items.iter().map(stringify_numbers).collect()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), stringify_numbers(value)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Deserialize a JSON number (or numeric string) into its text, for
+/// `#[serde(deserialize_with = "...")]` on `String` fields
+pub fn as_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    deserializer.deserialize_any(NumberTextVisitor)
+}
+
+struct NumberTextVisitor;
+
+impl<'de> de::Visitor<'de> for NumberTextVisitor {
+    type Value = String;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a number or numeric string")
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<String, E> {
+        Ok(value.to_string())
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<String, E> {
+        Ok(value.to_string())
+    }
+
+    fn visit_u128<E: de::Error>(self, value: u128) -> Result<String, E> {
+        Ok(value.to_string())
+    }
+
+    fn visit_i128<E: de::Error>(self, value: i128) -> Result<String, E> {
+        Ok(value.to_string())
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<String, E> {
+        Ok(serde_json::Number::from_f64(value)
+            .map_or_else(|| value.to_string(), |number| number.to_string()))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<String, E> {
+        let text = value.trim();
+        if text.parse::<f64>().is_ok_and(f64::is_finite) {
+            Ok(text.to_string())
+        } else {
+            Err(E::invalid_value(de::Unexpected::Str(value), &self))
+        }
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<String, A::Error> {
+        match map.next_entry::<String, String>()? {
+            Some((key, text)) if key == ARBITRARY_PRECISION_NUMBER => Ok(text),
+            _ => Err(de::Error::invalid_type(de::Unexpected::Map, &self)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::common::ContentBlock;
+    use serde::Deserialize;
+
+    const TOOL_USE: &str = r#"{
+        "type": "tool_use",
+        "id": "toolu_1",
+        "name": "transfer",
+        "input": {"account": 123456789012345678901234, "amount": 0.1000000000000000055, "count": 3}
+    }"#;
+
+    #[derive(Debug, Deserialize)]
+    struct Transfer {
+        #[serde(deserialize_with = "as_string")]
+        account: String,
+        #[serde(deserialize_with = "as_string")]
+        amount: String,
+        #[serde(deserialize_with = "as_string")]
+        count: String,
+    }
+
+    #[test]
+    fn test_tool_input_numbers_as_text() {
+        let block: ContentBlock = serde_json::from_str(TOOL_USE).unwrap();
+        let ContentBlock::ToolUse { input, .. } = block else {
+            panic!("expected tool_use");
+        };
+        let transfer: Transfer = serde_json::from_value(input.clone()).unwrap();
+        assert_eq!(transfer.count, "3");
+        assert_eq!(number_text(&input["count"]).as_deref(), Some("3"));
+        assert_eq!(stringify_numbers(&input)["count"], "3");
+
+        if EXACT {
+            assert_eq!(transfer.account, "123456789012345678901234");
+            assert_eq!(transfer.amount, "0.1000000000000000055");
+            assert_eq!(
+                stringify_numbers(&input)["account"],
+                "123456789012345678901234"
+            );
+        } else {
+            assert_eq!(transfer.amount, "0.1");
+        }
+    }
+
+    #[test]
+    fn test_as_string_accepts_numeric_strings_only() {
+        let from = |json: &str| serde_json::from_str::<Transfer>(json).map(|t| t.amount);
+        assert_eq!(
+            from(r#"{"account": "1", "amount": " 2.50 ", "count": 1}"#).unwrap(),
+            "2.50"
+        );
+        assert!(from(r#"{"account": "1", "amount": "lots", "count": 1}"#).is_err());
+        assert!(from(r#"{"account": "1", "amount": [1], "count": 1}"#).is_err());
+    }
+}
@@ -0,0 +1,171 @@
+//! Request and response interceptors
+//!
+//! Interceptors installed with [`Config::with_middleware`] see every request
+//! [`HttpClient`] sends, after authentication, beta headers and any
+//! [`RequestSigner`](crate::utils::signing::RequestSigner) signature have
+//! been added, and every response it receives, before it is parsed. They
+//! cover logging, auth proxies and caching without forking the crate:
+//!
+//! ```rust
+//! use reqwest::header::HeaderValue;
+//! use threatflux_anthropic_sdk::{
+//!     utils::middleware::{Intercept, Middleware},
+//!     Config,
+//! };
+//!
+//! let middleware = Middleware::new()
+//!     .with_request_interceptor(|request: &mut reqwest::Request| {
+//!         request
+//!             .headers_mut()
+//!             .insert("x-tenant", HeaderValue::from_static("acme"));
+//!         Ok(Intercept::Continue)
+//!     })
+//!     .with_response_interceptor(|response: &mut reqwest::Response| {
+//!         tracing::debug!(status = %response.status(), url = %response.url(), "anthropic response");
+//!         Ok(())
+//!     });
+//! let config = Config::new("sk-ant-...").unwrap().with_middleware(middleware);
+//! ```
+//!
+//! A request interceptor can answer the request itself with
+//! [`Intercept::Respond`], in which case nothing is sent and later request
+//! interceptors are skipped. Response interceptors run in order on every
+//! response, answered ones included. An error from either kind fails the
+//! request.
+//!
+//! [`Config::with_middleware`]: crate::config::Config::with_middleware
+//! [`HttpClient`]: crate::utils::http::HttpClient
+
+use crate::error::Result;
+use std::{fmt, sync::Arc};
+
+/// What to do with a request after an interceptor has seen it
+#[derive(Debug)]
+pub enum Intercept {
+    /// Pass the request on to the next interceptor, then the network
+    Continue,
+    /// Do not send the request; use this response instead
+    Respond(reqwest::Response),
+}
+
+impl Intercept {
+    /// Answer with `status` and a JSON `body`
+    pub fn json(status: reqwest::StatusCode, body: &serde_json::Value) -> Self {
+        let response = http::Response::builder()
+            .status(status)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.to_string())
+            .expect("status and header are valid");
+        Self::Respond(response.into())
+    }
+}
+
+/// Sees, and may change or answer, each outgoing request.
+///
+/// Closures of the form `Fn(&mut reqwest::Request) -> Result<Intercept>`
+/// implement this trait.
+pub trait RequestInterceptor: Send + Sync {
+    /// Inspect or modify `request`
+    fn intercept(&self, request: &mut reqwest::Request) -> Result<Intercept>;
+}
+
+impl<F> RequestInterceptor for F
+where
+    F: Fn(&mut reqwest::Request) -> Result<Intercept> + Send + Sync,
+{
+    fn intercept(&self, request: &mut reqwest::Request) -> Result<Intercept> {
+        self(request)
+    }
+}
+
+/// Sees each response before the SDK reads its body.
+///
+/// Closures of the form `Fn(&mut reqwest::Response) -> Result<()>` implement
+/// this trait.
+pub trait ResponseInterceptor: Send + Sync {
+    /// Inspect `response` or modify its headers
+    fn intercept(&self, response: &mut reqwest::Response) -> Result<()>;
+}
+
+impl<F> ResponseInterceptor for F
+where
+    F: Fn(&mut reqwest::Response) -> Result<()> + Send + Sync,
+{
+    fn intercept(&self, response: &mut reqwest::Response) -> Result<()> {
+        self(response)
+    }
+}
+
+/// Interceptors applied by [`HttpClient`](crate::utils::http::HttpClient),
+/// each kind in the order it was added
+#[derive(Clone, Default)]
+pub struct Middleware {
+    request: Vec<Arc<dyn RequestInterceptor>>,
+    response: Vec<Arc<dyn ResponseInterceptor>>,
+}
+
+impl Middleware {
+    /// No interceptors
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a request interceptor
+    pub fn with_request_interceptor(
+        mut self,
+        interceptor: impl RequestInterceptor + 'static,
+    ) -> Self {
+        self.request.push(Arc::new(interceptor));
+        self
+    }
+
+    /// Add a response interceptor
+    pub fn with_response_interceptor(
+        mut self,
+        interceptor: impl ResponseInterceptor + 'static,
+    ) -> Self {
+        self.response.push(Arc::new(interceptor));
+        self
+    }
+
+    /// Append the interceptors of `other` after these
+    pub fn extend(&mut self, other: Middleware) {
+        self.request.extend(other.request);
+        self.response.extend(other.response);
+    }
+
+    /// Whether no interceptors are installed
+    pub fn is_empty(&self) -> bool {
+        self.request.is_empty() && self.response.is_empty()
+    }
+
+    /// Run the request interceptors, returning the response one of them
+    /// answered with
+    pub(crate) fn on_request(
+        &self,
+        request: &mut reqwest::Request,
+    ) -> Result<Option<reqwest::Response>> {
+        for interceptor in &self.request {
+            if let Intercept::Respond(response) = interceptor.intercept(request)? {
+                return Ok(Some(response));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Run the response interceptors
+    pub(crate) fn on_response(&self, response: &mut reqwest::Response) -> Result<()> {
+        self.response
+            .iter()
+            .try_for_each(|interceptor| interceptor.intercept(response))
+    }
+}
+
+impl fmt::Debug for Middleware {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Middleware")
+            .field("request_interceptors", &self.request.len())
+            .field("response_interceptors", &self.response.len())
+            .finish()
+    }
+}
@@ -1,13 +1,50 @@
 //! Utility modules for HTTP, retry logic, and rate limiting
 
+pub mod canonical;
+pub mod chunking;
+pub mod concurrency;
+pub mod diff;
+pub mod dispatch;
+pub mod failover;
+pub mod html;
 pub mod http;
+pub(crate) mod instrumentation;
+pub mod integrity;
+pub mod json_number;
+pub mod middleware;
+pub mod object_store;
+#[cfg(feature = "pdf-raster")]
+pub mod pdf_raster;
+pub mod postprocess;
 pub mod rate_limit;
 pub mod retry;
+pub mod shadow;
+pub mod signing;
+pub mod similarity;
+#[cfg(feature = "csv")]
+pub mod table;
+pub mod text;
+pub mod timestamp;
+#[cfg(feature = "image")]
+pub mod vision;
+pub mod wire_log;
 
 // Re-export main utility types
-pub use http::{HttpClient, RateLimitInfo};
+pub use concurrency::ModelConcurrencyLimit;
+pub use diff::{response_diff, DiffHunk, DiffOp, ResponseDiff, TextDiff, ToolCallDiff, UsageDelta};
+pub use dispatch::DispatchQueue;
+pub use failover::{EndpointFailover, FailoverPolicy};
+pub use http::{AcceptedResponse, HttpClient, MaybeAccepted, RateLimitInfo, ResponseLimits};
+pub use middleware::{Intercept, Middleware, RequestInterceptor, ResponseInterceptor};
+pub use postprocess::{PostProcessor, PostProcessors};
 pub use rate_limit::{
-    AdaptiveRateLimiter, RateLimitConfig, RateLimitError, RateLimitMiddleware, RateLimitStats,
-    RateLimiter,
+    AdaptiveRateLimiter, DetectedLimits, RateLimitConfig, RateLimitError, RateLimitKey,
+    RateLimitMiddleware, RateLimitStats, RateLimiter, ThrottleEvent, TokenReservation,
 };
-pub use retry::{ExponentialBackoff, RetryClient, RetryPolicy, RetryStats};
+pub use retry::{
+    CircuitBreakerPolicy, CircuitState, ExponentialBackoff, RetryClient, RetryPolicy, RetryStats,
+};
+pub use shadow::{ShadowMode, ShadowTraffic};
+pub use signing::{RequestSigner, SignableRequest};
+pub use similarity::SimilarityScore;
+pub use wire_log::RedactionPolicy;
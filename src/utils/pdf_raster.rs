@@ -0,0 +1,233 @@
+//! PDF page rasterization fallback (feature `pdf-raster`).
+//!
+//! Models without native PDF support can still answer questions about a
+//! document if its pages are sent as images. This module renders pages with
+//! [pdfium](https://pdfium.googlesource.com/pdfium/) and turns them into
+//! labelled image blocks. The pdfium shared library is loaded at runtime, either
+//! from [`PdfRasterOptions::library_path`] or from the system library path.
+
+use crate::{
+    config::models,
+    error::{AnthropicError, Result},
+    models::common::{ContentBlock, DocumentSource, ImageSource},
+    utils::vision::{self, TileEncoding, MAX_IMAGE_DIMENSION},
+};
+use pdfium_render::prelude::*;
+use std::path::PathBuf;
+
+/// Options controlling PDF rasterization.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PdfRasterOptions {
+    /// Longest edge of each rendered page, in pixels.
+    pub max_dimension: u32,
+    /// Encoding for rendered pages.
+    pub encoding: TileEncoding,
+    /// 1-based page numbers to render; `None` renders every page.
+    pub pages: Option<Vec<usize>>,
+    /// Password for encrypted documents.
+    pub password: Option<String>,
+    /// Directory containing the pdfium shared library. Falls back to the
+    /// system library search path when `None`.
+    pub library_path: Option<PathBuf>,
+}
+
+impl Default for PdfRasterOptions {
+    fn default() -> Self {
+        Self {
+            max_dimension: MAX_IMAGE_DIMENSION,
+            encoding: TileEncoding::Jpeg(85),
+            pages: None,
+            password: None,
+            library_path: None,
+        }
+    }
+}
+
+impl PdfRasterOptions {
+    /// Create default rasterization options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the longest edge of each rendered page
+    pub fn with_max_dimension(mut self, max_dimension: u32) -> Self {
+        self.max_dimension = max_dimension;
+        self
+    }
+
+    /// Set the output encoding
+    pub fn with_encoding(mut self, encoding: TileEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Render only the given 1-based page numbers
+    pub fn with_pages(mut self, pages: impl IntoIterator<Item = usize>) -> Self {
+        self.pages = Some(pages.into_iter().collect());
+        self
+    }
+
+    /// Set the document password
+    pub fn with_password(mut self, password: impl Into<String>) -> Self {
+        self.password = Some(password.into());
+        self
+    }
+
+    /// Load pdfium from the given directory
+    pub fn with_library_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.library_path = Some(path.into());
+        self
+    }
+}
+
+/// A single rendered PDF page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RasterizedPage {
+    /// 1-based page number.
+    pub page_number: usize,
+    /// Total pages in the document.
+    pub page_count: usize,
+    /// Rendered width in pixels.
+    pub width: u32,
+    /// Rendered height in pixels.
+    pub height: u32,
+    /// Encoded page image.
+    pub source: ImageSource,
+}
+
+impl RasterizedPage {
+    /// Label placed before the page image.
+    pub fn label(&self) -> String {
+        format!("Page {} of {}", self.page_number, self.page_count)
+    }
+}
+
+/// Render PDF pages to encoded images.
+pub fn rasterize_pdf_pages(
+    bytes: &[u8],
+    options: &PdfRasterOptions,
+) -> Result<Vec<RasterizedPage>> {
+    if options.max_dimension == 0 {
+        return Err(AnthropicError::invalid_input(
+            "max_dimension must be greater than 0",
+        ));
+    }
+
+    let pdfium = bind_pdfium(options)?;
+    let document = pdfium
+        .load_pdf_from_byte_slice(bytes, options.password.as_deref())
+        .map_err(|e| AnthropicError::file_error(format!("Failed to open PDF: {}", e)))?;
+
+    let pages = document.pages();
+    let page_count = pages.len() as usize;
+    let selected: Vec<usize> = match &options.pages {
+        Some(pages) => {
+            if let Some(bad) = pages.iter().find(|&&p| p == 0 || p > page_count) {
+                return Err(AnthropicError::invalid_input(format!(
+                    "Page {} is out of range (document has {} pages)",
+                    bad, page_count
+                )));
+            }
+            pages.clone()
+        }
+        None => (1..=page_count).collect(),
+    };
+
+    let max = options.max_dimension.min(i32::MAX as u32) as i32;
+    let render_config = PdfRenderConfig::new()
+        .set_maximum_width(max)
+        .set_maximum_height(max);
+
+    selected
+        .into_iter()
+        .map(|page_number| {
+            let page = pages
+                .get((page_number - 1) as PdfPageIndex)
+                .map_err(|e| AnthropicError::file_error(format!("Failed to load page: {}", e)))?;
+            let image = page
+                .render_with_config(&render_config)
+                .map_err(|e| {
+                    AnthropicError::file_error(format!(
+                        "Failed to render page {}: {}",
+                        page_number, e
+                    ))
+                })?
+                .as_image();
+            Ok(RasterizedPage {
+                page_number,
+                page_count,
+                width: image.width(),
+                height: image.height(),
+                source: vision::encode(&image, options.encoding)?,
+            })
+        })
+        .collect()
+}
+
+/// Render PDF pages as content blocks: a "Page N of M" label followed by the
+/// page image, for every selected page.
+pub fn rasterize_pdf(bytes: &[u8], options: &PdfRasterOptions) -> Result<Vec<ContentBlock>> {
+    let pages = rasterize_pdf_pages(bytes, options)?;
+    let mut blocks = Vec::with_capacity(pages.len() * 2);
+    for page in pages {
+        blocks.push(ContentBlock::text(page.label()));
+        blocks.push(ContentBlock::image(page.source));
+    }
+    Ok(blocks)
+}
+
+/// Content blocks for a PDF suited to `model`: a native document block when the
+/// model accepts PDFs, rasterized pages otherwise.
+pub fn pdf_content_blocks(
+    model: &str,
+    bytes: &[u8],
+    options: &PdfRasterOptions,
+) -> Result<Vec<ContentBlock>> {
+    if models::supports_pdf_input(model) {
+        return Ok(vec![ContentBlock::document(DocumentSource::from_bytes(
+            "application/pdf",
+            bytes,
+        ))]);
+    }
+    rasterize_pdf(bytes, options)
+}
+
+fn bind_pdfium(options: &PdfRasterOptions) -> Result<Pdfium> {
+    let bindings = match &options.library_path {
+        Some(path) => Pdfium::bind_to_library(Pdfium::pdfium_platform_library_name_at_path(path)),
+        None => Pdfium::bind_to_system_library(),
+    }
+    .map_err(|e| AnthropicError::config(format!("Failed to load pdfium library: {}", e)))?;
+    Ok(Pdfium::new(bindings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_native_pdf_model_skips_rasterization() {
+        let blocks =
+            pdf_content_blocks(models::SONNET_4_6, b"%PDF-1.4", &PdfRasterOptions::new()).unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert!(blocks[0].as_document().is_some());
+    }
+
+    #[test]
+    fn test_zero_dimension_rejected() {
+        let options = PdfRasterOptions::new().with_max_dimension(0);
+        assert!(rasterize_pdf_pages(b"%PDF-1.4", &options).is_err());
+    }
+
+    #[test]
+    fn test_page_label() {
+        let page = RasterizedPage {
+            page_number: 2,
+            page_count: 5,
+            width: 10,
+            height: 10,
+            source: ImageSource::url("https://example.com/p2.png"),
+        };
+        assert_eq!(page.label(), "Page 2 of 5");
+    }
+}
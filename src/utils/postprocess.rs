@@ -0,0 +1,123 @@
+//! Post-processors run on every message response
+//!
+//! Processors installed with [`Config::with_postprocessor`] run in the order
+//! they were added on each [`MessageResponse`] returned by
+//! [`MessagesApi::create`](crate::api::messages::MessagesApi::create) (and
+//! the helpers built on it), after the response is parsed and before the
+//! caller sees it. Typical uses are cleaning up formatting, filtering words
+//! and stamping responses for auditing:
+//!
+//! ```rust
+//! use threatflux_anthropic_sdk::{models::message::MessageResponse, utils::postprocess, Config};
+//!
+//! let config = Config::new("sk-ant-...")
+//!     .unwrap()
+//!     .with_postprocessor("strip-bold", |response: &mut MessageResponse| {
+//!         postprocess::map_text(response, |text| text.replace("**", ""));
+//!         Ok(())
+//!     })
+//!     .with_postprocessor("trim", |response: &mut MessageResponse| {
+//!         postprocess::map_text(response, |text| text.trim().to_string());
+//!         Ok(())
+//!     });
+//! ```
+//!
+//! A request opts out of all processors with
+//! [`RequestOptions::without_postprocessing`](crate::types::RequestOptions::without_postprocessing),
+//! or of single ones by name with
+//! [`RequestOptions::without_postprocessor`](crate::types::RequestOptions::without_postprocessor).
+//! An error from a processor fails the request.
+//!
+//! [`Config::with_postprocessor`]: crate::config::Config::with_postprocessor
+
+use crate::{
+    error::Result,
+    models::{common::ContentBlock, message::MessageResponse},
+    types::RequestOptions,
+};
+use std::{fmt, sync::Arc};
+
+/// Changes or checks a message response before the caller gets it.
+///
+/// Closures of the form `Fn(&mut MessageResponse) -> Result<()>` implement
+/// this trait.
+pub trait PostProcessor: Send + Sync {
+    /// Process `response` in place
+    fn process(&self, response: &mut MessageResponse) -> Result<()>;
+}
+
+impl<F> PostProcessor for F
+where
+    F: Fn(&mut MessageResponse) -> Result<()> + Send + Sync,
+{
+    fn process(&self, response: &mut MessageResponse) -> Result<()> {
+        self(response)
+    }
+}
+
+/// Named post-processors, in the order they run
+#[derive(Clone, Default)]
+pub struct PostProcessors {
+    chain: Vec<(String, Arc<dyn PostProcessor>)>,
+}
+
+impl PostProcessors {
+    /// No processors
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `processor` under `name` after those already added
+    pub fn push(&mut self, name: impl Into<String>, processor: impl PostProcessor + 'static) {
+        self.chain.push((name.into(), Arc::new(processor)));
+    }
+
+    /// Names of the processors, in order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.chain.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Whether no processors are installed
+    pub fn is_empty(&self) -> bool {
+        self.chain.is_empty()
+    }
+
+    /// Run the processors `options` does not opt out of on `response`
+    pub(crate) fn apply(
+        &self,
+        response: &mut MessageResponse,
+        options: &Option<RequestOptions>,
+    ) -> Result<()> {
+        if options.as_ref().is_some_and(|o| o.skip_postprocessing) {
+            return Ok(());
+        }
+        let skipped: &[String] = options
+            .as_ref()
+            .map(|o| o.skip_postprocessors.as_slice())
+            .unwrap_or_default();
+        for (name, processor) in &self.chain {
+            if skipped.contains(name) {
+                continue;
+            }
+            processor
+                .process(response)
+                .map_err(|e| e.with_context(format!("Post-processor {}", name)))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for PostProcessors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+/// Replace the text of every text block in `response` with `f(text)`
+pub fn map_text(response: &mut MessageResponse, mut f: impl FnMut(&str) -> String) {
+    for block in &mut response.content {
+        if let ContentBlock::Text { text, .. } = block {
+            *text = f(text);
+        }
+    }
+}
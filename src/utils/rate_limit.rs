@@ -1,5 +1,6 @@
 //! Rate limiting utilities
 
+use crate::{models::common::Usage, utils::concurrency::glob_match};
 use governor::{
     clock::{Clock, DefaultClock, QuantaClock},
     middleware::NoOpMiddleware,
@@ -8,21 +9,132 @@ use governor::{
 };
 use nonzero_ext::nonzero;
 use std::{
+    collections::HashMap,
+    fmt,
     num::NonZeroU32,
     sync::Arc,
     time::{Duration, Instant},
 };
 
-/// Rate limiter for controlling request frequency
+/// Rate limiter for controlling request frequency, and optionally input and
+/// output tokens per minute
 #[derive(Clone)]
 pub struct RateLimiter {
     limiter: Arc<GovernorRateLimiter<NotKeyed, InMemoryState, DefaultClock, NoOpMiddleware>>,
     config: RateLimitConfig,
     stats: Arc<std::sync::Mutex<RateLimitStats>>,
+    tokens: Option<Arc<std::sync::Mutex<TokenBuckets>>>,
+}
+
+impl fmt::Debug for RateLimiter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimiter")
+            .field("config", &self.config)
+            .field("token_aware", &self.tokens.is_some())
+            .finish()
+    }
+}
+
+/// Tokens held back by [`RateLimiter::acquire_tokens`] until the request's
+/// actual usage is known
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[must_use = "reconcile or release the reservation once the request is done"]
+pub struct TokenReservation {
+    /// Input tokens reserved
+    pub input_tokens: u32,
+    /// Output tokens reserved
+    pub output_tokens: u32,
+}
+
+/// Input and output buckets, each `None` when that side is unlimited
+struct TokenBuckets {
+    input: Option<TokenBucket>,
+    output: Option<TokenBucket>,
+}
+
+impl TokenBuckets {
+    fn refilled(&mut self) -> [Option<&mut TokenBucket>; 2] {
+        let now = tokio::time::Instant::now();
+        let mut buckets = [self.input.as_mut(), self.output.as_mut()];
+        for bucket in buckets.iter_mut().flatten() {
+            bucket.refill(now);
+        }
+        buckets
+    }
+
+    /// Take `[input, output]` tokens if both fit, else how long to wait
+    fn try_take(&mut self, tokens: [u32; 2]) -> Duration {
+        let mut buckets = self.refilled();
+        let wait = buckets
+            .iter()
+            .zip(tokens)
+            .filter_map(|(bucket, tokens)| bucket.as_ref().map(|bucket| bucket.wait_for(tokens)))
+            .max()
+            .unwrap_or_default();
+        if wait.is_zero() {
+            for (bucket, tokens) in buckets.iter_mut().zip(tokens) {
+                if let Some(bucket) = bucket {
+                    bucket.adjust(-f64::from(tokens));
+                }
+            }
+        }
+        wait
+    }
+
+    /// Add `[input, output]` tokens back, or take them when negative
+    fn adjust(&mut self, tokens: [f64; 2]) {
+        for (bucket, tokens) in self.refilled().into_iter().zip(tokens) {
+            if let Some(bucket) = bucket {
+                bucket.adjust(tokens);
+            }
+        }
+    }
+}
+
+/// Holds up to a minute's worth of tokens and refills continuously. The
+/// level may go below zero when a request used more than it reserved; later
+/// requests then wait for the debt to be paid back.
+struct TokenBucket {
+    capacity: f64,
+    per_second: f64,
+    level: f64,
+    updated: tokio::time::Instant,
+}
+
+impl TokenBucket {
+    fn per_minute(tokens: u32) -> Option<Self> {
+        (tokens > 0).then(|| Self {
+            capacity: f64::from(tokens),
+            per_second: f64::from(tokens) / 60.0,
+            level: f64::from(tokens),
+            updated: tokio::time::Instant::now(),
+        })
+    }
+
+    fn refill(&mut self, now: tokio::time::Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.level = (self.level + elapsed * self.per_second).min(self.capacity);
+        self.updated = now;
+    }
+
+    /// How long until `tokens` can be taken. A request larger than the whole
+    /// bucket only waits for a full one.
+    fn wait_for(&self, tokens: u32) -> Duration {
+        let needed = f64::from(tokens).min(self.capacity);
+        if self.level >= needed {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((needed - self.level) / self.per_second)
+        }
+    }
+
+    fn adjust(&mut self, tokens: f64) {
+        self.level = (self.level + tokens).min(self.capacity);
+    }
 }
 
 /// Rate limit configuration
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct RateLimitConfig {
     /// Maximum requests per time window
     pub max_requests: NonZeroU32,
@@ -58,6 +170,11 @@ impl RateLimitConfig {
         self
     }
 
+    /// Sustained requests per second
+    fn rate(&self) -> f64 {
+        self.max_requests.get() as f64 / self.window.as_secs_f64()
+    }
+
     /// Create a quota from this configuration
     fn create_quota(&self) -> Quota {
         Quota::with_period(self.window / self.max_requests.get())
@@ -66,6 +183,64 @@ impl RateLimitConfig {
     }
 }
 
+/// Per-minute limits the API reported in a response's rate limit headers,
+/// for sizing limiters from the organization's actual tier. See
+/// [`Client::detect_rate_limits`](crate::Client::detect_rate_limits).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DetectedLimits {
+    /// `anthropic-ratelimit-requests-limit`
+    pub requests_per_minute: Option<u32>,
+    /// `anthropic-ratelimit-input-tokens-limit`
+    pub input_tokens_per_minute: Option<u32>,
+    /// `anthropic-ratelimit-output-tokens-limit`
+    pub output_tokens_per_minute: Option<u32>,
+}
+
+impl DetectedLimits {
+    /// Read the limits from response headers
+    pub fn from_headers(headers: &reqwest::header::HeaderMap) -> Self {
+        let limit = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse().ok())
+        };
+        Self {
+            requests_per_minute: crate::utils::http::HttpClient::parse_rate_limit_headers(headers)
+                .limit,
+            input_tokens_per_minute: limit("anthropic-ratelimit-input-tokens-limit"),
+            output_tokens_per_minute: limit("anthropic-ratelimit-output-tokens-limit"),
+        }
+    }
+
+    /// Whether no limit was reported
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// A request limit at `fraction` (e.g. `0.8`) of the detected one
+    pub fn request_config(&self, fraction: f32) -> Option<RateLimitConfig> {
+        self.requests_per_minute
+            .map(|rpm| RateLimitConfig::new(scale(rpm, fraction), Duration::from_secs(60)))
+    }
+
+    /// Input and output tokens per minute at `fraction` of the detected
+    /// limits, 0 where none was detected
+    pub fn token_limits(&self, fraction: f32) -> (u32, u32) {
+        let scaled = |tpm: Option<u32>| tpm.map_or(0, |tpm| scale(tpm, fraction));
+        (
+            scaled(self.input_tokens_per_minute),
+            scaled(self.output_tokens_per_minute),
+        )
+    }
+}
+
+/// `fraction` of `limit`, at least 1
+fn scale(limit: u32, fraction: f32) -> u32 {
+    let fraction = f64::from(fraction.clamp(0.0, 1.0));
+    ((f64::from(limit) * fraction).floor() as u32).max(1)
+}
+
 impl RateLimiter {
     /// Create a new rate limiter
     pub fn new(config: RateLimitConfig) -> Self {
@@ -76,6 +251,92 @@ impl RateLimiter {
             limiter,
             config,
             stats: Arc::new(std::sync::Mutex::new(RateLimitStats::default())),
+            tokens: None,
+        }
+    }
+
+    /// Also limit tokens: at most `input_tpm` input and `output_tpm` output
+    /// tokens per minute, either unlimited when 0.
+    ///
+    /// Requests reserve tokens with [`acquire_tokens`](Self::acquire_tokens)
+    /// before they are sent and settle the reservation with
+    /// [`reconcile`](Self::reconcile) once their usage is known.
+    pub fn with_token_limits(mut self, input_tpm: u32, output_tpm: u32) -> Self {
+        self.tokens = Some(Arc::new(std::sync::Mutex::new(TokenBuckets {
+            input: TokenBucket::per_minute(input_tpm),
+            output: TokenBucket::per_minute(output_tpm),
+        })));
+        self
+    }
+
+    /// Whether this limiter also limits tokens
+    pub fn is_token_aware(&self) -> bool {
+        self.tokens.is_some()
+    }
+
+    /// Wait until `input_tokens` (an estimate) and `output_tokens` (usually
+    /// the request's `max_tokens`) fit under the token limits, and reserve
+    /// them. Returns at once when the limiter is not token aware.
+    pub async fn acquire_tokens(&self, input_tokens: u32, output_tokens: u32) -> TokenReservation {
+        let reservation = TokenReservation {
+            input_tokens,
+            output_tokens,
+        };
+        let Some(tokens) = &self.tokens else {
+            return reservation;
+        };
+
+        let start = tokio::time::Instant::now();
+        loop {
+            let wait = tokens
+                .lock()
+                .unwrap()
+                .try_take([input_tokens, output_tokens]);
+            if wait.is_zero() {
+                break;
+            }
+            tokio::time::sleep(wait).await;
+        }
+
+        self.stats.lock().unwrap().record_wait(start.elapsed());
+        reservation
+    }
+
+    /// Settle `reservation` against the tokens the request actually used:
+    /// unused tokens go back, extra ones are taken from later requests.
+    /// Cache reads are not counted, as the API does not count them towards
+    /// input token limits.
+    pub fn reconcile(&self, reservation: TokenReservation, usage: &Usage) {
+        let input_used = usage
+            .input_tokens
+            .saturating_add(usage.cache_creation_input_tokens);
+        self.settle(reservation, input_used, usage.output_tokens);
+    }
+
+    /// Return all of `reservation`, for a request that failed before it used
+    /// any tokens
+    pub fn release(&self, reservation: TokenReservation) {
+        self.settle(reservation, 0, 0);
+    }
+
+    fn settle(&self, reservation: TokenReservation, input_used: u32, output_used: u32) {
+        let Some(tokens) = &self.tokens else {
+            return;
+        };
+        tokens.lock().unwrap().adjust([
+            f64::from(reservation.input_tokens) - f64::from(input_used),
+            f64::from(reservation.output_tokens) - f64::from(output_used),
+        ]);
+    }
+
+    /// A limiter enforcing `config` that keeps counting into this one's
+    /// statistics and shares its token buckets
+    fn reconfigured(&self, config: RateLimitConfig) -> Self {
+        Self {
+            limiter: Arc::new(GovernorRateLimiter::direct(config.create_quota())),
+            config,
+            stats: Arc::clone(&self.stats),
+            tokens: self.tokens.clone(),
         }
     }
 
@@ -182,13 +443,61 @@ pub enum RateLimitError {
     Config(String),
 }
 
-/// Adaptive rate limiter that adjusts based on response headers
+/// Adaptive rate limiter that adjusts based on response headers.
+///
+/// Paces requests at the configured rate until a response reports that less
+/// than the [safety margin](Self::with_safety_margin) of the window's
+/// requests remain. It then throttles, spreading what is left of the window
+/// until it resets (or halving the rate when no reset time is known), and
+/// calls the [`on_throttle`](Self::on_throttle) callback. The configured
+/// rate comes back once a response reports more than the margin plus the
+/// [hysteresis](Self::with_hysteresis) remaining, so a budget hovering around
+/// the margin does not flap between the two.
+///
+/// Install one with [`Config::with_adaptive_rate_limit`](crate::Config::with_adaptive_rate_limit)
+/// to have every request paced by it and every response feed it.
 #[derive(Clone)]
 pub struct AdaptiveRateLimiter {
     base_limiter: RateLimiter,
     current_limit: Arc<std::sync::RwLock<u32>>,
     last_reset: Arc<std::sync::RwLock<Instant>>,
     adaptation_factor: f32,
+    safety_margin: f32,
+    hysteresis: f32,
+    pacing: Arc<std::sync::RwLock<Pacing>>,
+    on_throttle: Option<ThrottleCallback>,
+}
+
+type ThrottleCallback = Arc<dyn Fn(&ThrottleEvent) + Send + Sync>;
+
+/// The limiter an [`AdaptiveRateLimiter`] currently paces with
+struct Pacing {
+    limiter: RateLimiter,
+    throttled: bool,
+}
+
+/// Passed to the [`AdaptiveRateLimiter::on_throttle`] callback
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThrottleEvent {
+    /// Requests remaining in the API's window
+    pub remaining: u32,
+    /// Requests allowed per window by the API
+    pub limit: u32,
+    /// Time until the API's window resets, if known
+    pub reset_in: Option<Duration>,
+    /// The rate requests are now paced at
+    pub config: RateLimitConfig,
+}
+
+impl fmt::Debug for AdaptiveRateLimiter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AdaptiveRateLimiter")
+            .field("config", self.base_limiter.config())
+            .field("safety_margin", &self.safety_margin)
+            .field("hysteresis", &self.hysteresis)
+            .field("throttled", &self.is_throttled())
+            .finish()
+    }
 }
 
 impl AdaptiveRateLimiter {
@@ -197,13 +506,43 @@ impl AdaptiveRateLimiter {
         let base_limiter = RateLimiter::new(initial_config.clone());
 
         Self {
+            pacing: Arc::new(std::sync::RwLock::new(Pacing {
+                limiter: base_limiter.clone(),
+                throttled: false,
+            })),
             base_limiter,
             current_limit: Arc::new(std::sync::RwLock::new(initial_config.max_requests.get())),
             last_reset: Arc::new(std::sync::RwLock::new(Instant::now())),
             adaptation_factor: 0.8, // Conservative adaptation
+            safety_margin: 0.1,
+            hysteresis: 0.15,
+            on_throttle: None,
         }
     }
 
+    /// Throttle once less than this fraction of the window's requests
+    /// remain (default 0.1)
+    pub fn with_safety_margin(mut self, margin: f32) -> Self {
+        self.safety_margin = margin.clamp(0.0, 1.0);
+        self
+    }
+
+    /// How far above the safety margin the remaining fraction must climb
+    /// before the configured rate is restored (default 0.15)
+    pub fn with_hysteresis(mut self, hysteresis: f32) -> Self {
+        self.hysteresis = hysteresis.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Call `callback` whenever the limiter starts throttling
+    pub fn on_throttle(
+        mut self,
+        callback: impl Fn(&ThrottleEvent) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_throttle = Some(Arc::new(callback));
+        self
+    }
+
     /// Update rate limit based on response headers
     pub fn update_from_headers(&self, rate_limit_info: &crate::utils::http::RateLimitInfo) {
         if let (Some(remaining), Some(limit)) = (rate_limit_info.remaining, rate_limit_info.limit) {
@@ -217,7 +556,7 @@ impl AdaptiveRateLimiter {
             }
 
             // Calculate usage ratio
-            let usage_ratio = 1.0 - (remaining as f32 / limit as f32);
+            let usage_ratio = 1.0 - (remaining as f32 / limit.max(1) as f32);
 
             // If we're getting close to the limit, log a warning
             if usage_ratio > self.adaptation_factor {
@@ -234,17 +573,70 @@ impl AdaptiveRateLimiter {
                 let mut last_reset = self.last_reset.write().unwrap();
                 *last_reset = Instant::now();
             }
+
+            self.adapt(remaining, limit, rate_limit_info.time_until_reset());
         }
     }
 
+    /// Throttle or restore the pace for a response reporting `remaining` of
+    /// `limit` requests left
+    fn adapt(&self, remaining: u32, limit: u32, reset_in: Option<Duration>) {
+        let remaining_ratio = remaining as f32 / limit.max(1) as f32;
+        let mut pacing = self.pacing.write().unwrap();
+
+        if remaining_ratio < self.safety_margin {
+            let base = self.base_limiter.config();
+            let config = match reset_in.filter(|reset_in| !reset_in.is_zero()) {
+                Some(reset_in) => RateLimitConfig::new(remaining.max(1), reset_in),
+                None => RateLimitConfig::new((base.max_requests.get() / 2).max(1), base.window),
+            };
+            // Never pace faster than configured
+            let config = if config.rate() < base.rate() {
+                config
+            } else {
+                base.clone()
+            };
+            let starting = !pacing.throttled;
+            pacing.limiter = self.base_limiter.reconfigured(config.clone());
+            pacing.throttled = true;
+            drop(pacing);
+
+            if starting {
+                tracing::warn!(
+                    "Throttling to {} requests per {:?}: {} of {} remaining",
+                    config.max_requests,
+                    config.window,
+                    remaining,
+                    limit
+                );
+                if let Some(callback) = &self.on_throttle {
+                    callback(&ThrottleEvent {
+                        remaining,
+                        limit,
+                        reset_in,
+                        config,
+                    });
+                }
+            }
+        } else if pacing.throttled && remaining_ratio >= self.safety_margin + self.hysteresis {
+            tracing::info!("Rate limit recovered: {} of {} remaining", remaining, limit);
+            pacing.limiter = self.base_limiter.clone();
+            pacing.throttled = false;
+        }
+    }
+
+    fn limiter(&self) -> RateLimiter {
+        self.pacing.read().unwrap().limiter.clone()
+    }
+
     /// Acquire with adaptive behavior
     pub async fn acquire(&self) -> Result<(), RateLimitError> {
-        self.base_limiter.acquire().await
+        self.limiter().acquire().await
     }
 
     /// Try to acquire with adaptive behavior
     pub fn try_acquire(&self) -> Result<(), RateLimitError> {
-        self.base_limiter.try_acquire()
+        self.limiter().try_acquire()
     }
 
     /// Get the current effective rate limit
@@ -252,6 +644,16 @@ impl AdaptiveRateLimiter {
         *self.current_limit.read().unwrap()
     }
 
+    /// Whether requests are currently paced below the configured rate
+    pub fn is_throttled(&self) -> bool {
+        self.pacing.read().unwrap().throttled
+    }
+
+    /// The rate requests are currently paced at
+    pub fn current_config(&self) -> RateLimitConfig {
+        self.limiter().config().clone()
+    }
+
     /// Set adaptation factor (0.0 to 1.0)
     pub fn set_adaptation_factor(&mut self, factor: f32) {
         self.adaptation_factor = factor.clamp(0.0, 1.0);
@@ -263,21 +665,128 @@ impl AdaptiveRateLimiter {
     }
 }
 
-/// Rate limiting middleware for automatic request pacing
+/// Rate limiting middleware for automatic request pacing.
+///
+/// By default one limiter paces every request. In per-model mode (see
+/// [`per_model`](Self::per_model)) each model class gets its own pool, since
+/// the API's limits differ between them: a model takes the limit of the first
+/// pattern added with [`with_model_limit`](Self::with_model_limit) that it
+/// matches (`*` and `?` globs), sharing that pool with the other models
+/// matching it, and otherwise gets a pool of its own with the default limit.
+/// With [`with_workspace_header`](Self::with_workspace_header) pools are
+/// further split by the value of that request header.
+///
+/// ```rust
+/// use std::time::Duration;
+/// use threatflux_anthropic_sdk::{utils::RateLimitConfig, utils::RateLimitMiddleware, Config};
+///
+/// let pools = RateLimitMiddleware::per_model(RateLimitConfig::new(50, Duration::from_secs(60)))
+///     .with_model_limit("claude-opus-*", RateLimitConfig::new(20, Duration::from_secs(60)))
+///     .with_workspace_header("x-workspace");
+/// let config = Config::new("sk-ant-...").unwrap().with_rate_limit_pools(pools);
+/// ```
+///
+/// Limits can be changed while requests are flowing with
+/// [`set_model_limit`](Self::set_model_limit) and
+/// [`set_default_limit`](Self::set_default_limit).
+#[derive(Debug)]
 pub struct RateLimitMiddleware {
     limiter: RateLimiter,
     enabled: bool,
+    pools: std::sync::Mutex<RateLimitPools>,
+}
+
+/// Identifies one pool of a per-model [`RateLimitMiddleware`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RateLimitKey {
+    /// The pattern whose limit applies, or the model itself when none matches
+    pub model: String,
+    /// Value of the workspace header, when pools are split by workspace
+    pub workspace: Option<String>,
+}
+
+#[derive(Debug)]
+struct RateLimitPools {
+    keyed: bool,
+    default: RateLimitConfig,
+    /// Model patterns and their limits, first match wins
+    rules: Vec<(String, RateLimitConfig)>,
+    workspace_header: Option<String>,
+    limiters: HashMap<RateLimitKey, RateLimiter>,
+}
+
+impl RateLimitPools {
+    fn limiter(&mut self, model: &str, workspace: Option<&str>) -> RateLimiter {
+        let (model, config) = match self
+            .rules
+            .iter()
+            .find(|(pattern, _)| glob_match(pattern, model))
+        {
+            Some((pattern, config)) => (pattern.clone(), config),
+            None => (model.to_string(), &self.default),
+        };
+        let key = RateLimitKey {
+            model,
+            workspace: self
+                .workspace_header
+                .as_ref()
+                .and(workspace.map(str::to_string)),
+        };
+        self.limiters
+            .entry(key)
+            .or_insert_with(|| RateLimiter::new(config.clone()))
+            .clone()
+    }
+
+    fn is_pattern(&self, model: &str) -> bool {
+        self.rules.iter().any(|(pattern, _)| pattern == model)
+    }
 }
 
 impl RateLimitMiddleware {
     /// Create new rate limiting middleware
     pub fn new(config: RateLimitConfig) -> Self {
         Self {
-            limiter: RateLimiter::new(config),
+            limiter: RateLimiter::new(config.clone()),
             enabled: true,
+            pools: std::sync::Mutex::new(RateLimitPools {
+                keyed: false,
+                default: config,
+                rules: Vec::new(),
+                workspace_header: None,
+                limiters: HashMap::new(),
+            }),
         }
     }
 
+    /// Middleware keeping a separate pool per model, each limited to
+    /// `default` unless a [`with_model_limit`](Self::with_model_limit)
+    /// pattern matches
+    pub fn per_model(default: RateLimitConfig) -> Self {
+        let middleware = Self::new(default);
+        middleware.pools().keyed = true;
+        middleware
+    }
+
+    /// Limit models matching `pattern` to `config`, in one pool shared by
+    /// all of them. Switches to per-model mode.
+    pub fn with_model_limit(self, pattern: impl Into<String>, config: RateLimitConfig) -> Self {
+        self.set_model_limit(pattern, config);
+        self
+    }
+
+    /// Split pools by the value of request header `name` as well. Requests
+    /// without the header share the model's pool.
+    pub fn with_workspace_header(self, name: impl Into<String>) -> Self {
+        self.pools().workspace_header = Some(name.into().to_ascii_lowercase());
+        self
+    }
+
+    /// The header pools are split by, if any
+    pub fn workspace_header(&self) -> Option<String> {
+        self.pools().workspace_header.clone()
+    }
+
     /// Enable or disable rate limiting
     pub fn set_enabled(&mut self, enabled: bool) {
         self.enabled = enabled;
@@ -291,6 +800,86 @@ impl RateLimitMiddleware {
             Ok(())
         }
     }
+
+    /// Apply rate limiting to a request for `model`, from `workspace` when
+    /// pools are split by workspace. Outside per-model mode this is
+    /// [`apply`](Self::apply).
+    pub async fn apply_for(
+        &self,
+        model: &str,
+        workspace: Option<&str>,
+    ) -> Result<(), RateLimitError> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let limiter = {
+            let mut pools = self.pools();
+            pools.keyed.then(|| pools.limiter(model, workspace))
+        };
+        limiter.as_ref().unwrap_or(&self.limiter).acquire().await
+    }
+
+    /// Change the limit for models matching `pattern`, or add it after the
+    /// existing patterns. Takes effect for the next request; the pool's
+    /// statistics carry on. Models that had a pool of their own and now
+    /// match `pattern` move to its pool. Switches to per-model mode.
+    pub fn set_model_limit(&self, pattern: impl Into<String>, config: RateLimitConfig) {
+        let pattern = pattern.into();
+        let mut pools = self.pools();
+        pools.keyed = true;
+        match pools
+            .rules
+            .iter_mut()
+            .find(|(existing, _)| *existing == pattern)
+        {
+            Some((_, existing)) => *existing = config.clone(),
+            None => pools.rules.push((pattern.clone(), config.clone())),
+        }
+        let RateLimitPools {
+            rules, limiters, ..
+        } = &mut *pools;
+        limiters.retain(|key, limiter| {
+            if key.model == pattern {
+                *limiter = limiter.reconfigured(config.clone());
+                true
+            } else {
+                rules.iter().any(|(rule, _)| *rule == key.model)
+                    || !glob_match(&pattern, &key.model)
+            }
+        });
+    }
+
+    /// Change the limit of models that match no pattern
+    pub fn set_default_limit(&self, config: RateLimitConfig) {
+        let mut pools = self.pools();
+        pools.default = config.clone();
+        let keys: Vec<RateLimitKey> = pools
+            .limiters
+            .keys()
+            .filter(|key| !pools.is_pattern(&key.model))
+            .cloned()
+            .collect();
+        for key in keys {
+            if let Some(limiter) = pools.limiters.get_mut(&key) {
+                *limiter = limiter.reconfigured(config.clone());
+            }
+        }
+    }
+
+    /// Statistics of each pool that has seen a request
+    pub fn stats_by_key(&self) -> HashMap<RateLimitKey, RateLimitStats> {
+        self.pools()
+            .limiters
+            .iter()
+            .map(|(key, limiter)| (key.clone(), limiter.stats()))
+            .collect()
+    }
+
+    fn pools(&self) -> std::sync::MutexGuard<'_, RateLimitPools> {
+        self.pools
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
 }
 
 /// Statistics for rate limiting
@@ -3,12 +3,17 @@
 use crate::{
     config::Config,
     error::{AnthropicError, Result},
+    metrics::endpoint,
     types::HttpMethod,
-    utils::http::{HttpClient, RateLimitInfo},
+    utils::http::{HttpClient, MaybeAccepted, RateLimitInfo},
 };
 use reqwest::header::HeaderMap;
 use serde::de::DeserializeOwned;
-use std::{sync::Arc, time::Duration};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::time::Instant;
 use url::Url;
 
 /// A lightweight exponential backoff state machine used by the retry client.
@@ -74,23 +79,136 @@ impl ExponentialBackoff {
     }
 }
 
+/// State of a [`RetryClient`]'s circuit breaker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CircuitState {
+    /// Requests are sent normally
+    #[default]
+    Closed,
+    /// Requests fail immediately with [`AnthropicError::CircuitOpen`] until
+    /// the cooldown has passed
+    Open,
+    /// The cooldown has passed; one probe request is let through and its
+    /// outcome closes or reopens the circuit
+    HalfOpen,
+}
+
+/// When a circuit breaker opens and for how long
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreakerPolicy {
+    /// Consecutive transient failures (network errors, timeouts, 429 and
+    /// 5xx responses) that open the circuit
+    pub failure_threshold: u32,
+    /// How long the circuit stays open before a probe is allowed
+    pub cooldown: Duration,
+}
+
+/// Closed/open/half-open state machine shared by clones of a [`RetryClient`]
+#[derive(Debug)]
+struct CircuitBreaker {
+    policy: CircuitBreakerPolicy,
+    state: CircuitState,
+    failures: u32,
+    opened_at: Instant,
+    probe_started: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    fn new(policy: CircuitBreakerPolicy) -> Self {
+        Self {
+            policy,
+            state: CircuitState::Closed,
+            failures: 0,
+            opened_at: Instant::now(),
+            probe_started: None,
+        }
+    }
+
+    /// Let an attempt through, or return how long until one may be tried
+    fn acquire(&mut self) -> std::result::Result<(), Duration> {
+        let now = Instant::now();
+        match self.state {
+            CircuitState::Closed => Ok(()),
+            CircuitState::Open => {
+                let reopens = self.opened_at + self.policy.cooldown;
+                if now < reopens {
+                    return Err(reopens - now);
+                }
+                self.state = CircuitState::HalfOpen;
+                self.probe_started = Some(now);
+                Ok(())
+            }
+            CircuitState::HalfOpen => {
+                // A probe whose caller went away never reports back, so
+                // another is allowed once a cooldown has passed
+                match self.probe_started {
+                    Some(started) if now < started + self.policy.cooldown => {
+                        Err(started + self.policy.cooldown - now)
+                    }
+                    _ => {
+                        self.probe_started = Some(now);
+                        Ok(())
+                    }
+                }
+            }
+        }
+    }
+
+    /// Record an attempt's outcome; returns whether the circuit just opened
+    fn record(&mut self, transient_failure: bool) -> bool {
+        if !transient_failure {
+            self.state = CircuitState::Closed;
+            self.failures = 0;
+            self.probe_started = None;
+            return false;
+        }
+        self.failures = self.failures.saturating_add(1);
+        let open = match self.state {
+            CircuitState::Closed => self.failures >= self.policy.failure_threshold,
+            CircuitState::HalfOpen => true,
+            CircuitState::Open => false,
+        };
+        if open {
+            self.state = CircuitState::Open;
+            self.opened_at = Instant::now();
+            self.probe_started = None;
+        }
+        open
+    }
+}
+
 /// Client wrapper that adds retry logic to HTTP requests
 #[derive(Clone)]
 pub struct RetryClient {
     http_client: HttpClient,
     config: Arc<Config>,
-    stats: Arc<std::sync::Mutex<RetryStats>>,
+    stats: Arc<Mutex<RetryStats>>,
+    breaker: Option<Arc<Mutex<CircuitBreaker>>>,
 }
 
 impl RetryClient {
-    /// Create a new retry client
+    /// Create a new retry client, following
+    /// [`Config::retry_policy`](crate::config::Config::retry_policy) if set
     pub fn new(config: Arc<Config>) -> Self {
         let http_client = HttpClient::new(config.clone());
+        let breaker = config
+            .retry_policy
+            .as_ref()
+            .and_then(|policy| policy.circuit_breaker)
+            .map(|policy| Arc::new(Mutex::new(CircuitBreaker::new(policy))));
 
         Self {
             http_client,
             config,
-            stats: Arc::new(std::sync::Mutex::new(RetryStats::default())),
+            stats: Arc::new(Mutex::new(RetryStats::default())),
+            breaker,
+        }
+    }
+
+    fn max_retries(&self) -> u32 {
+        match &self.config.retry_policy {
+            Some(policy) => policy.max_retries,
+            None => self.config.max_retries,
         }
     }
 
@@ -105,9 +223,45 @@ impl RetryClient {
     ) -> Result<T>
     where
         T: DeserializeOwned,
+    {
+        self.with_retries(url, || {
+            self.http_client
+                .request(method, url, body.clone(), headers.clone(), timeout)
+        })
+        .await
+    }
+
+    /// Make an HTTP request that may be answered with `202 Accepted`, with
+    /// retry logic
+    pub async fn request_accepting<T>(
+        &self,
+        method: HttpMethod,
+        url: &Url,
+        body: Option<serde_json::Value>,
+        headers: HeaderMap,
+        timeout: Duration,
+    ) -> Result<MaybeAccepted<T>>
+    where
+        T: DeserializeOwned,
+    {
+        self.with_retries(url, || {
+            self.http_client
+                .request_accepting(method, url, body.clone(), headers.clone(), timeout)
+        })
+        .await
+    }
+
+    /// Run `attempt_once` until it succeeds, fails with a non-retryable error or
+    /// runs out of retries
+    async fn with_retries<T, F, Fut>(&self, url: &Url, mut attempt_once: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T>>,
     {
         let _start_time = std::time::Instant::now();
         let mut backoff = self.create_backoff();
+        let max_retries = self.max_retries();
+        let mut last_error = None;
 
         // Update total requests stat
         {
@@ -117,12 +271,30 @@ impl RetryClient {
 
         // Track attempt statistics
 
-        for attempt in 0..=self.config.max_retries {
-            match self
-                .http_client
-                .request(method, url, body.clone(), headers.clone(), timeout)
-                .await
-            {
+        for attempt in 0..=max_retries {
+            if let Some(breaker) = &self.breaker {
+                let acquired = breaker.lock().unwrap().acquire();
+                if let Err(retry_after) = acquired {
+                    let mut stats = self.stats.lock().unwrap();
+                    stats.rejected_requests += 1;
+                    stats.failed_requests += 1;
+                    return Err(last_error.unwrap_or(AnthropicError::CircuitOpen { retry_after }));
+                }
+            }
+
+            let outcome = attempt_once().await;
+            if let Some(breaker) = &self.breaker {
+                let transient = outcome
+                    .as_ref()
+                    .err()
+                    .is_some_and(|error| self.should_retry(error));
+                if breaker.lock().unwrap().record(transient) {
+                    self.stats.lock().unwrap().circuits_opened += 1;
+                    tracing::warn!(endpoint = %endpoint(url), "Circuit breaker opened");
+                }
+            }
+
+            match outcome {
                 Ok(result) => {
                     if attempt == 0 {
                         let mut stats = self.stats.lock().unwrap();
@@ -138,7 +310,7 @@ impl RetryClient {
                     // Store error for potential return later
 
                     // Don't retry on final attempt
-                    if attempt == self.config.max_retries {
+                    if attempt == max_retries {
                         let mut stats = self.stats.lock().unwrap();
                         stats.failed_requests += 1;
                         return Err(error);
@@ -153,22 +325,37 @@ impl RetryClient {
 
                     // Calculate delay
                     let delay = self.calculate_delay(&error, &mut backoff);
+                    let advised = error.retry_after().is_some();
 
                     tracing::debug!(
-                        "Request failed (attempt {}/{}), retrying in {:?}: {}",
-                        attempt + 1,
-                        self.config.max_retries + 1,
-                        delay,
-                        error
+                        attempt = attempt + 1,
+                        max_attempts = max_retries + 1,
+                        delay_ms = delay.as_millis() as u64,
+                        advised,
+                        %error,
+                        "Request failed, retrying"
                     );
 
                     // Update retry delay stats
                     {
                         let mut stats = self.stats.lock().unwrap();
                         stats.total_retry_delay += delay;
+                        stats.last_retry_delay = Some(delay);
+                        if advised {
+                            stats.server_advised_retries += 1;
+                        }
+                    }
+                    if let Some(metrics) = &self.config.metrics {
+                        metrics.retry(&endpoint(url), attempt + 2);
+                        let rate_limited = matches!(error, AnthropicError::RateLimit(_))
+                            || error.status_code() == Some(429);
+                        if rate_limited {
+                            metrics.rate_limit_wait(delay);
+                        }
                     }
 
                     tokio::time::sleep(delay).await;
+                    last_error = Some(error);
                 }
             }
         }
@@ -181,6 +368,9 @@ impl RetryClient {
 
     /// Create exponential backoff configuration
     fn create_backoff(&self) -> ExponentialBackoff {
+        if let Some(policy) = &self.config.retry_policy {
+            return policy.create_backoff();
+        }
         ExponentialBackoff {
             initial_interval: Duration::from_millis(1000),
             max_interval: Duration::from_secs(60),
@@ -209,12 +399,29 @@ impl RetryClient {
         }
     }
 
-    /// Calculate delay before next retry attempt
+    /// Calculate delay before next retry attempt.
+    ///
+    /// A delay the API advised (`retry-after`, or the rate limit reset) wins,
+    /// capped at the policy's `max_delay` (60s by default) plus up to 10%
+    /// jitter so clients told the same time do not all return at once.
     fn calculate_delay(
         &self,
         error: &AnthropicError,
         backoff: &mut ExponentialBackoff,
     ) -> Duration {
+        if let Some(advised) = error.retry_after() {
+            let (cap, jittered) = match &self.config.retry_policy {
+                Some(policy) => (policy.max_delay, policy.jitter),
+                None => (Duration::from_secs(60), true),
+            };
+            let delay = advised.min(cap);
+            return if jittered {
+                delay + jitter(delay / 10)
+            } else {
+                delay
+            };
+        }
+
         match error {
             AnthropicError::RateLimit(_) => {
                 // For rate limit errors, use a longer delay
@@ -253,7 +460,11 @@ impl RetryClient {
 
     /// Get retry statistics
     pub fn stats(&self) -> RetryStats {
-        self.stats.lock().unwrap().clone()
+        let mut stats = self.stats.lock().unwrap().clone();
+        if let Some(breaker) = &self.breaker {
+            stats.circuit_state = breaker.lock().unwrap().state;
+        }
+        stats
     }
 
     /// Reset retry statistics
@@ -263,6 +474,16 @@ impl RetryClient {
     }
 }
 
+/// A random duration up to `max`
+fn jitter(max: Duration) -> Duration {
+    use std::hash::{BuildHasher, Hasher};
+
+    let random = std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish();
+    max.mul_f64((random % 1000) as f64 / 1000.0)
+}
+
 /// Retry policy configuration
 #[derive(Debug, Clone)]
 pub struct RetryPolicy {
@@ -278,6 +499,8 @@ pub struct RetryPolicy {
     pub max_elapsed_time: Option<Duration>,
     /// Jitter to add to delays (prevents thundering herd)
     pub jitter: bool,
+    /// Stop sending requests for a while after repeated failures
+    pub circuit_breaker: Option<CircuitBreakerPolicy>,
 }
 
 impl Default for RetryPolicy {
@@ -289,6 +512,7 @@ impl Default for RetryPolicy {
             backoff_multiplier: 2.0,
             max_elapsed_time: Some(Duration::from_secs(300)),
             jitter: true,
+            circuit_breaker: None,
         }
     }
 }
@@ -335,6 +559,18 @@ impl RetryPolicy {
         self
     }
 
+    /// Open the circuit after `failure_threshold` consecutive transient
+    /// failures: requests then fail fast with
+    /// [`AnthropicError::CircuitOpen`] for `cooldown`, after which a single
+    /// probe decides whether to close it again
+    pub fn with_circuit_breaker(mut self, failure_threshold: u32, cooldown: Duration) -> Self {
+        self.circuit_breaker = Some(CircuitBreakerPolicy {
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+        });
+        self
+    }
+
     /// Create exponential backoff from this policy
     pub fn create_backoff(&self) -> ExponentialBackoff {
         ExponentialBackoff {
@@ -362,6 +598,16 @@ pub struct RetryStats {
     pub total_retry_attempts: u64,
     /// Total time spent waiting for retries
     pub total_retry_delay: Duration,
+    /// Delay chosen before the most recent retry
+    pub last_retry_delay: Option<Duration>,
+    /// Number of retries that waited as long as the API advised
+    pub server_advised_retries: u64,
+    /// Current state of the circuit breaker (always closed without one)
+    pub circuit_state: CircuitState,
+    /// Number of times the circuit breaker opened
+    pub circuits_opened: u64,
+    /// Number of requests failed fast because the circuit was open
+    pub rejected_requests: u64,
 }
 
 impl RetryStats {
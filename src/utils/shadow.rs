@@ -0,0 +1,149 @@
+//! Request mirroring (shadow traffic)
+//!
+//! [`ShadowTraffic`] mirrors a deterministic sample of `messages.create`
+//! requests to a second model and/or endpoint in the background. Mirrored
+//! responses never reach the caller: they are either discarded or summarised in
+//! the `tracing` log, which makes it safe to canary a new model against
+//! production traffic.
+
+use crate::models::message::MessageRequest;
+use std::sync::atomic::{AtomicU64, Ordering};
+use url::Url;
+
+/// What to do with mirrored responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShadowMode {
+    /// Drop the response (errors are logged at debug level)
+    #[default]
+    Discard,
+    /// Log model, latency, token usage and stop reason at info level
+    Log,
+}
+
+/// Shadow traffic configuration and sampling state.
+#[derive(Debug)]
+pub struct ShadowTraffic {
+    sample_rate: f64,
+    model: Option<String>,
+    endpoint: Option<Url>,
+    mode: ShadowMode,
+    seen: AtomicU64,
+}
+
+impl ShadowTraffic {
+    /// Mirror the given fraction of requests (clamped to `0.0..=1.0`)
+    pub fn new(sample_rate: f64) -> Self {
+        let sample_rate = if sample_rate.is_finite() {
+            sample_rate.clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        Self {
+            sample_rate,
+            model: None,
+            endpoint: None,
+            mode: ShadowMode::default(),
+            seen: AtomicU64::new(0),
+        }
+    }
+
+    /// Send mirrored requests to a different model
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Send mirrored requests to a different base URL
+    pub fn with_endpoint(mut self, endpoint: Url) -> Self {
+        self.endpoint = Some(endpoint);
+        self
+    }
+
+    /// Set how mirrored responses are handled
+    pub fn with_mode(mut self, mode: ShadowMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Fraction of requests mirrored
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    /// Model override for mirrored requests
+    pub fn model(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
+
+    /// Endpoint override for mirrored requests
+    pub fn endpoint(&self) -> Option<&Url> {
+        self.endpoint.as_ref()
+    }
+
+    /// Response handling mode
+    pub fn mode(&self) -> ShadowMode {
+        self.mode
+    }
+
+    /// Decide whether the next request is mirrored.
+    ///
+    /// Sampling is deterministic: exactly `floor(n * rate)` of the first `n`
+    /// requests are mirrored, spread evenly.
+    pub fn should_mirror(&self) -> bool {
+        if self.sample_rate <= 0.0 {
+            return false;
+        }
+        let n = self.seen.fetch_add(1, Ordering::Relaxed);
+        let before = (n as f64 * self.sample_rate).floor();
+        let after = ((n + 1) as f64 * self.sample_rate).floor();
+        after > before
+    }
+
+    /// Build the mirrored copy of a request
+    pub fn mirror_request(&self, request: &MessageRequest) -> MessageRequest {
+        let mut mirrored = request.clone();
+        if let Some(model) = &self.model {
+            mirrored.model = model.clone();
+        }
+        mirrored.stream = None;
+        mirrored
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sampling_is_even() {
+        let shadow = ShadowTraffic::new(0.25);
+        let mirrored: Vec<bool> = (0..8).map(|_| shadow.should_mirror()).collect();
+        assert_eq!(
+            mirrored,
+            vec![false, false, false, true, false, false, false, true]
+        );
+    }
+
+    #[test]
+    fn test_sample_rate_bounds() {
+        assert!(!ShadowTraffic::new(0.0).should_mirror());
+        assert!(!ShadowTraffic::new(f64::NAN).should_mirror());
+        let all = ShadowTraffic::new(5.0);
+        assert_eq!(all.sample_rate(), 1.0);
+        assert!((0..3).all(|_| all.should_mirror()));
+    }
+
+    #[test]
+    fn test_mirror_request_overrides_model() {
+        let shadow = ShadowTraffic::new(1.0).with_model("claude-opus-4-8");
+        let request = MessageRequest::new()
+            .model("claude-sonnet-4-6")
+            .max_tokens(10)
+            .add_user_message("hi")
+            .stream(true);
+        let mirrored = shadow.mirror_request(&request);
+        assert_eq!(mirrored.model, "claude-opus-4-8");
+        assert_eq!(mirrored.stream, None);
+        assert_eq!(mirrored.messages, request.messages);
+    }
+}
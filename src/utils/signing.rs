@@ -0,0 +1,58 @@
+//! Request signing for zero-trust gateways
+//!
+//! Some deployments route API traffic through an internal gateway that only
+//! accepts requests carrying an HMAC or JWT signature. A [`RequestSigner`]
+//! installed with [`Config::with_request_signer`] is called by
+//! [`HttpClient`] for every request, right before it leaves the process, and
+//! the headers it returns are added to the request.
+//!
+//! When a signer is installed, JSON bodies are sent as canonical JSON (see
+//! [`crate::utils::canonical`]) and the signer sees exactly the bytes that go
+//! on the wire. Multipart forms are encoded in full before signing, so the
+//! signer sees the encoded body, boundary included.
+//!
+//! [`Config::with_request_signer`]: crate::config::Config::with_request_signer
+//! [`HttpClient`]: crate::utils::http::HttpClient
+
+use crate::{error::Result, types::HttpMethod};
+use reqwest::header::HeaderMap;
+use std::fmt;
+use url::Url;
+
+/// The parts of an outgoing request a signer can cover.
+#[derive(Debug, Clone, Copy)]
+pub struct SignableRequest<'a> {
+    /// HTTP method
+    pub method: HttpMethod,
+    /// Final request URL (after any failover rewrite)
+    pub url: &'a Url,
+    /// Request headers, including authentication and beta headers
+    pub headers: &'a HeaderMap,
+    /// Exact body bytes, or `None` for bodiless requests
+    pub body: Option<&'a [u8]>,
+}
+
+/// Computes signature headers for outgoing requests.
+///
+/// Closures of the form `Fn(&SignableRequest<'_>) -> Result<HeaderMap>`
+/// implement this trait. Returning an error aborts the request before it is
+/// sent.
+pub trait RequestSigner: Send + Sync {
+    /// Headers to add to the request
+    fn sign(&self, request: &SignableRequest<'_>) -> Result<HeaderMap>;
+}
+
+impl<F> RequestSigner for F
+where
+    F: Fn(&SignableRequest<'_>) -> Result<HeaderMap> + Send + Sync,
+{
+    fn sign(&self, request: &SignableRequest<'_>) -> Result<HeaderMap> {
+        self(request)
+    }
+}
+
+impl fmt::Debug for dyn RequestSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RequestSigner")
+    }
+}
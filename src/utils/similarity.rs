@@ -0,0 +1,160 @@
+//! Similarity scores for comparing model outputs against references
+//!
+//! Each score is in `0.0..=1.0`, where 1 means identical:
+//!
+//! * [`jaccard`] compares the sets of words, ignoring case, order and
+//!   punctuation, so it is forgiving of rephrasing;
+//! * [`normalized_levenshtein`] compares characters, so it catches small
+//!   edits words hide;
+//! * [`cosine`] compares embedding vectors the caller computed with an
+//!   embedding model of their choice.
+//!
+//! ```rust
+//! use threatflux_anthropic_sdk::utils::similarity;
+//!
+//! let score = similarity::score("The capital of France is Paris.", "Paris is France's capital");
+//! assert!(score.jaccard > 0.5);
+//! assert!(score.levenshtein < score.jaccard);
+//! assert_eq!(score.cosine, None);
+//! ```
+
+use std::collections::HashSet;
+
+/// Lowercased words of `text`, split on anything but letters and digits
+pub fn tokens(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Jaccard index of the word sets of `a` and `b`: shared words over all
+/// words. Two texts without words are identical.
+pub fn jaccard(a: &str, b: &str) -> f64 {
+    let a: HashSet<String> = tokens(a).into_iter().collect();
+    let b: HashSet<String> = tokens(b).into_iter().collect();
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+/// Edit distance between `a` and `b` in characters: the fewest insertions,
+/// deletions and substitutions turning one into the other
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, x) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let substitute = previous[j] + usize::from(x != y);
+            current[j + 1] = substitute.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// [`levenshtein`] distance scaled to a similarity: 1 minus the distance
+/// over the longer text's length in characters
+pub fn normalized_levenshtein(a: &str, b: &str) -> f64 {
+    let longest = a.chars().count().max(b.chars().count());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(a, b) as f64 / longest as f64
+}
+
+/// Cosine similarity of two embedding vectors, clamped to `0.0..=1.0`.
+///
+/// `None` if their lengths differ or either is all zeros.
+pub fn cosine(a: &[f32], b: &[f32]) -> Option<f64> {
+    if a.len() != b.len() {
+        return None;
+    }
+    let (mut dot, mut norm_a, mut norm_b) = (0.0f64, 0.0f64, 0.0f64);
+    for (&x, &y) in a.iter().zip(b) {
+        let (x, y) = (f64::from(x), f64::from(y));
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return None;
+    }
+    Some((dot / (norm_a.sqrt() * norm_b.sqrt())).clamp(0.0, 1.0))
+}
+
+/// Similarity of an output to a reference by each measure
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimilarityScore {
+    /// [`jaccard`] over words
+    pub jaccard: f64,
+    /// [`normalized_levenshtein`] over characters
+    pub levenshtein: f64,
+    /// [`cosine`] of the embeddings, when given
+    pub cosine: Option<f64>,
+}
+
+impl SimilarityScore {
+    /// Add the cosine similarity of the output's and reference's embeddings
+    pub fn with_embeddings(mut self, output: &[f32], reference: &[f32]) -> Self {
+        self.cosine = cosine(output, reference);
+        self
+    }
+
+    /// Mean of the available scores
+    pub fn mean(&self) -> f64 {
+        match self.cosine {
+            Some(cosine) => (self.jaccard + self.levenshtein + cosine) / 3.0,
+            None => (self.jaccard + self.levenshtein) / 2.0,
+        }
+    }
+}
+
+/// Score `output` against `reference` by words and characters; see
+/// [`SimilarityScore::with_embeddings`] to add embeddings
+pub fn score(output: &str, reference: &str) -> SimilarityScore {
+    SimilarityScore {
+        jaccard: jaccard(output, reference),
+        levenshtein: normalized_levenshtein(output, reference),
+        cosine: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jaccard() {
+        assert_eq!(jaccard("", "  ?! "), 1.0);
+        assert_eq!(jaccard("Hello, world", "world HELLO"), 1.0);
+        assert_eq!(jaccard("a b c", "b c d"), 0.5);
+        assert_eq!(jaccard("a", ""), 0.0);
+    }
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("café", "cafe"), 1);
+        assert_eq!(normalized_levenshtein("", ""), 1.0);
+        assert!((normalized_levenshtein("kitten", "sitting") - 4.0 / 7.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_cosine() {
+        assert_eq!(cosine(&[1.0, 0.0], &[2.0, 0.0]), Some(1.0));
+        assert_eq!(cosine(&[1.0, 0.0], &[0.0, 3.0]), Some(0.0));
+        assert_eq!(cosine(&[1.0, 0.0], &[-1.0, 0.0]), Some(0.0));
+        assert_eq!(cosine(&[1.0], &[1.0, 0.0]), None);
+        assert_eq!(cosine(&[0.0, 0.0], &[1.0, 0.0]), None);
+
+        let score = score("same", "same").with_embeddings(&[0.6, 0.8], &[0.6, 0.8]);
+        assert!((score.mean() - 1.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,432 @@
+//! CSV/TSV ingestion for prompts (feature `csv`).
+//!
+//! Dumping a large CSV verbatim into a prompt wastes tokens on repeated
+//! delimiters and rows the model never needs. [`render_csv`] reads a table in a
+//! single pass, keeps a bounded sample of rows, and renders them as a markdown
+//! table or compact TSV, optionally preceded by a schema summary (column types,
+//! empty-cell counts and the total row count) computed over every row.
+
+use crate::error::{AnthropicError, Result};
+use std::collections::VecDeque;
+use std::io::Read;
+
+/// Textual layout used for rendered rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TableFormat {
+    /// GitHub-flavoured markdown table.
+    #[default]
+    Markdown,
+    /// Tab-separated values; the most compact option.
+    Tsv,
+}
+
+/// How rows are kept when the table exceeds [`CsvOptions::max_rows`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RowSampling {
+    /// Keep the first `max_rows` rows.
+    Head,
+    /// Keep the first and last `max_rows / 2` rows.
+    #[default]
+    HeadTail,
+}
+
+/// Options for [`render_csv`] and `DocumentSource::from_csv`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvOptions {
+    /// Field delimiter (`b','` for CSV, `b'\t'` for TSV input).
+    pub delimiter: u8,
+    /// Whether the first record holds column names.
+    pub has_headers: bool,
+    /// Output layout.
+    pub format: TableFormat,
+    /// Maximum number of data rows to render.
+    pub max_rows: usize,
+    /// Sampling strategy used when the row limit is exceeded.
+    pub sampling: RowSampling,
+    /// Prepend a schema summary.
+    pub include_schema: bool,
+    /// Truncate individual cells to this many characters.
+    pub max_cell_chars: Option<usize>,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            has_headers: true,
+            format: TableFormat::Markdown,
+            max_rows: 200,
+            sampling: RowSampling::HeadTail,
+            include_schema: true,
+            max_cell_chars: Some(200),
+        }
+    }
+}
+
+impl CsvOptions {
+    /// Create default CSV options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Options for tab-separated input.
+    pub fn tsv() -> Self {
+        Self::default().with_delimiter(b'\t')
+    }
+
+    /// Set the input delimiter
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Set whether the first record is a header row
+    pub fn with_headers(mut self, has_headers: bool) -> Self {
+        self.has_headers = has_headers;
+        self
+    }
+
+    /// Set the output format
+    pub fn with_format(mut self, format: TableFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Set the row limit and sampling strategy
+    pub fn with_max_rows(mut self, max_rows: usize, sampling: RowSampling) -> Self {
+        self.max_rows = max_rows;
+        self.sampling = sampling;
+        self
+    }
+
+    /// Enable or disable the schema summary
+    pub fn with_schema(mut self, include_schema: bool) -> Self {
+        self.include_schema = include_schema;
+        self
+    }
+
+    /// Set the per-cell character limit
+    pub fn with_max_cell_chars(mut self, max: Option<usize>) -> Self {
+        self.max_cell_chars = max;
+        self
+    }
+}
+
+/// Inferred type of a column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    /// Every non-empty value is an integer.
+    Integer,
+    /// Every non-empty value is numeric.
+    Float,
+    /// Every non-empty value is `true`/`false`.
+    Boolean,
+    /// Anything else.
+    Text,
+    /// No non-empty values were seen.
+    Empty,
+}
+
+impl ColumnType {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Integer => "integer",
+            Self::Float => "float",
+            Self::Boolean => "boolean",
+            Self::Text => "text",
+            Self::Empty => "empty",
+        }
+    }
+
+    fn merge(self, value: &str) -> Self {
+        let observed = if value.parse::<i64>().is_ok() {
+            Self::Integer
+        } else if value.parse::<f64>().is_ok() {
+            Self::Float
+        } else if value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false") {
+            Self::Boolean
+        } else {
+            Self::Text
+        };
+        match (self, observed) {
+            (Self::Empty, t) => t,
+            (a, b) if a == b => a,
+            (Self::Integer, Self::Float) | (Self::Float, Self::Integer) => Self::Float,
+            _ => Self::Text,
+        }
+    }
+}
+
+/// Summary of a single column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnSummary {
+    /// Column name (or `column_N` without headers).
+    pub name: String,
+    /// Inferred type.
+    pub column_type: ColumnType,
+    /// Number of empty cells.
+    pub empty_count: usize,
+}
+
+/// Result of rendering a table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedTable {
+    /// Rendered text.
+    pub text: String,
+    /// Column summaries computed over every row.
+    pub columns: Vec<ColumnSummary>,
+    /// Total number of data rows in the input.
+    pub total_rows: usize,
+    /// Number of rows included in `text`.
+    pub rendered_rows: usize,
+}
+
+impl RenderedTable {
+    /// Whether rows were dropped by sampling.
+    pub fn is_sampled(&self) -> bool {
+        self.rendered_rows < self.total_rows
+    }
+}
+
+/// Read a CSV/TSV table and render a prompt-friendly representation.
+pub fn render_csv<R: Read>(reader: R, options: &CsvOptions) -> Result<RenderedTable> {
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .delimiter(options.delimiter)
+        .has_headers(options.has_headers)
+        .flexible(true)
+        .from_reader(reader);
+
+    let mut headers: Vec<String> = if options.has_headers {
+        csv_reader
+            .headers()
+            .map_err(csv_error)?
+            .iter()
+            .map(str::to_string)
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let (head_limit, tail_limit) = match options.sampling {
+        RowSampling::Head => (options.max_rows, 0),
+        RowSampling::HeadTail => {
+            let tail = options.max_rows / 2;
+            (options.max_rows - tail, tail)
+        }
+    };
+
+    let mut types: Vec<ColumnType> = vec![ColumnType::Empty; headers.len()];
+    let mut empties: Vec<usize> = vec![0; headers.len()];
+    let mut head: Vec<Vec<String>> = Vec::new();
+    let mut tail: VecDeque<Vec<String>> = VecDeque::with_capacity(tail_limit);
+    let mut total_rows = 0;
+
+    for record in csv_reader.records() {
+        let record = record.map_err(csv_error)?;
+        if record.len() > types.len() {
+            types.resize(record.len(), ColumnType::Empty);
+            empties.resize(record.len(), 0);
+        }
+        for (i, value) in record.iter().enumerate() {
+            let value = value.trim();
+            if value.is_empty() {
+                empties[i] += 1;
+            } else {
+                types[i] = types[i].merge(value);
+            }
+        }
+        // Short rows count their missing trailing cells as empty.
+        for count in empties.iter_mut().skip(record.len()) {
+            *count += 1;
+        }
+
+        total_rows += 1;
+        let row: Vec<String> = record.iter().map(str::to_string).collect();
+        if head.len() < head_limit {
+            head.push(row);
+        } else if tail_limit > 0 {
+            if tail.len() == tail_limit {
+                tail.pop_front();
+            }
+            tail.push_back(row);
+        }
+    }
+
+    for i in headers.len()..types.len() {
+        headers.push(format!("column_{}", i + 1));
+    }
+
+    let columns: Vec<ColumnSummary> = headers
+        .iter()
+        .zip(types.iter().zip(empties.iter()))
+        .map(|(name, (column_type, empty_count))| ColumnSummary {
+            name: name.clone(),
+            column_type: *column_type,
+            empty_count: *empty_count,
+        })
+        .collect();
+
+    let rendered_rows = head.len() + tail.len();
+    let omitted = total_rows - rendered_rows;
+
+    let mut text = String::new();
+    if options.include_schema {
+        text.push_str(&format!(
+            "Table: {} rows x {} columns",
+            total_rows,
+            columns.len()
+        ));
+        if omitted > 0 {
+            text.push_str(&format!(" (showing {} sampled rows)", rendered_rows));
+        }
+        text.push_str("\nColumns:\n");
+        for column in &columns {
+            text.push_str(&format!(
+                "- {} ({}, {} empty)\n",
+                column.name,
+                column.column_type.name(),
+                column.empty_count
+            ));
+        }
+        text.push('\n');
+    }
+
+    let width = headers.len();
+    let cell = |value: &str| format_cell(value, options);
+    let gap = format!("... {} rows omitted ...", omitted);
+    match options.format {
+        TableFormat::Markdown => {
+            let header_cells: Vec<String> = headers.iter().map(|h| cell(h)).collect();
+            text.push_str(&markdown_row(&header_cells, width));
+            text.push_str(&markdown_row(&vec!["---".to_string(); width], width));
+            for row in &head {
+                text.push_str(&markdown_row(
+                    &row.iter().map(|v| cell(v)).collect::<Vec<_>>(),
+                    width,
+                ));
+            }
+            if omitted > 0 && !tail.is_empty() {
+                text.push_str(&markdown_row(std::slice::from_ref(&gap), width));
+            }
+            for row in &tail {
+                text.push_str(&markdown_row(
+                    &row.iter().map(|v| cell(v)).collect::<Vec<_>>(),
+                    width,
+                ));
+            }
+        }
+        TableFormat::Tsv => {
+            let header_cells: Vec<String> = headers.iter().map(|h| cell(h)).collect();
+            text.push_str(&header_cells.join("\t"));
+            text.push('\n');
+            for row in &head {
+                text.push_str(&row.iter().map(|v| cell(v)).collect::<Vec<_>>().join("\t"));
+                text.push('\n');
+            }
+            if omitted > 0 && !tail.is_empty() {
+                text.push_str(&gap);
+                text.push('\n');
+            }
+            for row in &tail {
+                text.push_str(&row.iter().map(|v| cell(v)).collect::<Vec<_>>().join("\t"));
+                text.push('\n');
+            }
+        }
+    }
+    if omitted > 0 && tail.is_empty() {
+        text.push_str(&format!("... {} more rows omitted\n", omitted));
+    }
+
+    Ok(RenderedTable {
+        text,
+        columns,
+        total_rows,
+        rendered_rows,
+    })
+}
+
+fn format_cell(value: &str, options: &CsvOptions) -> String {
+    let mut value: String = value
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '\n' | '\r' | '\t' => ' ',
+            c => c,
+        })
+        .collect();
+    if let Some(max) = options.max_cell_chars {
+        if value.chars().count() > max {
+            value = value.chars().take(max).collect();
+            value.push('…');
+        }
+    }
+    if options.format == TableFormat::Markdown {
+        value = value.replace('|', "\\|");
+    }
+    value
+}
+
+fn markdown_row(cells: &[String], width: usize) -> String {
+    let mut row = String::from("|");
+    for i in 0..width.max(1) {
+        row.push(' ');
+        row.push_str(cells.get(i).map(String::as_str).unwrap_or(""));
+        row.push_str(" |");
+    }
+    row.push('\n');
+    row
+}
+
+fn csv_error(err: csv::Error) -> AnthropicError {
+    AnthropicError::invalid_input(format!("Failed to parse CSV: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "id,name,score,active\n1,alice,9.5,true\n2,bob,,false\n3,carol|x,7,true\n";
+
+    #[test]
+    fn test_markdown_with_schema() {
+        let table = render_csv(SAMPLE.as_bytes(), &CsvOptions::default()).unwrap();
+        assert_eq!(table.total_rows, 3);
+        assert!(!table.is_sampled());
+        assert_eq!(table.columns[0].column_type, ColumnType::Integer);
+        assert_eq!(table.columns[2].column_type, ColumnType::Float);
+        assert_eq!(table.columns[2].empty_count, 1);
+        assert_eq!(table.columns[3].column_type, ColumnType::Boolean);
+        assert!(table.text.contains("Table: 3 rows x 4 columns"));
+        assert!(table.text.contains("| id | name | score | active |"));
+        assert!(table.text.contains("carol\\|x"));
+    }
+
+    #[test]
+    fn test_head_tail_sampling() {
+        let mut csv = String::from("n\n");
+        for i in 0..100 {
+            csv.push_str(&format!("{}\n", i));
+        }
+        let options = CsvOptions::default()
+            .with_format(TableFormat::Tsv)
+            .with_schema(false)
+            .with_max_rows(4, RowSampling::HeadTail);
+        let table = render_csv(csv.as_bytes(), &options).unwrap();
+        assert_eq!(table.rendered_rows, 4);
+        assert_eq!(table.text, "n\n0\n1\n... 96 rows omitted ...\n98\n99\n");
+    }
+
+    #[test]
+    fn test_head_sampling_without_headers() {
+        let options = CsvOptions::tsv()
+            .with_headers(false)
+            .with_schema(false)
+            .with_format(TableFormat::Tsv)
+            .with_max_rows(1, RowSampling::Head);
+        let table = render_csv("a\tb\nc\td\n".as_bytes(), &options).unwrap();
+        assert_eq!(
+            table.text,
+            "column_1\tcolumn_2\na\tb\n... 1 more rows omitted\n"
+        );
+    }
+}
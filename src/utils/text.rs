@@ -0,0 +1,142 @@
+//! Unicode-safe truncation of prompt text
+//!
+//! Slicing a `&str` at an arbitrary byte offset panics inside a multi-byte
+//! character, and cutting between characters can still split a grapheme
+//! cluster: a flag, an emoji with a skin-tone modifier, or a letter followed
+//! by combining accents would lose part of itself. The helpers here only cut
+//! on grapheme cluster boundaries.
+//!
+//! Each takes an optional marker such as [`ELLIPSIS`] that is appended when
+//! the text is cut. The marker counts toward the limit, so the result is
+//! never longer than asked for; a marker that does not fit at all is dropped.
+//!
+//! ```rust
+//! use threatflux_anthropic_sdk::utils::text::{truncate_chars, truncate_graphemes, ELLIPSIS};
+//!
+//! assert_eq!(truncate_chars("Grüße aus Köln", 8, Some(ELLIPSIS)), "Grüße a…");
+//! // Each flag is two chars but one grapheme; neither is ever split.
+//! assert_eq!(truncate_chars("🇩🇪🇫🇷🇮🇹", 5, None), "🇩🇪🇫🇷");
+//! assert_eq!(truncate_graphemes("🇩🇪🇫🇷🇮🇹", 2, None), "🇩🇪🇫🇷");
+//! ```
+
+use std::borrow::Cow;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Single-character ellipsis marker
+pub const ELLIPSIS: &str = "…";
+
+/// Characters per token assumed by the SDK's token estimates
+pub const CHARS_PER_TOKEN: usize = 4;
+
+/// Rough token count of `text`, at [`CHARS_PER_TOKEN`] characters per token.
+///
+/// The same estimate as
+/// [`MessageRequest::estimated_input_tokens`](crate::models::message::MessageRequest::estimated_input_tokens);
+/// use the token counting endpoint when an exact figure matters.
+pub fn estimate_tokens(text: &str) -> u32 {
+    u32::try_from(text.chars().count().div_ceil(CHARS_PER_TOKEN)).unwrap_or(u32::MAX)
+}
+
+/// Cut `text` to at most `max_chars` characters (Unicode scalar values).
+///
+/// The cut backs off to the previous grapheme boundary, so the result may
+/// be a little shorter than `max_chars`.
+pub fn truncate_chars<'a>(text: &'a str, max_chars: usize, marker: Option<&str>) -> Cow<'a, str> {
+    truncate_by(text, max_chars, marker, |s| s.chars().count())
+}
+
+/// Cut `text` to at most `max_graphemes` user-perceived characters
+pub fn truncate_graphemes<'a>(
+    text: &'a str,
+    max_graphemes: usize,
+    marker: Option<&str>,
+) -> Cow<'a, str> {
+    truncate_by(text, max_graphemes, marker, |s| s.graphemes(true).count())
+}
+
+/// Cut `text` to roughly `max_tokens` tokens, by the [`estimate_tokens`]
+/// heuristic
+pub fn truncate_tokens<'a>(text: &'a str, max_tokens: u32, marker: Option<&str>) -> Cow<'a, str> {
+    let max_chars = usize::try_from(max_tokens)
+        .unwrap_or(usize::MAX)
+        .saturating_mul(CHARS_PER_TOKEN);
+    truncate_chars(text, max_chars, marker)
+}
+
+fn truncate_by<'a>(
+    text: &'a str,
+    max: usize,
+    marker: Option<&str>,
+    size: impl Fn(&str) -> usize,
+) -> Cow<'a, str> {
+    if size(text) <= max {
+        return Cow::Borrowed(text);
+    }
+    let marker = marker.filter(|marker| size(marker) <= max).unwrap_or("");
+    let budget = max - size(marker);
+
+    let mut end = 0;
+    let mut used = 0;
+    for (offset, grapheme) in text.grapheme_indices(true) {
+        used += size(grapheme);
+        if used > budget {
+            break;
+        }
+        end = offset + grapheme.len();
+    }
+
+    if marker.is_empty() {
+        Cow::Borrowed(&text[..end])
+    } else {
+        Cow::Owned(format!("{}{}", &text[..end], marker))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_text_is_borrowed_unchanged() {
+        let text = "fits";
+        assert!(matches!(
+            truncate_chars(text, 4, Some(ELLIPSIS)),
+            Cow::Borrowed("fits")
+        ));
+        assert_eq!(truncate_graphemes("", 0, Some(ELLIPSIS)), "");
+        assert_eq!(truncate_tokens("twelve chars", 3, None), "twelve chars");
+    }
+
+    #[test]
+    fn test_never_splits_grapheme_clusters() {
+        // "e" + combining acute accent is two chars, one grapheme.
+        let accented = "cafe\u{301} noir";
+        assert_eq!(truncate_chars(accented, 4, None), "caf");
+        assert_eq!(truncate_chars(accented, 5, None), "cafe\u{301}");
+        assert_eq!(truncate_graphemes(accented, 4, None), "cafe\u{301}");
+
+        let family = "👨‍👩‍👧 family";
+        assert_eq!(truncate_chars(family, 3, None), "");
+        assert_eq!(truncate_graphemes(family, 1, None), "👨‍👩‍👧");
+    }
+
+    #[test]
+    fn test_marker_counts_toward_limit() {
+        let result = truncate_chars("abcdefghij", 6, Some(ELLIPSIS));
+        assert_eq!(result, "abcde…");
+        assert_eq!(result.chars().count(), 6);
+
+        assert_eq!(truncate_graphemes("abcdefghij", 6, Some("[...]")), "a[...]");
+        // A marker longer than the limit is dropped rather than overflowing.
+        assert_eq!(truncate_chars("abcdefghij", 3, Some(" [truncated]")), "abc");
+    }
+
+    #[test]
+    fn test_truncate_tokens_uses_char_estimate() {
+        let text = "x".repeat(100);
+        assert_eq!(estimate_tokens(&text), 25);
+        let cut = truncate_tokens(&text, 10, Some(ELLIPSIS));
+        assert_eq!(cut.chars().count(), 40);
+        assert!(estimate_tokens(&cut) <= 10);
+    }
+}
@@ -0,0 +1,313 @@
+//! Tolerant timestamp parsing and clock-skew aware comparisons
+//!
+//! API responses normally carry RFC 3339 timestamps, but proxies and
+//! gateways in front of the API sometimes rewrite them as epoch numbers or
+//! drop the UTC offset. The model types deserialize every timestamp through
+//! this module so those payloads still parse:
+//!
+//! ```rust
+//! use serde::Deserialize;
+//! use chrono::{DateTime, Utc};
+//!
+//! #[derive(Deserialize)]
+//! struct Event {
+//!     #[serde(with = "threatflux_anthropic_sdk::utils::timestamp")]
+//!     at: DateTime<Utc>,
+//! }
+//!
+//! for raw in [
+//!     r#"{"at": "2026-01-01T00:00:00Z"}"#,
+//!     r#"{"at": "2026-01-01T00:00:00"}"#,
+//!     r#"{"at": 1767225600}"#,
+//!     r#"{"at": "1767225600000"}"#,
+//! ] {
+//!     let event: Event = serde_json::from_str(raw).unwrap();
+//!     assert_eq!(event.at.timestamp(), 1_767_225_600);
+//! }
+//! ```
+//!
+//! Timestamps are always serialized in chrono's RFC 3339 form.
+
+use super::json_number::ARBITRARY_PRECISION_NUMBER;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::{de, Deserializer, Serialize, Serializer};
+use std::{fmt, time::Duration};
+
+/// Clock difference assumed between this host and the API when comparing
+/// against server-issued deadlines
+pub const DEFAULT_CLOCK_SKEW: Duration = Duration::from_secs(30);
+
+/// Epoch values at or above this are taken as milliseconds (in seconds it
+/// would be the year 5138).
+const EPOCH_MILLIS_THRESHOLD: f64 = 1e11;
+
+/// Parse a timestamp in any of the forms seen in API payloads and headers.
+///
+/// Accepts RFC 3339 (`2026-01-01T00:00:00Z`), date-times without an offset
+/// (read as UTC, with `T` or a space as separator), bare dates (midnight
+/// UTC), RFC 2822 / HTTP dates, and epoch seconds or milliseconds, integral
+/// or fractional.
+pub fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    if let Ok(epoch) = value.parse::<f64>() {
+        return from_epoch(epoch);
+    }
+    if let Ok(parsed) = DateTime::parse_from_rfc3339(value) {
+        return Some(parsed.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(Utc.from_utc_datetime(&naive));
+        }
+    }
+    // Offsets written without a colon (`+0000`) are not RFC 3339.
+    if let Ok(parsed) = DateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f%z") {
+        return Some(parsed.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Some(Utc.from_utc_datetime(&date.and_time(chrono::NaiveTime::MIN)));
+    }
+    DateTime::parse_from_rfc2822(value)
+        .ok()
+        .map(|parsed| parsed.with_timezone(&Utc))
+}
+
+/// Convert an epoch number to a timestamp, treating very large values as
+/// milliseconds
+pub fn from_epoch(epoch: f64) -> Option<DateTime<Utc>> {
+    if !epoch.is_finite() {
+        return None;
+    }
+    let millis = if epoch.abs() >= EPOCH_MILLIS_THRESHOLD {
+        epoch
+    } else {
+        epoch * 1000.0
+    };
+    DateTime::from_timestamp_millis(millis.round() as i64)
+}
+
+/// Whether `deadline` has passed, giving the server the benefit of `skew`.
+///
+/// A deadline within `skew` of the local clock is not yet considered
+/// passed, so a host clock running slightly fast does not expire things
+/// early.
+pub fn is_past(deadline: DateTime<Utc>, skew: Duration) -> bool {
+    Utc::now() > deadline + skew_delta(skew)
+}
+
+/// Time left until `deadline` plus `skew`, or `None` once that has passed.
+pub fn time_until(deadline: DateTime<Utc>, skew: Duration) -> Option<Duration> {
+    (deadline + skew_delta(skew) - Utc::now())
+        .to_std()
+        .ok()
+        .filter(|left| !left.is_zero())
+}
+
+fn skew_delta(skew: Duration) -> chrono::Duration {
+    chrono::Duration::from_std(skew).unwrap_or(chrono::Duration::MAX)
+}
+
+/// Serialize a timestamp (for `#[serde(with = "...")]`)
+pub fn serialize<S: Serializer>(value: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+    value.serialize(serializer)
+}
+
+/// Deserialize a timestamp tolerantly (for `#[serde(with = "...")]`)
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+    deserializer.deserialize_any(TimestampVisitor)
+}
+
+/// `#[serde(with = "...")]` support for `Option<DateTime<Utc>>`.
+///
+/// `null` and empty strings deserialize to `None`. Pair with
+/// `#[serde(default)]` so a missing field does too.
+pub mod option {
+    use super::*;
+
+    /// Serialize an optional timestamp
+    pub fn serialize<S: Serializer>(
+        value: &Option<DateTime<Utc>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        value.serialize(serializer)
+    }
+
+    /// Deserialize an optional timestamp tolerantly
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<DateTime<Utc>>, D::Error> {
+        deserializer.deserialize_option(OptionVisitor)
+    }
+
+    struct OptionVisitor;
+
+    impl<'de> de::Visitor<'de> for OptionVisitor {
+        type Value = Option<DateTime<Utc>>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a timestamp or null")
+        }
+
+        fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_some<D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<Self::Value, D::Error> {
+            deserializer.deserialize_any(OptionalTimestampVisitor)
+        }
+    }
+
+    /// Like [`TimestampVisitor`], but an empty string means "no timestamp"
+    struct OptionalTimestampVisitor;
+
+    impl<'de> de::Visitor<'de> for OptionalTimestampVisitor {
+        type Value = Option<DateTime<Utc>>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            TimestampVisitor.expecting(f)
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+            if value.trim().is_empty() {
+                return Ok(None);
+            }
+            TimestampVisitor.visit_str(value).map(Some)
+        }
+
+        fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+            TimestampVisitor.visit_i64(value).map(Some)
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+            TimestampVisitor.visit_u64(value).map(Some)
+        }
+
+        fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+            TimestampVisitor.visit_f64(value).map(Some)
+        }
+
+        fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_map<A: de::MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+            TimestampVisitor.visit_map(map).map(Some)
+        }
+    }
+}
+
+struct TimestampVisitor;
+
+impl<'de> de::Visitor<'de> for TimestampVisitor {
+    type Value = DateTime<Utc>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an RFC 3339 timestamp or epoch seconds/milliseconds")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        parse_timestamp(value)
+            .ok_or_else(|| E::custom(format!("unrecognized timestamp: {:?}", value)))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+        self.visit_f64(value as f64)
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        self.visit_f64(value as f64)
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+        from_epoch(value).ok_or_else(|| E::custom(format!("epoch out of range: {}", value)))
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        match map.next_entry::<String, String>()? {
+            Some((key, text)) if key == ARBITRARY_PRECISION_NUMBER => self.visit_str(&text),
+            _ => Err(de::Error::invalid_type(de::Unexpected::Map, &self)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize)]
+    struct Stamped {
+        #[serde(with = "super")]
+        at: DateTime<Utc>,
+        #[serde(default, with = "super::option")]
+        seen: Option<DateTime<Utc>>,
+    }
+
+    fn expected() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 1, 1, 12, 30, 0).unwrap()
+    }
+
+    #[test]
+    fn test_parse_timestamp_forms() {
+        for raw in [
+            "2026-01-01T12:30:00Z",
+            "2026-01-01T14:30:00+02:00",
+            "2026-01-01T12:30:00",
+            "2026-01-01 12:30:00",
+            "2026-01-01T12:30:00.000+0000",
+            "Thu, 01 Jan 2026 12:30:00 GMT",
+            "1767270600",
+            "1767270600.0",
+            "1767270600000",
+        ] {
+            assert_eq!(parse_timestamp(raw), Some(expected()), "{}", raw);
+        }
+        assert_eq!(
+            parse_timestamp("2026-01-01"),
+            Some(Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap())
+        );
+        assert_eq!(parse_timestamp("yesterday"), None);
+        assert_eq!(parse_timestamp(""), None);
+    }
+
+    #[test]
+    fn test_deserialize_numbers_and_strings() {
+        let stamped: Stamped =
+            serde_json::from_str(r#"{"at": 1767270600, "seen": "2026-01-01T12:30:00"}"#).unwrap();
+        assert_eq!(stamped.at, expected());
+        assert_eq!(stamped.seen, Some(expected()));
+
+        let stamped: Stamped =
+            serde_json::from_str(r#"{"at": 1767270600000.0, "seen": ""}"#).unwrap();
+        assert_eq!(stamped.at, expected());
+        assert_eq!(stamped.seen, None);
+
+        let stamped: Stamped = serde_json::from_str(r#"{"at": "2026-01-01T12:30:00Z"}"#).unwrap();
+        assert_eq!(stamped.seen, None);
+
+        assert!(serde_json::from_str::<Stamped>(r#"{"at": "soon"}"#).is_err());
+    }
+
+    #[test]
+    fn test_skew_tolerant_comparisons() {
+        let skew = Duration::from_secs(30);
+        let just_passed = Utc::now() - chrono::Duration::seconds(5);
+        assert!(!is_past(just_passed, skew));
+        assert!(is_past(just_passed, Duration::ZERO));
+        assert!(time_until(just_passed, skew).is_some());
+
+        let long_gone = Utc::now() - chrono::Duration::minutes(5);
+        assert!(is_past(long_gone, skew));
+        assert_eq!(time_until(long_gone, skew), None);
+    }
+}
@@ -0,0 +1,482 @@
+//! Image tiling and collage helpers for vision requests (feature `image`).
+//!
+//! Very large images (document screenshots, architecture diagrams) are
+//! downscaled by the API when either side exceeds ~1568px, which destroys fine
+//! print. [`tile_image`] splits such an image into API-sized segments that keep
+//! their coordinates, and [`TiledImage::reassembly_prompt`] tells the model how
+//! the pieces fit back together. [`build_collage`] does the inverse for many
+//! small images: it packs them into a single labelled grid so one image block
+//! can carry several thumbnails.
+
+use crate::{
+    error::{AnthropicError, Result},
+    models::common::{ContentBlock, ImageSource},
+};
+use image::{DynamicImage, GenericImageView, ImageFormat, RgbaImage};
+use std::io::Cursor;
+
+/// Longest edge (in pixels) the API accepts without downscaling.
+pub const MAX_IMAGE_DIMENSION: u32 = 1568;
+
+/// Encoding used for generated tiles and collages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TileEncoding {
+    /// Lossless PNG (best for text and diagrams).
+    #[default]
+    Png,
+    /// JPEG at the given quality (1-100); smaller for photos.
+    Jpeg(u8),
+}
+
+impl TileEncoding {
+    /// MIME type for this encoding.
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::Jpeg(_) => "image/jpeg",
+        }
+    }
+}
+
+/// Options controlling how an image is split into tiles.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TileOptions {
+    /// Maximum width/height of a single tile, in pixels.
+    pub max_tile_dimension: u32,
+    /// Pixels shared between neighbouring tiles so text on a seam is not lost.
+    pub overlap: u32,
+    /// Output encoding for each tile.
+    pub encoding: TileEncoding,
+}
+
+impl Default for TileOptions {
+    fn default() -> Self {
+        Self {
+            max_tile_dimension: MAX_IMAGE_DIMENSION,
+            overlap: 32,
+            encoding: TileEncoding::Png,
+        }
+    }
+}
+
+impl TileOptions {
+    /// Create tile options with the default API-sized tiles.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum tile edge length
+    pub fn with_max_tile_dimension(mut self, max: u32) -> Self {
+        self.max_tile_dimension = max;
+        self
+    }
+
+    /// Set the overlap between neighbouring tiles
+    pub fn with_overlap(mut self, overlap: u32) -> Self {
+        self.overlap = overlap;
+        self
+    }
+
+    /// Set the output encoding
+    pub fn with_encoding(mut self, encoding: TileEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+}
+
+/// A single segment of a tiled image together with its position in the original.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageTile {
+    /// Sequential tile index (row-major, starting at 0).
+    pub index: usize,
+    /// Grid row of this tile.
+    pub row: u32,
+    /// Grid column of this tile.
+    pub col: u32,
+    /// Left edge in original-image pixels.
+    pub x: u32,
+    /// Top edge in original-image pixels.
+    pub y: u32,
+    /// Tile width in pixels.
+    pub width: u32,
+    /// Tile height in pixels.
+    pub height: u32,
+    /// Encoded tile ready to send.
+    pub source: ImageSource,
+}
+
+impl ImageTile {
+    /// Human-readable label describing where this tile sits.
+    pub fn label(&self) -> String {
+        format!(
+            "Tile {} (row {}, column {}): x={}..{}, y={}..{}",
+            self.index + 1,
+            self.row + 1,
+            self.col + 1,
+            self.x,
+            self.x + self.width,
+            self.y,
+            self.y + self.height
+        )
+    }
+}
+
+/// Result of [`tile_image`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TiledImage {
+    /// Width of the original image.
+    pub original_width: u32,
+    /// Height of the original image.
+    pub original_height: u32,
+    /// Number of tile rows.
+    pub rows: u32,
+    /// Number of tile columns.
+    pub cols: u32,
+    /// Overlap used between neighbouring tiles.
+    pub overlap: u32,
+    /// Tiles in row-major order.
+    pub tiles: Vec<ImageTile>,
+}
+
+impl TiledImage {
+    /// Number of tiles.
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// Whether no tiles were produced.
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    /// Prompt text explaining how the tiles reassemble into the original image.
+    pub fn reassembly_prompt(&self) -> String {
+        let mut prompt = format!(
+            "The following {} images are tiles of a single {}x{} pixel image, arranged in a \
+             grid of {} row(s) by {} column(s) in row-major order",
+            self.tiles.len(),
+            self.original_width,
+            self.original_height,
+            self.rows,
+            self.cols
+        );
+        if self.overlap > 0 && self.tiles.len() > 1 {
+            prompt.push_str(&format!(
+                ". Neighbouring tiles overlap by {} pixels, so content on a seam may appear twice",
+                self.overlap
+            ));
+        }
+        prompt.push_str(
+            ". Treat them as one image and refer to locations using original-image coordinates.",
+        );
+        prompt
+    }
+
+    /// Content blocks for a user message: the reassembly prompt, then a label and
+    /// image block for every tile.
+    pub fn to_content_blocks(&self) -> Vec<ContentBlock> {
+        let mut blocks = Vec::with_capacity(self.tiles.len() * 2 + 1);
+        blocks.push(ContentBlock::text(self.reassembly_prompt()));
+        for tile in &self.tiles {
+            blocks.push(ContentBlock::text(tile.label()));
+            blocks.push(ContentBlock::image(tile.source.clone()));
+        }
+        blocks
+    }
+}
+
+/// Split encoded image bytes into API-sized tiles with coordinate metadata.
+///
+/// Images that already fit in a single tile are returned as one tile.
+pub fn tile_image(bytes: &[u8], options: &TileOptions) -> Result<TiledImage> {
+    let image = decode(bytes)?;
+    tile_dynamic_image(&image, options)
+}
+
+/// Split an already-decoded image into tiles.
+pub fn tile_dynamic_image(image: &DynamicImage, options: &TileOptions) -> Result<TiledImage> {
+    if options.max_tile_dimension == 0 {
+        return Err(AnthropicError::invalid_input(
+            "max_tile_dimension must be greater than 0",
+        ));
+    }
+    if options.overlap >= options.max_tile_dimension {
+        return Err(AnthropicError::invalid_input(
+            "Tile overlap must be smaller than max_tile_dimension",
+        ));
+    }
+
+    let (width, height) = image.dimensions();
+    let xs = tile_offsets(width, options.max_tile_dimension, options.overlap);
+    let ys = tile_offsets(height, options.max_tile_dimension, options.overlap);
+
+    let mut tiles = Vec::with_capacity(xs.len() * ys.len());
+    for (row, &(y, tile_height)) in ys.iter().enumerate() {
+        for (col, &(x, tile_width)) in xs.iter().enumerate() {
+            let segment = image.crop_imm(x, y, tile_width, tile_height);
+            tiles.push(ImageTile {
+                index: tiles.len(),
+                row: row as u32,
+                col: col as u32,
+                x,
+                y,
+                width: tile_width,
+                height: tile_height,
+                source: encode(&segment, options.encoding)?,
+            });
+        }
+    }
+
+    Ok(TiledImage {
+        original_width: width,
+        original_height: height,
+        rows: ys.len() as u32,
+        cols: xs.len() as u32,
+        overlap: options.overlap,
+        tiles,
+    })
+}
+
+/// Compute `(offset, length)` pairs covering `total` pixels along one axis.
+fn tile_offsets(total: u32, max: u32, overlap: u32) -> Vec<(u32, u32)> {
+    if total <= max {
+        return vec![(0, total)];
+    }
+
+    let stride = max - overlap;
+    let mut offsets = Vec::new();
+    let mut start = 0;
+    loop {
+        let len = max.min(total - start);
+        offsets.push((start, len));
+        if start + len >= total {
+            break;
+        }
+        start += stride;
+    }
+    offsets
+}
+
+/// Options for packing several images into one collage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CollageOptions {
+    /// Number of grid columns.
+    pub columns: u32,
+    /// Edge length of each (square) cell; images are scaled to fit.
+    pub cell_size: u32,
+    /// Padding between cells, in pixels.
+    pub padding: u32,
+    /// Output encoding.
+    pub encoding: TileEncoding,
+}
+
+impl Default for CollageOptions {
+    fn default() -> Self {
+        Self {
+            columns: 3,
+            cell_size: 512,
+            padding: 8,
+            encoding: TileEncoding::Png,
+        }
+    }
+}
+
+/// Placement of one input image inside a collage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CollageCell {
+    /// Index of the input image.
+    pub index: usize,
+    /// Grid row.
+    pub row: u32,
+    /// Grid column.
+    pub col: u32,
+    /// Left edge inside the collage.
+    pub x: u32,
+    /// Top edge inside the collage.
+    pub y: u32,
+    /// Scaled width inside the collage.
+    pub width: u32,
+    /// Scaled height inside the collage.
+    pub height: u32,
+}
+
+/// Result of [`build_collage`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Collage {
+    /// Encoded collage image.
+    pub source: ImageSource,
+    /// Collage width in pixels.
+    pub width: u32,
+    /// Collage height in pixels.
+    pub height: u32,
+    /// Where each input image was placed.
+    pub cells: Vec<CollageCell>,
+}
+
+impl Collage {
+    /// Prompt text describing the collage layout.
+    pub fn layout_prompt(&self) -> String {
+        let mut prompt = format!(
+            "This image is a collage of {} separate images arranged left-to-right, \
+             top-to-bottom:",
+            self.cells.len()
+        );
+        for cell in &self.cells {
+            prompt.push_str(&format!(
+                "\n- Image {}: row {}, column {}",
+                cell.index + 1,
+                cell.row + 1,
+                cell.col + 1
+            ));
+        }
+        prompt
+    }
+}
+
+/// Pack several encoded images into a single grid image.
+pub fn build_collage<B: AsRef<[u8]>>(images: &[B], options: &CollageOptions) -> Result<Collage> {
+    if images.is_empty() {
+        return Err(AnthropicError::invalid_input(
+            "Collage requires at least one image",
+        ));
+    }
+    if options.columns == 0 || options.cell_size == 0 {
+        return Err(AnthropicError::invalid_input(
+            "Collage columns and cell_size must be greater than 0",
+        ));
+    }
+
+    let count = images.len() as u32;
+    let columns = options.columns.min(count);
+    let rows = count.div_ceil(columns);
+    let pitch = options.cell_size + options.padding;
+    let width = columns * pitch - options.padding;
+    let height = rows * pitch - options.padding;
+    if width > MAX_IMAGE_DIMENSION || height > MAX_IMAGE_DIMENSION {
+        tracing::debug!(
+            "Collage {}x{} exceeds {}px and will be downscaled by the API",
+            width,
+            height,
+            MAX_IMAGE_DIMENSION
+        );
+    }
+
+    let mut canvas = RgbaImage::from_pixel(width, height, image::Rgba([255, 255, 255, 255]));
+    let mut cells = Vec::with_capacity(images.len());
+    for (index, bytes) in images.iter().enumerate() {
+        let thumb = decode(bytes.as_ref())?.thumbnail(options.cell_size, options.cell_size);
+        let row = index as u32 / columns;
+        let col = index as u32 % columns;
+        let x = col * pitch;
+        let y = row * pitch;
+        image::imageops::overlay(&mut canvas, &thumb.to_rgba8(), x as i64, y as i64);
+        cells.push(CollageCell {
+            index,
+            row,
+            col,
+            x,
+            y,
+            width: thumb.width(),
+            height: thumb.height(),
+        });
+    }
+
+    Ok(Collage {
+        source: encode(&DynamicImage::ImageRgba8(canvas), options.encoding)?,
+        width,
+        height,
+        cells,
+    })
+}
+
+fn decode(bytes: &[u8]) -> Result<DynamicImage> {
+    image::load_from_memory(bytes)
+        .map_err(|e| AnthropicError::invalid_input(format!("Failed to decode image: {}", e)))
+}
+
+pub(crate) fn encode(image: &DynamicImage, encoding: TileEncoding) -> Result<ImageSource> {
+    let mut buffer = Cursor::new(Vec::new());
+    let result = match encoding {
+        TileEncoding::Png => image.write_to(&mut buffer, ImageFormat::Png),
+        TileEncoding::Jpeg(quality) => {
+            let encoder =
+                image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buffer, quality.max(1));
+            image.to_rgb8().write_with_encoder(encoder)
+        }
+    };
+    result.map_err(|e| AnthropicError::invalid_input(format!("Failed to encode image: {}", e)))?;
+    Ok(ImageSource::from_bytes(
+        encoding.media_type(),
+        buffer.get_ref(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = DynamicImage::ImageRgba8(RgbaImage::new(width, height));
+        let mut buffer = Cursor::new(Vec::new());
+        image.write_to(&mut buffer, ImageFormat::Png).unwrap();
+        buffer.into_inner()
+    }
+
+    #[test]
+    fn test_tile_offsets_cover_axis() {
+        assert_eq!(tile_offsets(100, 200, 10), vec![(0, 100)]);
+        assert_eq!(
+            tile_offsets(500, 200, 20),
+            vec![(0, 200), (180, 200), (360, 140)]
+        );
+    }
+
+    #[test]
+    fn test_tile_image_grid_and_metadata() {
+        let options = TileOptions::new()
+            .with_max_tile_dimension(100)
+            .with_overlap(10);
+        let tiled = tile_image(&png(250, 120), &options).unwrap();
+
+        assert_eq!((tiled.rows, tiled.cols), (2, 3));
+        assert_eq!(tiled.len(), 6);
+        let last = tiled.tiles.last().unwrap();
+        assert_eq!((last.x, last.y, last.width, last.height), (180, 90, 70, 30));
+        assert!(tiled
+            .reassembly_prompt()
+            .contains("2 row(s) by 3 column(s)"));
+        assert_eq!(tiled.to_content_blocks().len(), 13);
+    }
+
+    #[test]
+    fn test_small_image_single_tile() {
+        let tiled = tile_image(&png(64, 64), &TileOptions::default()).unwrap();
+        assert_eq!(tiled.len(), 1);
+        assert!(!tiled.reassembly_prompt().contains("overlap"));
+    }
+
+    #[test]
+    fn test_invalid_overlap_rejected() {
+        let options = TileOptions::new()
+            .with_max_tile_dimension(10)
+            .with_overlap(10);
+        assert!(tile_image(&png(20, 20), &options).is_err());
+    }
+
+    #[test]
+    fn test_build_collage_layout() {
+        let images = vec![png(40, 20), png(20, 40), png(30, 30)];
+        let options = CollageOptions {
+            columns: 2,
+            cell_size: 32,
+            padding: 4,
+            encoding: TileEncoding::Png,
+        };
+        let collage = build_collage(&images, &options).unwrap();
+
+        assert_eq!((collage.width, collage.height), (68, 68));
+        assert_eq!(collage.cells[2].row, 1);
+        assert_eq!(collage.cells[0].width, 32);
+        assert!(collage.layout_prompt().contains("Image 3: row 2, column 1"));
+    }
+}
@@ -0,0 +1,375 @@
+//! Wire logging of HTTP traffic for troubleshooting
+//!
+//! With [`Config::with_wire_logging`] every request the client sends and
+//! every response it receives is logged as a DEBUG `tracing` event with the
+//! target `anthropic::wire`: method, URL, status, headers and body.
+//! [`RedactionPolicy`] decides what is masked first. Credential headers and
+//! base64 payloads always are by default; message content only on request.
+//!
+//! Stream bodies (`text/event-stream` and JSON Lines) are logged a line at a
+//! time as they are read, so a stream shows up while it is still running.
+//! Other JSON and text bodies are logged once they have been read, and
+//! binary bodies only by size.
+//!
+//! ```rust
+//! use threatflux_anthropic_sdk::{utils::wire_log::RedactionPolicy, Config};
+//!
+//! // RUST_LOG=anthropic::wire=debug
+//! let config = Config::new("sk-ant-...")
+//!     .unwrap()
+//!     .with_wire_logging(RedactionPolicy::new().with_content_redaction(true));
+//! ```
+//!
+//! [`Config::with_wire_logging`]: crate::config::Config::with_wire_logging
+
+use futures::StreamExt;
+use reqwest::{
+    header::{HeaderMap, CONTENT_TYPE},
+    ResponseBuilderExt,
+};
+use serde_json::Value;
+use std::sync::Arc;
+
+/// `tracing` target of wire log events
+pub const WIRE_TARGET: &str = "anthropic::wire";
+
+/// Keys whose string values are message content
+const CONTENT_KEYS: &[&str] = &["text", "thinking", "partial_json", "content", "system"];
+
+/// Shortest string treated as a base64 payload when found outside a
+/// `"type": "base64"` source
+const MIN_BASE64_LEN: usize = 256;
+
+/// What wire logging masks before anything is written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedactionPolicy {
+    headers: Vec<String>,
+    content: bool,
+    base64: bool,
+    max_body_bytes: usize,
+}
+
+impl Default for RedactionPolicy {
+    fn default() -> Self {
+        Self {
+            headers: [
+                "x-api-key",
+                "authorization",
+                "proxy-authorization",
+                "cookie",
+                "set-cookie",
+            ]
+            .map(str::to_string)
+            .to_vec(),
+            content: false,
+            base64: true,
+            max_body_bytes: 64 * 1024,
+        }
+    }
+}
+
+impl RedactionPolicy {
+    /// Mask credential headers and base64 payloads, keep message content
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also mask message content: text, thinking, tool input deltas and
+    /// system prompts
+    pub fn with_content_redaction(mut self, redact: bool) -> Self {
+        self.content = redact;
+        self
+    }
+
+    /// Whether base64 payloads (images, documents) are masked
+    pub fn with_base64_redaction(mut self, redact: bool) -> Self {
+        self.base64 = redact;
+        self
+    }
+
+    /// Mask the value of header `name` as well. Headers marked sensitive are
+    /// always masked.
+    pub fn with_redacted_header(mut self, name: impl Into<String>) -> Self {
+        self.headers.push(name.into().to_ascii_lowercase());
+        self
+    }
+
+    /// Log buffered bodies up to this size; larger ones are logged by size
+    /// only. Stream lines are not limited.
+    pub fn with_max_body_bytes(mut self, bytes: usize) -> Self {
+        self.max_body_bytes = bytes;
+        self
+    }
+
+    /// Log `request` as it is about to be sent
+    pub(crate) fn log_request(&self, request: &reqwest::Request) {
+        if !tracing::enabled!(target: WIRE_TARGET, tracing::Level::DEBUG) {
+            return;
+        }
+        let body = match request.body().map(|body| body.as_bytes()) {
+            None => String::new(),
+            Some(Some(bytes)) => self.body(bytes),
+            Some(None) => "[streaming body]".to_string(),
+        };
+        tracing::debug!(
+            target: WIRE_TARGET,
+            method = %request.method(),
+            url = %request.url(),
+            headers = %self.headers(request.headers()),
+            body = %body,
+            "request"
+        );
+    }
+
+    /// Log `response`'s head now and its body as it is read
+    pub(crate) fn log_response(self: &Arc<Self>, response: reqwest::Response) -> reqwest::Response {
+        if !tracing::enabled!(target: WIRE_TARGET, tracing::Level::DEBUG) {
+            return response;
+        }
+        tracing::debug!(
+            target: WIRE_TARGET,
+            status = response.status().as_u16(),
+            url = %response.url(),
+            headers = %self.headers(response.headers()),
+            "response"
+        );
+
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let mode = if content_type.starts_with("text/event-stream")
+            || content_type.contains("jsonl")
+            || content_type.contains("ndjson")
+        {
+            BodyMode::Lines
+        } else if content_type.contains("json") || content_type.starts_with("text/") {
+            BodyMode::Whole
+        } else {
+            BodyMode::Opaque
+        };
+
+        let mut builder = http::Response::builder()
+            .status(response.status())
+            .version(response.version())
+            .url(response.url().clone());
+        if let Some(headers) = builder.headers_mut() {
+            *headers = response.headers().clone();
+        }
+        let mut log = BodyLog {
+            policy: Arc::clone(self),
+            mode,
+            pending: Vec::new(),
+            received: 0,
+        };
+        let body = response.bytes_stream().map(move |chunk| {
+            if let Ok(bytes) = &chunk {
+                log.chunk(bytes);
+            }
+            chunk
+        });
+        builder
+            .body(reqwest::Body::wrap_stream(body))
+            .expect("parts come from a valid response")
+            .into()
+    }
+
+    fn headers(&self, headers: &HeaderMap) -> String {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if value.is_sensitive()
+                    || self
+                        .headers
+                        .iter()
+                        .any(|redacted| redacted == name.as_str())
+                {
+                    "[REDACTED]".into()
+                } else {
+                    String::from_utf8_lossy(value.as_bytes())
+                };
+                format!("{}: {}", name, value)
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// A whole body, masked
+    fn body(&self, bytes: &[u8]) -> String {
+        if bytes.len() > self.max_body_bytes {
+            return format!("[{} bytes, over the wire log limit]", bytes.len());
+        }
+        match serde_json::from_slice::<Value>(bytes) {
+            Ok(mut value) => {
+                self.redact(&mut value);
+                value.to_string()
+            }
+            Err(_) if self.content => format!("[{} bytes of non-JSON content]", bytes.len()),
+            Err(_) => String::from_utf8_lossy(bytes).into_owned(),
+        }
+    }
+
+    /// One line of a stream, masked: a bare JSON value or an SSE field
+    fn line(&self, line: &str) -> String {
+        let (prefix, data) = match line.split_once(':') {
+            Some((field, data)) if !line.starts_with(['{', '[']) => {
+                (format!("{}: ", field), data.trim_start())
+            }
+            _ => (String::new(), line),
+        };
+        match serde_json::from_str::<Value>(data) {
+            Ok(mut value) => {
+                self.redact(&mut value);
+                format!("{}{}", prefix, value)
+            }
+            Err(_) => line.to_string(),
+        }
+    }
+
+    fn redact(&self, value: &mut Value) {
+        match value {
+            Value::Object(fields) => {
+                let base64_source = fields.get("type").and_then(Value::as_str) == Some("base64");
+                for (key, field) in fields.iter_mut() {
+                    match field {
+                        Value::String(data) if self.base64 && base64_source && key == "data" => {
+                            *field = Value::String(format!("[BASE64 {} bytes]", data.len()));
+                        }
+                        Value::String(text)
+                            if self.content && CONTENT_KEYS.contains(&key.as_str()) =>
+                        {
+                            *field = Value::String(format!("[REDACTED {} chars]", text.len()));
+                        }
+                        _ => self.redact(field),
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.redact(item)),
+            Value::String(text) if self.base64 && looks_like_base64(text) => {
+                *value = Value::String(format!("[BASE64 {} bytes]", text.len()));
+            }
+            _ => {}
+        }
+    }
+}
+
+fn looks_like_base64(text: &str) -> bool {
+    text.len() >= MIN_BASE64_LEN
+        && text
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'/' | b'=' | b'-' | b'_'))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BodyMode {
+    /// Log each line as it arrives
+    Lines,
+    /// Log the body once it has been read
+    Whole,
+    /// Log only the size
+    Opaque,
+}
+
+/// Logs a response body as it passes through; whatever is left is logged
+/// when the body is dropped
+struct BodyLog {
+    policy: Arc<RedactionPolicy>,
+    mode: BodyMode,
+    pending: Vec<u8>,
+    received: usize,
+}
+
+impl BodyLog {
+    fn chunk(&mut self, bytes: &[u8]) {
+        self.received += bytes.len();
+        match self.mode {
+            BodyMode::Lines => {
+                self.pending.extend_from_slice(bytes);
+                while let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+                    let line: Vec<u8> = self.pending.drain(..=end).collect();
+                    self.log_line(&line);
+                }
+            }
+            BodyMode::Whole => {
+                if self.pending.len() <= self.policy.max_body_bytes {
+                    self.pending.extend_from_slice(bytes);
+                }
+            }
+            BodyMode::Opaque => {}
+        }
+    }
+
+    fn log_line(&self, line: &[u8]) {
+        let line = String::from_utf8_lossy(line);
+        let line = line.trim_end_matches(['\r', '\n']);
+        if !line.is_empty() {
+            tracing::debug!(target: WIRE_TARGET, line = %self.policy.line(line), "response line");
+        }
+    }
+}
+
+impl Drop for BodyLog {
+    fn drop(&mut self) {
+        match self.mode {
+            BodyMode::Lines => {
+                let rest = std::mem::take(&mut self.pending);
+                self.log_line(&rest);
+            }
+            BodyMode::Whole => {
+                let body = if self.received > self.policy.max_body_bytes {
+                    format!("[{} bytes, over the wire log limit]", self.received)
+                } else {
+                    self.policy.body(&self.pending)
+                };
+                tracing::debug!(
+                    target: WIRE_TARGET,
+                    bytes = self.received,
+                    body = %body,
+                    "response body"
+                );
+            }
+            BodyMode::Opaque => {
+                tracing::debug!(target: WIRE_TARGET, bytes = self.received, "response body");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_redacts_base64_and_optionally_content() {
+        let image = "A".repeat(400);
+        let body = json!({
+            "system": "secret system prompt",
+            "messages": [{"role": "user", "content": [
+                {"type": "text", "text": "secret question"},
+                {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "iVBORw0K"}},
+                {"type": "document", "source": {"type": "url", "url": image}}
+            ]}]
+        })
+        .to_string();
+
+        let logged = RedactionPolicy::new().body(body.as_bytes());
+        assert!(logged.contains("secret question"));
+        assert!(logged.contains("[BASE64 8 bytes]"));
+        assert!(logged.contains("[BASE64 400 bytes]"));
+
+        let logged = RedactionPolicy::new()
+            .with_content_redaction(true)
+            .body(body.as_bytes());
+        assert!(!logged.contains("secret"), "{}", logged);
+        assert!(logged.contains("[REDACTED 15 chars]"));
+
+        let line = RedactionPolicy::new().with_content_redaction(true).line(
+            r#"data: {"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hello"}}"#,
+        );
+        assert!(line.starts_with("data: {"), "{}", line);
+        assert!(line.contains("[REDACTED 5 chars]"));
+    }
+}
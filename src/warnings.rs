@@ -0,0 +1,203 @@
+//! Structured non-fatal warnings
+//!
+//! Some conditions are worth an operator's attention without failing any
+//! request: the rate limit is nearly used up, a deprecated model is still in
+//! use, a cached prompt prefix expired, a background refresh failed. Besides
+//! being logged, each is sent as a [`Warning`] to every receiver returned by
+//! [`Client::warnings`](crate::Client::warnings), so it can be routed to
+//! alerting without scraping logs:
+//!
+//! ```rust,no_run
+//! use threatflux_anthropic_sdk::{warnings::Warning, Client};
+//!
+//! # fn example() -> threatflux_anthropic_sdk::Result<()> {
+//! let client = Client::from_env()?;
+//! let mut warnings = client.warnings();
+//! tokio::spawn(async move {
+//!     while let Ok(warning) = warnings.recv().await {
+//!         if let Warning::RateLimitNearing { remaining, limit, .. } = &warning {
+//!             eprintln!("page: {} of {} requests left", remaining, limit);
+//!         }
+//!     }
+//! });
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Warnings are dropped when nobody is subscribed, and a receiver that falls
+//! more than [`DEFAULT_CAPACITY`] warnings behind skips the oldest.
+
+use std::{
+    collections::HashSet,
+    fmt,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::sync::broadcast;
+
+/// Warnings buffered per receiver before the oldest are skipped
+pub const DEFAULT_CAPACITY: usize = 64;
+
+/// Share of the request rate limit used up before warning
+pub const DEFAULT_RATE_LIMIT_THRESHOLD: f32 = 0.9;
+
+/// A condition worth attention that did not fail a request
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Warning {
+    /// A response reported the request rate limit nearly used up
+    RateLimitNearing {
+        /// Requests left in the current window
+        remaining: u32,
+        /// Requests allowed per window
+        limit: u32,
+        /// Time until the window resets, if reported
+        reset_in: Option<Duration>,
+    },
+    /// A request used a deprecated or retired model; sent once per model
+    DeprecatedModel {
+        /// The model id
+        model: String,
+    },
+    /// A cached prompt prefix expired before it was used or refreshed
+    CacheExpired {
+        /// Model the prefix is cached for
+        model: String,
+        /// How long ago it expired
+        expired_for: Duration,
+    },
+    /// A background task failed to refresh something it keeps up to date
+    BackgroundRefreshFailed {
+        /// What was being refreshed, e.g. `prompt_cache`
+        task: String,
+        /// Why it failed
+        error: String,
+    },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RateLimitNearing {
+                remaining, limit, ..
+            } => write!(
+                f,
+                "Rate limit nearly reached: {} of {} requests left",
+                remaining, limit
+            ),
+            Self::DeprecatedModel { model } => write!(f, "Model {} is deprecated", model),
+            Self::CacheExpired { model, expired_for } => write!(
+                f,
+                "Prompt cache prefix for {} expired {:?} ago",
+                model, expired_for
+            ),
+            Self::BackgroundRefreshFailed { task, error } => {
+                write!(f, "Background {} refresh failed: {}", task, error)
+            }
+        }
+    }
+}
+
+/// Sends [`Warning`]s to subscribers.
+///
+/// Clones share the same channel, so every client built from one
+/// [`Config`](crate::config::Config) reports to the same receivers.
+#[derive(Clone)]
+pub struct Warnings {
+    sender: broadcast::Sender<Warning>,
+    /// Models already warned about
+    deprecated_seen: Arc<Mutex<HashSet<String>>>,
+}
+
+impl Default for Warnings {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl fmt::Debug for Warnings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Warnings")
+            .field("receivers", &self.sender.receiver_count())
+            .finish()
+    }
+}
+
+impl Warnings {
+    /// A channel buffering `capacity` warnings per receiver
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self {
+            sender,
+            deprecated_seen: Arc::default(),
+        }
+    }
+
+    /// Receive every warning sent from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<Warning> {
+        self.sender.subscribe()
+    }
+
+    /// Log `warning` and send it to the receivers
+    pub fn emit(&self, warning: Warning) {
+        tracing::warn!("{}", warning);
+        // No receivers is not an error
+        let _ = self.sender.send(warning);
+    }
+
+    /// Warn the first time a deprecated `model` is used
+    pub(crate) fn check_model(&self, model: &str) {
+        if !crate::config::models::is_deprecated(model) {
+            return;
+        }
+        let first = self
+            .deprecated_seen
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(model.to_string());
+        if first {
+            self.emit(Warning::DeprecatedModel {
+                model: model.to_string(),
+            });
+        }
+    }
+
+    /// Warn if a response's rate limit headers show `threshold` of the
+    /// limit used
+    pub(crate) fn check_rate_limit(
+        &self,
+        info: &crate::utils::http::RateLimitInfo,
+        threshold: f32,
+    ) {
+        if let (Some(remaining), Some(limit)) = (info.remaining, info.limit) {
+            if limit > 0 && info.is_approaching_limit(threshold) {
+                self.emit(Warning::RateLimitNearing {
+                    remaining,
+                    limit,
+                    reset_in: info.time_until_reset(),
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deprecated_model_warned_once() {
+        let warnings = Warnings::default();
+        let mut receiver = warnings.subscribe();
+        warnings.check_model(crate::config::models::SONNET_4_6);
+        warnings.check_model(crate::config::models::OPUS_4_1);
+        warnings.check_model(crate::config::models::OPUS_4_1);
+        assert_eq!(
+            receiver.try_recv().unwrap(),
+            Warning::DeprecatedModel {
+                model: "claude-opus-4-1".to_string()
+            }
+        );
+        assert!(receiver.try_recv().is_err());
+    }
+}
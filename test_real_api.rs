@@ -75,7 +75,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 match event {
                     Ok(evt) => {
                         if let threatflux::models::message::StreamEvent::ContentBlockDelta { delta, .. } = evt {
-                            if let Some(text) = delta.text {
+                            if let Some(text) = delta.as_text() {
                                 print!("{}", text);
                             }
                         }
@@ -57,6 +57,7 @@ pub mod fixtures {
             id: "msg_test123".to_string(),
             object_type: "message".to_string(),
             created_at: Utc::now(),
+            provenance: None,
             model: "claude-3-5-haiku-20241022".to_string(),
             role: Role::Assistant,
             content: vec![ContentBlock::text("Test response")],
@@ -93,8 +94,8 @@ pub mod fixtures {
         ModelListResponse {
             data: vec![test_model()],
             has_more: false,
-            first_id: Some("claude-3-5-haiku-20241022".to_string()),
-            last_id: Some("claude-3-5-haiku-20241022".to_string()),
+            first_id: Some("claude-3-5-haiku-20241022".into()),
+            last_id: Some("claude-3-5-haiku-20241022".into()),
         }
     }
 
@@ -129,8 +130,8 @@ pub mod fixtures {
         MessageBatchListResponse {
             data: vec![test_batch()],
             has_more: false,
-            first_id: Some("batch_test123".to_string()),
-            last_id: Some("batch_test123".to_string()),
+            first_id: Some("batch_test123".into()),
+            last_id: Some("batch_test123".into()),
         }
     }
 
@@ -155,8 +156,8 @@ pub mod fixtures {
         FileListResponse {
             data: vec![test_file()],
             has_more: false,
-            first_id: Some("file_test123".to_string()),
-            last_id: Some("file_test123".to_string()),
+            first_id: Some("file_test123".into()),
+            last_id: Some("file_test123".into()),
         }
     }
 
@@ -606,3 +607,29 @@ pub mod responses {
             .set_body_string(events.join("\n"))
     }
 }
+
+/// Raw TCP servers for behavior wiremock cannot produce
+pub mod raw_server {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve every connection `head` (status line and headers) followed by
+    /// `partial_body`, then stall without closing. Returns the base URL.
+    pub async fn stalling_server(head: &'static str, partial_body: &'static [u8]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut request = [0u8; 4096];
+                    let _ = socket.read(&mut request).await;
+                    let _ = socket.write_all(head.as_bytes()).await;
+                    let _ = socket.write_all(b"\r\n").await;
+                    let _ = socket.write_all(partial_body).await;
+                    tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+                });
+            }
+        });
+        url
+    }
+}
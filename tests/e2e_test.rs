@@ -112,9 +112,9 @@ async fn test_streaming_responses() -> Result<(), Box<dyn Error>> {
     while let Some(event) = stream.next().await {
         match event? {
             StreamEvent::ContentBlockDelta { delta, .. } => {
-                if let Some(text) = delta.text {
+                if let Some(text) = delta.as_text() {
                     print!("{}", text);
-                    total_text.push_str(&text);
+                    total_text.push_str(text);
                 }
             }
             StreamEvent::MessageStop => {
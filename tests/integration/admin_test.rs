@@ -50,6 +50,36 @@ mod admin_api_tests {
         assert_eq!(org.display_name, Some("Test Org".to_string()));
     }
 
+    #[tokio::test]
+    async fn test_admin_requests_wait_in_dispatch_queue() {
+        use std::time::{Duration, Instant};
+        use threatflux_anthropic_sdk::{
+            types::RequestOptions, utils::DispatchQueue, AnthropicError,
+        };
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/organizations/me"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(fixtures::test_organization()))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let config = Config::new("admin-test-key")
+            .unwrap()
+            .with_admin_key("admin-test-key")
+            .with_base_url(mock_server.uri().parse().unwrap())
+            .with_dispatch_queue(DispatchQueue::per_second(1));
+        let client = Client::new(config);
+        let admin = client.admin().unwrap();
+
+        admin.organization().get(None).await.unwrap();
+        let options =
+            RequestOptions::new().with_deadline(Instant::now() + Duration::from_millis(100));
+        let error = admin.organization().get(Some(options)).await.unwrap_err();
+        assert!(matches!(error, AnthropicError::Timeout(_)), "{:?}", error);
+    }
+
     #[tokio::test]
     async fn test_list_workspaces() {
         let mock_server = MockServer::start().await;
@@ -3,7 +3,12 @@
 //! Tests Batch API operations with mocked responses.
 
 use serde_json::json;
-use threatflux_anthropic_sdk::{builders::BatchBuilder, types::Pagination, Client, Config};
+use std::time::Duration;
+use threatflux_anthropic_sdk::{
+    builders::BatchBuilder, models::batch::PollOptions, types::Pagination, AnthropicError, Client,
+    Config,
+};
+use tokio_util::sync::CancellationToken;
 use wiremock::{
     matchers::{header, method, path, query_param},
     Mock, MockServer, ResponseTemplate,
@@ -53,6 +58,151 @@ mod batches_api_tests {
         assert_eq!(batch.request_counts.total, 1);
     }
 
+    #[tokio::test]
+    async fn test_create_all_submits_every_chunk() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/messages/batches"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(fixtures::test_batch()))
+            .expect(3)
+            .mount(&mock_server)
+            .await;
+
+        let client = setup_test_client(&mock_server).await;
+        let mut builder = BatchBuilder::new();
+        for i in 0..5 {
+            builder = builder.add_simple_request(format!("req{}", i), "claude-haiku-4-5", "Hi", 10);
+        }
+        let chunks = builder.build_chunked(2, usize::MAX).unwrap();
+
+        let batches = client.message_batches().create_all(chunks, None).await;
+        assert_eq!(batches.len(), 3);
+        assert!(batches.iter().all(|batch| batch.is_ok()));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_completion_reports_progress() {
+        let mock_server = MockServer::start().await;
+        let mut ended = fixtures::test_batch();
+        ended.processing_status =
+            threatflux_anthropic_sdk::models::batch::MessageBatchStatus::Completed;
+        ended.request_counts.processing = 0;
+        ended.request_counts.completed = 1;
+
+        Mock::given(method("GET"))
+            .and(path("/v1/messages/batches/batch_test123"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(fixtures::test_batch()))
+            .up_to_n_times(2)
+            .with_priority(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/messages/batches/batch_test123"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&ended))
+            .mount(&mock_server)
+            .await;
+
+        let client = setup_test_client(&mock_server).await;
+        let seen = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let progress = std::sync::Arc::clone(&seen);
+        let poll = PollOptions::new()
+            .with_backoff(Duration::from_millis(5), Duration::from_millis(20), 2.0)
+            .on_progress(move |counts| progress.lock().unwrap().push(counts.completed));
+
+        let batch = client
+            .message_batches()
+            .wait_for_completion("batch_test123", poll)
+            .await
+            .unwrap();
+        assert!(batch.is_complete());
+        assert_eq!(*seen.lock().unwrap(), [0, 0, 1]);
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let stuck = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/messages/batches/batch_test123"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(fixtures::test_batch()))
+            .expect(1)
+            .mount(&stuck)
+            .await;
+        let err = setup_test_client(&stuck)
+            .await
+            .message_batches()
+            .wait_for_completion("batch_test123", PollOptions::new().with_cancel(token))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AnthropicError::Cancelled), "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn test_create_batch_accepted_follows_location() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/messages/batches"))
+            .respond_with(
+                ResponseTemplate::new(202)
+                    .insert_header("location", "/v1/messages/batches/batch_test123")
+                    .insert_header("retry-after", "0"),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // Not visible on the first fetch, then ready.
+        Mock::given(method("GET"))
+            .and(path("/v1/messages/batches/batch_test123"))
+            .respond_with(ResponseTemplate::new(404).set_body_json(json!({
+                "type": "error",
+                "error": {"type": "not_found_error", "message": "Not found"}
+            })))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/messages/batches/batch_test123"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(fixtures::test_batch()))
+            .mount(&mock_server)
+            .await;
+
+        let client = setup_test_client(&mock_server).await;
+        let batch_request = BatchBuilder::new()
+            .add_simple_request("req1", "claude-haiku-4-5", "Hello", 100)
+            .build();
+
+        let batch = client
+            .message_batches()
+            .create(batch_request, None)
+            .await
+            .unwrap();
+        assert_eq!(batch.id, "batch_test123");
+    }
+
+    #[tokio::test]
+    async fn test_create_batch_accepted_without_id_is_api_error() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/messages/batches"))
+            .respond_with(ResponseTemplate::new(202))
+            .mount(&mock_server)
+            .await;
+
+        let client = setup_test_client(&mock_server).await;
+        let batch_request = BatchBuilder::new()
+            .add_simple_request("req1", "claude-haiku-4-5", "Hello", 100)
+            .build();
+
+        let err = client
+            .message_batches()
+            .create(batch_request, None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), Some(202));
+    }
+
     #[tokio::test]
     async fn test_create_batch_with_builder() {
         let mock_server = MockServer::start().await;
@@ -232,6 +382,34 @@ mod batches_api_tests {
         assert_eq!(results[0].custom_id, "req1");
     }
 
+    #[tokio::test]
+    async fn test_results_to_file_keeps_partial_file_beside_full_name() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/messages/batches/batch_test123/results"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("{}\n"))
+            .mount(&mock_server)
+            .await;
+        let client = setup_test_client(&mock_server).await;
+
+        // A sibling whose temporary file `with_extension` would have clobbered
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("results.partial"), "other download").unwrap();
+        let target = dir.path().join("results.jsonl");
+        let written = client
+            .message_batches()
+            .results_to_file("batch_test123", &target, None)
+            .await
+            .unwrap();
+        assert_eq!(written, 3);
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "{}\n");
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("results.partial")).unwrap(),
+            "other download"
+        );
+        assert!(!dir.path().join("results.jsonl.partial").exists());
+    }
+
     #[tokio::test]
     async fn test_batch_status_transitions() {
         let mock_server = MockServer::start().await;
@@ -485,4 +663,288 @@ mod batches_api_tests {
         assert_eq!(batch.request_counts.expired, 5);
         assert!(batch.completed_at.is_some());
     }
+
+    #[tokio::test]
+    async fn test_retry_failed_submits_follow_up_batch() {
+        use std::collections::HashMap;
+        use threatflux_anthropic_sdk::models::batch::{
+            BatchResults, BatchRetryPolicy, MessageBatchResultEntry,
+        };
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/messages/batches"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(fixtures::test_batch()))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let client = setup_test_client(&mock_server).await;
+
+        let original = BatchBuilder::new()
+            .add_simple_request("ok", "claude-haiku-4-5", "a", 1000)
+            .add_simple_request("overloaded", "claude-haiku-4-5", "b", 1000)
+            .add_simple_request("invalid", "claude-haiku-4-5", "c", 1000)
+            .add_simple_request("expired", "claude-haiku-4-5", "d", 1000)
+            .build();
+        let entries: Vec<MessageBatchResultEntry> = serde_json::from_value(json!([
+            {"custom_id": "ok", "result": {"type": "succeeded", "message": {
+                "id": "msg_1", "type": "message", "role": "assistant", "model": "claude-haiku-4-5",
+                "content": [{"type": "text", "text": "hi"}], "stop_reason": "end_turn",
+                "usage": {"input_tokens": 1, "output_tokens": 1}
+            }}},
+            {"custom_id": "overloaded", "result": {"type": "errored", "error": {"type": "overloaded_error", "message": "busy"}}},
+            {"custom_id": "invalid", "result": {"type": "errored", "error": {"type": "invalid_request_error", "message": "bad"}}},
+            {"custom_id": "expired", "result": {"type": "expired"}}
+        ]))
+        .unwrap();
+        let results = BatchResults::new(original.requests, entries);
+        assert_eq!(results.failed().count(), 3);
+
+        let batches = client.message_batches();
+        let retry = results
+            .retry_failed(&batches, &BatchRetryPolicy::new().scale_max_tokens(0.5))
+            .await
+            .unwrap()
+            .expect("follow-up batch");
+
+        assert_eq!(retry.batch.id, "batch_test123");
+        assert_eq!(
+            retry.id_map,
+            HashMap::from([
+                ("overloaded".to_string(), "overloaded-retry".to_string()),
+                ("expired".to_string(), "expired-retry".to_string()),
+            ])
+        );
+
+        let requests = mock_server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        let submitted = body["requests"].as_array().unwrap();
+        assert_eq!(submitted.len(), 2);
+        assert_eq!(submitted[0]["custom_id"], "overloaded-retry");
+        assert_eq!(submitted[0]["params"]["max_tokens"], 500);
+
+        // Nothing left to retry once only invalid requests remain
+        let only_invalid = BatchResults::new(Vec::new(), vec![results.entries()[2].clone()]);
+        assert!(only_invalid
+            .retry_failed(&batches, &BatchRetryPolicy::new())
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_retry_ids_stay_unique_for_long_shared_prefixes() {
+        use std::collections::HashSet;
+        use threatflux_anthropic_sdk::models::batch::{
+            BatchResults, BatchRetryPolicy, MessageBatchResultEntry,
+        };
+
+        let prefix = "x".repeat(60);
+        let ids = [format!("{}0001", prefix), format!("{}0002", prefix)];
+        let original = BatchBuilder::new()
+            .add_simple_request(&ids[0], "claude-haiku-4-5", "a", 1000)
+            .add_simple_request(&ids[1], "claude-haiku-4-5", "b", 1000)
+            .build();
+        let entries: Vec<MessageBatchResultEntry> = serde_json::from_value(json!([
+            {"custom_id": ids[0], "result": {"type": "expired"}},
+            {"custom_id": ids[1], "result": {"type": "expired"}},
+            {"custom_id": ids[1], "result": {"type": "expired"}}
+        ]))
+        .unwrap();
+        let results = BatchResults::new(original.requests, entries);
+
+        let (request, id_map) = results.retry_plan(&BatchRetryPolicy::new()).unwrap();
+        assert_eq!(request.requests.len(), 2);
+        let retry_ids: HashSet<&str> = request
+            .requests
+            .iter()
+            .map(|item| item.custom_id.as_str())
+            .collect();
+        assert_eq!(retry_ids.len(), 2);
+        for id in &ids {
+            let retry_id = &id_map[id];
+            assert!(retry_id.len() <= 64, "{}", retry_id);
+            assert!(retry_id.ends_with("-retry"));
+            assert!(retry_ids.contains(retry_id.as_str()));
+        }
+
+        let retry = results.retry_batch().unwrap();
+        let custom_ids: Vec<&str> = retry
+            .requests
+            .iter()
+            .map(|item| item.custom_id.as_str())
+            .collect();
+        assert_eq!(custom_ids, [ids[0].as_str(), ids[1].as_str()]);
+    }
+
+    #[test]
+    fn test_retry_ids_fit_in_bytes_for_non_ascii_ids_and_long_suffixes() {
+        use threatflux_anthropic_sdk::models::batch::{
+            BatchResults, BatchRetryPolicy, MessageBatchResultEntry,
+        };
+
+        // 40 chars, but 120 bytes
+        let id = "日".repeat(40);
+        let original = BatchBuilder::new()
+            .add_simple_request(&id, "claude-haiku-4-5", "a", 1000)
+            .build();
+        let entries: Vec<MessageBatchResultEntry> =
+            serde_json::from_value(json!([{"custom_id": id, "result": {"type": "expired"}}]))
+                .unwrap();
+        let results = BatchResults::new(original.requests, entries);
+
+        let (_, id_map) = results.retry_plan(&BatchRetryPolicy::new()).unwrap();
+        let retry_id = &id_map[&id];
+        assert!(retry_id.len() <= 64, "{}", retry_id);
+        assert!(retry_id.starts_with("日日日"));
+        assert!(retry_id.ends_with("-retry"));
+
+        let policy = BatchRetryPolicy::new().with_id_suffix("-".repeat(80));
+        let (_, id_map) = results.retry_plan(&policy).unwrap();
+        let retry_id = &id_map[&id];
+        assert_eq!(retry_id.len(), 64, "{}", retry_id);
+        // The suffix gives way to the hash tag keeping the ID distinct
+        assert!(
+            retry_id[1..9].chars().all(|c| c.is_ascii_hexdigit()),
+            "{}",
+            retry_id
+        );
+    }
+
+    #[tokio::test]
+    async fn test_batch_results_stream() {
+        use futures::StreamExt;
+
+        let mock_server = MockServer::start().await;
+        let body = format!(
+            "{}\r\n\nnot json\n{}\n",
+            json!({"custom_id": "req1", "result": {"type": "expired"}}),
+            json!({"custom_id": "req2", "result": {"type": "canceled"}}),
+        );
+
+        Mock::given(method("GET"))
+            .and(path("/v1/messages/batches/batch_test123/results"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(body))
+            .mount(&mock_server)
+            .await;
+
+        let client = setup_test_client(&mock_server).await;
+        let entries: Vec<_> = client
+            .message_batches()
+            .results_stream("batch_test123", None)
+            .await
+            .unwrap()
+            .collect()
+            .await;
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].as_ref().unwrap().custom_id, "req1");
+        let err = entries[1].as_ref().unwrap_err().to_string();
+        assert!(err.contains("line 3"), "{}", err);
+        assert_eq!(entries[2].as_ref().unwrap().custom_id, "req2");
+    }
+
+    #[tokio::test]
+    async fn test_results_for_maps_entries_to_requests() {
+        let mock_server = MockServer::start().await;
+        let body = [
+            json!({"custom_id": "a", "result": {"type": "errored", "error": {"type": "overloaded_error", "message": "busy"}}}),
+            json!({"custom_id": "b", "result": {"type": "canceled"}}),
+            json!({"custom_id": "c", "result": {"type": "succeeded", "message": {
+                "id": "msg_1", "type": "message", "role": "assistant", "model": "claude-haiku-4-5",
+                "content": [{"type": "text", "text": "hi"}], "stop_reason": "end_turn",
+                "usage": {"input_tokens": 1, "output_tokens": 1}
+            }}}),
+        ]
+        .map(|entry| entry.to_string())
+        .join("\n");
+        Mock::given(method("GET"))
+            .and(path("/v1/messages/batches/batch_test123/results"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(body))
+            .mount(&mock_server)
+            .await;
+        let client = setup_test_client(&mock_server).await;
+
+        let original = BatchBuilder::new()
+            .add_simple_request("a", "claude-haiku-4-5", "a", 100)
+            .add_simple_request("b", "claude-haiku-4-5", "b", 100)
+            .add_simple_request("c", "claude-haiku-4-5", "c", 100)
+            .build();
+        let results = client
+            .message_batches()
+            .results_for("batch_test123", original.requests.clone(), None)
+            .await
+            .unwrap();
+
+        assert!(results.get("c").unwrap().is_success());
+        assert!(results.get("missing").is_none());
+        let errored: Vec<_> = results.errored().map(|(id, _)| id).collect();
+        assert_eq!(errored, ["a"]);
+
+        let retry = results.retry_batch().expect("failed entries");
+        assert_eq!(retry.requests, original.requests[..2]);
+
+        let map = results.into_map();
+        assert_eq!(map.len(), 3);
+        assert!(map["a"].error().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_archive_batches_downloads_each_ended_batch_once() {
+        use threatflux_anthropic_sdk::{
+            models::batch::MessageBatchStatus,
+            pipelines::{archive_batches, ArchiveManifest, ArchiveOptions, DirectorySink},
+        };
+
+        let mock_server = MockServer::start().await;
+        let batch = |id: &str, status: MessageBatchStatus, age_days: i64| {
+            let mut batch = fixtures::test_batch();
+            batch.id = id.to_string();
+            batch.processing_status = status;
+            batch.created_at = chrono::Utc::now() - chrono::Duration::days(age_days);
+            batch
+        };
+        let mut list = fixtures::test_batch_list_response();
+        list.data = vec![
+            batch("batch_ended", MessageBatchStatus::Completed, 2),
+            batch("batch_running", MessageBatchStatus::InProgress, 0),
+            batch("batch_old", MessageBatchStatus::Completed, 40),
+        ];
+        Mock::given(method("GET"))
+            .and(path("/v1/messages/batches"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&list))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/messages/batches/batch_ended/results"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("{\"custom_id\":\"a\"}\n"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let client = setup_test_client(&mock_server).await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let sink = DirectorySink::new(dir.path().join("archive"));
+        let options = ArchiveOptions::new(dir.path().join("manifest.json"))
+            .with_temp_dir(dir.path().join("tmp"));
+
+        let report = archive_batches(&client, &sink, &options).await.unwrap();
+        assert_eq!(report.archived, ["batch_ended"]);
+        assert_eq!(report.expired, ["batch_old"]);
+        assert_eq!(report.skipped, 1);
+        assert!(report.failed.is_empty());
+        let archived = dir.path().join("archive").join("batch_ended.jsonl");
+        assert_eq!(
+            std::fs::read_to_string(&archived).unwrap(),
+            "{\"custom_id\":\"a\"}\n"
+        );
+        assert!(!dir.path().join("tmp").join("batch_ended.jsonl").exists());
+
+        let manifest = ArchiveManifest::load(&options.manifest).await.unwrap();
+        assert_eq!(manifest.batches["batch_ended"].bytes, 18);
+
+        let report = archive_batches(&client, &sink, &options).await.unwrap();
+        assert!(report.archived.is_empty());
+        assert_eq!(report.skipped, 2);
+    }
 }
@@ -167,7 +167,7 @@ mod e2e_tests {
                     delta,
                     ..
                 } => {
-                    if let Some(text) = delta.text.as_deref() {
+                    if let Some(text) = delta.as_text() {
                         collected_text.push_str(text);
                     }
                 }
@@ -459,6 +459,135 @@ mod e2e_tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_circuit_breaker_fails_fast_then_probes() {
+        use std::{sync::Arc, time::Duration};
+        use threatflux_anthropic_sdk::{
+            error::AnthropicError,
+            types::HttpMethod,
+            utils::retry::{CircuitState, RetryClient, RetryPolicy},
+        };
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .with_priority(1)
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"data": []})))
+            .mount(&mock_server)
+            .await;
+
+        let config = Config::new("sk-ant-test-key")
+            .unwrap()
+            .with_base_url(mock_server.uri().parse().unwrap())
+            .with_retry_policy(
+                RetryPolicy::new()
+                    .with_max_retries(0)
+                    .with_circuit_breaker(2, Duration::from_millis(200)),
+            );
+        let retry_client = RetryClient::new(Arc::new(config));
+        let url = format!("{}/v1/models", mock_server.uri()).parse().unwrap();
+        let get = || {
+            retry_client.request::<serde_json::Value>(
+                HttpMethod::Get,
+                &url,
+                None,
+                Default::default(),
+                Duration::from_secs(5),
+            )
+        };
+
+        assert!(matches!(
+            get().await,
+            Err(AnthropicError::Api { status: 503, .. })
+        ));
+        assert!(matches!(
+            get().await,
+            Err(AnthropicError::Api { status: 503, .. })
+        ));
+        assert_eq!(retry_client.stats().circuit_state, CircuitState::Open);
+        assert!(matches!(
+            get().await,
+            Err(AnthropicError::CircuitOpen { .. })
+        ));
+
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert!(get().await.is_ok());
+
+        let stats = retry_client.stats();
+        assert_eq!(stats.circuit_state, CircuitState::Closed);
+        assert_eq!(stats.circuits_opened, 1);
+        assert_eq!(stats.rejected_requests, 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_waits_server_advised_delay() {
+        use std::{sync::Arc, time::Duration};
+        use threatflux_anthropic_sdk::{
+            types::HttpMethod,
+            utils::retry::{RetryClient, RetryPolicy},
+        };
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "120"))
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(529))
+            .up_to_n_times(1)
+            .with_priority(2)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"data": []})))
+            .with_priority(3)
+            .mount(&mock_server)
+            .await;
+
+        let config = Config::new("sk-ant-test-key")
+            .unwrap()
+            .with_base_url(mock_server.uri().parse().unwrap())
+            .with_retry_policy(
+                RetryPolicy::new()
+                    .with_max_retries(2)
+                    .with_initial_delay(Duration::from_millis(10))
+                    .with_max_delay(Duration::from_millis(50))
+                    .with_jitter(false),
+            );
+        let retry_client = RetryClient::new(Arc::new(config));
+        let url = format!("{}/v1/models", mock_server.uri()).parse().unwrap();
+
+        retry_client
+            .request::<serde_json::Value>(
+                HttpMethod::Get,
+                &url,
+                None,
+                Default::default(),
+                Duration::from_secs(5),
+            )
+            .await
+            .unwrap();
+
+        let stats = retry_client.stats();
+        assert_eq!(stats.total_retry_attempts, 2);
+        assert_eq!(stats.server_advised_retries, 1);
+        // The 529 carried no advice, so the last wait is the policy's backoff
+        assert_eq!(stats.last_retry_delay, Some(Duration::from_millis(10)));
+        assert_eq!(stats.total_retry_delay, Duration::from_millis(60));
+    }
+
     #[tokio::test]
     async fn test_multi_modal_conversation() {
         let mock_server = MockServer::start().await;
@@ -4,7 +4,8 @@
 
 use serde_json::json;
 use threatflux_anthropic_sdk::{
-    models::file::FileUploadRequest, types::Pagination, Client, Config,
+    models::file::FileUploadRequest, types::Pagination, utils::integrity::ContentDigest,
+    AnthropicError, ChecksumAlgorithm, Client, Config,
 };
 use wiremock::{
     matchers::{body_string_contains, header, method, path, query_param},
@@ -200,6 +201,107 @@ mod files_api_tests {
         assert_eq!(download, file_content.to_vec());
     }
 
+    #[tokio::test]
+    async fn test_download_fails_when_body_stalls() {
+        use std::time::{Duration, Instant};
+
+        let url = crate::common::raw_server::stalling_server(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/octet-stream\r\ncontent-length: 100\r\n",
+            b"0123456789",
+        )
+        .await;
+        let config = Config::new("sk-ant-test-key")
+            .unwrap()
+            .with_base_url(url.parse().unwrap())
+            .with_timeout(Duration::from_secs(1));
+        let client = Client::new(config);
+
+        let started = Instant::now();
+        let err = client
+            .files()
+            .download("file_test123", None)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, AnthropicError::Http(e) if e.is_timeout()),
+            "{:?}",
+            err
+        );
+        assert!(err.is_retryable());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_upload_sends_content_checksums() {
+        let mock_server = MockServer::start().await;
+        let content = b"abc";
+        let digest = ContentDigest::compute(content);
+
+        Mock::given(method("POST"))
+            .and(path("/v1/files"))
+            .and(body_string_contains(format!(
+                "content-md5: {}",
+                digest.md5_base64()
+            )))
+            .and(body_string_contains(format!(
+                "content-digest: sha-256=:{}:",
+                digest.sha256_base64()
+            )))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(fixtures::test_file_upload_response()),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = setup_test_client(&mock_server).await;
+        let upload_request = FileUploadRequest::new(content.to_vec(), "test.txt", "text/plain");
+        client.files().upload(upload_request, None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_download_verifies_server_checksums() {
+        let mock_server = MockServer::start().await;
+        let file_content = b"This is the downloaded file content";
+        let digest = ContentDigest::compute(file_content);
+
+        Mock::given(method("GET"))
+            .and(path("/v1/files/file_good/download"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-md5", digest.md5_base64().as_str())
+                    .insert_header(
+                        "content-digest",
+                        format!("sha-256=:{}:", digest.sha256_base64()).as_str(),
+                    )
+                    .set_body_bytes(file_content),
+            )
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/files/file_bad/download"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-md5", digest.md5_base64().as_str())
+                    .set_body_bytes(&b"This is the downloaded file c0ntent"[..]),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let client = setup_test_client(&mock_server).await;
+
+        let download = client.files().download("file_good", None).await.unwrap();
+        assert_eq!(download, file_content.to_vec());
+
+        let err = client.files().download("file_bad", None).await.unwrap_err();
+        let AnthropicError::Integrity(integrity) = err else {
+            panic!("expected an integrity error, got {:?}", err);
+        };
+        assert_eq!(integrity.algorithm, ChecksumAlgorithm::Md5);
+        assert_eq!(integrity.resource, "file file_bad");
+        assert_eq!(integrity.expected, digest.md5_base64());
+    }
+
     #[tokio::test]
     async fn test_delete_file() {
         let mock_server = MockServer::start().await;
@@ -397,7 +397,7 @@ mod legacy_comprehensive_tests {
                     ..
                 } = event
                 {
-                    if delta.text.is_some() {
+                    if delta.as_text().is_some() {
                         received_text = true;
                     }
                 } else if let threatflux_anthropic_sdk::models::message::StreamEvent::MessageStop =
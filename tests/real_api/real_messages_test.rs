@@ -360,6 +360,7 @@ mod real_api_tests {
 #[cfg(not(feature = "real_api_tests"))]
 mod placeholder_tests {
     #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn real_api_tests_disabled() {
         println!("Real API tests are disabled. Enable with --features real_api_tests");
         assert!(true);
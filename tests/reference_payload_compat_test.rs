@@ -60,7 +60,7 @@ fn test_streaming_reference_payloads_deserialize() {
 
     match citation_delta {
         StreamEvent::ContentBlockDelta { delta, .. } => {
-            assert!(delta.citation.is_some());
+            assert!(delta.as_citation().is_some());
         }
         other => panic!("Expected ContentBlockDelta, got {other:?}"),
     }
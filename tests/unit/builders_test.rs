@@ -6,10 +6,10 @@ use serde_json::json;
 use threatflux_anthropic_sdk::{
     builders::{
         BatchBuilder, FluentBuilder, MessageBuilder, ParameterBuilder, PresetConfig,
-        ValidationUtils,
+        PromptTemplate, ValidationUtils,
     },
     models::{
-        batch::MessageBatchCreateRequest,
+        batch::{MessageBatchCreateRequest, MessageBatchResultEntry},
         common::{ContentBlock, ImageSource, Metadata, Role, Tool, ToolChoice},
         message::{MessageRequest, SystemPrompt},
     },
@@ -331,6 +331,46 @@ mod message_builder_tests {
         assert_eq!(request.top_p, Some(1.0));
         assert_eq!(request.top_k, Some(1000));
     }
+
+    #[test]
+    fn test_build_validated_enforces_request_limits() {
+        use threatflux_anthropic_sdk::config::limits;
+
+        let mut builder = MessageBuilder::new();
+        for _ in 0..=limits::MAX_IMAGES {
+            builder = builder.user_with_image("Describe", vec![0x89, b'P'], "image/png");
+        }
+        let error = builder.build_validated().unwrap_err().to_string();
+        assert!(error.contains("101 images"), "{}", error);
+
+        let oversized = "A".repeat(limits::MAX_IMAGE_BYTES + 4);
+        let error = MessageBuilder::new()
+            .user("First")
+            .user_with_base64_image("Look", oversized, "image/png")
+            .build_validated()
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("messages[1].content[1]"), "{}", error);
+        assert!(error.contains("5242884 bytes"), "{}", error);
+
+        let mut builder = MessageBuilder::new();
+        for _ in 0..=limits::MAX_PDF_PAGES {
+            builder = builder.user_with_document_url("Read", "https://example.com/a.pdf");
+        }
+        let state = builder.inspect();
+        assert!(state
+            .warnings
+            .iter()
+            .any(|w| w.contains("101 PDF documents")));
+
+        let huge = "x".repeat(limits::MAX_REQUEST_BYTES);
+        let error = MessageBuilder::new()
+            .user(huge)
+            .build_validated()
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("request size limit"), "{}", error);
+    }
 }
 
 #[cfg(test)]
@@ -349,6 +389,34 @@ mod batch_builder_tests {
         assert_eq!(batch.requests[1].custom_id, "req2");
     }
 
+    #[test]
+    fn test_batch_builder_build_chunked() {
+        let mut builder = BatchBuilder::new();
+        for i in 0..7 {
+            builder = builder.add_simple_request(format!("req{}", i), "claude-haiku-4-5", "Hi", 10);
+        }
+
+        let by_count = builder.clone().build_chunked(3, usize::MAX).unwrap();
+        let sizes: Vec<_> = by_count.iter().map(|chunk| chunk.requests.len()).collect();
+        assert_eq!(sizes, [3, 3, 1]);
+        assert_eq!(by_count[2].requests[0].custom_id, "req6");
+
+        let one = serde_json::to_string(&by_count[2]).unwrap().len();
+        let two = serde_json::to_string(&MessageBatchCreateRequest {
+            requests: by_count[0].requests[..2].to_vec(),
+        })
+        .unwrap()
+        .len();
+        let by_bytes = builder.clone().build_chunked(100, two).unwrap();
+        assert_eq!(by_bytes.len(), 4);
+        for chunk in &by_bytes {
+            assert!(serde_json::to_string(chunk).unwrap().len() <= two);
+        }
+
+        let err = builder.build_chunked(100, one - 1).unwrap_err();
+        assert!(err.to_string().contains("req0"));
+    }
+
     #[test]
     fn test_batch_builder_with_message_request() {
         let message_request = MessageBuilder::new()
@@ -606,6 +674,190 @@ mod batch_builder_tests {
             .build_validated();
         assert!(invalid.is_err());
     }
+
+    #[test]
+    fn test_add_templated_joins_results_to_rows() {
+        let rows = vec![
+            json!({"id": 1, "text": "Arrived broken"}),
+            json!({"id": 2, "text": "Works great"}),
+            json!({"id": 3, "text": "Meh"}),
+        ];
+        let template = PromptTemplate::new("Sentiment of: {text}")
+            .model("claude-haiku-4-5")
+            .max_tokens(8);
+
+        let (batch, join) = BatchBuilder::new()
+            .add_templated(rows, &template, |_, row| format!("row-{}", row["id"]))
+            .unwrap();
+        assert_eq!(batch.len(), 3);
+        assert_eq!(batch.requests()[1].custom_id, "row-2");
+        assert_eq!(batch.requests()[1].params.model, "claude-haiku-4-5");
+        assert_eq!(
+            join.custom_ids().collect::<Vec<_>>(),
+            vec!["row-1", "row-2", "row-3"]
+        );
+
+        let results: Vec<MessageBatchResultEntry> = serde_json::from_value(json!([
+            {"custom_id": "row-2", "result": {"type": "succeeded", "message": {
+                "id": "msg_2", "type": "message", "role": "assistant",
+                "model": "claude-haiku-4-5",
+                "content": [{"type": "text", "text": "positive"}],
+                "stop_reason": "end_turn",
+                "usage": {"input_tokens": 10, "output_tokens": 1}
+            }}},
+            {"custom_id": "row-1", "result": {"type": "errored", "error": {
+                "type": "invalid_request_error", "message": "bad"
+            }}},
+            {"custom_id": "unrelated", "result": {"type": "expired"}}
+        ]))
+        .unwrap();
+
+        let joined = join.join(results);
+        assert_eq!(joined.len(), 3);
+        assert_eq!(joined[0].row["text"], "Arrived broken");
+        assert!(joined[0].result.as_ref().unwrap().error().is_some());
+        assert_eq!(joined[1].text().as_deref(), Some("positive"));
+        assert!(joined[2].result.is_none());
+    }
+
+    #[test]
+    fn test_add_templated_rejects_duplicates_and_missing_vars() {
+        let template = PromptTemplate::new("Summarize {text}");
+
+        let duplicate = BatchBuilder::new().add_templated(
+            vec![json!({"text": "a"}), json!({"text": "b"})],
+            &template,
+            |_, _| "same".to_string(),
+        );
+        assert!(duplicate.is_err());
+
+        let missing =
+            BatchBuilder::new().add_templated(vec![json!({"body": "a"})], &template, |index, _| {
+                format!("row-{}", index)
+            });
+        let error = missing.unwrap_err().to_string();
+        assert!(error.contains("Row 0"), "{}", error);
+    }
+}
+
+#[cfg(test)]
+mod spec_tests {
+    use super::*;
+    use threatflux_anthropic_sdk::builders::{BatchSpec, JobSpec, SpecFormat};
+
+    #[test]
+    fn test_message_request_from_json_spec_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sentiment.json");
+        std::fs::write(
+            &path,
+            json!({
+                "model": "claude-haiku-4-5",
+                "system": "Answer with one word.",
+                "template": "Classify the sentiment of: {review}",
+                "variables": {"review": "Great battery life"},
+                "options": {"max_tokens": 16, "temperature": 0.0, "stop_sequences": ["\n"]}
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let request = MessageRequest::from_spec_file(&path).unwrap();
+        assert_eq!(request.model, "claude-haiku-4-5");
+        assert_eq!(request.max_tokens, 16);
+        assert_eq!(request.temperature, Some(0.0));
+        assert_eq!(request.stop_sequences, Some(vec!["\n".to_string()]));
+        assert!(
+            matches!(&request.system, Some(SystemPrompt::Text(text)) if text == "Answer with one word.")
+        );
+        assert_eq!(request.messages.len(), 1);
+
+        // Typos are errors rather than silently ignored
+        let error = JobSpec::from_str(
+            r#"{"template": "Hi", "options": {"max_token": 5}}"#,
+            SpecFormat::Json,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(error.contains("max_token"), "{}", error);
+        assert!(SpecFormat::from_path("job.txt").is_err());
+    }
+
+    #[test]
+    fn test_batch_spec_merges_shared_and_job_variables() {
+        let spec = BatchSpec::from_str(
+            &json!({
+                "template": "Translate to {language}: {text}",
+                "variables": {"text": "Good morning", "language": "Spanish"},
+                "jobs": [
+                    {"custom_id": "fr", "variables": {"language": "French"}},
+                    {"custom_id": "es"}
+                ]
+            })
+            .to_string(),
+            SpecFormat::Json,
+        )
+        .unwrap();
+        let batch = spec.to_batch().unwrap().build();
+        assert_eq!(batch.requests.len(), 2);
+        assert_eq!(
+            batch.requests[0].params.messages[0].text(),
+            "Translate to French: Good morning"
+        );
+        assert_eq!(
+            batch.requests[1].params.messages[0].text(),
+            "Translate to Spanish: Good morning"
+        );
+
+        let mut duplicate = spec.clone();
+        duplicate.jobs[1].custom_id = "fr".to_string();
+        assert!(duplicate.to_batch().is_err());
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_yaml_job_spec() {
+        let spec = JobSpec::from_str(
+            "template: \"Summarize: {text}\"\nvariables:\n  text: A long report\noptions:\n  max_tokens: 200\n  thinking_budget: 1024\n",
+            SpecFormat::Yaml,
+        )
+        .unwrap();
+        let request = spec.to_request().unwrap();
+        assert_eq!(request.max_tokens, 200);
+        assert!(request.thinking.is_some());
+        assert_eq!(request.messages[0].text(), "Summarize: A long report");
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_toml_batch_spec() {
+        let spec = BatchSpec::from_str(
+            r#"
+model = "claude-haiku-4-5"
+template = "Tag: {title}"
+
+[options]
+max_tokens = 32
+
+[[jobs]]
+custom_id = "a"
+variables = { title = "Release notes" }
+
+[[jobs]]
+custom_id = "b"
+variables = { title = "Incident report" }
+"#,
+            SpecFormat::Toml,
+        )
+        .unwrap();
+        let batch = spec.to_batch().unwrap().build();
+        assert_eq!(batch.requests[1].params.model, "claude-haiku-4-5");
+        assert_eq!(batch.requests[1].params.max_tokens, 32);
+        assert_eq!(
+            batch.requests[1].params.messages[0].text(),
+            "Tag: Incident report"
+        );
+    }
 }
 
 #[cfg(test)]
@@ -676,13 +928,64 @@ mod common_traits_tests {
 
     #[test]
     fn test_fluent_builder_trait() {
-        let message_builder = MessageBuilder::new();
-        let debug_info = message_builder.inspect();
-        assert!(debug_info.is_some());
+        let state = MessageBuilder::new().inspect();
+        assert!(!state.is_valid());
+        assert!(state.fields_set.is_empty());
+        assert_eq!(
+            state.warnings,
+            vec!["MessageRequest must contain at least one message".to_string()]
+        );
+
+        let state = MessageBuilder::new()
+            .system("Be terse.")
+            .user("Hello there, how are you today?")
+            .temperature(0.5)
+            .max_tokens(0)
+            .inspect();
+        assert!(state.fields_set.contains(&"system".to_string()));
+        assert!(state.fields_set.contains(&"messages".to_string()));
+        assert!(state.fields_set.contains(&"temperature".to_string()));
+        assert!(!state.fields_set.contains(&"model".to_string()));
+        assert!(state.estimated_tokens > 0);
+        assert_eq!(
+            state.warnings,
+            vec!["MessageRequest max_tokens must be greater than 0".to_string()]
+        );
+
+        let state = BatchBuilder::new().inspect();
+        assert!(!state.is_valid());
 
-        let batch_builder = BatchBuilder::new();
-        let debug_info = batch_builder.inspect();
-        assert!(debug_info.is_some());
+        let state = BatchBuilder::new()
+            .add_simple_request("req1", "claude-3-5-haiku-20241022", "Hello", 100)
+            .add_simple_request("req1", "claude-3-5-haiku-20241022", "Hello", 0)
+            .inspect();
+        assert_eq!(
+            state.warnings,
+            vec![
+                "Duplicate custom_id found: req1".to_string(),
+                "Request req1 max_tokens must be greater than 0".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_message_builder_to_partial_json() {
+        let builder = MessageBuilder::new()
+            .user_with_base64_image("What is this?", "aGVsbG8gd29ybGQ=", "image/png")
+            .max_tokens(256);
+        let json = builder.to_partial_json();
+        assert_eq!(json["max_tokens"], 256);
+        let source = &json["messages"][0]["content"][1]["source"];
+        assert_eq!(source["data"], "<base64: 16 bytes>");
+        assert_eq!(source["media_type"], "image/png");
+
+        // The builder itself is untouched
+        let request = builder.build();
+        let serialized = serde_json::to_value(&request).unwrap();
+        assert_eq!(
+            serialized["messages"][0]["content"][1]["source"]["data"],
+            "aGVsbG8gd29ybGQ="
+        );
     }
 
     #[test]
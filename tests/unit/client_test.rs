@@ -136,7 +136,7 @@ mod client_tests {
     fn test_client_try_new_invalid_config() {
         // Create a config with invalid API key (empty string)
         let config = Config {
-            api_key: String::new(),
+            api_key: String::new().into(),
             admin_key: None,
             base_url: url::Url::parse("https://api.anthropic.com").unwrap(),
             timeout: Duration::from_secs(30),
@@ -145,6 +145,7 @@ mod client_tests {
             default_model: "claude-sonnet-4-6".to_string(),
             enable_rate_limiting: true,
             rate_limit_rps: 50,
+            ..Config::default()
         };
 
         let result = Client::try_new(config);
@@ -157,6 +158,22 @@ mod client_tests {
         }
     }
 
+    #[test]
+    fn test_rate_limiter_built_from_final_config() {
+        // Limits set in either order reach the client's limiter
+        let config = Config::new("test-key")
+            .unwrap()
+            .with_rate_limit_tpm(0, 60_000)
+            .with_rate_limit_rps(7);
+        let client = Client::new(config);
+        assert!(client.rate_limiter().is_token_aware());
+        assert_eq!(client.rate_limiter().config().max_requests.get(), 7);
+
+        let client = Client::new(Config::new("test-key").unwrap().with_rate_limit_rps(7));
+        assert!(!client.rate_limiter().is_token_aware());
+        assert_eq!(client.rate_limiter().config().max_requests.get(), 7);
+    }
+
     #[test]
     fn test_client_new_vs_try_new_consistency() {
         let config = Config::new("test-key").unwrap();
@@ -280,4 +297,24 @@ mod client_tests {
         assert_eq!(options.beta_features.len(), 1);
         assert_eq!(options.beta_features[0], "custom-feature");
     }
+
+    #[test]
+    fn test_scoped_clients() {
+        use threatflux_anthropic_sdk::scope::{Inference, MessagesOnly, NoAdmin};
+
+        let config = Config::new("sk-ant-test-key").unwrap();
+        let client = Client::new(config);
+
+        let messages_only = client.scoped(MessagesOnly);
+        let _ = messages_only.messages();
+        assert!(format!("{:?}", messages_only).contains("MessagesOnly"));
+
+        let inference = client.scoped(Inference);
+        let _ = inference.models();
+        let _ = inference.message_batches();
+
+        let no_admin = client.scoped(NoAdmin).clone();
+        let _ = no_admin.files();
+        let _ = no_admin.sessions();
+    }
 }
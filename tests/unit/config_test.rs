@@ -131,7 +131,7 @@ mod config_tests {
     #[test]
     fn test_config_validation_empty_key() {
         let mut config = Config::new("valid-key").unwrap();
-        config.api_key = String::new();
+        config.api_key = String::new().into();
 
         let result = config.validate();
         assert!(matches!(result, Err(AnthropicError::Config(_))));
@@ -180,8 +180,9 @@ mod config_tests {
         let config = Config::new("secret-api-key").unwrap();
         let debug_str = format!("{:?}", config);
 
-        // Debug should contain the API key (no redaction in this implementation)
-        assert!(debug_str.contains("secret-api-key"));
+        // The API key is redacted
+        assert!(!debug_str.contains("secret-api-key"));
+        assert!(debug_str.contains("SecretKey([REDACTED])"));
     }
 
     #[test]
@@ -195,6 +196,23 @@ mod config_tests {
         assert!(!config.enable_rate_limiting);
     }
 
+    #[test]
+    fn test_rate_limit_rps_paces_only_when_configured() {
+        use threatflux_anthropic_sdk::utils::DetectedLimits;
+
+        let config = Config::new("test-key").unwrap();
+        assert!(!config.pace_rate_limit_rps);
+        assert!(config.clone().with_rate_limit_rps(10).pace_rate_limit_rps);
+
+        let detected = DetectedLimits {
+            requests_per_minute: Some(1200),
+            ..DetectedLimits::default()
+        };
+        let config = config.with_detected_rate_limits(&detected, 0.75);
+        assert!(config.pace_rate_limit_rps);
+        assert_eq!(config.rate_limit_rps, 15);
+    }
+
     #[test]
     fn test_config_edge_cases() {
         // Very long API key
@@ -249,7 +267,7 @@ mod config_tests {
     #[test]
     fn test_config_with_admin_key() {
         let config = Config::new("test-key").unwrap().with_admin_key("admin-key");
-        assert_eq!(config.admin_key, Some("admin-key".to_string()));
+        assert_eq!(config.admin_key, Some("admin-key".into()));
     }
 
     #[test]
@@ -259,6 +277,74 @@ mod config_tests {
             .with_user_agent("custom-agent/1.0");
         assert_eq!(config.user_agent, "custom-agent/1.0");
     }
+
+    #[test]
+    fn test_config_with_app_info() {
+        let config = Config::new("test-key")
+            .unwrap()
+            .with_user_agent("sdk/1.0")
+            .with_app_info("my-app", "2.3.1");
+        assert_eq!(config.user_agent, "sdk/1.0");
+        assert_eq!(config.full_user_agent(), "sdk/1.0 my-app/2.3.1");
+    }
+
+    #[test]
+    fn test_config_with_failover() {
+        use threatflux_anthropic_sdk::utils::FailoverPolicy;
+
+        let config = Config::new("test-key").unwrap().with_failover(
+            [
+                "https://us.example.com".parse().unwrap(),
+                "https://eu.example.com".parse().unwrap(),
+            ],
+            FailoverPolicy::default(),
+        );
+        assert_eq!(config.base_url.as_str(), "https://us.example.com/");
+        assert_eq!(config.active_base_url(), config.base_url);
+        assert!(config.validate().is_ok());
+
+        let empty = Config::new("test-key")
+            .unwrap()
+            .with_failover(Vec::new(), FailoverPolicy::default());
+        assert!(empty.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_with_model_concurrency() {
+        let config = Config::new("test-key")
+            .unwrap()
+            .with_model_concurrency("claude-opus-*", 2)
+            .with_model_concurrency("*", 16);
+        assert_eq!(config.model_concurrency.len(), 2);
+        assert!(config.model_concurrency[0].matches("claude-opus-4-6"));
+        assert!(!config.model_concurrency[0].matches("claude-haiku-4-5"));
+        assert!(config.validate().is_ok());
+
+        let zero = Config::new("test-key")
+            .unwrap()
+            .with_model_concurrency("claude-opus-*", 0);
+        assert!(zero.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_telemetry_headers() {
+        let config = Config::new("test-key").unwrap();
+        let headers = config.telemetry_headers();
+        assert!(headers.contains(&("x-stainless-lang", "rust".to_string())));
+        assert!(headers.contains(&(
+            "x-stainless-package-version",
+            env!("CARGO_PKG_VERSION").to_string()
+        )));
+        assert!(headers.iter().any(|(name, _)| *name == "x-stainless-os"));
+        let runtime_version = headers
+            .iter()
+            .find(|(name, _)| *name == "x-stainless-runtime-version")
+            .map(|(_, value)| value.as_str());
+        assert!(runtime_version.is_some_and(|v| v.starts_with("1.")));
+
+        let disabled = config.with_telemetry_headers(false);
+        assert!(disabled.telemetry_headers().is_empty());
+    }
 }
 
 #[cfg(test)]
@@ -286,6 +372,36 @@ mod model_tests {
         assert!(!models::supports_1m_context(""));
     }
 
+    #[test]
+    fn test_supports_pdf_input() {
+        assert!(models::supports_pdf_input(models::SONNET_4_6));
+        assert!(models::supports_pdf_input(models::HAIKU_4_5));
+        assert!(models::supports_pdf_input(models::FABLE_5));
+        assert!(!models::supports_pdf_input("claude-3-opus-20240229"));
+        assert!(!models::supports_pdf_input("unknown-model"));
+        assert!(!models::supports_pdf_input(""));
+    }
+
+    #[test]
+    fn test_model_pricing() {
+        use threatflux_anthropic_sdk::models::common::{CacheCreationUsage, Usage};
+
+        let sonnet = models::pricing(models::SONNET_4_6).unwrap();
+        assert_eq!(sonnet, models::ModelPricing::new(3.0, 15.0));
+        assert!(models::pricing("unknown-model").is_none());
+
+        let mut usage = Usage::new(1_000_000, 100_000);
+        assert!((sonnet.cost(&usage) - 4.5).abs() < 1e-9);
+
+        usage.cache_read_input_tokens = 1_000_000;
+        usage.cache_creation = Some(CacheCreationUsage {
+            ephemeral_5m_input_tokens: 0,
+            ephemeral_1h_input_tokens: 1_000_000,
+        });
+        // 3.0 input + 0.3 cache read + 6.0 one-hour cache write + 1.5 output
+        assert!((sonnet.cost(&usage) - 10.8).abs() < 1e-9);
+    }
+
     #[test]
     fn test_max_thinking_tokens() {
         // budget_tokens is removed for adaptive-thinking models; the helper now
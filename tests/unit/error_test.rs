@@ -45,6 +45,7 @@ mod error_tests {
             status,
             message,
             error_type,
+            ..
         } = api_error
         {
             assert_eq!(status, 404);
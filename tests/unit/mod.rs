@@ -67,7 +67,7 @@ mod legacy_config_tests {
         assert!(config.validate().is_ok());
 
         let mut invalid_config = config.clone();
-        invalid_config.api_key = String::new();
+        invalid_config.api_key = String::new().into();
         assert!(invalid_config.validate().is_err());
     }
 }
@@ -343,19 +343,19 @@ mod legacy_pagination_tests {
 
     #[test]
     fn test_pagination_builder() {
-        let pagination = Pagination::new()
+        let pagination = Pagination::<()>::new()
             .with_limit(50)
             .with_after("cursor_123")
             .with_before("cursor_456");
 
         assert_eq!(pagination.limit, Some(50));
-        assert_eq!(pagination.after, Some("cursor_123".to_string()));
-        assert_eq!(pagination.before, Some("cursor_456".to_string()));
+        assert_eq!(pagination.after, Some("cursor_123".into()));
+        assert_eq!(pagination.before, Some("cursor_456".into()));
     }
 
     #[test]
     fn test_pagination_default() {
-        let pagination = Pagination::default();
+        let pagination = Pagination::<()>::default();
         assert_eq!(pagination.limit, Some(20));
         assert!(pagination.after.is_none());
         assert!(pagination.before.is_none());
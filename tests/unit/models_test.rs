@@ -83,6 +83,7 @@ mod common_models_tests {
             tool_use_id,
             content,
             is_error,
+            ..
         } = &tool_result
         {
             assert_eq!(tool_use_id, "tool_123");
@@ -233,6 +234,7 @@ mod message_models_tests {
             id: "msg_123".to_string(),
             object_type: "message".to_string(),
             created_at: Utc::now(),
+            provenance: None,
             model: "claude-haiku-4-5".to_string(),
             role: Role::Assistant,
             content: vec![ContentBlock::text("Hello!")],
@@ -252,6 +254,39 @@ mod message_models_tests {
         assert_eq!(deserialized.text(), "Hello!");
     }
 
+    #[test]
+    fn test_message_response_thinking() {
+        let response: MessageResponse = from_str(
+            r#"{
+                "id": "msg_123",
+                "type": "message",
+                "role": "assistant",
+                "model": "claude-sonnet-4-5",
+                "content": [
+                    {"type": "thinking", "thinking": "Two plus two.", "signature": "sig"},
+                    {"type": "redacted_thinking", "data": "EmwKAhgB"},
+                    {"type": "thinking", "thinking": "It is four."},
+                    {"type": "text", "text": "4"}
+                ],
+                "stop_reason": "end_turn",
+                "stop_sequence": null,
+                "usage": {"input_tokens": 10, "output_tokens": 5}
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(response.thinking_text(), "Two plus two.\nIt is four.");
+        assert!(response.has_redacted_thinking());
+        assert_eq!(response.text(), "4");
+
+        let request = MessageRequest::new()
+            .add_user_message("What is 2+2?")
+            .thinking(2048);
+        let value = serde_json::to_value(&request).unwrap();
+        assert_eq!(value["thinking"]["type"], "enabled");
+        assert_eq!(value["thinking"]["budget_tokens"], 2048);
+    }
+
     #[test]
     fn test_stream_events() {
         let events = vec![
@@ -260,6 +295,7 @@ mod message_models_tests {
                     id: "msg_123".to_string(),
                     object_type: "message".to_string(),
                     created_at: Utc::now(),
+                    provenance: None,
                     model: "claude-haiku-4-5".to_string(),
                     role: Role::Assistant,
                     content: vec![],
@@ -296,6 +332,22 @@ mod message_models_tests {
             }
         }
     }
+
+    #[test]
+    fn test_message_request_canonical_json() {
+        let request = MessageRequest::new()
+            .model("claude-sonnet-4-6")
+            .max_tokens(100)
+            .temperature(0.7)
+            .add_user_message("Hi");
+
+        let canonical = request.canonical_json().unwrap();
+        assert_eq!(
+            canonical,
+            r#"{"max_tokens":100,"messages":[{"content":[{"text":"Hi","type":"text"}],"role":"user"}],"model":"claude-sonnet-4-6","temperature":0.7}"#
+        );
+        assert_eq!(request.clone().canonical_json().unwrap(), canonical);
+    }
 }
 
 #[cfg(test)]
@@ -413,8 +465,8 @@ mod model_info_tests {
                 deprecation_date: None,
             }],
             has_more: false,
-            first_id: Some("claude-3-5-haiku-20241022".to_string()),
-            last_id: Some("claude-3-5-haiku-20241022".to_string()),
+            first_id: Some("claude-3-5-haiku-20241022".into()),
+            last_id: Some("claude-3-5-haiku-20241022".into()),
         };
 
         // Test serialization
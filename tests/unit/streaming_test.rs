@@ -73,8 +73,8 @@ mod event_parser_tests {
 
         if let StreamEvent::ContentBlockDelta { index, delta } = event {
             assert_eq!(index, 0);
-            assert_eq!(delta.block_type, "text_delta");
-            assert_eq!(delta.text.as_deref(), Some("Hello"));
+            assert_eq!(delta.delta_type(), "text_delta");
+            assert_eq!(delta.as_text(), Some("Hello"));
         } else {
             panic!("Expected ContentBlockDelta event");
         }
@@ -193,7 +193,7 @@ data: {"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text
         let event = parser.parse_event(event_type, event_data).unwrap();
 
         if let StreamEvent::ContentBlockDelta { delta, .. } = event {
-            assert_eq!(delta.text.as_deref(), Some("Hello"));
+            assert_eq!(delta.as_text(), Some("Hello"));
         } else {
             panic!("Expected ContentBlockDelta event");
         }
@@ -213,6 +213,52 @@ data: {"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text
         assert!(matches!(event1, StreamEvent::MessageStart { .. }));
         assert!(matches!(event2, StreamEvent::MessageStop));
     }
+
+    const MALFORMED_FRAMES: [&str; 7] = [
+        "event: ping",
+        "data: {}",
+        "",
+        "event: content_block_delta",
+        "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hel",
+        "",
+        "event: message_stop",
+    ];
+
+    #[test]
+    fn test_malformed_event_error_has_context() {
+        let mut parser = EventParser::new();
+        let mut error = None;
+        for line in MALFORMED_FRAMES {
+            if let Err(e) = parser.parse_line(line) {
+                error = Some(e);
+                break;
+            }
+        }
+
+        let message = error.expect("malformed frame should fail").to_string();
+        assert!(message.contains("content_block_delta event"), "{}", message);
+        assert!(message.contains("at bytes 22..136"), "{}", message);
+        assert!(message.contains(r#"\"text\":\"Hel"#), "{}", message);
+    }
+
+    #[test]
+    fn test_malformed_event_skip_policy() {
+        use threatflux_anthropic_sdk::streaming::MalformedEventPolicy;
+
+        let mut parser = EventParser::new().with_malformed_policy(MalformedEventPolicy::Skip);
+        let mut events = Vec::new();
+        for line in MALFORMED_FRAMES.iter().chain(&["data: {}", ""]) {
+            if let Some(event) = parser.parse_line(line).unwrap() {
+                events.push(event);
+            }
+        }
+
+        assert!(matches!(
+            events.as_slice(),
+            [StreamEvent::Ping, StreamEvent::MessageStop]
+        ));
+        assert_eq!(parser.skipped_events(), 1);
+    }
 }
 
 #[cfg(test)]
@@ -222,14 +268,8 @@ mod message_stream_tests {
 
     /// Build a `text_delta` content block delta carrying the given text.
     fn text_delta(text: &str) -> ContentBlockDelta {
-        ContentBlockDelta {
-            block_type: "text_delta".to_string(),
-            text: Some(text.to_string()),
-            partial_json: None,
-            thinking: None,
-            signature: None,
-            citation: None,
-            extra: std::collections::HashMap::new(),
+        ContentBlockDelta::TextDelta {
+            text: text.to_string(),
         }
     }
 
@@ -261,6 +301,7 @@ mod message_stream_tests {
                     id: "msg_123".to_string(),
                     object_type: "message".to_string(),
                     created_at: Utc::now(),
+                    provenance: None,
                     model: "claude-haiku-4-5".to_string(),
                     role: Role::Assistant,
                     content: vec![],
@@ -313,6 +354,7 @@ mod message_stream_tests {
                     id: "msg_123".to_string(),
                     object_type: "message".to_string(),
                     created_at: Utc::now(),
+                    provenance: None,
                     model: "claude-haiku-4-5".to_string(),
                     role: Role::Assistant,
                     content: vec![],
@@ -350,7 +392,7 @@ mod message_stream_tests {
         while let Some(event) = stream.next().await {
             match event.unwrap() {
                 StreamEvent::ContentBlockDelta { delta, .. } => {
-                    if let Some(text_str) = delta.text.as_deref() {
+                    if let Some(text_str) = delta.as_text() {
                         text.push_str(text_str);
                     }
                 }
@@ -370,6 +412,7 @@ mod message_stream_tests {
                     id: "msg_123".to_string(),
                     object_type: "message".to_string(),
                     created_at: Utc::now(),
+                    provenance: None,
                     model: "claude-haiku-4-5".to_string(),
                     role: Role::Assistant,
                     content: vec![],
@@ -409,6 +452,7 @@ mod message_stream_tests {
                     id: "msg_123".to_string(),
                     object_type: "message".to_string(),
                     created_at: Utc::now(),
+                    provenance: None,
                     model: "claude-haiku-4-5".to_string(),
                     role: Role::Assistant,
                     content: vec![],
@@ -453,6 +497,7 @@ mod message_stream_tests {
                     id: "msg_123".to_string(),
                     object_type: "message".to_string(),
                     created_at: Utc::now(),
+                    provenance: None,
                     model: "claude-haiku-4-5".to_string(),
                     role: Role::Assistant,
                     content: vec![],
@@ -493,7 +538,7 @@ mod message_stream_tests {
         while let Some(event) = stream.next().await {
             match event.unwrap() {
                 StreamEvent::ContentBlockDelta { index, delta } => {
-                    if let Some(text_str) = delta.text.as_deref() {
+                    if let Some(text_str) = delta.as_text() {
                         content_blocks
                             .entry(index)
                             .and_modify(|s| s.push_str(text_str))
@@ -209,7 +209,7 @@ mod pagination_tests {
 
     #[test]
     fn test_pagination_new() {
-        let pagination = Pagination::new();
+        let pagination = Pagination::<()>::new();
         assert_eq!(pagination.limit, Some(20));
         assert!(pagination.after.is_none());
         assert!(pagination.before.is_none());
@@ -217,7 +217,7 @@ mod pagination_tests {
 
     #[test]
     fn test_pagination_default() {
-        let pagination = Pagination::default();
+        let pagination = Pagination::<()>::default();
         assert_eq!(pagination.limit, Some(20));
         assert!(pagination.after.is_none());
         assert!(pagination.before.is_none());
@@ -225,51 +225,53 @@ mod pagination_tests {
 
     #[test]
     fn test_pagination_with_limit() {
-        let pagination = Pagination::new().with_limit(50);
+        let pagination = Pagination::<()>::new().with_limit(50);
         assert_eq!(pagination.limit, Some(50));
     }
 
     #[test]
     fn test_pagination_with_after() {
-        let pagination = Pagination::new().with_after("cursor123");
-        assert_eq!(pagination.after, Some("cursor123".to_string()));
+        let pagination = Pagination::<()>::new().with_after("cursor123");
+        assert_eq!(pagination.after, Some("cursor123".into()));
     }
 
     #[test]
     fn test_pagination_with_before() {
-        let pagination = Pagination::new().with_before("cursor456");
-        assert_eq!(pagination.before, Some("cursor456".to_string()));
+        let pagination = Pagination::<()>::new().with_before("cursor456");
+        assert_eq!(pagination.before, Some("cursor456".into()));
     }
 
     #[test]
     fn test_pagination_chaining() {
-        let pagination = Pagination::new()
+        let pagination = Pagination::<()>::new()
             .with_limit(100)
             .with_after("start_cursor")
             .with_before("end_cursor");
 
         assert_eq!(pagination.limit, Some(100));
-        assert_eq!(pagination.after, Some("start_cursor".to_string()));
-        assert_eq!(pagination.before, Some("end_cursor".to_string()));
+        assert_eq!(pagination.after, Some("start_cursor".into()));
+        assert_eq!(pagination.before, Some("end_cursor".into()));
     }
 
     #[test]
     fn test_pagination_clone() {
-        let pagination1 = Pagination::new().with_limit(25);
+        let pagination1 = Pagination::<()>::new().with_limit(25);
         let pagination2 = pagination1.clone();
         assert_eq!(pagination1.limit, pagination2.limit);
     }
 
     #[test]
     fn test_pagination_debug() {
-        let pagination = Pagination::new();
+        let pagination = Pagination::<()>::new();
         let debug_str = format!("{:?}", pagination);
         assert!(debug_str.contains("Pagination"));
     }
 
     #[test]
     fn test_pagination_serialization() {
-        let pagination = Pagination::new().with_limit(10).with_after("test_cursor");
+        let pagination = Pagination::<()>::new()
+            .with_limit(10)
+            .with_after("test_cursor");
 
         let serialized = serde_json::to_string(&pagination).unwrap();
         assert!(serialized.contains("\"limit\":10"));
@@ -287,14 +289,14 @@ mod paginated_response_tests {
         let response: PaginatedResponse<String> = PaginatedResponse {
             data: vec!["item1".to_string(), "item2".to_string()],
             has_more: true,
-            first_id: Some("first".to_string()),
-            last_id: Some("last".to_string()),
+            first_id: Some("first".into()),
+            last_id: Some("last".into()),
         };
 
         assert_eq!(response.data.len(), 2);
         assert!(response.has_more);
-        assert_eq!(response.first_id, Some("first".to_string()));
-        assert_eq!(response.last_id, Some("last".to_string()));
+        assert_eq!(response.first_id, Some("first".into()));
+        assert_eq!(response.last_id, Some("last".into()));
     }
 
     #[test]
@@ -325,8 +327,8 @@ mod paginated_response_tests {
         let response: PaginatedResponse<String> = serde_json::from_value(json).unwrap();
         assert_eq!(response.data, vec!["a", "b"]);
         assert!(response.has_more);
-        assert_eq!(response.first_id, Some("first_cursor".to_string()));
-        assert_eq!(response.last_id, Some("last_cursor".to_string()));
+        assert_eq!(response.first_id, Some("first_cursor".into()));
+        assert_eq!(response.last_id, Some("last_cursor".into()));
     }
 
     #[test]
@@ -334,7 +336,7 @@ mod paginated_response_tests {
         let response1: PaginatedResponse<i32> = PaginatedResponse {
             data: vec![42],
             has_more: false,
-            first_id: Some("test".to_string()),
+            first_id: Some("test".into()),
             last_id: None,
         };
         let response2 = response1.clone();
@@ -344,6 +346,27 @@ mod paginated_response_tests {
         assert_eq!(response1.first_id, response2.first_id);
     }
 
+    #[test]
+    fn test_next_page_params() {
+        let response: PaginatedResponse<i32> = serde_json::from_value(json!({
+            "data": [1, 2],
+            "has_more": true,
+            "first_id": "first",
+            "last_id": "last"
+        }))
+        .unwrap();
+
+        let next: Pagination<i32> = response.next_page_params().unwrap();
+        assert_eq!(next.after, Some(Cursor::new("last")));
+        assert_eq!(next.before, None);
+
+        let last_page = PaginatedResponse {
+            has_more: false,
+            ..response
+        };
+        assert!(last_page.next_page_params().is_none());
+    }
+
     #[test]
     fn test_paginated_response_debug() {
         let response: PaginatedResponse<String> = PaginatedResponse {
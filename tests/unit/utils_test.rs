@@ -513,6 +513,77 @@ mod enhanced_utils_tests {
         }
     }
 
+    #[test]
+    fn test_detected_limits_from_headers() {
+        use reqwest::header::{HeaderMap, HeaderValue};
+        use threatflux_anthropic_sdk::utils::DetectedLimits;
+
+        let mut headers = HeaderMap::new();
+        assert!(DetectedLimits::from_headers(&headers).is_empty());
+        headers.insert(
+            "anthropic-ratelimit-requests-limit",
+            HeaderValue::from_static("50"),
+        );
+        headers.insert(
+            "anthropic-ratelimit-output-tokens-limit",
+            HeaderValue::from_static("8000"),
+        );
+
+        let limits = DetectedLimits::from_headers(&headers);
+        assert_eq!(limits.requests_per_minute, Some(50));
+        assert_eq!(limits.input_tokens_per_minute, None);
+        let config = limits.request_config(0.8).unwrap();
+        assert_eq!(config.max_requests.get(), 40);
+        assert_eq!(config.window, Duration::from_secs(60));
+        assert_eq!(limits.token_limits(0.8), (0, 6400));
+        assert_eq!(
+            DetectedLimits {
+                requests_per_minute: Some(1),
+                ..Default::default()
+            }
+            .request_config(0.1)
+            .unwrap()
+            .max_requests
+            .get(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_parse_anthropic_rate_limit_headers() {
+        use reqwest::header::{HeaderMap, HeaderValue};
+        use threatflux_anthropic_sdk::utils::http::HttpClient;
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "anthropic-ratelimit-requests-remaining",
+            HeaderValue::from_static("0"),
+        );
+        headers.insert(
+            "anthropic-ratelimit-requests-limit",
+            HeaderValue::from_static("50"),
+        );
+        let reset = (Utc::now() + chrono::Duration::seconds(20)).to_rfc3339();
+        headers.insert(
+            "anthropic-ratelimit-requests-reset",
+            HeaderValue::from_str(&reset).unwrap(),
+        );
+
+        let info = HttpClient::parse_rate_limit_headers(&headers);
+        assert_eq!(info.remaining, Some(0));
+        assert_eq!(info.limit, Some(50));
+        // The reset is 20s away, plus the allowance for clock skew
+        let delay = info.recommended_delay().unwrap();
+        assert!(delay > Duration::from_secs(15) && delay <= Duration::from_secs(60));
+
+        let date = (Utc::now() + chrono::Duration::seconds(30)).to_rfc2822();
+        headers.insert("retry-after", HeaderValue::from_str(&date).unwrap());
+        let retry_after = HttpClient::parse_rate_limit_headers(&headers)
+            .retry_after
+            .unwrap();
+        assert!(retry_after > Duration::from_secs(25) && retry_after <= Duration::from_secs(30));
+    }
+
     #[test]
     fn test_retry_policy_builder() {
         let policy = RetryPolicy::new()
@@ -597,6 +668,100 @@ mod enhanced_utils_tests {
         assert_eq!(adaptive.current_limit(), 200);
     }
 
+    #[test]
+    fn test_adaptive_rate_limiter_throttles_with_hysteresis() {
+        use std::sync::{Arc, Mutex};
+        use threatflux_anthropic_sdk::utils::ThrottleEvent;
+
+        let events: Arc<Mutex<Vec<ThrottleEvent>>> = Arc::default();
+        let recorded = Arc::clone(&events);
+        let base = RateLimitConfig::new(100, Duration::from_secs(1));
+        let adaptive = AdaptiveRateLimiter::new(base.clone())
+            .with_safety_margin(0.1)
+            .with_hysteresis(0.2)
+            .on_throttle(move |event| recorded.lock().unwrap().push(event.clone()));
+        let info = |remaining, reset: Option<i64>| RateLimitInfo {
+            remaining: Some(remaining),
+            limit: Some(1000),
+            reset: reset.map(|seconds| Utc::now() + chrono::Duration::seconds(seconds)),
+            retry_after: None,
+        };
+
+        adaptive.update_from_headers(&info(500, None));
+        assert!(!adaptive.is_throttled());
+
+        // Under the margin: spread the 50 remaining over the 10s left
+        adaptive.update_from_headers(&info(50, Some(10)));
+        assert!(adaptive.is_throttled());
+        assert_eq!(adaptive.current_config().max_requests.get(), 50);
+        assert!(adaptive.current_config().window > Duration::from_secs(8));
+
+        // Without a reset time the configured rate is halved; still one event
+        adaptive.update_from_headers(&info(40, None));
+        assert_eq!(adaptive.current_config().max_requests.get(), 50);
+        assert_eq!(adaptive.current_config().window, Duration::from_secs(1));
+        assert_eq!(events.lock().unwrap().len(), 1);
+        assert_eq!(events.lock().unwrap()[0].remaining, 50);
+
+        // Back above the margin but within the hysteresis band: still throttled
+        adaptive.update_from_headers(&info(200, None));
+        assert!(adaptive.is_throttled());
+        adaptive.update_from_headers(&info(300, None));
+        assert!(!adaptive.is_throttled());
+        assert_eq!(adaptive.current_config(), base);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_queue_orders_by_priority_with_aging() {
+        use std::sync::{Arc, Mutex};
+        use threatflux_anthropic_sdk::utils::DispatchQueue;
+
+        // Saturate the limiter, then queue low, normal and high in that order
+        async fn dispatch_order(queue: DispatchQueue) -> Vec<RequestPriority> {
+            queue.admit(RequestPriority::Normal).await;
+            let order = Arc::new(Mutex::new(Vec::new()));
+            let mut tasks = Vec::new();
+            for priority in [
+                RequestPriority::Low,
+                RequestPriority::Normal,
+                RequestPriority::High,
+            ] {
+                let (queue, order) = (queue.clone(), Arc::clone(&order));
+                tasks.push(tokio::spawn(async move {
+                    queue.admit(priority).await;
+                    order.lock().unwrap().push(priority);
+                }));
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            assert_eq!(queue.queued(), 3);
+            futures::future::join_all(tasks).await;
+            let order = order.lock().unwrap().clone();
+            order
+        }
+
+        let by_priority = dispatch_order(DispatchQueue::per_second(5)).await;
+        assert_eq!(
+            by_priority,
+            [
+                RequestPriority::High,
+                RequestPriority::Normal,
+                RequestPriority::Low
+            ]
+        );
+
+        // Everything has aged to the top by the time a slot frees up
+        let aged =
+            dispatch_order(DispatchQueue::per_second(5).with_aging(Duration::from_millis(1))).await;
+        assert_eq!(
+            aged,
+            [
+                RequestPriority::Low,
+                RequestPriority::Normal,
+                RequestPriority::High
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn test_rate_limiter_async_operations() {
         let limiter = RateLimiter::per_second(2);
@@ -615,6 +780,109 @@ mod enhanced_utils_tests {
         assert_eq!(stats.total_requests, 0);
     }
 
+    #[tokio::test(start_paused = true)]
+    async fn test_token_aware_rate_limiter_reconciles_usage() {
+        use threatflux_anthropic_sdk::models::common::Usage;
+
+        // 600 input tokens a minute is 10 a second; output is unlimited
+        let limiter = RateLimiter::per_second(10).with_token_limits(600, 0);
+        assert!(limiter.is_token_aware());
+
+        let started = Instant::now();
+        let reservation = limiter.acquire_tokens(600, 4096).await;
+        assert_eq!(started.elapsed(), Duration::ZERO);
+
+        // Only 100 of the 600 reserved were used
+        let usage = Usage {
+            input_tokens: 80,
+            cache_creation_input_tokens: 20,
+            cache_read_input_tokens: 1000,
+            ..Default::default()
+        };
+        limiter.reconcile(reservation, &usage);
+        let reservation = limiter.acquire_tokens(500, 4096).await;
+        assert_eq!(started.elapsed(), Duration::ZERO);
+
+        // The bucket is empty: 50 more tokens take five seconds to refill
+        let waiting = limiter.acquire_tokens(50, 0).await;
+        assert_eq!(started.elapsed(), Duration::from_secs(5));
+
+        // A failed request gives back everything it reserved
+        limiter.release(reservation);
+        limiter.release(waiting);
+        let _ = limiter.acquire_tokens(550, 0).await;
+        assert_eq!(started.elapsed(), Duration::from_secs(5));
+        assert_eq!(limiter.stats().rate_limited_requests, 1);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_middleware_pools_by_model_and_workspace() {
+        use threatflux_anthropic_sdk::utils::{RateLimitKey, RateLimitMiddleware};
+
+        let key = |model: &str, workspace: Option<&str>| RateLimitKey {
+            model: model.to_string(),
+            workspace: workspace.map(str::to_string),
+        };
+        let middleware =
+            RateLimitMiddleware::per_model(RateLimitConfig::new(100, Duration::from_secs(1)))
+                .with_model_limit(
+                    "claude-opus-*",
+                    RateLimitConfig::new(5, Duration::from_secs(1)),
+                )
+                .with_workspace_header("X-Workspace");
+        assert_eq!(
+            middleware.workspace_header().as_deref(),
+            Some("x-workspace")
+        );
+
+        // Opus models share one slow pool; other models and workspaces don't
+        let started = std::time::Instant::now();
+        middleware.apply_for("claude-opus-4-8", None).await.unwrap();
+        middleware
+            .apply_for("claude-sonnet-4-6", None)
+            .await
+            .unwrap();
+        middleware
+            .apply_for("claude-opus-4-1", Some("team-a"))
+            .await
+            .unwrap();
+        assert!(started.elapsed() < Duration::from_millis(150));
+        middleware.apply_for("claude-opus-4-1", None).await.unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(150));
+
+        let stats = middleware.stats_by_key();
+        assert_eq!(stats.len(), 3);
+        assert_eq!(stats[&key("claude-opus-*", None)].total_requests, 2);
+        assert_eq!(
+            stats[&key("claude-opus-*", Some("team-a"))].total_requests,
+            1
+        );
+        assert_eq!(stats[&key("claude-sonnet-4-6", None)].total_requests, 1);
+
+        // Reconfiguring keeps a pool's stats; a new pattern takes over the
+        // models it covers
+        middleware.set_model_limit(
+            "claude-opus-*",
+            RateLimitConfig::new(100, Duration::from_secs(1)),
+        );
+        middleware.set_model_limit(
+            "claude-sonnet-*",
+            RateLimitConfig::new(100, Duration::from_secs(1)),
+        );
+        let started = std::time::Instant::now();
+        middleware.apply_for("claude-opus-4-8", None).await.unwrap();
+        assert!(started.elapsed() < Duration::from_millis(150));
+        middleware
+            .apply_for("claude-sonnet-4-6", None)
+            .await
+            .unwrap();
+
+        let stats = middleware.stats_by_key();
+        assert_eq!(stats[&key("claude-opus-*", None)].total_requests, 3);
+        assert_eq!(stats[&key("claude-sonnet-*", None)].total_requests, 1);
+        assert!(!stats.contains_key(&key("claude-sonnet-4-6", None)));
+    }
+
     #[test]
     fn test_http_status_code_utilities() {
         use threatflux_anthropic_sdk::utils::http::HttpClient;
@@ -660,4 +928,30 @@ mod enhanced_utils_tests {
         assert!(rate_limit_info.reset.is_some());
         assert_eq!(rate_limit_info.retry_after, Some(Duration::from_secs(60)));
     }
+
+    #[test]
+    fn test_rate_limit_reset_header_accepts_rfc3339() {
+        use reqwest::header::{HeaderMap, HeaderValue};
+        use threatflux_anthropic_sdk::utils::http::HttpClient;
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-ratelimit-reset",
+            HeaderValue::from_static("2022-01-01T00:00:00Z"),
+        );
+        let rate_limit_info = HttpClient::parse_rate_limit_headers(&headers);
+        assert_eq!(
+            rate_limit_info.reset.map(|reset| reset.timestamp()),
+            Some(1640995200)
+        );
+
+        // A reset a few seconds in the past may just be clock skew.
+        let rate_limit_info = RateLimitInfo {
+            remaining: Some(0),
+            limit: Some(100),
+            reset: Some(Utc::now() - chrono::Duration::seconds(5)),
+            retry_after: None,
+        };
+        assert!(rate_limit_info.time_until_reset().is_some());
+    }
 }